@@ -0,0 +1,146 @@
+//! Benchmarks of the arbitrage pipeline on synthetic markets.
+//!
+//! Run with `cargo bench --features synthetic`. To gate a change on
+//! performance, save a baseline before the change with
+//! `cargo bench --features synthetic -- --save-baseline main` and compare
+//! against it afterwards with `-- --baseline main`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use num_bigint::BigUint;
+use tycho_atomic_arbitrage::graph::synthetic::{SyntheticMarket, SyntheticMarketConfig};
+use tycho_atomic_arbitrage::graph::TradingGraph;
+use tycho_atomic_arbitrage::path::{Path, PathRepository};
+use tycho_atomic_arbitrage::simulation::{build_solution, encode_solution};
+use tycho_common::Bytes;
+use tycho_execution::encoding::models::{Swap as TychoExecutionSwap, UserTransferType};
+
+/// Maximum number of swaps of discovered paths.
+const MAX_PATH_LENGTH: usize = 3;
+
+/// Number of input amounts evaluated per path by the grid search benchmarks.
+const GRID_POINTS: usize = 16;
+
+fn market(pool_count: usize) -> SyntheticMarket {
+    SyntheticMarket::generate(
+        &SyntheticMarketConfig::new()
+            .with_token_count(pool_count / 5)
+            .with_pool_count(pool_count),
+    )
+}
+
+fn discover(market: &SyntheticMarket, graph: &TradingGraph) -> PathRepository {
+    let mut repository = PathRepository::new(market.hub_tokens(), MAX_PATH_LENGTH);
+    repository.discover_paths(graph, 0, graph.token_count(), 0, graph.pool_count());
+    repository
+}
+
+fn build_paths(market: &SyntheticMarket, graph: &TradingGraph, repository: &PathRepository, limit: usize) -> Vec<Path> {
+    let path_indices = (0..repository.statistics().pool_path_count.min(limit)).collect();
+    repository
+        .build_paths_from_indices(path_indices, graph, &market.simulations, &market.components)
+        .expect("synthetic paths build")
+}
+
+fn grid(upper_bound: &BigUint) -> Vec<BigUint> {
+    (1..=GRID_POINTS).map(|i| upper_bound * i / GRID_POINTS).collect()
+}
+
+fn bench_graph_insertion(c: &mut Criterion) {
+    let market = market(100_000);
+    let mut group = c.benchmark_group("graph");
+    group.sample_size(10);
+    group.bench_function("insert_100k_pools", |b| {
+        b.iter(|| black_box(market.build_graph().expect("synthetic pools insert")))
+    });
+    group.finish();
+}
+
+fn bench_path_discovery(c: &mut Criterion) {
+    let mut group = c.benchmark_group("paths");
+    group.sample_size(10);
+    for pool_count in [1_000, 10_000] {
+        let market = market(pool_count);
+        let graph = market.build_graph().expect("synthetic pools insert");
+        group.bench_function(format!("discover_{}_pools", pool_count), |b| {
+            b.iter(|| black_box(discover(&market, &graph)))
+        });
+    }
+
+    let market = market(10_000);
+    let graph = market.build_graph().expect("synthetic pools insert");
+    let repository = discover(&market, &graph);
+    group.bench_function("build_1000_paths", |b| {
+        b.iter(|| black_box(build_paths(&market, &graph, &repository, 1_000)))
+    });
+    group.finish();
+}
+
+fn bench_optimization(c: &mut Criterion) {
+    let market = market(10_000);
+    let graph = market.build_graph().expect("synthetic pools insert");
+    let repository = discover(&market, &graph);
+    let paths = build_paths(&market, &graph, &repository, 100);
+    let amounts = grid(&BigUint::from(10u32).pow(20));
+
+    let mut group = c.benchmark_group("optimization");
+    group.bench_function("grid_search_100_paths", |b| {
+        b.iter(|| {
+            for path in &paths {
+                black_box(path.evaluate_amounts(&amounts));
+            }
+        })
+    });
+    group.bench_function("grid_search_100_paths_sequential", |b| {
+        b.iter(|| {
+            for path in &paths {
+                for amount in &amounts {
+                    let _ = black_box(path.calculate_profit_loss(amount.clone()));
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_encoding(c: &mut Criterion) {
+    let market = market(1_000);
+    let graph = market.build_graph().expect("synthetic pools insert");
+    let repository = discover(&market, &graph);
+    let path = build_paths(&market, &graph, &repository, 1)
+        .pop()
+        .expect("synthetic market has a path");
+    let swaps: Vec<_> = path
+        .iter()
+        .map(|swap| TychoExecutionSwap {
+            component: (*swap.pool_comp).clone().into(),
+            token_in: swap.token_in.address.clone(),
+            token_out: swap.token_out.address.clone(),
+            split: 0.0,
+        })
+        .collect();
+    let sender = Bytes::from(vec![0x11; 20]);
+    let amount_in = BigUint::from(10u32).pow(18);
+
+    let mut group = c.benchmark_group("encoding");
+    group.bench_function("build_and_encode_solution", |b| {
+        b.iter_batched(
+            || amount_in.clone(),
+            |amount_in| {
+                let solution = build_solution(&swaps, amount_in.clone(), &sender, &sender, amount_in, 50)
+                    .expect("solution builds");
+                black_box(encode_solution(&solution, "ethereum", UserTransferType::TransferFrom, None))
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_graph_insertion,
+    bench_path_discovery,
+    bench_optimization,
+    bench_encoding
+);
+criterion_main!(benches);
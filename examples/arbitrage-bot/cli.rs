@@ -1,10 +1,24 @@
-use clap::Parser;
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser};
 use tycho_common::Bytes;
 use tycho_common::models::Chain;
-use tycho_atomic_arbitrage::errors::Result;
-use std::str::FromStr;
+use tycho_atomic_arbitrage::{
+    alerts::{AlertMonitor, AlertRule, WebhookSink},
+    budget::SearchBudget,
+    bundle::{ExecutionMode, SubmissionMode, SubmissionScheduler, SubmissionTiming},
+    config::{ArbitrageSettings, SettingsLoader},
+    errors::Result,
+    risk::RiskLimits,
+    rpc::RateLimit,
+    utils::{
+        convert::{self, Rounding},
+        well_known_token_address,
+    },
+};
+use crate::stream::replayer::ReplaySpeed;
+use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+use tycho_execution::encoding::models::UserTransferType;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[clap(author, version, about, long_about = None)]
 pub struct Args {
     #[clap(long, env = "TYCHO_CHAIN", default_value = "ethereum", help = "Target blockchain (e.g., ethereum, base)")]
@@ -13,17 +27,50 @@ pub struct Args {
     #[clap(long, env = "TYCHO_RPC_URL", help = "RPC URL for on-chain interaction")]
     pub rpc_url: String,
 
+    #[clap(long, env = "TYCHO_FALLBACK_RPC_URLS", value_delimiter = ',', help = "Comma-separated list of RPC URLs simulations fail over to when the primary RPC fails")]
+    pub fallback_rpc_urls: Vec<String>,
+
+    #[clap(long, env = "TYCHO_RPC_RACE_WIDTH", default_value = "1", help = "Send every simulation to this many RPC endpoints at once and take the first success")]
+    pub rpc_race_width: usize,
+
+    #[clap(long, env = "TYCHO_RPC_RATE_LIMIT", help = "Requests per second allowed across simulations, balance checks and gas queries. Requests above the limit wait instead of failing")]
+    pub rpc_rate_limit: Option<f64>,
+
+    #[clap(long, env = "TYCHO_RPC_MAX_CONCURRENT", help = "Maximum number of RPC requests in flight across simulations, balance checks and gas queries")]
+    pub rpc_max_concurrent: Option<usize>,
+
+    #[clap(long, env = "TYCHO_RPC_ENDPOINT_RATE_LIMITS", value_delimiter = ',', help = "Comma-separated list of per-endpoint rate limits as url=requests_per_second (e.g., https://eth.example.com=25)")]
+    pub rpc_endpoint_rate_limits: Vec<String>,
+
+    #[clap(long, env = "TYCHO_WS_RPC_URL", help = "WebSocket RPC URL to follow new block headers on. Enables head tracking")]
+    pub ws_rpc_url: Option<String>,
+
+    #[clap(long, env = "TYCHO_HEAD_POLL_INTERVAL_MS", help = "Poll the latest block over HTTP at this interval while no WebSocket subscription is available. Enables head tracking")]
+    pub head_poll_interval_ms: Option<u64>,
+
     #[clap(long, env = "TYCHO_API_KEY", help = "Tycho API key")]
     pub tycho_api_key: String,
 
     #[clap(long, value_delimiter = ',', help = "Comma-separated list of token symbols or addresses to start cycles from (e.g., WETH,USDC)")]
     pub start_tokens: Vec<String>,
 
-    #[clap(long, value_delimiter = ',', help = "Comma-separated list of optimization tolerance percentages, one for each start token (e.g., 1.0,0.5). Defaults to 1.0 for each start token if not provided.")]
+    #[clap(long, env = "TYCHO_OPTIMIZATION_TOLERANCES", value_delimiter = ',', help = "Comma-separated list of optimization tolerance percentages, one for each start token (e.g., 1.0,0.5). Tolerances keyed by token in the settings file take precedence, other start tokens use the default tolerance of the settings (1.0).")]
     pub optimization_tolerances: Vec<f64>,
 
-    #[clap(long, env = "TYCHO_EXECUTOR_PRIVATE_KEY", help = "Private key for the executor EOA")]
-    pub executor_private_key: String,
+    #[clap(long, value_delimiter = ',', help = "Comma-separated list of balance cap percentages, one for each start token (e.g., 100,50). Caps bound how much of each token's balance a search may commit. Defaults to 100 for each start token if not provided.")]
+    pub balance_cap_percentages: Vec<f64>,
+
+    #[clap(long, env = "TYCHO_MAX_PATH_LENGTH", default_value_t = 3, help = "Maximum number of swaps of a searched path")]
+    pub max_path_length: usize,
+
+    #[clap(long, value_delimiter = ',', help = "Comma-separated list of maximum path lengths, one for each start token (e.g., 3,2). Capped by --max-path-length, which is also the default for each start token")]
+    pub max_path_lengths: Vec<usize>,
+
+    #[clap(long, env = "TYCHO_EXECUTOR_PRIVATE_KEY", required_unless_present = "executor_keystore", help = "Private key for the executor EOA")]
+    pub executor_private_key: Option<String>,
+
+    #[clap(long, env = "TYCHO_EXECUTOR_KEYSTORE", help = "Encrypted JSON keystore holding the executor key, used instead of a private key. The passphrase is read from TYCHO_EXECUTOR_KEYSTORE_PASSWORD or prompted for")]
+    pub executor_keystore: Option<PathBuf>,
 
     #[clap(long, env = "TYCHO_TVL_THRESHOLD", default_value_t = 70.0, help = "Minimum TVL for pools to consider")]
     pub tvl_threshold: f64,
@@ -39,25 +86,201 @@ pub struct Args {
 
     #[clap(long, env = "TYCHO_BRIBE_PERCENTAGE", default_value_t = 99, help = "Bribe percentage of expected profit")]
     pub bribe_percentage: u64,
-}
 
-const WETH_ADDRESSES: &[(&str, &str)] = &[
-    ("ethereum", "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
-    ("base", "0x4200000000000000000000000000000000000006"),
-    ("unichain", "0x4200000000000000000000000000000000000006"),
-];
+    #[clap(long, env = "TYCHO_HEATMAP_INTERVAL_BLOCKS", default_value_t = 100, help = "Number of blocks between profitability heatmap reports (0 disables)")]
+    pub heatmap_interval_blocks: u64,
+
+    #[clap(long, env = "TYCHO_SEARCH_BUDGET_MS", help = "Wall-clock budget per block search in milliseconds. Defaults to two thirds of the chain's block time.")]
+    pub search_budget_ms: Option<u64>,
+
+    #[clap(long, env = "TYCHO_MAX_PATHS", help = "Maximum number of candidate paths to build per block search")]
+    pub max_paths: Option<usize>,
+
+    #[clap(long, env = "TYCHO_MAX_SIMULATIONS", help = "Maximum number of simulations to run per block search")]
+    pub max_simulations: Option<usize>,
+
+    #[clap(long, env = "TYCHO_SUBMISSION_MODE", default_value = "separate", help = "How a block's opportunities are submitted: separate (one bundle each) or combined (one bundle)")]
+    pub submission_mode: String,
+
+    #[clap(long, env = "TYCHO_SUBMISSION_TIMING", default_value = "immediate", help = "When bundles are submitted within the slot: immediate, delayed (hold until the submission offset) or resubmit (submit immediately and an updated bundle at the offset). Requires head tracking unless immediate")]
+    pub submission_timing: String,
+
+    #[clap(long, env = "TYCHO_SUBMISSION_OFFSET_MS", default_value_t = 8000, help = "Offset after the parent block timestamp at which delayed bundles and resubmissions are submitted")]
+    pub submission_offset_ms: u64,
+
+    #[clap(long, env = "TYCHO_BACKTEST_FILE", help = "Replay recorded Tycho feed messages from this file or recording directory instead of streaming live updates. Simulations run on top of each recorded block, so the RPC must serve historical state, and no bundle is submitted")]
+    pub backtest_file: Option<PathBuf>,
+
+    #[clap(long, env = "TYCHO_REPLAY_SPEED", default_value = "max", help = "Pace of backtest replays: original (keep the recorded spacing between blocks) or max")]
+    pub replay_speed: String,
+
+    #[clap(long, env = "TYCHO_MAX_BLOCK_GAP", default_value_t = 0, help = "Number of blocks that may be missing between two stream updates before the stream is resynchronized from a fresh snapshot")]
+    pub max_block_gap: u64,
+
+    #[clap(long, env = "TYCHO_RESYNC_SETTLE_BLOCKS", default_value_t = 1, help = "Contiguous blocks to receive after a resync snapshot before searching again")]
+    pub resync_settle_blocks: u64,
+
+    #[clap(long, env = "TYCHO_RECORD_DIR", help = "Record every raw feed message of the live stream to compressed files in this directory")]
+    pub record_dir: Option<PathBuf>,
+
+    #[clap(long, env = "TYCHO_RECORD_BLOCKS_PER_FILE", default_value_t = 1000, help = "Number of blocks written to one recording file before rotating")]
+    pub record_blocks_per_file: u64,
+
+    #[clap(long, env = "TYCHO_EVENT_LOG", help = "Append opportunity, simulation, submission and inclusion events as JSON lines to this file")]
+    pub event_log: Option<PathBuf>,
+
+    #[clap(long, env = "TYCHO_ALERT_WEBHOOK_URL", help = "Post triggered alerts as JSON to this webhook URL")]
+    pub alert_webhook_url: Option<String>,
+
+    #[clap(long, env = "TYCHO_ALERT_WEBHOOK_TEMPLATE", help = "JSON body of webhook alerts with {{kind}}, {{message}}, {{block_number}}, {{value}} and {{threshold}} placeholders")]
+    pub alert_webhook_template: Option<String>,
+
+    #[clap(long, env = "TYCHO_ALERT_MIN_PROFIT", help = "Alert on opportunities simulated with a net profit above this in native tokens, e.g. 1.0 for 1 ETH")]
+    pub alert_min_profit: Option<f64>,
+
+    #[clap(long, env = "TYCHO_ALERT_MAX_ERROR_RATE", help = "Alert once more than this share of simulations failed within the error window, e.g. 0.5")]
+    pub alert_max_error_rate: Option<f64>,
+
+    #[clap(long, env = "TYCHO_ALERT_ERROR_WINDOW_BLOCKS", default_value_t = 20, help = "Number of blocks the simulation error rate is computed over")]
+    pub alert_error_window_blocks: u64,
+
+    #[clap(long, env = "TYCHO_ALERT_LOW_INVENTORY", help = "Alert when a token of the executor falls below its inventory floor")]
+    pub alert_low_inventory: bool,
+
+    #[clap(long, env = "TYCHO_ALERT_MAX_STREAM_LAG", help = "Alert once the processed stream trails the chain head by more than this many blocks")]
+    pub alert_max_stream_lag: Option<u64>,
+
+    #[clap(long, env = "TYCHO_ALERT_COOLDOWN_BLOCKS", default_value_t = 50, help = "Blocks before an alert of the same rule and subject is sent again")]
+    pub alert_cooldown_blocks: u64,
+
+    #[clap(long, env = "TYCHO_DRY_RUN", help = "Sign and build bundles but do not submit them; the would-be bundles are written to dry_run_bundles.csv")]
+    pub dry_run: bool,
+
+    #[clap(long, env = "TYCHO_MAX_PORTFOLIO_SIZE", help = "Maximum number of non-conflicting opportunities executed per block")]
+    pub max_portfolio_size: Option<usize>,
+
+    #[clap(long, env = "TYCHO_ALLOW_SHARED_POOLS", help = "Let opportunities executed in the same block trade through the same pools")]
+    pub allow_shared_pools: bool,
+
+    #[clap(long, env = "TYCHO_REVERT_MEMORY_BLOCKS", default_value_t = 7200, help = "Number of blocks a bundle that reverted on chain is not submitted again byte for byte")]
+    pub revert_memory_blocks: u64,
+
+    #[clap(long, env = "TYCHO_REVERT_MEMORY_FILE", help = "JSON file keeping the bundles that reverted on chain across restarts")]
+    pub revert_memory_file: Option<PathBuf>,
 
-const USDC_ADDRESSES: &[(&str, &str)] = &[
-    ("ethereum", "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
-    ("base", "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
-    ("unichain", "0x078D782b760474a361dDA0AF3839290b0EF57AD6"),
-];
+    #[clap(long, env = "TYCHO_SKIP_APPROVALS", help = "Track Permit2 allowances and leave out approval transactions when the allowance already suffices. Approvals then grant an unlimited allowance.")]
+    pub skip_approvals: bool,
 
-const WBTC_ADDRESSES: &[(&str, &str)] = &[
-    ("ethereum", "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599"),
-    ("base", "0x0555e30da8f98308edb960aa94c0db47230d2b9c"),
-    ("unichain", "0x0555E30da8f98308EdB960aa94C0Db47230d2B9c"),
-];
+    #[clap(long, env = "TYCHO_BOOTSTRAP_APPROVALS", help = "Submit unlimited Permit2 approvals of the start tokens lacking one on startup, in one batch before the first search")]
+    pub bootstrap_approvals: bool,
+
+    #[clap(long, env = "TYCHO_TRANSFER_TYPE", default_value = "permit2", help = "How the router obtains input tokens: permit2 (signed permit per path), transfer-from (standard approval of the router) or none (router is pre-funded)")]
+    pub transfer_type: String,
+
+    #[clap(long, env = "TYCHO_PERMIT_VALIDITY_SECS", help = "Reuse Permit2 signatures within validity windows of this many seconds instead of signing a permit per path")]
+    pub permit_validity_secs: Option<u64>,
+
+    #[clap(long, env = "TYCHO_MAX_OUTPUT_SHORTFALL_BPS", default_value_t = 50.0, help = "Maximum shortfall in BPS of the output received according to transfer logs versus the optimizer's expected output before an opportunity is discarded")]
+    pub max_output_shortfall_bps: f64,
+
+    #[clap(long, env = "TYCHO_MIN_PROFIT_GAS_MULTIPLE", default_value_t = 0.0, help = "Require the simulated profit after gas to exceed this multiple of the simulated gas cost; 0 only requires a profit after gas")]
+    pub min_profit_gas_multiple: f64,
+
+    #[clap(long, env = "TYCHO_MAX_POOL_TOKENS", default_value_t = 4, help = "Maximum number of tokens of a pool added to the trading graph. Raise to include large Balancer pools; their token pairs are only added once they can be part of a cycle through a start token")]
+    pub max_pool_tokens: usize,
+
+    #[clap(long, env = "TYCHO_MAX_PROTOCOL_DEVIATION_BPS", help = "Track how far local pool simulations deviate from eth_simulateV1 per protocol system, and skip paths through protocols whose mean absolute deviation exceeds this many BPS")]
+    pub max_protocol_deviation_bps: Option<f64>,
+
+    #[clap(long, env = "TYCHO_MIN_ACCURACY_SAMPLES", default_value_t = 10, help = "Number of simulated swaps of a protocol system required before it can be distrusted")]
+    pub min_accuracy_samples: u64,
+
+    #[clap(long, env = "TYCHO_REALIZED_SLIPPAGE_SAMPLES", help = "Log the realized slippage of included paths per protocol system from their receipts, and tolerate the realized slippage instead of TYCHO_SLIPPAGE_BPS once every protocol of a path has this many included swaps")]
+    pub realized_slippage_samples: Option<usize>,
+
+    #[clap(long, env = "TYCHO_STALE_POOL_BLOCKS", help = "Prune pools without a state update in this many blocks from the graph and evict their paths; pruned pools are revived on their next update")]
+    pub stale_pool_blocks: Option<u64>,
+
+    #[clap(long, env = "TYCHO_PRUNE_INTERVAL_BLOCKS", default_value_t = 100, help = "Number of blocks between stale pool pruning rounds")]
+    pub prune_interval_blocks: u64,
+
+    #[clap(long, env = "TYCHO_TOKEN_PROBES_PER_BLOCK", help = "Vet new tokens with a small round-trip simulation, probing at most this many tokens per block, and denylist tokens that are taxed on transfer or cannot be traded")]
+    pub token_probes_per_block: Option<usize>,
+
+    #[clap(long, env = "TYCHO_MAX_PROBE_SHORTFALL_BPS", default_value_t = 100.0, help = "Shortfall of the quoted round trip in BPS above which a probed token counts as taxed on transfer")]
+    pub max_probe_shortfall_bps: f64,
+
+    #[clap(long, env = "TYCHO_STATS_WINDOW_BLOCKS", default_value_t = 100, help = "Number of recent blocks aggregated into the search, simulation and inclusion statistics written on shutdown")]
+    pub stats_window_blocks: usize,
+
+    #[cfg(feature = "status-server")]
+    #[clap(long, env = "TYCHO_STATUS_ADDRESS", help = "Serve liveness, readiness and status JSON on this address, e.g. 0.0.0.0:8080")]
+    pub status_address: Option<String>,
+
+    #[clap(long, env = "TYCHO_RANKED_PATHS", help = "Rank paths by their exponentially decayed simulated and executed profit, and evaluate this many top ranked paths first each block")]
+    pub ranked_paths: Option<usize>,
+
+    #[clap(long, env = "TYCHO_RANKING_HALF_LIFE_BLOCKS", default_value_t = 50.0, help = "Number of blocks after which the profitability score of a path has decayed to half")]
+    pub ranking_half_life_blocks: f64,
+
+    #[clap(long, env = "TYCHO_COOLDOWN_FAILURES", help = "Cool down pools and paths after this many reverted simulations or bundles that were not included")]
+    pub cooldown_failures: Option<u32>,
+
+    #[clap(long, env = "TYCHO_COOLDOWN_BASE_BLOCKS", default_value_t = 2, help = "Blocks a pool or path is skipped once it reaches the failure threshold, doubling with every further failure")]
+    pub cooldown_base_blocks: u64,
+
+    #[clap(long, env = "TYCHO_COOLDOWN_MAX_BLOCKS", default_value_t = 64, help = "Upper bound of the cooldown of a pool or path in blocks")]
+    pub cooldown_max_blocks: u64,
+
+    #[clap(long, env = "TYCHO_COOLDOWN_DECAY_BLOCKS", default_value_t = 100, help = "Blocks without failure after which the failures of a pool or path are forgotten")]
+    pub cooldown_decay_blocks: u64,
+
+    #[clap(long, env = "TYCHO_MAX_TRADE_NOTIONAL", help = "Do not submit trades whose input is worth more than this in native tokens, e.g. 5.0 for 5 ETH")]
+    pub max_trade_notional: Option<f64>,
+
+    #[clap(long, env = "TYCHO_MAX_GAS_PRICE_GWEI", help = "Do not submit bundles whose swaps would pay more than this fee per gas in gwei, bribe included")]
+    pub max_gas_price_gwei: Option<f64>,
+
+    #[clap(long, env = "TYCHO_MAX_DAILY_LOSS", help = "Halt submissions for the rest of the UTC day once the realized loss exceeds this in native tokens")]
+    pub max_daily_loss: Option<f64>,
+
+    #[clap(long, env = "TYCHO_MAX_CONSECUTIVE_FAILURES", help = "Halt submissions after this many consecutive bundles failed to land")]
+    pub max_consecutive_failures: Option<u32>,
+
+    #[clap(long, env = "TYCHO_KILL_SWITCH_FILE", help = "Stop submitting bundles while this file exists; searching continues in dry-run mode")]
+    pub kill_switch_file: Option<PathBuf>,
+
+    #[clap(long, env = "TYCHO_INVENTORY_FLOOR_PERCENTAGE", help = "Suggest rebalancing once a source token falls below this percentage of its highest balance seen")]
+    pub inventory_floor_percentage: Option<f64>,
+
+    #[clap(long, env = "TYCHO_GAS_FLOOR", help = "Native ETH kept for gas, e.g. 0.1; suggests unwrapping WETH once the balance falls below it")]
+    pub gas_floor: Option<f64>,
+
+    #[clap(long, env = "TYCHO_AUTO_REBALANCE", help = "Submit wrap and unwrap transactions for rebalancing suggestions instead of only logging them")]
+    pub auto_rebalance: bool,
+
+    #[clap(long, env = "TYCHO_GAS_MARGIN_BPS", help = "Set gas limits to the simulated gas usage plus this margin in BPS instead of fixed limits, e.g. 2000")]
+    pub gas_margin_bps: Option<u64>,
+
+    #[clap(long, env = "TYCHO_PROFIT_GUARD", help = "Revert bundles on chain unless each path returns its input, its gas cost and TYCHO_MIN_PROFIT_BPS, instead of only its slippage-adjusted output")]
+    pub profit_guard: bool,
+
+    #[clap(long, env = "TYCHO_TRACE_SIMULATIONS", help = "Trace the call tree of reverted simulations and of those falling short of the expected output; needs the debug namespace of the RPC node")]
+    pub trace_simulations: bool,
+
+    #[clap(long, env = "TYCHO_SIMULATION_CACHE_SIZE", help = "Reuse up to this many simulation results for paths simulated again with the same amount, nonce and unchanged pools")]
+    pub simulation_cache_size: Option<usize>,
+
+    #[clap(long, env = "TYCHO_RECEIVER_ADDRESS", help = "Address receiving the output of executed paths, e.g. a cold wallet. Defaults to the executor")]
+    pub receiver_address: Option<String>,
+
+    #[clap(long, env = "TYCHO_CONFIG_FILE", help = "TOML or JSON settings file. Environment variables and command line arguments override its values")]
+    pub config: Option<PathBuf>,
+
+    /// Settings merged from the settings file, the environment and the command line
+    #[clap(skip)]
+    pub settings: ArbitrageSettings,
+}
 
 impl Args {
     /// Set environment variables from parsed CLI arguments
@@ -68,7 +291,12 @@ impl Args {
         tracing::debug!("Setting environment variables from CLI arguments");
         
         // Set required environment variables from CLI arguments
-        env::set_var("TYCHO_EXECUTOR_PRIVATE_KEY", &self.executor_private_key);
+        if let Some(ref executor_key) = self.executor_private_key {
+            env::set_var("TYCHO_EXECUTOR_PRIVATE_KEY", executor_key);
+        }
+        if let Some(ref keystore) = self.executor_keystore {
+            env::set_var("TYCHO_EXECUTOR_KEYSTORE", keystore);
+        }
         env::set_var("TYCHO_CHAIN", &self.chain);
         env::set_var("TYCHO_RPC_URL", &self.rpc_url);
         env::set_var("TYCHO_API_KEY", &self.tycho_api_key);
@@ -104,6 +332,68 @@ impl Args {
         Ok(())
     }
 
+    /// Load the settings in layers and apply them to the arguments.
+    ///
+    /// The defaults of this bot are overridden by the settings file, which is
+    /// overridden by environment variables, which are in turn overridden by
+    /// values given on the command line.
+    pub fn with_settings(mut self, matches: &ArgMatches) -> Result<Self> {
+        let mut loader = SettingsLoader::new()
+            .with_overrides(self.settings_from(matches, ValueSource::DefaultValue));
+        if let Some(path) = &self.config {
+            loader = loader.with_file(path)?;
+        }
+
+        let settings = loader
+            .with_env()?
+            .with_overrides(self.settings_from(matches, ValueSource::CommandLine))
+            .load()?;
+
+        self.chain = settings.chain.clone();
+        self.tvl_threshold = settings.tvl_threshold;
+        self.min_profit_bps = settings.min_profit_bps;
+        self.slippage_bps = settings.slippage_bps;
+        self.bribe_percentage = settings.bribe_percentage;
+        self.optimization_tolerances = settings.optimization_tolerances.clone();
+        self.receiver_address = settings.receiver_address.map(|address| address.to_string());
+        self.settings = settings;
+
+        Ok(self)
+    }
+
+    /// Settings values that were taken from the given source.
+    fn settings_from(&self, matches: &ArgMatches, source: ValueSource) -> serde_json::Value {
+        let from_source = |id: &str| matches.value_source(id) == Some(source);
+        let mut overrides = serde_json::Map::new();
+
+        if from_source("chain") {
+            overrides.insert("chain".to_string(), self.chain.clone().into());
+        }
+        if from_source("tvl_threshold") {
+            overrides.insert("tvl_threshold".to_string(), self.tvl_threshold.into());
+        }
+        if from_source("min_profit_bps") {
+            overrides.insert("min_profit_bps".to_string(), self.min_profit_bps.into());
+        }
+        if from_source("slippage_bps") {
+            overrides.insert("slippage_bps".to_string(), self.slippage_bps.into());
+        }
+        if from_source("bribe_percentage") {
+            overrides.insert("bribe_percentage".to_string(), self.bribe_percentage.into());
+        }
+        if let (Some(receiver_address), true) = (&self.receiver_address, from_source("receiver_address")) {
+            overrides.insert("receiver_address".to_string(), receiver_address.clone().into());
+        }
+        if from_source("optimization_tolerances") {
+            overrides.insert(
+                "optimization_tolerances".to_string(),
+                self.optimization_tolerances.clone().into(),
+            );
+        }
+
+        serde_json::Value::Object(overrides)
+    }
+
     pub fn with_defaults(mut self) -> Result<Self> {
         // Handle default WETH token first if no start tokens provided
         if self.start_tokens.is_empty() {
@@ -111,65 +401,37 @@ impl Args {
             self.start_tokens.push(weth_address);
         }
 
-        // Now handle optimization tolerances based on final start_tokens count
-        use std::cmp::Ordering;
-        match self.start_tokens.len().cmp(&self.optimization_tolerances.len()) {
-            Ordering::Greater => {
-                // More start tokens than tolerances - extend with 1.0 defaults
-                let diff = self.start_tokens.len() - self.optimization_tolerances.len();
-                self.optimization_tolerances.extend(vec![1.0; diff]);
-            }
-            Ordering::Less => {
-                // More tolerances than start tokens - truncate to match
-                self.optimization_tolerances.truncate(self.start_tokens.len());
-            }
-            Ordering::Equal => {
-                // Equal counts - no change needed
-            }
+        // Start tokens without a positional tolerance use the default of the settings
+        self.optimization_tolerances.truncate(self.start_tokens.len());
+
+        // Balance caps follow the same rules, defaulting to the full balance
+        if self.balance_cap_percentages.len() < self.start_tokens.len() {
+            let diff = self.start_tokens.len() - self.balance_cap_percentages.len();
+            self.balance_cap_percentages.extend(vec![100.0; diff]);
+        } else {
+            self.balance_cap_percentages.truncate(self.start_tokens.len());
         }
 
-        // Handle completely empty optimization_tolerances (fallback safety)
-        if self.optimization_tolerances.is_empty() && !self.start_tokens.is_empty() {
-            self.optimization_tolerances = vec![1.0; self.start_tokens.len()];
+        // Path lengths default to the global maximum
+        if self.max_path_lengths.len() < self.start_tokens.len() {
+            let diff = self.start_tokens.len() - self.max_path_lengths.len();
+            self.max_path_lengths.extend(vec![self.max_path_length; diff]);
+        } else {
+            self.max_path_lengths.truncate(self.start_tokens.len());
         }
 
         Ok(self)
     }
 
     fn get_weth_address(chain: &str) -> Result<String> {
-        WETH_ADDRESSES
-            .iter()
-            .find(|(c, _)| *c == chain)
-            .map(|(_, addr)| addr.to_string())
-            .ok_or_else(|| {
-                anyhow::anyhow!("Default WETH address not set for chain: {}", chain).into()
-            })
+        Ok(Self::get_token_address("WETH", chain)?.to_string())
     }
 
     fn get_token_address(token_symbol: &str, chain: &str) -> Result<Bytes> {
-        let address_str = match token_symbol {
-            "WETH" => WETH_ADDRESSES
-                .iter()
-                .find(|(c, _)| *c == chain)
-                .map(|(_, addr)| *addr),
-            "USDC" => USDC_ADDRESSES
-                .iter()
-                .find(|(c, _)| *c == chain)
-                .map(|(_, addr)| *addr),
-            "WBTC" => WBTC_ADDRESSES
-                .iter()
-                .find(|(c, _)| *c == chain)
-                .map(|(_, addr)| *addr),
-            _ => None,
-        };
-
-        match address_str {
-            Some(addr) => Bytes::from_str(addr).map_err(|e| anyhow::anyhow!("Invalid address format: {}", e).into()),
-            None => Err(anyhow::anyhow!("Token {} not supported on chain {}", token_symbol, chain).into()),
-        }
+        let address = well_known_token_address(chain, token_symbol)?;
+        Ok(Bytes::from(address.to_vec()))
     }
 
-
     pub fn native_token(&self) -> Result<Bytes> {
         Self::get_token_address("WETH", &self.chain)
     }
@@ -217,6 +479,144 @@ impl Args {
         Ok(source_tokens)
     }
 
+    pub fn search_budget(&self) -> Result<SearchBudget> {
+        let mut budget = match self.search_budget_ms {
+            Some(ms) => SearchBudget::new(Duration::from_millis(ms)),
+            None => SearchBudget::for_chain(&self.chain)?,
+        };
+
+        if let Some(max_paths) = self.max_paths {
+            budget = budget.with_max_paths(max_paths);
+        }
+        if let Some(max_simulations) = self.max_simulations {
+            budget = budget.with_max_simulations(max_simulations);
+        }
+
+        Ok(budget)
+    }
+
+    /// The configured risk limits, or `None` if no limit is set.
+    ///
+    /// Native token amounts are converted to base units assuming 18 decimals.
+    pub fn risk_limits(&self) -> Option<RiskLimits> {
+        let to_base_units = |amount: f64, decimals: i32| {
+            convert::f64_to_biguint_saturating(amount * 10f64.powi(decimals), Rounding::Floor)
+        };
+
+        let mut limits = RiskLimits::new();
+        if let Some(max_notional) = self.max_trade_notional {
+            limits = limits.with_max_notional_per_trade(to_base_units(max_notional, 18));
+        }
+        if let Some(max_gas_price_gwei) = self.max_gas_price_gwei {
+            let max_gas_price = convert::biguint_to_u256_saturating(&to_base_units(max_gas_price_gwei, 9));
+            limits = limits.with_max_gas_price(max_gas_price.saturating_to());
+        }
+        if let Some(max_daily_loss) = self.max_daily_loss {
+            limits = limits.with_max_daily_loss(to_base_units(max_daily_loss, 18));
+        }
+        if let Some(max_failures) = self.max_consecutive_failures {
+            limits = limits.with_max_consecutive_failures(max_failures);
+        }
+
+        (limits != RiskLimits::new()).then_some(limits)
+    }
+
+    /// The configured alert rules and webhook, or `None` if no rule is set.
+    ///
+    /// Native token amounts are converted to base units assuming 18 decimals.
+    /// Without a webhook URL, triggered alerts are only logged.
+    pub fn alert_monitor(&self) -> Result<Option<AlertMonitor>> {
+        let mut monitor = AlertMonitor::new().with_cooldown_blocks(self.alert_cooldown_blocks);
+        if let Some(min_profit) = self.alert_min_profit {
+            monitor = monitor.with_rule(AlertRule::ProfitAbove {
+                min_net_profit: convert::f64_to_biguint_saturating(min_profit * 1e18, Rounding::Floor),
+            });
+        }
+        if let Some(max_rate) = self.alert_max_error_rate {
+            monitor = monitor.with_rule(AlertRule::ErrorRateAbove {
+                max_rate,
+                window_blocks: self.alert_error_window_blocks,
+            });
+        }
+        if self.alert_low_inventory {
+            monitor = monitor.with_rule(AlertRule::InventoryBelowFloor);
+        }
+        if let Some(max_lag_blocks) = self.alert_max_stream_lag {
+            monitor = monitor.with_rule(AlertRule::StreamLagAbove { max_lag_blocks });
+        }
+        if monitor.rules().is_empty() {
+            return Ok(None);
+        }
+
+        if let Some(url) = &self.alert_webhook_url {
+            let mut webhook = WebhookSink::new(url)?;
+            if let Some(template) = &self.alert_webhook_template {
+                webhook = webhook.with_template(template.as_str())?;
+            }
+            monitor = monitor.with_sink(Arc::new(webhook));
+        }
+        Ok(Some(monitor))
+    }
+
+    /// The rate limit shared by all RPC callers, or `None` if neither a rate nor a concurrency cap is set.
+    pub fn rpc_rate_limit(&self) -> Option<RateLimit> {
+        if self.rpc_rate_limit.is_none() && self.rpc_max_concurrent.is_none() {
+            return None;
+        }
+        let limit = RateLimit::per_second(self.rpc_rate_limit.unwrap_or(f64::MAX));
+        Some(match self.rpc_max_concurrent {
+            Some(max_concurrent) => limit.with_max_concurrent(max_concurrent),
+            None => limit,
+        })
+    }
+
+    /// The rate limit of each RPC endpoint with one configured.
+    pub fn rpc_endpoint_rate_limits(&self) -> Result<Vec<(String, RateLimit)>> {
+        self.rpc_endpoint_rate_limits
+            .iter()
+            .map(|entry| {
+                let (url, requests_per_second) = entry
+                    .rsplit_once('=')
+                    .ok_or_else(|| anyhow::anyhow!("Invalid endpoint rate limit {}, expected url=requests_per_second", entry))?;
+                let requests_per_second: f64 = requests_per_second
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("Invalid requests per second in endpoint rate limit {}: {}", entry, e))?;
+                Ok((url.to_string(), RateLimit::per_second(requests_per_second)))
+            })
+            .collect()
+    }
+
+    pub fn submission_mode(&self) -> Result<SubmissionMode> {
+        Ok(SubmissionMode::from_str(&self.submission_mode)?)
+    }
+
+    /// The submission scheduler for slots of `block_time`.
+    pub fn submission_scheduler(&self, block_time: Duration) -> Result<SubmissionScheduler> {
+        let timing = SubmissionTiming::from_str(&self.submission_timing)?;
+        SubmissionScheduler::new(timing, Duration::from_millis(self.submission_offset_ms), block_time)
+    }
+
+    pub fn replay_speed(&self) -> Result<ReplaySpeed> {
+        Ok(ReplaySpeed::from_str(&self.replay_speed)?)
+    }
+
+    pub fn execution_mode(&self) -> ExecutionMode {
+        if self.dry_run || self.backtest_file.is_some() {
+            ExecutionMode::DryRun
+        } else {
+            ExecutionMode::Live
+        }
+    }
+
+    pub fn transfer_type(&self) -> Result<UserTransferType> {
+        match self.transfer_type.to_lowercase().as_str() {
+            "permit2" => Ok(UserTransferType::TransferFromPermit2),
+            "transfer-from" => Ok(UserTransferType::TransferFrom),
+            "none" => Ok(UserTransferType::None),
+            other => Err(anyhow::anyhow!("Unknown transfer type: {}", other).into()),
+        }
+    }
+
     pub fn tycho_url(&self) -> Result<String> {
         use tycho_atomic_arbitrage::utils::get_default_tycho_url;
         
@@ -233,7 +633,11 @@ impl Args {
 }
 
 pub fn parse_cli_args() -> Result<Args> {
-    let args = Args::parse().with_defaults()?;
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches)
+        .unwrap_or_else(|e| e.exit())
+        .with_settings(&matches)?
+        .with_defaults()?;
     
     // Set environment variables from CLI arguments so that config module can find them
     args.set_environment_variables()?;
@@ -3,22 +3,38 @@
 //! This module orchestrates the complete arbitrage search and execution process,
 //! coordinating between path optimization, simulation, and trade execution.
 
+use alloy::primitives::U256;
 use futures::StreamExt;
-use tycho_atomic_arbitrage::errors::Result;
+use num_bigint::{BigInt, BigUint};
+use std::collections::HashMap;
+use tycho_atomic_arbitrage::{
+    bundle::ExecutionMode,
+    errors::{OpportunityContext, OpportunityId, Result},
+    events::{EventHandler, OpportunityEvent, RiskEvent, SimulationEvent, SimulationOutcome, SubmissionEvent},
+    path::{PathExt, PathId, PortfolioCandidate, PortfolioSelector},
+    simulation::checked_amount,
+    utils::convert::{self, Rounding},
+};
+use tycho_common::Bytes;
 
 use super::{
-    components::{ExecutionContext, MarketContext, SearchParams},
+    components::{ExecutionContext, MarketContext, PendingExecution, SearchParams},
     logging::{BlockSummary, PathLogger},
-    optimization, simulation,
+    optimization,
+    simulation::{self, ProfitableOpportunity},
 };
 
+/// Ethereum WETH address; bundles are only submitted on Ethereum mainnet.
+const ETHEREUM_WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
 /// Execute the complete arbitrage search process.
 ///
 /// This function coordinates the entire arbitrage workflow:
 /// 1. Filter and optimize paths for profitability
 /// 2. Get current nonce and base fee
 /// 3. Run simulations for profitable paths
-/// 4. Process simulation results and execute profitable trades
+/// 4. Process simulation results and collect profitable opportunities
+/// 5. Select a portfolio of non-conflicting opportunities and execute it
 pub async fn execute_arbitrage_search(
     search_params: SearchParams,
     market_context: MarketContext<'_>,
@@ -31,6 +47,24 @@ pub async fn execute_arbitrage_search(
         "Starting arbitrage search"
     );
 
+    let deadline = search_params.deadline;
+
+    // Value every source token in native units so opportunities can be ranked across tokens
+    let reference_rates = simulation::native_reference_rates(
+        &*market_context.path_finder.source_balances.read().await,
+        &execution_context.params.native_token,
+        &market_context.market_data.graph,
+        &market_context.market_data.protocol_sim,
+        &market_context.market_data.protocol_comp,
+    ).await;
+
+    // Rank by profit after estimated gas, priced at the base fee following the last recorded block
+    let estimated_gas_price = execution_context
+        .trade_executor
+        .base_fee_predictor
+        .predict_next()
+        .map(convert::u256_to_f64);
+
     // Step 1: Filter and optimize paths
     let (profitable_paths, initial_paths, candidate_paths) = optimization::filter_and_optimize_paths(
         search_params.updated_pools,
@@ -39,21 +73,50 @@ pub async fn execute_arbitrage_search(
         &market_context.market_data.protocol_sim,
         &market_context.market_data.protocol_comp,
         &market_context.path_finder.source_balances,
+        &market_context.path_finder.balance_caps,
+        &reference_rates,
         &market_context.path_finder.optimization_tolerances,
+        market_context.path_finder.ranking.as_deref(),
+        market_context.path_finder.ranked_paths,
+        market_context.path_finder.cooldowns.as_deref(),
+        &market_context.market_data.pool_versions,
+        &market_context.path_finder.evaluation_cache,
+        &market_context.path_finder.spot_price_cache,
+        &market_context.path_finder.limit_cache,
+        estimated_gas_price.map(|gas_price| (&market_context.path_finder.protocol_gas, gas_price)),
         execution_context.params.min_profit_bps,
         search_params.block_number,
+        &deadline,
         logger,
     ).await?;
 
+    // Skip paths through protocols whose local simulations proved unreliable
+    let profitable_paths = match execution_context.trade_executor.simulator.accuracy_tracker() {
+        Some(tracker) => profitable_paths
+            .into_iter()
+            .filter(|path| match tracker.untrusted_protocol(path) {
+                Some(protocol_system) => {
+                    tracing::debug!(
+                        protocol_system = protocol_system,
+                        stats = ?tracker.stats(protocol_system),
+                        "Skipping path through distrusted protocol"
+                    );
+                    false
+                }
+                None => true,
+            })
+            .collect(),
+        None => profitable_paths,
+    };
+
     if profitable_paths.is_empty() {
         // Log block summary even if no profitable paths found
         let block_summary = BlockSummary {
             block_number: search_params.block_number,
             initial_paths,
             candidate_paths,
-            optimised_profitable_paths: 0,
-            successful_simulations: 0,
-            profitable_simulations: 0,
+            search_duration: Some(deadline.elapsed()),
+            ..Default::default()
         };
         
         if let Err(e) = logger.log_block_summary(&block_summary) {
@@ -62,15 +125,30 @@ pub async fn execute_arbitrage_search(
                 "Failed to log block summary"
             );
         }
+        execution_context.block_stats.record_summary(block_summary);
         
         tracing::info!("No profitable paths found");
         return Ok(());
     }
 
+    let events = execution_context.events;
+    for path in &profitable_paths {
+        events.on_opportunity_found(&OpportunityEvent {
+            block_number: search_params.block_number,
+            opportunity_id: OpportunityId::of_path_ext(path, search_params.block_number),
+            path,
+            expected_profit: path.profit().unwrap_or_else(|_| BigInt::from(0)),
+        });
+    }
+
+    // Paths arrive ranked by native-denominated profit across all source tokens,
+    // so a limited simulation budget is spent on the globally best opportunities
     let profitable_paths_count = profitable_paths.len();
     
     tracing::info!(
         profitable_paths_count = profitable_paths_count,
+        max_simulations = deadline.max_simulations().min(profitable_paths_count),
+        remaining_budget_ms = deadline.remaining().as_millis(),
         "Found profitable paths, proceeding with simulations"
     );
 
@@ -78,6 +156,9 @@ pub async fn execute_arbitrage_search(
     let (nonce, base_fee) = simulation::get_nonce_and_base_fee(
         &execution_context.trade_executor.provider,
         execution_context.trade_executor.signer.address(),
+        execution_context.trade_executor.simulator.simulation_block(),
+        &execution_context.trade_executor.base_fee_predictor,
+        execution_context.trade_executor.rate_limiter.as_deref(),
     ).await?;
 
     tracing::debug!(
@@ -86,6 +167,20 @@ pub async fn execute_arbitrage_search(
         "Retrieved nonce and base fee for simulations"
     );
 
+    // Long paths may lose their profit to calldata alone, skip them before simulating
+    let profitable_paths = simulation::retain_profitable_after_gas(
+        profitable_paths,
+        base_fee,
+        &reference_rates,
+        &execution_context.trade_executor.simulator,
+        &execution_context.trade_executor.signer,
+    );
+    tracing::debug!(
+        skipped_paths = profitable_paths_count - profitable_paths.len(),
+        remaining_paths = profitable_paths.len(),
+        "Filtered paths by estimated gas cost"
+    );
+
     // Step 3: Run simulations
     let mut simulation_stream = simulation::run_simulations(
         profitable_paths,
@@ -94,42 +189,77 @@ pub async fn execute_arbitrage_search(
         &execution_context.trade_executor.provider,
         &execution_context.trade_executor.simulator,
         &execution_context.trade_executor.signer,
+        deadline,
     ).await;
 
     let mut processed_count = 0;
     let mut successful_count = 0;
     let mut failed_count = 0;
     let mut profitable_count = 0;
+    let mut opportunities: Vec<ProfitableOpportunity> = Vec::new();
 
     // Step 4: Process simulation results
-    while let Some((path, sim_result)) = simulation_stream.next().await {
+    while let Some((path, sim_result, simulation_duration)) = simulation_stream.next().await {
         processed_count += 1;
+        execution_context.block_stats.record_simulation(search_params.block_number, simulation_duration);
+        let path_id = PathId::of_path_ext(&path);
+        let opportunity_id = OpportunityId::of_path_ext(&path, search_params.block_number);
+        // The path is consumed by processing, keep a copy only if someone listens
+        let event_path = (!events.is_empty()).then(|| path.clone());
+        let notify = |outcome: SimulationOutcome| {
+            if let Some(path) = &event_path {
+                events.on_simulation_complete(&SimulationEvent {
+                    block_number: search_params.block_number,
+                    opportunity_id,
+                    path,
+                    outcome,
+                });
+            }
+        };
         
-        match sim_result {
+        match sim_result.with_opportunity(opportunity_id) {
             Ok(simulation_result) => {
                 match simulation::process_simulation_result(
                     simulation_result,
                     path,
                     search_params.block_number,
                     base_fee,
-                    &execution_context.trade_executor.executor,
                     &execution_context.params.native_token,
+                    execution_context.params.max_output_shortfall_bps,
+                    execution_context.params.min_profit_gas_multiple,
+                    execution_context.trade_executor.l2_fees.as_deref(),
                     &market_context.market_data.graph,
                     &market_context.market_data.protocol_sim,
                     &market_context.market_data.protocol_comp,
                     logger,
-                ).await {
-                    Ok(was_profitable) => {
+                ).await.with_opportunity(opportunity_id) {
+                    Ok(opportunity) => {
                         successful_count += 1;
-                        if was_profitable {
+                        notify(match &opportunity {
+                            Some(opportunity) => SimulationOutcome::Profitable {
+                                net_profit: opportunity.net_profit.clone(),
+                            },
+                            None => SimulationOutcome::Unprofitable,
+                        });
+                        if let Some(ranking) = &market_context.path_finder.ranking {
+                            let profit = opportunity.as_ref().map_or(0.0, |opportunity| opportunity.score());
+                            ranking.record(path_id, profit, search_params.block_number);
+                        }
+                        if let Some(opportunity) = opportunity {
                             profitable_count += 1;
+                            opportunities.push(opportunity);
                         }
                         tracing::debug!("Simulation result processed successfully");
                     }
                     Err(e) => {
                         failed_count += 1;
+                        notify(SimulationOutcome::Failed { error: e.to_string() });
+                        if let Some(ranking) = &market_context.path_finder.ranking {
+                            ranking.record(path_id, 0.0, search_params.block_number);
+                        }
                         tracing::info!(
-                            error = %e,
+                            opportunity_id = %opportunity_id,
+                            error = %e.without_context(),
                             "Failed to process simulation result"
                         );
                     }
@@ -137,14 +267,36 @@ pub async fn execute_arbitrage_search(
             }
             Err(e) => {
                 failed_count += 1;
+                notify(SimulationOutcome::Failed { error: e.to_string() });
+                if let Some(ranking) = &market_context.path_finder.ranking {
+                    ranking.record(path_id, 0.0, search_params.block_number);
+                }
+                // A reverting simulation predicts a reverting bundle
+                if let Some(cooldowns) = &market_context.path_finder.cooldowns {
+                    cooldowns.record_failure(&path, search_params.block_number);
+                }
                 tracing::error!(
-                    error = %e,
+                    opportunity_id = %opportunity_id,
+                    error = %e.without_context(),
                     "Simulation failed for path"
                 );
             }
         }
     }
 
+    // Step 5: Execute the best set of opportunities that do not interfere with each other
+    if !opportunities.is_empty() {
+        execute_portfolio(
+            opportunities,
+            search_params.block_number,
+            base_fee,
+            &reference_rates,
+            &market_context,
+            &execution_context,
+            logger,
+        ).await?;
+    }
+
     // Log block summary with all collected statistics
     let block_summary = BlockSummary {
         block_number: search_params.block_number,
@@ -153,6 +305,8 @@ pub async fn execute_arbitrage_search(
         optimised_profitable_paths: profitable_paths_count,
         successful_simulations: successful_count,
         profitable_simulations: profitable_count,
+        failed_simulations: failed_count,
+        search_duration: Some(deadline.elapsed()),
     };
     
     if let Err(e) = logger.log_block_summary(&block_summary) {
@@ -161,6 +315,7 @@ pub async fn execute_arbitrage_search(
             "Failed to log block summary"
         );
     }
+    execution_context.block_stats.record_summary(block_summary);
 
     tracing::info!(
         block_number = search_params.block_number,
@@ -168,6 +323,8 @@ pub async fn execute_arbitrage_search(
         successful_simulations = successful_count,
         failed_simulations = failed_count,
         profitable_simulations = profitable_count,
+        elapsed_ms = deadline.elapsed().as_millis(),
+        deadline_expired = deadline.is_expired(),
         success_rate = if processed_count > 0 {
             format!("{:.1}%", (successful_count as f64 / processed_count as f64) * 100.0)
         } else {
@@ -179,6 +336,233 @@ pub async fn execute_arbitrage_search(
     Ok(())
 }
 
+/// Select a portfolio from the block's profitable opportunities and submit it.
+///
+/// Opportunities are selected greedily by net profit, up to the maximum portfolio
+/// size, while keeping their pools disjoint unless shared pools are allowed and
+/// their combined input within the available source balances.
+/// If a risk limit blocks submission, the portfolio is only built as a dry run.
+async fn execute_portfolio(
+    opportunities: Vec<ProfitableOpportunity>,
+    block_number: u64,
+    base_fee: U256,
+    reference_rates: &HashMap<Bytes, f64>,
+    market_context: &MarketContext<'_>,
+    execution_context: &ExecutionContext<'_>,
+    logger: &PathLogger,
+) -> Result<()> {
+    let native_token = &execution_context.params.native_token;
+    if native_token.to_string().to_lowercase() != ETHEREUM_WETH.to_lowercase() {
+        tracing::debug!(
+            native_token = %native_token,
+            opportunities = opportunities.len(),
+            "Bundle execution only supported on Ethereum, skipping"
+        );
+        return Ok(());
+    }
+
+    let balances = market_context.path_finder.source_balances.read().await.clone();
+    let native_balance_before = balances.get(native_token).cloned();
+    let mut selector = PortfolioSelector::new(balances)
+        .with_disjoint_pools(!execution_context.params.allow_shared_pools);
+    if let Some(max_size) = execution_context.params.max_portfolio_size {
+        selector = selector.with_max_opportunities(max_size);
+    }
+
+    let candidates_count = opportunities.len();
+    let portfolio = selector.select(opportunities);
+
+    tracing::info!(
+        candidates = candidates_count,
+        selected = portfolio.len(),
+        total_net_profit = portfolio.total_score(),
+        "Executing opportunity portfolio"
+    );
+
+    let executed_paths: Vec<(PathId, f64)> = portfolio
+        .entries
+        .iter()
+        .map(|entry| (PathId::of_path_ext(entry.candidate.path()), entry.candidate.score()))
+        .collect();
+    let notionals: Vec<BigUint> = portfolio
+        .entries
+        .iter()
+        .map(|entry| native_notional(entry.candidate.path(), reference_rates))
+        .collect();
+
+    let executed_path_exts: Vec<_> = portfolio.entries.iter().map(|entry| entry.candidate.path.clone()).collect();
+    let opportunity_ids: Vec<OpportunityId> = portfolio.entries.iter().map(|entry| entry.candidate.opportunity_id).collect();
+
+    let trade_inputs: Vec<(Bytes, BigUint)> = portfolio
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            let first_swap = entry.candidate.path().first()?;
+            Some((first_swap.token_in.address.clone(), first_swap.amount_in.clone()))
+        })
+        .collect();
+
+    let legs = portfolio
+        .into_candidates()
+        .into_iter()
+        .map(ProfitableOpportunity::into_bundle_leg)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let risk = execution_context.trade_executor.risk.as_deref();
+    let mut execution_mode = execution_context.trade_executor.executor.execution_mode();
+    if let Some(risk) = risk {
+        // The gas price limit applies to what the swaps pay, bribe included
+        let max_fee_per_gas = execution_context
+            .trade_executor
+            .executor
+            .max_fee_per_gas(&legs, block_number + 1, base_fee)?;
+        if let Err(breach) = risk.check_submission(&notionals, max_fee_per_gas) {
+            tracing::warn!(
+                block_number = block_number,
+                limit = breach.kind(),
+                reason = %breach,
+                "Risk limit breached, building bundles without submitting"
+            );
+            execution_context.events.on_risk_breach(&RiskEvent {
+                block_number,
+                breach: &breach,
+            });
+            execution_mode = ExecutionMode::DryRun;
+        }
+    }
+
+    let base_nonce = legs.first()
+        .and_then(|leg| leg.tx_requests.first())
+        .and_then(|req| req.nonce);
+
+    match execution_context
+        .trade_executor
+        .executor
+        .execute_portfolio_with_mode(legs, block_number + 1, base_fee, execution_mode)
+        .await
+    {
+        Ok(submissions) if submissions.is_empty() => {
+            tracing::info!(
+                target_block = block_number + 1,
+                "All opportunities failed pre-submission checks, nothing submitted"
+            );
+        }
+        Ok(submissions) => {
+            let successful_count = submissions.iter().filter(|s| s.is_successful()).count();
+            tracing::info!(
+                successful_submissions = successful_count,
+                total_submissions = submissions.len(),
+                opportunity_ids = ?opportunity_ids.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                "Bundle execution completed"
+            );
+
+            execution_context.events.on_bundle_submitted(&SubmissionEvent {
+                target_block: block_number + 1,
+                paths: &executed_path_exts,
+                opportunity_ids: &opportunity_ids,
+                submissions: &submissions,
+            });
+
+            // Dry runs only record the would-be bundles, nothing can land or fail
+            let dry_run_bundles: Vec<_> = submissions.iter().filter_map(|s| s.dry_run_bundle()).collect();
+            if !dry_run_bundles.is_empty() {
+                for bundle in dry_run_bundles {
+                    if let Err(e) = logger.log_dry_run_bundle(bundle, block_number) {
+                        tracing::warn!(
+                            error = %e,
+                            "Failed to log dry-run bundle"
+                        );
+                    }
+                }
+                return Ok(());
+            }
+
+            execution_context.block_stats.record_submissions(block_number, successful_count);
+
+            if successful_count > 0 {
+                let inventory = &execution_context.trade_executor.inventory;
+                for (token, amount_in) in &trade_inputs {
+                    inventory.record_usage(token, amount_in);
+                    tracing::debug!(
+                        token = %token,
+                        utilization = ?inventory.utilization(token),
+                        "Recorded inventory usage"
+                    );
+                }
+            }
+
+            // Submitted paths count once more, so routes that reach execution rank higher
+            if let (Some(ranking), true) = (&market_context.path_finder.ranking, successful_count > 0) {
+                for (path_id, profit) in &executed_paths {
+                    ranking.record(*path_id, *profit, block_number);
+                }
+            }
+
+            // Inclusion is checked once the target block arrives
+            match (successful_count > 0, base_nonce) {
+                (true, Some(base_nonce)) => {
+                    let simulator = &execution_context.trade_executor.simulator;
+                    let checked_amounts = executed_path_exts
+                        .iter()
+                        .map(|path| {
+                            let expected_amount_out = path.last().map(|swap| swap.amount_out.clone()).unwrap_or_default();
+                            checked_amount(&expected_amount_out, simulator.slippage_bps(path))
+                        })
+                        .collect();
+                    execution_context.trade_executor.pending_executions.write().await.push(PendingExecution {
+                        base_nonce,
+                        target_block: block_number + 1,
+                        paths: executed_path_exts,
+                        opportunity_ids,
+                        checked_amounts,
+                        native_balance_before,
+                    });
+                }
+                _ => {
+                    if let Some(risk) = risk {
+                        risk.record_bundle_result(false);
+                    }
+                    if let Some(cooldowns) = &market_context.path_finder.cooldowns {
+                        for path in &executed_path_exts {
+                            cooldowns.record_failure(path, block_number);
+                        }
+                    }
+                }
+            }
+
+            // A submitted approval may land, so allowances are re-read next block
+            if successful_count > 0 {
+                if let Some(cache) = execution_context.trade_executor.simulator.allowance_cache() {
+                    cache.mark_stale();
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                "Bundle execution failed"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Value the input of a path in native token base units.
+///
+/// Inputs of source tokens without a reference rate cannot be valued and are
+/// treated as unbounded, so a notional limit blocks them.
+fn native_notional(path: &PathExt, reference_rates: &HashMap<Bytes, f64>) -> BigUint {
+    let Some(first_swap) = path.first() else {
+        return BigUint::default();
+    };
+    let rate = reference_rates
+        .get(&first_swap.token_in.address)
+        .copied()
+        .unwrap_or(f64::INFINITY);
+    convert::f64_to_biguint_saturating(convert::biguint_to_f64(&first_swap.amount_in) * rate, Rounding::Floor)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,131 +1,156 @@
 //! Balance management for arbitrage operations.
 //!
-//! This module handles token balance queries and updates for the arbitrage bot.
+//! Balances are read through the library's `InventoryManager`, which tracks
+//! them across blocks and suggests how to restore depleted source tokens.
 
-use alloy::{
-    network::Ethereum,
-    primitives::{Address, U256},
-    providers::{Provider, RootProvider},
-};
-use futures::stream::{self, StreamExt};
-use num_bigint::BigUint;
 use std::sync::Arc;
-use tycho_atomic_arbitrage::{errors::Result, utils::u256_to_biguint};
-use tycho_common::Bytes;
 
-/// Update the source token balances for the given signer address.
+use alloy::{primitives::U256, providers::Provider};
+use tycho_atomic_arbitrage::{
+    bootstrap::ExecutorBootstrap, errors::Result, inventory::RebalanceAction, simulation::AllowanceCache,
+};
+
+use super::components::{ArbitrageParams, PathFinder, TradeExecutor};
+use super::simulation;
+
+/// Update the source token balances of the executor.
 pub async fn update_source_balances(
-    path_finder: &super::components::PathFinder,
-    provider: &Arc<RootProvider<Ethereum>>,
-    signer_address: Address,
+    path_finder: &PathFinder,
+    trade_executor: &TradeExecutor,
+    block_number: u64,
 ) -> Result<()> {
-    let balance_futures = path_finder.source_tokens.iter().map(|token| {
-        let provider = Arc::clone(provider);
-        let token_address = Address::from_slice(token.as_ref());
-        async move {
-            let balance = get_token_balance(provider, token_address, signer_address).await?;
-            Ok((token.clone(), balance))
-        }
-    });
+    let balances = trade_executor.inventory
+        .refresh(&trade_executor.provider, &path_finder.source_tokens, block_number)
+        .await?;
 
-    let results: Vec<Result<(Bytes, BigUint)>> = stream::iter(balance_futures)
-        .buffer_unordered(10)
-        .collect()
-        .await;
+    path_finder.source_balances.write().await.extend(balances);
+    Ok(())
+}
 
-    let mut balances = path_finder.source_balances.write().await;
-    let mut successful_updates = 0;
+/// Check the source token balances and Permit2 allowances of the executor on startup.
+///
+/// Unfunded tokens and missing approvals are logged. If enabled, the missing
+/// approvals are submitted as one bundle of unlimited approvals, so bundles no
+/// longer need to carry them once it lands.
+pub async fn bootstrap_executor(
+    bootstrap: &ExecutorBootstrap,
+    path_finder: &PathFinder,
+    trade_executor: &TradeExecutor,
+    params: &ArbitrageParams,
+) -> Result<()> {
+    let block_number = trade_executor.provider.get_block_number().await?;
+    // Without a cache of the simulator the allowances are read once and discarded
+    let allowances = match trade_executor.simulator.allowance_cache() {
+        Some(cache) => cache.clone(),
+        None => Arc::new(AllowanceCache::new(trade_executor.signer.address(), bootstrap.permit2())),
+    };
+    let report = bootstrap
+        .check(&trade_executor.provider, &trade_executor.inventory, &allowances, &path_finder.source_tokens, block_number)
+        .await?;
 
-    for result in results {
-        match result {
-            Ok((token, balance)) => {
-                balances.insert(token.clone(), balance.clone());
-                successful_updates += 1;
-                
-                tracing::debug!(
-                    token = %token,
-                    balance = %balance,
-                    "Token balance updated"
-                );
-            }
-            Err(e) => {
-                tracing::error!(
-                    error = %e,
-                    "Failed to update token balance"
-                );
-            }
-        }
+    for token in report.unfunded_tokens() {
+        tracing::warn!(token = %token, "Source token is not funded, its paths cannot be executed");
     }
-
-    tracing::debug!(
-        total_tokens = path_finder.source_tokens.len(),
-        successful_updates = successful_updates,
-        "Balance update completed"
-    );
-
-    if successful_updates == 0 {
-        return Err(anyhow::anyhow!("Failed to update any token balances").into());
+    let missing_approvals = report.missing_approvals();
+    if missing_approvals.is_empty() {
+        return Ok(());
     }
+    if !params.bootstrap_approvals {
+        tracing::warn!(
+            tokens = ?missing_approvals,
+            "Source tokens lack a Permit2 allowance, bundles will carry approvals"
+        );
+        return Ok(());
+    }
+
+    let (nonce, base_fee) = simulation::get_nonce_and_base_fee(
+        &trade_executor.provider,
+        trade_executor.signer.address(),
+        None,
+        &trade_executor.base_fee_predictor,
+        trade_executor.rate_limiter.as_deref(),
+    ).await?;
+    let requests = bootstrap.approval_requests(&report, nonce, params.chain_id);
+    let submissions = trade_executor.executor
+        .execute(requests, block_number + 1, base_fee, U256::ZERO)
+        .await?;
+    // The allowances change once the approvals land
+    allowances.mark_stale();
 
+    tracing::info!(
+        tokens = ?missing_approvals,
+        successful_submissions = submissions.iter().filter(|s| s.is_successful()).count(),
+        total_submissions = submissions.len(),
+        "Submitted missing Permit2 approvals"
+    );
     Ok(())
 }
 
-/// Get the balance of a specific token for a given owner address.
-async fn get_token_balance(
-    provider: Arc<RootProvider<Ethereum>>,
-    token_address: Address,
-    owner_address: Address,
-) -> Result<BigUint> {
-    // Construct the balanceOf(address) call data
-    let mut call_data = vec![0x70, 0xa0, 0x82, 0x31]; // balanceOf(address) selector
-    call_data.extend_from_slice(&[0u8; 12]); // Padding
-    call_data.extend_from_slice(owner_address.as_slice());
+/// Log the rebalancing suggestions of the inventory and submit wraps and unwraps if enabled.
+///
+/// Wrap and unwrap transactions are submitted as single-transaction bundles
+/// without a priority fee, so they may take a few blocks to land.
+pub async fn rebalance_inventory(
+    trade_executor: &TradeExecutor,
+    params: &ArbitrageParams,
+    block_number: u64,
+) {
+    let suggestions = trade_executor.inventory.suggestions();
+    for suggestion in &suggestions {
+        tracing::warn!(
+            token = %suggestion.token,
+            balance = %suggestion.balance,
+            floor = %suggestion.floor,
+            deficit = %suggestion.deficit,
+            action = ?suggestion.action,
+            "Inventory below floor"
+        );
+    }
 
-    let tx = alloy::rpc::types::TransactionRequest {
-        to: Some(alloy::primitives::TxKind::Call(token_address)),
-        input: alloy::rpc::types::TransactionInput {
-            input: Some(call_data.into()),
-            data: None,
-        },
-        ..Default::default()
+    if !params.auto_rebalance {
+        return;
+    }
+    let Some(suggestion) = suggestions
+        .iter()
+        .find(|suggestion| !matches!(suggestion.action, RebalanceAction::TopUp))
+    else {
+        return;
     };
 
-    let result = provider
-        .call(tx.into())
-        .await
-        .map_err(|e| anyhow::anyhow!("RPC call failed: {}", e))?;
-
-    // Parse the result as a U256
-    let balance_bytes = result.to_vec();
-    let balance = if balance_bytes.len() >= 32 {
-        let mut bytes = [0u8; 32];
-        bytes.copy_from_slice(&balance_bytes[balance_bytes.len() - 32..]);
-        U256::from_be_bytes(bytes)
-    } else {
-        U256::ZERO
+    let (nonce, base_fee) = match simulation::get_nonce_and_base_fee(
+        &trade_executor.provider,
+        trade_executor.signer.address(),
+        None,
+        &trade_executor.base_fee_predictor,
+        trade_executor.rate_limiter.as_deref(),
+    ).await {
+        Ok(nonce_and_base_fee) => nonce_and_base_fee,
+        Err(e) => {
+            tracing::warn!(
+                error = %e,
+                "Failed to fetch nonce for rebalancing"
+            );
+            return;
+        }
+    };
+    let Some(request) = trade_executor.inventory.rebalance_request(suggestion, nonce, params.chain_id) else {
+        return;
     };
 
-    Ok(u256_to_biguint(balance))
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::str::FromStr;
-
-    #[tokio::test]
-    #[ignore] // Requires network access
-    async fn test_get_token_balance() {
-        let provider = Arc::new(RootProvider::new_http(
-            "https://eth.llamarpc.com".parse().unwrap(),
-        ));
-        
-        // USDC contract address
-        let token_address = Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap();
-        // Vitalik's address
-        let owner_address = Address::from_str("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045").unwrap();
-        
-        let balance = get_token_balance(provider, token_address, owner_address).await;
-        assert!(balance.is_ok());
+    match trade_executor.executor.execute(vec![request], block_number + 1, base_fee, U256::ZERO).await {
+        Ok(submissions) => {
+            tracing::info!(
+                action = ?suggestion.action,
+                successful_submissions = submissions.iter().filter(|s| s.is_successful()).count(),
+                total_submissions = submissions.len(),
+                "Submitted rebalancing transaction"
+            );
+        }
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                "Rebalancing transaction failed"
+            );
+        }
     }
 }
@@ -9,13 +9,22 @@ use alloy::{
     signers::local::PrivateKeySigner,
 };
 use num_bigint::BigUint;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::{HashMap, HashSet, VecDeque}, sync::Arc};
 use tokio::sync::RwLock;
 use tycho_atomic_arbitrage::{
+    budget::SearchDeadline,
     bundle::TxExecutor,
-    graph::TradingGraph,
-    path::PathRepository,
-    simulation::Simulator,
+    config::OptimizationTolerances,
+    errors::OpportunityId,
+    events::EventDispatcher,
+    graph::{SharedTradingGraph, TradingGraph},
+    inventory::InventoryManager,
+    path::{CooldownRegistry, DiscoveryPolicy, PathEvaluationCache, PathExt, PathLimitCache, PathRanking, PathRepository, PoolVersions, ProtocolGasTable, SpotPriceCache},
+    risk::RiskManager,
+    rpc::RateLimiter,
+    simulation::{RealizedSlippageLog, Simulator, TokenVetter},
+    stats::BlockStatsAggregator,
+    utils::{fees::BaseFeePredictor, l2_fees::L2FeeModel},
 };
 use tycho_common::Bytes;
 use tycho_simulation::protocol::{models::ProtocolComponent, state::ProtocolSim};
@@ -23,22 +32,36 @@ use tycho_simulation::protocol::{models::ProtocolComponent, state::ProtocolSim};
 /// Manages market data including protocol states, components, and trading graph.
 #[derive(Debug)]
 pub struct MarketDataManager {
-    pub protocol_sim: Arc<RwLock<HashMap<Bytes, Box<dyn ProtocolSim>>>>,
-    pub protocol_comp: Arc<RwLock<HashMap<Bytes, ProtocolComponent>>>,
-    pub graph: Arc<RwLock<TradingGraph>>,
+    pub protocol_sim: Arc<RwLock<HashMap<Bytes, Arc<dyn ProtocolSim>>>>,
+    pub protocol_comp: Arc<RwLock<HashMap<Bytes, Arc<ProtocolComponent>>>>,
+    pub graph: Arc<SharedTradingGraph>,
+    /// Versions of the pool states, increased whenever a state changes
+    pub pool_versions: Arc<PoolVersions>,
     pub block_number: Arc<RwLock<u64>>,
 }
 
 impl MarketDataManager {
     pub fn new() -> Self {
+        Self::with_graph(TradingGraph::new())
+    }
+
+    /// Create a manager around a preconfigured, typically empty, trading graph.
+    pub fn with_graph(graph: TradingGraph) -> Self {
         Self {
             protocol_sim: Arc::new(RwLock::new(HashMap::new())),
             protocol_comp: Arc::new(RwLock::new(HashMap::new())),
-            graph: Arc::new(RwLock::new(TradingGraph::new())),
+            graph: Arc::new(SharedTradingGraph::new(graph)),
+            pool_versions: Arc::new(PoolVersions::new()),
             block_number: Arc::new(RwLock::new(0u64)),
         }
     }
 
+    /// Record pool state changes in a registry shared with other components.
+    pub fn with_pool_versions(mut self, pool_versions: Arc<PoolVersions>) -> Self {
+        self.pool_versions = pool_versions;
+        self
+    }
+
     pub async fn update_block_number(&self, block_number: u64) {
         let mut guard = self.block_number.write().await;
         *guard = block_number;
@@ -60,19 +83,88 @@ impl Default for MarketDataManager {
 pub struct PathFinder {
     pub paths: Arc<RwLock<PathRepository>>,
     pub source_balances: Arc<RwLock<HashMap<Bytes, BigUint>>>,
-    pub optimization_tolerances: HashMap<Bytes, f64>,
+    pub balance_caps: HashMap<Bytes, f64>,
+    pub optimization_tolerances: OptimizationTolerances,
     pub source_tokens: Vec<Bytes>,
+    /// Historical profitability of paths, if ranking is enabled
+    pub ranking: Option<Arc<PathRanking>>,
+    /// Number of ranked paths evaluated first each block
+    pub ranked_paths: usize,
+    /// Pools and paths cooling down after failed executions, if enabled
+    pub cooldowns: Option<Arc<CooldownRegistry>>,
+    /// Outcomes of optimized paths, reused while their pools are unchanged
+    pub evaluation_cache: Arc<PathEvaluationCache>,
+    /// Spot prices of pool directions, reused while their pools are unchanged
+    pub spot_price_cache: Arc<SpotPriceCache>,
+    /// Largest inputs within the pool limits of paths, reused while their pools are unchanged
+    pub limit_cache: Arc<PathLimitCache>,
+    /// Estimated gas of swaps by protocol, ranking paths before they are simulated
+    pub protocol_gas: ProtocolGasTable,
 }
 
 impl PathFinder {
-    pub fn new(source_tokens: Vec<Bytes>, optimization_tolerances: HashMap<Bytes, f64>) -> Self {
+    pub fn new(
+        source_tokens: Vec<Bytes>,
+        optimization_tolerances: OptimizationTolerances,
+        balance_caps: HashMap<Bytes, f64>,
+    ) -> Self {
         Self {
             paths: Arc::new(RwLock::new(PathRepository::new(source_tokens.clone(), 3))),
             source_balances: Arc::new(RwLock::new(HashMap::new())),
+            balance_caps,
             optimization_tolerances,
             source_tokens,
+            ranking: None,
+            ranked_paths: 0,
+            cooldowns: None,
+            evaluation_cache: Arc::new(PathEvaluationCache::new()),
+            spot_price_cache: Arc::new(SpotPriceCache::new()),
+            limit_cache: Arc::new(PathLimitCache::new()),
+            protocol_gas: ProtocolGasTable::default(),
         }
     }
+
+    /// Discover paths with the given constraints.
+    pub fn with_discovery_policy(mut self, discovery_policy: DiscoveryPolicy) -> Self {
+        self.paths = Arc::new(RwLock::new(
+            PathRepository::new(self.source_tokens.clone(), 3).with_discovery_policy(discovery_policy),
+        ));
+        self
+    }
+
+    /// Limit the number of swaps of discovered paths, globally and per source token.
+    pub fn with_path_lengths(mut self, maximum_path_length: usize, source_path_lengths: HashMap<Bytes, usize>) -> Self {
+        let discovery_policy = self
+            .paths
+            .try_read()
+            .map(|paths| paths.discovery_policy().clone())
+            .unwrap_or_default();
+        self.paths = Arc::new(RwLock::new(
+            PathRepository::new(self.source_tokens.clone(), maximum_path_length)
+                .with_discovery_policy(discovery_policy)
+                .with_source_path_lengths(source_path_lengths),
+        ));
+        self
+    }
+
+    /// Evaluate the `ranked_paths` historically most profitable paths first.
+    pub fn with_ranking(mut self, ranking: Arc<PathRanking>, ranked_paths: usize) -> Self {
+        self.ranking = Some(ranking);
+        self.ranked_paths = ranked_paths;
+        self
+    }
+
+    /// Skip pools and paths whose executions keep failing.
+    pub fn with_cooldowns(mut self, cooldowns: Arc<CooldownRegistry>) -> Self {
+        self.cooldowns = Some(cooldowns);
+        self
+    }
+
+    /// Estimate the gas of swaps with the given table.
+    pub fn with_protocol_gas(mut self, protocol_gas: ProtocolGasTable) -> Self {
+        self.protocol_gas = protocol_gas;
+        self
+    }
 }
 
 /// Manages trade execution including simulation and bundle submission.
@@ -81,6 +173,20 @@ pub struct TradeExecutor {
     pub executor: Arc<TxExecutor>,
     pub provider: Arc<RootProvider<Ethereum>>,
     pub signer: PrivateKeySigner,
+    /// Submitted portfolios whose inclusion has not been checked yet
+    pub pending_executions: Arc<RwLock<Vec<PendingExecution>>>,
+    /// Limits checked before every submission
+    pub risk: Option<Arc<RiskManager>>,
+    /// Executor balances across blocks
+    pub inventory: Arc<InventoryManager>,
+    /// Base fees of recent blocks, shared with the simulator and the executor
+    pub base_fee_predictor: Arc<BaseFeePredictor>,
+    /// L1 data fees of transactions, set on OP-stack chains
+    pub l2_fees: Option<Arc<L2FeeModel>>,
+    /// Realized slippage of included paths, logged from their receipts if enabled
+    pub realized_slippage: Option<Arc<RealizedSlippageLog>>,
+    /// Rate limit of RPC requests, shared by simulations, balance checks and gas queries
+    pub rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl TradeExecutor {
@@ -94,9 +200,79 @@ impl TradeExecutor {
             simulator: Arc::new(simulator),
             executor: Arc::new(executor),
             provider,
+            pending_executions: Arc::new(RwLock::new(Vec::new())),
+            risk: None,
+            inventory: Arc::new(InventoryManager::new(signer.address())),
+            base_fee_predictor: Arc::new(BaseFeePredictor::new()),
+            l2_fees: None,
+            realized_slippage: None,
+            rate_limiter: None,
             signer,
         }
     }
+
+    /// Track balances with floors and rebalancing suggestions.
+    pub fn with_inventory(mut self, inventory: InventoryManager) -> Self {
+        self.inventory = Arc::new(inventory);
+        self
+    }
+
+    /// Record block headers in a predictor shared with the simulator and the executor.
+    pub fn with_base_fee_predictor(mut self, predictor: Arc<BaseFeePredictor>) -> Self {
+        self.base_fee_predictor = predictor;
+        self
+    }
+
+    /// Add the L1 data fee to the gas cost of every opportunity.
+    pub fn with_l2_fee_model(mut self, l2_fees: Arc<L2FeeModel>) -> Self {
+        self.l2_fees = Some(l2_fees);
+        self
+    }
+
+    /// Log the realized slippage of included paths from their receipts.
+    pub fn with_realized_slippage(mut self, log: Arc<RealizedSlippageLog>) -> Self {
+        self.realized_slippage = Some(log);
+        self
+    }
+
+    /// Make nonce and gas queries wait for a limiter shared with other RPC callers.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Check risk limits before submitting bundles.
+    pub fn with_risk_manager(mut self, risk: Arc<RiskManager>) -> Self {
+        self.risk = Some(risk);
+        self
+    }
+}
+
+/// A submitted portfolio waiting for its target block.
+#[derive(Debug, Clone)]
+pub struct PendingExecution {
+    /// Nonce of the first transaction of the portfolio
+    pub base_nonce: u64,
+    /// Block the bundles were submitted for
+    pub target_block: u64,
+    /// Paths executed by the portfolio
+    pub paths: Vec<PathExt>,
+    /// Ids of the executed opportunities, in the order of their paths
+    pub opportunity_ids: Vec<OpportunityId>,
+    /// Minimum output the router enforced for each path
+    pub checked_amounts: Vec<BigUint>,
+    /// Native token balance of the executor when the portfolio was submitted
+    pub native_balance_before: Option<BigUint>,
+}
+
+impl PendingExecution {
+    /// Whether the portfolio landed, given the executor nonce after the target block.
+    ///
+    /// Any included transaction consumes the base nonce, so a nonce that did not
+    /// advance means every bundle reverted or lost the race.
+    pub fn is_included(&self, nonce: u64) -> bool {
+        nonce > self.base_nonce
+    }
 }
 
 /// Configuration parameters for arbitrage operations.
@@ -104,6 +280,18 @@ impl TradeExecutor {
 pub struct ArbitrageParams {
     pub native_token: Bytes,
     pub min_profit_bps: u64,
+    pub max_portfolio_size: Option<usize>,
+    /// Whether opportunities of a portfolio may trade through the same pools
+    pub allow_shared_pools: bool,
+    pub max_output_shortfall_bps: f64,
+    /// Multiple of the gas cost the net profit of a simulation has to exceed
+    pub min_profit_gas_multiple: f64,
+    /// Chain ID wrap and unwrap transactions are signed for
+    pub chain_id: u64,
+    /// Whether wrap and unwrap suggestions are submitted
+    pub auto_rebalance: bool,
+    /// Whether missing Permit2 approvals found on startup are submitted
+    pub bootstrap_approvals: bool,
 }
 
 impl ArbitrageParams {
@@ -111,8 +299,153 @@ impl ArbitrageParams {
         Self {
             native_token,
             min_profit_bps,
+            max_portfolio_size: None,
+            allow_shared_pools: false,
+            max_output_shortfall_bps: 50.0,
+            min_profit_gas_multiple: 0.0,
+            chain_id: 1,
+            auto_rebalance: false,
+            bootstrap_approvals: false,
+        }
+    }
+
+    pub fn with_max_portfolio_size(mut self, max_portfolio_size: Option<usize>) -> Self {
+        self.max_portfolio_size = max_portfolio_size;
+        self
+    }
+
+    pub fn with_shared_pools(mut self, allow_shared_pools: bool) -> Self {
+        self.allow_shared_pools = allow_shared_pools;
+        self
+    }
+
+    pub fn with_max_output_shortfall_bps(mut self, max_output_shortfall_bps: f64) -> Self {
+        self.max_output_shortfall_bps = max_output_shortfall_bps;
+        self
+    }
+
+    pub fn with_min_profit_gas_multiple(mut self, min_profit_gas_multiple: f64) -> Self {
+        self.min_profit_gas_multiple = min_profit_gas_multiple;
+        self
+    }
+
+    /// Submit wrap and unwrap transactions signed for the given chain.
+    pub fn with_auto_rebalance(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self.auto_rebalance = true;
+        self
+    }
+
+    /// Submit the missing Permit2 approvals found on startup, signed for the given chain.
+    pub fn with_bootstrap_approvals(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self.bootstrap_approvals = true;
+        self
+    }
+}
+
+/// Policy for pruning pools that stopped receiving state updates.
+///
+/// Pools without an update for `max_age_blocks` are often paused or drained.
+/// They are pruned from the graph every `interval_blocks` and revived as soon
+/// as they receive a state update again.
+#[derive(Debug, Clone)]
+pub struct StalePoolPruner {
+    pub max_age_blocks: u64,
+    pub interval_blocks: u64,
+    last_pruned_at: u64,
+    pruned_pools: HashSet<Bytes>,
+}
+
+impl StalePoolPruner {
+    pub fn new(max_age_blocks: u64, interval_blocks: u64) -> Self {
+        Self {
+            max_age_blocks,
+            interval_blocks: interval_blocks.max(1),
+            last_pruned_at: 0,
+            pruned_pools: HashSet::new(),
+        }
+    }
+
+    /// The block before which pools are stale, if a pruning round is due.
+    pub fn cutoff(&mut self, block_number: u64) -> Option<u64> {
+        if block_number < self.last_pruned_at + self.interval_blocks {
+            return None;
+        }
+        self.last_pruned_at = block_number;
+        block_number.checked_sub(self.max_age_blocks)
+    }
+
+    /// Remember pruned pools so they can be revived on their next update.
+    pub fn record_pruned(&mut self, pools: Vec<Bytes>) {
+        self.pruned_pools.extend(pools);
+    }
+
+    /// Stop tracking pools that no longer exist.
+    pub fn forget(&mut self, pools: &[Bytes]) {
+        for pool in pools {
+            self.pruned_pools.remove(pool);
         }
     }
+
+    /// Pruned pools among the updated pools, which are no longer tracked as pruned.
+    pub fn take_revived(&mut self, updated_pools: &[Bytes]) -> Vec<Bytes> {
+        updated_pools
+            .iter()
+            .filter(|pool| self.pruned_pools.remove(*pool))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Queue of new tokens waiting for their probe simulation.
+///
+/// New tokens are probed in arrival order, at most `probes_per_block` per
+/// block, so the snapshot of a fresh stream is vetted over several blocks.
+#[derive(Debug)]
+pub struct TokenVetting {
+    pub vetter: Arc<TokenVetter>,
+    pub probes_per_block: usize,
+    queue: VecDeque<Bytes>,
+    queued: HashSet<Bytes>,
+}
+
+impl TokenVetting {
+    pub fn new(vetter: Arc<TokenVetter>, probes_per_block: usize) -> Self {
+        Self {
+            vetter,
+            probes_per_block: probes_per_block.max(1),
+            queue: VecDeque::new(),
+            queued: HashSet::new(),
+        }
+    }
+
+    /// Queue tokens that are neither vetted nor queued yet.
+    pub fn enqueue<I>(&mut self, tokens: I)
+    where
+        I: IntoIterator<Item = Bytes>,
+    {
+        for token in tokens {
+            if !self.vetter.is_vetted(&token) && self.queued.insert(token.clone()) {
+                self.queue.push_back(token);
+            }
+        }
+    }
+
+    /// Take the tokens to probe in this block.
+    pub fn next_batch(&mut self) -> Vec<Bytes> {
+        let count = self.probes_per_block.min(self.queue.len());
+        let batch: Vec<Bytes> = self.queue.drain(..count).collect();
+        for token in &batch {
+            self.queued.remove(token);
+        }
+        batch
+    }
+
+    /// The number of tokens waiting for their probe.
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
 }
 
 /// Parameters for a single arbitrage search operation.
@@ -120,13 +453,15 @@ impl ArbitrageParams {
 pub struct SearchParams {
     pub updated_pools: Vec<Bytes>,
     pub block_number: u64,
+    pub deadline: SearchDeadline,
 }
 
 impl SearchParams {
-    pub fn new(updated_pools: Vec<Bytes>, block_number: u64) -> Self {
+    pub fn new(updated_pools: Vec<Bytes>, block_number: u64, deadline: SearchDeadline) -> Self {
         Self {
             updated_pools,
             block_number,
+            deadline,
         }
     }
 }
@@ -151,13 +486,24 @@ impl<'a> MarketContext<'a> {
 pub struct ExecutionContext<'a> {
     pub trade_executor: &'a TradeExecutor,
     pub params: &'a ArbitrageParams,
+    /// Handlers notified of opportunities, simulations and submissions
+    pub events: &'a EventDispatcher,
+    /// Rolling statistics of the recent searches
+    pub block_stats: &'a BlockStatsAggregator,
 }
 
 impl<'a> ExecutionContext<'a> {
-    pub fn new(trade_executor: &'a TradeExecutor, params: &'a ArbitrageParams) -> Self {
+    pub fn new(
+        trade_executor: &'a TradeExecutor,
+        params: &'a ArbitrageParams,
+        events: &'a EventDispatcher,
+        block_stats: &'a BlockStatsAggregator,
+    ) -> Self {
         Self {
             trade_executor,
             params,
+            events,
+            block_stats,
         }
     }
 }
@@ -0,0 +1,380 @@
+//! Profitability heatmap reporting for arbitrage operations.
+//!
+//! This module aggregates simulated profitability by token pair and protocol
+//! combination, producing a heatmap-style matrix that is periodically written
+//! to `heatmap.json` and `heatmap.csv` in the run directory. The report shows
+//! which pairs, protocols and source tokens actually produce profit, which
+//! helps decide where configuration attention (start tokens, exchanges, TVL
+//! thresholds) is best spent.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use csv::Writer;
+use num_bigint::{BigInt, BigUint};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+use tycho_atomic_arbitrage::path::PathExt;
+use tycho_common::Bytes;
+
+/// Separator used when joining protocol systems into a combination label.
+const PROTOCOL_SEPARATOR: &str = ">";
+
+/// A single simulated opportunity reduced to the dimensions used by the heatmap.
+#[derive(Debug, Clone)]
+pub struct HeatmapObservation {
+    /// Start token of the simulated path
+    pub start_token: Bytes,
+    /// Normalized token pair label of every hop in the path
+    pub token_pairs: Vec<String>,
+    /// Protocol systems of the path joined in execution order
+    pub protocol_combination: String,
+    /// Gross profit of the path converted to the native token
+    pub gross_profit_in_native: BigUint,
+    /// Gas cost of the simulated bundle in the native token
+    pub gas_cost: BigUint,
+}
+
+impl HeatmapObservation {
+    /// Build an observation from a simulated path and its simulation outcome.
+    pub fn from_path_ext(
+        path_ext: &PathExt,
+        gross_profit_in_native: BigUint,
+        gas_cost: BigUint,
+    ) -> Result<Self> {
+        let start_token = path_ext.start_token()
+            .map_err(|e| anyhow::anyhow!("Failed to get start token: {}", e))?;
+
+        let token_pairs = path_ext.iter()
+            .map(|swap| {
+                let token_in = swap.token_in();
+                let token_out = swap.token_out();
+                pair_label(
+                    (&token_in.address, &token_in.symbol),
+                    (&token_out.address, &token_out.symbol),
+                )
+            })
+            .collect();
+
+        let protocol_combination = path_ext.iter()
+            .map(|swap| swap.pool_comp.protocol_system.as_str())
+            .collect::<Vec<_>>()
+            .join(PROTOCOL_SEPARATOR);
+
+        Ok(Self {
+            start_token,
+            token_pairs,
+            protocol_combination,
+            gross_profit_in_native,
+            gas_cost,
+        })
+    }
+
+    /// Whether the opportunity covered its gas cost.
+    pub fn is_profitable(&self) -> bool {
+        self.gross_profit_in_native > self.gas_cost
+    }
+
+    /// Net profit of the opportunity, negative if gas exceeded gross profit.
+    pub fn net_profit(&self) -> BigInt {
+        BigInt::from(self.gross_profit_in_native.clone()) - BigInt::from(self.gas_cost.clone())
+    }
+}
+
+/// Create an order-independent label for a token pair.
+///
+/// Tokens are ordered by address so that `A -> B` and `B -> A` hops land in
+/// the same heatmap row.
+fn pair_label(a: (&Bytes, &str), b: (&Bytes, &str)) -> String {
+    let (first, second) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+    format!("{}/{}", display_symbol(first), display_symbol(second))
+}
+
+/// Use the token symbol when known, falling back to the address.
+fn display_symbol((address, symbol): (&Bytes, &str)) -> String {
+    if symbol.is_empty() {
+        address.to_string()
+    } else {
+        symbol.to_string()
+    }
+}
+
+/// Aggregated statistics for one heatmap cell.
+#[derive(Debug, Clone, Default)]
+pub struct HeatmapCell {
+    pub simulations: u64,
+    pub profitable_simulations: u64,
+    pub total_gross_profit_in_native: BigUint,
+    pub total_gas_cost: BigUint,
+    pub total_net_profit: BigInt,
+    pub best_net_profit: Option<BigInt>,
+}
+
+impl HeatmapCell {
+    fn record(&mut self, observation: &HeatmapObservation) {
+        let net_profit = observation.net_profit();
+
+        self.simulations += 1;
+        if observation.is_profitable() {
+            self.profitable_simulations += 1;
+        }
+        self.total_gross_profit_in_native += &observation.gross_profit_in_native;
+        self.total_gas_cost += &observation.gas_cost;
+        self.total_net_profit += &net_profit;
+        if self.best_net_profit.as_ref().is_none_or(|best| net_profit > *best) {
+            self.best_net_profit = Some(net_profit);
+        }
+    }
+
+    /// Share of simulations that covered their gas cost, in percent.
+    pub fn hit_rate(&self) -> f64 {
+        if self.simulations == 0 {
+            0.0
+        } else {
+            self.profitable_simulations as f64 / self.simulations as f64 * 100.0
+        }
+    }
+}
+
+/// Heatmap of simulated profitability keyed by token pair and protocol combination.
+///
+/// Every hop of a simulated path contributes the full path outcome to the cell of
+/// its token pair in the path's protocol combination column. Source token totals
+/// are tracked separately so the report can also rank start tokens.
+#[derive(Debug, Clone, Default)]
+pub struct ProfitHeatmap {
+    cells: BTreeMap<(String, String), HeatmapCell>,
+    source_tokens: BTreeMap<String, HeatmapCell>,
+    first_block: Option<u64>,
+    last_block: Option<u64>,
+}
+
+impl ProfitHeatmap {
+    /// Create an empty heatmap.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a simulated opportunity observed in the given block.
+    pub fn record(&mut self, observation: &HeatmapObservation, block_number: u64) {
+        let mut seen_pairs = BTreeSet::new();
+        for pair in &observation.token_pairs {
+            // A pair traversed twice in one path should still count a single simulation
+            if !seen_pairs.insert(pair) {
+                continue;
+            }
+            self.cells
+                .entry((pair.clone(), observation.protocol_combination.clone()))
+                .or_default()
+                .record(observation);
+        }
+
+        self.source_tokens
+            .entry(observation.start_token.to_string())
+            .or_default()
+            .record(observation);
+
+        self.first_block = Some(self.first_block.map_or(block_number, |b| b.min(block_number)));
+        self.last_block = Some(self.last_block.map_or(block_number, |b| b.max(block_number)));
+    }
+
+    /// Get the aggregated cell for a token pair and protocol combination.
+    pub fn cell(&self, token_pair: &str, protocol_combination: &str) -> Option<&HeatmapCell> {
+        self.cells.get(&(token_pair.to_string(), protocol_combination.to_string()))
+    }
+
+    /// Number of populated cells.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Whether no observations have been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Build a serializable report of the current heatmap state.
+    pub fn report(&self) -> HeatmapReport {
+        let token_pairs: Vec<String> = self.cells.keys()
+            .map(|(pair, _)| pair.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let protocol_combinations: Vec<String> = self.cells.keys()
+            .map(|(_, protocols)| protocols.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let net_profit_matrix = token_pairs.iter()
+            .map(|pair| {
+                protocol_combinations.iter()
+                    .map(|protocols| {
+                        self.cells
+                            .get(&(pair.clone(), protocols.clone()))
+                            .map(|cell| cell.total_net_profit.to_string())
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let cells = self.cells.iter()
+            .map(|((pair, protocols), cell)| HeatmapCellReport::new(pair, protocols, cell))
+            .collect();
+
+        let source_tokens = self.source_tokens.iter()
+            .map(|(token, cell)| HeatmapCellReport::new(token, "*", cell))
+            .collect();
+
+        HeatmapReport {
+            generated_at: Utc::now(),
+            first_block: self.first_block,
+            last_block: self.last_block,
+            token_pairs,
+            protocol_combinations,
+            net_profit_matrix,
+            cells,
+            source_tokens,
+        }
+    }
+}
+
+/// Serializable view of a single heatmap cell.
+///
+/// Amounts are rendered as decimal strings to keep arbitrary-precision values intact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapCellReport {
+    pub token_pair: String,
+    pub protocol_combination: String,
+    pub simulations: u64,
+    pub profitable_simulations: u64,
+    pub hit_rate_percentage: f64,
+    pub total_gross_profit_in_native: String,
+    pub total_gas_cost: String,
+    pub total_net_profit: String,
+    pub best_net_profit: String,
+}
+
+impl HeatmapCellReport {
+    fn new(token_pair: &str, protocol_combination: &str, cell: &HeatmapCell) -> Self {
+        Self {
+            token_pair: token_pair.to_string(),
+            protocol_combination: protocol_combination.to_string(),
+            simulations: cell.simulations,
+            profitable_simulations: cell.profitable_simulations,
+            hit_rate_percentage: cell.hit_rate(),
+            total_gross_profit_in_native: cell.total_gross_profit_in_native.to_string(),
+            total_gas_cost: cell.total_gas_cost.to_string(),
+            total_net_profit: cell.total_net_profit.to_string(),
+            best_net_profit: cell.best_net_profit
+                .as_ref()
+                .map(|profit| profit.to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Serializable heatmap report.
+///
+/// `net_profit_matrix[i][j]` holds the total net profit for `token_pairs[i]`
+/// routed through `protocol_combinations[j]`, or `null` when no simulation
+/// covered that combination.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapReport {
+    pub generated_at: DateTime<Utc>,
+    pub first_block: Option<u64>,
+    pub last_block: Option<u64>,
+    pub token_pairs: Vec<String>,
+    pub protocol_combinations: Vec<String>,
+    pub net_profit_matrix: Vec<Vec<Option<String>>>,
+    pub cells: Vec<HeatmapCellReport>,
+    pub source_tokens: Vec<HeatmapCellReport>,
+}
+
+impl HeatmapReport {
+    /// Write the report as `heatmap.json` and `heatmap.csv` into the given directory.
+    ///
+    /// Existing reports are overwritten so the files always reflect the latest state.
+    pub fn write_to_dir<P: AsRef<Path>>(&self, output_dir: P) -> Result<()> {
+        let output_dir = output_dir.as_ref();
+
+        let report_json = serde_json::to_string_pretty(self)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize heatmap report: {}", e))?;
+        std::fs::write(output_dir.join("heatmap.json"), report_json)
+            .map_err(|e| anyhow::anyhow!("Failed to write heatmap report: {}", e))?;
+
+        let mut writer = Writer::from_path(output_dir.join("heatmap.csv"))?;
+        for cell in &self.cells {
+            writer.serialize(cell)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    fn observation(pairs: &[&str], protocols: &str, gross: u32, gas: u32) -> HeatmapObservation {
+        HeatmapObservation {
+            start_token: Bytes::from_str("0x0001").unwrap(),
+            token_pairs: pairs.iter().map(|p| p.to_string()).collect(),
+            protocol_combination: protocols.to_string(),
+            gross_profit_in_native: BigUint::from(gross),
+            gas_cost: BigUint::from(gas),
+        }
+    }
+
+    #[test]
+    fn test_pair_label_is_order_independent() {
+        let weth = Bytes::from_str("0x0001").unwrap();
+        let usdc = Bytes::from_str("0x0002").unwrap();
+
+        assert_eq!(
+            pair_label((&weth, "WETH"), (&usdc, "USDC")),
+            pair_label((&usdc, "USDC"), (&weth, "WETH")),
+        );
+        assert_eq!(pair_label((&weth, "WETH"), (&usdc, "USDC")), "WETH/USDC");
+    }
+
+    #[test]
+    fn test_heatmap_aggregates_cells() {
+        let mut heatmap = ProfitHeatmap::new();
+        heatmap.record(&observation(&["WETH/USDC", "USDC/DAI"], "uniswap_v2>uniswap_v3", 150, 100), 10);
+        heatmap.record(&observation(&["WETH/USDC"], "uniswap_v2>uniswap_v3", 50, 100), 12);
+
+        let cell = heatmap.cell("WETH/USDC", "uniswap_v2>uniswap_v3").unwrap();
+        assert_eq!(cell.simulations, 2);
+        assert_eq!(cell.profitable_simulations, 1);
+        assert_eq!(cell.total_net_profit, BigInt::from(0));
+        assert_eq!(cell.best_net_profit, Some(BigInt::from(50)));
+        assert_eq!(cell.hit_rate(), 50.0);
+        assert_eq!(heatmap.len(), 2);
+
+        let report = heatmap.report();
+        assert_eq!(report.first_block, Some(10));
+        assert_eq!(report.last_block, Some(12));
+        assert_eq!(report.token_pairs.len(), 2);
+        assert_eq!(report.protocol_combinations, vec!["uniswap_v2>uniswap_v3".to_string()]);
+        assert_eq!(report.source_tokens.len(), 1);
+        assert_eq!(report.source_tokens[0].simulations, 2);
+    }
+
+    #[test]
+    fn test_heatmap_report_written_to_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut heatmap = ProfitHeatmap::new();
+        heatmap.record(&observation(&["WETH/USDC"], "uniswap_v2", 150, 100), 1);
+
+        heatmap.report().write_to_dir(temp_dir.path()).unwrap();
+
+        assert!(temp_dir.path().join("heatmap.json").exists());
+        assert!(temp_dir.path().join("heatmap.csv").exists());
+    }
+}
@@ -15,9 +15,11 @@ use std::{
     sync::{Arc, Mutex},
     time::{SystemTime, UNIX_EPOCH},
 };
-use tycho_atomic_arbitrage::path::PathExt;
+use tycho_atomic_arbitrage::{bundle::Bundle, errors::OpportunityId, path::PathExt, stats::{BlockSummary, WindowStats}};
 use tycho_common::Bytes;
 
+use super::heatmap::{HeatmapObservation, ProfitHeatmap};
+
 /// Configuration data for a single arbitrage run.
 ///
 /// This struct captures all the static parameters and settings used
@@ -36,10 +38,18 @@ pub struct RunConfiguration {
     pub start_tokens: Vec<String>,
     /// Resolved start token addresses
     pub start_token_addresses: Vec<String>,
-    /// Optimization tolerance percentages for each start token
+    /// Optimization tolerance percentages of the resolved start token addresses
     pub optimization_tolerances: Vec<f64>,
+    /// Share of each start token's balance that may be committed per search, in percent
+    pub balance_cap_percentages: Vec<f64>,
+    /// Maximum number of swaps of a searched path
+    pub max_path_length: usize,
+    /// Maximum number of swaps of paths from each start token
+    pub max_path_lengths: Vec<usize>,
     /// Whether executor private key was provided (masked for security)
     pub has_executor_private_key: bool,
+    /// Whether the executor key was loaded from a keystore
+    pub has_executor_keystore: bool,
     /// Minimum TVL for pools to consider
     pub tvl_threshold: f64,
     /// Minimum profit in BPS for optimization
@@ -54,6 +64,114 @@ pub struct RunConfiguration {
     pub native_token_address: String,
     /// Tycho URL for this chain
     pub tycho_url: String,
+    /// Number of blocks between heatmap report refreshes (0 disables the report)
+    pub heatmap_interval_blocks: u64,
+    /// Wall-clock budget per block search in milliseconds
+    pub search_budget_ms: u64,
+    /// Maximum number of candidate paths built per block search
+    pub max_paths: Option<usize>,
+    /// Maximum number of simulations run per block search
+    pub max_simulations: Option<usize>,
+    /// How portfolios of several opportunities are submitted (separate or combined)
+    pub submission_mode: String,
+    /// When bundles are submitted within the slot (immediate, delayed or resubmit)
+    pub submission_timing: String,
+    /// Offset after the parent block timestamp of delayed submissions and resubmissions in milliseconds
+    pub submission_offset_ms: u64,
+    /// Whether bundles are only recorded instead of submitted
+    pub dry_run: bool,
+    /// Recorded feed messages replayed instead of the live stream, if backtesting
+    pub backtest_file: Option<String>,
+    /// Pace of backtest replays
+    pub replay_speed: String,
+    /// Tracked exchanges
+    pub exchanges: Vec<String>,
+    /// Missing blocks tolerated between two stream updates
+    pub max_block_gap: u64,
+    /// Contiguous blocks after a resync before searching again
+    pub resync_settle_blocks: u64,
+    /// Directory raw feed messages are recorded to
+    pub record_dir: Option<String>,
+    /// Blocks per recording file
+    pub record_blocks_per_file: u64,
+    /// File lifecycle events are written to as JSON lines
+    pub event_log: Option<String>,
+    /// Kinds of the configured alert rules
+    pub alert_rules: Vec<String>,
+    /// Whether triggered alerts are posted to a webhook
+    pub alert_webhook: bool,
+    /// Maximum number of opportunities executed per block
+    pub max_portfolio_size: Option<usize>,
+    /// Whether opportunities of a portfolio may trade through the same pools
+    pub allow_shared_pools: bool,
+    /// Number of blocks bundles that reverted on chain are not submitted again
+    pub revert_memory_blocks: u64,
+    /// File keeping reverted bundles across restarts
+    pub revert_memory_file: Option<String>,
+    /// Whether approvals are skipped when the Permit2 allowance already suffices
+    pub skip_approvals: bool,
+    /// Whether missing Permit2 approvals are submitted on startup
+    pub bootstrap_approvals: bool,
+    /// How the router obtains input tokens (permit2, transfer-from or none)
+    pub transfer_type: String,
+    /// Validity window of reused Permit2 signatures in seconds, if enabled
+    pub permit_validity_secs: Option<u64>,
+    /// Address receiving the output of executed paths, if not the executor
+    pub receiver_address: Option<String>,
+    /// Maximum shortfall in BPS of the realized versus the expected output
+    pub max_output_shortfall_bps: f64,
+    /// Maximum number of tokens of a pool added to the trading graph
+    pub max_pool_tokens: usize,
+    /// Mean absolute deviation in BPS above which a protocol is distrusted, if tracked
+    pub max_protocol_deviation_bps: Option<f64>,
+    /// Requests per second allowed across all RPC callers, if limited
+    pub rpc_rate_limit: Option<f64>,
+    /// Maximum number of RPC requests in flight, if capped
+    pub rpc_max_concurrent: Option<usize>,
+    /// Simulated swaps required before a protocol can be distrusted
+    pub min_accuracy_samples: u64,
+    /// Included swaps per protocol before its realized slippage is tolerated, if adaptive
+    pub realized_slippage_samples: Option<usize>,
+    /// Blocks without a state update after which a pool is pruned, if enabled
+    pub stale_pool_blocks: Option<u64>,
+    /// Blocks between stale pool pruning rounds
+    pub prune_interval_blocks: u64,
+    /// Top ranked paths evaluated first each block, if ranking is enabled
+    pub ranked_paths: Option<usize>,
+    /// Blocks after which a path's profitability score has halved
+    pub ranking_half_life_blocks: f64,
+    /// Failures before a pool or path is cooled down, if enabled
+    pub cooldown_failures: Option<u32>,
+    /// Initial cooldown in blocks
+    pub cooldown_base_blocks: u64,
+    /// Maximum cooldown in blocks
+    pub cooldown_max_blocks: u64,
+    /// Quiet blocks after which failures are forgotten
+    pub cooldown_decay_blocks: u64,
+    /// Maximum input of a trade in native tokens
+    pub max_trade_notional: Option<f64>,
+    /// Maximum gas price for submissions in gwei
+    pub max_gas_price_gwei: Option<f64>,
+    /// Maximum realized loss per UTC day in native tokens
+    pub max_daily_loss: Option<f64>,
+    /// Consecutive failed bundles after which submissions halt
+    pub max_consecutive_failures: Option<u32>,
+    /// File whose existence stops submissions
+    pub kill_switch_file: Option<String>,
+    /// Percentage of the peak balance below which rebalancing is suggested
+    pub inventory_floor_percentage: Option<f64>,
+    /// Native ETH kept for gas
+    pub gas_floor: Option<f64>,
+    /// Whether wrap and unwrap suggestions are executed
+    pub auto_rebalance: bool,
+    /// Margin on top of the simulated gas usage in BPS, fixed gas limits if unset
+    pub gas_margin_bps: Option<u64>,
+    /// Whether the router reverts paths below the post-gas profit threshold
+    pub profit_guard: bool,
+    /// Whether reverted and short simulations are traced with the `callTracer`
+    pub trace_simulations: bool,
+    /// Protocol systems whose paths may be submitted, every protocol if `None`
+    pub execution_protocols: Option<Vec<String>>,
 }
 
 impl RunConfiguration {
@@ -69,31 +187,31 @@ impl RunConfiguration {
     }
 }
 
-/// Block-level statistics for arbitrage operations.
-#[derive(Debug, Clone, Default)]
-pub struct BlockSummary {
-    pub block_number: u64,
-    pub initial_paths: usize,
-    pub candidate_paths: usize,
-    pub optimised_profitable_paths: usize,
-    pub successful_simulations: usize,
-    pub profitable_simulations: usize,
-}
+/// Default number of blocks between heatmap report refreshes.
+pub const DEFAULT_HEATMAP_INTERVAL_BLOCKS: u64 = 100;
 
 /// Main logger for arbitrage operations.
 ///
-/// Manages four CSV files:
+/// Manages five CSV files:
 /// 1. paths.csv - All generated paths with IDs, pools, and tokens
 /// 2. filtered_paths.csv - Blockwise data of filtered/optimized paths
 /// 3. simulation_results.csv - Simulation results with gas usage
 /// 4. block_summary.csv - Block-level statistics and performance metrics
+/// 5. dry_run_bundles.csv - Bundles built but not submitted in dry-run mode
+///
+/// In addition, simulation results are aggregated into a profitability heatmap
+/// that is periodically written to heatmap.json and heatmap.csv, and the
+/// rolling block statistics are written to block_stats.json on shutdown.
 pub struct PathLogger {
     paths_writer: Arc<Mutex<Writer<File>>>,
     filtered_paths_writer: Arc<Mutex<Writer<File>>>,
     simulation_results_writer: Arc<Mutex<Writer<File>>>,
     block_summary_writer: Arc<Mutex<Writer<File>>>,
+    dry_run_bundles_writer: Arc<Mutex<Writer<File>>>,
     path_id_counter: Arc<Mutex<u64>>,
     path_id_map: Arc<Mutex<HashMap<String, u64>>>,
+    heatmap: Arc<Mutex<ProfitHeatmap>>,
+    heatmap_interval_blocks: u64,
     run_directory: PathBuf,
 }
 
@@ -153,7 +271,8 @@ impl PathLogger {
             "simulation_output_amount", 
             "gas_used",
             "gas_cost",
-            "gross_profit_in_native"
+            "gross_profit_in_native",
+            "opportunity_id"
         ])?;
         simulation_results_writer.flush()?;
 
@@ -169,10 +288,28 @@ impl PathLogger {
             "candidate_paths",
             "optimised_profitable_paths",
             "successful_simulations",
-            "profitable_simulations"
+            "profitable_simulations",
+            "failed_simulations",
+            "search_duration_ms"
         ])?;
         block_summary_writer.flush()?;
 
+        let dry_run_bundles_file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(output_dir.join("dry_run_bundles.csv"))?;
+        let mut dry_run_bundles_writer = Writer::from_writer(dry_run_bundles_file);
+        dry_run_bundles_writer.write_record(&[
+            "block_number",
+            "target_block",
+            "tx_count",
+            "bribe",
+            "raw_transactions",
+            "opportunity_ids"
+        ])?;
+        dry_run_bundles_writer.flush()?;
+
         tracing::info!(
             output_directory = %output_dir.display(),
             "Logger initialized"
@@ -183,12 +320,23 @@ impl PathLogger {
             filtered_paths_writer: Arc::new(Mutex::new(filtered_paths_writer)),
             simulation_results_writer: Arc::new(Mutex::new(simulation_results_writer)),
             block_summary_writer: Arc::new(Mutex::new(block_summary_writer)),
+            dry_run_bundles_writer: Arc::new(Mutex::new(dry_run_bundles_writer)),
             path_id_counter: Arc::new(Mutex::new(1)),
             path_id_map: Arc::new(Mutex::new(HashMap::new())),
+            heatmap: Arc::new(Mutex::new(ProfitHeatmap::new())),
+            heatmap_interval_blocks: DEFAULT_HEATMAP_INTERVAL_BLOCKS,
             run_directory: output_dir,
         })
     }
 
+    /// Set how many blocks pass between heatmap report refreshes.
+    ///
+    /// An interval of 0 disables periodic heatmap reports.
+    pub fn with_heatmap_interval(mut self, interval_blocks: u64) -> Self {
+        self.heatmap_interval_blocks = interval_blocks;
+        self
+    }
+
     /// Log a generated path with its pools and tokens.
     ///
     /// This creates a unique ID for the path based on its pool sequence
@@ -341,6 +489,7 @@ impl PathLogger {
         
         let tokens = self.extract_tokens_from_path_ext(path_ext)?;
         let path_id = self.get_or_create_path_id(&pools, &tokens)?;
+        let opportunity_id = OpportunityId::of_path_ext(path_ext, block_number);
 
         // Write to CSV
         {
@@ -354,12 +503,14 @@ impl PathLogger {
                 gas_used.to_string(),
                 gas_cost.to_string(),
                 gross_profit_in_native.to_string(),
+                opportunity_id.to_string(),
             ])?;
             writer.flush()?;
         }
 
         tracing::debug!(
             path_id = path_id,
+            opportunity_id = %opportunity_id,
             block_number = block_number,
             simulation_input_amount = %simulation_input_amount,
             simulation_output_amount = %simulation_output_amount,
@@ -369,6 +520,14 @@ impl PathLogger {
             "Logged simulation result"
         );
 
+        // Aggregate into the profitability heatmap
+        let observation = HeatmapObservation::from_path_ext(
+            path_ext,
+            gross_profit_in_native.clone(),
+            gas_cost.clone(),
+        )?;
+        self.heatmap.lock().unwrap().record(&observation, block_number);
+
         Ok(())
     }
 
@@ -384,6 +543,11 @@ impl PathLogger {
                 summary.optimised_profitable_paths.to_string(),
                 summary.successful_simulations.to_string(),
                 summary.profitable_simulations.to_string(),
+                summary.failed_simulations.to_string(),
+                summary
+                    .search_duration
+                    .map(|duration| duration.as_millis().to_string())
+                    .unwrap_or_default(),
             ])?;
             writer.flush()?;
         }
@@ -395,9 +559,77 @@ impl PathLogger {
             optimised_profitable_paths = summary.optimised_profitable_paths,
             successful_simulations = summary.successful_simulations,
             profitable_simulations = summary.profitable_simulations,
+            failed_simulations = summary.failed_simulations,
+            search_duration_ms = summary.search_duration.map(|duration| duration.as_millis() as u64),
             "Logged block summary"
         );
 
+        if self.heatmap_interval_blocks > 0 && summary.block_number % self.heatmap_interval_blocks == 0 {
+            self.write_heatmap_report()?;
+        }
+
+        Ok(())
+    }
+
+    /// Log a bundle that was built but not submitted in dry-run mode.
+    pub fn log_dry_run_bundle(&self, bundle: &Bundle, block_number: u64) -> Result<()> {
+        {
+            let mut writer = self.dry_run_bundles_writer.lock().unwrap();
+            writer.write_record(&[
+                block_number.to_string(),
+                bundle.target_block().to_string(),
+                bundle.transaction_count().to_string(),
+                bundle.bribe().to_string(),
+                bundle.transactions().join(","),
+                bundle.opportunity_ids().iter().map(ToString::to_string).collect::<Vec<_>>().join(","),
+            ])?;
+            writer.flush()?;
+        }
+
+        tracing::info!(
+            block_number = block_number,
+            target_block = bundle.target_block(),
+            tx_count = bundle.transaction_count(),
+            bribe = %bundle.bribe(),
+            "Logged dry-run bundle"
+        );
+
+        Ok(())
+    }
+
+    /// Write the current profitability heatmap to heatmap.json and heatmap.csv.
+    pub fn write_heatmap_report(&self) -> Result<()> {
+        let report = self.heatmap.lock().unwrap().report();
+        report.write_to_dir(&self.run_directory)?;
+
+        tracing::info!(
+            cells = report.cells.len(),
+            token_pairs = report.token_pairs.len(),
+            protocol_combinations = report.protocol_combinations.len(),
+            "Heatmap report written"
+        );
+
+        Ok(())
+    }
+
+    /// Write the rolling statistics of recent blocks to block_stats.json.
+    pub fn write_block_stats(&self, stats: &WindowStats) -> Result<()> {
+        let stats_json = serde_json::to_string_pretty(stats)?;
+        std::fs::write(self.run_directory.join("block_stats.json"), stats_json)?;
+
+        tracing::info!(
+            blocks = stats.blocks,
+            successful_simulations = stats.successful_simulations,
+            failed_simulations = stats.failed_simulations,
+            submitted_bundles = stats.submitted_bundles,
+            included_bundles = stats.included_bundles,
+            search_p50_ms = stats.search_latency.p50_ms,
+            search_p99_ms = stats.search_latency.p99_ms,
+            simulation_p50_ms = stats.simulation_latency.p50_ms,
+            simulation_p99_ms = stats.simulation_latency.p99_ms,
+            "Block statistics written"
+        );
+
         Ok(())
     }
 
@@ -452,6 +684,8 @@ impl Clone for PathLogger {
             block_summary_writer: Arc::clone(&self.block_summary_writer),
             path_id_counter: Arc::clone(&self.path_id_counter),
             path_id_map: Arc::clone(&self.path_id_map),
+            heatmap: Arc::clone(&self.heatmap),
+            heatmap_interval_blocks: self.heatmap_interval_blocks,
             run_directory: self.run_directory.clone(),
         }
     }
@@ -6,6 +6,7 @@
 pub mod arbitrage;
 pub mod balance;
 pub mod components;
+pub mod heatmap;
 pub mod logging;
 pub mod optimization;
 pub mod optimizers;
@@ -13,18 +14,46 @@ pub mod simulation;
 
 use crate::cli::Args;
 use alloy::{
-    providers::RootProvider,
-    signers::local::PrivateKeySigner,
+    primitives::Address,
+    providers::{Provider, RootProvider},
+    rpc::types::TransactionReceipt,
+};
+use num_bigint::{BigInt, BigUint};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use std::{collections::HashMap, str::FromStr, sync::Arc};
 use tycho_atomic_arbitrage::{
-    bundle::TxExecutor,
+    alerts::{AlertMonitor, AlertRule},
+    bootstrap::ExecutorBootstrap,
+    budget::SearchBudget,
+    bundle::{RevertedBundles, SubmissionTiming, TxExecutor},
+    chain::{HeadTracker, Reorg},
     config::ArbitrageConfig,
-    builders::SimulatorBuilder,
+    builders::{SimulatorBuilder, TradingGraphBuilder},
     errors::Result,
-    graph::TradingGraph,
+    events::{BlockEvent, EventDispatcher, EventHandler, InclusionEvent, NdjsonEventLog, ReorgEvent},
+    inventory::InventoryManager,
+    path::{CooldownRegistry, DiscoveryMonitor, PathExt, PathRanking, PoolVersions},
+    risk::RiskManager,
+    rpc::{ProviderPool, RateLimiter},
+    simulation::{
+        AccuracyTracker, AllowanceCache, FixedSlippage, GasEstimator, LogParser, PermitCache, ProfitGuard,
+        RealizedSlippage, RealizedSlippageLog, SimulationBlock, SimulationCache, TokenVetter, TraceCapture,
+    },
+    stats::BlockStatsAggregator,
+    utils::{
+        convert::{self, Rounding},
+        fees::BaseFeePredictor,
+        l2_fees::L2FeeModel,
+    },
 };
+#[cfg(feature = "status-server")]
+use tycho_atomic_arbitrage::status::{StatusBoard, StatusServer};
 use tycho_common::Bytes;
+use tycho_execution::encoding::models::UserTransferType;
 use tycho_simulation::protocol::{
     models::{BlockUpdate, ProtocolComponent},
     state::ProtocolSim,
@@ -32,7 +61,7 @@ use tycho_simulation::protocol::{
 
 use components::{
     ArbitrageParams, ExecutionContext, MarketContext, MarketDataManager,
-    PathFinder, SearchParams, TradeExecutor,
+    PathFinder, PendingExecution, SearchParams, StalePoolPruner, TokenVetting, TradeExecutor,
 };
 use logging::{PathLogger, RunConfiguration};
 
@@ -46,44 +75,260 @@ pub struct Context {
     trade_executor: TradeExecutor,
     params: ArbitrageParams,
     logger: PathLogger,
+    search_budget: SearchBudget,
+    block_received_at: Instant,
+    stale_pool_pruner: Option<StalePoolPruner>,
+    simulation_block: Option<Arc<SimulationBlock>>,
+    head_tracker: Option<Arc<HeadTracker>>,
+    token_vetting: Option<TokenVetting>,
+    block_stats: BlockStatsAggregator,
+    #[cfg(feature = "status-server")]
+    status_address: Option<String>,
+    #[cfg(feature = "status-server")]
+    status_board: Option<Arc<StatusBoard>>,
+    events: EventDispatcher,
+    alerts: Option<Arc<AlertMonitor>>,
+    executor_bootstrap: Option<ExecutorBootstrap>,
 }
 
 impl Context {
     pub fn new(args: Args) -> Result<Self> {
         let native_token = args.native_token()?;
         let source_tokens = args.start_tokens()?;
+        let search_budget = args.search_budget()?;
 
         let provider = Arc::new(RootProvider::new_http(
             args.rpc_url.parse()
                 .map_err(|e| anyhow::anyhow!("Invalid RPC URL: {}", e))?,
         ));
 
-        // Create configuration directly from the args
-        let config = ArbitrageConfig::from_env(&args.chain)?;
+        // Create configuration from the layered settings
+        let config = ArbitrageConfig::from_settings(args.settings.clone())?;
+
+        // Permits are signed during parallel simulation, which needs a local key
+        let signer = config.executor_signer().as_local().cloned()
+            .ok_or_else(|| anyhow::anyhow!("The executor key must be a private key or keystore"))?;
+
+        let transfer_type = args.transfer_type()?;
+        // The simulator falls back to TransferFrom on chains without Permit2
+        let uses_permit2 = matches!(transfer_type, UserTransferType::TransferFromPermit2)
+            && config.chain_profile.permit2_address().is_some();
+        let executor_bootstrap = uses_permit2
+            .then(|| ExecutorBootstrap::new(signer.address(), config.permit2_address));
 
-        let simulator = SimulatorBuilder::from_config(&config)
+        let mut simulator_builder = SimulatorBuilder::from_config(&config)
+            .with_transfer_type(transfer_type);
+        if args.skip_approvals && uses_permit2 {
+            simulator_builder = simulator_builder.with_allowance_cache(Arc::new(
+                AllowanceCache::new(signer.address(), config.permit2_address),
+            ));
+        }
+        if let (Some(validity_secs), true) = (args.permit_validity_secs, uses_permit2) {
+            simulator_builder = simulator_builder.with_permit_cache(Arc::new(
+                PermitCache::with_validity(Duration::from_secs(validity_secs)),
+            ));
+        }
+        if let Some(max_deviation_bps) = args.max_protocol_deviation_bps {
+            simulator_builder = simulator_builder.with_accuracy_tracker(Arc::new(
+                AccuracyTracker::new()
+                    .with_min_samples(args.min_accuracy_samples)
+                    .with_max_deviation_bps(max_deviation_bps),
+            ));
+        }
+        if args.profit_guard {
+            simulator_builder = simulator_builder.with_profit_guard(ProfitGuard::new(args.min_profit_bps));
+        }
+        if args.trace_simulations {
+            simulator_builder = simulator_builder.with_trace_capture(
+                TraceCapture::failed().with_max_output_shortfall_bps(args.max_output_shortfall_bps),
+            );
+        }
+        let realized_slippage = args.realized_slippage_samples.map(|min_samples| {
+            Arc::new(RealizedSlippageLog::new().with_min_samples(min_samples))
+        });
+        if let Some(log) = &realized_slippage {
+            simulator_builder = simulator_builder.with_slippage_policy(Arc::new(RealizedSlippage::new(
+                Arc::clone(log),
+                Arc::new(FixedSlippage::new(args.slippage_bps)),
+            )));
+        }
+        // One limiter is shared by simulations, balance checks and gas queries
+        let rate_limiter = args.rpc_rate_limit().map(|limit| Arc::new(RateLimiter::new(limit)));
+        let endpoint_rate_limits = args.rpc_endpoint_rate_limits()?;
+        if !args.fallback_rpc_urls.is_empty()
+            || args.rpc_race_width > 1
+            || rate_limiter.is_some()
+            || !endpoint_rate_limits.is_empty()
+        {
+            let mut endpoints = vec![(args.rpc_url.clone(), provider.clone())];
+            for url in &args.fallback_rpc_urls {
+                let fallback = url.parse().map_err(|e| anyhow::anyhow!("Invalid fallback RPC URL {}: {}", url, e))?;
+                endpoints.push((url.clone(), Arc::new(RootProvider::new_http(fallback))));
+            }
+            let mut pool = ProviderPool::new(endpoints)?.with_racing(args.rpc_race_width);
+            for (url, limit) in &endpoint_rate_limits {
+                pool = pool.with_endpoint_limit(url, *limit);
+            }
+            if let Some(limiter) = &rate_limiter {
+                pool = pool.with_rate_limiter(limiter.clone());
+            }
+            simulator_builder = simulator_builder.with_provider_pool(Arc::new(pool));
+        }
+        if let Some(margin_bps) = args.gas_margin_bps {
+            simulator_builder = simulator_builder.with_gas_estimator(
+                GasEstimator::new().with_safety_margin_bps(margin_bps),
+            );
+        }
+        // Simulations are reused while the pools they ran against are unchanged
+        let pool_versions = Arc::new(PoolVersions::new());
+        if let Some(capacity) = args.simulation_cache_size {
+            simulator_builder = simulator_builder.with_result_cache(Arc::new(
+                SimulationCache::new(pool_versions.clone()).with_capacity(capacity),
+            ));
+        }
+        // Backtests simulate on top of each recorded block instead of the latest one
+        let simulation_block = args.backtest_file.as_ref().map(|_| Arc::new(SimulationBlock::new()));
+        if let Some(block) = &simulation_block {
+            simulator_builder = simulator_builder.with_simulation_block(block.clone());
+        }
+        // Follow the chain head to retarget bundles whose block the Tycho stream lags behind
+        let head_tracker = if simulation_block.is_none() && (args.ws_rpc_url.is_some() || args.head_poll_interval_ms.is_some()) {
+            let tracker = Arc::new(HeadTracker::new());
+            let poll_interval = Duration::from_millis(args.head_poll_interval_ms.unwrap_or(1000));
+            tokio::spawn(tracker.clone().run(args.ws_rpc_url.clone(), provider.clone(), poll_interval));
+            simulator_builder = simulator_builder.with_head_tracker(tracker.clone());
+            Some(tracker)
+        } else {
+            None
+        };
+        let base_fee_predictor = Arc::new(BaseFeePredictor::new());
+        let simulator = simulator_builder
+            .with_base_fee_predictor(base_fee_predictor.clone())
             .build();
 
-        let executor = TxExecutor::from_config(config)?;
+        let submission_scheduler = args.submission_scheduler(config.chain_profile.block_time())?;
+        if submission_scheduler.timing() != SubmissionTiming::Immediate && head_tracker.is_none() {
+            return Err(anyhow::anyhow!(
+                "Scheduled submissions need the slot timestamps of head tracking, set --ws-rpc-url or --head-poll-interval-ms"
+            ).into());
+        }
+
+        let (chain_id, wrapped_native) = (config.chain_id, config.wrapped_native);
+        let mut executor = TxExecutor::from_config(config)?
+            .with_submission_mode(args.submission_mode()?)
+            .with_submission_scheduler(submission_scheduler)
+            .with_execution_mode(args.execution_mode())
+            .with_base_fee_predictor(base_fee_predictor.clone());
+        if let Some(tracker) = &head_tracker {
+            executor = executor.with_head_tracker(tracker.clone());
+        }
+        // Bundles that reverted on chain are never resubmitted byte for byte
+        let mut reverted_bundles = RevertedBundles::new(args.revert_memory_blocks);
+        if let Some(path) = &args.revert_memory_file {
+            reverted_bundles = reverted_bundles.with_persistence(path)?;
+        }
+        executor = executor.with_reverted_bundles(Arc::new(reverted_bundles));
+
+        // Tolerances keyed by token in the settings take precedence over positional ones
+        let optimization_tolerances = args.settings.resolve_start_token_tolerances(&source_tokens)?;
 
-        let signer = args.executor_private_key.parse::<PrivateKeySigner>()
-            .map_err(|e| anyhow::anyhow!("Invalid swapper private key: {}", e))?;
+        let balance_caps = source_tokens
+            .iter()
+            .cloned()
+            .zip(args.balance_cap_percentages.iter().cloned())
+            .collect();
 
-        let optimization_tolerances = source_tokens
+        let source_path_lengths = source_tokens
             .iter()
             .cloned()
-            .zip(args.optimization_tolerances.iter().cloned())
+            .zip(args.max_path_lengths.iter().cloned())
             .collect();
 
         // Create components
-        let market_data = MarketDataManager::new();
-        let path_finder = PathFinder::new(source_tokens, optimization_tolerances);
-        let trade_executor = TradeExecutor::new(simulator, executor, provider, signer);
-        let params = ArbitrageParams::new(native_token.clone(), args.min_profit_bps);
+        let market_data = MarketDataManager::with_graph(
+            TradingGraphBuilder::new()
+                .with_max_pool_tokens(args.max_pool_tokens)
+                .with_source_tokens(source_tokens.iter().cloned())
+                .with_exchange_filter(args.settings.exchange_filter())
+                .build()?,
+        )
+        .with_pool_versions(pool_versions);
+        let mut path_finder = PathFinder::new(source_tokens, optimization_tolerances, balance_caps)
+            .with_discovery_policy(args.settings.discovery_policy())
+            .with_path_lengths(args.max_path_length, source_path_lengths)
+            .with_protocol_gas(args.settings.protocol_gas_table());
+        if let Some(ranked_paths) = args.ranked_paths {
+            let ranking = PathRanking::new().with_half_life_blocks(args.ranking_half_life_blocks);
+            path_finder = path_finder.with_ranking(Arc::new(ranking), ranked_paths);
+        }
+        if let Some(min_failures) = args.cooldown_failures {
+            path_finder = path_finder.with_cooldowns(Arc::new(
+                CooldownRegistry::new()
+                    .with_min_failures(min_failures)
+                    .with_base_cooldown_blocks(args.cooldown_base_blocks)
+                    .with_max_cooldown_blocks(args.cooldown_max_blocks)
+                    .with_decay_blocks(args.cooldown_decay_blocks),
+            ));
+        }
+        let mut inventory = InventoryManager::new(signer.address())
+            .with_wrapped_native(wrapped_native);
+        if let Some(limiter) = &rate_limiter {
+            inventory = inventory.with_rate_limiter(limiter.clone());
+        }
+        if let Some(percentage) = args.inventory_floor_percentage {
+            inventory = inventory.with_floor_percentage(percentage);
+        }
+        if let Some(gas_floor) = args.gas_floor {
+            inventory = inventory.with_gas_floor(
+                convert::f64_to_biguint_saturating(gas_floor * 1e18, Rounding::Floor),
+            );
+        }
+
+        // OP-stack chains charge an L1 data fee on top of the L2 gas
+        let l2_fees = L2FeeModel::for_chain(&args.chain, provider.clone());
+        let mut trade_executor = TradeExecutor::new(simulator, executor, provider, signer)
+            .with_inventory(inventory)
+            .with_base_fee_predictor(base_fee_predictor);
+        if let Some(l2_fees) = l2_fees {
+            trade_executor = trade_executor.with_l2_fee_model(Arc::new(l2_fees));
+        }
+        if let Some(log) = realized_slippage {
+            trade_executor = trade_executor.with_realized_slippage(log);
+        }
+        if let Some(limiter) = rate_limiter {
+            trade_executor = trade_executor.with_rate_limiter(limiter);
+        }
+        let risk_limits = args.risk_limits();
+        if risk_limits.is_some() || args.kill_switch_file.is_some() {
+            let mut risk = RiskManager::new(risk_limits.unwrap_or_default());
+            if let Some(path) = &args.kill_switch_file {
+                risk = risk.with_kill_switch_file(path.clone());
+            }
+            trade_executor = trade_executor.with_risk_manager(Arc::new(risk));
+        }
+        let params = ArbitrageParams::new(native_token.clone(), args.min_profit_bps)
+            .with_max_portfolio_size(args.max_portfolio_size)
+            .with_shared_pools(args.allow_shared_pools)
+            .with_max_output_shortfall_bps(args.max_output_shortfall_bps)
+            .with_min_profit_gas_multiple(args.min_profit_gas_multiple);
+        let params = if args.auto_rebalance {
+            params.with_auto_rebalance(chain_id)
+        } else {
+            params
+        };
+        let params = if args.bootstrap_approvals {
+            params.with_bootstrap_approvals(chain_id)
+        } else {
+            params
+        };
 
         // Initialize logger with default output directory
         let logger = PathLogger::new("./arbitrage_logs")
-            .map_err(|e| anyhow::anyhow!("Failed to initialize logger: {}", e))?;
+            .map_err(|e| anyhow::anyhow!("Failed to initialize logger: {}", e))?
+            .with_heatmap_interval(args.heatmap_interval_blocks);
+
+        // Alerts are evaluated on the events of every block
+        let alerts = args.alert_monitor()?.map(Arc::new);
 
         // Create and log the run configuration
         let run_config = RunConfiguration {
@@ -95,8 +340,14 @@ impl Context {
             start_token_addresses: path_finder.source_tokens.iter()
                 .map(|token| token.to_string())
                 .collect(),
-            optimization_tolerances: args.optimization_tolerances.clone(),
-            has_executor_private_key: !args.executor_private_key.is_empty(),
+            optimization_tolerances: path_finder.source_tokens.iter()
+                .map(|token| path_finder.optimization_tolerances.tolerance(token))
+                .collect(),
+            balance_cap_percentages: args.balance_cap_percentages.clone(),
+            max_path_length: args.max_path_length,
+            max_path_lengths: args.max_path_lengths.clone(),
+            has_executor_private_key: args.executor_private_key.is_some(),
+            has_executor_keystore: args.executor_keystore.is_some(),
             tvl_threshold: args.tvl_threshold,
             min_profit_bps: args.min_profit_bps,
             slippage_bps: args.slippage_bps,
@@ -104,8 +355,84 @@ impl Context {
             bribe_percentage: args.bribe_percentage,
             native_token_address: native_token.to_string(),
             tycho_url: args.tycho_url().unwrap_or_else(|_| "unknown".to_string()),
+            heatmap_interval_blocks: args.heatmap_interval_blocks,
+            search_budget_ms: search_budget.wall_clock.as_millis() as u64,
+            max_paths: search_budget.max_paths,
+            max_simulations: search_budget.max_simulations,
+            submission_mode: args.submission_mode.clone(),
+            submission_timing: args.submission_timing.clone(),
+            submission_offset_ms: args.submission_offset_ms,
+            dry_run: args.dry_run,
+            backtest_file: args.backtest_file.as_ref().map(|path| path.display().to_string()),
+            replay_speed: args.replay_speed.clone(),
+            exchanges: args.settings.exchange_filter()
+                .enabled_exchanges()
+                .map(|(exchange, _)| exchange.to_string())
+                .collect(),
+            max_block_gap: args.max_block_gap,
+            resync_settle_blocks: args.resync_settle_blocks,
+            record_dir: args.record_dir.as_ref().map(|path| path.display().to_string()),
+            record_blocks_per_file: args.record_blocks_per_file,
+            event_log: args.event_log.as_ref().map(|path| path.display().to_string()),
+            alert_rules: alerts
+                .iter()
+                .flat_map(|monitor| monitor.rules().iter().map(|rule| rule.kind().to_string()))
+                .collect(),
+            alert_webhook: alerts.is_some() && args.alert_webhook_url.is_some(),
+            max_portfolio_size: args.max_portfolio_size,
+            allow_shared_pools: args.allow_shared_pools,
+            revert_memory_blocks: args.revert_memory_blocks,
+            revert_memory_file: args.revert_memory_file.as_ref().map(|path| path.display().to_string()),
+            skip_approvals: args.skip_approvals,
+            bootstrap_approvals: args.bootstrap_approvals,
+            transfer_type: args.transfer_type.clone(),
+            permit_validity_secs: args.permit_validity_secs,
+            receiver_address: args.receiver_address.clone(),
+            max_output_shortfall_bps: args.max_output_shortfall_bps,
+            max_pool_tokens: args.max_pool_tokens,
+            max_protocol_deviation_bps: args.max_protocol_deviation_bps,
+            rpc_rate_limit: args.rpc_rate_limit,
+            rpc_max_concurrent: args.rpc_max_concurrent,
+            min_accuracy_samples: args.min_accuracy_samples,
+            realized_slippage_samples: args.realized_slippage_samples,
+            stale_pool_blocks: args.stale_pool_blocks,
+            prune_interval_blocks: args.prune_interval_blocks,
+            ranked_paths: args.ranked_paths,
+            ranking_half_life_blocks: args.ranking_half_life_blocks,
+            cooldown_failures: args.cooldown_failures,
+            cooldown_base_blocks: args.cooldown_base_blocks,
+            cooldown_max_blocks: args.cooldown_max_blocks,
+            cooldown_decay_blocks: args.cooldown_decay_blocks,
+            max_trade_notional: args.max_trade_notional,
+            max_gas_price_gwei: args.max_gas_price_gwei,
+            max_daily_loss: args.max_daily_loss,
+            max_consecutive_failures: args.max_consecutive_failures,
+            kill_switch_file: args.kill_switch_file.as_ref().map(|path| path.display().to_string()),
+            inventory_floor_percentage: args.inventory_floor_percentage,
+            gas_floor: args.gas_floor,
+            auto_rebalance: args.auto_rebalance,
+            gas_margin_bps: args.gas_margin_bps,
+            profit_guard: args.profit_guard,
+            trace_simulations: args.trace_simulations,
+            execution_protocols: args.settings.execution_protocols.clone(),
         };
 
+        // The status endpoint summarizes the same masked configuration
+        #[cfg(feature = "status-server")]
+        let status_board = args.status_address.as_ref().map(|_| {
+            let config = serde_json::to_value(&run_config).unwrap_or_default();
+            let board = config
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter(|(_, value)| !value.is_null())
+                .fold(StatusBoard::new(), |board, (key, value)| match value {
+                    serde_json::Value::String(value) => board.with_config_entry(key.as_str(), value),
+                    value => board.with_config_entry(key.as_str(), value),
+                });
+            Arc::new(board)
+        });
+
         // Log the configuration to config.json
         if let Err(e) = logger.log_config(run_config) {
             tracing::warn!(
@@ -114,11 +441,22 @@ impl Context {
             );
         }
 
+        let mut events = EventDispatcher::new();
+        if let Some(path) = &args.event_log {
+            let event_log = NdjsonEventLog::create(path)
+                .map_err(|e| anyhow::anyhow!("Failed to open event log {}: {}", path.display(), e))?;
+            events.register(Arc::new(event_log));
+        }
+        if let Some(monitor) = &alerts {
+            events.register(monitor.clone());
+        }
+
         tracing::info!(
             chain = args.chain,
             native_token = %native_token,
             source_tokens_count = path_finder.source_tokens.len(),
             min_profit_bps = args.min_profit_bps,
+            search_budget_ms = search_budget.wall_clock.as_millis(),
             "Context initialized successfully"
         );
 
@@ -128,31 +466,351 @@ impl Context {
             trade_executor,
             params,
             logger,
+            search_budget,
+            block_received_at: Instant::now(),
+            stale_pool_pruner: args.stale_pool_blocks
+                .map(|max_age_blocks| StalePoolPruner::new(max_age_blocks, args.prune_interval_blocks)),
+            simulation_block,
+            head_tracker,
+            token_vetting: args.token_probes_per_block.map(|probes_per_block| {
+                let vetter = TokenVetter::new().with_max_shortfall_bps(args.max_probe_shortfall_bps);
+                TokenVetting::new(Arc::new(vetter), probes_per_block)
+            }),
+            block_stats: BlockStatsAggregator::new().with_window_blocks(args.stats_window_blocks),
+            #[cfg(feature = "status-server")]
+            status_address: args.status_address.clone(),
+            #[cfg(feature = "status-server")]
+            status_board,
+            events,
+            alerts,
+            executor_bootstrap,
         })
     }
 
+    /// Check the balances and Permit2 allowances of the executor before the first search.
+    ///
+    /// Missing approvals are submitted as one batch if enabled. Without
+    /// Permit2 there are no allowances to check and nothing is done.
+    pub async fn bootstrap(&self) -> Result<()> {
+        let Some(bootstrap) = &self.executor_bootstrap else {
+            return Ok(());
+        };
+        balance::bootstrap_executor(bootstrap, &self.path_finder, &self.trade_executor, &self.params).await
+    }
+
+    /// How long until updated bundles may replace the bundles submitted for the next block.
+    ///
+    /// `None` unless resubmissions are scheduled and still possible in the current slot.
+    pub fn resubmission_delay(&self) -> Option<Duration> {
+        let head = self.head_tracker.as_ref()?.block_number()?;
+        self.trade_executor.executor.resubmission_delay(head + 1)
+    }
+
+    /// Rolling statistics of the recent searches, submissions and inclusions.
+    pub fn block_stats(&self) -> &BlockStatsAggregator {
+        &self.block_stats
+    }
+
+    /// Register a handler notified of blocks, opportunities, simulations and bundles.
+    pub fn register_event_handler(&mut self, handler: Arc<dyn EventHandler>) {
+        self.events.register(handler);
+    }
+
     pub async fn apply(&mut self, update: BlockUpdate) -> Result<Vec<Bytes>> {
+        // The search budget for this block starts as soon as the update arrives
+        self.block_received_at = Instant::now();
+
+        if let Some(block) = &self.simulation_block {
+            block.pin(update.block_number);
+        }
+
         // Update balances
         balance::update_source_balances(
             &self.path_finder,
-            &self.trade_executor.provider,
-            self.trade_executor.signer.address(),
+            &self.trade_executor,
+            update.block_number,
         ).await?;
+        balance::rebalance_inventory(&self.trade_executor, &self.params, update.block_number).await;
+
+        // Re-read Permit2 allowances on startup and after submitted approvals
+        self.refresh_allowances().await;
 
         // Update block number
         self.market_data.update_block_number(update.block_number).await;
 
+        // Cool down routes whose submitted bundles did not land
+        self.resolve_pending_executions(update.block_number).await;
+
         // Handle market data updates
         self.handle_removed_pairs(&update.removed_pairs).await;
         self.handle_new_pairs(&update.new_pairs).await;
-        self.handle_states(&update.states).await
+        let mut updated_pools = self.handle_states(&update.states).await?;
+
+        // Tokens failing their probe are never searched
+        if self.token_vetting.is_some() {
+            let dropped_pools = self.vet_new_tokens(&update.new_pairs).await;
+            updated_pools.retain(|pool| !dropped_pools.contains(pool));
+        }
+
+        // Paths skipped for missing protocol data are searched again once it arrived
+        self.recover_missing_data(&mut updated_pools).await;
+
+        // Pending bundles and cached simulations of replaced blocks are invalid
+        if let Some(reorg) = self.head_tracker.as_ref().and_then(|tracker| tracker.take_reorg()) {
+            updated_pools = self.handle_reorg(&reorg).await;
+        }
+
+        if self.stale_pool_pruner.is_some() {
+            self.prune_stale_pools(update.block_number, &update.new_pairs, &updated_pools).await;
+        }
+
+        self.events.on_block(&BlockEvent {
+            block_number: update.block_number,
+            updated_pools: &updated_pools,
+        });
+        self.observe_alerts(update.block_number).await;
+
+        #[cfg(feature = "status-server")]
+        self.publish_status(update.block_number).await;
+
+        Ok(updated_pools)
+    }
+
+    /// Report low inventory and the stream lag to the alert monitor, if any.
+    async fn observe_alerts(&self, block_number: u64) {
+        let Some(monitor) = &self.alerts else {
+            return;
+        };
+        monitor.observe_inventory(block_number, &self.trade_executor.inventory.suggestions());
+
+        if !monitor.rules().iter().any(|rule| matches!(rule, AlertRule::StreamLagAbove { .. })) {
+            return;
+        }
+        let chain_head = match self.head_tracker.as_ref().and_then(|tracker| tracker.block_number()) {
+            Some(chain_head) => chain_head,
+            None => match self.trade_executor.provider.get_block_number().await {
+                Ok(chain_head) => chain_head,
+                Err(e) => {
+                    tracing::debug!(error = %e, "Failed to fetch chain head for stream lag alerts");
+                    return;
+                }
+            },
+        };
+        monitor.observe_stream_lag(block_number, chain_head);
+    }
+
+    /// Start the status endpoint if an address is configured.
+    #[cfg(feature = "status-server")]
+    pub async fn start_status_server(&self) -> Result<Option<StatusServer>> {
+        let (Some(address), Some(board)) = (&self.status_address, &self.status_board) else {
+            return Ok(None);
+        };
+        Ok(Some(StatusServer::start(address, Arc::clone(board)).await?))
+    }
+
+    /// Publish the processed block, the stream lag, pending bundles and balances to the status endpoint.
+    #[cfg(feature = "status-server")]
+    async fn publish_status(&self, block_number: u64) {
+        let Some(board) = &self.status_board else {
+            return;
+        };
+        board.record_block(block_number);
+        match self.trade_executor.provider.get_block_number().await {
+            Ok(chain_head) => board.record_chain_head(chain_head),
+            Err(e) => tracing::debug!(error = %e, "Failed to fetch chain head for status"),
+        }
+        board.set_pending_bundles(self.trade_executor.pending_executions.read().await.len());
+        board.set_inventory(&self.trade_executor.inventory.balances());
+    }
+
+    /// Add pools whose missing component or state has arrived to the updated pools.
+    ///
+    /// Pools newly found missing data while building paths are reported once;
+    /// the stream delivers their data with a later update.
+    async fn recover_missing_data(&self, updated_pools: &mut Vec<Bytes>) {
+        let guard_paths = self.path_finder.paths.read().await;
+        let missing_data = guard_paths.missing_data();
+        for (pool, missing) in missing_data.take_requests() {
+            tracing::warn!(
+                pool = %pool,
+                missing = ?missing,
+                "Paths through pool skipped, awaiting its protocol data from the stream"
+            );
+        }
+        if missing_data.is_empty() {
+            return;
+        }
+
+        let recovered = {
+            let guard_sim = self.market_data.protocol_sim.read().await;
+            let guard_comp = self.market_data.protocol_comp.read().await;
+            missing_data.recover(&guard_comp, &guard_sim)
+        };
+        for pool in recovered {
+            if !updated_pools.contains(&pool) {
+                updated_pools.push(pool);
+            }
+        }
+    }
+
+    /// Probe the tokens of new pairs and drop every pool of denylisted tokens.
+    ///
+    /// Tokens are probed against a source token sharing one of their pools,
+    /// with 0.1% of its balance. Tokens without such a pool cannot be probed
+    /// and stay unvetted.
+    ///
+    /// # Returns
+    ///
+    /// The pools dropped from the market state and the graph
+    async fn vet_new_tokens(&mut self, new_pairs: &HashMap<String, ProtocolComponent>) -> Vec<Bytes> {
+        let Some(vetting) = self.token_vetting.as_mut() else {
+            return Vec::new();
+        };
+        let balances = self.path_finder.source_balances.read().await.clone();
+        let new_tokens: HashSet<Bytes> = new_pairs
+            .values()
+            .flat_map(|comp| comp.tokens.iter().map(|token| token.address.clone()))
+            .filter(|token| !balances.contains_key(token))
+            .collect();
+        vetting.enqueue(new_tokens.iter().cloned());
+        let batch = vetting.next_batch();
+        let pending = vetting.pending();
+        let vetter = Arc::clone(&vetting.vetter);
+
+        let probes: Vec<(Bytes, PathExt)> = {
+            let guard_sim = self.market_data.protocol_sim.read().await;
+            let guard_comp = self.market_data.protocol_comp.read().await;
+            batch
+                .iter()
+                .filter_map(|token| {
+                    let probe = guard_comp.iter().find_map(|(pool, comp)| {
+                        if !comp.tokens.iter().any(|pool_token| &pool_token.address == token) {
+                            return None;
+                        }
+                        let (source_token, balance) = comp
+                            .tokens
+                            .iter()
+                            .find_map(|pool_token| balances.get(&pool_token.address).map(|balance| (&pool_token.address, balance)))?;
+                        let amount_in = (balance / BigUint::from(1000u32)).max(BigUint::from(1u32));
+                        TokenVetter::probe_path(Arc::clone(comp), Arc::clone(guard_sim.get(pool)?), source_token, token, amount_in).ok()
+                    });
+                    if probe.is_none() {
+                        tracing::debug!(token = %token, "No pool with a source token to probe token with");
+                    }
+                    probe.map(|probe| (token.clone(), probe))
+                })
+                .collect()
+        };
+
+        if !probes.is_empty() {
+            let executor = &self.trade_executor;
+            match simulation::get_nonce_and_base_fee(
+                &executor.provider,
+                executor.signer.address(),
+                executor.simulator.simulation_block(),
+                &executor.base_fee_predictor,
+                executor.rate_limiter.as_deref(),
+            ).await {
+                Ok((nonce, base_fee)) => {
+                    for (token, probe) in &probes {
+                        vetter.vet(&executor.simulator, &executor.provider, token, probe, nonce, base_fee, &executor.signer).await;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to get nonce and base fee for token probes");
+                }
+            }
+        }
+
+        let denied: HashSet<Bytes> = new_tokens
+            .into_iter()
+            .chain(batch)
+            .filter(|token| vetter.is_denied(token))
+            .collect();
+        if denied.is_empty() {
+            return Vec::new();
+        }
+
+        let dropped_pools: Vec<Bytes> = {
+            let mut guard_sim = self.market_data.protocol_sim.write().await;
+            let mut guard_comp = self.market_data.protocol_comp.write().await;
+            let pools: Vec<Bytes> = guard_comp
+                .iter()
+                .filter(|(_, comp)| comp.tokens.iter().any(|token| denied.contains(&token.address)))
+                .map(|(pool, _)| pool.clone())
+                .collect();
+            for pool in &pools {
+                guard_sim.remove(pool);
+                guard_comp.remove(pool);
+            }
+            pools
+        };
+        let removed_from_graph = {
+            let mut guard_paths = self.path_finder.paths.write().await;
+            self.market_data.graph.update(|graph| guard_paths.remove_pools(graph, &dropped_pools))
+        };
+
+        tracing::info!(
+            probed_tokens = probes.len(),
+            pending_probes = pending,
+            denied_tokens = denied.len(),
+            dropped_pools = dropped_pools.len(),
+            removed_from_graph = removed_from_graph.len(),
+            denylist_size = vetter.denylist().len(),
+            "Dropped pools of denylisted tokens"
+        );
+        dropped_pools
+    }
+
+    /// Drop state built on the blocks a reorg replaced.
+    ///
+    /// Pending submissions targeting replaced blocks are dropped without
+    /// cooling down their routes, cached simulation results are cleared, and
+    /// every pool is searched again on the new canonical head.
+    ///
+    /// # Returns
+    ///
+    /// All known pools, to search as updated
+    async fn handle_reorg(&mut self, reorg: &Reorg) -> Vec<Bytes> {
+        let dropped_submissions = {
+            let mut pending = self.trade_executor.pending_executions.write().await;
+            let initial_count = pending.len();
+            pending.retain(|execution| execution.target_block < reorg.fork_block);
+            initial_count - pending.len()
+        };
+        if let Some(cache) = self.trade_executor.simulator.result_cache() {
+            cache.clear();
+        }
+
+        tracing::warn!(
+            old_head = reorg.old_head.number,
+            new_head = reorg.new_head.number,
+            fork_block = reorg.fork_block,
+            depth = reorg.depth(),
+            dropped_submissions = dropped_submissions,
+            "Reorg replaced recorded blocks, searching all pools again"
+        );
+        self.events.on_reorg(&ReorgEvent {
+            old_head: reorg.old_head.number,
+            new_head: reorg.new_head.number,
+            fork_block: reorg.fork_block,
+            dropped_submissions,
+        });
+
+        self.market_data.protocol_sim.read().await.keys().cloned().collect()
     }
 
     pub async fn search(&self, updated_pools: Vec<Bytes>) -> Result<()> {
         let block_number = self.market_data.get_block_number().await;
-        let search_params = SearchParams::new(updated_pools, block_number);
+        let deadline = self.search_budget.start_at(self.block_received_at);
+        let search_params = SearchParams::new(updated_pools, block_number, deadline);
         let market_context = MarketContext::new(&self.market_data, &self.path_finder);
-        let execution_context = ExecutionContext::new(&self.trade_executor, &self.params);
+        let execution_context = ExecutionContext::new(
+            &self.trade_executor,
+            &self.params,
+            &self.events,
+            &self.block_stats,
+        );
 
         arbitrage::execute_arbitrage_search(
             search_params,
@@ -162,6 +820,305 @@ impl Context {
         ).await
     }
 
+    /// Track pool activity and evict pools without recent state updates.
+    ///
+    /// Pruned pools that receive a state update are added back to the graph and
+    /// their paths are rediscovered.
+    async fn prune_stale_pools(
+        &mut self,
+        block_number: u64,
+        new_pairs: &HashMap<String, ProtocolComponent>,
+        updated_pools: &[Bytes],
+    ) {
+        let Some(pruner) = self.stale_pool_pruner.as_mut() else {
+            return;
+        };
+
+        let revived = pruner.take_revived(updated_pools);
+        if !revived.is_empty() {
+            let revived_pairs: HashMap<String, ProtocolComponent> = {
+                let guard_comp = self.market_data.protocol_comp.read().await;
+                revived
+                    .iter()
+                    .filter_map(|pool| guard_comp.get(pool).map(|comp| (pool.to_string(), ProtocolComponent::clone(comp))))
+                    .collect()
+            };
+
+            tracing::info!(revived_pools = revived_pairs.len(), "Reviving pruned pools");
+            self.handle_new_pairs(&revived_pairs).await;
+        }
+
+        // Record activity and prune in a single graph update
+        let cutoff = self.stale_pool_pruner.as_mut().and_then(|pruner| pruner.cutoff(block_number));
+        let mut guard_paths = match cutoff {
+            Some(_) => Some(self.path_finder.paths.write().await),
+            None => None,
+        };
+        let pruned = self.market_data.graph.update(|graph| {
+            for pool in new_pairs.keys().filter_map(|key| Bytes::from_str(key).ok()) {
+                graph.record_update(&pool, block_number);
+            }
+            for pool in updated_pools {
+                graph.record_update(pool, block_number);
+            }
+            cutoff.zip(guard_paths.as_mut()).map(|(cutoff, paths)| paths.prune_stale_pools(graph, cutoff))
+        });
+
+        let (Some(pruner), Some(cutoff), Some(pruned), Some(guard_paths)) =
+            (self.stale_pool_pruner.as_mut(), cutoff, pruned, guard_paths)
+        else {
+            return;
+        };
+        if !pruned.is_empty() {
+            tracing::info!(
+                block_number = block_number,
+                cutoff_block = cutoff,
+                pruned_pools = pruned.len(),
+                remaining_paths = guard_paths.pool_paths.len(),
+                "Pruned stale pools"
+            );
+        }
+        pruner.record_pruned(pruned);
+    }
+
+    /// Rebuild market state from a snapshot received after (re)connecting.
+    ///
+    /// Pools that are not part of the snapshot were removed or dropped out of the
+    /// tracked set while the stream was out of sync, so their state, graph edges
+    /// and paths are dropped. Pools that are already known are taken out of the
+    /// snapshot's new pairs, as only their state needs to be replaced.
+    pub async fn resynchronize(&mut self, update: &mut BlockUpdate) {
+        let vanished: Vec<Bytes> = {
+            let guard_comp = self.market_data.protocol_comp.read().await;
+            if guard_comp.is_empty() {
+                return;
+            }
+
+            let snapshot_pools: HashSet<Bytes> = update
+                .states
+                .keys()
+                .chain(update.new_pairs.keys())
+                .filter_map(|key| Bytes::from_str(key).ok())
+                .collect();
+
+            update.new_pairs.retain(|key, _| {
+                Bytes::from_str(key).map_or(true, |pool| !guard_comp.contains_key(&pool))
+            });
+
+            guard_comp
+                .keys()
+                .filter(|pool| !snapshot_pools.contains(*pool))
+                .cloned()
+                .collect()
+        };
+
+        if !vanished.is_empty() {
+            let mut guard_sim = self.market_data.protocol_sim.write().await;
+            let mut guard_comp = self.market_data.protocol_comp.write().await;
+            for pool in &vanished {
+                guard_sim.remove(pool);
+                guard_comp.remove(pool);
+            }
+        }
+
+        let removed_from_graph = {
+            let mut guard_paths = self.path_finder.paths.write().await;
+            self.market_data.graph.update(|graph| guard_paths.remove_pools(graph, &vanished))
+        };
+
+        if let Some(pruner) = self.stale_pool_pruner.as_mut() {
+            pruner.forget(&vanished);
+        }
+
+        tracing::info!(
+            block_number = update.block_number,
+            snapshot_states = update.states.len(),
+            new_pairs = update.new_pairs.len(),
+            dropped_pools = vanished.len(),
+            removed_from_graph = removed_from_graph.len(),
+            "Resynchronized market state from snapshot"
+        );
+    }
+
+    /// Check whether portfolios submitted for past blocks were included.
+    ///
+    /// Paths of included portfolios have their failure history cleared, paths of
+    /// portfolios that reverted or lost the race count as failed. With risk limits,
+    /// the change of the native token balance is recorded as realized profit or loss.
+    async fn resolve_pending_executions(&self, block_number: u64) {
+        let cooldowns = self.path_finder.cooldowns.as_deref();
+        let risk = self.trade_executor.risk.as_deref();
+        if let Some(cooldowns) = cooldowns {
+            cooldowns.prune(block_number);
+        }
+
+        let resolved: Vec<PendingExecution> = {
+            let mut pending = self.trade_executor.pending_executions.write().await;
+            let (resolved, waiting): (Vec<_>, Vec<_>) = pending
+                .drain(..)
+                .partition(|execution| execution.target_block <= block_number);
+            *pending = waiting;
+            resolved
+        };
+        if resolved.is_empty() {
+            return;
+        }
+
+        let nonce = match self.trade_executor.provider
+            .get_transaction_count(self.trade_executor.signer.address())
+            .await
+        {
+            Ok(nonce) => nonce,
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    "Failed to fetch executor nonce, skipping inclusion check"
+                );
+                return;
+            }
+        };
+
+        // Balances were refreshed for this block, the earliest portfolio holds the balance before all of them
+        let native_balance = self.path_finder.source_balances.read().await
+            .get(&self.params.native_token)
+            .cloned();
+        if let (Some(risk), Some(before), Some(after)) = (
+            risk,
+            resolved.first().and_then(|execution| execution.native_balance_before.as_ref()),
+            &native_balance,
+        ) {
+            risk.record_pnl(&(BigInt::from(after.clone()) - BigInt::from(before.clone())));
+        }
+
+        for execution in resolved {
+            let included = execution.is_included(nonce);
+            tracing::info!(
+                target_block = execution.target_block,
+                base_nonce = execution.base_nonce,
+                nonce = nonce,
+                included = included,
+                paths = execution.paths.len(),
+                opportunity_ids = ?execution.opportunity_ids.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                "Resolved pending execution"
+            );
+            self.block_stats.record_inclusion(execution.target_block.saturating_sub(1), included);
+
+            self.events.on_bundle_included(&InclusionEvent {
+                target_block: execution.target_block,
+                paths: &execution.paths,
+                opportunity_ids: &execution.opportunity_ids,
+                included,
+            });
+
+            if let Some(risk) = risk {
+                risk.record_bundle_result(included);
+            }
+            if included {
+                if let Some(receipts) = self.executor_receipts(execution.target_block).await {
+                    // Included transactions that reverted must not be submitted again as they are
+                    let reverted: Vec<_> = receipts
+                        .iter()
+                        .filter(|receipt| !receipt.status())
+                        .map(|receipt| receipt.transaction_hash)
+                        .collect();
+                    self.trade_executor.executor.record_reverted_transactions(&reverted, execution.target_block);
+                    self.record_realized_slippage(&execution, &receipts);
+                }
+            }
+
+            let Some(cooldowns) = cooldowns else {
+                continue;
+            };
+            for path in &execution.paths {
+                if included {
+                    cooldowns.record_success(path);
+                } else {
+                    cooldowns.record_failure(path, block_number);
+                }
+            }
+        }
+    }
+
+    /// The receipts of the executor's transactions in a block.
+    async fn executor_receipts(&self, block_number: u64) -> Option<Vec<TransactionReceipt>> {
+        match self.trade_executor.provider.get_block_receipts(block_number.into()).await {
+            Ok(receipts) => {
+                let executor = self.trade_executor.signer.address();
+                Some(receipts.unwrap_or_default().into_iter().filter(|receipt| receipt.from == executor).collect())
+            }
+            Err(e) => {
+                tracing::warn!(
+                    target_block = block_number,
+                    error = %e,
+                    "Failed to fetch block receipts, skipping revert and realized slippage checks"
+                );
+                None
+            }
+        }
+    }
+
+    /// Log the realized slippage of an included portfolio from the executor's receipts of its target block.
+    ///
+    /// Each path is compared with the receipt whose swaps include its final pool.
+    fn record_realized_slippage(&self, execution: &PendingExecution, receipts: &[TransactionReceipt]) {
+        let Some(log) = &self.trade_executor.realized_slippage else {
+            return;
+        };
+
+        let decoded_receipts: Vec<_> = receipts
+            .iter()
+            .filter(|receipt| receipt.status())
+            .map(|receipt| LogParser::decode_swaps(receipt.inner.logs()))
+            .filter(|swaps| !swaps.is_empty())
+            .collect();
+        for (path, checked_amount) in execution.paths.iter().zip(&execution.checked_amounts) {
+            let Some(final_swap) = path.last() else {
+                continue;
+            };
+            let decoded = decoded_receipts.iter().find(|swaps| {
+                swaps.iter().any(|swap| swap.pool == final_swap.pool_comp.id || swap.pool == final_swap.pool_comp.address)
+            });
+            if let Some(realized) = decoded.and_then(|swaps| log.record_execution(path, checked_amount, swaps)) {
+                tracing::info!(
+                    target_block = execution.target_block,
+                    slippage_bps = realized.slippage_bps,
+                    checked_margin_bps = realized.checked_margin_bps,
+                    "Recorded realized slippage"
+                );
+            }
+        }
+    }
+
+    /// Write the reports that are otherwise only refreshed periodically, and the block statistics.
+    pub fn write_reports(&self) -> Result<()> {
+        self.logger.write_heatmap_report()
+            .map_err(|e| anyhow::anyhow!("Failed to write heatmap report: {}", e))?;
+        self.logger.write_block_stats(&self.block_stats.window_stats())
+            .map_err(|e| anyhow::anyhow!("Failed to write block statistics: {}", e))?;
+        Ok(())
+    }
+
+    async fn refresh_allowances(&self) {
+        let Some(cache) = self.trade_executor.simulator.allowance_cache() else {
+            return;
+        };
+        if !cache.is_stale() {
+            return;
+        }
+
+        let tokens: Vec<Address> = self.path_finder.source_tokens
+            .iter()
+            .map(|token| Address::from_slice(token.as_ref()))
+            .collect();
+
+        if let Err(e) = cache.refresh(&self.trade_executor.provider, &tokens).await {
+            tracing::warn!(
+                error = %e,
+                "Failed to refresh Permit2 allowances, approvals will be included"
+            );
+        }
+    }
+
     async fn handle_states(
         &mut self,
         states: &HashMap<String, Box<dyn ProtocolSim>>,
@@ -178,7 +1135,9 @@ impl Context {
         for (key, sim) in states {
             match Bytes::from_str(key) {
                 Ok(pool) => {
-                    write_guard.insert(pool.clone(), sim.clone());
+                    // Every update invalidates the cached evaluations, simulations and spot prices of the pool
+                    self.market_data.pool_versions.record_change(&pool);
+                    write_guard.insert(pool.clone(), Arc::from(sim.clone()));
                     updated_pools.push(pool);
                 }
                 Err(e) => {
@@ -205,7 +1164,6 @@ impl Context {
         }
 
         let mut guard_comp = self.market_data.protocol_comp.write().await;
-        let mut guard_graph = self.market_data.graph.write().await;
         let mut guard_paths = self.path_finder.paths.write().await;
 
         let mut new_node_idxs = Vec::new();
@@ -213,34 +1171,12 @@ impl Context {
 
         tracing::info!(new_pairs_count = new_pairs.len(), "Processing new pairs");
         
+        let mut components = Vec::with_capacity(new_pairs.len());
         for (key, comp) in new_pairs {
             match Bytes::from_str(key) {
                 Ok(pool_address) => {
-                    guard_comp.insert(pool_address.clone(), comp.clone());
-                    
-                    match guard_graph.add_protocol_component(pool_address.clone(), comp.clone()) {
-                        Ok(pool_infos) => {
-                            for pool_info in &pool_infos {
-                                new_node_idxs.extend(pool_info.token_ids);
-                                new_edge_idxs.extend(pool_info.pool_ids);
-                            }
-                            
-                            tracing::debug!(
-                                pool_address = %pool_address,
-                                protocol_system = %comp.protocol_system,
-                                token_count = comp.tokens.len(),
-                                pairs_created = pool_infos.len(),
-                                "New pair added successfully"
-                            );
-                        }
-                        Err(e) => {
-                            tracing::error!(
-                                pool_address = %pool_address,
-                                error = %e,
-                                "Failed to add protocol component to graph"
-                            );
-                        }
-                    }
+                    guard_comp.insert(pool_address.clone(), Arc::new(comp.clone()));
+                    components.push((pool_address, comp));
                 }
                 Err(e) => {
                     tracing::warn!(
@@ -252,6 +1188,30 @@ impl Context {
             }
         }
 
+        // The startup snapshot holds thousands of pairs, so they are added in bulk
+        self.market_data.graph.update(|graph| match graph.add_protocol_components(components) {
+            Ok((pool_infos, statistics)) => {
+                for pool_info in &pool_infos {
+                    new_node_idxs.extend(pool_info.token_ids);
+                    new_edge_idxs.extend(pool_info.pool_ids);
+                }
+                if statistics.rejected > 0 {
+                    tracing::warn!(
+                        rejected = statistics.rejected,
+                        components = statistics.components,
+                        "Some new pairs could not be added to the graph"
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::error!(
+                    error = %e,
+                    "Failed to add new pairs to graph"
+                );
+            }
+        });
+        let graph = self.market_data.graph.snapshot();
+
         // Sort and deduplicate indices
         new_node_idxs.sort_unstable();
         new_node_idxs.dedup();
@@ -262,12 +1222,24 @@ impl Context {
             let pool_paths_count_before = guard_paths.pool_paths.len();
             let token_paths_count_before = guard_paths.token_paths.len();
             
-            guard_paths.discover_paths(
-                &guard_graph,
+            // Discovering the paths of the startup snapshot takes a while, so report its progress
+            let mut monitor = DiscoveryMonitor::new().with_progress(|progress| {
+                tracing::info!(
+                    stage = ?progress.stage,
+                    token_paths_found = progress.token_paths_found,
+                    pool_paths_found = progress.pool_paths_found,
+                    frontier_size = progress.frontier_size,
+                    elapsed_ms = progress.elapsed.as_millis() as u64,
+                    "Discovering paths"
+                );
+            });
+            guard_paths.discover_paths_monitored(
+                &graph,
                 new_node_idxs[0],
                 new_node_idxs.len(),
                 new_edge_idxs[0],
                 new_edge_idxs.len(),
+                &mut monitor,
             );
             
             let pool_paths_count_after = guard_paths.pool_paths.len();
@@ -284,50 +1256,29 @@ impl Context {
             // Log newly created paths
             if new_paths_count > 0 {
                 for path_idx in pool_paths_count_before..pool_paths_count_after {
-                    // Extract pool addresses from the pool path
-                    if let Some(pool_path) = guard_paths.pool_paths.get(path_idx) {
-                        let mut pools = Vec::new();
-                        
-                        // Get pools from the pool path
-                        for &pool_idx in pool_path {
-                            if let Ok(pool) = guard_graph.get_pool(pool_idx) {
-                                pools.push(pool.address().clone());
-                            }
-                        }
-                        
-                        // Derive tokens from the pool sequence to ensure consistency
-                        let tokens = if let Ok(derived_tokens) = self.derive_tokens_from_pool_path(&guard_graph, pool_path) {
-                            derived_tokens
-                        } else {
-                            tracing::warn!(
-                                path_index = path_idx,
-                                pool_count = pools.len(),
-                                "Failed to derive tokens from pool path"
-                            );
-                            continue;
-                        };
-                        
-                        // Validate path consistency before logging
-                        if let Err(e) = tycho_atomic_arbitrage::path::PathValidator::validate_path_consistency(&pools, &tokens) {
+                    let discovered = match guard_paths.get_discovered_path(path_idx, &graph) {
+                        Ok(discovered) => discovered,
+                        Err(e) => {
                             tracing::warn!(
                                 path_index = path_idx,
-                                pool_count = pools.len(),
-                                token_count = tokens.len(),
                                 error = %e,
-                                "Skipping inconsistent path"
+                                "Failed to resolve newly created path"
                             );
                             continue;
                         }
+                    };
+                    let (Ok(pools), Ok(tokens)) = (discovered.pool_addresses(&graph), discovered.token_addresses(&graph)) else {
+                        continue;
+                    };
 
-                        if let Err(e) = self.logger.log_path(&pools, &tokens) {
-                            tracing::warn!(
-                                error = %e,
-                                path_index = path_idx,
-                                pool_count = pools.len(),
-                                token_count = tokens.len(),
-                                "Failed to log newly created path"
-                            );
-                        }
+                    if let Err(e) = self.logger.log_path(&pools, &tokens) {
+                        tracing::warn!(
+                            error = %e,
+                            path_index = path_idx,
+                            pool_count = pools.len(),
+                            token_count = tokens.len(),
+                            "Failed to log newly created path"
+                        );
                     }
                 }
                 
@@ -348,84 +1299,6 @@ impl Context {
         }
     }
 
-    /// Derive the token sequence from a pool path to ensure consistency.
-    ///
-    /// For an arbitrage path, we need to trace through the pools to determine
-    /// the correct sequence of tokens. This ensures that the logged tokens
-    /// match exactly with the pools used.
-    fn derive_tokens_from_pool_path(
-        &self,
-        graph: &TradingGraph,
-        pool_path: &[usize],
-    ) -> Result<Vec<Bytes>> {
-        if pool_path.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let mut tokens = Vec::new();
-        
-        // For the first pool, we need to determine which token is the input
-        // We'll use the first source token that appears in the first pool
-        let first_pool = graph.get_pool(pool_path[0])
-            .map_err(|e| anyhow::anyhow!("Failed to get first pool: {}", e))?;
-        
-        let first_pool_tokens = first_pool.tokens();
-        let mut current_token_idx = None;
-        
-        // Find a source token that's in the first pool
-        for source_token in &self.path_finder.source_tokens {
-            for token_idx in first_pool_tokens {
-                if let Ok(token) = graph.get_token(token_idx) {
-                    if token.address() == source_token {
-                        current_token_idx = Some(token_idx);
-                        tokens.push(source_token.clone());
-                        break;
-                    }
-                }
-            }
-            if current_token_idx.is_some() {
-                break;
-            }
-        }
-        
-        if current_token_idx.is_none() {
-            return Err(anyhow::anyhow!("No source token found in first pool").into());
-        }
-        
-        let mut current_token_idx = current_token_idx.unwrap();
-        
-        // Trace through each pool to find the output token
-        for &pool_idx in pool_path {
-            let pool = graph.get_pool(pool_idx)
-                .map_err(|e| anyhow::anyhow!("Failed to get pool {}: {}", pool_idx, e))?;
-            
-            let pool_tokens = pool.tokens();
-            
-            // Find the other token in this pool (the output token)
-            let mut next_token_idx = None;
-            for token_idx in pool_tokens {
-                if token_idx != current_token_idx {
-                    next_token_idx = Some(token_idx);
-                    break;
-                }
-            }
-            
-            if let Some(next_idx) = next_token_idx {
-                if let Ok(next_token) = graph.get_token(next_idx) {
-                    tokens.push(next_token.address().clone());
-                    current_token_idx = next_idx;
-                } else {
-                    return Err(anyhow::anyhow!("Failed to get token {}", next_idx).into());
-                }
-            } else {
-                return Err(anyhow::anyhow!("Could not find output token for pool {}", pool_idx).into());
-            }
-        }
-        
-        Ok(tokens)
-    }
-    
-
     async fn handle_removed_pairs(&mut self, removed_pairs: &HashMap<String, ProtocolComponent>) {
         if removed_pairs.is_empty() {
             return;
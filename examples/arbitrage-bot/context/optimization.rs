@@ -4,14 +4,18 @@
 //! to find profitable arbitrage opportunities.
 
 use num_bigint::BigUint;
-use num_traits::ToPrimitive;
-use rayon::prelude::*;
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::RwLock;
 use tycho_atomic_arbitrage::{
+    budget::SearchDeadline,
+    config::OptimizationTolerances,
     errors::Result,
-    graph::TradingGraph,
-    path::{Path, PathExt, PathRepository, PathOptimizer},
+    graph::SharedTradingGraph,
+    path::{
+        CooldownRegistry, MultiSourceSearch, Path, PathEvaluationCache, PathExt, PathId,
+        PathFilter, PathLimitCache, PathOptimizer, PathRanking, PathRepository, PoolVersions, ProtocolGasTable, SpotPriceCache, SpotPricePrefilter, Q96,
+    },
+    utils::convert,
 };
 use tycho_common::Bytes;
 use tycho_simulation::protocol::{models::ProtocolComponent, state::ProtocolSim};
@@ -27,21 +31,42 @@ fn optimizer_lower_bound() -> BigUint {
 pub async fn filter_and_optimize_paths(
     updated_pools: Vec<Bytes>,
     paths: &Arc<RwLock<PathRepository>>,
-    graph: &Arc<RwLock<TradingGraph>>,
-    protocol_sim: &Arc<RwLock<HashMap<Bytes, Box<dyn ProtocolSim>>>>,
-    protocol_comp: &Arc<RwLock<HashMap<Bytes, ProtocolComponent>>>,
+    graph: &Arc<SharedTradingGraph>,
+    protocol_sim: &Arc<RwLock<HashMap<Bytes, Arc<dyn ProtocolSim>>>>,
+    protocol_comp: &Arc<RwLock<HashMap<Bytes, Arc<ProtocolComponent>>>>,
     source_balances: &Arc<RwLock<HashMap<Bytes, BigUint>>>,
-    optimization_tolerances: &HashMap<Bytes, f64>,
+    balance_caps: &HashMap<Bytes, f64>,
+    reference_rates: &HashMap<Bytes, f64>,
+    optimization_tolerances: &OptimizationTolerances,
+    ranking: Option<&PathRanking>,
+    ranked_paths: usize,
+    cooldowns: Option<&CooldownRegistry>,
+    pool_versions: &PoolVersions,
+    evaluation_cache: &PathEvaluationCache,
+    spot_price_cache: &SpotPriceCache,
+    limit_cache: &PathLimitCache,
+    gas_pricing: Option<(&ProtocolGasTable, f64)>,
     min_profit_bps: u64,
     block_number: u64,
+    deadline: &SearchDeadline,
     logger: &PathLogger,
 ) -> Result<(Vec<PathExt>, usize, usize)> {
     tracing::debug!(
         updated_pools_count = updated_pools.len(),
+        remaining_budget_ms = deadline.remaining().as_millis(),
         "Starting path filtering and optimization"
     );
 
-    let mut paths = get_paths_of_pools(updated_pools, paths, graph, protocol_sim, protocol_comp).await?;
+    let mut paths = get_paths_of_pools(
+        updated_pools,
+        paths,
+        graph,
+        protocol_sim,
+        protocol_comp,
+        ranking.map(|ranking| (ranking, ranked_paths)),
+        block_number,
+        deadline,
+    ).await?;
     let initial_path_count = paths.len();
     
     tracing::debug!(
@@ -49,63 +74,81 @@ pub async fn filter_and_optimize_paths(
         "Retrieved paths from updated pools"
     );
 
+    // Skip routes whose recent executions kept failing
+    if let Some(cooldowns) = cooldowns {
+        paths.retain(|path| !cooldowns.is_cooling_down(path, block_number));
+
+        let (cooling_pools, cooling_paths) = cooldowns.cooling_down_count(block_number);
+        tracing::debug!(
+            skipped_paths = initial_path_count - paths.len(),
+            cooling_pools = cooling_pools,
+            cooling_paths = cooling_paths,
+            "Skipped paths cooling down after failed executions"
+        );
+    }
+
     // Filter paths by spot price product > threshold
-    let threshold = 1.0 + 0.01 * (min_profit_bps as f64 / 100.0);
-    paths.retain(|path| {
-        match path.spot_price_product() {
-            Ok(product) => product > threshold,
-            Err(e) => {
-                tracing::debug!(
-                    error = %e,
-                    "Failed to calculate spot price product, filtering out path"
-                );
-                false
-            }
-        }
-    });
-    
+    let prefilter = SpotPricePrefilter::from_min_profit_bps(min_profit_bps).with_cache(spot_price_cache, pool_versions);
+    prefilter.retain(&mut paths);
+
     let filtered_path_count = paths.len();
     
     tracing::info!(
         initial_paths = initial_path_count,
         filtered_paths = filtered_path_count,
         filtered_out = initial_path_count - filtered_path_count,
-        threshold = threshold,
+        threshold = prefilter.min_product,
         "Filtered paths by spot price product"
     );
 
-    let balances = source_balances.read().await;
+    let search = build_multi_source_search(
+        &*source_balances.read().await,
+        balance_caps,
+        reference_rates,
+        gas_pricing,
+    );
 
-    let path_exts: Vec<_> = paths
-        .par_iter()
-        .filter_map(|path| {
-            optimize_single_path(path, &balances, optimization_tolerances)
-        })
-        .filter(|path_ext| {
-            match path_ext.is_profitable() {
-                Ok(is_profitable) => {
-                    if !is_profitable {
-                        if let Ok(start_token) = path_ext.start_token() {
-                            tracing::debug!(
-                                start_token = %start_token,
-                                "Filtering out unprofitable path"
-                            );
-                        }
-                    }
-                    is_profitable
-                }
+    // Optimize all source tokens in one pass and rank opportunities globally
+    let path_exts: Vec<PathExt> = search
+        .search(&paths, |path, balance_cap| {
+            // Paths not reached before the deadline are dropped rather than optimized late
+            if deadline.is_expired() {
+                return Err(anyhow::anyhow!("Search deadline expired").into());
+            }
+            // Search no higher than the limits of the pools allow
+            let upper_bound = match limit_cache.max_amount_in(path, pool_versions) {
+                Ok(max_amount_in) => max_amount_in.min(balance_cap.clone()),
                 Err(e) => {
-                    tracing::debug!(
-                        error = %e,
-                        "Failed to check profitability, filtering out path"
-                    );
-                    false
+                    tracing::debug!(path_id = %PathId::of_path(path), error = %e, "Failed to compute the feasible input of path");
+                    balance_cap.clone()
                 }
-            }
+            };
+            // Paths whose pools did not change since their last optimization reuse its outcome
+            evaluation_cache.evaluate(path, pool_versions, &upper_bound, || {
+                optimize_single_path(path, &upper_bound, optimization_tolerances)
+            })
+        })
+        .into_iter()
+        .map(|opportunity| {
+            tracing::debug!(
+                start_token = %opportunity.source_token,
+                profit = %opportunity.profit,
+                reference_profit = opportunity.reference_profit,
+                "Selected multi-source opportunity"
+            );
+            opportunity.path
         })
         .collect();
 
     let profitable_path_count = path_exts.len();
+
+    if deadline.is_expired() {
+        tracing::warn!(
+            block_number = block_number,
+            elapsed_ms = deadline.elapsed().as_millis(),
+            "Search budget exhausted during path optimization"
+        );
+    }
     
     // Log filtered paths to CSV
     for path_ext in &path_exts {
@@ -124,6 +167,13 @@ pub async fn filter_and_optimize_paths(
         initial_paths = initial_path_count,
         filtered_paths = filtered_path_count,
         profitable_paths = profitable_path_count,
+        cached_evaluations = evaluation_cache.len(),
+        cache_hits = evaluation_cache.hits(),
+        cache_misses = evaluation_cache.misses(),
+        spot_price_cache_hits = spot_price_cache.hits(),
+        spot_price_cache_misses = spot_price_cache.misses(),
+        limit_cache_hits = limit_cache.hits(),
+        limit_cache_misses = limit_cache.misses(),
         optimization_success_rate = if filtered_path_count > 0 {
             format!("{:.1}%", (profitable_path_count as f64 / filtered_path_count as f64) * 100.0)
         } else {
@@ -135,18 +185,61 @@ pub async fn filter_and_optimize_paths(
     Ok((path_exts, initial_path_count, filtered_path_count))
 }
 
-/// Optimize a single path using the provided balances and tolerances.
+/// Build a multi-source search from wallet balances, cap percentages and reference rates.
+///
+/// Each source token may commit at most `balance_caps[token]` percent of its wallet
+/// balance (100% if not configured). Tokens without a reference rate to the native
+/// token are still searched but rank behind every token with a known rate. With a
+/// gas table and a gas price in wei, opportunities rank by their profit after the
+/// estimated gas of their swaps.
+fn build_multi_source_search(
+    balances: &HashMap<Bytes, BigUint>,
+    balance_caps: &HashMap<Bytes, f64>,
+    reference_rates: &HashMap<Bytes, f64>,
+    gas_pricing: Option<(&ProtocolGasTable, f64)>,
+) -> MultiSourceSearch {
+    let mut search = match gas_pricing {
+        Some((gas_table, gas_price)) => MultiSourceSearch::new().with_gas_price(gas_table.clone(), gas_price),
+        None => MultiSourceSearch::new(),
+    };
+
+    for (token, balance) in balances {
+        let cap_percentage = balance_caps.get(token).copied().unwrap_or(100.0).clamp(0.0, 100.0);
+        let balance_cap = apply_cap_percentage(balance, cap_percentage);
+        let reference_rate = reference_rates.get(token).copied().unwrap_or(0.0);
+
+        tracing::debug!(
+            token = %token,
+            balance = %balance,
+            balance_cap = %balance_cap,
+            reference_rate = reference_rate,
+            "Configured multi-source search token"
+        );
+
+        search.set_source(token.clone(), balance_cap, reference_rate);
+    }
+
+    search
+}
+
+/// Apply a percentage cap to a balance with basis-point precision.
+fn apply_cap_percentage(balance: &BigUint, cap_percentage: f64) -> BigUint {
+    let cap_bps = (cap_percentage * 100.0).round() as u64;
+    balance * BigUint::from(cap_bps) / BigUint::from(10_000u64)
+}
+
+/// Optimize a single path within the given upper bound using the configured tolerances.
 fn optimize_single_path(
     path: &Path,
-    balances: &HashMap<Bytes, BigUint>,
-    optimization_tolerances: &HashMap<Bytes, f64>,
-) -> Option<PathExt> {
-    let start_token = path.start_token().ok()?;
-    let upper_bound = balances.get(&start_token)?.clone();
-    let tolerance_percentage = *optimization_tolerances.get(&start_token)?;
+    upper_bound: &BigUint,
+    optimization_tolerances: &OptimizationTolerances,
+) -> Result<PathExt> {
+    let start_token = path.start_token()?;
+    let tolerance_percentage = optimization_tolerances.tolerance(&start_token);
     
-    // Calculate tolerance as absolute value
-    let tolerance_f64 = upper_bound.to_string().parse::<f64>().unwrap_or(0.0) * tolerance_percentage / 100.0;
+    // Tolerance as a fraction of the search range
+    let tolerance = Q96::from_f64(tolerance_percentage / 100.0)
+        .ok_or_else(|| anyhow::anyhow!("Invalid optimization tolerance {}%", tolerance_percentage))?;
 
     tracing::debug!(
         start_token = %start_token,
@@ -157,8 +250,8 @@ fn optimize_single_path(
 
     // Create optimizer with appropriate search range and tolerance
     let optimizer = TernarySearchOptimizer::new()
-        .with_search_range(optimizer_lower_bound(), upper_bound)
-        .with_tolerance(tolerance_f64.max(1.0)) // Ensure minimum tolerance of 1.0
+        .with_search_range(optimizer_lower_bound(), upper_bound.clone())
+        .with_tolerance(tolerance)
         .with_max_iterations(100);
     
     match optimizer.optimize_and_execute(path) {
@@ -169,9 +262,10 @@ fn optimize_single_path(
                 expected_profit = %optimization_result.expected_profit,
                 iterations = optimization_result.iterations,
                 converged = optimization_result.converged,
+                limited = optimization_result.limited,
                 "Path optimization completed"
             );
-            Some(path_ext)
+            Ok(path_ext)
         }
         Err(e) => {
             tracing::debug!(
@@ -179,7 +273,7 @@ fn optimize_single_path(
                 error = %e,
                 "Path optimization failed"
             );
-            None
+            Err(e)
         }
     }
 }
@@ -193,8 +287,8 @@ fn calculate_spot_price_product_from_path_ext(path_ext: &PathExt) -> f64 {
             Ok(price) => product *= price,
             Err(_) => {
                 // If we can't get spot price, estimate from amounts
-                let price_estimate = swap.amount_out.clone().to_f64().unwrap_or(1.0) 
-                    / swap.amount_in.clone().to_f64().unwrap_or(1.0);
+                let price_estimate = convert::biguint_to_f64(&swap.amount_out)
+                    / convert::biguint_to_f64(&swap.amount_in);
                 product *= price_estimate;
             }
         }
@@ -204,25 +298,38 @@ fn calculate_spot_price_product_from_path_ext(path_ext: &PathExt) -> f64 {
 }
 
 /// Get paths that involve the specified pools.
+///
+/// With a ranking, the top ranked paths are built first so they are evaluated
+/// even when the search budget runs out.
 async fn get_paths_of_pools(
     updated_pools: Vec<Bytes>,
     paths: &Arc<RwLock<PathRepository>>,
-    graph: &Arc<RwLock<TradingGraph>>,
-    protocol_sim: &Arc<RwLock<HashMap<Bytes, Box<dyn ProtocolSim>>>>,
-    protocol_comp: &Arc<RwLock<HashMap<Bytes, ProtocolComponent>>>,
+    graph: &Arc<SharedTradingGraph>,
+    protocol_sim: &Arc<RwLock<HashMap<Bytes, Arc<dyn ProtocolSim>>>>,
+    protocol_comp: &Arc<RwLock<HashMap<Bytes, Arc<ProtocolComponent>>>>,
+    ranking: Option<(&PathRanking, usize)>,
+    block_number: u64,
+    deadline: &SearchDeadline,
 ) -> Result<Vec<Path>> {
-    let graph_guard = graph.read().await;
+    let graph_guard = graph.snapshot();
     let paths_repo = paths.read().await;
     let protocol_sim_guard = protocol_sim.read().await;
     let protocol_comp_guard = protocol_comp.read().await;
     
-    let path_idxs = paths_repo.get_path_indices_for_pools(&updated_pools)?;
+    let mut path_idxs = paths_repo.get_path_indices_for_pools(&updated_pools)?;
+    if let Some((ranking, ranked_paths)) = ranking {
+        path_idxs = ranking.prioritize(path_idxs, ranked_paths, block_number, |&path_idx| {
+            let pool_path = paths_repo.get_pool_path_by_index(path_idx).ok()?;
+            PathId::from_pool_path(pool_path, &graph_guard).ok()
+        });
+    }
 
-    paths_repo.build_paths_from_indices(
+    paths_repo.build_paths_within_budget(
         path_idxs,
         &graph_guard,
         &protocol_sim_guard,
         &protocol_comp_guard,
+        deadline,
     )
 }
 
@@ -236,6 +343,14 @@ mod tests {
         assert_eq!(optimizer_lower_bound(), BigUint::from(1u32));
     }
 
+    #[test]
+    fn test_apply_cap_percentage() {
+        let balance = BigUint::from(10_000u32);
+        assert_eq!(apply_cap_percentage(&balance, 100.0), balance);
+        assert_eq!(apply_cap_percentage(&balance, 25.5), BigUint::from(2_550u32));
+        assert_eq!(apply_cap_percentage(&balance, 0.0), BigUint::from(0u32));
+    }
+
     #[test]
     fn test_threshold_calculation() {
         let min_profit_bps = 100u64;
@@ -10,23 +10,53 @@
 //! - **`GoldenSectionOptimizer`**: Uses golden section search for optimization
 //! - **`GridSearchOptimizer`**: Simple grid search for comparison and testing
 //!
+//! All optimizers search over `BigUint` amounts; tolerances are `Q96` fractions
+//! of the search range, so probes of 18-decimals amounts are never rounded.
+//!
 //! # Usage
 //!
 //! ```rust,no_run
-//! use tycho_atomic_arbitrage::path::optimization::PathOptimizer;
+//! use tycho_atomic_arbitrage::path::optimization::{PathOptimizer, Q96};
 //! use crate::optimizers::TernarySearchOptimizer;
 //! 
 //! let optimizer = TernarySearchOptimizer::new()
 //!     .with_max_iterations(100)
-//!     .with_tolerance(1e-6);
+//!     .with_tolerance(Q96::from_f64(0.001).unwrap());
 //! 
 //! let result = optimizer.find_optimal_amount(&path)?;
 //! ```
 
-use tycho_atomic_arbitrage::path::optimization::{PathOptimizer, OptimizationResult};
+use alloy::primitives::U256;
+use tycho_atomic_arbitrage::path::optimization::{PathOptimizer, OptimizationResult, Q96};
 use tycho_atomic_arbitrage::path::Path;
 use tycho_atomic_arbitrage::errors::{PathError, Result};
 use num_bigint::{BigInt, BigUint};
+use num_traits::Zero;
+
+/// Smallest search interval in base units; integer probes cannot narrow it further.
+const MIN_SEARCH_WIDTH: u32 = 2;
+
+/// Inverse golden ratio (√5 - 1) / 2 ≈ 0.618 in Q96.
+const INVERSE_GOLDEN_RATIO: Q96 = Q96::from_raw(U256::from_limbs([0x7f4a7c15f39cc060, 0x9e3779b9, 0, 0]));
+
+/// Default convergence tolerance, one millionth of the search range.
+fn default_tolerance() -> Q96 {
+    Q96::from_ratio(&BigUint::from(1u32), &BigUint::from(1_000_000u32)).unwrap_or_default()
+}
+
+/// Width of the interval between `lower` and `upper`, zero if it is empty.
+fn interval_width(lower: &BigUint, upper: &BigUint) -> BigUint {
+    if upper > lower {
+        upper - lower
+    } else {
+        BigUint::zero()
+    }
+}
+
+/// Absolute tolerance in base units for a relative tolerance of the search range.
+fn absolute_tolerance(tolerance: &Q96, range: &BigUint) -> BigUint {
+    tolerance.mul_floor(range).max(BigUint::from(MIN_SEARCH_WIDTH))
+}
 
 /// Ternary search-based path optimizer.
 ///
@@ -35,8 +65,8 @@ use num_bigint::{BigInt, BigUint};
 pub struct TernarySearchOptimizer {
     /// Maximum number of iterations
     max_iterations: usize,
-    /// Convergence tolerance
-    tolerance: f64,
+    /// Convergence tolerance as a fraction of the search range
+    tolerance: Q96,
     /// Minimum search amount
     min_amount: BigUint,
     /// Maximum search amount
@@ -48,7 +78,7 @@ impl TernarySearchOptimizer {
     pub fn new() -> Self {
         Self {
             max_iterations: 100,
-            tolerance: 1e-6,
+            tolerance: default_tolerance(),
             min_amount: BigUint::from(1u32),
             max_amount: BigUint::from(1_000_000_000u64), // 1B units
         }
@@ -60,8 +90,8 @@ impl TernarySearchOptimizer {
         self
     }
 
-    /// Set the convergence tolerance.
-    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+    /// Set the convergence tolerance as a fraction of the search range.
+    pub fn with_tolerance(mut self, tolerance: Q96) -> Self {
         self.tolerance = tolerance;
         self
     }
@@ -72,25 +102,6 @@ impl TernarySearchOptimizer {
         self.max_amount = max_amount;
         self
     }
-
-    /// Convert BigUint to f64 for calculations.
-    fn biguint_to_f64(&self, value: &BigUint) -> f64 {
-        value.to_string().parse().unwrap_or(0.0)
-    }
-
-    /// Convert f64 to BigUint for calculations.
-    fn f64_to_biguint(&self, value: f64) -> BigUint {
-        if value <= 0.0 {
-            BigUint::from(0u32)
-        } else {
-            BigUint::from(value as u64)
-        }
-    }
-
-    /// Evaluate the profit function at a given amount.
-    fn evaluate_profit(&self, path: &Path, amount: &BigUint) -> BigInt {
-        path.calculate_profit_loss(amount.clone()).unwrap_or(BigInt::from(0))
-    }
 }
 
 impl Default for TernarySearchOptimizer {
@@ -108,25 +119,27 @@ impl PathOptimizer for TernarySearchOptimizer {
         tracing::debug!(
             path_length = path.len(),
             max_iterations = self.max_iterations,
-            tolerance = self.tolerance,
+            tolerance = %self.tolerance,
             "Starting ternary search optimization"
         );
 
-        let mut left = self.biguint_to_f64(&self.min_amount);
-        let mut right = self.biguint_to_f64(&self.max_amount);
+        let mut left = self.min_amount.clone();
+        let mut right = self.max_amount.clone().max(left.clone());
+        let range = interval_width(&left, &right);
+        let tolerance = absolute_tolerance(&self.tolerance, &range);
         let mut iterations = 0;
         let mut best_amount = self.min_amount.clone();
         let mut best_profit = BigInt::from(0);
 
-        while iterations < self.max_iterations && (right - left) > self.tolerance {
-            let mid1 = left + (right - left) / 3.0;
-            let mid2 = right - (right - left) / 3.0;
+        while iterations < self.max_iterations && interval_width(&left, &right) > tolerance {
+            let third = interval_width(&left, &right) / 3u32;
+            let amount1 = &left + &third;
+            let amount2 = &right - &third;
 
-            let amount1 = self.f64_to_biguint(mid1);
-            let amount2 = self.f64_to_biguint(mid2);
-
-            let profit1 = self.evaluate_profit(path, &amount1);
-            let profit2 = self.evaluate_profit(path, &amount2);
+            // Both probes pass through every pool together
+            let mut profits = path.evaluate_amounts(&[amount1.clone(), amount2.clone()]).into_iter();
+            let profit1 = profits.next().unwrap_or_default();
+            let profit2 = profits.next().unwrap_or_default();
 
             // Update best result
             if profit1 > best_profit {
@@ -140,27 +153,28 @@ impl PathOptimizer for TernarySearchOptimizer {
 
             // Narrow search space
             if profit1 > profit2 {
-                right = mid2;
+                right = amount2.clone();
             } else {
-                left = mid1;
+                left = amount1.clone();
             }
 
             iterations += 1;
 
             tracing::trace!(
                 iteration = iterations,
-                left = left,
-                right = right,
-                mid1 = mid1,
-                mid2 = mid2,
+                left = %left,
+                right = %right,
+                mid1 = %amount1,
+                mid2 = %amount2,
                 profit1 = %profit1,
                 profit2 = %profit2,
                 "Ternary search iteration"
             );
         }
 
-        let converged = (right - left) <= self.tolerance;
-        let final_tolerance = right - left;
+        let width = interval_width(&left, &right);
+        let converged = width <= tolerance;
+        let final_tolerance = Q96::from_ratio(&width, &range).unwrap_or_default();
 
         let result = OptimizationResult::new(
             best_amount,
@@ -189,14 +203,12 @@ impl PathOptimizer for TernarySearchOptimizer {
 pub struct GoldenSectionOptimizer {
     /// Maximum number of iterations
     max_iterations: usize,
-    /// Convergence tolerance
-    tolerance: f64,
+    /// Convergence tolerance as a fraction of the search range
+    tolerance: Q96,
     /// Minimum search amount
     min_amount: BigUint,
     /// Maximum search amount
     max_amount: BigUint,
-    /// Golden ratio constant
-    golden_ratio: f64,
 }
 
 impl GoldenSectionOptimizer {
@@ -204,10 +216,9 @@ impl GoldenSectionOptimizer {
     pub fn new() -> Self {
         Self {
             max_iterations: 100,
-            tolerance: 1e-6,
+            tolerance: default_tolerance(),
             min_amount: BigUint::from(1u32),
             max_amount: BigUint::from(1_000_000_000u64),
-            golden_ratio: (1.0 + 5.0_f64.sqrt()) / 2.0, // φ ≈ 1.618
         }
     }
 
@@ -217,8 +228,8 @@ impl GoldenSectionOptimizer {
         self
     }
 
-    /// Set the convergence tolerance.
-    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+    /// Set the convergence tolerance as a fraction of the search range.
+    pub fn with_tolerance(mut self, tolerance: Q96) -> Self {
         self.tolerance = tolerance;
         self
     }
@@ -230,20 +241,6 @@ impl GoldenSectionOptimizer {
         self
     }
 
-    /// Convert BigUint to f64 for calculations.
-    fn biguint_to_f64(&self, value: &BigUint) -> f64 {
-        value.to_string().parse().unwrap_or(0.0)
-    }
-
-    /// Convert f64 to BigUint for calculations.
-    fn f64_to_biguint(&self, value: f64) -> BigUint {
-        if value <= 0.0 {
-            BigUint::from(0u32)
-        } else {
-            BigUint::from(value as u64)
-        }
-    }
-
     /// Evaluate the profit function at a given amount.
     fn evaluate_profit(&self, path: &Path, amount: &BigUint) -> BigInt {
         path.calculate_profit_loss(amount.clone()).unwrap_or(BigInt::from(0))
@@ -265,67 +262,67 @@ impl PathOptimizer for GoldenSectionOptimizer {
         tracing::debug!(
             path_length = path.len(),
             max_iterations = self.max_iterations,
-            tolerance = self.tolerance,
+            tolerance = %self.tolerance,
             "Starting golden section search optimization"
         );
 
-        let mut a = self.biguint_to_f64(&self.min_amount);
-        let mut b = self.biguint_to_f64(&self.max_amount);
+        let mut a = self.min_amount.clone();
+        let mut b = self.max_amount.clone().max(a.clone());
+        let range = interval_width(&a, &b);
+        let tolerance = absolute_tolerance(&self.tolerance, &range);
         let mut iterations = 0;
         let mut best_amount = self.min_amount.clone();
         let mut best_profit = BigInt::from(0);
 
         // Initial points
-        let mut c = b - (b - a) / self.golden_ratio;
-        let mut d = a + (b - a) / self.golden_ratio;
+        let mut c = &b - INVERSE_GOLDEN_RATIO.mul_floor(&range);
+        let mut d = &a + INVERSE_GOLDEN_RATIO.mul_floor(&range);
 
-        let mut fc = self.evaluate_profit(path, &self.f64_to_biguint(c));
-        let mut fd = self.evaluate_profit(path, &self.f64_to_biguint(d));
+        let mut fc = self.evaluate_profit(path, &c);
+        let mut fd = self.evaluate_profit(path, &d);
 
-        while iterations < self.max_iterations && (b - a).abs() > self.tolerance {
+        while iterations < self.max_iterations && interval_width(&a, &b) > tolerance {
             // Update best result
-            let amount_c = self.f64_to_biguint(c);
-            let amount_d = self.f64_to_biguint(d);
-
             if fc > best_profit {
                 best_profit = fc.clone();
-                best_amount = amount_c.clone();
+                best_amount = c.clone();
             }
             if fd > best_profit {
                 best_profit = fd.clone();
-                best_amount = amount_d.clone();
+                best_amount = d.clone();
             }
 
             if fc > fd {
                 b = d;
                 d = c;
                 fd = fc;
-                c = b - (b - a) / self.golden_ratio;
-                fc = self.evaluate_profit(path, &self.f64_to_biguint(c));
+                c = &b - INVERSE_GOLDEN_RATIO.mul_floor(&interval_width(&a, &b));
+                fc = self.evaluate_profit(path, &c);
             } else {
                 a = c;
                 c = d;
                 fc = fd;
-                d = a + (b - a) / self.golden_ratio;
-                fd = self.evaluate_profit(path, &self.f64_to_biguint(d));
+                d = &a + INVERSE_GOLDEN_RATIO.mul_floor(&interval_width(&a, &b));
+                fd = self.evaluate_profit(path, &d);
             }
 
             iterations += 1;
 
             tracing::trace!(
                 iteration = iterations,
-                a = a,
-                b = b,
-                c = c,
-                d = d,
+                a = %a,
+                b = %b,
+                c = %c,
+                d = %d,
                 fc = %fc,
                 fd = %fd,
                 "Golden section search iteration"
             );
         }
 
-        let converged = (b - a).abs() <= self.tolerance;
-        let final_tolerance = (b - a).abs();
+        let width = interval_width(&a, &b);
+        let converged = width <= tolerance;
+        let final_tolerance = Q96::from_ratio(&width, &range).unwrap_or_default();
 
         let result = OptimizationResult::new(
             best_amount,
@@ -376,20 +373,6 @@ impl GridSearchOptimizer {
         self.max_amount = max_amount;
         self
     }
-
-    /// Convert BigUint to f64 for calculations.
-    fn biguint_to_f64(&self, value: &BigUint) -> f64 {
-        value.to_string().parse().unwrap_or(0.0)
-    }
-
-    /// Convert f64 to BigUint for calculations.
-    fn f64_to_biguint(&self, value: f64) -> BigUint {
-        if value <= 0.0 {
-            BigUint::from(0u32)
-        } else {
-            BigUint::from(value as u64)
-        }
-    }
 }
 
 impl PathOptimizer for GridSearchOptimizer {
@@ -404,19 +387,19 @@ impl PathOptimizer for GridSearchOptimizer {
             "Starting grid search optimization"
         );
 
-        let min_f64 = self.biguint_to_f64(&self.min_amount);
-        let max_f64 = self.biguint_to_f64(&self.max_amount);
-        let step = (max_f64 - min_f64) / (self.grid_points - 1) as f64;
+        let range = interval_width(&self.min_amount, &self.max_amount);
+        let intervals = BigUint::from(self.grid_points.saturating_sub(1).max(1));
 
         let mut best_amount = self.min_amount.clone();
         let mut best_profit = BigInt::from(0);
 
-        for i in 0..self.grid_points {
-            let amount_f64 = min_f64 + i as f64 * step;
-            let amount = self.f64_to_biguint(amount_f64);
-            
-            let profit = path.calculate_profit_loss(amount.clone()).unwrap_or(BigInt::from(0));
-            
+        // Exact fractions i / (n - 1) of the range, so the last point is the maximum
+        let amounts: Vec<BigUint> = (0..self.grid_points)
+            .map(|i| &self.min_amount + &range * BigUint::from(i) / &intervals)
+            .collect();
+        let profits = path.evaluate_amounts(&amounts);
+
+        for (amount, profit) in amounts.into_iter().zip(profits) {
             if profit > best_profit {
                 best_profit = profit;
                 best_amount = amount;
@@ -428,7 +411,7 @@ impl PathOptimizer for GridSearchOptimizer {
             best_profit,
             self.grid_points,
             true, // Grid search always "converges"
-            0.0,
+            Q96::ZERO,
         );
 
         tracing::debug!(
@@ -446,7 +429,9 @@ impl PathOptimizer for GridSearchOptimizer {
 mod tests {
     use super::*;
     use tycho_atomic_arbitrage::path::{Path, Swap};
+    use tycho_atomic_arbitrage::utils::convert::{self, Rounding};
     use std::collections::HashMap;
+    use std::sync::Arc;
     use tycho_common::Bytes;
     use tycho_simulation::protocol::models::ProtocolComponent;
     use tycho_simulation::protocol::state::ProtocolSim;
@@ -487,7 +472,7 @@ mod tests {
             _token_in: &tycho_simulation::models::Token,
             _token_out: &tycho_simulation::models::Token,
         ) -> std::result::Result<tycho_simulation::protocol::models::GetAmountOutResult, tycho_simulation::protocol::errors::SimulationError> {
-            let amount_f64 = amount_in.to_string().parse::<f64>().unwrap_or(0.0);
+            let amount_f64 = convert::biguint_to_f64(&amount_in);
             
             // Simple quadratic function with maximum at optimal_amount
             if amount_f64 <= 0.0 {
@@ -505,7 +490,7 @@ mod tests {
                 0.9 // Diminishing returns for very large amounts
             };
             
-            let amount_out = BigUint::from((amount_f64 * multiplier).max(0.0) as u64);
+            let amount_out = convert::f64_to_biguint_saturating(amount_f64 * multiplier, Rounding::Floor);
 
             Ok(tycho_simulation::protocol::models::GetAmountOutResult {
                 amount: amount_out,
@@ -577,11 +562,7 @@ mod tests {
             creation_tx: tycho_common::Bytes::default(),
         };
 
-        let swap = Swap {
-            pool_comp,
-            pool_sim: Box::new(MockProtocolSim::new(1.0)),
-            zero_for_one: true,
-        };
+        let swap = Swap::new(pool_comp, Arc::new(MockProtocolSim::new(1.0)), &token_a, &token_b).unwrap();
 
         Path(vec![swap])
     }
@@ -591,7 +572,7 @@ mod tests {
         let path = create_mock_path();
         let optimizer = TernarySearchOptimizer::new()
             .with_max_iterations(50)
-            .with_tolerance(1.0);
+            .with_tolerance(Q96::from_f64(1e-6).unwrap());
 
         let result = optimizer.find_optimal_amount(&path);
         assert!(result.is_ok());
@@ -606,7 +587,7 @@ mod tests {
         let path = create_mock_path();
         let optimizer = GoldenSectionOptimizer::new()
             .with_max_iterations(50)
-            .with_tolerance(1.0);
+            .with_tolerance(Q96::from_f64(1e-6).unwrap());
 
         let result = optimizer.find_optimal_amount(&path);
         assert!(result.is_ok());
@@ -641,6 +622,31 @@ mod tests {
         assert_eq!(path_ext.len(), 1);
     }
 
+    #[test]
+    fn test_search_probes_exact_large_amounts() {
+        // 10^24 + 1 base units cannot be represented as an f64
+        let min_amount = BigUint::from(10u32).pow(24) + 1u32;
+        let max_amount = &min_amount * 3u32;
+
+        let optimizer = GridSearchOptimizer::new(3).with_search_range(min_amount.clone(), max_amount.clone());
+        let result = optimizer.find_optimal_amount(&create_mock_path()).unwrap();
+        assert_eq!(result.final_tolerance, Q96::ZERO);
+
+        // Probes of an exhausted interval must stay within the range
+        let optimizer = TernarySearchOptimizer::new()
+            .with_search_range(min_amount.clone(), max_amount.clone())
+            .with_tolerance(Q96::ONE);
+        let result = optimizer.find_optimal_amount(&create_mock_path()).unwrap();
+        assert!(result.converged);
+        assert_eq!(result.iterations, 0);
+
+        assert_eq!(
+            absolute_tolerance(&Q96::from_f64(0.5).unwrap(), &(&max_amount - &min_amount)),
+            min_amount
+        );
+        assert!((INVERSE_GOLDEN_RATIO.to_f64() - (5f64.sqrt() - 1.0) / 2.0).abs() < 1e-15);
+    }
+
     #[test]
     fn test_empty_path_optimization() {
         let path = Path(vec![]);
@@ -1,31 +1,76 @@
 //! Simulation management for arbitrage operations.
 //!
-//! This module handles transaction simulation and execution for profitable paths.
+//! This module handles transaction simulation for profitable paths and turns
+//! profitable simulations into portfolio candidates.
 
 use alloy::{
     network::Ethereum,
     primitives::{Address, U256},
     providers::{Provider, RootProvider},
+    rpc::types::TransactionRequest,
     signers::local::PrivateKeySigner,
 };
 use anyhow::Result;
 use futures::stream::{self, Stream, StreamExt};
 use num_bigint::BigUint;
-use std::{collections::HashMap, sync::Arc};
+use num_traits::ToPrimitive;
+use std::{collections::HashMap, sync::Arc, time::{Duration, Instant}};
 use tokio::sync::RwLock;
 use tycho_atomic_arbitrage::{
-    bundle::TxExecutor,
-    graph::TradingGraph,
-    path::PathExt,
-    simulation::{LogParser, SimulationResult, Simulator},
-    utils::{biguint_to_u256, u256_to_biguint},
+    budget::SearchDeadline,
+    bundle::BundleLeg,
+    errors::OpportunityId,
+    graph::SharedTradingGraph,
+    path::{PathExt, PortfolioCandidate, ProfitCalculator},
+    rpc::RateLimiter,
+    simulation::{LogParser, SimulationOptions, SimulationResult, Simulator},
+    utils::{biguint_to_u256, convert, fees::BaseFeePredictor, l2_fees::L2FeeModel, u256_to_biguint},
 };
 use tycho_common::Bytes;
 use tycho_simulation::protocol::{models::ProtocolComponent, state::ProtocolSim};
 
 use super::logging::PathLogger;
 
+/// A simulated opportunity that is profitable after gas costs.
+#[derive(Debug, Clone)]
+pub struct ProfitableOpportunity {
+    /// The deterministic id of the opportunity, submitted at most once
+    pub opportunity_id: OpportunityId,
+    /// The simulated path
+    pub path: PathExt,
+    /// Transaction requests from the simulation, in execution order
+    pub tx_requests: Vec<TransactionRequest>,
+    /// Expected profit after gas costs, in native token
+    pub net_profit: BigUint,
+}
+
+impl ProfitableOpportunity {
+    /// Convert the opportunity into a bundle leg for execution.
+    pub fn into_bundle_leg(self) -> Result<BundleLeg> {
+        let profit_after_gas = biguint_to_u256(&self.net_profit)
+            .map_err(|e| anyhow::anyhow!("Failed to convert net profit to U256: {}", e))?;
+
+        Ok(BundleLeg::new(self.tx_requests, profit_after_gas)
+            .with_path(self.path)
+            .with_opportunity_id(self.opportunity_id))
+    }
+}
+
+impl PortfolioCandidate for ProfitableOpportunity {
+    fn path(&self) -> &PathExt {
+        &self.path
+    }
+
+    fn score(&self) -> f64 {
+        self.net_profit.to_f64().unwrap_or(0.0)
+    }
+}
+
 /// Run simulations for a collection of profitable paths.
+///
+/// At most `deadline.max_simulations()` paths are simulated, in the given order,
+/// and every simulation is aborted once the search deadline expires. Each
+/// result is yielded with the time its simulation took.
 pub async fn run_simulations<'a>(
     paths: Vec<PathExt>,
     nonce: u64,
@@ -33,23 +78,82 @@ pub async fn run_simulations<'a>(
     provider: &'a Arc<RootProvider<Ethereum>>,
     simulator: &'a Arc<Simulator>,
     signer: &'a PrivateKeySigner,
-) -> impl Stream<Item = (PathExt, tycho_atomic_arbitrage::Result<SimulationResult>)> + 'a {
+    deadline: SearchDeadline,
+) -> impl Stream<Item = (PathExt, tycho_atomic_arbitrage::Result<SimulationResult>, Duration)> + 'a {
     const SIMULATION_BUFFER_SIZE: usize = 10;
 
     stream::iter(paths)
+        .take(deadline.max_simulations())
         .map(move |path| {
             let simulator = Arc::clone(simulator);
             let provider = Arc::clone(provider);
             async move {
+                let started_at = Instant::now();
                 let sim_result = simulator
-                    .run_simulation(&provider, &path, nonce, base_fee, signer)
+                    .run_simulation_within_budget(&provider, &path, nonce, base_fee, signer, &SimulationOptions::default(), &deadline)
                     .await;
-                (path, sim_result)
+                (path, sim_result, started_at.elapsed())
             }
         })
         .buffer_unordered(SIMULATION_BUFFER_SIZE)
 }
 
+/// Drop paths whose expected profit does not cover their estimated gas cost.
+///
+/// The gas of the swaps is extended with the base cost and calldata of the
+/// router call, which the optimizer's profit ignores and which grows with
+/// every hop. Profits are valued with the native reference rates of their
+/// start token; paths that cannot be valued or encoded are kept and left to
+/// the simulation.
+pub fn retain_profitable_after_gas(
+    paths: Vec<PathExt>,
+    base_fee: U256,
+    reference_rates: &HashMap<Bytes, f64>,
+    simulator: &Simulator,
+    signer: &PrivateKeySigner,
+) -> Vec<PathExt> {
+    let gas_price = u256_to_biguint(base_fee);
+
+    paths
+        .into_iter()
+        .filter(|path| {
+            let (Ok(start_token), Ok(profit)) = (path.start_token(), path.profit()) else {
+                return true;
+            };
+            let Some(rate) = reference_rates.get(&start_token) else {
+                return true;
+            };
+            let calldata = match simulator.estimate_calldata_cost(path, signer) {
+                Ok(calldata) => calldata,
+                Err(e) => {
+                    tracing::debug!(
+                        error = %e,
+                        start_token = %start_token,
+                        "Failed to estimate calldata cost, leaving the path to the simulation"
+                    );
+                    return true;
+                }
+            };
+
+            let gas_cost = ProfitCalculator::estimate_gas_cost(path, &calldata, &gas_price);
+            let profit_in_native = convert::bigint_to_f64(&profit) * rate;
+            let profitable = profit_in_native > convert::biguint_to_f64(&gas_cost);
+            if !profitable {
+                tracing::debug!(
+                    path_length = path.len(),
+                    start_token = %start_token,
+                    profit_in_native = profit_in_native,
+                    gas_cost = %gas_cost,
+                    calldata_bytes = calldata.len(),
+                    calldata_gas = calldata.gas(),
+                    "Expected profit does not cover the estimated gas cost, skipping simulation"
+                );
+            }
+            profitable
+        })
+        .collect()
+}
+
 /// Convert a token amount to native token using the best available exchange rate.
 ///
 /// This function finds the best exchange rate between the given token and the native token
@@ -72,9 +176,9 @@ async fn swap_to_native(
     token: &Bytes,
     amount: BigUint,
     native_token: &Bytes,
-    graph: &Arc<RwLock<TradingGraph>>,
-    protocol_sim: &Arc<RwLock<HashMap<Bytes, Box<dyn ProtocolSim>>>>,
-    protocol_comp: &Arc<RwLock<HashMap<Bytes, ProtocolComponent>>>,
+    graph: &Arc<SharedTradingGraph>,
+    protocol_sim: &Arc<RwLock<HashMap<Bytes, Arc<dyn ProtocolSim>>>>,
+    protocol_comp: &Arc<RwLock<HashMap<Bytes, Arc<ProtocolComponent>>>>,
 ) -> Result<Option<BigUint>> {
     // Quick check: if token is already native token, no conversion needed
     if token == native_token {
@@ -82,7 +186,7 @@ async fn swap_to_native(
     }
 
     // Acquire all read locks once at the beginning to minimize lock contention
-    let graph_guard = graph.read().await;
+    let graph_guard = graph.snapshot();
     let protocol_sims_guard = protocol_sim.read().await;
     let protocol_comp_guard = protocol_comp.read().await;
 
@@ -110,17 +214,15 @@ async fn swap_to_native(
     };
 
     // Early exit if no pools exist between tokens
-    let pools = match graph_guard.pools_between_tokens([token_idx, native_idx]) {
-        Ok(pools) => pools,
-        Err(_) => {
-            tracing::info!(
-                token = %token,
-                native_token = %native_token,
-                "No direct pools between token and native token, skipping conversion"
-            );
-            return Ok(None);
-        }
-    };
+    let pools = graph_guard.pools_from_to(token_idx, native_idx);
+    if pools.is_empty() {
+        tracing::info!(
+            token = %token,
+            native_token = %native_token,
+            "No direct pools between token and native token, skipping conversion"
+        );
+        return Ok(None);
+    }
 
     let mut best_rate = BigUint::from(0u32);
 
@@ -196,20 +298,113 @@ async fn swap_to_native(
     }
 }
 
-/// Process a successful simulation result and potentially execute the trade.
+/// Estimate the value of each source token in units of the native token.
+///
+/// Rates are derived by quoting a small probe amount (0.1% of the balance) through
+/// the best direct pool to the native token. Tokens without a direct pool are
+/// omitted from the result.
+pub async fn native_reference_rates(
+    balances: &HashMap<Bytes, BigUint>,
+    native_token: &Bytes,
+    graph: &Arc<SharedTradingGraph>,
+    protocol_sim: &Arc<RwLock<HashMap<Bytes, Arc<dyn ProtocolSim>>>>,
+    protocol_comp: &Arc<RwLock<HashMap<Bytes, Arc<ProtocolComponent>>>>,
+) -> HashMap<Bytes, f64> {
+    let mut rates = HashMap::new();
+
+    for (token, balance) in balances {
+        if token == native_token {
+            rates.insert(token.clone(), 1.0);
+            continue;
+        }
+
+        let probe_amount = (balance / BigUint::from(1000u32)).max(BigUint::from(1u32));
+        match swap_to_native(token, probe_amount.clone(), native_token, graph, protocol_sim, protocol_comp).await {
+            Ok(Some(native_amount)) => {
+                let rate = native_amount.to_f64().unwrap_or(0.0) / probe_amount.to_f64().unwrap_or(1.0);
+                rates.insert(token.clone(), rate);
+            }
+            Ok(None) => {
+                tracing::debug!(
+                    token = %token,
+                    "No native reference rate available for source token"
+                );
+            }
+            Err(e) => {
+                tracing::warn!(
+                    token = %token,
+                    error = %e,
+                    "Failed to compute native reference rate"
+                );
+            }
+        }
+    }
+
+    rates
+}
+
+/// Process a successful simulation result.
+///
+/// Returns the opportunity if its profit after gas exceeds `min_profit_gas_multiple`
+/// times the simulated gas cost, so it can be considered for the block's
+/// portfolio, or `None` otherwise.
 pub async fn process_simulation_result(
     sim_result: SimulationResult,
     path: PathExt,
     block_number: u64,
     base_fee: U256,
-    executor: &Arc<TxExecutor>,
     native_token: &Bytes,
-    graph: &Arc<RwLock<TradingGraph>>,
-    protocol_sim: &Arc<RwLock<HashMap<Bytes, Box<dyn ProtocolSim>>>>,
-    protocol_comp: &Arc<RwLock<HashMap<Bytes, ProtocolComponent>>>,
+    max_output_shortfall_bps: f64,
+    min_profit_gas_multiple: f64,
+    l2_fees: Option<&L2FeeModel>,
+    graph: &Arc<SharedTradingGraph>,
+    protocol_sim: &Arc<RwLock<HashMap<Bytes, Arc<dyn ProtocolSim>>>>,
+    protocol_comp: &Arc<RwLock<HashMap<Bytes, Arc<ProtocolComponent>>>>,
     logger: &PathLogger,
-) -> Result<bool> {
-    let decoded_logs = LogParser::parse_simulation_results(sim_result.simulated_blocks)
+) -> Result<Option<ProfitableOpportunity>> {
+    // The call tree names the pool or token a failed or short swap stopped at
+    if let Some(call_trace) = &sim_result.call_trace {
+        let failure = call_trace.deepest_failure();
+        tracing::warn!(
+            path_length = path.len(),
+            call_count = call_trace.call_count(),
+            failed_call_to = ?failure.and_then(|failure| failure.to),
+            failed_call_error = ?failure.and_then(|failure| failure.error.as_deref()),
+            revert_reason = ?failure.and_then(|failure| failure.revert_reason.as_deref()),
+            "Anomalous simulation traced"
+        );
+        if let Ok(trace) = serde_json::to_string(call_trace) {
+            tracing::debug!(call_trace = %trace, "Call tree of anomalous simulation");
+        }
+    }
+
+    // Check the output actually received before trusting the decoded swap amounts
+    if let Some(expected_amount_out) = path.last().map(|swap| &swap.amount_out) {
+        let verification = sim_result.verify_amount_out(expected_amount_out);
+        tracing::debug!(
+            expected_amount_out = %verification.expected_amount_out,
+            realized_amount_out = %verification.realized_amount_out,
+            divergence_bps = verification.divergence_bps,
+            "Verified realized output against transfer logs"
+        );
+
+        if !verification.within_tolerance(max_output_shortfall_bps) {
+            tracing::warn!(
+                expected_amount_out = %verification.expected_amount_out,
+                realized_amount_out = %verification.realized_amount_out,
+                shortfall = %verification.shortfall(),
+                divergence_bps = verification.divergence_bps,
+                max_output_shortfall_bps = max_output_shortfall_bps,
+                "Realized output falls short of the expected output, discarding opportunity"
+            );
+            return Ok(None);
+        }
+    }
+
+    let tx_requests = sim_result.transaction_requests();
+    let approval_index = sim_result.approval_call_index();
+    let swap_index = sim_result.swap_call_index();
+    let decoded_logs = LogParser::parse_simulation_results_at(sim_result.simulated_blocks, approval_index, swap_index)
         .map_err(|e| anyhow::anyhow!("Failed to parse simulation logs: {}", e))?;
 
     let gross_profit = decoded_logs.profit()
@@ -228,13 +423,23 @@ pub async fn process_simulation_result(
         .map(|swap| &swap.amount_out)
         .unwrap_or(&default_amount);
 
-    let total_gas_used = decoded_logs.approval_gas + decoded_logs.swap_gas;
+    let total_gas_used = decoded_logs.total_gas();
 
     let gross_profit_biguint = gross_profit
         .to_biguint()
         .ok_or_else(|| anyhow::anyhow!("Gross profit less than zero"))?;
 
-    let gas_cost = decoded_logs.gas_cost(u256_to_biguint(base_fee));
+    let mut gas_cost = decoded_logs.gas_cost(u256_to_biguint(base_fee));
+    if let Some(l2_fees) = l2_fees {
+        let l1_data_fee = l2_fees.l1_data_fee_of_requests(&tx_requests).await
+            .map_err(|e| anyhow::anyhow!("Failed to estimate L1 data fee: {}", e))?;
+        tracing::debug!(
+            l2_gas_cost = %gas_cost,
+            l1_data_fee = %l1_data_fee,
+            "Added L1 data fee to gas cost"
+        );
+        gas_cost += u256_to_biguint(l1_data_fee);
+    }
     
     tracing::debug!(
         path_length = path.len(),
@@ -259,7 +464,7 @@ pub async fn process_simulation_result(
                 native_token = %native_token,
                 "No conversion path available, skipping trade execution"
             );
-            return Ok(false);
+            return Ok(None);
         }
         Err(e) => {
             tracing::error!(
@@ -289,69 +494,71 @@ pub async fn process_simulation_result(
         );
     }
 
-    let is_profitable = gross_profit_in_native > gas_cost;
-
-    if is_profitable {
-        // Check if this is Ethereum by comparing native token to Ethereum WETH address
-        let ethereum_weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
-        if native_token.to_string().to_lowercase() == ethereum_weth.to_lowercase() {
-            let net_profit = gross_profit_in_native.clone() - gas_cost.clone();
-            let tx_requests = vec![sim_result.approval_request, sim_result.swap_request];
-
-            tracing::info!(
-                gross_profit = %gross_profit_in_native,
-                gas_cost = %gas_cost,
-                net_profit = %net_profit,
-                "Executing profitable bundle"
-            );
-
-            let result = executor
-                .execute(
-                    tx_requests,
-                    block_number + 1,
-                    base_fee,
-                    biguint_to_u256(&net_profit)
-                        .map_err(|e| anyhow::anyhow!("Failed to convert net profit to U256: {}", e))?,
-                )
-                .await;
-
-            match result {
-                Ok(submissions) => {
-                    let successful_count = submissions.iter().filter(|s| s.is_successful()).count();
-                    tracing::info!(
-                        successful_submissions = successful_count,
-                        total_submissions = submissions.len(),
-                        "Bundle execution completed"
-                    );
-                }
-                Err(e) => {
-                    tracing::error!(
-                        error = %e,
-                        "Bundle execution failed"
-                    );
-                }
-            }
-        }
-    } else {
+    if !ProfitCalculator::exceeds_gas_multiple(&gross_profit_in_native, &gas_cost, min_profit_gas_multiple) {
         tracing::info!(
             gross_profit = %gross_profit_in_native,
             gas_cost = %gas_cost,
+            min_profit_gas_multiple = min_profit_gas_multiple,
             start_token = %start_token,
-            "Arbitrage not profitable after gas costs"
+            "Arbitrage not profitable enough after gas costs"
         );
+        return Ok(None);
     }
 
-    Ok(is_profitable)
+    let net_profit = gross_profit_in_native.clone() - gas_cost.clone();
+
+    let opportunity_id = OpportunityId::of_path_ext(&path, block_number);
+    tracing::info!(
+        opportunity_id = %opportunity_id,
+        gross_profit = %gross_profit_in_native,
+        gas_cost = %gas_cost,
+        net_profit = %net_profit,
+        "Found profitable opportunity"
+    );
+
+    Ok(Some(ProfitableOpportunity {
+        opportunity_id,
+        path,
+        tx_requests,
+        net_profit,
+    }))
 }
 
 /// Get the current nonce and calculate the next base fee.
+///
+/// Both are read at `block_number` when simulations are pinned to a historical
+/// block, and at the latest block otherwise. The block header is recorded by
+/// the base fee predictor, which prices bundles for later target blocks. On
+/// chains without a base fee, the gas price quoted by the node is returned
+/// instead. All queries wait for the rate limiter, if set.
 pub async fn get_nonce_and_base_fee(
     provider: &Arc<RootProvider<Ethereum>>,
     signer_address: Address,
+    block_number: Option<u64>,
+    base_fee_predictor: &BaseFeePredictor,
+    rate_limiter: Option<&RateLimiter>,
 ) -> Result<(u64, U256)> {
-    let nonce_future = provider.get_transaction_count(signer_address);
-    let block_future = provider
-        .get_block_by_number(alloy::rpc::types::BlockNumberOrTag::Latest);
+    let block_tag = block_number.map_or(
+        alloy::rpc::types::BlockNumberOrTag::Latest,
+        alloy::rpc::types::BlockNumberOrTag::Number,
+    );
+    let permit = move || async move {
+        match rate_limiter {
+            Some(limiter) => Some(limiter.acquire().await),
+            None => None,
+        }
+    };
+    let nonce_future = async {
+        let _permit = permit().await;
+        provider
+            .get_transaction_count(signer_address)
+            .block_id(block_tag.into())
+            .await
+    };
+    let block_future = async {
+        let _permit = permit().await;
+        provider.get_block_by_number(block_tag).await
+    };
 
     let (nonce, block_res) = tokio::try_join!(nonce_future, block_future)
         .map_err(|e| anyhow::anyhow!("Failed to fetch nonce and block: {}", e))?;
@@ -359,15 +566,18 @@ pub async fn get_nonce_and_base_fee(
     let block = block_res
         .ok_or_else(|| anyhow::anyhow!("Failed to get latest block"))?;
 
-    let current_base_fee_per_gas = block.header.base_fee_per_gas.unwrap_or_default();
-    let current_gas_used = block.header.gas_used;
-    let current_gas_limit = block.header.gas_limit;
-
-    let next_base_fee = tycho_atomic_arbitrage::utils::calculate_next_base_fee(
-        current_base_fee_per_gas.into(),
-        current_gas_used.into(),
-        current_gas_limit.into(),
-    );
+    // Chains without a base fee price transactions with the gas price quoted by the node
+    let Some(current_base_fee_per_gas) = block.header.base_fee_per_gas else {
+        let _permit = permit().await;
+        let gas_price = provider
+            .get_gas_price()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch gas price: {}", e))?;
+        tracing::debug!(nonce = nonce, gas_price = gas_price, "Fetched nonce and gas price");
+        return Ok((nonce, U256::from(gas_price)));
+    };
+    base_fee_predictor.record_header(&block.header);
+    let next_base_fee = base_fee_predictor.predict_next().unwrap_or_default();
 
     tracing::debug!(
         nonce = nonce,
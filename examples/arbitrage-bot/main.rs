@@ -1,7 +1,9 @@
+pub mod backtest;
 pub mod cli;
 pub mod context;
 pub mod stream;
 
+use tokio::time::Instant;
 use tycho_atomic_arbitrage::errors::Result;
 
 #[tokio::main]
@@ -20,19 +22,60 @@ async fn main() -> Result<()> {
         .init();
 
     let args = cli::parse_cli_args()?;
+    if args.backtest_file.is_some() {
+        return backtest::run(args).await;
+    }
+
     let mut stream = stream::TychoStream::new(&args).await?;
     let mut ctx = context::Context::new(args)?;
+    ctx.bootstrap().await?;
+
+    #[cfg(feature = "status-server")]
+    let _status_server = ctx.start_status_server().await?;
 
     tracing::info!("Starting atomic arbitrage bot");
 
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+
+    // Pools of the last update, searched again late in the slot so updated bundles replace the early ones
+    let mut resubmission = None;
+
     loop {
-        match stream.next().await {
-            Some(block_update) => {
+        let resubmit_at = resubmission.as_ref().map(|(deadline, _)| *deadline);
+        let block_update = tokio::select! {
+            block_update = stream.next() => block_update,
+            _ = tokio::time::sleep_until(resubmit_at.unwrap_or_else(Instant::now)), if resubmit_at.is_some() => {
+                if let Some((_, updated_pools)) = resubmission.take() {
+                    if let Err(e) = ctx.search(updated_pools).await {
+                        tracing::error!(error = %e, "Resubmission search failed");
+                    }
+                }
+                continue;
+            }
+            _ = &mut shutdown => {
+                tracing::info!("Shutdown requested, writing reports");
+                break;
+            }
+        };
+
+        match block_update {
+            Some(mut block_update) => {
+                if stream.is_snapshot() {
+                    ctx.resynchronize(&mut block_update).await;
+                }
+
                 match ctx.apply(block_update).await {
+                    Ok(_) if !stream.is_synced() => {
+                        tracing::info!("Stream resynchronizing, skipping search");
+                    }
                     Ok(updated_pools) => {
-                        if let Err(e) = ctx.search(updated_pools).await {
+                        if let Err(e) = ctx.search(updated_pools.clone()).await {
                             tracing::error!(error = %e, "Search operation failed");
                         }
+                        resubmission = ctx
+                            .resubmission_delay()
+                            .map(|delay| (Instant::now() + delay, updated_pools));
                     }
                     Err(e) => {
                         tracing::error!(error = %e, "Failed to apply block update");
@@ -45,4 +88,7 @@ async fn main() -> Result<()> {
             }
         }
     }
+
+    ctx.write_reports()?;
+    Ok(())
 }
@@ -0,0 +1,405 @@
+pub mod recorder;
+pub mod replayer;
+
+use crate::cli::Args;
+use futures::{Stream, StreamExt};
+use recorder::Recorder;
+use std::path::Path;
+use std::pin::Pin;
+use std::time::Duration;
+use tycho_atomic_arbitrage::config::{ExchangeConfig, ExchangeFilter};
+use tycho_atomic_arbitrage::errors::Result;
+use tycho_atomic_arbitrage::sync::{StreamSync, SyncAction};
+use tycho_simulation::utils::load_all_tokens;
+use tycho_simulation::evm::decoder::{StreamDecodeError, TychoStreamDecoder};
+use tycho_simulation::evm::engine_db::tycho_db::PreCachedDB;
+use tycho_simulation::evm::stream::ProtocolStreamBuilder;
+use tycho_simulation::evm::tycho_models::Chain;
+use tycho_simulation::protocol::models::BlockUpdate;
+use tycho_simulation::tycho_client::feed::component_tracker::ComponentFilter;
+use tycho_simulation::tycho_client::stream::TychoStreamBuilder;
+use tycho_simulation::evm::protocol::{
+    filters::{
+        balancer_pool_filter as BalancerPF, curve_pool_filter as CurvePF,
+        uniswap_v4_pool_with_hook_filter as UniV4PF,
+    },
+    pancakeswap_v2::state::PancakeswapV2State,
+    uniswap_v2::state::UniswapV2State,
+    uniswap_v3::state::UniswapV3State,
+    uniswap_v4::state::UniswapV4State,
+    vm::state::EVMPoolState,
+};
+
+/// Pinned stream of decoded block updates
+type UpdateStream = Pin<Box<dyn Stream<Item = std::result::Result<BlockUpdate, StreamDecodeError>> + Send>>;
+
+/// Initial delay before reconnecting a disconnected stream
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Upper bound of the reconnect backoff
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+pub struct TychoStream {
+    stream: UpdateStream,
+    args: Args,
+    sync: StreamSync,
+    last_action: SyncAction,
+}
+
+impl TychoStream {
+    pub async fn new(args: &Args) -> Result<Self> {
+        let stream = Self::connect(args).await?;
+        let sync = StreamSync::new()
+            .with_max_block_gap(args.max_block_gap)
+            .with_settle_blocks(args.resync_settle_blocks);
+
+        Ok(Self {
+            stream,
+            args: args.clone(),
+            sync,
+            last_action: SyncAction::Apply,
+        })
+    }
+
+    /// Connect to Tycho; the first update of a new connection is a full snapshot.
+    async fn connect(args: &Args) -> Result<UpdateStream> {
+        let tycho_url = args.tycho_url()?;
+        let chain = Self::chain(args);
+
+        tracing::info!(
+            chain = %chain,
+            tycho_url = %tycho_url,
+            tvl_threshold = args.tvl_threshold,
+            "Initializing Tycho stream"
+        );
+
+        if let Some(record_dir) = &args.record_dir {
+            return Self::recording(args, record_dir).await;
+        }
+
+        let tokens = load_all_tokens(
+            &tycho_url,
+            false,
+            Some(&args.tycho_api_key),
+            chain.clone(),
+            None,
+            None,
+        )
+        .await;
+
+        let exchange_filter = args.settings.exchange_filter();
+        let mut stream_builder = ProtocolStreamBuilder::new(&tycho_url, chain.clone());
+        for (exchange, config) in exchange_filter.enabled_exchanges() {
+            let component_filter = Self::component_filter(&exchange_filter, exchange, config);
+            stream_builder = Self::with_exchange(stream_builder, exchange, component_filter);
+        }
+
+        let stream = stream_builder
+            .auth_key(Some(args.tycho_api_key.clone()))
+            .skip_state_decode_failures(true)
+            .set_tokens(tokens)
+            .await
+            .build()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to build Tycho stream: {}", e))?;
+
+        tracing::info!("Tycho stream initialized successfully");
+
+        Ok(Box::pin(stream))
+    }
+
+    /// Build a stream that records every raw feed message before decoding it.
+    ///
+    /// The raw Tycho client stream is subscribed to the same exchanges as the
+    /// protocol stream and its messages are decoded with `decoder`, so the
+    /// recording replays into exactly the updates the run received.
+    async fn recording(args: &Args, record_dir: &Path) -> Result<UpdateStream> {
+        let tycho_url = args.tycho_url()?;
+        let chain = Self::chain(args);
+        let exchange_filter = args.settings.exchange_filter();
+
+        let mut client_builder = TychoStreamBuilder::new(&tycho_url, chain.clone().into());
+        for (exchange, config) in exchange_filter.enabled_exchanges() {
+            if Self::is_supported(exchange) {
+                let component_filter = Self::component_filter(&exchange_filter, exchange, config);
+                client_builder = client_builder.exchange(exchange, component_filter);
+            }
+        }
+
+        let (_client_handle, messages) = client_builder
+            .auth_key(Some(args.tycho_api_key.clone()))
+            .build()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to build Tycho client stream: {}", e))?;
+
+        let decoder = Self::decoder(args).await?;
+        let recorder = Recorder::new(record_dir)?
+            .with_blocks_per_file(args.record_blocks_per_file);
+
+        tracing::info!(
+            record_dir = %record_dir.display(),
+            blocks_per_file = args.record_blocks_per_file,
+            "Tycho stream initialized with recording"
+        );
+
+        let stream = futures::stream::unfold(
+            (messages, decoder, recorder),
+            |(mut messages, decoder, mut recorder)| async move {
+                let message = messages.recv().await?;
+                if let Err(e) = recorder.record(&message) {
+                    tracing::error!(error = %e, "Failed to record feed message");
+                }
+                let block_update = decoder.decode(message).await;
+                Some((block_update, (messages, decoder, recorder)))
+            },
+        );
+
+        Ok(Box::pin(stream))
+    }
+
+    /// The Tycho chain selected on the command line.
+    pub fn chain(args: &Args) -> Chain {
+        match args.chain.as_str() {
+            "base" => Chain::Base,
+            "unichain" => Chain::Unichain,
+            _ => Chain::Ethereum,
+        }
+    }
+
+    /// Build a decoder for raw Tycho feed messages with the exchanges of the live stream.
+    ///
+    /// Recorded messages were already filtered by TVL and pool lists when they
+    /// were received, so only the per-protocol inclusion filters are registered.
+    pub async fn decoder(args: &Args) -> Result<TychoStreamDecoder> {
+        let tycho_url = args.tycho_url()?;
+        let chain = Self::chain(args);
+
+        let tokens = load_all_tokens(
+            &tycho_url,
+            false,
+            Some(&args.tycho_api_key),
+            chain.clone(),
+            None,
+            None,
+        )
+        .await;
+
+        let mut decoder = TychoStreamDecoder::new();
+        decoder.skip_state_decode_failures(true);
+        decoder.set_tokens(tokens).await;
+
+        for (exchange, _) in args.settings.exchange_filter().enabled_exchanges() {
+            Self::register_exchange(&mut decoder, exchange);
+        }
+
+        Ok(decoder)
+    }
+
+    /// Receive the next block update.
+    ///
+    /// A stream that ends is reconnected with exponential backoff, and an update
+    /// following a gap is discarded in favour of a fresh snapshot. Whether the
+    /// returned update is a snapshot and whether searching may proceed is
+    /// available from `is_snapshot` and `is_synced`.
+    pub async fn next(&mut self) -> Option<BlockUpdate> {
+        loop {
+            match self.stream.next().await {
+                Some(Ok(block_update)) => {
+                    let action = self.sync.observe(block_update.block_number);
+                    if let SyncAction::Resync(reason) = action {
+                        tracing::warn!(
+                            block_number = block_update.block_number,
+                            reason = %reason,
+                            "Discarding block update, requesting fresh snapshot"
+                        );
+                        self.reconnect().await;
+                        continue;
+                    }
+
+                    tracing::info!(
+                        block_number = block_update.block_number,
+                        new_pairs = block_update.new_pairs.len(),
+                        removed_pairs = block_update.removed_pairs.len(),
+                        state_updates = block_update.states.len(),
+                        snapshot = action == SyncAction::Snapshot,
+                        "Received block update"
+                    );
+                    self.last_action = action;
+                    return Some(block_update);
+                }
+                Some(Err(err)) => {
+                    tracing::error!(error = %err, "Block decode error");
+                    return None;
+                }
+                None => {
+                    tracing::warn!(last_block = ?self.sync.last_block(), "Stream ended, reconnecting");
+                    self.sync.disconnected();
+                    self.reconnect().await;
+                }
+            }
+        }
+    }
+
+    /// Whether the last returned update is a full snapshot after (re)connecting.
+    ///
+    /// Pools that are not part of a snapshot no longer exist or are no longer
+    /// tracked, and their state must be dropped.
+    pub fn is_snapshot(&self) -> bool {
+        self.last_action == SyncAction::Snapshot
+    }
+
+    /// Whether the state built from the stream is consistent enough to search.
+    pub fn is_synced(&self) -> bool {
+        self.sync.is_synced()
+    }
+
+    /// Replace the stream with a new connection, retrying until it succeeds.
+    async fn reconnect(&mut self) {
+        let mut delay = INITIAL_RECONNECT_DELAY;
+        loop {
+            match Self::connect(&self.args).await {
+                Ok(stream) => {
+                    self.stream = stream;
+                    tracing::info!(resync_count = self.sync.resync_count(), "Tycho stream reconnected");
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!(
+                        error = %e,
+                        retry_in_ms = delay.as_millis(),
+                        "Failed to reconnect Tycho stream"
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            }
+        }
+    }
+
+    /// The Tycho component filter of an exchange.
+    ///
+    /// An allowlist subscribes to exactly the listed pools, otherwise pools are
+    /// selected by the exchange's TVL threshold.
+    fn component_filter(
+        exchange_filter: &ExchangeFilter,
+        exchange: &str,
+        config: &ExchangeConfig,
+    ) -> ComponentFilter {
+        if !config.allowlist.is_empty() {
+            return ComponentFilter::Ids(config.allowlist.iter().map(|pool| pool.to_string()).collect());
+        }
+
+        let tvl_threshold = exchange_filter.tvl_threshold(exchange).unwrap_or_default();
+        ComponentFilter::with_tvl_range(tvl_threshold, tvl_threshold)
+    }
+
+    /// Whether the bot can decode the states of an exchange.
+    fn is_supported(exchange: &str) -> bool {
+        matches!(
+            exchange,
+            "uniswap_v2"
+                | "sushiswap_v2"
+                | "pancakeswap_v2"
+                | "uniswap_v3"
+                | "pancakeswap_v3"
+                | "uniswap_v4"
+                | "vm:balancer_v2"
+                | "vm:curve"
+        )
+    }
+
+    fn with_exchange(
+        stream_builder: ProtocolStreamBuilder,
+        exchange: &str,
+        component_filter: ComponentFilter,
+    ) -> ProtocolStreamBuilder {
+        match exchange {
+            "uniswap_v2" | "sushiswap_v2" => {
+                stream_builder.exchange::<UniswapV2State>(exchange, component_filter, None)
+            }
+            "pancakeswap_v2" => {
+                stream_builder.exchange::<PancakeswapV2State>(exchange, component_filter, None)
+            }
+            "uniswap_v3" | "pancakeswap_v3" => {
+                stream_builder.exchange::<UniswapV3State>(exchange, component_filter, None)
+            }
+            "uniswap_v4" => {
+                stream_builder.exchange::<UniswapV4State>(exchange, component_filter, Some(UniV4PF))
+            }
+            "vm:balancer_v2" => stream_builder.exchange::<EVMPoolState<PreCachedDB>>(
+                exchange,
+                component_filter,
+                Some(BalancerPF),
+            ),
+            "vm:curve" => stream_builder.exchange::<EVMPoolState<PreCachedDB>>(
+                exchange,
+                component_filter,
+                Some(CurvePF),
+            ),
+            _ => {
+                tracing::warn!(exchange = exchange, "Unsupported exchange, skipping");
+                stream_builder
+            }
+        }
+    }
+
+    fn register_exchange(decoder: &mut TychoStreamDecoder, exchange: &str) {
+        match exchange {
+            "uniswap_v2" | "sushiswap_v2" => decoder.register_decoder::<UniswapV2State>(exchange),
+            "pancakeswap_v2" => decoder.register_decoder::<PancakeswapV2State>(exchange),
+            "uniswap_v3" | "pancakeswap_v3" => decoder.register_decoder::<UniswapV3State>(exchange),
+            "uniswap_v4" => {
+                decoder.register_decoder::<UniswapV4State>(exchange);
+                decoder.register_filter(exchange, UniV4PF);
+            }
+            "vm:balancer_v2" => {
+                decoder.register_decoder::<EVMPoolState<PreCachedDB>>(exchange);
+                decoder.register_filter(exchange, BalancerPF);
+            }
+            "vm:curve" => {
+                decoder.register_decoder::<EVMPoolState<PreCachedDB>>(exchange);
+                decoder.register_filter(exchange, CurvePF);
+            }
+            _ => {
+                tracing::warn!(exchange = exchange, "Unsupported exchange, skipping");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod stream_test {
+    use crate::stream::TychoStream;
+    use dotenv::dotenv;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_tycho_stream_new() {
+        dotenv().ok();
+        let tycho_api_key = std::env::var("TYCHO_API_KEY").unwrap_or_default();
+        TychoStream::new(
+            "ethereum",
+            "https://eth.mainnet.tycho.xyz",
+            &tycho_api_key,
+            1000.0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_tycho_stream_next() {
+        dotenv().ok();
+        let tycho_api_key = std::env::var("TYCHO_API_KEY").unwrap_or_default();
+        let mut stream = TychoStream::new(
+            "ethereum",
+            "https://eth.mainnet.tycho.xyz",
+            &tycho_api_key,
+            1000.0,
+        )
+        .await;
+
+        let next = stream.next().await;
+        assert!(next.is_some());
+    }
+}
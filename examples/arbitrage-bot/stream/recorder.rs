@@ -0,0 +1,139 @@
+//! Recording of raw Tycho feed messages.
+//!
+//! Decoded block updates hold protocol simulation states that cannot be
+//! serialized, so the recorder stores the raw feed messages they are decoded
+//! from. Every message is written as one JSON line, together with the time it was
+//! received, to gzip-compressed files that are rotated every `blocks_per_file`
+//! messages. The `Replayer` decodes them into the same block updates again.
+
+use anyhow::Result;
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tycho_simulation::tycho_client::feed::FeedMessage;
+
+/// Default number of messages written to one file before rotating.
+pub const DEFAULT_BLOCKS_PER_FILE: u64 = 1000;
+
+/// File extension of recorded message files.
+pub const RECORDING_EXTENSION: &str = "jsonl.gz";
+
+/// A feed message together with the time it was received.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// Unix time in milliseconds at which the message was received
+    pub received_at_ms: u64,
+    /// The raw feed message
+    pub message: FeedMessage,
+}
+
+/// Writes every received feed message to rotating compressed files.
+pub struct Recorder {
+    directory: PathBuf,
+    blocks_per_file: u64,
+    writer: Option<GzEncoder<BufWriter<File>>>,
+    blocks_in_file: u64,
+}
+
+impl Recorder {
+    /// Create a recorder writing into `directory`, which is created if needed.
+    pub fn new<P: AsRef<Path>>(directory: P) -> Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| anyhow::anyhow!("Failed to create recording directory {}: {}", directory.display(), e))?;
+
+        Ok(Self {
+            directory,
+            blocks_per_file: DEFAULT_BLOCKS_PER_FILE,
+            writer: None,
+            blocks_in_file: 0,
+        })
+    }
+
+    /// Set how many messages are written to one file before rotating.
+    pub fn with_blocks_per_file(mut self, blocks_per_file: u64) -> Self {
+        self.blocks_per_file = blocks_per_file.max(1);
+        self
+    }
+
+    /// Record a feed message.
+    ///
+    /// The message is flushed right away, so a crashed run leaves a readable
+    /// recording of every block it received.
+    pub fn record(&mut self, message: &FeedMessage) -> Result<()> {
+        if self.writer.is_none() || self.blocks_in_file >= self.blocks_per_file {
+            self.rotate(message)?;
+        }
+
+        let recorded = RecordedMessage {
+            received_at_ms: now_ms(),
+            message: message.clone(),
+        };
+        let line = serde_json::to_string(&recorded)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize feed message: {}", e))?;
+
+        let writer = self.writer.as_mut()
+            .ok_or_else(|| anyhow::anyhow!("No open recording file"))?;
+        writer.write_all(line.as_bytes())?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        self.blocks_in_file += 1;
+
+        Ok(())
+    }
+
+    /// Finish the current file, writing the gzip trailer.
+    pub fn finish(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer.finish()?.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Finish the current file and open a new one named after the message's block.
+    fn rotate(&mut self, message: &FeedMessage) -> Result<()> {
+        self.finish()?;
+
+        let name = match block_number(message) {
+            Some(block_number) => format!("updates_{:012}.{}", block_number, RECORDING_EXTENSION),
+            None => format!("updates_t{}.{}", now_ms(), RECORDING_EXTENSION),
+        };
+        let path = self.directory.join(name);
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)?;
+
+        tracing::info!(recording_file = %path.display(), "Recording feed messages");
+
+        self.writer = Some(GzEncoder::new(BufWriter::new(file), Compression::default()));
+        self.blocks_in_file = 0;
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            tracing::warn!(error = %e, "Failed to finish recording file");
+        }
+    }
+}
+
+/// The highest block number of the protocol messages in a feed message.
+pub fn block_number(message: &FeedMessage) -> Option<u64> {
+    message.state_msgs.values().map(|state| state.header.number).max()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
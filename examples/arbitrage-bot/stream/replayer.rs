@@ -0,0 +1,252 @@
+//! Replay of recorded Tycho feed messages.
+//!
+//! The `Replayer` reads the files written by the `Recorder`, or plain files with
+//! one JSON feed message per line, decodes every message into a block update and
+//! yields it through the same `next` interface as the live `TychoStream`. Updates
+//! are replayed either with their original spacing or as fast as possible.
+
+use anyhow::Result;
+use flate2::read::GzDecoder;
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tycho_simulation::{
+    evm::decoder::TychoStreamDecoder,
+    protocol::models::BlockUpdate,
+    tycho_client::feed::FeedMessage,
+};
+
+use super::recorder::RecordedMessage;
+
+/// Pace at which recorded updates are replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplaySpeed {
+    /// Keep the spacing between updates at which they were received
+    Original,
+    /// Yield every update as soon as the previous one was processed
+    #[default]
+    Max,
+}
+
+impl FromStr for ReplaySpeed {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "original" => Ok(ReplaySpeed::Original),
+            "max" | "fast" => Ok(ReplaySpeed::Max),
+            _ => Err(anyhow::anyhow!("Unknown replay speed: {}", s)),
+        }
+    }
+}
+
+/// Yields block updates decoded from recorded feed messages.
+pub struct Replayer {
+    files: VecDeque<PathBuf>,
+    lines: Option<Box<dyn BufRead + Send>>,
+    current_file: Option<PathBuf>,
+    line_number: usize,
+    decoder: TychoStreamDecoder,
+    speed: ReplaySpeed,
+    /// Receive time of the previous update and when it was replayed
+    last_replayed: Option<(u64, Instant)>,
+}
+
+impl Replayer {
+    /// Open a recording, either a single file or a directory of recorded files.
+    ///
+    /// Files of a directory are replayed in name order, which is block order for
+    /// files written by the `Recorder`.
+    pub fn open<P: AsRef<Path>>(path: P, decoder: TychoStreamDecoder) -> Result<Self> {
+        let files = recording_files(path.as_ref())?;
+        if files.is_empty() {
+            return Err(anyhow::anyhow!("No recordings found at {}", path.as_ref().display()));
+        }
+
+        tracing::info!(
+            recording = %path.as_ref().display(),
+            files = files.len(),
+            "Opened recorded block updates"
+        );
+
+        Ok(Self {
+            files: files.into(),
+            lines: None,
+            current_file: None,
+            line_number: 0,
+            decoder,
+            speed: ReplaySpeed::default(),
+            last_replayed: None,
+        })
+    }
+
+    /// Set the pace at which updates are replayed.
+    pub fn with_speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Decode the next recorded block update.
+    ///
+    /// Lines that cannot be parsed or decoded are skipped, `None` marks the end
+    /// of the recording.
+    pub async fn next(&mut self) -> Option<BlockUpdate> {
+        loop {
+            let line = self.next_line()?;
+            let Some(recorded) = self.parse_line(&line) else {
+                continue;
+            };
+
+            self.wait_for(recorded.received_at_ms).await;
+
+            match self.decoder.decode(recorded.message).await {
+                Ok(block_update) => return Some(block_update),
+                Err(e) => {
+                    tracing::warn!(
+                        file = ?self.current_file,
+                        line_number = self.line_number,
+                        error = %e,
+                        "Failed to decode recorded feed message, skipping"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Read the next non-empty line, moving on to the next file at the end of one.
+    fn next_line(&mut self) -> Option<String> {
+        loop {
+            if self.lines.is_none() {
+                let path = self.files.pop_front()?;
+                match open_lines(&path) {
+                    Ok(lines) => {
+                        tracing::debug!(file = %path.display(), "Replaying recording file");
+                        self.lines = Some(lines);
+                        self.current_file = Some(path);
+                        self.line_number = 0;
+                    }
+                    Err(e) => {
+                        tracing::error!(file = %path.display(), error = %e, "Failed to open recording file");
+                        continue;
+                    }
+                }
+            }
+
+            let lines = self.lines.as_mut()?;
+            let mut line = String::new();
+            match lines.read_line(&mut line) {
+                Ok(0) => self.lines = None,
+                Ok(_) => {
+                    self.line_number += 1;
+                    if !line.trim().is_empty() {
+                        return Some(line);
+                    }
+                }
+                Err(e) => {
+                    // Recordings of an interrupted run end without a gzip trailer
+                    tracing::warn!(
+                        file = ?self.current_file,
+                        line_number = self.line_number,
+                        error = %e,
+                        "Recording file ended unexpectedly"
+                    );
+                    self.lines = None;
+                }
+            }
+        }
+    }
+
+    /// Parse a recorded message, or a bare feed message without receive time.
+    fn parse_line(&self, line: &str) -> Option<RecordedMessage> {
+        if let Ok(recorded) = serde_json::from_str::<RecordedMessage>(line) {
+            return Some(recorded);
+        }
+
+        match serde_json::from_str::<FeedMessage>(line) {
+            Ok(message) => Some(RecordedMessage { received_at_ms: 0, message }),
+            Err(e) => {
+                tracing::warn!(
+                    file = ?self.current_file,
+                    line_number = self.line_number,
+                    error = %e,
+                    "Failed to parse recorded feed message, skipping"
+                );
+                None
+            }
+        }
+    }
+
+    /// Sleep until the update is due at the configured replay speed.
+    async fn wait_for(&mut self, received_at_ms: u64) {
+        if self.speed == ReplaySpeed::Original {
+            if let Some((last_received_at_ms, last_replayed_at)) = self.last_replayed {
+                let due = replay_delay(last_received_at_ms, received_at_ms);
+                if let Some(remaining) = due.checked_sub(last_replayed_at.elapsed()) {
+                    tokio::time::sleep(remaining).await;
+                }
+            }
+        }
+        self.last_replayed = Some((received_at_ms, Instant::now()));
+    }
+}
+
+/// Time between two updates at the original pace.
+///
+/// Updates without a receive time, or out of order, are replayed immediately.
+fn replay_delay(previous_received_at_ms: u64, received_at_ms: u64) -> Duration {
+    if previous_received_at_ms == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(received_at_ms.saturating_sub(previous_received_at_ms))
+}
+
+/// The files of a recording, in replay order.
+fn recording_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(path)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| file.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Open a recording file, decompressing gzip files.
+fn open_lines(path: &Path) -> Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path)?;
+    let is_gzip = path.extension().is_some_and(|extension| extension == "gz");
+
+    Ok(if is_gzip {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_speed_from_str() {
+        assert_eq!(ReplaySpeed::from_str("original").unwrap(), ReplaySpeed::Original);
+        assert_eq!(ReplaySpeed::from_str("MAX").unwrap(), ReplaySpeed::Max);
+        assert_eq!(ReplaySpeed::from_str("fast").unwrap(), ReplaySpeed::Max);
+        assert!(ReplaySpeed::from_str("slow").is_err());
+    }
+
+    #[test]
+    fn test_replay_delay() {
+        assert_eq!(replay_delay(1_000, 13_000), Duration::from_secs(12));
+        assert_eq!(replay_delay(13_000, 1_000), Duration::ZERO);
+        assert_eq!(replay_delay(0, 13_000), Duration::ZERO);
+    }
+}
@@ -0,0 +1,382 @@
+//! Alerts on large profits and failure anomalies.
+//!
+//! Operators otherwise learn about a depleted inventory, a stalled stream or a
+//! burst of reverting simulations hours later from the CSV logs. An
+//! `AlertMonitor` evaluates configurable rules and hands every triggered
+//! [`Alert`] to its sinks, e.g. a [`WebhookSink`] posting JSON to a chat
+//! service or an incident tool:
+//!
+//! - **Profit**: a single opportunity simulated with a net profit above a threshold
+//! - **Error rate**: the share of failed simulations over a window of blocks
+//!   exceeds a maximum
+//! - **Inventory**: a token of the executor fell below its floor
+//! - **Stream lag**: the processed stream trails the chain head by too many blocks
+//!
+//! The monitor is an [`EventHandler`], so it observes profits and error rates
+//! once registered with an [`EventDispatcher`](crate::events::EventDispatcher).
+//! Inventory and stream lag are reported with [`AlertMonitor::observe_inventory`]
+//! and [`AlertMonitor::observe_stream_lag`]. An alert of a rule and subject is
+//! repeated at most once per cooldown of blocks, so a lasting condition does
+//! not flood the sinks.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::alerts::{AlertMonitor, AlertRule, WebhookSink};
+//!
+//! let monitor = Arc::new(
+//!     AlertMonitor::new()
+//!         .with_rule(AlertRule::ProfitAbove { min_net_profit: BigUint::from(10u64).pow(18) })
+//!         .with_rule(AlertRule::ErrorRateAbove { max_rate: 0.5, window_blocks: 20 })
+//!         .with_sink(Arc::new(WebhookSink::new(webhook_url)?)),
+//! );
+//! events.register(monitor.clone());
+//! monitor.observe_stream_lag(block_number, chain_head);
+//! ```
+
+pub mod webhook;
+
+pub use webhook::WebhookSink;
+
+use crate::events::{BlockEvent, EventHandler, SimulationEvent, SimulationOutcome};
+use crate::inventory::RebalanceSuggestion;
+use num_bigint::BigUint;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Default number of blocks before an alert of the same rule and subject is repeated.
+pub const DEFAULT_ALERT_COOLDOWN_BLOCKS: u64 = 50;
+
+/// A condition that triggers an alert.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertRule {
+    /// A simulated opportunity is profitable by more than `min_net_profit` after gas, in native token
+    ProfitAbove {
+        /// The net profit above which an opportunity is reported
+        min_net_profit: BigUint,
+    },
+    /// More than `max_rate` of the simulations within `window_blocks` failed
+    ErrorRateAbove {
+        /// The largest tolerated share of failed simulations, between 0 and 1
+        max_rate: f64,
+        /// The number of blocks the rate is computed over
+        window_blocks: u64,
+    },
+    /// A token of the executor fell below its inventory floor
+    InventoryBelowFloor,
+    /// The processed stream trails the chain head by more than `max_lag_blocks`
+    StreamLagAbove {
+        /// The largest tolerated lag
+        max_lag_blocks: u64,
+    },
+}
+
+impl AlertRule {
+    /// A stable identifier of the rule, used as the kind of its alerts.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AlertRule::ProfitAbove { .. } => "large_profit",
+            AlertRule::ErrorRateAbove { .. } => "error_rate",
+            AlertRule::InventoryBelowFloor => "low_inventory",
+            AlertRule::StreamLagAbove { .. } => "stream_lag",
+        }
+    }
+}
+
+/// A triggered alert, as sent to sinks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    /// The kind of the triggering rule, see [`AlertRule::kind`]
+    pub kind: &'static str,
+    /// The block the condition was observed in
+    pub block_number: u64,
+    /// A human-readable description
+    pub message: String,
+    /// The observed value
+    pub value: String,
+    /// The threshold the value crossed
+    pub threshold: String,
+}
+
+/// Receives triggered alerts.
+///
+/// Sinks are called on the search path and should return quickly, handing
+/// slow I/O to a spawned task like [`WebhookSink`] does.
+pub trait AlertSink: Send + Sync {
+    /// Deliver an alert.
+    fn send(&self, alert: &Alert);
+}
+
+/// Simulation counts of one block.
+#[derive(Debug, Clone, Copy, Default)]
+struct SimulationCounts {
+    total: u64,
+    failed: u64,
+}
+
+/// Observations shared by all rules.
+#[derive(Debug, Default)]
+struct MonitorState {
+    simulations: BTreeMap<u64, SimulationCounts>,
+    last_alerted: HashMap<(&'static str, String), u64>,
+}
+
+/// Evaluates alert rules and forwards triggered alerts to sinks.
+pub struct AlertMonitor {
+    rules: Vec<AlertRule>,
+    sinks: Vec<Arc<dyn AlertSink>>,
+    cooldown_blocks: u64,
+    state: Mutex<MonitorState>,
+}
+
+impl AlertMonitor {
+    /// Create a monitor without rules or sinks.
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            sinks: Vec::new(),
+            cooldown_blocks: DEFAULT_ALERT_COOLDOWN_BLOCKS,
+            state: Mutex::new(MonitorState::default()),
+        }
+    }
+
+    /// Evaluate a rule.
+    pub fn with_rule(mut self, rule: AlertRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Send triggered alerts to a sink.
+    pub fn with_sink(mut self, sink: Arc<dyn AlertSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Repeat an alert of the same rule and subject at most every `cooldown_blocks` blocks.
+    pub fn with_cooldown_blocks(mut self, cooldown_blocks: u64) -> Self {
+        self.cooldown_blocks = cooldown_blocks;
+        self
+    }
+
+    /// The evaluated rules.
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Report the tokens below their inventory floor.
+    pub fn observe_inventory(&self, block_number: u64, suggestions: &[RebalanceSuggestion]) {
+        if !self.rules.contains(&AlertRule::InventoryBelowFloor) {
+            return;
+        }
+        for suggestion in suggestions {
+            self.trigger(suggestion.token.to_string(), Alert {
+                kind: AlertRule::InventoryBelowFloor.kind(),
+                block_number,
+                message: format!("Inventory of {} below its floor", suggestion.token),
+                value: suggestion.balance.to_string(),
+                threshold: suggestion.floor.to_string(),
+            });
+        }
+    }
+
+    /// Report the lag of the processed block behind the chain head.
+    pub fn observe_stream_lag(&self, block_number: u64, chain_head: u64) {
+        let lag = chain_head.saturating_sub(block_number);
+        for rule in &self.rules {
+            if let AlertRule::StreamLagAbove { max_lag_blocks } = rule {
+                if lag > *max_lag_blocks {
+                    self.trigger(String::new(), Alert {
+                        kind: rule.kind(),
+                        block_number,
+                        message: format!("Stream trails the chain head by {} blocks", lag),
+                        value: lag.to_string(),
+                        threshold: max_lag_blocks.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Send an alert unless one of the same rule and subject was sent within the cooldown.
+    fn trigger(&self, subject: String, alert: Alert) {
+        {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let key = (alert.kind, subject);
+            if let Some(last_alerted) = state.last_alerted.get(&key) {
+                if alert.block_number < last_alerted + self.cooldown_blocks.max(1) {
+                    return;
+                }
+            }
+            state.last_alerted.insert(key, alert.block_number);
+        }
+
+        tracing::warn!(
+            kind = alert.kind,
+            block_number = alert.block_number,
+            value = %alert.value,
+            threshold = %alert.threshold,
+            "{}", alert.message
+        );
+        for sink in &self.sinks {
+            sink.send(&alert);
+        }
+    }
+
+    /// Evaluate the error rate rules over the blocks before `block_number`.
+    fn check_error_rates(&self, block_number: u64) {
+        for rule in &self.rules {
+            let AlertRule::ErrorRateAbove { max_rate, window_blocks } = rule else {
+                continue;
+            };
+            let counts = {
+                let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                state
+                    .simulations
+                    .range(block_number.saturating_sub(*window_blocks)..block_number)
+                    .fold(SimulationCounts::default(), |sum, (_, counts)| SimulationCounts {
+                        total: sum.total + counts.total,
+                        failed: sum.failed + counts.failed,
+                    })
+            };
+            if counts.total == 0 {
+                continue;
+            }
+
+            let rate = counts.failed as f64 / counts.total as f64;
+            if rate > *max_rate {
+                self.trigger(String::new(), Alert {
+                    kind: rule.kind(),
+                    block_number,
+                    message: format!(
+                        "{} of {} simulations failed within {} blocks",
+                        counts.failed, counts.total, window_blocks
+                    ),
+                    value: format!("{:.4}", rate),
+                    threshold: format!("{:.4}", max_rate),
+                });
+            }
+        }
+    }
+
+    /// The largest error rate window, to prune older simulation counts.
+    fn max_window_blocks(&self) -> u64 {
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule {
+                AlertRule::ErrorRateAbove { window_blocks, .. } => Some(*window_blocks),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Default for AlertMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventHandler for AlertMonitor {
+    fn on_block(&self, event: &BlockEvent<'_>) {
+        self.check_error_rates(event.block_number);
+
+        let keep_from = event.block_number.saturating_sub(self.max_window_blocks());
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.simulations = state.simulations.split_off(&keep_from);
+    }
+
+    fn on_simulation_complete(&self, event: &SimulationEvent<'_>) {
+        {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let counts = state.simulations.entry(event.block_number).or_default();
+            counts.total += 1;
+            if matches!(event.outcome, SimulationOutcome::Failed { .. }) {
+                counts.failed += 1;
+            }
+        }
+
+        let SimulationOutcome::Profitable { net_profit } = &event.outcome else {
+            return;
+        };
+        for rule in &self.rules {
+            if let AlertRule::ProfitAbove { min_net_profit } = rule {
+                if net_profit > min_net_profit {
+                    self.trigger(event.opportunity_id.to_string(), Alert {
+                        kind: rule.kind(),
+                        block_number: event.block_number,
+                        message: format!("Opportunity {} simulated with a large net profit", event.opportunity_id),
+                        value: net_profit.to_string(),
+                        threshold: min_net_profit.to_string(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for AlertMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlertMonitor")
+            .field("rules", &self.rules)
+            .field("sinks", &self.sinks.len())
+            .field("cooldown_blocks", &self.cooldown_blocks)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::OpportunityId;
+    use crate::path::PathExt;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        alerts: Mutex<Vec<Alert>>,
+    }
+
+    impl AlertSink for RecordingSink {
+        fn send(&self, alert: &Alert) {
+            self.alerts.lock().unwrap().push(alert.clone());
+        }
+    }
+
+    #[test]
+    fn test_triggers_rules_once_per_cooldown() {
+        let sink = Arc::new(RecordingSink::default());
+        let monitor = AlertMonitor::new()
+            .with_rule(AlertRule::ErrorRateAbove { max_rate: 0.5, window_blocks: 2 })
+            .with_rule(AlertRule::StreamLagAbove { max_lag_blocks: 3 })
+            .with_cooldown_blocks(10)
+            .with_sink(sink.clone());
+
+        let path = PathExt(vec![]);
+        for outcome in [
+            SimulationOutcome::Failed { error: "reverted".to_string() },
+            SimulationOutcome::Failed { error: "reverted".to_string() },
+            SimulationOutcome::Unprofitable,
+        ] {
+            monitor.on_simulation_complete(&SimulationEvent {
+                block_number: 100,
+                opportunity_id: OpportunityId::of_path_ext(&path, 100),
+                path: &path,
+                outcome,
+            });
+        }
+        monitor.on_block(&BlockEvent { block_number: 101, updated_pools: &[] });
+        // Within the cooldown the lasting error rate is not reported again
+        monitor.on_block(&BlockEvent { block_number: 102, updated_pools: &[] });
+
+        monitor.observe_stream_lag(102, 105);
+        monitor.observe_stream_lag(102, 106);
+
+        let alerts = sink.alerts.lock().unwrap();
+        let kinds: Vec<_> = alerts.iter().map(|alert| alert.kind).collect();
+        assert_eq!(kinds, vec!["error_rate", "stream_lag"]);
+        assert_eq!(alerts[0].value, "0.6667");
+        assert_eq!(alerts[1].value, "4");
+    }
+}
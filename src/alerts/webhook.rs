@@ -0,0 +1,140 @@
+//! Alert delivery by HTTP POST.
+//!
+//! A `WebhookSink` posts every alert as JSON to a URL. Without a template the
+//! body is the serialized [`Alert`]; chat services and incident tools expect
+//! their own shapes, so a template can lay out the body instead. Placeholders
+//! are replaced by the fields of the alert, escaped as JSON string contents:
+//!
+//! ```json
+//! {"text": "[{{kind}}] {{message}} at block {{block_number}} ({{value}}, threshold {{threshold}})"}
+//! ```
+//!
+//! Requests are sent from a spawned task, so a slow or unreachable endpoint
+//! never delays the search. Failed deliveries are logged and not retried.
+
+use super::{Alert, AlertSink};
+use crate::errors::Result;
+use reqwest::Client as HttpClient;
+use std::time::Duration;
+
+/// Timeout of a webhook request.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts alerts as JSON to a webhook URL.
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    client: HttpClient,
+    url: reqwest::Url,
+    template: Option<String>,
+}
+
+impl WebhookSink {
+    /// Post alerts to `url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL cannot be parsed or the HTTP client cannot be built
+    pub fn new(url: &str) -> Result<Self> {
+        let url = url.parse::<reqwest::Url>().map_err(|e| anyhow::anyhow!("Invalid webhook URL '{}': {}", url, e))?;
+        let client = HttpClient::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+        Ok(Self {
+            client,
+            url,
+            template: None,
+        })
+    }
+
+    /// Lay out request bodies with a template of `{{kind}}`, `{{message}}`,
+    /// `{{block_number}}`, `{{value}}` and `{{threshold}}` placeholders.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template does not render to valid JSON
+    pub fn with_template(mut self, template: impl Into<String>) -> Result<Self> {
+        let template = template.into();
+        let sample = Alert {
+            kind: "sample",
+            block_number: 0,
+            message: "Sample \"alert\"".to_string(),
+            value: "0".to_string(),
+            threshold: "0".to_string(),
+        };
+        serde_json::from_str::<serde_json::Value>(&render(&template, &sample))?;
+        self.template = Some(template);
+        Ok(self)
+    }
+
+    /// The request body of an alert.
+    pub fn body(&self, alert: &Alert) -> String {
+        match &self.template {
+            Some(template) => render(template, alert),
+            // Serializing plain strings and integers cannot fail
+            None => serde_json::to_string(alert).unwrap_or_default(),
+        }
+    }
+}
+
+impl AlertSink for WebhookSink {
+    fn send(&self, alert: &Alert) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            tracing::warn!(kind = alert.kind, "No async runtime to deliver webhook alert");
+            return;
+        };
+
+        let request = self
+            .client
+            .post(self.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(self.body(alert));
+        let kind = alert.kind;
+        runtime.spawn(async move {
+            match request.send().await.and_then(|response| response.error_for_status()) {
+                Ok(_) => tracing::debug!(kind = kind, "Delivered webhook alert"),
+                Err(e) => tracing::warn!(kind = kind, error = %e, "Failed to deliver webhook alert"),
+            }
+        });
+    }
+}
+
+/// Replace the placeholders of a template by the escaped fields of an alert.
+fn render(template: &str, alert: &Alert) -> String {
+    [
+        ("{{kind}}", alert.kind.to_string()),
+        ("{{message}}", alert.message.clone()),
+        ("{{block_number}}", alert.block_number.to_string()),
+        ("{{value}}", alert.value.clone()),
+        ("{{threshold}}", alert.threshold.clone()),
+    ]
+    .iter()
+    .fold(template.to_string(), |body, (placeholder, value)| body.replace(placeholder, &escape(value)))
+}
+
+/// Escape a value as the contents of a JSON string.
+fn escape(value: &str) -> String {
+    let quoted = serde_json::Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_renders_escaped_json() {
+        let sink = WebhookSink::new("https://hooks.example.com/alerts")
+            .unwrap()
+            .with_template(r#"{"text": "[{{kind}}] {{message}} at {{block_number}}"}"#)
+            .unwrap();
+        let alert = Alert {
+            kind: "low_inventory",
+            block_number: 7,
+            message: "Token \"WETH\" below floor".to_string(),
+            value: "1".to_string(),
+            threshold: "2".to_string(),
+        };
+
+        let body: serde_json::Value = serde_json::from_str(&sink.body(&alert)).unwrap();
+        assert_eq!(body["text"], "[low_inventory] Token \"WETH\" below floor at 7");
+        assert!(WebhookSink::new("https://hooks.example.com").unwrap().with_template("{{message}}").is_err());
+    }
+}
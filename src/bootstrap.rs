@@ -0,0 +1,245 @@
+//! Cold-start checks of the executor before arbitrage starts.
+//!
+//! A freshly deployed executor usually lacks something: a source token was
+//! never funded, or Permit2 was never approved to spend it. Without an
+//! allowance every bundle of the token carries its own approval, paying its
+//! gas and a nonce again and again until one of them lands.
+//!
+//! `ExecutorBootstrap` reads the balance and the Permit2 allowance of every
+//! source token once on startup and reports what is missing. The missing
+//! approvals can be turned into one batch of unlimited `approve` transactions
+//! with consecutive nonces, sent before the first search instead of with it.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::bootstrap::ExecutorBootstrap;
+//!
+//! let bootstrap = ExecutorBootstrap::new(executor, permit2);
+//! let report = bootstrap.check(&provider, &inventory, &allowances, &source_tokens, block_number).await?;
+//! let approvals = bootstrap.approval_requests(&report, nonce, chain_id);
+//! executor.execute(approvals, block_number + 1, base_fee, U256::ZERO).await?;
+//! ```
+
+use crate::errors::Result;
+use crate::inventory::InventoryManager;
+use crate::simulation::encoding::create_approval_calldata;
+use crate::simulation::AllowanceCache;
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, TxKind, U256},
+    providers::RootProvider,
+    rpc::types::{TransactionInput, TransactionRequest},
+};
+use num_bigint::BigUint;
+use num_traits::Zero;
+use tycho_common::Bytes;
+
+/// Gas limit of an ERC-20 `approve` call, with headroom.
+const APPROVAL_GAS_LIMIT: u64 = 100_000;
+
+/// Balance and Permit2 allowance of one source token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenReadiness {
+    /// The source token
+    pub token: Address,
+    /// The executor's balance, `None` if it could not be read
+    pub balance: Option<BigUint>,
+    /// The executor's allowance to Permit2, `None` if it could not be read
+    pub allowance: Option<U256>,
+}
+
+impl TokenReadiness {
+    /// Whether the executor holds none of the token, or its balance is unknown.
+    pub fn is_unfunded(&self) -> bool {
+        self.balance.as_ref().is_none_or(BigUint::is_zero)
+    }
+}
+
+/// What the executor lacks before arbitrage starts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootstrapReport {
+    /// Every checked source token
+    pub tokens: Vec<TokenReadiness>,
+    /// Allowances below which a token counts as unapproved
+    pub min_allowance: U256,
+}
+
+impl BootstrapReport {
+    /// Tokens whose Permit2 allowance is below the minimum.
+    ///
+    /// Tokens whose allowance could not be read are not reported, as an
+    /// approval cannot be told apart from a token that is no ERC-20.
+    pub fn missing_approvals(&self) -> Vec<Address> {
+        self.tokens
+            .iter()
+            .filter(|readiness| readiness.allowance.is_some_and(|allowance| allowance < self.min_allowance))
+            .map(|readiness| readiness.token)
+            .collect()
+    }
+
+    /// Tokens the executor holds none of.
+    pub fn unfunded_tokens(&self) -> Vec<Address> {
+        self.tokens
+            .iter()
+            .filter(|readiness| readiness.is_unfunded())
+            .map(|readiness| readiness.token)
+            .collect()
+    }
+
+    /// Whether every token is funded and approved.
+    pub fn is_ready(&self) -> bool {
+        self.missing_approvals().is_empty() && self.unfunded_tokens().is_empty()
+    }
+}
+
+/// Checks the balances and Permit2 allowances of the executor on startup.
+#[derive(Debug, Clone)]
+pub struct ExecutorBootstrap {
+    owner: Address,
+    permit2: Address,
+    min_allowance: U256,
+}
+
+impl ExecutorBootstrap {
+    /// Check the allowances `owner` granted to `permit2`.
+    ///
+    /// Allowances below half of the maximum count as missing, so unlimited
+    /// approvals that were partly spent by tokens decreasing them still count.
+    pub fn new(owner: Address, permit2: Address) -> Self {
+        Self {
+            owner,
+            permit2,
+            min_allowance: U256::MAX >> 1,
+        }
+    }
+
+    /// The Permit2 contract the allowances are granted to.
+    pub fn permit2(&self) -> Address {
+        self.permit2
+    }
+
+    /// Count allowances below `min_allowance` as missing.
+    pub fn with_min_allowance(mut self, min_allowance: U256) -> Self {
+        self.min_allowance = min_allowance;
+        self
+    }
+
+    /// Read the balance and Permit2 allowance of every source token.
+    ///
+    /// Balances are recorded in the inventory and allowances in the cache, so
+    /// neither has to be read again for the first block.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The RPC provider used for the `balanceOf` and `allowance` calls
+    /// * `inventory` - The inventory of the executor
+    /// * `allowances` - The allowance cache from the executor to Permit2
+    /// * `tokens` - The source tokens
+    /// * `block_number` - The block the balances are recorded for
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no balance could be read. Tokens whose allowance
+    /// cannot be read are reported without one.
+    pub async fn check(
+        &self,
+        provider: &RootProvider<Ethereum>,
+        inventory: &InventoryManager,
+        allowances: &AllowanceCache,
+        tokens: &[Bytes],
+        block_number: u64,
+    ) -> Result<BootstrapReport> {
+        let balances = inventory.refresh(provider, tokens, block_number).await?;
+
+        let mut report = BootstrapReport {
+            tokens: Vec::with_capacity(tokens.len()),
+            min_allowance: self.min_allowance,
+        };
+        for token in tokens.iter().filter(|token| token.len() == 20) {
+            let address = Address::from_slice(token.as_ref());
+            if let Err(e) = allowances.refresh(provider, &[address]).await {
+                tracing::warn!(token = %token, error = %e, "Failed to read Permit2 allowance");
+            }
+            report.tokens.push(TokenReadiness {
+                token: address,
+                balance: balances.get(token).cloned(),
+                allowance: allowances.allowance(&address),
+            });
+        }
+
+        tracing::info!(
+            owner = %self.owner,
+            tokens = report.tokens.len(),
+            missing_approvals = report.missing_approvals().len(),
+            unfunded_tokens = report.unfunded_tokens().len(),
+            "Checked executor balances and Permit2 allowances"
+        );
+        Ok(report)
+    }
+
+    /// Build unlimited Permit2 approvals of every token missing one.
+    ///
+    /// The requests still need fees before they can be signed.
+    ///
+    /// # Arguments
+    ///
+    /// * `report` - The report of a previous check
+    /// * `nonce` - The nonce of the owner for the first approval
+    /// * `chain_id` - The chain the transactions are signed for
+    ///
+    /// # Returns
+    ///
+    /// One approval per token, with consecutive nonces starting at `nonce`
+    pub fn approval_requests(&self, report: &BootstrapReport, nonce: u64, chain_id: u64) -> Vec<TransactionRequest> {
+        report
+            .missing_approvals()
+            .into_iter()
+            .zip(nonce..)
+            .map(|(token, nonce)| TransactionRequest {
+                from: Some(self.owner),
+                to: Some(TxKind::Call(token)),
+                input: TransactionInput {
+                    input: Some(create_approval_calldata(self.permit2, U256::MAX)),
+                    data: None,
+                },
+                gas: Some(APPROVAL_GAS_LIMIT),
+                nonce: Some(nonce),
+                chain_id: Some(chain_id),
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approves_only_tokens_missing_an_allowance() {
+        let readiness = |last_byte: u8, balance: Option<u32>, allowance: Option<U256>| TokenReadiness {
+            token: Address::with_last_byte(last_byte),
+            balance: balance.map(BigUint::from),
+            allowance,
+        };
+        let (approved, unapproved, unknown) = (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let report = BootstrapReport {
+            tokens: vec![
+                readiness(1, Some(1), Some(U256::MAX - U256::from(5))),
+                readiness(2, Some(0), Some(U256::from(1_000))),
+                readiness(3, None, None),
+            ],
+            min_allowance: U256::MAX >> 1,
+        };
+        assert_eq!(report.missing_approvals(), vec![unapproved]);
+        assert_eq!(report.unfunded_tokens(), vec![unapproved, unknown]);
+        assert!(!report.is_ready());
+
+        let bootstrap = ExecutorBootstrap::new(Address::with_last_byte(9), Address::with_last_byte(8));
+        let requests = bootstrap.approval_requests(&report, 7, 1);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].to, Some(TxKind::Call(unapproved)));
+        assert_eq!(requests[0].nonce, Some(7));
+    }
+}
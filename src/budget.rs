@@ -0,0 +1,185 @@
+//! Deadline-aware search budgets for per-block arbitrage searches.
+//!
+//! An arbitrage search is only useful if its result can still be submitted for the
+//! block it targets. This module provides a common budget that every stage of the
+//! pipeline - path building, optimization and simulation - checks before doing more
+//! work, so that a slow stage cannot push the whole search past the next block.
+//!
+//! # Core Types
+//!
+//! - **`SearchBudget`**: Static limits for a search (wall-clock time, path and simulation caps)
+//! - **`SearchDeadline`**: A running budget anchored at the moment the search started
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use tycho_atomic_arbitrage::budget::SearchBudget;
+//!
+//! let budget = SearchBudget::for_chain("ethereum")?
+//!     .with_max_paths(5_000)
+//!     .with_max_simulations(20);
+//!
+//! let deadline = budget.start();
+//! if deadline.is_expired() {
+//!     // Skip remaining work for this block
+//! }
+//! # Ok::<(), tycho_atomic_arbitrage::ArbitrageError>(())
+//! ```
+
+use crate::errors::{Result, UtilityError};
+use std::time::{Duration, Instant};
+
+/// Share of the block time, in percent, granted to the search by default.
+///
+/// The remainder is left for signing, bundle submission and relay propagation.
+const DEFAULT_BLOCK_TIME_SHARE_PERCENT: u32 = 66;
+
+/// Limits for a single per-block arbitrage search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchBudget {
+    /// Maximum wall-clock time the search may take
+    pub wall_clock: Duration,
+    /// Maximum number of candidate paths to build, if limited
+    pub max_paths: Option<usize>,
+    /// Maximum number of simulations to run, if limited
+    pub max_simulations: Option<usize>,
+}
+
+impl SearchBudget {
+    /// Create a budget with the given wall-clock limit and no count limits.
+    pub fn new(wall_clock: Duration) -> Self {
+        Self {
+            wall_clock,
+            max_paths: None,
+            max_simulations: None,
+        }
+    }
+
+    /// Create a budget without any limits.
+    pub fn unlimited() -> Self {
+        Self::new(Duration::MAX)
+    }
+
+    /// Create a budget from a chain's block time.
+    ///
+    /// The search is granted roughly two thirds of the block time, leaving the rest
+    /// for submission.
+    pub fn from_block_time(block_time: Duration) -> Self {
+        Self::new(block_time * DEFAULT_BLOCK_TIME_SHARE_PERCENT / 100)
+    }
+
+    /// Create a budget from the block time of a supported chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no block time is known for the chain.
+    pub fn for_chain(chain: &str) -> Result<Self> {
+        let block_time = crate::utils::block_time(chain)
+            .ok_or_else(|| UtilityError::UnsupportedChain {
+                chain: chain.to_string(),
+            })?;
+
+        Ok(Self::from_block_time(block_time))
+    }
+
+    /// Limit the number of candidate paths built per search.
+    pub fn with_max_paths(mut self, max_paths: usize) -> Self {
+        self.max_paths = Some(max_paths);
+        self
+    }
+
+    /// Limit the number of simulations run per search.
+    pub fn with_max_simulations(mut self, max_simulations: usize) -> Self {
+        self.max_simulations = Some(max_simulations);
+        self
+    }
+
+    /// Start the budget now.
+    pub fn start(&self) -> SearchDeadline {
+        self.start_at(Instant::now())
+    }
+
+    /// Start the budget at a given instant, e.g. when the block update was received.
+    pub fn start_at(&self, started_at: Instant) -> SearchDeadline {
+        SearchDeadline {
+            budget: *self,
+            started_at,
+        }
+    }
+}
+
+impl Default for SearchBudget {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+/// A running search budget.
+///
+/// Deadlines are cheap to copy and can be shared across threads, so every
+/// pipeline stage can check the same deadline independently.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchDeadline {
+    budget: SearchBudget,
+    started_at: Instant,
+}
+
+impl SearchDeadline {
+    /// The budget this deadline was started from.
+    pub fn budget(&self) -> &SearchBudget {
+        &self.budget
+    }
+
+    /// Time elapsed since the search started.
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Time left before the deadline, zero once expired.
+    pub fn remaining(&self) -> Duration {
+        self.budget.wall_clock.saturating_sub(self.elapsed())
+    }
+
+    /// Whether the wall-clock budget has been used up.
+    pub fn is_expired(&self) -> bool {
+        self.elapsed() >= self.budget.wall_clock
+    }
+
+    /// Maximum number of candidate paths, `usize::MAX` if unlimited.
+    pub fn max_paths(&self) -> usize {
+        self.budget.max_paths.unwrap_or(usize::MAX)
+    }
+
+    /// Maximum number of simulations, `usize::MAX` if unlimited.
+    pub fn max_simulations(&self) -> usize {
+        self.budget.max_simulations.unwrap_or(usize::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_from_block_time() {
+        let budget = SearchBudget::from_block_time(Duration::from_secs(12));
+        assert_eq!(budget.wall_clock, Duration::from_millis(7_920));
+        assert_eq!(budget.max_paths, None);
+        assert_eq!(budget.max_simulations, None);
+
+        assert!(SearchBudget::for_chain("ethereum").is_ok());
+        assert!(SearchBudget::for_chain("invalid_chain").is_err());
+    }
+
+    #[test]
+    fn test_deadline_expiry() {
+        let expired = SearchBudget::new(Duration::ZERO).start();
+        assert!(expired.is_expired());
+        assert_eq!(expired.remaining(), Duration::ZERO);
+
+        let unlimited = SearchBudget::unlimited().with_max_simulations(3).start();
+        assert!(!unlimited.is_expired());
+        assert_eq!(unlimited.max_paths(), usize::MAX);
+        assert_eq!(unlimited.max_simulations(), 3);
+    }
+}
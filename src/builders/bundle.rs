@@ -1,12 +1,14 @@
 //! Builder pattern for TxExecutor
 
-use crate::bundle::TxExecutor;
+use crate::bundle::{ExecutionMode, SubmissionMode, TxExecutor};
 use crate::config::ArbitrageConfig;
 use crate::errors::Result;
 
 /// Builder for creating TxExecutor instances with a fluent API
 pub struct TxExecutorBuilder {
     config: Option<ArbitrageConfig>,
+    submission_mode: SubmissionMode,
+    execution_mode: ExecutionMode,
 }
 
 impl TxExecutorBuilder {
@@ -14,6 +16,8 @@ impl TxExecutorBuilder {
     pub fn new() -> Self {
         Self {
             config: None,
+            submission_mode: SubmissionMode::default(),
+            execution_mode: ExecutionMode::default(),
         }
     }
 
@@ -23,6 +27,18 @@ impl TxExecutorBuilder {
         self
     }
 
+    /// Set how portfolios with several opportunities are submitted
+    pub fn with_submission_mode(mut self, submission_mode: SubmissionMode) -> Self {
+        self.submission_mode = submission_mode;
+        self
+    }
+
+    /// Set whether signed bundles are submitted or only recorded
+    pub fn with_execution_mode(mut self, execution_mode: ExecutionMode) -> Self {
+        self.execution_mode = execution_mode;
+        self
+    }
+
     /// Build the TxExecutor
     /// 
     /// # Errors
@@ -34,7 +50,9 @@ impl TxExecutorBuilder {
                 message: "Configuration is required to build TxExecutor".to_string(),
             })?;
 
-        TxExecutor::from_config(config)
+        Ok(TxExecutor::from_config(config)?
+            .with_submission_mode(self.submission_mode)
+            .with_execution_mode(self.execution_mode))
     }
 }
 
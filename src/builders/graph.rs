@@ -1,13 +1,19 @@
 //! Builder pattern for TradingGraph
 
-use crate::graph::TradingGraph;
+use crate::config::ExchangeFilter;
+use crate::graph::{GraphLoadStatistics, TradingGraph};
 use crate::errors::Result;
 use tycho_common::Bytes;
+use tycho_simulation::protocol::models::ProtocolComponent;
 
 /// Builder for creating TradingGraph instances with a fluent API
 pub struct TradingGraphBuilder {
     tokens: Vec<Bytes>,
     pools: Vec<(Bytes, [usize; 2])>,
+    max_pool_tokens: Option<usize>,
+    source_tokens: Vec<Bytes>,
+    exchange_filter: Option<ExchangeFilter>,
+    components: Vec<(Bytes, ProtocolComponent)>,
 }
 
 impl TradingGraphBuilder {
@@ -16,6 +22,30 @@ impl TradingGraphBuilder {
         Self {
             tokens: Vec::new(),
             pools: Vec::new(),
+            max_pool_tokens: None,
+            source_tokens: Vec::new(),
+            exchange_filter: None,
+            components: Vec::new(),
+        }
+    }
+
+    /// Create a builder loading protocol components, e.g. from the initial Tycho snapshot
+    ///
+    /// The components are added in bulk with
+    /// [`TradingGraph::add_protocol_components`] after the tokens and pools of
+    /// the builder, so loading thousands of components avoids the cost of
+    /// adding them one by one.
+    ///
+    /// # Arguments
+    ///
+    /// * `components` - Pool addresses and their protocol components
+    pub fn from_components<I>(components: I) -> Self
+    where
+        I: IntoIterator<Item = (Bytes, ProtocolComponent)>,
+    {
+        Self {
+            components: components.into_iter().collect(),
+            ..Self::new()
         }
     }
 
@@ -66,13 +96,57 @@ impl TradingGraphBuilder {
         self
     }
 
+    /// Accept protocol components with up to `max_pool_tokens` tokens
+    ///
+    /// See [`TradingGraph::with_max_pool_tokens`].
+    pub fn with_max_pool_tokens(mut self, max_pool_tokens: usize) -> Self {
+        self.max_pool_tokens = Some(max_pool_tokens);
+        self
+    }
+
+    /// Set the tokens arbitrage cycles start from
+    ///
+    /// See [`TradingGraph::with_source_tokens`].
+    pub fn with_source_tokens<I>(mut self, source_tokens: I) -> Self
+    where
+        I: IntoIterator<Item = Bytes>,
+    {
+        self.source_tokens = source_tokens.into_iter().collect();
+        self
+    }
+
+    /// Only accept protocol components of the exchanges and pools of a filter
+    ///
+    /// # Arguments
+    ///
+    /// * `exchange_filter` - The tracked exchanges, e.g. from [`ArbitrageSettings::exchange_filter`](crate::config::ArbitrageSettings::exchange_filter)
+    pub fn with_exchange_filter(mut self, exchange_filter: ExchangeFilter) -> Self {
+        self.exchange_filter = Some(exchange_filter);
+        self
+    }
+
     /// Build the TradingGraph
     /// 
     /// # Errors
     /// 
     /// Returns an error if any pool references non-existent tokens
     pub fn build(self) -> Result<TradingGraph> {
-        let mut graph = TradingGraph::new();
+        self.build_with_statistics().map(|(graph, _)| graph)
+    }
+
+    /// Build the TradingGraph and return statistics of the loaded protocol components
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any pool references non-existent tokens
+    pub fn build_with_statistics(self) -> Result<(TradingGraph, GraphLoadStatistics)> {
+        let mut graph = TradingGraph::new().with_source_tokens(self.source_tokens);
+        if let Some(max_pool_tokens) = self.max_pool_tokens {
+            graph = graph.with_max_pool_tokens(max_pool_tokens);
+        }
+        if let Some(exchange_filter) = self.exchange_filter {
+            graph = graph.with_exchange_filter(exchange_filter);
+        }
 
         // Add all tokens first
         for address in self.tokens {
@@ -90,7 +164,15 @@ impl TradingGraphBuilder {
             graph.add_pool(address, token_indices)?;
         }
 
-        Ok(graph)
+        // Finally load the protocol components in bulk
+        let statistics = if self.components.is_empty() {
+            GraphLoadStatistics::default()
+        } else {
+            let components = self.components.iter().map(|(address, component)| (address.clone(), component));
+            graph.add_protocol_components(components)?.1
+        };
+
+        Ok((graph, statistics))
     }
 }
 
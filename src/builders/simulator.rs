@@ -1,10 +1,33 @@
 //! Builder pattern for Simulator
 
-use crate::simulation::Simulator;
+use crate::simulation::{
+    AccuracyTracker, AllowanceCache, FixedSlippage, GasEstimator, PermitCache, ProfitGuard, RouterEncoder,
+    SimulationBlock, SimulationCache, SimulationFlags, Simulator, SlippagePolicy, TraceCapture,
+};
+use crate::chain::HeadTracker;
+use crate::rpc::ProviderPool;
+use crate::utils::fees::BaseFeePredictor;
+use std::sync::Arc;
+use tycho_execution::encoding::models::UserTransferType;
 
 /// Builder for creating Simulator instances with a fluent API
 pub struct SimulatorBuilder {
     config: crate::config::ArbitrageConfig,
+    allowance_cache: Option<Arc<AllowanceCache>>,
+    permit_cache: Option<Arc<PermitCache>>,
+    accuracy_tracker: Option<Arc<AccuracyTracker>>,
+    simulation_block: Option<Arc<SimulationBlock>>,
+    transfer_type: UserTransferType,
+    slippage_policy: Option<Arc<dyn SlippagePolicy>>,
+    profit_guard: Option<ProfitGuard>,
+    gas_estimator: Option<GasEstimator>,
+    base_fee_predictor: Option<Arc<BaseFeePredictor>>,
+    result_cache: Option<Arc<SimulationCache>>,
+    simulation_flags: SimulationFlags,
+    provider_pool: Option<Arc<ProviderPool>>,
+    head_tracker: Option<Arc<HeadTracker>>,
+    router_encoder: Option<Arc<dyn RouterEncoder>>,
+    trace_capture: Option<TraceCapture>,
 }
 
 impl SimulatorBuilder {
@@ -16,13 +39,202 @@ impl SimulatorBuilder {
     pub fn from_config(config: &crate::config::ArbitrageConfig) -> Self {
         Self {
             config: config.clone(),
+            allowance_cache: None,
+            permit_cache: None,
+            accuracy_tracker: None,
+            simulation_block: None,
+            transfer_type: UserTransferType::TransferFromPermit2,
+            slippage_policy: None,
+            profit_guard: None,
+            gas_estimator: None,
+            base_fee_predictor: None,
+            result_cache: None,
+            simulation_flags: SimulationFlags::default(),
+            provider_pool: None,
+            head_tracker: None,
+            router_encoder: None,
+            trace_capture: None,
         }
     }
 
+    /// Skip approval transactions when the cached Permit2 allowance suffices
+    pub fn with_allowance_cache(mut self, cache: Arc<AllowanceCache>) -> Self {
+        self.allowance_cache = Some(cache);
+        self
+    }
+
+    /// Reuse Permit2 signatures across paths and blocks
+    pub fn with_permit_cache(mut self, cache: Arc<PermitCache>) -> Self {
+        self.permit_cache = Some(cache);
+        self
+    }
+
+    /// Track the accuracy of local swap simulations per protocol system
+    pub fn with_accuracy_tracker(mut self, tracker: Arc<AccuracyTracker>) -> Self {
+        self.accuracy_tracker = Some(tracker);
+        self
+    }
+
+    /// Pin simulations to a historical block, e.g. when replaying recorded updates
+    pub fn with_simulation_block(mut self, block: Arc<SimulationBlock>) -> Self {
+        self.simulation_block = Some(block);
+        self
+    }
+
+    /// Set how the router obtains the input tokens
+    ///
+    /// Use `TransferFrom` with standard ERC-20 approvals of the router, or `None`
+    /// when the router is pre-funded, to avoid Permit2 signing on every path.
+    pub fn with_transfer_type(mut self, transfer_type: UserTransferType) -> Self {
+        self.transfer_type = transfer_type;
+        self
+    }
+
+    /// Set the default slippage tolerance in BPS
+    ///
+    /// Overrides the slippage of the configuration's settings.
+    pub fn with_slippage_bps(self, slippage_bps: u64) -> Self {
+        self.with_slippage_policy(Arc::new(FixedSlippage::new(slippage_bps)))
+    }
+
+    /// Choose the slippage tolerance of each path with a policy
+    ///
+    /// Overrides the fixed slippage of the configuration's settings.
+    pub fn with_slippage_policy(mut self, policy: Arc<dyn SlippagePolicy>) -> Self {
+        self.slippage_policy = Some(policy);
+        self
+    }
+
+    /// Revert paths whose output does not cover their input, gas cost and a minimum profit
+    pub fn with_profit_guard(mut self, guard: ProfitGuard) -> Self {
+        self.profit_guard = Some(guard);
+        self
+    }
+
+    /// Attach the call tree of failed or anomalous simulations, traced with the `callTracer`
+    pub fn with_trace_capture(mut self, capture: TraceCapture) -> Self {
+        self.trace_capture = Some(capture);
+        self
+    }
+
+    /// Set gas limits from simulated gas usage plus a safety margin
+    ///
+    /// Falls back to `eth_estimateGas` for calls without simulated gas usage.
+    pub fn with_gas_estimator(mut self, estimator: GasEstimator) -> Self {
+        self.gas_estimator = Some(estimator);
+        self
+    }
+
+    /// Price simulated transactions with the predicted base fee of the next block
+    pub fn with_base_fee_predictor(mut self, predictor: Arc<BaseFeePredictor>) -> Self {
+        self.base_fee_predictor = Some(predictor);
+        self
+    }
+
+    /// Price simulated transactions with the next base fee of the tracked chain head
+    pub fn with_head_tracker(mut self, tracker: Arc<HeadTracker>) -> Self {
+        self.head_tracker = Some(tracker);
+        self
+    }
+
+    /// Reuse results of paths simulated again with unchanged pools, amounts and nonce
+    pub fn with_result_cache(mut self, cache: Arc<SimulationCache>) -> Self {
+        self.result_cache = Some(cache);
+        self
+    }
+
+    /// Send simulations through a pool of RPC endpoints with failover and optional racing
+    pub fn with_provider_pool(mut self, pool: Arc<ProviderPool>) -> Self {
+        self.provider_pool = Some(pool);
+        self
+    }
+
+    /// Report native token transfers as logs, enabled by default
+    pub fn with_trace_transfers(mut self, trace_transfers: bool) -> Self {
+        self.simulation_flags.trace_transfers = trace_transfers;
+        self
+    }
+
+    /// Validate simulated transactions like a real block would, enabled by default
+    pub fn with_validation(mut self, validation: bool) -> Self {
+        self.simulation_flags.validation = validation;
+        self
+    }
+
+    /// Return full transactions from simulations instead of hashes, enabled by default
+    pub fn with_full_transactions(mut self, return_full_transactions: bool) -> Self {
+        self.simulation_flags.return_full_transactions = return_full_transactions;
+        self
+    }
+
+    /// Execute paths through a custom router contract
+    ///
+    /// Overrides the router address of the configuration.
+    pub fn with_router_address(mut self, router_address: alloy::primitives::Address) -> Self {
+        self.config.settings.router_address = Some(router_address);
+        self
+    }
+
+    /// Encode router calls with a custom calldata layout
+    pub fn with_router_encoder(mut self, encoder: Arc<dyn RouterEncoder>) -> Self {
+        self.router_encoder = Some(encoder);
+        self
+    }
+
+    /// Send path outputs to a separate receiver, e.g. a cold wallet
+    ///
+    /// Overrides the receiver of the configuration.
+    pub fn with_receiver_address(mut self, receiver_address: alloy::primitives::Address) -> Self {
+        self.config.receiver_address = Some(receiver_address);
+        self
+    }
+
     /// Build the Simulator
     /// 
     /// Creates a new Simulator instance using the provided configuration.
     pub fn build(self) -> Simulator {
-        Simulator::from_config(&self.config)
+        let mut simulator = Simulator::from_config(&self.config)
+            .with_transfer_type(self.transfer_type)
+            .with_simulation_flags(self.simulation_flags);
+        if let Some(cache) = self.allowance_cache {
+            simulator = simulator.with_allowance_cache(cache);
+        }
+        if let Some(cache) = self.permit_cache {
+            simulator = simulator.with_permit_cache(cache);
+        }
+        if let Some(tracker) = self.accuracy_tracker {
+            simulator = simulator.with_accuracy_tracker(tracker);
+        }
+        if let Some(block) = self.simulation_block {
+            simulator = simulator.with_simulation_block(block);
+        }
+        if let Some(policy) = self.slippage_policy {
+            simulator = simulator.with_slippage_policy(policy);
+        }
+        if let Some(guard) = self.profit_guard {
+            simulator = simulator.with_profit_guard(guard);
+        }
+        if let Some(capture) = self.trace_capture {
+            simulator = simulator.with_trace_capture(capture);
+        }
+        if let Some(estimator) = self.gas_estimator {
+            simulator = simulator.with_gas_estimator(estimator);
+        }
+        if let Some(predictor) = self.base_fee_predictor {
+            simulator = simulator.with_base_fee_predictor(predictor);
+        }
+        if let Some(cache) = self.result_cache {
+            simulator = simulator.with_result_cache(cache);
+        }
+        if let Some(pool) = self.provider_pool {
+            simulator = simulator.with_provider_pool(pool);
+        }
+        if let Some(tracker) = self.head_tracker {
+            simulator = simulator.with_head_tracker(tracker);
+        }
+        if let Some(encoder) = self.router_encoder {
+            simulator = simulator.with_router_encoder(encoder);
+        }
+        simulator
     }
 }
@@ -0,0 +1,245 @@
+//! Checks run on every opportunity right before its bundle is submitted.
+//!
+//! Bundles are priced against the pool states of the last block. A large swap
+//! waiting in the mempool on one of the path's pools can move that pool before
+//! the bundle lands, so the bundle reverts or, worse, is sandwiched around the
+//! pending swap. Paths through several protocols are exposed on every hop.
+//!
+//! `PreSubmissionCheck` implementations decide whether a path may still be
+//! submitted. `NoopCheck` accepts everything and is used when no mempool data
+//! is available; `MempoolCheck` rejects paths whose pools are targeted by large
+//! pending swaps that the caller feeds it from its mempool source.
+
+use crate::errors::{BundleError, Result};
+use crate::path::{PathExt, SwapExt};
+use crate::utils::convert;
+use alloy::primitives::B256;
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tycho_common::Bytes;
+
+/// Decides whether an opportunity may be submitted.
+pub trait PreSubmissionCheck: Send + Sync {
+    /// Check a path right before it is submitted for `target_block`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing why the path must not be submitted.
+    fn check(&self, path: &PathExt, target_block: u64) -> Result<()>;
+}
+
+/// Accepts every path.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopCheck;
+
+impl PreSubmissionCheck for NoopCheck {
+    fn check(&self, _path: &PathExt, _target_block: u64) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A swap seen in the mempool that has not been included yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingSwap {
+    /// Hash of the pending transaction
+    pub tx_hash: B256,
+    /// The pool the swap trades on
+    pub pool: Bytes,
+    /// The token sold into the pool
+    pub token_in: Bytes,
+    /// The amount sold into the pool
+    pub amount_in: BigUint,
+    /// The block at which the swap was first seen
+    pub seen_at: u64,
+}
+
+/// Rejects paths whose pools are targeted by large pending swaps.
+///
+/// A pending swap is large when its input is at least `min_relative_size`
+/// times the amount our own swap trades of the same token on that pool.
+/// Pending swaps in tokens our swap does not trade cannot be compared and
+/// always count as large. Swaps are forgotten after `max_age_blocks` blocks,
+/// by which time they were either included or dropped.
+#[derive(Debug)]
+pub struct MempoolCheck {
+    pending: RwLock<HashMap<Bytes, Vec<PendingSwap>>>,
+    min_relative_size: f64,
+    max_age_blocks: u64,
+}
+
+impl MempoolCheck {
+    /// Create a check rejecting pending swaps of at least 10% of our size, forgotten after 2 blocks.
+    pub fn new() -> Self {
+        Self {
+            pending: RwLock::new(HashMap::new()),
+            min_relative_size: 0.1,
+            max_age_blocks: 2,
+        }
+    }
+
+    /// Set the size of a pending swap relative to ours from which it conflicts.
+    pub fn with_min_relative_size(mut self, min_relative_size: f64) -> Self {
+        self.min_relative_size = min_relative_size.max(0.0);
+        self
+    }
+
+    /// Set the number of blocks after which pending swaps are forgotten.
+    pub fn with_max_age_blocks(mut self, max_age_blocks: u64) -> Self {
+        self.max_age_blocks = max_age_blocks;
+        self
+    }
+
+    /// Record a swap seen in the mempool.
+    pub fn record(&self, swap: PendingSwap) {
+        if let Ok(mut pending) = self.pending.write() {
+            pending.entry(swap.pool.clone()).or_default().push(swap);
+        }
+    }
+
+    /// Forget a pending transaction, e.g. once it was included or replaced.
+    pub fn remove(&self, tx_hash: &B256) {
+        if let Ok(mut pending) = self.pending.write() {
+            pending.retain(|_, swaps| {
+                swaps.retain(|swap| swap.tx_hash != *tx_hash);
+                !swaps.is_empty()
+            });
+        }
+    }
+
+    /// Forget pending swaps that are too old to still matter at `block_number`.
+    pub fn prune(&self, block_number: u64) {
+        if let Ok(mut pending) = self.pending.write() {
+            pending.retain(|_, swaps| {
+                swaps.retain(|swap| !self.is_expired(swap, block_number));
+                !swaps.is_empty()
+            });
+        }
+    }
+
+    /// The number of tracked pending swaps.
+    pub fn len(&self) -> usize {
+        self.pending
+            .read()
+            .map(|pending| pending.values().map(Vec::len).sum())
+            .unwrap_or(0)
+    }
+
+    /// Whether no pending swaps are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn is_expired(&self, swap: &PendingSwap, block_number: u64) -> bool {
+        block_number > swap.seen_at.saturating_add(self.max_age_blocks)
+    }
+
+    /// Whether a pending swap moves the pool enough to invalidate our swap.
+    fn is_large(&self, pending: &PendingSwap, swap: &SwapExt) -> bool {
+        let reference = if pending.token_in == swap.token_in.address {
+            &swap.amount_in
+        } else if pending.token_in == swap.token_out.address {
+            &swap.amount_out
+        } else {
+            return true;
+        };
+
+        convert::biguint_to_f64(&pending.amount_in) >= self.min_relative_size * convert::biguint_to_f64(reference)
+    }
+}
+
+impl Default for MempoolCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PreSubmissionCheck for MempoolCheck {
+    fn check(&self, path: &PathExt, target_block: u64) -> Result<()> {
+        let Ok(pending) = self.pending.read() else {
+            return Ok(());
+        };
+
+        for swap in path.iter() {
+            let Some(pending_swaps) = pending.get(&swap.pool_comp.id) else {
+                continue;
+            };
+            let conflict = pending_swaps
+                .iter()
+                .filter(|pending| !self.is_expired(pending, target_block))
+                .find(|pending| self.is_large(pending, swap));
+
+            if let Some(conflict) = conflict {
+                return Err(BundleError::PendingSwapConflict {
+                    pool: swap.pool_comp.id.to_string(),
+                    tx_hash: conflict.tx_hash.to_string(),
+                }.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{cyclic_path, MockProtocolSim};
+    use crate::errors::ArbitrageError;
+    use crate::path::PathExecutor;
+    use std::str::FromStr;
+
+    // Two hops: 0x0001 -> 0x0002 on 0x1001, back on 0x1002
+    fn two_hop_path(amount: u32) -> PathExt {
+        let tokens: Vec<Bytes> = ["0x0001", "0x0002"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        let pool = Bytes::from_str("0x1001").unwrap();
+        let path = cyclic_path(&tokens, &pool, &MockProtocolSim::new()).unwrap();
+        PathExecutor::new().execute_with_amount(&path, BigUint::from(amount)).unwrap()
+    }
+
+    fn pending_swap(pool: &str, token_in: &str, amount_in: u32, seen_at: u64) -> PendingSwap {
+        PendingSwap {
+            tx_hash: B256::random(),
+            pool: Bytes::from_str(pool).unwrap(),
+            token_in: Bytes::from_str(token_in).unwrap(),
+            amount_in: BigUint::from(amount_in),
+            seen_at,
+        }
+    }
+
+    #[test]
+    fn test_rejects_paths_with_large_pending_swaps() {
+        let path = two_hop_path(1_000);
+        let check = MempoolCheck::new().with_min_relative_size(0.5);
+        assert!(NoopCheck.check(&path, 11).is_ok());
+
+        // Small swaps and swaps on unrelated pools do not conflict
+        check.record(pending_swap("0x1002", "0x0002", 100, 10));
+        check.record(pending_swap("0x1003", "0x0002", 5_000, 10));
+        assert!(check.check(&path, 11).is_ok());
+
+        // A large swap on the second hop invalidates the whole path
+        let large = pending_swap("0x1002", "0x0001", 600, 10);
+        check.record(large.clone());
+        assert!(matches!(
+            check.check(&path, 11),
+            Err(ArbitrageError::Bundle(BundleError::PendingSwapConflict { .. }))
+        ));
+
+        check.remove(&large.tx_hash);
+        assert!(check.check(&path, 11).is_ok());
+    }
+
+    #[test]
+    fn test_forgets_old_pending_swaps() {
+        let path = two_hop_path(1_000);
+        let check = MempoolCheck::new().with_max_age_blocks(2);
+        check.record(pending_swap("0x1001", "0x0001", 1_000, 10));
+
+        assert!(check.check(&path, 12).is_err());
+        assert!(check.check(&path, 13).is_ok());
+
+        check.prune(13);
+        assert!(check.is_empty());
+    }
+}
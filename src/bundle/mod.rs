@@ -3,38 +3,75 @@
 //! This module provides the core bundle functionality:
 //! - `Bundle`: A collection of transactions to be executed atomically
 //! - `BundleSubmission`: Result of submitting a bundle to relayers
-//! - `TxExecutor`: High-level interface for executing arbitrage transactions
+//! - `RetryPolicy`: How failed submissions to relayers are retried
+//! - `TxExecutor`: High-level interface for executing arbitrage transactions, submitting every opportunity once
+//! - `SubmissionMode`: How multiple opportunities of one block are submitted
+//! - `ExecutionMode`: Whether signed bundles are submitted or only recorded
+//! - `PreSubmissionCheck`: Last checks of an opportunity before it is submitted
+//! - `TxBuilder`: Builds the transaction envelopes signed into bundles
+//! - `WalletPool`: Rotates several executor wallets with their own nonces and balances
+//! - `RevertedBundles`: Remembers bundles that reverted on chain, so they are not submitted again
+//! - `SubmissionScheduler`: When bundles are submitted within the slot of their target block
 
+pub mod check;
 pub mod relay;
+pub mod retry;
+pub mod reverted;
+pub mod schedule;
+pub mod tx;
+pub mod wallet;
 
 // Re-export relay types for convenience
-pub use relay::RelayClient;
+pub use check::{MempoolCheck, NoopCheck, PendingSwap, PreSubmissionCheck};
+pub use relay::{RelayClient, RelayDialect, RelayResponse};
+pub use retry::RetryPolicy;
+pub use reverted::{RevertedBundles, DEFAULT_REVERT_MEMORY_BLOCKS};
+pub use schedule::{SubmissionScheduler, SubmissionTiming};
+pub use tx::{EnvelopeTxBuilder, TxBuilder};
+pub use wallet::{SelectedWallet, WalletAccount, WalletPool, WalletSelection};
 
 use alloy::consensus::{SignableTransaction, TxEnvelope};
 use alloy::eips::Encodable2718;
-use alloy::network::TxSignerSync;
-use alloy::primitives::U256;
+use alloy::primitives::{keccak256, Address, B256, U256};
 use alloy::rpc::types::TransactionRequest;
 use alloy::signers::local::PrivateKeySigner;
+use crate::chain::{FeeMarket, HeadTracker};
 use crate::config::ArbitrageConfig;
-use crate::errors::{BundleError, Result};
-use std::sync::Arc;
+use crate::errors::{BundleError, OpportunityId, Result};
+use crate::path::{PathExt, ProtocolAllowlist};
+use reverted::SubmittedBundle;
+use crate::signer::ExecutorSigner;
+use crate::utils::fees::BaseFeePredictor;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Number of blocks after which submitted opportunities are forgotten.
+///
+/// An opportunity found this many blocks before the target block is stale, so
+/// a duplicate of it cannot land anyway.
+const SUBMITTED_RETENTION_BLOCKS: u64 = 64;
 
 /// A bundle submission result from a relayer.
 #[derive(Debug, Clone)]
 pub struct BundleSubmission {
     target_block: u64,
-    bundle_hash: Option<String>,
+    bundle_hash: Option<B256>,
     relayer_url: String,
     success: bool,
     error: Option<String>,
+    relay_response: Option<RelayResponse>,
+    dry_run_bundle: Option<Bundle>,
+    opportunity_ids: Vec<OpportunityId>,
+    scheduled: bool,
 }
 
 impl BundleSubmission {
     /// Create a new bundle submission result.
     pub fn new(
         target_block: u64,
-        bundle_hash: Option<String>,
+        bundle_hash: Option<B256>,
         relayer_url: String,
         success: bool,
         error: Option<String>,
@@ -45,17 +82,78 @@ impl BundleSubmission {
             relayer_url,
             success,
             error,
+            relay_response: None,
+            dry_run_bundle: None,
+            opportunity_ids: Vec::new(),
+            scheduled: false,
+        }
+    }
+
+    /// Create the result of a bundle that was built but not submitted.
+    ///
+    /// Dry runs are never successful, as no relayer received the bundle.
+    pub fn dry_run(bundle: Bundle) -> Self {
+        Self {
+            target_block: bundle.target_block(),
+            bundle_hash: None,
+            relayer_url: "dry-run".to_string(),
+            success: false,
+            error: None,
+            relay_response: None,
+            opportunity_ids: bundle.opportunity_ids().to_vec(),
+            dry_run_bundle: Some(bundle),
+            scheduled: false,
+        }
+    }
+
+    /// Create the result of a bundle held until the submission offset of its slot.
+    ///
+    /// Held bundles count as successful, as they are submitted to all relayers
+    /// in the background once the hold time has passed.
+    pub fn scheduled(bundle: &Bundle) -> Self {
+        Self {
+            target_block: bundle.target_block(),
+            bundle_hash: None,
+            relayer_url: "scheduled".to_string(),
+            success: true,
+            error: None,
+            relay_response: None,
+            dry_run_bundle: None,
+            opportunity_ids: bundle.opportunity_ids().to_vec(),
+            scheduled: true,
         }
     }
 
+    /// Attach the parsed response of the relayer.
+    pub fn with_relay_response(mut self, relay_response: RelayResponse) -> Self {
+        self.relay_response = Some(relay_response);
+        self
+    }
+
+    /// Attach the opportunities executed by the submitted bundle.
+    pub fn with_opportunity_ids(mut self, opportunity_ids: Vec<OpportunityId>) -> Self {
+        self.opportunity_ids = opportunity_ids;
+        self
+    }
+
+    /// Get the opportunities executed by the submitted bundle, if known.
+    pub fn opportunity_ids(&self) -> &[OpportunityId] {
+        &self.opportunity_ids
+    }
+
     /// Get the target block number for this submission.
     pub fn target_block(&self) -> u64 {
         self.target_block
     }
 
-    /// Get the bundle hash if the submission was successful.
-    pub fn bundle_hash(&self) -> Option<&str> {
-        self.bundle_hash.as_deref()
+    /// Get the bundle hash if the relayer returned one.
+    pub fn bundle_hash(&self) -> Option<B256> {
+        self.bundle_hash
+    }
+
+    /// Get the parsed response of the relayer, if it answered.
+    pub fn relay_response(&self) -> Option<&RelayResponse> {
+        self.relay_response.as_ref()
     }
 
     /// Get the relayer URL this bundle was submitted to.
@@ -72,26 +170,62 @@ impl BundleSubmission {
     pub fn error(&self) -> Option<&str> {
         self.error.as_deref()
     }
+
+    /// Check if the bundle was only recorded in dry-run mode.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run_bundle.is_some()
+    }
+
+    /// Check if the bundle is held and submitted later in the slot.
+    pub fn is_scheduled(&self) -> bool {
+        self.scheduled
+    }
+
+    /// Get the bundle that would have been submitted in dry-run mode.
+    pub fn dry_run_bundle(&self) -> Option<&Bundle> {
+        self.dry_run_bundle.as_ref()
+    }
 }
 
 /// A bundle of transactions to be executed atomically.
 #[derive(Debug, Clone)]
 pub struct Bundle {
-    transactions: [String; 2],
+    transactions: Vec<String>,
     target_block: u64,
+    bribe: U256,
+    opportunity_ids: Vec<OpportunityId>,
 }
 
 impl Bundle {
     /// Create a new bundle with the given transactions and target block.
-    pub fn new(transactions: [String; 2], target_block: u64) -> Self {
+    pub fn new(transactions: Vec<String>, target_block: u64) -> Self {
         Self {
             transactions,
             target_block,
+            bribe: U256::ZERO,
+            opportunity_ids: Vec::new(),
         }
     }
 
+    /// Attach the opportunities the bundle executes.
+    pub fn with_opportunity_ids(mut self, opportunity_ids: Vec<OpportunityId>) -> Self {
+        self.opportunity_ids = opportunity_ids;
+        self
+    }
+
+    /// Get the opportunities the bundle executes, if known.
+    pub fn opportunity_ids(&self) -> &[OpportunityId] {
+        &self.opportunity_ids
+    }
+
+    /// Set the total priority fee per gas paid by the bundle's transactions.
+    pub fn with_bribe(mut self, bribe: U256) -> Self {
+        self.bribe = bribe;
+        self
+    }
+
     /// Get the transactions in this bundle.
-    pub fn transactions(&self) -> &[String; 2] {
+    pub fn transactions(&self) -> &[String] {
         &self.transactions
     }
 
@@ -104,12 +238,155 @@ impl Bundle {
     pub fn transaction_count(&self) -> usize {
         self.transactions.len()
     }
+
+    /// Get the total priority fee per gas paid by the bundle's transactions.
+    pub fn bribe(&self) -> U256 {
+        self.bribe
+    }
+
+    /// Get the hashes of the bundle's transactions, as they appear in receipts.
+    ///
+    /// Transactions that are not hex encoded have no receipt hash and are skipped.
+    pub fn transaction_hashes(&self) -> Vec<B256> {
+        self.transactions
+            .iter()
+            .filter_map(|transaction| match hex::decode(transaction.trim_start_matches("0x")) {
+                Ok(encoded) => Some(keccak256(encoded)),
+                Err(e) => {
+                    tracing::warn!(
+                        error = %e,
+                        "Skipping undecodable transaction of bundle"
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Get the hash identifying the bundle's signed transactions, independent of its target block.
+    ///
+    /// Transactions that are not hex encoded are hashed as they are, so every
+    /// transaction contributes to the hash.
+    pub fn content_hash(&self) -> B256 {
+        let hashes: Vec<B256> = self
+            .transactions
+            .iter()
+            .map(|transaction| match hex::decode(transaction.trim_start_matches("0x")) {
+                Ok(encoded) => keccak256(encoded),
+                Err(_) => keccak256(transaction.as_bytes()),
+            })
+            .collect();
+        keccak256(hashes.concat())
+    }
+}
+
+/// How the opportunities of a portfolio are submitted to relayers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SubmissionMode {
+    /// Submit every opportunity as its own bundle
+    ///
+    /// Nonces are sequential across bundles, so a later bundle can only land
+    /// together with all bundles before it.
+    #[default]
+    Separate,
+    /// Submit all opportunities as one bundle that lands entirely or not at all
+    Combined,
+}
+
+impl FromStr for SubmissionMode {
+    type Err = BundleError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "separate" => Ok(SubmissionMode::Separate),
+            "combined" => Ok(SubmissionMode::Combined),
+            _ => Err(BundleError::InvalidConfiguration {
+                message: format!("Unknown submission mode: {}", s),
+            }),
+        }
+    }
+}
+
+/// Whether signed bundles are submitted to relayers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionMode {
+    /// Submit bundles to all configured relayers
+    #[default]
+    Live,
+    /// Sign and build bundles, but only return them instead of submitting
+    ///
+    /// Used to validate a configuration on live market data without risking funds.
+    DryRun,
+}
+
+impl FromStr for ExecutionMode {
+    type Err = BundleError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "live" => Ok(ExecutionMode::Live),
+            "dry-run" | "dryrun" => Ok(ExecutionMode::DryRun),
+            _ => Err(BundleError::InvalidConfiguration {
+                message: format!("Unknown execution mode: {}", s),
+            }),
+        }
+    }
+}
+
+/// Transactions and expected profit of a single opportunity in a portfolio.
+#[derive(Debug, Clone)]
+pub struct BundleLeg {
+    /// Transaction requests of the opportunity, the last one pays the bribe
+    pub tx_requests: Vec<TransactionRequest>,
+    /// Expected profit of the opportunity after gas costs
+    pub profit_after_gas: U256,
+    /// The executed path, checked before submission when known
+    pub path: Option<PathExt>,
+    /// The opportunity of the leg, submitted at most once when known
+    pub opportunity_id: Option<OpportunityId>,
+}
+
+impl BundleLeg {
+    /// Create a new bundle leg.
+    pub fn new(tx_requests: Vec<TransactionRequest>, profit_after_gas: U256) -> Self {
+        Self {
+            tx_requests,
+            profit_after_gas,
+            path: None,
+            opportunity_id: None,
+        }
+    }
+
+    /// Attach the executed path, so pre-submission checks can inspect its pools.
+    pub fn with_path(mut self, path: PathExt) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// Identify the opportunity of the leg, so the executor submits it at most once.
+    pub fn with_opportunity_id(mut self, opportunity_id: OpportunityId) -> Self {
+        self.opportunity_id = Some(opportunity_id);
+        self
+    }
 }
 
 /// High-level transaction executor for arbitrage operations.
 pub struct TxExecutor {
     relay_client: Arc<RelayClient>,
     config: ArbitrageConfig,
+    submission_mode: SubmissionMode,
+    execution_mode: ExecutionMode,
+    pre_submission_check: Arc<dyn PreSubmissionCheck>,
+    execution_allowlist: ProtocolAllowlist,
+    base_fee_predictor: Option<Arc<BaseFeePredictor>>,
+    tx_builder: Arc<dyn TxBuilder>,
+    wallet_pool: Option<Arc<WalletPool>>,
+    head_tracker: Option<Arc<HeadTracker>>,
+    submission_scheduler: SubmissionScheduler,
+    submitted_opportunities: Mutex<HashSet<OpportunityId>>,
+    resubmitted_opportunities: Mutex<HashSet<OpportunityId>>,
+    reverted_bundles: Arc<RevertedBundles>,
+    submitted_bundles: Mutex<Vec<SubmittedBundle>>,
 }
 
 impl TxExecutor {
@@ -129,30 +406,351 @@ impl TxExecutor {
         };
 
         let relay_client = Arc::new(RelayClient::from_config(&config, &identity_key)?);
+        let tx_builder = EnvelopeTxBuilder::new(config.chain_profile.tx_type());
+        let execution_allowlist = config.settings.execution_allowlist();
 
         Ok(Self {
             relay_client,
             config,
+            submission_mode: SubmissionMode::default(),
+            execution_mode: ExecutionMode::default(),
+            pre_submission_check: Arc::new(NoopCheck),
+            execution_allowlist,
+            base_fee_predictor: None,
+            tx_builder: Arc::new(tx_builder),
+            wallet_pool: None,
+            head_tracker: None,
+            submission_scheduler: SubmissionScheduler::default(),
+            submitted_opportunities: Mutex::new(HashSet::new()),
+            resubmitted_opportunities: Mutex::new(HashSet::new()),
+            reverted_bundles: Arc::new(RevertedBundles::default()),
+            submitted_bundles: Mutex::new(Vec::new()),
         })
     }
 
+    /// Set how portfolios with several opportunities are submitted.
+    pub fn with_submission_mode(mut self, submission_mode: SubmissionMode) -> Self {
+        self.submission_mode = submission_mode;
+        self
+    }
+
+    /// Get the configured submission mode.
+    pub fn submission_mode(&self) -> SubmissionMode {
+        self.submission_mode
+    }
+
+    /// Set whether signed bundles are submitted or only recorded.
+    pub fn with_execution_mode(mut self, execution_mode: ExecutionMode) -> Self {
+        self.execution_mode = execution_mode;
+        self
+    }
+
+    /// Get the configured execution mode.
+    pub fn execution_mode(&self) -> ExecutionMode {
+        self.execution_mode
+    }
+
+    /// Set the check every portfolio leg with a known path has to pass before submission.
+    pub fn with_pre_submission_check(mut self, check: Arc<dyn PreSubmissionCheck>) -> Self {
+        self.pre_submission_check = check;
+        self
+    }
+
+    /// Only submit legs whose paths trade through protocols trusted for execution.
+    ///
+    /// Defaults to the allowlist of the configuration's settings. Legs without
+    /// a known path cannot be checked and are always submitted.
+    pub fn with_execution_allowlist(mut self, allowlist: ProtocolAllowlist) -> Self {
+        self.execution_allowlist = allowlist;
+        self
+    }
+
+    /// The protocols trusted for execution.
+    pub fn execution_allowlist(&self) -> &ProtocolAllowlist {
+        &self.execution_allowlist
+    }
+
+    /// Set how signed transactions are built, replacing the envelope type of the chain.
+    pub fn with_tx_builder(mut self, tx_builder: Arc<dyn TxBuilder>) -> Self {
+        self.tx_builder = tx_builder;
+        self
+    }
+
+    /// Sign transactions sent from a pool wallet with that wallet's signer.
+    ///
+    /// Transactions from any other address are signed by the configured executor.
+    pub fn with_wallet_pool(mut self, wallet_pool: Arc<WalletPool>) -> Self {
+        self.wallet_pool = Some(wallet_pool);
+        self
+    }
+
+    /// The signer of transactions sent from `from`.
+    fn signer_for(&self, from: Option<Address>) -> &ExecutorSigner {
+        from.zip(self.wallet_pool.as_ref())
+            .and_then(|(from, pool)| pool.signer(&from))
+            .unwrap_or_else(|| self.config.executor_signer())
+    }
+
+    /// Price bundles with the base fee predicted for their target block.
+    ///
+    /// The predicted base fee replaces the given one when it is higher, so
+    /// bundles for later blocks stay valid while the base fee keeps rising.
+    pub fn with_base_fee_predictor(mut self, predictor: Arc<BaseFeePredictor>) -> Self {
+        self.base_fee_predictor = Some(predictor);
+        self
+    }
+
+    /// Target bundles at blocks after the tracked chain head.
+    ///
+    /// A bundle whose target block is already on chain is retargeted at the
+    /// block after the head, and the head's next base fee prices bundles for
+    /// that block when no base fee predictor has recorded it.
+    pub fn with_head_tracker(mut self, tracker: Arc<HeadTracker>) -> Self {
+        self.head_tracker = Some(tracker);
+        self
+    }
+
+    /// Submit bundles at the time within the slot the scheduler chooses.
+    ///
+    /// Slots are derived from the timestamp of the tracked chain head, so
+    /// without a head tracker bundles are always submitted immediately.
+    pub fn with_submission_scheduler(mut self, scheduler: SubmissionScheduler) -> Self {
+        self.submission_scheduler = scheduler;
+        self
+    }
+
+    /// Get the configured submission scheduler.
+    pub fn submission_scheduler(&self) -> &SubmissionScheduler {
+        &self.submission_scheduler
+    }
+
+    /// How long until updated bundles for `target_block` may replace the submitted ones.
+    ///
+    /// # Returns
+    ///
+    /// `None` unless resubmissions are scheduled and still possible in the
+    /// slot of the target block
+    pub fn resubmission_delay(&self, target_block: u64) -> Option<Duration> {
+        let parent_timestamp = self.parent_timestamp(target_block)?;
+        self.submission_scheduler.resubmission_delay(parent_timestamp, SystemTime::now())
+    }
+
+    /// Remember reverted bundles in the given memory, e.g. one persisted across restarts.
+    pub fn with_reverted_bundles(mut self, reverted_bundles: Arc<RevertedBundles>) -> Self {
+        self.reverted_bundles = reverted_bundles;
+        self
+    }
+
+    /// Get the memory of reverted bundles.
+    pub fn reverted_bundles(&self) -> &RevertedBundles {
+        &self.reverted_bundles
+    }
+
+    /// Remember the submitted bundles containing any of the given reverted transactions.
+    ///
+    /// Byte-identical bundles are refused for the window of the memory
+    /// afterwards. Bundles submitted for blocks more than 64 blocks before
+    /// the latest submission are no longer recognized.
+    ///
+    /// # Arguments
+    ///
+    /// * `transaction_hashes` - Hashes of included transactions that reverted
+    /// * `block_number` - The block the transactions reverted in
+    ///
+    /// # Returns
+    ///
+    /// The number of submitted bundles that reverted
+    pub fn record_reverted_transactions(&self, transaction_hashes: &[B256], block_number: u64) -> usize {
+        if transaction_hashes.is_empty() {
+            return 0;
+        }
+        let submitted = self.submitted_bundles.lock().unwrap_or_else(|e| e.into_inner());
+        let reverted: Vec<&SubmittedBundle> = submitted
+            .iter()
+            .filter(|bundle| bundle.transaction_hashes.iter().any(|hash| transaction_hashes.contains(hash)))
+            .collect();
+        for bundle in &reverted {
+            tracing::warn!(
+                content_hash = %bundle.content_hash,
+                target_block = bundle.target_block,
+                block_number = block_number,
+                "Bundle reverted on chain, refusing identical resubmissions"
+            );
+            self.reverted_bundles.record(bundle.content_hash, block_number);
+        }
+        reverted.len()
+    }
+
+    /// Whether an opportunity was submitted to relayers by this executor.
+    pub fn was_submitted(&self, opportunity_id: &OpportunityId) -> bool {
+        self.submitted_opportunities
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(opportunity_id)
+    }
+
+    /// Mark an opportunity as submitted for `target_block`.
+    ///
+    /// # Returns
+    ///
+    /// Whether the opportunity was not submitted before and may be submitted now
+    fn claim_opportunity(&self, opportunity_id: OpportunityId, target_block: u64) -> bool {
+        let mut submitted = self.submitted_opportunities.lock().unwrap_or_else(|e| e.into_inner());
+        submitted.retain(|id| id.block_number + SUBMITTED_RETENTION_BLOCKS >= target_block);
+        submitted.insert(opportunity_id)
+    }
+
+    /// Mark an already submitted opportunity as resubmitted late in the slot of `target_block`.
+    ///
+    /// # Returns
+    ///
+    /// Whether resubmissions are due and the opportunity was not resubmitted before
+    fn claim_resubmission(&self, opportunity_id: OpportunityId, target_block: u64) -> bool {
+        let Some(parent_timestamp) = self.parent_timestamp(target_block) else {
+            return false;
+        };
+        if !self.submission_scheduler.allows_resubmission(parent_timestamp, SystemTime::now()) {
+            return false;
+        }
+        let mut resubmitted = self.resubmitted_opportunities.lock().unwrap_or_else(|e| e.into_inner());
+        resubmitted.retain(|id| id.block_number + SUBMITTED_RETENTION_BLOCKS >= target_block);
+        resubmitted.insert(opportunity_id)
+    }
+
+    /// The timestamp of the parent of `target_block`, estimated from the tracked chain head.
+    fn parent_timestamp(&self, target_block: u64) -> Option<u64> {
+        let head = self.head_tracker.as_ref()?.latest()?;
+        Some(self.submission_scheduler.parent_timestamp(target_block, head.number, head.timestamp))
+    }
+
+    /// Allow opportunities whose bundle reached no relayer to be submitted again.
+    fn release_unsubmitted(&self, submissions: &[BundleSubmission], opportunity_ids: &[OpportunityId]) {
+        if opportunity_ids.is_empty() || submissions.iter().any(BundleSubmission::is_successful) {
+            return;
+        }
+        let mut submitted = self.submitted_opportunities.lock().unwrap_or_else(|e| e.into_inner());
+        for opportunity_id in opportunity_ids {
+            submitted.remove(opportunity_id);
+        }
+    }
+
+    /// The block to target instead of `target_block`, if the chain head already passed it.
+    fn next_target_block(&self, target_block: u64) -> u64 {
+        match self.head_tracker.as_ref().and_then(|tracker| tracker.block_number()) {
+            Some(head) if head >= target_block => {
+                tracing::debug!(
+                    target_block = target_block,
+                    head_block = head,
+                    "Target block already on chain, targeting the next block"
+                );
+                head + 1
+            }
+            _ => target_block,
+        }
+    }
+
+    /// The base fee to price a bundle for `target_block` with.
+    ///
+    /// On chains without a base fee, the given gas price is used as is.
+    fn target_base_fee(&self, target_block: u64, base_fee: U256) -> U256 {
+        if !self.config.chain_profile.has_base_fee() {
+            return base_fee;
+        }
+        let predicted = self.base_fee_predictor
+            .as_ref()
+            .and_then(|predictor| predictor.predict(target_block))
+            .or_else(|| {
+                let head = self.head_tracker.as_ref()?.latest()?;
+                (head.number + 1 == target_block).then(|| head.next_base_fee()).flatten()
+            });
+        match predicted {
+            Some(predicted) if predicted > base_fee => {
+                tracing::debug!(
+                    target_block = target_block,
+                    base_fee = %base_fee,
+                    predicted_base_fee = %predicted,
+                    "Using predicted base fee of the target block"
+                );
+                predicted
+            }
+            _ => base_fee,
+        }
+    }
+
+    /// The bribe paid out of the given profit.
+    fn bribe(&self, profit: U256) -> U256 {
+        profit * U256::from(self.config.bribe_percentage) / U256::from(100)
+    }
 
     /// Update transaction requests with bribe and fee information.
+    ///
+    /// The bribe is paid by the last transaction, which is the swap, through its
+    /// priority fee, or through its gas price on chains without a base fee.
     fn update_requests(
         &self,
         mut reqs: Vec<TransactionRequest>,
         base_fee: U256,
         profit: U256,
-    ) -> [TransactionRequest; 2] {
-        let bribe = profit * U256::from(self.config.bribe_percentage) / U256::from(100);
+    ) -> Result<Vec<TransactionRequest>> {
+        let bribe = self.bribe(profit);
         
-        // Update the swap request (second transaction) with bribe
-        reqs[1].max_priority_fee_per_gas = Some(bribe.to());
-        reqs[1].max_fee_per_gas = Some((base_fee + bribe).to());
+        let swap_request = reqs.last_mut()
+            .ok_or(BundleError::InvalidTransactionCount { expected: 1, actual: 0 })?;
+        match self.config.chain_profile.fee_market() {
+            FeeMarket::Eip1559 => {
+                swap_request.max_priority_fee_per_gas = Some(bribe.to());
+                swap_request.max_fee_per_gas = Some((base_fee + bribe).to());
+            }
+            FeeMarket::GasPrice => swap_request.gas_price = Some((base_fee + bribe).to()),
+        }
 
-        // Convert to array without cloning
-        let mut iter = reqs.into_iter();
-        [iter.next().unwrap(), iter.next().unwrap()]
+        Ok(reqs)
+    }
+
+    /// The highest fee per gas any of the legs pays in `target_block`.
+    ///
+    /// Legs are priced as they are signed for submission: at the predicted
+    /// base fee of the target block, with the bribe on the swap.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a leg has no transactions.
+    pub fn max_fee_per_gas(&self, legs: &[BundleLeg], target_block: u64, base_fee: U256) -> Result<u128> {
+        let base_fee = self.target_base_fee(self.next_target_block(target_block), base_fee);
+        let mut max_fee_per_gas = 0;
+        for leg in legs {
+            let requests = self.update_requests(leg.tx_requests.clone(), base_fee, leg.profit_after_gas)?;
+            let swap_fee = requests
+                .last()
+                .and_then(|swap_request| swap_request.max_fee_per_gas.or(swap_request.gas_price))
+                .unwrap_or_default();
+            max_fee_per_gas = max_fee_per_gas.max(swap_fee);
+        }
+        Ok(max_fee_per_gas)
+    }
+
+    /// Apply bribes to a leg and sign all of its transactions.
+    ///
+    /// Returns the signed transactions and the bribe paid by the leg.
+    async fn sign_leg(&self, leg: BundleLeg, base_fee: U256) -> Result<(Vec<String>, U256)> {
+        let bribe = self.bribe(leg.profit_after_gas);
+        let requests = self.update_requests(leg.tx_requests, base_fee, leg.profit_after_gas)?;
+        if self.wallet_pool.is_some() {
+            tracing::debug!(
+                wallet = %self.signer_for(requests.first().and_then(|req| req.from)).address(),
+                bribe = %bribe,
+                tx_count = requests.len(),
+                "Signing leg"
+            );
+        }
+
+        let mut transactions = Vec::with_capacity(requests.len());
+        for req in requests {
+            let encoded_tx = self.sign_and_encode_transaction(req).await?;
+            transactions.push(format!("0x{}", hex::encode(encoded_tx)));
+        }
+
+        Ok((transactions, bribe))
     }
 
     /// Execute arbitrage transactions by submitting them as a bundle.
@@ -168,12 +766,14 @@ impl TxExecutor {
     /// 
     /// A vector of bundle submission results, one for each relayer.
     pub async fn execute(
-        &self,
+        self: &Arc<Self>,
         tx_requests: Vec<TransactionRequest>,
         target_block: u64,
         base_fee: U256,
         profit_after_gas: U256,
     ) -> Result<Vec<BundleSubmission>> {
+        let target_block = self.next_target_block(target_block);
+        let base_fee = self.target_base_fee(target_block, base_fee);
         tracing::info!(
             target_block = target_block,
             base_fee = %base_fee,
@@ -182,25 +782,253 @@ impl TxExecutor {
             "Starting bundle execution"
         );
 
-        let reqs = self.update_requests(tx_requests, base_fee, profit_after_gas);
+        let (transactions, bribe) = self.sign_leg(BundleLeg::new(tx_requests, profit_after_gas), base_fee).await?;
         
         tracing::debug!(
             bribe_percentage = self.config.bribe_percentage,
-            "Updated transaction requests with bribe information"
+            tx_hashes = ?transactions.iter().map(|tx| &tx[..10]).collect::<Vec<_>>(),
+            "Transactions signed and encoded"
         );
 
-        let transactions: [String; 2] = [
-            format!("0x{}", hex::encode(self.sign_and_encode_transaction(reqs[0].clone())?)),
-            format!("0x{}", hex::encode(self.sign_and_encode_transaction(reqs[1].clone())?)),
-        ];
+        let bundle = Bundle::new(transactions, target_block).with_bribe(bribe);
+        Ok(self.submit(bundle, self.execution_mode).await)
+    }
 
-        tracing::debug!(
-            tx_hashes = ?transactions.iter().map(|tx| &tx[..10]).collect::<Vec<_>>(),
-            "Transactions signed and encoded"
+    /// Execute a portfolio of non-conflicting opportunities.
+    ///
+    /// Nonces are reassigned sequentially across legs, starting from the nonce of
+    /// the first transaction of the first leg, so legs simulated independently with
+    /// the same nonce can be executed together. Depending on the submission mode, the
+    /// legs are submitted as separate bundles or as one combined bundle. Legs with a
+    /// path that fails the pre-submission check are left out, and so are legs whose
+    /// opportunity was already submitted, e.g. when the caller retries after a
+    /// transient error. An opportunity whose bundle reached no relayer may be retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `legs` - The opportunities to execute, in execution order
+    /// * `target_block` - The block number to target for execution
+    /// * `base_fee` - The base fee for the target block
+    ///
+    /// # Returns
+    ///
+    /// The submission results of all bundles, one per bundle and relayer.
+    pub async fn execute_portfolio(
+        self: &Arc<Self>,
+        legs: Vec<BundleLeg>,
+        target_block: u64,
+        base_fee: U256,
+    ) -> Result<Vec<BundleSubmission>> {
+        self.execute_portfolio_with_mode(legs, target_block, base_fee, self.execution_mode).await
+    }
+
+    /// Sign a portfolio and submit it, or only build it, regardless of the configured mode.
+    ///
+    /// Used to keep building bundles in dry-run mode while a risk limit blocks
+    /// submission. See `execute_portfolio` for how legs are combined.
+    pub async fn execute_portfolio_with_mode(
+        self: &Arc<Self>,
+        mut legs: Vec<BundleLeg>,
+        target_block: u64,
+        base_fee: U256,
+        execution_mode: ExecutionMode,
+    ) -> Result<Vec<BundleSubmission>> {
+        let target_block = self.next_target_block(target_block);
+        legs.retain(|leg| {
+            let Some(path) = &leg.path else {
+                return true;
+            };
+            if !self.execution_allowlist.allows_path_ext(path) {
+                tracing::info!(
+                    target_block = target_block,
+                    opportunity_id = ?leg.opportunity_id.map(|id| id.to_string()),
+                    blocked_protocols = ?self.execution_allowlist.blocked_protocols(path),
+                    "Leg trades through protocols not allowlisted for execution, leaving it out"
+                );
+                return false;
+            }
+            match self.pre_submission_check.check(path, target_block) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::warn!(
+                        target_block = target_block,
+                        opportunity_id = ?leg.opportunity_id.map(|id| id.to_string()),
+                        error = %e,
+                        "Leg failed pre-submission check, leaving it out"
+                    );
+                    false
+                }
+            }
+        });
+        if execution_mode == ExecutionMode::Live {
+            legs.retain(|leg| match leg.opportunity_id {
+                Some(opportunity_id) if !self.claim_opportunity(opportunity_id, target_block) => {
+                    if self.claim_resubmission(opportunity_id, target_block) {
+                        tracing::info!(
+                            target_block = target_block,
+                            opportunity_id = %opportunity_id,
+                            "Resubmitting updated leg late in the slot"
+                        );
+                        return true;
+                    }
+                    tracing::warn!(
+                        target_block = target_block,
+                        opportunity_id = %opportunity_id,
+                        "Opportunity already submitted, leaving out duplicate leg"
+                    );
+                    false
+                }
+                _ => true,
+            });
+        }
+        if legs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let claimed: Vec<OpportunityId> = match execution_mode {
+            ExecutionMode::Live => legs.iter().filter_map(|leg| leg.opportunity_id).collect(),
+            ExecutionMode::DryRun => Vec::new(),
+        };
+        let release_all = |claimed: &[OpportunityId]| self.release_unsubmitted(&[], claimed);
+        let base_fee = self.target_base_fee(target_block, base_fee);
+
+        let Some(base_nonce) = legs.first()
+            .and_then(|leg| leg.tx_requests.first())
+            .and_then(|req| req.nonce)
+        else {
+            release_all(&claimed);
+            return Err(BundleError::InvalidTransactionCount { expected: 1, actual: 0 }.into());
+        };
+
+        // Legs from different pool wallets continue the nonce sequence of their own wallet
+        let mut next_nonces: HashMap<Option<Address>, u64> = HashMap::new();
+        for leg in legs.iter_mut() {
+            let from = leg.tx_requests.first().and_then(|req| req.from);
+            let leg_nonce = leg.tx_requests.first().and_then(|req| req.nonce).unwrap_or(base_nonce);
+            let nonce = next_nonces.entry(from).or_insert(leg_nonce);
+            for req in leg.tx_requests.iter_mut() {
+                req.nonce = Some(*nonce);
+                *nonce += 1;
+            }
+        }
+
+        tracing::info!(
+            target_block = target_block,
+            legs = legs.len(),
+            base_nonce = base_nonce,
+            submission_mode = ?self.submission_mode,
+            execution_mode = ?execution_mode,
+            opportunity_ids = ?legs.iter().filter_map(|leg| leg.opportunity_id.map(|id| id.to_string())).collect::<Vec<_>>(),
+            "Starting portfolio execution"
         );
 
-        let bundle = Bundle::new(transactions, target_block);
-        let submission_results = self.relay_client.submit_bundle(&bundle).await;
+        let mut signed_legs = Vec::with_capacity(legs.len());
+        for leg in legs {
+            let opportunity_id = leg.opportunity_id;
+            match self.sign_leg(leg, base_fee).await {
+                Ok((transactions, bribe)) => signed_legs.push((transactions, bribe, opportunity_id)),
+                Err(e) => {
+                    release_all(&claimed);
+                    return Err(e);
+                }
+            }
+        }
+
+        let bundles: Vec<Bundle> = match self.submission_mode {
+            SubmissionMode::Combined => {
+                let bribe = signed_legs.iter().map(|(_, bribe, _)| *bribe).fold(U256::ZERO, |a, b| a + b);
+                let opportunity_ids = signed_legs.iter().filter_map(|(_, _, id)| *id).collect();
+                let transactions = signed_legs.into_iter().flat_map(|(transactions, _, _)| transactions).collect();
+                vec![Bundle::new(transactions, target_block).with_bribe(bribe).with_opportunity_ids(opportunity_ids)]
+            }
+            SubmissionMode::Separate => signed_legs.into_iter()
+                .map(|(transactions, bribe, id)| {
+                    Bundle::new(transactions, target_block)
+                        .with_bribe(bribe)
+                        .with_opportunity_ids(id.into_iter().collect())
+                })
+                .collect(),
+        };
+        let results = futures::future::join_all(
+            bundles.into_iter().map(|bundle| self.submit(bundle, execution_mode)),
+        ).await;
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Submit a signed bundle to all relayers and log the results.
+    ///
+    /// In dry-run mode the bundle is returned in a single dry-run submission instead.
+    /// Bundles identical to a reverted one are not submitted. Bundles held until
+    /// the submission offset are submitted by a background task, so the caller
+    /// is not blocked for the hold time, and a single scheduled submission is
+    /// returned for them.
+    async fn submit(self: &Arc<Self>, bundle: Bundle, execution_mode: ExecutionMode) -> Vec<BundleSubmission> {
+        let target_block = bundle.target_block();
+        let opportunity_ids: Vec<String> = bundle.opportunity_ids().iter().map(ToString::to_string).collect();
+
+        if execution_mode == ExecutionMode::DryRun {
+            tracing::info!(
+                target_block = target_block,
+                tx_count = bundle.transaction_count(),
+                bribe = %bundle.bribe(),
+                opportunity_ids = ?opportunity_ids,
+                "Dry run, bundle not submitted"
+            );
+            return vec![BundleSubmission::dry_run(bundle)];
+        }
+
+        let content_hash = bundle.content_hash();
+        if self.reverted_bundles.contains(&content_hash, target_block) {
+            tracing::warn!(
+                target_block = target_block,
+                content_hash = %content_hash,
+                opportunity_ids = ?opportunity_ids,
+                "Identical bundle reverted before, not submitting it again"
+            );
+            self.release_unsubmitted(&[], bundle.opportunity_ids());
+            return Vec::new();
+        }
+
+        let hold_time = self.parent_timestamp(target_block)
+            .map(|parent_timestamp| self.submission_scheduler.hold_time(parent_timestamp, SystemTime::now()))
+            .unwrap_or_default();
+        if !hold_time.is_zero() {
+            tracing::debug!(
+                target_block = target_block,
+                hold_time_ms = hold_time.as_millis(),
+                "Holding bundle until the submission offset of the slot"
+            );
+            let scheduled = BundleSubmission::scheduled(&bundle);
+            let executor = Arc::clone(self);
+            tokio::spawn(async move {
+                tokio::time::sleep(hold_time).await;
+                executor.relay(bundle).await;
+            });
+            return vec![scheduled];
+        }
+
+        self.relay(bundle).await
+    }
+
+    /// Submit a bundle to all relayers now and log the results.
+    ///
+    /// Opportunities of a bundle that reached no relayer are released for
+    /// another submission.
+    async fn relay(&self, bundle: Bundle) -> Vec<BundleSubmission> {
+        let target_block = bundle.target_block();
+        let opportunity_ids: Vec<String> = bundle.opportunity_ids().iter().map(ToString::to_string).collect();
+
+        let submission_results: Vec<BundleSubmission> = self.relay_client
+            .submit_bundle(&bundle)
+            .await
+            .into_iter()
+            .map(|submission| submission.with_opportunity_ids(bundle.opportunity_ids().to_vec()))
+            .collect();
+        self.release_unsubmitted(&submission_results, bundle.opportunity_ids());
+        if submission_results.iter().any(BundleSubmission::is_successful) {
+            let mut submitted = self.submitted_bundles.lock().unwrap_or_else(|e| e.into_inner());
+            submitted.retain(|submitted| submitted.target_block + SUBMITTED_RETENTION_BLOCKS >= target_block);
+            submitted.push(SubmittedBundle::of_bundle(&bundle));
+        }
 
         // Log submission results
         let successful_submissions = submission_results.iter().filter(|s| s.is_successful()).count();
@@ -208,6 +1036,8 @@ impl TxExecutor {
 
         tracing::info!(
             target_block = target_block,
+            tx_count = bundle.transaction_count(),
+            opportunity_ids = ?opportunity_ids,
             successful_submissions = successful_submissions,
             total_submissions = total_submissions,
             success_rate = format!("{:.1}%", (successful_submissions as f64 / total_submissions as f64) * 100.0),
@@ -233,18 +1063,19 @@ impl TxExecutor {
             }
         }
 
-        Ok(submission_results)
+        submission_results
     }
 
     /// Sign and encode a transaction request.
-    fn sign_and_encode_transaction(&self, tx_request: TransactionRequest) -> Result<Vec<u8>> {
-        let mut typed_tx = tx_request
-            .build_typed_tx()
-            .map_err(|_| BundleError::TransactionSigningFailed { 
-                reason: "Failed to build typed tx".to_string() 
-            })?;
-
-        let signature = self.config.executor_signer().sign_transaction_sync(&mut typed_tx)?;
+    ///
+    /// The envelope is built by the configured `TxBuilder` and signed by the pool
+    /// wallet sending it, if any, or the executor. Local keys sign synchronously,
+    /// remote signers are awaited.
+    async fn sign_and_encode_transaction(&self, tx_request: TransactionRequest) -> Result<Vec<u8>> {
+        let signer = self.signer_for(tx_request.from);
+        let mut typed_tx = self.tx_builder.build(tx_request)?;
+
+        let signature = signer.sign_transaction(&mut typed_tx).await?;
         let signed_tx = typed_tx.into_signed(signature);
         let tx_envelope = TxEnvelope::from(signed_tx);
         let encoded_tx = tx_envelope.encoded_2718();
@@ -282,7 +1113,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = executor.sign_and_encode_transaction(tx_request.clone());
+        let result = executor.sign_and_encode_transaction(tx_request.clone()).await;
         assert!(result.is_ok());
 
         let encoded_tx = result.unwrap();
@@ -295,4 +1126,212 @@ mod tests {
         let recovered_signer = signed_tx.recover_signer().unwrap();
         assert_eq!(recovered_signer, executor.config.executor_signer().address());
     }
+
+    #[test]
+    fn test_target_base_fee_uses_higher_prediction() {
+        let config = ArbitrageConfig::for_testing("ethereum").unwrap();
+        let predictor = Arc::new(BaseFeePredictor::new());
+        let executor = TxExecutor::from_config(config).unwrap()
+            .with_base_fee_predictor(predictor.clone());
+        let base_fee = U256::from(1_000_000_000u64);
+        assert_eq!(executor.target_base_fee(101, base_fee), base_fee);
+
+        // A full block at the latest base fee raises the next one by 12.5%
+        predictor.record_header(&alloy::consensus::Header {
+            number: 100,
+            base_fee_per_gas: Some(1_000_000_000),
+            gas_used: 30_000_000,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        });
+        assert_eq!(executor.target_base_fee(101, base_fee), U256::from(1_125_000_000u64));
+        assert!(executor.target_base_fee(103, base_fee) > U256::from(1_125_000_000u64));
+        assert_eq!(executor.target_base_fee(101, U256::from(2_000_000_000u64)), U256::from(2_000_000_000u64));
+    }
+
+    #[test]
+    fn test_retargets_bundles_after_the_chain_head() {
+        let config = ArbitrageConfig::for_testing("ethereum").unwrap();
+        let tracker = Arc::new(HeadTracker::new());
+        let executor = TxExecutor::from_config(config).unwrap()
+            .with_head_tracker(tracker.clone());
+        assert_eq!(executor.next_target_block(101), 101);
+
+        tracker.record(crate::chain::ChainHead {
+            number: 102,
+            hash: alloy::primitives::B256::repeat_byte(1),
+            parent_hash: alloy::primitives::B256::ZERO,
+            timestamp: 0,
+            base_fee: Some(1_000_000_000),
+            gas_used: 15_000_000,
+            gas_limit: 30_000_000,
+        });
+        assert_eq!(executor.next_target_block(101), 103);
+        assert_eq!(executor.target_base_fee(103, U256::ZERO), U256::from(1_000_000_000u64));
+    }
+
+    #[tokio::test]
+    async fn test_pool_wallets_sign_their_own_transactions() {
+        let config = ArbitrageConfig::for_testing("ethereum").unwrap();
+        let pool_signer = PrivateKeySigner::random();
+        let pool = WalletPool::new(vec![ExecutorSigner::from(pool_signer.clone())]).unwrap();
+        let executor = TxExecutor::from_config(config).unwrap()
+            .with_wallet_pool(Arc::new(pool));
+
+        let tx_request = |from: Address| TransactionRequest {
+            from: Some(from),
+            to: Some(alloy::primitives::TxKind::Call(Address::random())),
+            chain_id: Some(1),
+            gas: Some(100_000),
+            max_fee_per_gas: Some(1_000_000_000u128),
+            max_priority_fee_per_gas: Some(1u128),
+            nonce: Some(0),
+            ..Default::default()
+        };
+        let recover = |encoded: Vec<u8>| {
+            TxEnvelope::decode(&mut encoded.as_slice()).unwrap().recover_signer().unwrap()
+        };
+
+        let encoded = executor.sign_and_encode_transaction(tx_request(pool_signer.address())).await.unwrap();
+        assert_eq!(recover(encoded), pool_signer.address());
+
+        // Transactions from outside the pool fall back to the executor key
+        let encoded = executor.sign_and_encode_transaction(tx_request(Address::random())).await.unwrap();
+        assert_eq!(recover(encoded), executor.config.executor_signer().address());
+    }
+
+    #[test]
+    fn test_update_requests_bribes_last_transaction() {
+        let config = ArbitrageConfig::for_testing("ethereum").unwrap();
+        let executor = TxExecutor::from_config(config).unwrap();
+
+        let reqs = vec![TransactionRequest::default(), TransactionRequest::default()];
+        let updated = executor
+            .update_requests(reqs, U256::from(100), U256::from(1_000))
+            .unwrap();
+
+        assert_eq!(updated.len(), 2);
+        assert!(updated[0].max_priority_fee_per_gas.is_none());
+        assert!(updated[1].max_priority_fee_per_gas.is_some());
+
+        assert!(executor
+            .update_requests(Vec::new(), U256::from(100), U256::from(1_000))
+            .is_err());
+
+        // The fee cap of the signed swap includes the bribe
+        let legs = [BundleLeg::new(vec![TransactionRequest::default()], U256::from(1_000))];
+        assert_eq!(executor.max_fee_per_gas(&legs, 101, U256::from(100)).unwrap(), 600);
+    }
+
+    #[test]
+    fn test_gas_price_chains_pay_the_bribe_through_the_gas_price() {
+        let mut config = ArbitrageConfig::for_testing("ethereum").unwrap();
+        config.chain_profile = crate::chain::ChainSpec::new("legacy_l2", 999_997, Address::random())
+            .without_base_fee()
+            .profile();
+        let executor = TxExecutor::from_config(config).unwrap();
+        assert_eq!(executor.target_base_fee(101, U256::from(100)), U256::from(100));
+
+        let updated = executor
+            .update_requests(vec![TransactionRequest::default()], U256::from(100), U256::from(1_000))
+            .unwrap();
+        // Half of the profit is bribed on top of the quoted gas price
+        assert_eq!(updated[0].gas_price, Some(600));
+        assert!(updated[0].max_fee_per_gas.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_returns_signed_bundle() {
+        let config = ArbitrageConfig::for_testing("ethereum").unwrap();
+        let bribe_percentage = config.bribe_percentage;
+        let executor = Arc::new(
+            TxExecutor::from_config(config)
+                .unwrap()
+                .with_execution_mode(ExecutionMode::DryRun),
+        );
+
+        let tx_request = TransactionRequest {
+            to: Some(alloy::primitives::TxKind::Call(Address::random())),
+            chain_id: Some(1),
+            gas: Some(100_000),
+            nonce: Some(1),
+            ..Default::default()
+        };
+
+        let submissions = executor
+            .execute(vec![tx_request], 100, U256::from(1_000), U256::from(10_000))
+            .await
+            .unwrap();
+
+        assert_eq!(submissions.len(), 1);
+        assert!(submissions[0].is_dry_run());
+        assert!(!submissions[0].is_successful());
+
+        let bundle = submissions[0].dry_run_bundle().unwrap();
+        assert_eq!(bundle.target_block(), 100);
+        assert_eq!(bundle.transaction_count(), 1);
+        assert_eq!(bundle.bribe(), U256::from(10_000) * U256::from(bribe_percentage) / U256::from(100));
+    }
+
+    #[tokio::test]
+    async fn test_opportunities_are_submitted_once() {
+        let config = ArbitrageConfig::for_testing("ethereum").unwrap();
+        let executor = Arc::new(TxExecutor::from_config(config).unwrap());
+        let id = OpportunityId::new(crate::path::PathId::from_hops([]), 100).with_amount_in(&num_bigint::BigUint::from(1000u32));
+
+        assert!(executor.claim_opportunity(id, 101));
+        assert!(executor.was_submitted(&id));
+        assert!(!executor.claim_opportunity(id, 101));
+
+        // A duplicate leg is left out before anything is signed
+        let leg = BundleLeg::new(vec![TransactionRequest::default()], U256::from(1)).with_opportunity_id(id);
+        let submissions = executor.execute_portfolio(vec![leg], 101, U256::ZERO).await.unwrap();
+        assert!(submissions.is_empty());
+
+        // Bundles that reached no relayer release their opportunities
+        let failed = BundleSubmission::new(101, None, "https://relay.example".to_string(), false, Some("timeout".to_string()));
+        executor.release_unsubmitted(&[failed], &[id]);
+        assert!(!executor.was_submitted(&id));
+
+        // Opportunities are forgotten once they are stale
+        assert!(executor.claim_opportunity(id, 101));
+        let newer = OpportunityId::new(id.path_id, 200);
+        assert!(executor.claim_opportunity(newer, 200));
+        assert!(!executor.was_submitted(&id));
+    }
+
+    #[tokio::test]
+    async fn test_reverted_bundles_are_not_submitted_again() {
+        let config = ArbitrageConfig::for_testing("ethereum").unwrap();
+        let executor = Arc::new(TxExecutor::from_config(config).unwrap());
+        let bundle = Bundle::new(vec!["0x02f8".to_string()], 101);
+        executor.submitted_bundles.lock().unwrap().push(SubmittedBundle::of_bundle(&bundle));
+
+        assert_eq!(executor.record_reverted_transactions(&[B256::ZERO], 101), 0);
+        assert_eq!(executor.record_reverted_transactions(&bundle.transaction_hashes(), 101), 1);
+
+        // The identical bundle for a later block is refused before reaching any relayer
+        let resubmitted = Bundle::new(bundle.transactions().to_vec(), 102);
+        assert!(executor.submit(resubmitted, ExecutionMode::Live).await.is_empty());
+    }
+
+    #[test]
+    fn test_undecodable_bundles_keep_distinct_content_hashes() {
+        let first = Bundle::new(vec!["not hex".to_string()], 101);
+        let second = Bundle::new(vec!["also not hex".to_string()], 101);
+        assert!(first.transaction_hashes().is_empty());
+        assert_ne!(first.content_hash(), second.content_hash());
+    }
+
+    #[test]
+    fn test_submission_mode_from_str() {
+        assert_eq!(SubmissionMode::from_str("separate").unwrap(), SubmissionMode::Separate);
+        assert_eq!(SubmissionMode::from_str("Combined").unwrap(), SubmissionMode::Combined);
+        assert!(SubmissionMode::from_str("parallel").is_err());
+        assert_eq!(SubmissionMode::default(), SubmissionMode::Separate);
+
+        assert_eq!(ExecutionMode::from_str("dry-run").unwrap(), ExecutionMode::DryRun);
+        assert_eq!(ExecutionMode::from_str("Live").unwrap(), ExecutionMode::Live);
+        assert!(ExecutionMode::from_str("paper").is_err());
+    }
 }
@@ -1,16 +1,25 @@
 //! Bundle relay client for submitting bundles to MEV relayers.
 //! 
 //! This module handles the networking aspects of bundle submission,
-//! including JSON-RPC communication and signature handling.
+//! including JSON-RPC communication, signature handling and retries of
+//! transient failures.
+//!
+//! Relayers do not agree on the shape of an `eth_sendBundle` response: most
+//! return `{"bundleHash": "0x…"}`, some a bare hash and some nothing at all.
+//! Every response is parsed into a [`RelayResponse`] recording its
+//! [`RelayDialect`], the typed bundle hash and the error code and message of
+//! rejections. Results of an unknown shape are kept as `raw_response`, so a
+//! new relayer dialect can be debugged from the submission.
 
-use crate::bundle::{Bundle, BundleSubmission};
+use crate::bundle::{Bundle, BundleSubmission, RetryPolicy};
 use crate::config::ArbitrageConfig;
 use crate::errors::{BundleError, Result};
-use alloy::primitives::keccak256;
+use alloy::primitives::{keccak256, B256};
 use alloy::signers::{local::PrivateKeySigner, Signer};
 use reqwest::Client as HttpClient;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 /// Parameters for the eth_sendBundle JSON-RPC method.
 #[derive(Serialize, Debug)]
@@ -28,7 +37,7 @@ impl EthSendBundleParams {
         let builder_params = crate::utils::builder_params(relayer);
 
         Self {
-            txs: bundle.transactions().clone().to_vec(),
+            txs: bundle.transactions().to_vec(),
             block_number: format!("0x{:x}", bundle.target_block()),
             builders: builder_params,
         }
@@ -77,11 +86,120 @@ pub struct JsonRpcError {
     pub message: String,
 }
 
+/// The shape of a relayer's `eth_sendBundle` response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayDialect {
+    /// `{"result": {"bundleHash": "0x…"}}`, answered by Flashbots and most builders
+    BundleHashObject,
+    /// `{"result": "0x…"}`, the bundle hash without an object
+    BundleHashString,
+    /// A `null` or missing result without an error
+    Empty,
+    /// `{"error": {"code": …, "message": …}}`, a JSON-RPC rejection
+    RpcError,
+    /// A result or error of any other shape
+    Unknown,
+}
+
+/// An `eth_sendBundle` response parsed independent of the relayer's dialect.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RelayResponse {
+    /// The shape the relayer answered in
+    pub dialect: RelayDialect,
+    /// The hash the relayer assigned to the bundle, if it returned one
+    pub bundle_hash: Option<B256>,
+    /// The JSON-RPC error code of a rejection
+    pub error_code: Option<i64>,
+    /// The error message of a rejection
+    pub error_message: Option<String>,
+    /// The result or error as received, if its dialect is unknown
+    pub raw_response: Option<serde_json::Value>,
+}
+
+impl RelayResponse {
+    fn of_dialect(dialect: RelayDialect) -> Self {
+        Self {
+            dialect,
+            bundle_hash: None,
+            error_code: None,
+            error_message: None,
+            raw_response: None,
+        }
+    }
+
+    /// Parse a JSON-RPC response body of `eth_sendBundle`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body is not a JSON object.
+    pub fn parse(body: &str) -> serde_json::Result<Self> {
+        let mut response: serde_json::Map<String, serde_json::Value> = serde_json::from_str(body)?;
+        let error = response.remove("error").filter(|error| !error.is_null());
+        let result = response.remove("result").filter(|result| !result.is_null());
+
+        let parsed = match (error, result) {
+            (Some(error), _) => match serde_json::from_value::<JsonRpcError>(error.clone()) {
+                Ok(rpc_error) => Self {
+                    error_code: Some(rpc_error.code),
+                    error_message: Some(rpc_error.message),
+                    ..Self::of_dialect(RelayDialect::RpcError)
+                },
+                // Still a rejection, whatever shape the error has
+                Err(_) => Self {
+                    error_message: Some(error.to_string()),
+                    raw_response: Some(error),
+                    ..Self::of_dialect(RelayDialect::Unknown)
+                },
+            },
+            (None, None) => Self::of_dialect(RelayDialect::Empty),
+            (None, Some(result)) => {
+                let (dialect, bundle_hash) = match &result {
+                    serde_json::Value::String(hash) => (RelayDialect::BundleHashString, hash.parse::<B256>().ok()),
+                    serde_json::Value::Object(_) => (
+                        RelayDialect::BundleHashObject,
+                        serde_json::from_value::<EthSendBundleResponse>(result.clone())
+                            .ok()
+                            .and_then(|response| response.bundle_hash.parse::<B256>().ok()),
+                    ),
+                    _ => (RelayDialect::Unknown, None),
+                };
+                match bundle_hash {
+                    Some(bundle_hash) => Self {
+                        bundle_hash: Some(bundle_hash),
+                        ..Self::of_dialect(dialect)
+                    },
+                    None => Self {
+                        raw_response: Some(result),
+                        ..Self::of_dialect(RelayDialect::Unknown)
+                    },
+                }
+            }
+        };
+        Ok(parsed)
+    }
+
+    /// Whether the relayer rejected the bundle.
+    pub fn is_rejection(&self) -> bool {
+        self.error_message.is_some()
+    }
+
+    /// The error of a rejection of a bundle for `target_block` by the relayer at `url`.
+    pub fn rejection(&self, url: &str, target_block: u64) -> Option<BundleError> {
+        let message = self.error_message.as_deref()?;
+        Some(BundleError::from_relay_rejection(url, target_block, self.error_code.unwrap_or_default(), message))
+    }
+}
+
 /// Client for communicating with MEV relayers.
 pub struct RelayClient {
     http_client: HttpClient,
     identity_signer: PrivateKeySigner,
     relayer_urls: Vec<String>,
+    retry_policy: RetryPolicy,
+    relayer_retry_policies: HashMap<String, RetryPolicy>,
+    /// Time after the first attempt within which retries have to start
+    submission_window: Option<Duration>,
 }
 
 impl RelayClient {
@@ -97,62 +215,197 @@ impl RelayClient {
                 message: format!("Failed to parse identity key: {}", e),
             })?;
 
+        let client = Self::new(
+            identity_signer,
+            config.relayer_urls().to_vec(),
+            Duration::from_millis(config.relayer.timeout_ms),
+        )?
+        .with_retry_policy(config.relayer.retry_policy())
+        .with_submission_window(config.chain_profile.block_time());
+        Ok(client)
+    }
+
+    /// Create a new RelayClient submitting to explicit relayer URLs.
+    ///
+    /// Used to submit to relayers that are not part of the configuration, such
+    /// as the mock relayers of the `testing` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `identity_signer` - The signer for Flashbots identification
+    /// * `relayer_urls` - The relayers to submit bundles to
+    /// * `timeout` - The timeout of each relayer request
+    pub fn new(identity_signer: PrivateKeySigner, relayer_urls: Vec<String>, timeout: Duration) -> Result<Self> {
         let http_client = HttpClient::builder()
-            .timeout(Duration::from_millis(config.relayer.timeout_ms))
+            .timeout(timeout)
             .build()?;
 
         Ok(Self {
             http_client,
             identity_signer,
-            relayer_urls: config.relayer_urls().to_vec(),
+            relayer_urls,
+            retry_policy: RetryPolicy::default(),
+            relayer_retry_policies: HashMap::new(),
+            submission_window: None,
         })
     }
 
+    /// Set how failed submissions are retried.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set how failed submissions to one relayer are retried, overriding the default policy.
+    pub fn with_relayer_retry_policy(mut self, relayer_url: impl Into<String>, retry_policy: RetryPolicy) -> Self {
+        self.relayer_retry_policies.insert(relayer_url.into(), retry_policy);
+        self
+    }
+
+    /// Only start retries within `submission_window` of the first attempt.
+    ///
+    /// Bundles target the next block, so the window is usually the block time:
+    /// retries never start after the target block could have been built.
+    pub fn with_submission_window(mut self, submission_window: Duration) -> Self {
+        self.submission_window = Some(submission_window);
+        self
+    }
+
+    /// The retry policy of a relayer.
+    pub fn retry_policy(&self, relayer_url: &str) -> &RetryPolicy {
+        self.relayer_retry_policies.get(relayer_url).unwrap_or(&self.retry_policy)
+    }
+
+    /// The relayers bundles are submitted to.
+    pub fn relayer_urls(&self) -> &[String] {
+        &self.relayer_urls
+    }
 
     /// Submit a bundle to all configured relayers concurrently.
     /// 
+    /// Transient failures are retried within the submission window.
     /// Returns a vector of submission results, one for each relayer.
     pub async fn submit_bundle(&self, bundle: &Bundle) -> Vec<BundleSubmission> {
+        let deadline = self.submission_window.map(|window| Instant::now() + window);
+        self.submit_bundle_until(bundle, deadline).await
+    }
+
+    /// Submit a bundle to all configured relayers concurrently, retrying until a deadline.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` - The bundle to submit
+    /// * `deadline` - The instant after which no retry is started, if any
+    ///
+    /// # Returns
+    ///
+    /// The submission results, one for each relayer
+    pub async fn submit_bundle_until(&self, bundle: &Bundle, deadline: Option<Instant>) -> Vec<BundleSubmission> {
         use futures::future::join_all;
-        
+
         let futures = self.relayer_urls
             .iter()
-            .map(|relayer_url| self.submit_to_relayer(bundle, relayer_url));
-        
+            .map(|relayer_url| self.submit_with_retries(bundle, relayer_url, deadline));
+
         join_all(futures).await
     }
 
-    async fn submit_to_relayer(&self, bundle: &Bundle, relayer_url: &str) -> BundleSubmission {
-        let params = EthSendBundleParams::new(bundle, relayer_url);
-        let request = JsonRpcRequest::new(params);
+    async fn submit_with_retries(&self, bundle: &Bundle, relayer_url: &str, deadline: Option<Instant>) -> BundleSubmission {
+        let policy = self.retry_policy(relayer_url);
+        let mut attempt = 1;
+        loop {
+            let (error, response) = match self.submit_to_relayer(bundle, relayer_url).await {
+                Ok(response) => match response.rejection(relayer_url, bundle.target_block()) {
+                    Some(rejection) => (rejection.into(), Some(response)),
+                    None => return Self::accepted_submission(bundle, relayer_url, response),
+                },
+                Err(e) => (e, None),
+            };
+
+            let backoff = policy.backoff(attempt);
+            let within_window = !deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline);
+            if attempt >= policy.max_attempts() || !policy.is_retryable(&error) || !within_window {
+                let submission = BundleSubmission::new(
+                    bundle.target_block(),
+                    None,
+                    relayer_url.to_string(),
+                    false,
+                    Some(error.to_string()),
+                );
+                return match response {
+                    Some(response) => submission.with_relay_response(response),
+                    None => submission,
+                };
+            }
 
-        let default_submission =
-            |success, bundle_hash: Option<String>, error: Option<String>| BundleSubmission::new(
-                bundle.target_block(),
-                bundle_hash,
-                relayer_url.to_string(),
-                success,
-                error,
+            tracing::debug!(
+                relayer_url = relayer_url,
+                attempt = attempt,
+                backoff_ms = backoff.as_millis(),
+                error = %error,
+                "Retrying bundle submission"
             );
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
 
-        match self
-            .send_request::<EthSendBundleParams, EthSendBundleResponse>(&request, relayer_url)
-            .await
-        {
-            Ok(res) => match (res.error, res.result) {
-                (Some(err), _) => default_submission(false, None, Some(err.message)),
-                (None, Some(result)) => default_submission(true, Some(result.bundle_hash), None),
-                _ => default_submission(false, None, Some("Empty response".into())),
-            },
-            Err(e) => default_submission(false, None, Some(e.to_string())),
+    /// The submission of a bundle the relayer answered without rejecting it.
+    ///
+    /// Empty responses are not successful, as nothing confirms the relayer
+    /// received the bundle. Results of an unknown dialect are.
+    fn accepted_submission(bundle: &Bundle, relayer_url: &str, response: RelayResponse) -> BundleSubmission {
+        let (success, error) = match response.dialect {
+            RelayDialect::Empty => (false, Some("Empty response".to_string())),
+            _ => (true, None),
+        };
+        if response.dialect == RelayDialect::Unknown {
+            tracing::warn!(
+                relayer_url = relayer_url,
+                raw_response = ?response.raw_response,
+                "Relayer answered in an unknown dialect"
+            );
+        } else {
+            tracing::debug!(
+                relayer_url = relayer_url,
+                dialect = ?response.dialect,
+                bundle_hash = ?response.bundle_hash,
+                "Parsed relayer response"
+            );
         }
+
+        BundleSubmission::new(
+            bundle.target_block(),
+            response.bundle_hash,
+            relayer_url.to_string(),
+            success,
+            error,
+        )
+        .with_relay_response(response)
     }
 
-    async fn send_request<T: serde::Serialize, R: serde::de::DeserializeOwned>(
+    /// Submit a bundle to one relayer once.
+    ///
+    /// Every parsed response is returned, rejections included. Failures to
+    /// get a response are returned as errors.
+    async fn submit_to_relayer(&self, bundle: &Bundle, relayer_url: &str) -> Result<RelayResponse> {
+        let params = EthSendBundleParams::new(bundle, relayer_url);
+        let request = JsonRpcRequest::new(params);
+
+        let response_text = self.send_request(&request, relayer_url).await?;
+        let response = RelayResponse::parse(&response_text)
+            .map_err(|e| BundleError::InvalidRelayerResponse {
+                url: relayer_url.to_string(),
+                message: format!("Failed to parse response: {}", e)
+            })?;
+        Ok(response)
+    }
+
+    async fn send_request<T: serde::Serialize>(
         &self,
         request: &JsonRpcRequest<T>,
         relayer_url: &str,
-    ) -> Result<JsonRpcResponse<R>> {
+    ) -> Result<String> {
         let request_body = serde_json::to_string(request)?;
         let signature = self.sign_request(&request_body).await?;
 
@@ -166,14 +419,17 @@ impl RelayClient {
             .send()
             .await?;
 
+        let status = response.status();
         let response_text = response.text().await?;
-        let json_response: JsonRpcResponse<R> = serde_json::from_str(&response_text)
-            .map_err(|e| BundleError::InvalidRelayerResponse { 
+        if !status.is_success() {
+            return Err(BundleError::RelayerHttpStatus {
                 url: relayer_url.to_string(),
-                message: format!("Failed to parse response: {}", e) 
-            })?;
+                status: status.as_u16(),
+                message: response_text,
+            }.into());
+        }
 
-        Ok(json_response)
+        Ok(response_text)
     }
 
     async fn sign_request(&self, request_body: &str) -> Result<String> {
@@ -192,3 +448,110 @@ impl RelayClient {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockRelayResponse, MockRelayer};
+
+    const BUNDLE_HASH: &str = "0x00000000000000000000000000000000000000000000000000000000000000ab";
+
+    #[tokio::test]
+    async fn test_submission_results_per_relayer() {
+        let relayers = [
+            MockRelayer::start(MockRelayResponse::accept(BUNDLE_HASH)).await.unwrap(),
+            MockRelayer::start(MockRelayResponse::rate_limited()).await.unwrap(),
+            MockRelayer::start(MockRelayResponse::Malformed { body: "<html>".to_string() }).await.unwrap(),
+            MockRelayer::start(MockRelayResponse::RpcError { code: -32000, message: "bundle rejected".to_string() })
+                .await
+                .unwrap(),
+            MockRelayer::start(MockRelayResponse::accept(BUNDLE_HASH).delayed(Duration::from_secs(5))).await.unwrap(),
+        ];
+        let client = RelayClient::new(
+            PrivateKeySigner::random(),
+            relayers.iter().map(|relayer| relayer.url().to_string()).collect(),
+            Duration::from_millis(500),
+        )
+        .unwrap()
+        .with_retry_policy(RetryPolicy::no_retries());
+
+        let bundle = Bundle::new(vec!["0x02f8".to_string()], 100);
+        let submissions = client.submit_bundle(&bundle).await;
+
+        // Submissions are returned in relayer order, and one success does not hide the failures
+        assert_eq!(submissions.len(), relayers.len());
+        assert!(submissions[0].is_successful());
+        assert_eq!(submissions[0].bundle_hash(), Some(BUNDLE_HASH.parse().unwrap()));
+        assert!(submissions[1].error().unwrap().contains("429"));
+        assert!(submissions[2].error().unwrap().contains("Failed to parse response"));
+        assert!(submissions[3].error().unwrap().ends_with("rejected the bundle with code -32000: bundle rejected"));
+        assert_eq!(submissions[3].relay_response().unwrap().error_code, Some(-32000));
+        assert!(!submissions[4].is_successful());
+        assert_eq!(submissions.iter().filter(|submission| submission.is_successful()).count(), 1);
+        assert!(submissions.iter().all(|submission| submission.target_block() == 100));
+
+        // Every relayer received the signed eth_sendBundle request
+        for relayer in &relayers {
+            assert_eq!(relayer.request_count(), 1);
+            assert!(relayer.requests()[0].contains("eth_sendBundle"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures_within_the_window() {
+        let flaky = MockRelayer::start_sequence(vec![MockRelayResponse::rate_limited(), MockRelayResponse::accept(BUNDLE_HASH)])
+            .await
+            .unwrap();
+        let rejecting = MockRelayer::start(MockRelayResponse::RpcError { code: -32000, message: "bundle rejected".to_string() })
+            .await
+            .unwrap();
+        let policy = RetryPolicy::new().with_max_attempts(3).with_base_backoff(Duration::from_millis(1));
+        let client = RelayClient::new(
+            PrivateKeySigner::random(),
+            vec![flaky.url().to_string(), rejecting.url().to_string()],
+            Duration::from_millis(500),
+        )
+        .unwrap()
+        .with_retry_policy(policy);
+
+        let bundle = Bundle::new(vec!["0x02f8".to_string()], 100);
+        let submissions = client.submit_bundle(&bundle).await;
+        assert!(submissions[0].is_successful());
+        assert_eq!(flaky.request_count(), 2);
+        assert!(!submissions[1].is_successful());
+        assert_eq!(rejecting.request_count(), 1);
+
+        // No retry starts after the deadline
+        let limited = MockRelayer::start(MockRelayResponse::rate_limited()).await.unwrap();
+        let client = RelayClient::new(PrivateKeySigner::random(), vec![limited.url().to_string()], Duration::from_millis(500))
+            .unwrap()
+            .with_retry_policy(policy);
+        let submissions = client.submit_bundle_until(&bundle, Some(Instant::now())).await;
+        assert!(submissions[0].error().unwrap().contains("429"));
+        assert_eq!(limited.request_count(), 1);
+    }
+
+    #[test]
+    fn test_parses_relayer_dialects() {
+        let object = RelayResponse::parse(&format!(r#"{{"jsonrpc":"2.0","id":1,"result":{{"bundleHash":"{BUNDLE_HASH}"}}}}"#)).unwrap();
+        assert_eq!(object.dialect, RelayDialect::BundleHashObject);
+        assert_eq!(object.bundle_hash, Some(BUNDLE_HASH.parse().unwrap()));
+
+        let string = RelayResponse::parse(&format!(r#"{{"jsonrpc":"2.0","id":1,"result":"{BUNDLE_HASH}"}}"#)).unwrap();
+        assert_eq!(string.dialect, RelayDialect::BundleHashString);
+        assert_eq!(string.bundle_hash, object.bundle_hash);
+
+        let empty = RelayResponse::parse(r#"{"jsonrpc":"2.0","id":1,"result":null}"#).unwrap();
+        assert_eq!(empty.dialect, RelayDialect::Empty);
+        assert!(!empty.is_rejection());
+
+        let unknown = RelayResponse::parse(r#"{"jsonrpc":"2.0","id":1,"result":{"uuid":"f3a1"}}"#).unwrap();
+        assert_eq!(unknown.dialect, RelayDialect::Unknown);
+        assert_eq!(unknown.bundle_hash, None);
+        assert_eq!(unknown.raw_response, Some(serde_json::json!({ "uuid": "f3a1" })));
+
+        let rejection = RelayResponse::parse(r#"{"jsonrpc":"2.0","id":1,"error":"bundle too large"}"#).unwrap();
+        assert!(rejection.is_rejection());
+        assert!(matches!(rejection.rejection("https://relay.example", 100), Some(BundleError::RelayRejected { code: 0, .. })));
+    }
+}
@@ -0,0 +1,151 @@
+//! Retries of failed relayer submissions.
+//!
+//! Relayers rate limit searchers and occasionally time out or fail with server
+//! errors, while a bundle is only worth submitting until its target block is
+//! built. A `RetryPolicy` retries transient failures with an exponential,
+//! jittered backoff, and the relay client stops retrying once the next attempt
+//! would start after the submission window of the target block.
+
+use crate::errors::ArbitrageError;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Default number of attempts per relayer, including the first one.
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Default backoff before the first retry.
+const DEFAULT_BASE_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Default upper bound of the exponential backoff.
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Default share of the backoff that is randomized.
+const DEFAULT_JITTER: f64 = 0.5;
+
+/// How failed submissions to a relayer are retried.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_backoff: Duration,
+    max_backoff: Duration,
+    jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Create a policy with the default attempts and backoff.
+    pub fn new() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_backoff: DEFAULT_BASE_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            jitter: DEFAULT_JITTER,
+        }
+    }
+
+    /// Create a policy submitting once, without retries.
+    pub fn no_retries() -> Self {
+        Self::new().with_max_attempts(1)
+    }
+
+    /// Set the number of attempts per relayer, including the first one.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the backoff before the first retry, doubled for every further retry.
+    pub fn with_base_backoff(mut self, base_backoff: Duration) -> Self {
+        self.base_backoff = base_backoff;
+        self
+    }
+
+    /// Set the upper bound of the exponential backoff.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the share of the backoff that is randomized, between 0 and 1.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = if jitter.is_nan() { 0.0 } else { jitter.clamp(0.0, 1.0) };
+        self
+    }
+
+    /// The number of attempts per relayer, including the first one.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// The backoff after a failed attempt.
+    ///
+    /// The backoff doubles with every attempt up to the maximum backoff, and
+    /// the jittered share of it is drawn uniformly at random.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The number of the failed attempt, starting at 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self.base_backoff.saturating_mul(1 << exponent).min(self.max_backoff);
+
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        backoff.mul_f64(1.0 - self.jitter * random)
+    }
+
+    /// Whether a failed submission may succeed when retried.
+    ///
+    /// Timeouts, connection failures, rate limits and server errors are
+    /// transient. Rejections of the bundle itself are not retried.
+    pub fn is_retryable(&self, error: &ArbitrageError) -> bool {
+        error.is_retryable()
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::BundleError;
+
+    #[test]
+    fn test_backoff_doubles_within_jitter_and_bound() {
+        let policy = RetryPolicy::new()
+            .with_base_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(300))
+            .with_jitter(0.5);
+
+        for _ in 0..20 {
+            let first = policy.backoff(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let capped = policy.backoff(5);
+            assert!(capped >= Duration::from_millis(150) && capped <= Duration::from_millis(300));
+        }
+        assert_eq!(policy.with_jitter(0.0).backoff(2), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_classifies_retryable_errors() {
+        let policy = RetryPolicy::new();
+        let status = |status| {
+            ArbitrageError::from(BundleError::RelayerHttpStatus {
+                url: "https://relay.example".to_string(),
+                status,
+                message: String::new(),
+            })
+        };
+
+        assert!(policy.is_retryable(&status(429)));
+        assert!(policy.is_retryable(&status(503)));
+        assert!(!policy.is_retryable(&status(400)));
+        assert!(!policy.is_retryable(&BundleError::InvalidRelayerResponse {
+            url: "https://relay.example".to_string(),
+            message: "Failed to parse response".to_string(),
+        }.into()));
+    }
+}
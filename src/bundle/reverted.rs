@@ -0,0 +1,206 @@
+//! Memory of bundles that reverted on chain.
+//!
+//! A bundle that was included but reverted paid gas for nothing. Submitting
+//! the same signed transactions again, e.g. after a restart or when an
+//! opportunity is rebuilt with the same nonce and fees, can only revert again
+//! or be rejected for its nonce, and costs relay reputation either way.
+//!
+//! `RevertedBundles` remembers the content hash of every reverted bundle, see
+//! [`Bundle::content_hash`], for a window of blocks. The
+//! [`TxExecutor`](crate::bundle::TxExecutor) refuses to submit bundles it
+//! remembers. With a persistence file the memory survives restarts.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::bundle::RevertedBundles;
+//!
+//! let reverted = RevertedBundles::new(7_200).with_persistence("reverted_bundles.json")?;
+//! let executor = TxExecutor::from_config(config)?.with_reverted_bundles(Arc::new(reverted));
+//!
+//! // Once the target block arrived, report the executor's reverted transactions
+//! executor.record_reverted_transactions(&reverted_tx_hashes, target_block);
+//! ```
+
+use crate::bundle::Bundle;
+use crate::errors::{BundleError, Result};
+use alloy::primitives::B256;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// Number of blocks a reverted bundle is remembered by default, about a day on Ethereum.
+pub const DEFAULT_REVERT_MEMORY_BLOCKS: u64 = 7_200;
+
+/// Content hashes of reverted bundles with the block they reverted in.
+#[derive(Debug)]
+pub struct RevertedBundles {
+    window_blocks: u64,
+    reverted: Mutex<HashMap<B256, u64>>,
+    persistence_path: Option<PathBuf>,
+}
+
+impl RevertedBundles {
+    /// Remember reverted bundles for `window_blocks` blocks.
+    pub fn new(window_blocks: u64) -> Self {
+        Self {
+            window_blocks,
+            reverted: Mutex::new(HashMap::new()),
+            persistence_path: None,
+        }
+    }
+
+    /// Keep the memory in a JSON file, loading the bundles it already holds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn with_persistence(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            let loaded = Self::load(&path)?;
+            tracing::info!(
+                path = %path.display(),
+                reverted_bundles = loaded.len(),
+                "Loaded reverted bundles"
+            );
+            self.reverted = Mutex::new(loaded);
+        }
+        self.persistence_path = Some(path);
+        Ok(self)
+    }
+
+    /// The number of blocks a reverted bundle is remembered.
+    pub fn window_blocks(&self) -> u64 {
+        self.window_blocks
+    }
+
+    /// Remember a bundle that reverted in `block_number`.
+    ///
+    /// Bundles that reverted before the window are forgotten. Failures to
+    /// persist the memory are logged, the bundle is remembered regardless.
+    pub fn record(&self, content_hash: B256, block_number: u64) {
+        let mut reverted = self.reverted.lock().unwrap_or_else(|e| e.into_inner());
+        reverted.retain(|_, reverted_in| *reverted_in + self.window_blocks >= block_number);
+        reverted.insert(content_hash, block_number);
+        tracing::debug!(
+            content_hash = %content_hash,
+            block_number = block_number,
+            reverted_bundles = reverted.len(),
+            "Recorded reverted bundle"
+        );
+
+        if let Err(e) = self.save(&reverted) {
+            tracing::warn!(error = %e, "Failed to persist reverted bundles");
+        }
+    }
+
+    /// Whether a bundle reverted within the window before `block_number`.
+    pub fn contains(&self, content_hash: &B256, block_number: u64) -> bool {
+        self.reverted
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(content_hash)
+            .is_some_and(|reverted_in| *reverted_in + self.window_blocks >= block_number)
+    }
+
+    /// The number of remembered bundles.
+    pub fn len(&self) -> usize {
+        self.reverted.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Whether no bundle is remembered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write the memory to the persistence file, if any, replacing it atomically.
+    fn save(&self, reverted: &HashMap<B256, u64>) -> Result<()> {
+        let Some(path) = &self.persistence_path else {
+            return Ok(());
+        };
+        let persistence_error = |reason: String| BundleError::RevertMemoryPersistence {
+            path: path.display().to_string(),
+            reason,
+        };
+
+        let entries: BTreeMap<String, u64> = reverted
+            .iter()
+            .map(|(content_hash, block_number)| (content_hash.to_string(), *block_number))
+            .collect();
+        let contents = serde_json::to_string_pretty(&entries)?;
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, contents).map_err(|e| persistence_error(e.to_string()))?;
+        std::fs::rename(&temporary, path).map_err(|e| persistence_error(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Read a memory written by `save`.
+    fn load(path: &Path) -> Result<HashMap<B256, u64>> {
+        let persistence_error = |reason: String| BundleError::RevertMemoryPersistence {
+            path: path.display().to_string(),
+            reason,
+        };
+
+        let contents = std::fs::read_to_string(path).map_err(|e| persistence_error(e.to_string()))?;
+        let entries: BTreeMap<String, u64> = serde_json::from_str(&contents)?;
+        entries
+            .into_iter()
+            .map(|(content_hash, block_number)| -> Result<(B256, u64)> {
+                let content_hash = B256::from_str(&content_hash)
+                    .map_err(|e| persistence_error(format!("invalid bundle hash {}: {}", content_hash, e)))?;
+                Ok((content_hash, block_number))
+            })
+            .collect()
+    }
+}
+
+impl Default for RevertedBundles {
+    fn default() -> Self {
+        Self::new(DEFAULT_REVERT_MEMORY_BLOCKS)
+    }
+}
+
+/// A submitted bundle, kept to recognize it among reverted transactions.
+#[derive(Debug, Clone)]
+pub(crate) struct SubmittedBundle {
+    pub(crate) content_hash: B256,
+    pub(crate) transaction_hashes: Vec<B256>,
+    pub(crate) target_block: u64,
+}
+
+impl SubmittedBundle {
+    pub(crate) fn of_bundle(bundle: &Bundle) -> Self {
+        Self {
+            content_hash: bundle.content_hash(),
+            transaction_hashes: bundle.transaction_hashes(),
+            target_block: bundle.target_block(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remembers_reverted_bundles_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reverted_bundles.json");
+        let bundle = Bundle::new(vec!["0x02f8".to_string()], 100);
+
+        let reverted = RevertedBundles::new(10).with_persistence(&path).unwrap();
+        reverted.record(bundle.content_hash(), 100);
+        assert!(reverted.contains(&bundle.content_hash(), 110));
+        assert!(!reverted.contains(&bundle.content_hash(), 111));
+
+        let restarted = RevertedBundles::new(10).with_persistence(&path).unwrap();
+        assert_eq!(restarted.len(), 1);
+        assert!(restarted.contains(&bundle.content_hash(), 105));
+
+        // Bundles of another target block with the same transactions are identical
+        let retargeted = Bundle::new(vec!["0x02f8".to_string()], 101);
+        assert_eq!(retargeted.content_hash(), bundle.content_hash());
+    }
+}
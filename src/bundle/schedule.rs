@@ -0,0 +1,174 @@
+//! When bundles are submitted within the slot of their target block.
+//!
+//! Builders keep building the block of a slot until shortly before it is
+//! proposed, and weigh late bundles differently than early ones. Submitting
+//! right after the search finishes is not always best, so the moment of
+//! submission is configurable:
+//!
+//! - **`Immediate`**: submit bundles as soon as they are signed
+//! - **`Delayed`**: hold bundles until an offset into the slot, e.g. 8 s after
+//!   the timestamp of the parent block
+//! - **`Resubmit`**: submit immediately, and once more after the offset, so an
+//!   updated bundle of the same opportunity replaces the early one late in
+//!   the slot
+//!
+//! The slot of a target block starts at the timestamp of its parent block and
+//! lasts one block time.
+
+use crate::errors::{BundleError, Result};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// When bundles are submitted within the slot of their target block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SubmissionTiming {
+    /// Submit bundles as soon as they are signed
+    #[default]
+    Immediate,
+    /// Hold bundles until the offset into the slot
+    Delayed,
+    /// Submit immediately, and allow one updated bundle of every opportunity after the offset
+    Resubmit,
+}
+
+impl FromStr for SubmissionTiming {
+    type Err = BundleError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "immediate" => Ok(SubmissionTiming::Immediate),
+            "delayed" => Ok(SubmissionTiming::Delayed),
+            "resubmit" => Ok(SubmissionTiming::Resubmit),
+            _ => Err(BundleError::InvalidConfiguration {
+                message: format!("Unknown submission timing: {}", s),
+            }),
+        }
+    }
+}
+
+/// Decides when bundles are submitted within the slot of their target block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmissionScheduler {
+    timing: SubmissionTiming,
+    offset: Duration,
+    block_time: Duration,
+}
+
+impl Default for SubmissionScheduler {
+    fn default() -> Self {
+        Self {
+            timing: SubmissionTiming::Immediate,
+            offset: Duration::ZERO,
+            block_time: Duration::from_secs(12),
+        }
+    }
+}
+
+impl SubmissionScheduler {
+    /// Schedule submissions at `offset` into slots of `block_time`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the offset does not fall within the slot.
+    pub fn new(timing: SubmissionTiming, offset: Duration, block_time: Duration) -> Result<Self> {
+        if timing != SubmissionTiming::Immediate && offset >= block_time {
+            return Err(BundleError::InvalidConfiguration {
+                message: format!(
+                    "Submission offset of {} ms does not fall within slots of {} ms",
+                    offset.as_millis(),
+                    block_time.as_millis()
+                ),
+            }
+            .into());
+        }
+        Ok(Self {
+            timing,
+            offset,
+            block_time,
+        })
+    }
+
+    /// Get the configured timing.
+    pub fn timing(&self) -> SubmissionTiming {
+        self.timing
+    }
+
+    /// Get the offset into the slot of delayed submissions and resubmissions.
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    /// The timestamp in seconds of the parent of `target_block`, given the latest block.
+    ///
+    /// Block times below a second are added up in milliseconds and the sum is
+    /// rounded to the nearest second.
+    pub fn parent_timestamp(&self, target_block: u64, latest_block: u64, latest_timestamp: u64) -> u64 {
+        let blocks_ahead = u128::from(target_block.saturating_sub(latest_block + 1));
+        let ahead_millis = blocks_ahead * self.block_time.as_millis();
+        latest_timestamp + ((ahead_millis + 500) / 1000) as u64
+    }
+
+    /// The time `offset` into the slot starting at `parent_timestamp`.
+    pub fn submission_time(&self, parent_timestamp: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(parent_timestamp) + self.offset
+    }
+
+    /// How long to hold a bundle signed at `now` before submitting it.
+    ///
+    /// Only delayed submissions are held, and never past the offset.
+    pub fn hold_time(&self, parent_timestamp: u64, now: SystemTime) -> Duration {
+        match self.timing {
+            SubmissionTiming::Delayed => self
+                .submission_time(parent_timestamp)
+                .duration_since(now)
+                .unwrap_or_default(),
+            SubmissionTiming::Immediate | SubmissionTiming::Resubmit => Duration::ZERO,
+        }
+    }
+
+    /// How long after `now` an updated bundle may be resubmitted, `None` if it cannot be in this slot.
+    pub fn resubmission_delay(&self, parent_timestamp: u64, now: SystemTime) -> Option<Duration> {
+        if self.timing != SubmissionTiming::Resubmit {
+            return None;
+        }
+        match self.submission_time(parent_timestamp).duration_since(now) {
+            Ok(delay) => Some(delay),
+            Err(_) if self.allows_resubmission(parent_timestamp, now) => Some(Duration::ZERO),
+            Err(_) => None,
+        }
+    }
+
+    /// Whether a bundle signed at `now` may replace an earlier bundle of the same opportunity.
+    pub fn allows_resubmission(&self, parent_timestamp: u64, now: SystemTime) -> bool {
+        let slot_end = UNIX_EPOCH + Duration::from_secs(parent_timestamp) + self.block_time;
+        self.timing == SubmissionTiming::Resubmit && now >= self.submission_time(parent_timestamp) && now < slot_end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holds_or_resubmits_at_the_slot_offset() {
+        let parent_timestamp = 1_700_000_000;
+        let slot_start = UNIX_EPOCH + Duration::from_secs(parent_timestamp);
+        let delayed = SubmissionScheduler::new(SubmissionTiming::Delayed, Duration::from_secs(8), Duration::from_secs(12)).unwrap();
+        assert_eq!(delayed.hold_time(parent_timestamp, slot_start + Duration::from_secs(3)), Duration::from_secs(5));
+        assert_eq!(delayed.hold_time(parent_timestamp, slot_start + Duration::from_secs(9)), Duration::ZERO);
+        assert_eq!(delayed.resubmission_delay(parent_timestamp, slot_start), None);
+
+        let resubmit = SubmissionScheduler::new(SubmissionTiming::Resubmit, Duration::from_secs(8), Duration::from_secs(12)).unwrap();
+        assert_eq!(resubmit.hold_time(parent_timestamp, slot_start), Duration::ZERO);
+        assert_eq!(resubmit.resubmission_delay(parent_timestamp, slot_start + Duration::from_secs(2)), Some(Duration::from_secs(6)));
+        assert!(!resubmit.allows_resubmission(parent_timestamp, slot_start + Duration::from_secs(7)));
+        assert!(resubmit.allows_resubmission(parent_timestamp, slot_start + Duration::from_secs(10)));
+        assert_eq!(resubmit.resubmission_delay(parent_timestamp, slot_start + Duration::from_secs(13)), None);
+
+        // A bundle for two blocks ahead belongs to the slot after the next one
+        assert_eq!(resubmit.parent_timestamp(102, 100, parent_timestamp), parent_timestamp + 12);
+        let sub_second = SubmissionScheduler::new(SubmissionTiming::Resubmit, Duration::from_millis(100), Duration::from_millis(250)).unwrap();
+        assert_eq!(sub_second.parent_timestamp(106, 100, parent_timestamp), parent_timestamp + 1);
+        assert!(SubmissionScheduler::new(SubmissionTiming::Delayed, Duration::from_secs(12), Duration::from_secs(12)).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Construction of the transaction envelopes signed into bundles.
+//!
+//! Bundle transactions are built as EIP-1559 transactions by default, with the
+//! bribe paid through the priority fee. Some chains, e.g. L2s without EIP-1559
+//! or flows attaching blobs, need a different envelope. A `TxBuilder` turns a
+//! fully priced `TransactionRequest` into the typed transaction that is signed,
+//! and `EnvelopeTxBuilder` covers the standard envelope types with their fee
+//! fields and extra fields. The envelope type of a chain is set on its
+//! [`ChainSpec`](crate::chain::ChainSpec).
+
+use crate::errors::{BundleError, Result};
+use alloy::consensus::{TxType, TypedTransaction};
+use alloy::eips::eip2930::AccessList;
+use alloy::primitives::B256;
+use alloy::rpc::types::TransactionRequest;
+
+/// Builds the typed transaction signed for a transaction request.
+pub trait TxBuilder: Send + Sync {
+    /// Build the typed transaction of a request priced by the executor.
+    ///
+    /// The request carries EIP-1559 fee fields, with `max_fee_per_gas`
+    /// including the bribe paid through `max_priority_fee_per_gas`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request lacks fields required by the envelope.
+    fn build(&self, request: TransactionRequest) -> Result<TypedTransaction>;
+}
+
+/// Builds a configurable standard envelope type.
+///
+/// Envelopes without EIP-1559 fee fields pay `max_fee_per_gas` as their gas
+/// price, so the bribe stays included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvelopeTxBuilder {
+    tx_type: TxType,
+    access_list: Option<AccessList>,
+    max_fee_per_blob_gas: Option<u128>,
+    blob_versioned_hashes: Vec<B256>,
+}
+
+impl EnvelopeTxBuilder {
+    /// Create a builder for the given envelope type.
+    pub fn new(tx_type: TxType) -> Self {
+        Self {
+            tx_type,
+            access_list: None,
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: Vec::new(),
+        }
+    }
+
+    /// Attach an access list to every transaction, for EIP-2930 and later envelopes.
+    pub fn with_access_list(mut self, access_list: AccessList) -> Self {
+        self.access_list = Some(access_list);
+        self
+    }
+
+    /// Set the blob fee cap of EIP-4844 transactions.
+    pub fn with_max_fee_per_blob_gas(mut self, max_fee_per_blob_gas: u128) -> Self {
+        self.max_fee_per_blob_gas = Some(max_fee_per_blob_gas);
+        self
+    }
+
+    /// Set the versioned hashes of the blobs referenced by EIP-4844 transactions.
+    pub fn with_blob_versioned_hashes(mut self, blob_versioned_hashes: Vec<B256>) -> Self {
+        self.blob_versioned_hashes = blob_versioned_hashes;
+        self
+    }
+
+    /// The envelope type of built transactions.
+    pub fn tx_type(&self) -> TxType {
+        self.tx_type
+    }
+
+    fn build_failed(&self, reason: &str) -> BundleError {
+        BundleError::TransactionBuildFailed {
+            reason: format!("{:?}: {}", self.tx_type, reason),
+        }
+    }
+}
+
+impl Default for EnvelopeTxBuilder {
+    fn default() -> Self {
+        Self::new(TxType::Eip1559)
+    }
+}
+
+impl TxBuilder for EnvelopeTxBuilder {
+    fn build(&self, mut request: TransactionRequest) -> Result<TypedTransaction> {
+        if self.access_list.is_some() && self.tx_type != TxType::Legacy {
+            request.access_list = self.access_list.clone();
+        }
+
+        match self.tx_type {
+            TxType::Legacy | TxType::Eip2930 => {
+                let gas_price = request.max_fee_per_gas.take()
+                    .or(request.gas_price)
+                    .ok_or_else(|| self.build_failed("missing gas price"))?;
+                request.gas_price = Some(gas_price);
+                request.max_priority_fee_per_gas = None;
+                if self.tx_type == TxType::Eip2930 {
+                    request.access_list.get_or_insert_with(AccessList::default);
+                } else {
+                    request.access_list = None;
+                }
+            }
+            TxType::Eip1559 => {}
+            TxType::Eip4844 => {
+                if self.blob_versioned_hashes.is_empty() {
+                    return Err(self.build_failed("missing blob versioned hashes").into());
+                }
+                let max_fee_per_blob_gas = self.max_fee_per_blob_gas
+                    .ok_or_else(|| self.build_failed("missing blob fee cap"))?;
+                request.max_fee_per_blob_gas = Some(max_fee_per_blob_gas);
+                request.blob_versioned_hashes = Some(self.blob_versioned_hashes.clone());
+            }
+            TxType::Eip7702 => {
+                if request.authorization_list.is_none() {
+                    return Err(self.build_failed("missing authorization list").into());
+                }
+            }
+        }
+        request.transaction_type = Some(self.tx_type as u8);
+
+        request
+            .build_typed_tx()
+            .map_err(|_| self.build_failed("incomplete transaction request").into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Address, TxKind, U256};
+
+    fn priced_request() -> TransactionRequest {
+        TransactionRequest {
+            to: Some(TxKind::Call(Address::random())),
+            value: Some(U256::ZERO),
+            chain_id: Some(1),
+            gas: Some(100_000),
+            max_fee_per_gas: Some(2_000_000_000u128),
+            max_priority_fee_per_gas: Some(500_000_000u128),
+            nonce: Some(7),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_builds_configured_envelopes() {
+        let eip1559 = EnvelopeTxBuilder::default().build(priced_request()).unwrap();
+        assert_eq!(eip1559.tx_type(), TxType::Eip1559);
+
+        // Legacy envelopes pay the fee cap including the bribe as gas price
+        let legacy = EnvelopeTxBuilder::new(TxType::Legacy).build(priced_request()).unwrap();
+        assert_eq!(legacy.tx_type(), TxType::Legacy);
+        assert_eq!(legacy.legacy().unwrap().gas_price, 2_000_000_000u128);
+
+        let eip2930 = EnvelopeTxBuilder::new(TxType::Eip2930).build(priced_request()).unwrap();
+        assert_eq!(eip2930.tx_type(), TxType::Eip2930);
+    }
+
+    #[test]
+    fn test_blob_envelopes_require_blob_fields() {
+        let builder = EnvelopeTxBuilder::new(TxType::Eip4844);
+        assert!(builder.build(priced_request()).is_err());
+
+        let blob = builder
+            .with_max_fee_per_blob_gas(1)
+            .with_blob_versioned_hashes(vec![B256::with_last_byte(1)])
+            .build(priced_request())
+            .unwrap();
+        assert_eq!(blob.tx_type(), TxType::Eip4844);
+    }
+}
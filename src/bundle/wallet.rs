@@ -0,0 +1,277 @@
+//! Rotation of several executor wallets.
+//!
+//! A single executor wallet serializes all opportunities on one nonce sequence
+//! and links every trade to the same address. `WalletPool` holds several
+//! executor signers, selects one per opportunity and tracks the next nonce and
+//! the native balance of each, so opportunities of one block can be built on
+//! independent nonce sequences. The `TxExecutor` signs every transaction with
+//! the pool wallet matching its `from` address.
+
+use crate::errors::{BundleError, Result};
+use crate::signer::ExecutorSigner;
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, U256},
+    providers::{Provider, RootProvider},
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// How the pool selects the wallet of an opportunity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WalletSelection {
+    /// Cycle through the wallets in order
+    #[default]
+    RoundRobin,
+    /// Pick the wallet with the highest native balance
+    BalanceAware,
+}
+
+/// Nonce and balance of a pool wallet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalletAccount {
+    /// Next unused nonce, unknown until refreshed or set
+    pub next_nonce: Option<u64>,
+    /// Native balance as of the last refresh
+    pub balance: U256,
+}
+
+/// A wallet selected for an opportunity.
+#[derive(Debug, Clone)]
+pub struct SelectedWallet {
+    /// The address transactions are sent from
+    pub address: Address,
+    /// The signer of the wallet
+    pub signer: ExecutorSigner,
+}
+
+/// A pool of executor wallets with per-wallet nonces and balances.
+#[derive(Debug)]
+pub struct WalletPool {
+    signers: Vec<ExecutorSigner>,
+    accounts: RwLock<HashMap<Address, WalletAccount>>,
+    selection: WalletSelection,
+    next_index: AtomicUsize,
+}
+
+impl WalletPool {
+    /// Create a round-robin pool of the given signers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no signers are given or an address appears twice.
+    pub fn new(signers: Vec<ExecutorSigner>) -> Result<Self> {
+        if signers.is_empty() {
+            return Err(BundleError::InvalidConfiguration {
+                message: "A wallet pool needs at least one signer".to_string(),
+            }.into());
+        }
+
+        let mut accounts = HashMap::with_capacity(signers.len());
+        for signer in &signers {
+            if accounts.insert(signer.address(), WalletAccount::default()).is_some() {
+                return Err(BundleError::InvalidConfiguration {
+                    message: format!("Wallet {} appears twice in the wallet pool", signer.address()),
+                }.into());
+            }
+        }
+
+        Ok(Self {
+            signers,
+            accounts: RwLock::new(accounts),
+            selection: WalletSelection::default(),
+            next_index: AtomicUsize::new(0),
+        })
+    }
+
+    /// Set how wallets are selected.
+    pub fn with_selection(mut self, selection: WalletSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// The number of wallets in the pool.
+    pub fn len(&self) -> usize {
+        self.signers.len()
+    }
+
+    /// Whether the pool has no wallets.
+    pub fn is_empty(&self) -> bool {
+        self.signers.is_empty()
+    }
+
+    /// The addresses of all wallets, in pool order.
+    pub fn addresses(&self) -> Vec<Address> {
+        self.signers.iter().map(ExecutorSigner::address).collect()
+    }
+
+    /// The signer of a pool wallet.
+    pub fn signer(&self, address: &Address) -> Option<&ExecutorSigner> {
+        self.signers.iter().find(|signer| signer.address() == *address)
+    }
+
+    /// The tracked nonce and balance of a pool wallet.
+    pub fn account(&self, address: &Address) -> Option<WalletAccount> {
+        self.accounts.read().ok()?.get(address).cloned()
+    }
+
+    /// Select the wallet of the next opportunity.
+    pub fn select(&self) -> SelectedWallet {
+        self.select_with_min_balance(U256::ZERO)
+            .unwrap_or_else(|| self.wallet_at(self.next_round_robin_index()))
+    }
+
+    /// Select a wallet holding at least `min_balance` of the native token.
+    ///
+    /// # Returns
+    ///
+    /// `None` if no wallet holds enough according to the last refresh.
+    pub fn select_with_min_balance(&self, min_balance: U256) -> Option<SelectedWallet> {
+        let balances: Vec<U256> = {
+            let accounts = self.accounts.read().ok()?;
+            self.signers
+                .iter()
+                .map(|signer| accounts.get(&signer.address()).map(|account| account.balance).unwrap_or_default())
+                .collect()
+        };
+
+        let index = match self.selection {
+            WalletSelection::RoundRobin => (0..self.len())
+                .map(|_| self.next_round_robin_index())
+                .find(|index| balances[*index] >= min_balance)?,
+            WalletSelection::BalanceAware => balances
+                .iter()
+                .enumerate()
+                .filter(|(_, balance)| **balance >= min_balance)
+                .max_by_key(|(_, balance)| **balance)
+                .map(|(index, _)| index)?,
+        };
+
+        let wallet = self.wallet_at(index);
+        tracing::debug!(
+            wallet = %wallet.address,
+            balance = %balances[index],
+            selection = ?self.selection,
+            "Selected executor wallet"
+        );
+        Some(wallet)
+    }
+
+    /// Reserve `count` consecutive nonces of a wallet.
+    ///
+    /// # Returns
+    ///
+    /// The first reserved nonce, or `None` if the wallet is unknown or its nonce
+    /// was never refreshed.
+    pub fn reserve_nonces(&self, address: &Address, count: u64) -> Option<u64> {
+        let mut accounts = self.accounts.write().ok()?;
+        let account = accounts.get_mut(address)?;
+        let nonce = account.next_nonce?;
+        account.next_nonce = Some(nonce + count);
+        Some(nonce)
+    }
+
+    /// Set the next nonce of a wallet, e.g. after a bundle failed to land.
+    pub fn set_nonce(&self, address: &Address, nonce: u64) {
+        if let Ok(mut accounts) = self.accounts.write() {
+            if let Some(account) = accounts.get_mut(address) {
+                account.next_nonce = Some(nonce);
+            }
+        }
+    }
+
+    /// Record the native balance of a wallet.
+    pub fn record_balance(&self, address: &Address, balance: U256) {
+        if let Ok(mut accounts) = self.accounts.write() {
+            if let Some(account) = accounts.get_mut(address) {
+                account.balance = balance;
+            }
+        }
+    }
+
+    /// Read the nonces and native balances of all wallets.
+    ///
+    /// Nonces are read from the pending block, so transactions still in the
+    /// mempool are not reused.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the nonce or balance of any wallet cannot be read.
+    pub async fn refresh(&self, provider: &Arc<RootProvider<Ethereum>>) -> Result<()> {
+        for address in self.addresses() {
+            let (nonce, balance) = tokio::try_join!(
+                provider.get_transaction_count(address).pending(),
+                provider.get_balance(address),
+            )?;
+
+            if let Ok(mut accounts) = self.accounts.write() {
+                if let Some(account) = accounts.get_mut(&address) {
+                    account.next_nonce = Some(nonce);
+                    account.balance = balance;
+                }
+            }
+            tracing::debug!(
+                wallet = %address,
+                nonce = nonce,
+                balance = %balance,
+                "Refreshed executor wallet"
+            );
+        }
+        Ok(())
+    }
+
+    fn next_round_robin_index(&self) -> usize {
+        self.next_index.fetch_add(1, Ordering::Relaxed) % self.len()
+    }
+
+    fn wallet_at(&self, index: usize) -> SelectedWallet {
+        let signer = self.signers[index].clone();
+        SelectedWallet {
+            address: signer.address(),
+            signer,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::local::PrivateKeySigner;
+
+    fn pool(size: usize) -> WalletPool {
+        WalletPool::new((0..size).map(|_| ExecutorSigner::from(PrivateKeySigner::random())).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_selects_wallets_round_robin_and_by_balance() {
+        let pool = pool(3);
+        let addresses = pool.addresses();
+        let selected: Vec<Address> = (0..4).map(|_| pool.select().address).collect();
+        assert_eq!(selected, vec![addresses[0], addresses[1], addresses[2], addresses[0]]);
+
+        pool.record_balance(&addresses[1], U256::from(50));
+        pool.record_balance(&addresses[2], U256::from(100));
+        assert_eq!(pool.select_with_min_balance(U256::from(60)).unwrap().address, addresses[2]);
+        assert!(pool.select_with_min_balance(U256::from(200)).is_none());
+
+        let pool = pool.with_selection(WalletSelection::BalanceAware);
+        assert_eq!(pool.select().address, addresses[2]);
+
+        assert!(WalletPool::new(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_reserves_nonces_per_wallet() {
+        let pool = pool(2);
+        let addresses = pool.addresses();
+        assert_eq!(pool.reserve_nonces(&addresses[0], 1), None);
+
+        pool.set_nonce(&addresses[0], 7);
+        pool.set_nonce(&addresses[1], 3);
+        assert_eq!(pool.reserve_nonces(&addresses[0], 2), Some(7));
+        assert_eq!(pool.reserve_nonces(&addresses[0], 1), Some(9));
+        assert_eq!(pool.reserve_nonces(&addresses[1], 1), Some(3));
+        assert_eq!(pool.account(&addresses[0]).unwrap().next_nonce, Some(10));
+    }
+}
@@ -0,0 +1,379 @@
+//! Tracking of the chain head over WebSocket with an HTTP polling fallback.
+//!
+//! The Tycho stream reports the block its pool states were indexed at, which
+//! can lag the chain by the indexing latency. Bundles are only valid for the
+//! blocks after the real chain head, and are priced with the base fee of the
+//! next block. `HeadTracker` follows the chain head by subscribing to
+//! `newHeads` over WebSocket and falls back to polling the latest block over
+//! HTTP while the subscription is unavailable.
+//!
+//! The tracker also detects reorgs: a head replacing a recorded block, or
+//! whose parent is not the recorded head. The search loop takes the detected
+//! reorg with [`HeadTracker::take_reorg`] to drop state built on the replaced
+//! blocks.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::chain::HeadTracker;
+//!
+//! let tracker = Arc::new(HeadTracker::new());
+//! tokio::spawn(tracker.clone().run(Some(ws_url), provider, Duration::from_secs(1)));
+//! let simulator = simulator.with_head_tracker(tracker.clone());
+//! ```
+
+use crate::errors::Result;
+use crate::utils::{calculate_next_base_fee, fees::BaseFeePredictor};
+use alloy::{
+    consensus::BlockHeader,
+    eips::BlockNumberOrTag,
+    network::Ethereum,
+    primitives::{B256, U256},
+    providers::{Provider, ProviderBuilder, RootProvider, WsConnect},
+    rpc::types::Header,
+};
+use futures::StreamExt;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// How long the tracker polls over HTTP before reconnecting the WebSocket subscription.
+const WS_RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Number of recent heads kept for finding the common ancestor of a reorg.
+const HISTORY_BLOCKS: usize = 64;
+
+/// The latest block of the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainHead {
+    /// Block number
+    pub number: u64,
+    /// Block hash
+    pub hash: B256,
+    /// Hash of the parent block
+    pub parent_hash: B256,
+    /// Block timestamp in seconds
+    pub timestamp: u64,
+    /// Base fee per gas, `None` before EIP-1559
+    pub base_fee: Option<u128>,
+    /// Gas used by the block
+    pub gas_used: u128,
+    /// Gas limit of the block
+    pub gas_limit: u128,
+}
+
+impl ChainHead {
+    /// Read the chain head from an RPC block header.
+    pub fn from_header(header: &Header) -> Self {
+        Self {
+            number: header.number(),
+            hash: header.hash,
+            parent_hash: header.parent_hash(),
+            timestamp: header.timestamp(),
+            base_fee: header.base_fee_per_gas().map(Into::into),
+            gas_used: header.gas_used().into(),
+            gas_limit: header.gas_limit().into(),
+        }
+    }
+
+    /// The base fee of the next block under EIP-1559, `None` before EIP-1559.
+    pub fn next_base_fee(&self) -> Option<U256> {
+        self.base_fee
+            .map(|base_fee| calculate_next_base_fee(base_fee, self.gas_used, self.gas_limit))
+    }
+}
+
+/// A reorg replacing recorded blocks with a new canonical chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reorg {
+    /// The head before the reorg
+    pub old_head: ChainHead,
+    /// The new canonical head
+    pub new_head: ChainHead,
+    /// The first replaced block
+    ///
+    /// If the common ancestor is older than the recorded heads, all recorded
+    /// blocks are assumed to be replaced.
+    pub fork_block: u64,
+}
+
+impl Reorg {
+    /// The number of replaced blocks.
+    pub fn depth(&self) -> u64 {
+        (self.old_head.number + 1).saturating_sub(self.fork_block)
+    }
+}
+
+/// How a recorded head changed the tracked chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadUpdate {
+    /// The head is already known or older than the latest head of the same chain
+    Ignored,
+    /// The head extends the tracked chain
+    Extended,
+    /// The head replaced recorded blocks
+    Reorg(Reorg),
+}
+
+/// Recent heads of the tracked chain, oldest first.
+#[derive(Debug, Default)]
+struct HeadHistory {
+    heads: VecDeque<ChainHead>,
+    received_at: Option<Instant>,
+}
+
+/// Follows the chain head and shares it with the simulator and executor.
+#[derive(Debug, Default)]
+pub struct HeadTracker {
+    history: RwLock<HeadHistory>,
+    pending_reorg: Mutex<Option<Reorg>>,
+    base_fee_predictor: Option<Arc<BaseFeePredictor>>,
+}
+
+impl HeadTracker {
+    /// Create a tracker without a known head.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record every new header in a base fee predictor as well.
+    pub fn with_base_fee_predictor(mut self, predictor: Arc<BaseFeePredictor>) -> Self {
+        self.base_fee_predictor = Some(predictor);
+        self
+    }
+
+    /// The latest recorded head.
+    pub fn latest(&self) -> Option<ChainHead> {
+        self.history.read().ok()?.heads.back().copied()
+    }
+
+    /// The number of the latest recorded block.
+    pub fn block_number(&self) -> Option<u64> {
+        self.latest().map(|head| head.number)
+    }
+
+    /// The timestamp of the latest recorded block in seconds.
+    pub fn timestamp(&self) -> Option<u64> {
+        self.latest().map(|head| head.timestamp)
+    }
+
+    /// The base fee of the latest recorded block.
+    pub fn base_fee(&self) -> Option<U256> {
+        self.latest().and_then(|head| head.base_fee).map(U256::from)
+    }
+
+    /// The base fee of the block after the latest recorded block.
+    pub fn next_base_fee(&self) -> Option<U256> {
+        self.latest().and_then(|head| head.next_base_fee())
+    }
+
+    /// The time since the latest head was recorded.
+    pub fn age(&self) -> Option<Duration> {
+        self.history.read().ok()?.received_at.map(|received_at| received_at.elapsed())
+    }
+
+    /// Take the reorg detected since the last call, if any.
+    ///
+    /// Several reorgs between two calls are merged into one, replacing the
+    /// blocks from the earliest fork block.
+    pub fn take_reorg(&self) -> Option<Reorg> {
+        self.pending_reorg.lock().ok()?.take()
+    }
+
+    /// Record a new head.
+    ///
+    /// Known heads are ignored, so the WebSocket subscription and polling can
+    /// both report the same blocks, as are older heads filling a gap in the
+    /// recorded blocks. A head replacing a recorded block of the same number,
+    /// or whose parent is not the recorded block before it, is a reorg.
+    pub fn record(&self, head: ChainHead) -> HeadUpdate {
+        let Ok(mut history) = self.history.write() else {
+            return HeadUpdate::Ignored;
+        };
+        let Some(latest) = history.heads.back().copied() else {
+            history.heads.push_back(head);
+            history.received_at = Some(Instant::now());
+            return HeadUpdate::Extended;
+        };
+        if history.heads.iter().any(|known| known.hash == head.hash) {
+            return HeadUpdate::Ignored;
+        }
+
+        let replaces_block = history.heads.iter().any(|known| known.number == head.number);
+        let mismatches_parent = history
+            .heads
+            .iter()
+            .any(|known| known.number + 1 == head.number && known.hash != head.parent_hash);
+        let update = if replaces_block || mismatches_parent {
+            let fork_block = history
+                .heads
+                .iter()
+                .find(|known| known.hash == head.parent_hash)
+                .map_or_else(|| history.heads.front().map_or(head.number, |oldest| oldest.number), |ancestor| ancestor.number + 1);
+            HeadUpdate::Reorg(Reorg { old_head: latest, new_head: head, fork_block })
+        } else if head.number > latest.number {
+            HeadUpdate::Extended
+        } else {
+            return HeadUpdate::Ignored;
+        };
+
+        if let HeadUpdate::Reorg(reorg) = update {
+            while history.heads.back().is_some_and(|known| known.number >= reorg.fork_block) {
+                history.heads.pop_back();
+            }
+        }
+        history.heads.push_back(head);
+        while history.heads.len() > HISTORY_BLOCKS {
+            history.heads.pop_front();
+        }
+        history.received_at = Some(Instant::now());
+        drop(history);
+
+        match update {
+            HeadUpdate::Reorg(reorg) => {
+                tracing::warn!(
+                    old_head = reorg.old_head.number,
+                    new_head = reorg.new_head.number,
+                    fork_block = reorg.fork_block,
+                    depth = reorg.depth(),
+                    "Detected chain reorg"
+                );
+                if let Ok(mut pending) = self.pending_reorg.lock() {
+                    *pending = Some(match *pending {
+                        Some(earlier) => Reorg {
+                            old_head: earlier.old_head,
+                            new_head: reorg.new_head,
+                            fork_block: earlier.fork_block.min(reorg.fork_block),
+                        },
+                        None => reorg,
+                    });
+                }
+            }
+            _ => tracing::trace!(
+                block_number = head.number,
+                block_hash = %head.hash,
+                base_fee = ?head.base_fee,
+                "Recorded new chain head"
+            ),
+        }
+        update
+    }
+
+    /// Record a new header, updating the base fee predictor if one is set.
+    pub fn record_header(&self, header: &Header) -> HeadUpdate {
+        let update = self.record(ChainHead::from_header(header));
+        if let (HeadUpdate::Extended | HeadUpdate::Reorg(_), Some(predictor)) = (update, &self.base_fee_predictor) {
+            predictor.record_header(header);
+        }
+        update
+    }
+
+    /// Follow the chain head until the task is dropped.
+    ///
+    /// Subscribes to `newHeads` over WebSocket if a URL is given. While the
+    /// subscription is unavailable, the latest block is polled over HTTP, and
+    /// the subscription is retried every 30 seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `ws_url` - The WebSocket RPC URL, if any
+    /// * `provider` - The HTTP provider polled as a fallback
+    /// * `poll_interval` - The interval between polls of the latest block
+    pub async fn run(self: Arc<Self>, ws_url: Option<String>, provider: Arc<RootProvider<Ethereum>>, poll_interval: Duration) {
+        loop {
+            if let Some(ws_url) = &ws_url {
+                match self.follow_subscription(ws_url).await {
+                    Ok(()) => tracing::warn!(ws_url = %ws_url, "Block header subscription ended, polling over HTTP"),
+                    Err(e) => tracing::warn!(ws_url = %ws_url, error = %e, "Block header subscription failed, polling over HTTP"),
+                }
+            }
+
+            let polling_started = Instant::now();
+            while ws_url.is_none() || polling_started.elapsed() < WS_RECONNECT_INTERVAL {
+                if let Err(e) = self.poll_latest(&provider).await {
+                    tracing::debug!(error = %e, "Failed to poll the latest block");
+                }
+                tokio::time::sleep(poll_interval).await;
+            }
+        }
+    }
+
+    /// Record the headers of a `newHeads` subscription until it ends.
+    async fn follow_subscription(&self, ws_url: &str) -> Result<()> {
+        let provider = ProviderBuilder::new().connect_ws(WsConnect::new(ws_url)).await?;
+        let mut headers = provider.subscribe_blocks().await?.into_stream();
+        tracing::info!(ws_url = %ws_url, "Subscribed to new block headers");
+
+        while let Some(header) = headers.next().await {
+            self.record_header(&header);
+        }
+        Ok(())
+    }
+
+    /// Record the header of the latest block.
+    async fn poll_latest(&self, provider: &RootProvider<Ethereum>) -> Result<()> {
+        if let Some(block) = provider.get_block_by_number(BlockNumberOrTag::Latest).await? {
+            self.record_header(&block.header);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head(number: u64, hash: u8, parent: u8) -> ChainHead {
+        ChainHead {
+            number,
+            hash: B256::repeat_byte(hash),
+            parent_hash: B256::repeat_byte(parent),
+            timestamp: 1_700_000_000 + number * 12,
+            base_fee: Some(10_000_000_000),
+            gas_used: 30_000_000,
+            gas_limit: 30_000_000,
+        }
+    }
+
+    #[test]
+    fn test_records_newer_heads_only() {
+        let tracker = HeadTracker::new();
+        assert!(tracker.latest().is_none());
+
+        assert_eq!(tracker.record(head(100, 1, 0)), HeadUpdate::Extended);
+        assert_eq!(tracker.record(head(101, 2, 1)), HeadUpdate::Extended);
+        assert_eq!(tracker.record(head(99, 9, 8)), HeadUpdate::Ignored);
+        assert_eq!(tracker.record(head(100, 1, 0)), HeadUpdate::Ignored);
+        assert_eq!(tracker.block_number(), Some(101));
+        assert_eq!(tracker.timestamp(), Some(1_700_001_212));
+
+        // A head filling a gap behind the latest head is not a reorg
+        assert_eq!(tracker.record(head(103, 4, 3)), HeadUpdate::Extended);
+        assert_eq!(tracker.record(head(102, 3, 2)), HeadUpdate::Ignored);
+        assert_eq!(tracker.block_number(), Some(103));
+        assert!(tracker.take_reorg().is_none());
+
+        // A full block raises the next base fee by 12.5%
+        assert_eq!(tracker.base_fee(), Some(U256::from(10_000_000_000u64)));
+        assert_eq!(tracker.next_base_fee(), Some(U256::from(11_250_000_000u64)));
+    }
+
+    #[test]
+    fn test_detects_reorgs_from_the_common_ancestor() {
+        let tracker = HeadTracker::new();
+        for (number, hash, parent) in [(100, 1, 0), (101, 2, 1), (102, 3, 2)] {
+            tracker.record(head(number, hash, parent));
+        }
+
+        // Block 102 is replaced by a sibling, then block 101 by a longer fork
+        assert!(matches!(tracker.record(head(102, 4, 2)), HeadUpdate::Reorg(reorg) if reorg.depth() == 1));
+        assert!(matches!(tracker.record(head(102, 6, 5)), HeadUpdate::Reorg(reorg) if reorg.fork_block == 100));
+
+        let reorg = tracker.take_reorg().unwrap();
+        assert_eq!((reorg.old_head.hash, reorg.new_head.hash), (B256::repeat_byte(3), B256::repeat_byte(6)));
+        assert_eq!(reorg.fork_block, 100);
+        assert_eq!(reorg.depth(), 3);
+        assert!(tracker.take_reorg().is_none());
+        assert_eq!(tracker.latest().map(|head| head.hash), Some(B256::repeat_byte(6)));
+    }
+}
@@ -0,0 +1,364 @@
+//! Runtime registry of supported chains.
+//!
+//! Chain parameters such as the chain ID, the Permit2 deployment and the wrapped
+//! native token are looked up by chain name throughout the library. The registry
+//! ships with Ethereum, Base and Unichain, and downstream users can register
+//! further chains (e.g. Arbitrum, Optimism or custom L2s) at runtime without
+//! modifying the crate. The lookup functions in `utils` and the configuration
+//! module consult this registry.
+//!
+//! Every chain also lists well-known tokens by symbol, such as the wrapped
+//! native token and the major stablecoins, so source tokens can be configured
+//! as `USDC` instead of a checksummed address per chain.
+//!
+//! A [`ChainProfile`] resolves the execution quirks of a spec, such as a
+//! missing base fee or legacy-only transactions, for the simulator and the
+//! executor. The [`head_tracker`] module follows the head of the chain itself:
+//! its block number, timestamp, base fee and reorgs.
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use alloy::primitives::address;
+//! use tycho_atomic_arbitrage::chain::{ChainRegistry, ChainSpec};
+//!
+//! ChainRegistry::register(
+//!     ChainSpec::new("arbitrum", 42161, address!("82aF49447D8a07e3bd95BD0d56f35241523fBab1"))
+//!         .with_block_time(Duration::from_millis(250)),
+//! )?;
+//!
+//! assert_eq!(tycho_atomic_arbitrage::utils::chain_id("arbitrum")?, 42161);
+//! # Ok::<(), tycho_atomic_arbitrage::ArbitrageError>(())
+//! ```
+
+pub mod head_tracker;
+pub mod profile;
+
+pub use head_tracker::{ChainHead, HeadTracker, HeadUpdate, Reorg};
+pub use profile::{ChainProfile, FeeMarket, DEFAULT_BLOCK_TIME};
+
+use crate::errors::{Result, UtilityError};
+use crate::utils::l2_fees::OP_STACK_GAS_PRICE_ORACLE;
+use alloy::consensus::TxType;
+use alloy::primitives::{address, Address};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+/// Canonical Permit2 deployment, identical on all chains deployed via CREATE2.
+const CANONICAL_PERMIT2_ADDRESS: Address = address!("000000000022D473030F116dDEE9F6B43aC78BA3");
+
+/// Symbols of the well-known tokens pegged to the US dollar.
+pub const STABLECOIN_SYMBOLS: &[&str] = &["USDC", "USDT", "DAI"];
+
+/// Parameters of a supported chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainSpec {
+    /// Chain name used for lookups (e.g. "ethereum")
+    pub name: String,
+    /// EIP-155 chain ID
+    pub chain_id: u64,
+    /// Permit2 contract address
+    pub permit2_address: Address,
+    /// Whether Permit2 is deployed at `permit2_address`
+    pub permit2_deployed: bool,
+    /// Address representing the native token
+    pub native_token: Address,
+    /// Wrapped native token contract
+    pub wrapped_native: Address,
+    /// Average block time, if known
+    pub block_time: Option<Duration>,
+    /// Default Tycho indexer URL, if one exists
+    pub tycho_url: Option<String>,
+    /// Tycho protocol systems tracked when no exchanges are configured
+    pub default_exchanges: Vec<String>,
+    /// Envelope type of submitted transactions
+    pub tx_type: TxType,
+    /// How transactions are priced
+    pub fee_market: FeeMarket,
+    /// Oracle of the L1 data fee charged on top of L2 gas, if any
+    pub l1_fee_oracle: Option<Address>,
+    /// Canonical token contracts by upper-case symbol, including `WETH` for the wrapped native token
+    pub well_known_tokens: BTreeMap<String, Address>,
+}
+
+impl ChainSpec {
+    /// Create a chain spec with the canonical Permit2 address and the zero address as native token.
+    ///
+    /// The wrapped native token is listed as the well-known token `WETH`.
+    pub fn new(name: impl Into<String>, chain_id: u64, wrapped_native: Address) -> Self {
+        Self {
+            name: name.into(),
+            chain_id,
+            permit2_address: CANONICAL_PERMIT2_ADDRESS,
+            permit2_deployed: true,
+            native_token: Address::ZERO,
+            wrapped_native,
+            block_time: None,
+            tycho_url: None,
+            default_exchanges: Vec::new(),
+            tx_type: TxType::Eip1559,
+            fee_market: FeeMarket::Eip1559,
+            l1_fee_oracle: None,
+            well_known_tokens: BTreeMap::from([("WETH".to_string(), wrapped_native)]),
+        }
+    }
+
+    /// Set a non-canonical Permit2 address.
+    pub fn with_permit2_address(mut self, permit2_address: Address) -> Self {
+        self.permit2_address = permit2_address;
+        self.permit2_deployed = true;
+        self
+    }
+
+    /// Mark Permit2 as not deployed, so tokens are pulled with plain approvals.
+    pub fn without_permit2(mut self) -> Self {
+        self.permit2_deployed = false;
+        self
+    }
+
+    /// Set the address representing the native token.
+    pub fn with_native_token(mut self, native_token: Address) -> Self {
+        self.native_token = native_token;
+        self
+    }
+
+    /// Set the average block time.
+    pub fn with_block_time(mut self, block_time: Duration) -> Self {
+        self.block_time = Some(block_time);
+        self
+    }
+
+    /// Set the default Tycho indexer URL.
+    pub fn with_tycho_url(mut self, tycho_url: impl Into<String>) -> Self {
+        self.tycho_url = Some(tycho_url.into());
+        self
+    }
+
+    /// Set the envelope type of submitted transactions, e.g. `Legacy` on chains without EIP-1559.
+    pub fn with_tx_type(mut self, tx_type: TxType) -> Self {
+        self.tx_type = tx_type;
+        self
+    }
+
+    /// Price transactions with the gas price quoted by the node, for chains without a base fee.
+    ///
+    /// Such chains only accept legacy transactions, so the envelope type is set to `Legacy`.
+    pub fn without_base_fee(mut self) -> Self {
+        self.fee_market = FeeMarket::GasPrice;
+        self.tx_type = TxType::Legacy;
+        self
+    }
+
+    /// Set the `GasPriceOracle` pricing the L1 data fee, e.g. on OP-stack chains.
+    pub fn with_l1_fee_oracle(mut self, l1_fee_oracle: Address) -> Self {
+        self.l1_fee_oracle = Some(l1_fee_oracle);
+        self
+    }
+
+    /// Set the Tycho protocol systems tracked when no exchanges are configured.
+    pub fn with_default_exchanges<I, S>(mut self, exchanges: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.default_exchanges = exchanges.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// List a well-known token under a symbol, replacing any token listed under it.
+    ///
+    /// Symbols are matched case-insensitively.
+    pub fn with_well_known_token(mut self, symbol: &str, address: Address) -> Self {
+        self.well_known_tokens.insert(symbol.to_uppercase(), address);
+        self
+    }
+
+    /// The well-known token listed under a symbol, e.g. `USDC`.
+    pub fn well_known_token(&self, symbol: &str) -> Option<Address> {
+        self.well_known_tokens.get(&symbol.to_uppercase()).copied()
+    }
+
+    /// The execution quirks of the chain.
+    pub fn profile(&self) -> ChainProfile {
+        ChainProfile::from_spec(self)
+    }
+
+    /// The well-known stablecoins of the chain, see [`STABLECOIN_SYMBOLS`].
+    pub fn stablecoins(&self) -> Vec<Address> {
+        STABLECOIN_SYMBOLS
+            .iter()
+            .filter_map(|symbol| self.well_known_token(symbol))
+            .collect()
+    }
+}
+
+/// Process-wide registry of chain specs.
+pub struct ChainRegistry;
+
+impl ChainRegistry {
+    /// Register a chain, replacing any existing spec with the same name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chain ID is already registered under a different name.
+    pub fn register(spec: ChainSpec) -> Result<()> {
+        let mut chains = registry().write().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(existing) = chains
+            .values()
+            .find(|existing| existing.chain_id == spec.chain_id && existing.name != spec.name)
+        {
+            return Err(UtilityError::ConflictingChain {
+                chain: spec.name.clone(),
+                chain_id: spec.chain_id,
+                existing: existing.name.clone(),
+            }.into());
+        }
+
+        tracing::debug!(
+            chain = %spec.name,
+            chain_id = spec.chain_id,
+            "Registered chain"
+        );
+
+        chains.insert(spec.name.clone(), spec);
+        Ok(())
+    }
+
+    /// Look up a chain by name.
+    pub fn get(name: &str) -> Option<ChainSpec> {
+        registry()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .cloned()
+    }
+
+    /// Look up a chain by chain ID.
+    pub fn by_id(chain_id: u64) -> Option<ChainSpec> {
+        registry()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .find(|spec| spec.chain_id == chain_id)
+            .cloned()
+    }
+
+    /// Whether a chain with the given name is registered.
+    pub fn contains(name: &str) -> bool {
+        Self::get(name).is_some()
+    }
+
+    /// Names of all registered chains, sorted alphabetically.
+    pub fn names() -> Vec<String> {
+        let mut names: Vec<String> = registry()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+/// The global registry, initialized with the built-in chains.
+fn registry() -> &'static RwLock<HashMap<String, ChainSpec>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<String, ChainSpec>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let chains = builtin_chains()
+            .into_iter()
+            .map(|spec| (spec.name.clone(), spec))
+            .collect();
+        RwLock::new(chains)
+    })
+}
+
+/// Chains supported out of the box.
+fn builtin_chains() -> Vec<ChainSpec> {
+    vec![
+        ChainSpec::new("ethereum", 1, address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"))
+            .with_block_time(Duration::from_secs(12))
+            .with_tycho_url("tycho-beta.propellerheads.xyz")
+            .with_default_exchanges([
+                "uniswap_v2",
+                "sushiswap_v2",
+                "pancakeswap_v2",
+                "uniswap_v3",
+                "pancakeswap_v3",
+                "vm:balancer_v2",
+                "uniswap_v4",
+                "vm:curve",
+            ])
+            .with_well_known_token("USDC", address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"))
+            .with_well_known_token("USDT", address!("dAC17F958D2ee523a2206206994597C13D831ec7"))
+            .with_well_known_token("DAI", address!("6B175474E89094C44Da98b954EedeAC495271d0F"))
+            .with_well_known_token("WBTC", address!("2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599")),
+        ChainSpec::new("base", 8453, address!("4200000000000000000000000000000000000006"))
+            .with_block_time(Duration::from_secs(2))
+            .with_tycho_url("tycho-base-beta.propellerheads.xyz")
+            .with_l1_fee_oracle(OP_STACK_GAS_PRICE_ORACLE)
+            .with_default_exchanges(["uniswap_v2", "uniswap_v3"])
+            .with_well_known_token("USDC", address!("833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"))
+            .with_well_known_token("USDT", address!("fde4C96c8593536E31F229EA8f37b2ADa2699bb2"))
+            .with_well_known_token("DAI", address!("50c5725949A6F0c72E6C4a641F24049A917DB0Cb"))
+            .with_well_known_token("WBTC", address!("0555E30da8f98308EdB960aa94C0Db47230d2B9c")),
+        ChainSpec::new("unichain", 130, address!("4200000000000000000000000000000000000006"))
+            .with_block_time(Duration::from_secs(1))
+            .with_tycho_url("tycho-unichain-beta.propellerheads.xyz")
+            .with_l1_fee_oracle(OP_STACK_GAS_PRICE_ORACLE)
+            .with_default_exchanges(["uniswap_v2", "uniswap_v3", "pancakeswap_v3", "uniswap_v4"])
+            .with_well_known_token("USDC", address!("078D782b760474a361dDA0AF3839290b0EF57AD6"))
+            .with_well_known_token("WBTC", address!("0555E30da8f98308EdB960aa94C0Db47230d2B9c")),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_chains() {
+        let ethereum = ChainRegistry::get("ethereum").unwrap();
+        assert_eq!(ethereum.chain_id, 1);
+        assert_eq!(ethereum.permit2_address, CANONICAL_PERMIT2_ADDRESS);
+        assert_eq!(ethereum.tx_type, TxType::Eip1559);
+        assert_eq!(ChainRegistry::by_id(8453).unwrap().name, "base");
+        assert!(!ChainRegistry::contains("invalid_chain"));
+    }
+
+    #[test]
+    fn test_well_known_tokens() {
+        let base = ChainRegistry::get("base").unwrap();
+        assert_eq!(base.well_known_token("weth"), Some(base.wrapped_native));
+        assert_eq!(
+            base.well_known_token("USDC"),
+            Some(address!("833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"))
+        );
+        assert_eq!(base.stablecoins().len(), 3);
+        assert_eq!(
+            crate::utils::well_known_token_address("ethereum", "dai").unwrap(),
+            address!("6B175474E89094C44Da98b954EedeAC495271d0F")
+        );
+        assert!(crate::utils::well_known_token_address("unichain", "DAI").is_err());
+    }
+
+    #[test]
+    fn test_register_custom_chain() {
+        let weth = address!("4200000000000000000000000000000000000006");
+        ChainRegistry::register(
+            ChainSpec::new("test_l2", 999_999, weth).with_block_time(Duration::from_secs(2)),
+        )
+        .unwrap();
+
+        assert_eq!(crate::utils::chain_id("test_l2").unwrap(), 999_999);
+        assert_eq!(crate::utils::chain_name(999_999).unwrap(), "test_l2");
+        assert_eq!(crate::utils::wrapped_native_address("test_l2").unwrap(), weth);
+        assert!(ChainRegistry::names().contains(&"test_l2".to_string()));
+
+        // A chain ID cannot be claimed by a second name
+        assert!(ChainRegistry::register(ChainSpec::new("other_l2", 999_999, weth)).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Execution quirks of EVM chains.
+//!
+//! Pricing, envelopes and timing of bundles differ between EVM chains in ways
+//! that are easy to get wrong when Ethereum mainnet is assumed:
+//!
+//! - **Fee market**: some chains carry no base fee in their headers and price
+//!   transactions with a plain gas price quoted by the node
+//! - **Block time**: blocks arrive every 12 seconds on Ethereum, but every
+//!   second or faster on many L2s
+//! - **Envelopes**: chains without EIP-1559 only accept legacy transactions
+//! - **Permit2**: the canonical deployment may be missing or replaced
+//!
+//! A `ChainProfile` resolves these quirks from a [`ChainSpec`] once, so the
+//! simulator, the executor and the fee logic consult one place instead of
+//! assuming them.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::chain::ChainProfile;
+//!
+//! let profile = ChainProfile::for_chain_id(config.chain_id);
+//! let blocks = profile.blocks_in(Duration::from_secs(60));
+//! if !profile.has_base_fee() {
+//!     let gas_price = provider.get_gas_price().await?;
+//! }
+//! ```
+
+use crate::chain::{ChainRegistry, ChainSpec};
+use alloy::consensus::TxType;
+use alloy::primitives::Address;
+use std::time::Duration;
+
+/// Block time of chains that register none, Ethereum's slot time.
+pub const DEFAULT_BLOCK_TIME: Duration = Duration::from_secs(12);
+
+/// How transactions of a chain are priced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeeMarket {
+    /// Headers carry an EIP-1559 base fee, transactions pay a priority fee on top
+    #[default]
+    Eip1559,
+    /// Headers carry no base fee, transactions pay the gas price quoted by the node
+    GasPrice,
+}
+
+/// The execution quirks of a chain, resolved from its spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainProfile {
+    chain_id: u64,
+    block_time: Duration,
+    fee_market: FeeMarket,
+    tx_type: TxType,
+    permit2_address: Option<Address>,
+}
+
+impl ChainProfile {
+    /// Resolve the profile of a chain spec, assuming [`DEFAULT_BLOCK_TIME`] if it has none.
+    pub fn from_spec(spec: &ChainSpec) -> Self {
+        Self {
+            chain_id: spec.chain_id,
+            block_time: spec.block_time.unwrap_or(DEFAULT_BLOCK_TIME),
+            fee_market: spec.fee_market,
+            tx_type: spec.tx_type,
+            permit2_address: spec.permit2_deployed.then_some(spec.permit2_address),
+        }
+    }
+
+    /// Resolve the profile of a registered chain, or Ethereum's quirks for an unknown chain ID.
+    pub fn for_chain_id(chain_id: u64) -> Self {
+        match ChainRegistry::by_id(chain_id) {
+            Some(spec) => Self::from_spec(&spec),
+            None => {
+                tracing::debug!(chain_id = chain_id, "Unregistered chain, assuming Ethereum's execution quirks");
+                Self::from_spec(&ChainSpec::new("unregistered", chain_id, Address::ZERO))
+            }
+        }
+    }
+
+    /// Use a custom Permit2 deployment.
+    pub fn with_permit2_address(mut self, permit2_address: Address) -> Self {
+        self.permit2_address = Some(permit2_address);
+        self
+    }
+
+    /// The EIP-155 chain ID.
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// The average block time.
+    pub fn block_time(&self) -> Duration {
+        self.block_time
+    }
+
+    /// How transactions are priced.
+    pub fn fee_market(&self) -> FeeMarket {
+        self.fee_market
+    }
+
+    /// Whether block headers carry an EIP-1559 base fee.
+    pub fn has_base_fee(&self) -> bool {
+        self.fee_market == FeeMarket::Eip1559
+    }
+
+    /// The envelope type of submitted transactions.
+    pub fn tx_type(&self) -> TxType {
+        self.tx_type
+    }
+
+    /// The Permit2 deployment, if the chain has one.
+    pub fn permit2_address(&self) -> Option<Address> {
+        self.permit2_address
+    }
+
+    /// The number of blocks produced within `duration`, at least 1.
+    pub fn blocks_in(&self, duration: Duration) -> u64 {
+        let block_time = self.block_time.as_millis().max(1);
+        (duration.as_millis().div_ceil(block_time) as u64).max(1)
+    }
+}
+
+impl Default for ChainProfile {
+    /// The quirks of Ethereum mainnet.
+    fn default() -> Self {
+        Self::for_chain_id(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+
+    #[test]
+    fn test_profile_resolves_chain_quirks() {
+        let ethereum = ChainProfile::default();
+        assert!(ethereum.has_base_fee());
+        assert_eq!(ethereum.tx_type(), TxType::Eip1559);
+        assert_eq!(ethereum.blocks_in(Duration::from_secs(60)), 5);
+        assert!(ethereum.permit2_address().is_some());
+
+        let legacy = ChainProfile::from_spec(
+            &ChainSpec::new("legacy_l2", 999_998, address!("4200000000000000000000000000000000000006"))
+                .with_block_time(Duration::from_millis(250))
+                .without_base_fee()
+                .without_permit2(),
+        );
+        assert_eq!(legacy.fee_market(), FeeMarket::GasPrice);
+        assert_eq!(legacy.tx_type(), TxType::Legacy);
+        assert_eq!(legacy.blocks_in(Duration::from_secs(1)), 4);
+        assert_eq!(legacy.permit2_address(), None);
+    }
+}
@@ -2,20 +2,55 @@
 //! 
 //! This module provides secure configuration loading and validation,
 //! replacing hard-coded values with environment-based configuration.
+//!
+//! Tunable parameters are described by [`ArbitrageSettings`], which can be
+//! loaded in layers from TOML or JSON files and environment variables using a
+//! [`SettingsLoader`]. Later layers override individual fields of earlier ones.
+//! Private keys are never part of the settings and are always read from the
+//! environment when an [`ArbitrageConfig`] is built.
 
+use crate::bundle::RetryPolicy;
 use crate::errors::{BundleError, Result};
+use crate::path::{DiscoveryPolicy, ProtocolAllowlist, ProtocolGasTable};
+use crate::signer::ExecutorSigner;
+use alloy::primitives::Address;
 use alloy::signers::local::PrivateKeySigner;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
+use std::path::Path;
 use std::str::FromStr;
+use std::time::Duration;
+use tycho_common::Bytes;
 
 /// Configuration for relayer endpoints
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RelayerConfig {
     /// List of relayer URLs to submit bundles to
     pub urls: Vec<String>,
     /// Timeout for relayer requests in milliseconds
     pub timeout_ms: u64,
+    /// Attempts per relayer and bundle, including the first one
+    pub max_attempts: u32,
+    /// Backoff before the first retry in milliseconds, doubled for every further retry
+    pub retry_backoff_ms: u64,
+    /// Upper bound of the retry backoff in milliseconds
+    pub max_retry_backoff_ms: u64,
+    /// Share of the retry backoff that is randomized, between 0 and 1
+    pub retry_jitter: f64,
+}
+
+impl RelayerConfig {
+    /// The retry policy of submissions to the relayers.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new()
+            .with_max_attempts(self.max_attempts)
+            .with_base_backoff(Duration::from_millis(self.retry_backoff_ms))
+            .with_max_backoff(Duration::from_millis(self.max_retry_backoff_ms))
+            .with_jitter(self.retry_jitter)
+    }
 }
 
 impl Default for RelayerConfig {
@@ -27,17 +62,761 @@ impl Default for RelayerConfig {
                 "https://relay.flashbots.net".to_string(),
             ],
             timeout_ms: 5000,
+            max_attempts: 3,
+            retry_backoff_ms: 50,
+            max_retry_backoff_ms: 500,
+            retry_jitter: 0.5,
+        }
+    }
+}
+
+/// Filters of discovered paths.
+///
+/// Paths trading through a single protocol or the same pool twice are almost
+/// never profitable after fees and flood the candidate set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathFilterConfig {
+    /// Number of distinct protocol systems a path has to trade through
+    pub min_distinct_protocols: usize,
+    /// Whether paths trading through the same pool address more than once are excluded
+    pub exclude_pool_reuse: bool,
+}
+
+impl PathFilterConfig {
+    /// The discovery policy applying these filters.
+    pub fn discovery_policy(&self) -> DiscoveryPolicy {
+        DiscoveryPolicy::new()
+            .with_min_distinct_protocols(self.min_distinct_protocols)
+            .with_pool_reuse(!self.exclude_pool_reuse)
+    }
+}
+
+impl Default for PathFilterConfig {
+    fn default() -> Self {
+        Self {
+            min_distinct_protocols: 1,
+            exclude_pool_reuse: true,
+        }
+    }
+}
+
+/// Optimization tolerances of start tokens, keyed by token address or symbol.
+///
+/// The tolerance is the percentage of the search range at which the optimizer
+/// stops narrowing down the input amount of a path. Keys are token addresses
+/// or symbols well known on the configured chain, e.g. `WETH`; start tokens
+/// without a key use the default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OptimizationToleranceConfig {
+    /// Tolerance percentage of start tokens without their own
+    pub default: f64,
+    /// Tolerance percentages by token address or well-known symbol
+    pub tokens: BTreeMap<String, f64>,
+}
+
+impl OptimizationToleranceConfig {
+    /// Resolve the token keys to addresses on `chain`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a key is neither an address nor a symbol well known on the chain.
+    pub fn resolve(&self, chain: &str) -> Result<OptimizationTolerances> {
+        self.tokens.iter().try_fold(OptimizationTolerances::new(self.default), |tolerances, (key, tolerance)| -> Result<_> {
+            let address = match Address::from_str(key) {
+                Ok(address) => address,
+                Err(_) => crate::utils::well_known_token_address(chain, key).map_err(|e| {
+                    BundleError::InvalidConfiguration {
+                        message: format!("Unknown token {} in optimization tolerances: {}", key, e),
+                    }
+                })?,
+            };
+            Ok(tolerances.with_tolerance(Bytes::from(address.to_vec()), *tolerance))
+        })
+    }
+}
+
+impl Default for OptimizationToleranceConfig {
+    fn default() -> Self {
+        Self {
+            default: 1.0,
+            tokens: BTreeMap::new(),
+        }
+    }
+}
+
+/// Optimization tolerance percentages of start tokens, resolved to addresses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizationTolerances {
+    default: f64,
+    by_token: HashMap<Bytes, f64>,
+}
+
+impl OptimizationTolerances {
+    /// Use `default` for every start token.
+    pub fn new(default: f64) -> Self {
+        Self {
+            default,
+            by_token: HashMap::new(),
+        }
+    }
+
+    /// Use `tolerance` for the given start token.
+    pub fn with_tolerance(mut self, token: Bytes, tolerance: f64) -> Self {
+        self.by_token.insert(token, tolerance);
+        self
+    }
+
+    /// The tolerance percentage of a start token.
+    pub fn tolerance(&self, token: &Bytes) -> f64 {
+        self.by_token.get(token).copied().unwrap_or(self.default)
+    }
+
+    /// The tolerance percentage of start tokens without their own.
+    pub fn default_tolerance(&self) -> f64 {
+        self.default
+    }
+}
+
+/// Filter configuration of one exchange, i.e. one Tycho protocol system.
+///
+/// With an allowlist, only the listed pools of the exchange are streamed and
+/// the TVL threshold does not apply. Pools on the denylist are never added to
+/// the trading graph.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExchangeConfig {
+    /// Whether pools of the exchange are tracked
+    pub enabled: bool,
+    /// Minimum TVL for pools of this exchange (default: the global threshold)
+    pub tvl_threshold: Option<f64>,
+    /// Pools to track exclusively, by component ID
+    pub allowlist: Vec<Bytes>,
+    /// Pools to ignore, by component ID
+    pub denylist: Vec<Bytes>,
+}
+
+impl ExchangeConfig {
+    /// Create an enabled exchange without pool lists.
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            tvl_threshold: None,
+            allowlist: Vec::new(),
+            denylist: Vec::new(),
+        }
+    }
+
+    /// Create a disabled exchange.
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::new()
+        }
+    }
+
+    /// Set the minimum TVL for pools of this exchange.
+    pub fn with_tvl_threshold(mut self, tvl_threshold: f64) -> Self {
+        self.tvl_threshold = Some(tvl_threshold);
+        self
+    }
+
+    /// Track only the given pools.
+    pub fn with_allowlist<I>(mut self, pools: I) -> Self
+    where
+        I: IntoIterator<Item = Bytes>,
+    {
+        self.allowlist = pools.into_iter().collect();
+        self
+    }
+
+    /// Ignore the given pools.
+    pub fn with_denylist<I>(mut self, pools: I) -> Self
+    where
+        I: IntoIterator<Item = Bytes>,
+    {
+        self.denylist = pools.into_iter().collect();
+        self
+    }
+
+    /// Whether a pool of this exchange passes the pool lists.
+    pub fn allows_pool(&self, pool: &Bytes) -> bool {
+        self.enabled
+            && (self.allowlist.is_empty() || self.allowlist.contains(pool))
+            && !self.denylist.contains(pool)
+    }
+}
+
+impl Default for ExchangeConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The exchanges tracked on a chain, with their filters.
+///
+/// Consumed by the stream layer to subscribe to exchanges and by the trading
+/// graph to reject components of untracked exchanges or denied pools.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExchangeFilter {
+    exchanges: BTreeMap<String, ExchangeConfig>,
+    default_tvl_threshold: f64,
+}
+
+impl ExchangeFilter {
+    /// Create a filter without exchanges.
+    ///
+    /// # Arguments
+    ///
+    /// * `default_tvl_threshold` - Minimum TVL of exchanges without their own threshold
+    pub fn new(default_tvl_threshold: f64) -> Self {
+        Self {
+            exchanges: BTreeMap::new(),
+            default_tvl_threshold,
+        }
+    }
+
+    /// Create a filter with the default exchanges registered for a chain.
+    pub fn for_chain(chain: &str, default_tvl_threshold: f64) -> Self {
+        let default_exchanges = crate::chain::ChainRegistry::get(chain)
+            .map(|spec| spec.default_exchanges)
+            .unwrap_or_default();
+
+        default_exchanges
+            .into_iter()
+            .fold(Self::new(default_tvl_threshold), |filter, name| {
+                filter.with_exchange(name, ExchangeConfig::new())
+            })
+    }
+
+    /// Add an exchange, replacing its previous configuration.
+    pub fn with_exchange(mut self, name: impl Into<String>, config: ExchangeConfig) -> Self {
+        self.exchanges.insert(name.into(), config);
+        self
+    }
+
+    /// The enabled exchanges and their configuration, sorted by name.
+    pub fn enabled_exchanges(&self) -> impl Iterator<Item = (&str, &ExchangeConfig)> {
+        self.exchanges
+            .iter()
+            .filter(|(_, config)| config.enabled)
+            .map(|(name, config)| (name.as_str(), config))
+    }
+
+    /// The configuration of an exchange, if it is enabled.
+    pub fn exchange(&self, name: &str) -> Option<&ExchangeConfig> {
+        self.exchanges.get(name).filter(|config| config.enabled)
+    }
+
+    /// The minimum TVL of an enabled exchange.
+    pub fn tvl_threshold(&self, name: &str) -> Option<f64> {
+        self.exchange(name)
+            .map(|config| config.tvl_threshold.unwrap_or(self.default_tvl_threshold))
+    }
+
+    /// Whether a pool of the given protocol system is tracked.
+    pub fn allows(&self, protocol_system: &str, pool: &Bytes) -> bool {
+        self.exchange(protocol_system)
+            .is_some_and(|config| config.allows_pool(pool))
+    }
+}
+
+/// Tunable parameters of the arbitrage system.
+///
+/// All fields have defaults, so a settings file only needs to contain the
+/// values it changes. Address overrides fall back to the chain's registered
+/// addresses when unset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArbitrageSettings {
+    /// Target blockchain
+    pub chain: String,
+    /// Minimum TVL for pools to consider
+    pub tvl_threshold: f64,
+    /// Minimum profit in BPS of spot price product to consider for optimization
+    pub min_profit_bps: u64,
+    /// Slippage tolerance in BPS for trades
+    pub slippage_bps: u64,
+    /// Bribe percentage of expected profit (0-100)
+    pub bribe_percentage: u64,
+    /// Optimization tolerance percentages, one for each start token in order
+    ///
+    /// Superseded by `start_token_tolerances`, whose entries take precedence.
+    pub optimization_tolerances: Vec<f64>,
+    /// Optimization tolerance percentages by start token, with a default
+    pub start_token_tolerances: OptimizationToleranceConfig,
+    /// Relayer configuration
+    pub relayer: RelayerConfig,
+    /// Permit2 contract address override
+    pub permit2_address: Option<Address>,
+    /// Native token address override
+    pub native_token: Option<Address>,
+    /// Wrapped native token address override
+    pub wrapped_native: Option<Address>,
+    /// Address receiving the output of executed paths (default: the executor)
+    pub receiver_address: Option<Address>,
+    /// Custom router contract executing paths (default: the Tycho router of the chain)
+    pub router_address: Option<Address>,
+    /// Function of the router called with the Tycho router arguments, e.g. `swap(uint256,...)`
+    pub router_function_signature: Option<String>,
+    /// Per-exchange filters, by Tycho protocol system, on top of the chain's default exchanges
+    pub exchanges: BTreeMap<String, ExchangeConfig>,
+    /// Filters of discovered paths
+    pub path_filters: PathFilterConfig,
+    /// Estimated gas of one swap by Tycho protocol system, on top of the shipped defaults
+    pub protocol_gas: BTreeMap<String, u64>,
+    /// Tycho protocol systems whose paths may be submitted (default: every protocol)
+    pub execution_protocols: Option<Vec<String>>,
+}
+
+impl Default for ArbitrageSettings {
+    fn default() -> Self {
+        Self {
+            chain: "ethereum".to_string(),
+            tvl_threshold: 70.0,
+            min_profit_bps: 100,
+            slippage_bps: 50,
+            bribe_percentage: 50,
+            optimization_tolerances: Vec::new(),
+            start_token_tolerances: OptimizationToleranceConfig::default(),
+            relayer: RelayerConfig::default(),
+            permit2_address: None,
+            native_token: None,
+            wrapped_native: None,
+            receiver_address: None,
+            router_address: None,
+            router_function_signature: None,
+            exchanges: BTreeMap::new(),
+            path_filters: PathFilterConfig::default(),
+            protocol_gas: BTreeMap::new(),
+            execution_protocols: None,
+        }
+    }
+}
+
+impl ArbitrageSettings {
+    /// The exchanges to track: the chain's defaults, overridden by the configured ones.
+    pub fn exchange_filter(&self) -> ExchangeFilter {
+        self.exchanges.iter().fold(
+            ExchangeFilter::for_chain(&self.chain, self.tvl_threshold),
+            |filter, (name, config)| filter.with_exchange(name.clone(), config.clone()),
+        )
+    }
+
+    /// The discovery policy applying the configured path filters.
+    pub fn discovery_policy(&self) -> DiscoveryPolicy {
+        self.path_filters.discovery_policy()
+    }
+
+    /// The estimated gas of swaps: the shipped defaults, overridden by the configured ones.
+    pub fn protocol_gas_table(&self) -> ProtocolGasTable {
+        ProtocolGasTable::default().with_overrides(&self.protocol_gas)
+    }
+
+    /// The protocols trusted for execution; paths through others are analyzed but never submitted.
+    pub fn execution_allowlist(&self) -> ProtocolAllowlist {
+        match &self.execution_protocols {
+            Some(protocols) => ProtocolAllowlist::only(protocols.iter().cloned()),
+            None => ProtocolAllowlist::all(),
+        }
+    }
+
+    /// The optimization tolerances of the given start tokens.
+    ///
+    /// Tokens listed in `start_token_tolerances` use their own tolerance. The
+    /// others take the positional `optimization_tolerances` entry of their
+    /// index, if any, and the default otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a token key cannot be resolved on the configured chain.
+    pub fn resolve_start_token_tolerances(&self, start_tokens: &[Bytes]) -> Result<OptimizationTolerances> {
+        let mut tolerances = self.start_token_tolerances.resolve(&self.chain)?;
+        for (token, tolerance) in start_tokens.iter().zip(&self.optimization_tolerances) {
+            if !tolerances.by_token.contains_key(token) {
+                tolerances = tolerances.with_tolerance(token.clone(), *tolerance);
+            }
+        }
+        Ok(tolerances)
+    }
+
+    /// Load settings from the environment on top of the defaults.
+    ///
+    /// See [`SettingsLoader::with_env`] for the variables read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable cannot be parsed or a value is out of range.
+    pub fn from_env() -> Result<Self> {
+        SettingsLoader::new().with_env()?.load()
+    }
+
+    /// Load settings from a TOML or JSON file on top of the defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or a value is out of range.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        SettingsLoader::new().with_file(path)?.load()
+    }
+
+    /// Check that all values are within their valid ranges.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first invalid value.
+    pub fn validate(&self) -> Result<()> {
+        if !crate::chain::ChainRegistry::contains(&self.chain) {
+            return Err(BundleError::InvalidConfiguration {
+                message: format!(
+                    "Invalid chain: {}. Must be one of: {}",
+                    self.chain,
+                    crate::chain::ChainRegistry::names().join(", ")
+                ),
+            }.into());
+        }
+
+        if self.tvl_threshold.is_nan() || self.tvl_threshold < 0.0 {
+            return Err(BundleError::InvalidConfiguration {
+                message: format!("tvl_threshold must be non-negative, got: {}", self.tvl_threshold),
+            }.into());
+        }
+
+        if self.min_profit_bps > 10000 {
+            return Err(BundleError::InvalidConfiguration {
+                message: format!("min_profit_bps must be <= 10000 (100%), got: {}", self.min_profit_bps),
+            }.into());
+        }
+
+        if self.slippage_bps > 10000 {
+            return Err(BundleError::InvalidConfiguration {
+                message: format!("slippage_bps must be <= 10000 (100%), got: {}", self.slippage_bps),
+            }.into());
+        }
+
+        if self.bribe_percentage > 100 {
+            return Err(BundleError::InvalidConfiguration {
+                message: format!("bribe_percentage must be between 0 and 100, got: {}", self.bribe_percentage),
+            }.into());
+        }
+
+        if let Some(tolerance) = self
+            .optimization_tolerances
+            .iter()
+            .find(|tolerance| tolerance.is_nan() || **tolerance <= 0.0)
+        {
+            return Err(BundleError::InvalidConfiguration {
+                message: format!("Optimization tolerances must be positive, got: {}", tolerance),
+            }.into());
+        }
+
+        if let Some(tolerance) = std::iter::once(&self.start_token_tolerances.default)
+            .chain(self.start_token_tolerances.tokens.values())
+            .find(|tolerance| tolerance.is_nan() || **tolerance <= 0.0)
+        {
+            return Err(BundleError::InvalidConfiguration {
+                message: format!("Optimization tolerances must be positive, got: {}", tolerance),
+            }.into());
+        }
+        self.start_token_tolerances.resolve(&self.chain)?;
+
+        if let Some((name, config)) = self.exchanges.iter().find(|(_, config)| {
+            config.tvl_threshold.is_some_and(|tvl| tvl.is_nan() || tvl < 0.0)
+        }) {
+            return Err(BundleError::InvalidConfiguration {
+                message: format!(
+                    "tvl_threshold of exchange {} must be non-negative, got: {:?}",
+                    name, config.tvl_threshold
+                ),
+            }.into());
+        }
+
+        if let Some(signature) = self.router_function_signature.as_deref() {
+            let is_signature = signature
+                .split_once('(')
+                .is_some_and(|(name, _)| !name.is_empty() && !name.contains(char::is_whitespace))
+                && signature.ends_with(')');
+            if !is_signature {
+                return Err(BundleError::InvalidConfiguration {
+                    message: format!("router_function_signature must look like name(type,...), got: {}", signature),
+                }.into());
+            }
+        }
+
+        ArbitrageConfig::validate_relayer_urls(&self.relayer.urls)
+    }
+}
+
+/// Builds [`ArbitrageSettings`] from layered sources.
+///
+/// The loader starts from the default settings. Each added layer overrides the
+/// fields it contains and leaves all other fields untouched, so a typical setup
+/// adds a settings file first and the environment last.
+///
+/// ```rust,no_run
+/// use tycho_atomic_arbitrage::config::SettingsLoader;
+///
+/// let settings = SettingsLoader::new()
+///     .with_file("arbitrage.toml")?
+///     .with_env()?
+///     .load()?;
+/// # Ok::<(), tycho_atomic_arbitrage::ArbitrageError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct SettingsLoader {
+    merged: Value,
+}
+
+impl SettingsLoader {
+    /// Create a loader containing only the default settings.
+    pub fn new() -> Self {
+        Self {
+            merged: serde_json::to_value(ArbitrageSettings::default())
+                .unwrap_or_else(|_| Value::Object(Map::new())),
+        }
+    }
+
+    /// Add a layer parsed from a TOML document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document is not valid TOML.
+    pub fn with_toml(self, contents: &str) -> Result<Self> {
+        let layer: Value = toml::from_str(contents).map_err(|e| BundleError::InvalidConfiguration {
+            message: format!("Invalid TOML settings: {}", e),
+        })?;
+        Ok(self.with_overrides(layer))
+    }
+
+    /// Add a layer parsed from a JSON document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document is not valid JSON.
+    pub fn with_json(self, contents: &str) -> Result<Self> {
+        let layer: Value = serde_json::from_str(contents).map_err(|e| BundleError::InvalidConfiguration {
+            message: format!("Invalid JSON settings: {}", e),
+        })?;
+        Ok(self.with_overrides(layer))
+    }
+
+    /// Add a layer read from a settings file.
+    ///
+    /// Files ending in `.json` are parsed as JSON, all other files as TOML.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn with_file(self, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| BundleError::InvalidConfiguration {
+            message: format!("Failed to read settings file {}: {}", path.display(), e),
+        })?;
+
+        tracing::debug!(path = %path.display(), "Loading settings file");
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => self.with_json(&contents),
+            _ => self.with_toml(&contents),
+        }
+    }
+
+    /// Add a layer from environment variables.
+    ///
+    /// Only variables that are set override the previous layers:
+    ///
+    /// - `TYCHO_CHAIN`: Target blockchain
+    /// - `TYCHO_TVL_THRESHOLD`: Minimum TVL for pools to consider
+    /// - `TYCHO_MIN_PROFIT_BPS`: Minimum profit in BPS
+    /// - `TYCHO_SLIPPAGE_BPS`: Slippage tolerance in BPS
+    /// - `TYCHO_BRIBE_PERCENTAGE`: Bribe percentage (`BRIBE_PERCENTAGE` is accepted as well)
+    /// - `TYCHO_OPTIMIZATION_TOLERANCES`: Comma-separated optimization tolerances
+    /// - `TYCHO_DEFAULT_OPTIMIZATION_TOLERANCE`: Optimization tolerance of start tokens without their own
+    /// - `RELAYER_URLS`: Comma-separated relayer URLs
+    /// - `RELAYER_TIMEOUT_MS`: Relayer request timeout in milliseconds
+    /// - `PERMIT2_ADDRESS`, `NATIVE_TOKEN_ADDRESS`, `WRAPPED_NATIVE_ADDRESS`: Address overrides
+    /// - `TYCHO_RECEIVER_ADDRESS`: Address receiving the output of executed paths
+    /// - `TYCHO_ROUTER_ADDRESS`, `TYCHO_ROUTER_FUNCTION_SIGNATURE`: Custom router contract and function
+    /// - `TYCHO_MIN_DISTINCT_PROTOCOLS`: Number of distinct protocol systems per path
+    /// - `TYCHO_EXCLUDE_POOL_REUSE`: Whether paths reusing a pool address are excluded
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a set variable cannot be parsed.
+    pub fn with_env(self) -> Result<Self> {
+        let mut layer = Map::new();
+        let mut relayer = Map::new();
+        let mut path_filters = Map::new();
+
+        if let Ok(chain) = env::var("TYCHO_CHAIN") {
+            layer.insert("chain".to_string(), Value::from(chain));
+        }
+        if let Some(tvl_threshold) = parse_env::<f64>("TYCHO_TVL_THRESHOLD")? {
+            layer.insert("tvl_threshold".to_string(), Value::from(tvl_threshold));
+        }
+        if let Some(min_profit_bps) = parse_env::<u64>("TYCHO_MIN_PROFIT_BPS")? {
+            layer.insert("min_profit_bps".to_string(), Value::from(min_profit_bps));
+        }
+        if let Some(slippage_bps) = parse_env::<u64>("TYCHO_SLIPPAGE_BPS")? {
+            layer.insert("slippage_bps".to_string(), Value::from(slippage_bps));
+        }
+        for var_name in ["BRIBE_PERCENTAGE", "TYCHO_BRIBE_PERCENTAGE"] {
+            if let Some(bribe_percentage) = parse_env::<u64>(var_name)? {
+                layer.insert("bribe_percentage".to_string(), Value::from(bribe_percentage));
+            }
+        }
+        if let Ok(tolerances_str) = env::var("TYCHO_OPTIMIZATION_TOLERANCES") {
+            let tolerances = split_list(&tolerances_str)
+                .into_iter()
+                .map(|tolerance| parse_value::<f64>(&tolerance, "TYCHO_OPTIMIZATION_TOLERANCES"))
+                .collect::<Result<Vec<_>>>()?;
+            layer.insert("optimization_tolerances".to_string(), Value::from(tolerances));
+        }
+        if let Some(default_tolerance) = parse_env::<f64>("TYCHO_DEFAULT_OPTIMIZATION_TOLERANCE")? {
+            let mut start_token_tolerances = Map::new();
+            start_token_tolerances.insert("default".to_string(), Value::from(default_tolerance));
+            layer.insert("start_token_tolerances".to_string(), Value::Object(start_token_tolerances));
+        }
+
+        if let Ok(urls_str) = env::var("RELAYER_URLS") {
+            relayer.insert("urls".to_string(), Value::from(split_list(&urls_str)));
+        }
+        if let Some(timeout_ms) = parse_env::<u64>("RELAYER_TIMEOUT_MS")? {
+            relayer.insert("timeout_ms".to_string(), Value::from(timeout_ms));
+        }
+        if let Some(max_attempts) = parse_env::<u32>("RELAYER_MAX_ATTEMPTS")? {
+            relayer.insert("max_attempts".to_string(), Value::from(max_attempts));
+        }
+        if let Ok(signature) = env::var("TYCHO_ROUTER_FUNCTION_SIGNATURE") {
+            layer.insert("router_function_signature".to_string(), Value::from(signature));
+        }
+
+        if !relayer.is_empty() {
+            layer.insert("relayer".to_string(), Value::Object(relayer));
+        }
+
+        if let Some(min_distinct_protocols) = parse_env::<usize>("TYCHO_MIN_DISTINCT_PROTOCOLS")? {
+            path_filters.insert("min_distinct_protocols".to_string(), Value::from(min_distinct_protocols));
+        }
+        if let Some(exclude_pool_reuse) = parse_env::<bool>("TYCHO_EXCLUDE_POOL_REUSE")? {
+            path_filters.insert("exclude_pool_reuse".to_string(), Value::from(exclude_pool_reuse));
+        }
+        if !path_filters.is_empty() {
+            layer.insert("path_filters".to_string(), Value::Object(path_filters));
+        }
+
+        for (var_name, key) in [
+            ("PERMIT2_ADDRESS", "permit2_address"),
+            ("NATIVE_TOKEN_ADDRESS", "native_token"),
+            ("WRAPPED_NATIVE_ADDRESS", "wrapped_native"),
+            ("TYCHO_RECEIVER_ADDRESS", "receiver_address"),
+            ("TYCHO_ROUTER_ADDRESS", "router_address"),
+        ] {
+            if let Ok(address_str) = env::var(var_name) {
+                let address = ArbitrageConfig::parse_and_validate_address(&address_str, var_name)?;
+                layer.insert(key.to_string(), Value::from(address.to_string()));
+            }
+        }
+
+        tracing::debug!(
+            overridden_settings = layer.len(),
+            "Settings loaded from environment"
+        );
+
+        Ok(self.with_overrides(Value::Object(layer)))
+    }
+
+    /// Add a layer of explicit overrides, e.g. parsed command line arguments.
+    pub fn with_overrides(mut self, overrides: Value) -> Self {
+        merge_values(&mut self.merged, overrides);
+        self
+    }
+
+    /// Build and validate the settings from all layers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a layer contains unknown fields or values of the
+    /// wrong type, or if the merged settings fail validation.
+    pub fn load(self) -> Result<ArbitrageSettings> {
+        let settings: ArbitrageSettings = serde_json::from_value(self.merged).map_err(|e| {
+            BundleError::InvalidConfiguration {
+                message: format!("Invalid settings: {}", e),
+            }
+        })?;
+
+        settings.validate()?;
+
+        tracing::debug!(
+            chain = settings.chain,
+            tvl_threshold = settings.tvl_threshold,
+            min_profit_bps = settings.min_profit_bps,
+            slippage_bps = settings.slippage_bps,
+            bribe_percentage = settings.bribe_percentage,
+            relayer_count = settings.relayer.urls.len(),
+            "Settings loaded"
+        );
+
+        Ok(settings)
+    }
+}
+
+impl Default for SettingsLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Recursively merge `overrides` into `base`, replacing everything but objects.
+fn merge_values(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
         }
+        (base, overrides) => *base = overrides,
     }
 }
 
+/// Parse an environment variable if it is set.
+fn parse_env<T: FromStr>(var_name: &str) -> Result<Option<T>> {
+    env::var(var_name)
+        .ok()
+        .map(|value| parse_value(&value, var_name))
+        .transpose()
+}
+
+/// Parse a single value, naming the variable it came from on failure.
+fn parse_value<T: FromStr>(value: &str, var_name: &str) -> Result<T> {
+    value.trim().parse().map_err(|_| {
+        BundleError::InvalidConfiguration {
+            message: format!("Invalid {} value: {}", var_name, value),
+        }.into()
+    })
+}
+
+/// Split a comma-separated list, dropping empty entries.
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
 /// Security configuration for private keys and identity management
 #[derive(Debug, Clone)]
 pub struct SecurityConfig {
     /// Flashbots identity private key (optional)
     pub flashbots_identity: Option<PrivateKeySigner>,
-    /// Executor private key for signing transactions
-    pub executor_key: PrivateKeySigner,
+    /// Executor signer for signing transactions
+    pub executor_key: ExecutorSigner,
     /// Whether to validate private keys on creation
     pub validate_keys: bool,
 }
@@ -53,8 +832,18 @@ pub struct ArbitrageConfig {
     pub chain_id: u64,
     /// Permit2 contract address for the chain
     pub permit2_address: alloy::primitives::Address,
+    /// Address representing the chain's native token
+    pub native_token: alloy::primitives::Address,
+    /// Wrapped native token contract used to wrap and unwrap the native token
+    pub wrapped_native: alloy::primitives::Address,
+    /// Execution quirks of the chain, with the configured Permit2 deployment
+    pub chain_profile: crate::chain::ChainProfile,
     /// Bribe percentage (0-100)
     pub bribe_percentage: u64,
+    /// Address receiving swap outputs, e.g. a cold wallet (default: the executor)
+    pub receiver_address: Option<alloy::primitives::Address>,
+    /// Settings the configuration was built from
+    pub settings: ArbitrageSettings,
 }
 
 impl ArbitrageConfig {
@@ -62,18 +851,17 @@ impl ArbitrageConfig {
     /// 
     /// # Environment Variables
     /// 
-    /// ## Required
+    /// ## Required (one of)
     /// - `TYCHO_EXECUTOR_PRIVATE_KEY`: Private key for transaction signing (without 0x prefix)
+    /// - `TYCHO_EXECUTOR_KEYSTORE`: Encrypted JSON keystore file holding the executor key.
+    ///   The passphrase is read from `TYCHO_EXECUTOR_KEYSTORE_PASSWORD`, or prompted for
+    ///   on the terminal if that variable is not set.
     /// 
-    /// ## Optional (CLI-specific with TYCHO_ prefix)
-    /// - `TYCHO_CHAIN`: Target blockchain (default: ethereum)
+    /// ## Optional
     /// - `TYCHO_RPC_URL`: RPC URL for on-chain interaction
     /// - `TYCHO_API_KEY`: Tycho API key
-    /// - `TYCHO_TVL_THRESHOLD`: Minimum TVL for pools to consider (default: 70.0)
-    /// - `TYCHO_MIN_PROFIT_BPS`: Minimum profit in BPS (default: 100)
-    /// - `TYCHO_SLIPPAGE_BPS`: Slippage tolerance in BPS (default: 50)
-    /// - `TYCHO_FLASHBOTS_IDENTITY_KEY`: Private key for Flashbots authentication
-    /// - `TYCHO_BRIBE_PERCENTAGE`: Bribe percentage (default: 99)
+    /// - `FLASHBOTS_IDENTITY_KEY`: Private key for Flashbots authentication
+    /// - All settings variables read by [`SettingsLoader::with_env`]
     /// 
     /// # Errors
     /// 
@@ -87,17 +875,48 @@ impl ArbitrageConfig {
             "Loading arbitrage configuration from environment"
         );
 
-        // Load executor private key (required)
-        let executor_key_str = env::var("TYCHO_EXECUTOR_PRIVATE_KEY")
-            .map_err(|_| {
-                tracing::error!("TYCHO_EXECUTOR_PRIVATE_KEY environment variable is required but not found");
-                BundleError::InvalidConfiguration {
-                    message: "TYCHO_EXECUTOR_PRIVATE_KEY environment variable is required".to_string(),
-                }
-            })?;
+        let mut settings = ArbitrageSettings::from_env()?;
+        settings.chain = chain.to_string();
 
-        let executor_key = Self::parse_and_validate_private_key(&executor_key_str, "TYCHO_EXECUTOR_PRIVATE_KEY")?;
-        tracing::debug!("Executor private key loaded and validated successfully");
+        Self::from_settings(settings)
+    }
+
+    /// Create a configuration from loaded settings
+    ///
+    /// Private keys are read from the environment, as they are never part of
+    /// the settings. See [`ArbitrageConfig::from_env`] for the variables used.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The tunable parameters of the arbitrage system
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Neither an executor private key nor a keystore is configured
+    /// - Private keys are invalid or the keystore cannot be decrypted
+    /// - Settings values are out of valid ranges
+    pub fn from_settings(settings: ArbitrageSettings) -> Result<Self> {
+        let executor_key = Self::load_executor_signer()?;
+        Self::from_settings_with_signer(settings, executor_key)
+    }
+
+    /// Create a configuration from loaded settings and an explicit executor signer
+    ///
+    /// Use this for signers that cannot be described by environment variables,
+    /// such as AWS KMS keys or Ledger devices.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The tunable parameters of the arbitrage system
+    /// * `executor_key` - The signer for executor transactions
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Flashbots identity key is invalid or settings
+    /// values are out of valid ranges.
+    pub fn from_settings_with_signer(settings: ArbitrageSettings, executor_key: ExecutorSigner) -> Result<Self> {
+        settings.validate()?;
 
         // Load optional flashbots identity key
         let flashbots_identity = if let Ok(identity_key_str) = env::var("FLASHBOTS_IDENTITY_KEY") {
@@ -108,85 +927,50 @@ impl ArbitrageConfig {
             None
         };
 
-        // Load relayer configuration
-        let relayer_urls = if let Ok(urls_str) = env::var("RELAYER_URLS") {
-            let urls: Vec<String> = urls_str
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
-            tracing::debug!(
-                relayer_count = urls.len(),
-                relayers = ?urls,
-                "Custom relayer URLs loaded from environment"
-            );
-            urls
-        } else {
-            let default_urls = RelayerConfig::default().urls;
-            tracing::debug!(
-                relayer_count = default_urls.len(),
-                relayers = ?default_urls,
-                "Using default relayer URLs"
-            );
-            default_urls
-        };
-
-        let timeout_ms = env::var("RELAYER_TIMEOUT_MS")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(5000);
-
-        tracing::debug!(
-            timeout_ms = timeout_ms,
-            "Relayer configuration loaded"
-        );
-
-        // Validate relayer URLs
-        Self::validate_relayer_urls(&relayer_urls)?;
+        let chain = settings.chain.as_str();
+        let chain_id = crate::utils::chain_id(chain)?;
 
-        let relayer = RelayerConfig {
-            urls: relayer_urls,
-            timeout_ms,
+        let permit2_address = match settings.permit2_address {
+            Some(custom_address) => {
+                tracing::debug!(
+                    custom_address = %custom_address,
+                    "Using custom Permit2 address"
+                );
+                custom_address
+            }
+            None => crate::utils::permit2_address(chain)?,
         };
 
-        // Load other configuration
-        let bribe_percentage = env::var("BRIBE_PERCENTAGE")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(50);
-
-        if bribe_percentage > 100 {
-            tracing::error!(
-                bribe_percentage = bribe_percentage,
-                "Invalid bribe percentage - must be between 0 and 100"
-            );
-            return Err(BundleError::InvalidConfiguration {
-                message: "BRIBE_PERCENTAGE must be between 0 and 100".to_string(),
-            }.into());
-        }
+        let native_token = match settings.native_token {
+            Some(custom_address) => custom_address,
+            None => crate::utils::native_token_address(chain)?,
+        };
 
-        let chain_id = crate::utils::chain_id(chain)?;
+        let wrapped_native = match settings.wrapped_native {
+            Some(custom_address) => custom_address,
+            None => crate::utils::wrapped_native_address(chain)?,
+        };
 
-        // Load permit2 address (with optional override)
-        let permit2_address = if let Ok(custom_address) = env::var("PERMIT2_ADDRESS") {
-            tracing::debug!(
-                custom_address = custom_address,
-                "Using custom Permit2 address from environment"
-            );
-            Self::parse_and_validate_address(&custom_address, "PERMIT2_ADDRESS")?
+        let chain_profile = crate::utils::chain_profile(chain)?;
+        let chain_profile = if settings.permit2_address.is_some() {
+            chain_profile.with_permit2_address(permit2_address)
         } else {
-            let default_address = crate::utils::permit2_address(chain)?;
-            tracing::debug!(
-                permit2_address = %default_address,
-                "Using default Permit2 address for chain"
-            );
-            default_address
+            chain_profile
         };
 
         tracing::debug!(
-            bribe_percentage = bribe_percentage,
+            bribe_percentage = settings.bribe_percentage,
             chain_id = chain_id,
             permit2_address = %permit2_address,
+            fee_market = ?chain_profile.fee_market(),
+            block_time_ms = chain_profile.block_time().as_millis() as u64,
+            native_token = %native_token,
+            wrapped_native = %wrapped_native,
+            receiver_address = ?settings.receiver_address,
+            router_address = ?settings.router_address,
+            relayers = ?settings.relayer.urls,
+            timeout_ms = settings.relayer.timeout_ms,
+            max_attempts = settings.relayer.max_attempts,
             "Business logic configuration loaded"
         );
 
@@ -197,18 +981,23 @@ impl ArbitrageConfig {
         };
 
         let config = Self {
-            relayer,
+            relayer: settings.relayer.clone(),
             security,
             chain_id,
             permit2_address,
-            bribe_percentage,
+            native_token,
+            wrapped_native,
+            chain_profile,
+            bribe_percentage: settings.bribe_percentage,
+            receiver_address: settings.receiver_address,
+            settings,
         };
 
-        // Validate CLI-specific environment variables
+        // Validate the remaining CLI-specific environment variables
         Self::validate_cli_env_vars()?;
 
         tracing::info!(
-            chain = chain,
+            chain = config.settings.chain,
             chain_id = chain_id,
             relayer_count = config.relayer.urls.len(),
             bribe_percentage = config.bribe_percentage,
@@ -229,10 +1018,13 @@ impl ArbitrageConfig {
     pub fn for_testing(chain: &str) -> Result<Self> {
         use alloy::signers::local::PrivateKeySigner;
         
-        let executor_key = PrivateKeySigner::random();
+        let executor_key = ExecutorSigner::Local(PrivateKeySigner::random());
         let flashbots_identity = Some(PrivateKeySigner::random());
         let chain_id = crate::utils::chain_id(chain)?;
         let permit2_address = crate::utils::permit2_address(chain)?;
+        let native_token = crate::utils::native_token_address(chain)?;
+        let wrapped_native = crate::utils::wrapped_native_address(chain)?;
+        let chain_profile = crate::utils::chain_profile(chain)?;
 
         let security = SecurityConfig {
             flashbots_identity,
@@ -245,119 +1037,41 @@ impl ArbitrageConfig {
             security,
             chain_id,
             permit2_address,
+            native_token,
+            wrapped_native,
+            chain_profile,
             bribe_percentage: 50,
+            receiver_address: None,
+            settings: ArbitrageSettings::default(),
         })
     }
 
-    /// Validate CLI-specific environment variables and set defaults if not provided
-    /// This ensures all TYCHO_ prefixed environment variables are properly validated
-    fn validate_cli_env_vars() -> Result<()> {
-        tracing::debug!("Validating CLI-specific environment variables");
-
-        // Validate TYCHO_CHAIN if set
-        if let Ok(chain) = env::var("TYCHO_CHAIN") {
-            match chain.as_str() {
-                "ethereum" | "base" | "unichain" => {
-                    tracing::debug!(chain = chain, "Valid TYCHO_CHAIN value");
-                }
-                _ => {
-                    return Err(BundleError::InvalidConfiguration {
-                        message: format!("Invalid TYCHO_CHAIN value: {}. Must be one of: ethereum, base, unichain", chain),
-                    }.into());
-                }
-            }
-        } else {
-            tracing::debug!("TYCHO_CHAIN not set, using default: ethereum");
-            env::set_var("TYCHO_CHAIN", "ethereum");
-        }
-
-        // Validate TYCHO_TVL_THRESHOLD if set
-        if let Ok(tvl_str) = env::var("TYCHO_TVL_THRESHOLD") {
-            match tvl_str.parse::<f64>() {
-                Ok(tvl) if tvl >= 0.0 => {
-                    tracing::debug!(tvl_threshold = tvl, "Valid TYCHO_TVL_THRESHOLD value");
-                }
-                Ok(tvl) => {
-                    return Err(BundleError::InvalidConfiguration {
-                        message: format!("TYCHO_TVL_THRESHOLD must be non-negative, got: {}", tvl),
-                    }.into());
-                }
-                Err(_) => {
-                    return Err(BundleError::InvalidConfiguration {
-                        message: format!("Invalid TYCHO_TVL_THRESHOLD value: {}. Must be a valid number", tvl_str),
-                    }.into());
-                }
-            }
-        } else {
-            tracing::debug!("TYCHO_TVL_THRESHOLD not set, using default: 70.0");
-            env::set_var("TYCHO_TVL_THRESHOLD", "70.0");
+    /// Load the executor signer from a private key or keystore in the environment
+    fn load_executor_signer() -> Result<ExecutorSigner> {
+        if let Ok(executor_key_str) = env::var("TYCHO_EXECUTOR_PRIVATE_KEY") {
+            let executor_key = Self::parse_and_validate_private_key(&executor_key_str, "TYCHO_EXECUTOR_PRIVATE_KEY")?;
+            tracing::debug!("Executor private key loaded and validated successfully");
+            return Ok(ExecutorSigner::Local(executor_key));
         }
 
-        // Validate TYCHO_MIN_PROFIT_BPS if set
-        if let Ok(profit_str) = env::var("TYCHO_MIN_PROFIT_BPS") {
-            match profit_str.parse::<u64>() {
-                Ok(profit) if profit <= 10000 => {
-                    tracing::debug!(min_profit_bps = profit, "Valid TYCHO_MIN_PROFIT_BPS value");
-                }
-                Ok(profit) => {
-                    return Err(BundleError::InvalidConfiguration {
-                        message: format!("TYCHO_MIN_PROFIT_BPS must be <= 10000 (100%), got: {}", profit),
-                    }.into());
-                }
-                Err(_) => {
-                    return Err(BundleError::InvalidConfiguration {
-                        message: format!("Invalid TYCHO_MIN_PROFIT_BPS value: {}. Must be a valid integer", profit_str),
-                    }.into());
-                }
-            }
-        } else {
-            tracing::debug!("TYCHO_MIN_PROFIT_BPS not set, using default: 100");
-            env::set_var("TYCHO_MIN_PROFIT_BPS", "100");
+        if let Ok(keystore) = env::var("TYCHO_EXECUTOR_KEYSTORE") {
+            tracing::debug!(keystore = keystore, "Loading executor key from keystore");
+            return match env::var("TYCHO_EXECUTOR_KEYSTORE_PASSWORD") {
+                Ok(password) => ExecutorSigner::from_keystore(&keystore, &password),
+                Err(_) => ExecutorSigner::from_keystore_prompt(&keystore),
+            };
         }
 
-        // Validate TYCHO_SLIPPAGE_BPS if set
-        if let Ok(slippage_str) = env::var("TYCHO_SLIPPAGE_BPS") {
-            match slippage_str.parse::<u64>() {
-                Ok(slippage) if slippage <= 10000 => {
-                    tracing::debug!(slippage_bps = slippage, "Valid TYCHO_SLIPPAGE_BPS value");
-                }
-                Ok(slippage) => {
-                    return Err(BundleError::InvalidConfiguration {
-                        message: format!("TYCHO_SLIPPAGE_BPS must be <= 10000 (100%), got: {}", slippage),
-                    }.into());
-                }
-                Err(_) => {
-                    return Err(BundleError::InvalidConfiguration {
-                        message: format!("Invalid TYCHO_SLIPPAGE_BPS value: {}. Must be a valid integer", slippage_str),
-                    }.into());
-                }
-            }
-        } else {
-            tracing::debug!("TYCHO_SLIPPAGE_BPS not set, using default: 50");
-            env::set_var("TYCHO_SLIPPAGE_BPS", "50");
-        }
+        tracing::error!("Neither TYCHO_EXECUTOR_PRIVATE_KEY nor TYCHO_EXECUTOR_KEYSTORE environment variable is set");
+        Err(BundleError::InvalidConfiguration {
+            message: "TYCHO_EXECUTOR_PRIVATE_KEY or TYCHO_EXECUTOR_KEYSTORE environment variable is required".to_string(),
+        }.into())
+    }
 
-        // Validate TYCHO_BRIBE_PERCENTAGE if set
-        if let Ok(bribe_str) = env::var("TYCHO_BRIBE_PERCENTAGE") {
-            match bribe_str.parse::<u64>() {
-                Ok(bribe) if bribe <= 100 => {
-                    tracing::debug!(bribe_percentage = bribe, "Valid TYCHO_BRIBE_PERCENTAGE value");
-                }
-                Ok(bribe) => {
-                    return Err(BundleError::InvalidConfiguration {
-                        message: format!("TYCHO_BRIBE_PERCENTAGE must be <= 100, got: {}", bribe),
-                    }.into());
-                }
-                Err(_) => {
-                    return Err(BundleError::InvalidConfiguration {
-                        message: format!("Invalid TYCHO_BRIBE_PERCENTAGE value: {}. Must be a valid integer", bribe_str),
-                    }.into());
-                }
-            }
-        } else {
-            tracing::debug!("TYCHO_BRIBE_PERCENTAGE not set, using default: 99");
-            env::set_var("TYCHO_BRIBE_PERCENTAGE", "99");
-        }
+    /// Validate CLI-specific environment variables that are not part of the settings
+    /// This ensures the remaining TYCHO_ prefixed environment variables are properly validated
+    fn validate_cli_env_vars() -> Result<()> {
+        tracing::debug!("Validating CLI-specific environment variables");
 
         // Validate TYCHO_EXECUTOR_PRIVATE_KEY if set
         if let Ok(key_str) = env::var("TYCHO_EXECUTOR_PRIVATE_KEY") {
@@ -474,9 +1188,21 @@ impl ArbitrageConfig {
     }
 
     /// Get the executor signer
-    pub fn executor_signer(&self) -> &PrivateKeySigner {
+    pub fn executor_signer(&self) -> &ExecutorSigner {
         &self.security.executor_key
     }
+
+    /// The address receiving swap outputs for the given executor
+    ///
+    /// Defaults to the executor itself when no separate receiver is configured.
+    pub fn receiver_for(&self, executor: alloy::primitives::Address) -> alloy::primitives::Address {
+        self.receiver_address.unwrap_or(executor)
+    }
+
+    /// Whether the token is the chain's native token and must be wrapped before swapping
+    pub fn is_native_token(&self, token: &alloy::primitives::Address) -> bool {
+        *token == self.native_token
+    }
 }
 
 #[cfg(test)]
@@ -549,6 +1275,13 @@ mod tests {
         assert_eq!(config.chain_id, 1);
         assert!(config.security.flashbots_identity.is_some());
         assert_eq!(config.bribe_percentage, 50);
+        assert!(config.is_native_token(&alloy::primitives::Address::ZERO));
+        let executor = config.executor_signer().address();
+        assert_eq!(config.receiver_for(executor), executor);
+        assert_eq!(
+            config.wrapped_native,
+            crate::utils::wrapped_native_address("ethereum").unwrap()
+        );
     }
 
     #[test]
@@ -591,4 +1324,181 @@ mod tests {
             assert!(result.is_err(), "Expected error for {}: {}", description, key);
         }
     }
+
+    #[test]
+    fn test_settings_layers_override_fields() {
+        let settings = SettingsLoader::new()
+            .with_toml(
+                r#"
+                min_profit_bps = 25
+                optimization_tolerances = [1.0, 0.5]
+
+                [relayer]
+                timeout_ms = 2000
+                "#,
+            )
+            .unwrap()
+            .with_json(r#"{ "slippage_bps": 300, "relayer": { "urls": ["https://relay.flashbots.net"] } }"#)
+            .unwrap()
+            .load()
+            .unwrap();
+
+        assert_eq!(settings.chain, "ethereum");
+        assert_eq!(settings.min_profit_bps, 25);
+        assert_eq!(settings.slippage_bps, 300);
+        assert_eq!(settings.optimization_tolerances, vec![1.0, 0.5]);
+        assert_eq!(settings.relayer.timeout_ms, 2000);
+        assert_eq!(settings.relayer.urls, vec!["https://relay.flashbots.net".to_string()]);
+    }
+
+    #[test]
+    fn test_settings_env_layer() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+
+        env::set_var("TYCHO_MIN_PROFIT_BPS", "75");
+        env::set_var("RELAYER_TIMEOUT_MS", "1500");
+
+        let settings = SettingsLoader::new()
+            .with_toml("min_profit_bps = 25\ntvl_threshold = 10.0")
+            .unwrap()
+            .with_env()
+            .unwrap()
+            .load()
+            .unwrap();
+
+        assert_eq!(settings.min_profit_bps, 75);
+        assert_eq!(settings.tvl_threshold, 10.0);
+        assert_eq!(settings.relayer.timeout_ms, 1500);
+
+        env::set_var("TYCHO_MIN_PROFIT_BPS", "not_a_number");
+        assert!(SettingsLoader::new().with_env().is_err());
+
+        env::remove_var("TYCHO_MIN_PROFIT_BPS");
+        env::remove_var("RELAYER_TIMEOUT_MS");
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        assert!(ArbitrageSettings::default().validate().is_ok());
+
+        let invalid = [
+            "chain = \"invalid_chain\"",
+            "bribe_percentage = 101",
+            "slippage_bps = 10001",
+            "optimization_tolerances = [0.0]",
+            "[start_token_tolerances]\ndefault = -1.0",
+            "[start_token_tolerances.tokens]\nNOT_A_TOKEN = 0.5",
+            "unknown_setting = 1",
+            "[exchanges.uniswap_v2]\ntvl_threshold = -1.0",
+            "[exchanges.uniswap_v2]\nunknown = true",
+            "router_function_signature = \"not a signature\"",
+        ];
+        for contents in invalid {
+            let result = SettingsLoader::new().with_toml(contents).unwrap().load();
+            assert!(result.is_err(), "Expected error for {}", contents);
+        }
+    }
+
+    #[test]
+    fn test_path_filters_from_settings() {
+        let policy = ArbitrageSettings::default().discovery_policy();
+        assert_eq!(policy.min_distinct_protocols(), 1);
+        assert!(!policy.allows_pool_reuse());
+
+        let settings = SettingsLoader::new()
+            .with_toml("[path_filters]\nmin_distinct_protocols = 2\nexclude_pool_reuse = false")
+            .unwrap()
+            .load()
+            .unwrap();
+        let policy = settings.discovery_policy();
+        assert_eq!(policy.min_distinct_protocols(), 2);
+        assert!(policy.allows_pool_reuse());
+    }
+
+    #[test]
+    fn test_start_token_tolerances_by_address_symbol_and_position() {
+        let weth = Bytes::from(crate::utils::well_known_token_address("ethereum", "WETH").unwrap().to_vec());
+        let other = Bytes::from(Address::with_last_byte(1).to_vec());
+        let unlisted = Bytes::from(Address::with_last_byte(2).to_vec());
+        let settings = SettingsLoader::new()
+            .with_toml(&format!(
+                "optimization_tolerances = [2.0, 3.0]\n[start_token_tolerances]\ndefault = 0.5\n[start_token_tolerances.tokens]\nweth = 0.1\n\"{}\" = 0.2",
+                Address::with_last_byte(1)
+            ))
+            .unwrap()
+            .load()
+            .unwrap();
+
+        let tolerances = settings.resolve_start_token_tolerances(&[weth.clone(), unlisted.clone()]).unwrap();
+        assert_eq!(tolerances.tolerance(&weth), 0.1);
+        assert_eq!(tolerances.tolerance(&other), 0.2);
+        assert_eq!(tolerances.tolerance(&unlisted), 3.0);
+        assert_eq!(tolerances.tolerance(&Bytes::from(Address::with_last_byte(3).to_vec())), 0.5);
+    }
+
+    #[test]
+    fn test_execution_allowlist_from_settings() {
+        assert!(!ArbitrageSettings::default().execution_allowlist().is_restricted());
+
+        let settings = SettingsLoader::new()
+            .with_toml("execution_protocols = [\"uniswap_v2\", \"uniswap_v3\"]")
+            .unwrap()
+            .load()
+            .unwrap();
+        let allowlist = settings.execution_allowlist();
+        assert!(allowlist.allows("uniswap_v3"));
+        assert!(!allowlist.allows("ekubo_v2"));
+    }
+
+    #[test]
+    fn test_protocol_gas_from_settings() {
+        let settings = SettingsLoader::new()
+            .with_toml("[protocol_gas]\n\"vm:curve\" = 350000")
+            .unwrap()
+            .load()
+            .unwrap();
+        let table = settings.protocol_gas_table();
+        assert_eq!(table.swap_gas("vm:curve"), 350_000);
+        assert_eq!(table.swap_gas("uniswap_v2"), 100_000);
+    }
+
+    #[test]
+    fn test_exchange_filter_from_settings() {
+        let settings = SettingsLoader::new()
+            .with_toml(
+                r#"
+                chain = "base"
+                tvl_threshold = 50.0
+
+                [exchanges.uniswap_v2]
+                enabled = false
+
+                [exchanges.uniswap_v3]
+                tvl_threshold = 200.0
+                denylist = ["0x1000"]
+
+                [exchanges.pancakeswap_v3]
+                allowlist = ["0x2000"]
+                "#,
+            )
+            .unwrap()
+            .load()
+            .unwrap();
+
+        let filter = settings.exchange_filter();
+        let enabled: Vec<&str> = filter.enabled_exchanges().map(|(name, _)| name).collect();
+        assert_eq!(enabled, vec!["pancakeswap_v3", "uniswap_v3"]);
+
+        assert_eq!(filter.tvl_threshold("uniswap_v3"), Some(200.0));
+        assert_eq!(filter.tvl_threshold("pancakeswap_v3"), Some(50.0));
+        assert_eq!(filter.tvl_threshold("uniswap_v2"), None);
+
+        let pool = |address: &str| Bytes::from_str(address).unwrap();
+        assert!(!filter.allows("uniswap_v2", &pool("0x3000")));
+        assert!(!filter.allows("uniswap_v3", &pool("0x1000")));
+        assert!(filter.allows("uniswap_v3", &pool("0x3000")));
+        assert!(filter.allows("pancakeswap_v3", &pool("0x2000")));
+        assert!(!filter.allows("pancakeswap_v3", &pool("0x3000")));
+        assert!(!filter.allows("vm:curve", &pool("0x3000")));
+    }
 }
@@ -33,9 +33,72 @@ pub enum BundleError {
     #[error("Invalid response from relayer {url}: {message}")]
     InvalidRelayerResponse { url: String, message: String },
 
+    #[error("Relayer {url} responded with HTTP status {status}: {message}")]
+    RelayerHttpStatus { url: String, status: u16, message: String },
+
     #[error("Insufficient bribe amount: {amount} is below minimum")]
     InsufficientBribe { amount: String },
 
     #[error("Target block {block} is in the past")]
     InvalidTargetBlock { block: u64 },
+
+    #[error("Pool {pool} is targeted by pending swap {tx_hash}")]
+    PendingSwapConflict { pool: String, tx_hash: String },
+
+    #[error("Target block {target_block} has already passed")]
+    TargetBlockPassed { target_block: u64 },
+
+    #[error("Nonce too low: {message}")]
+    NonceTooLow { message: String },
+
+    #[error("Insufficient balance: {message}")]
+    InsufficientBalance { message: String },
+
+    #[error("Relayer {url} rejected the bundle with code {code}: {message}")]
+    RelayRejected { url: String, code: i64, message: String },
+
+    #[error("Failed to persist reverted bundles in {path}: {reason}")]
+    RevertMemoryPersistence { path: String, reason: String },
+}
+
+impl BundleError {
+    /// Classify the JSON-RPC error a relayer rejected a bundle with.
+    ///
+    /// Rejections for a stale nonce, a missing balance or a passed target
+    /// block get their own variants, all others are `RelayRejected`.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the relayer
+    /// * `target_block` - The target block of the rejected bundle
+    /// * `code` - The JSON-RPC error code
+    /// * `message` - The JSON-RPC error message
+    pub fn from_relay_rejection(url: &str, target_block: u64, code: i64, message: &str) -> Self {
+        let lowercase = message.to_lowercase();
+        if lowercase.contains("nonce too low") {
+            Self::NonceTooLow { message: message.to_string() }
+        } else if lowercase.contains("insufficient funds") || lowercase.contains("insufficient balance") {
+            Self::InsufficientBalance { message: message.to_string() }
+        } else if lowercase.contains("block") && lowercase.contains("past") {
+            Self::TargetBlockPassed { target_block }
+        } else {
+            Self::RelayRejected {
+                url: url.to_string(),
+                code,
+                message: message.to_string(),
+            }
+        }
+    }
+
+    /// Whether submitting the same bundle again may succeed.
+    ///
+    /// Connection failures, rate limits and server errors of relayers are
+    /// transient. Rejections of the bundle itself are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RelayerConnectionFailed { .. } => true,
+            Self::RelayerHttpStatus { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,112 @@
+//! Context identifying the opportunity an error belongs to.
+//!
+//! Optimization, simulation and execution of many paths run concurrently, so
+//! their errors interleave in the logs. Wrapping errors with the
+//! [`OpportunityId`] of their path and block at module boundaries lets every
+//! error be correlated with the opportunity it was produced for.
+//!
+//! The id is derived deterministically from the path, the block and the input
+//! amount, so the same opportunity gets the same id when it is processed
+//! again, e.g. by a retry after a transient error. The
+//! [`TxExecutor`](crate::bundle::TxExecutor) refuses to submit an id twice.
+
+use super::{ArbitrageError, Result};
+use crate::path::{PathExt, PathId};
+use num_bigint::BigUint;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Identifier of an arbitrage opportunity: a path at a block, with an input amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OpportunityId {
+    /// The stable id of the path
+    pub path_id: PathId,
+    /// The block the opportunity was found at
+    pub block_number: u64,
+    /// Hash of the input amount, 0 if the id is not bound to an amount
+    pub amount_hash: u64,
+}
+
+impl OpportunityId {
+    /// Create the id of the opportunity along a path at a block, for any input amount.
+    pub fn new(path_id: PathId, block_number: u64) -> Self {
+        Self {
+            path_id,
+            block_number,
+            amount_hash: 0,
+        }
+    }
+
+    /// The id of an executed path at a block, bound to its input amount.
+    pub fn of_path_ext(path: &PathExt, block_number: u64) -> Self {
+        let id = Self::new(PathId::of_path_ext(path), block_number);
+        match path.first() {
+            Some(first) => id.with_amount_in(&first.amount_in),
+            None => id,
+        }
+    }
+
+    /// Bind the id to the input amount of the opportunity.
+    pub fn with_amount_in(mut self, amount_in: &BigUint) -> Self {
+        let mut hasher = DefaultHasher::new();
+        amount_in.to_bytes_le().hash(&mut hasher);
+        self.amount_hash = hasher.finish();
+        self
+    }
+}
+
+impl fmt::Display for OpportunityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.path_id, self.block_number)?;
+        if self.amount_hash != 0 {
+            write!(f, ":{:016x}", self.amount_hash)?;
+        }
+        Ok(())
+    }
+}
+
+/// Attach the opportunity of an operation to its error.
+pub trait OpportunityContext<T> {
+    /// Wrap the error, if any, with the id of the opportunity it was produced for.
+    ///
+    /// Errors that already carry an opportunity keep it.
+    fn with_opportunity(self, id: OpportunityId) -> Result<T>;
+}
+
+impl<T, E: Into<ArbitrageError>> OpportunityContext<T> for std::result::Result<T, E> {
+    fn with_opportunity(self, id: OpportunityId) -> Result<T> {
+        self.map_err(|e| e.into().with_opportunity(id))
+    }
+}
+
+impl ArbitrageError {
+    /// Wrap the error with the id of the opportunity it was produced for.
+    ///
+    /// Errors that already carry an opportunity keep it.
+    pub fn with_opportunity(self, id: OpportunityId) -> Self {
+        match self {
+            Self::Opportunity { .. } => self,
+            source => Self::Opportunity {
+                id,
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// The opportunity the error was produced for, if known.
+    pub fn opportunity(&self) -> Option<OpportunityId> {
+        match self {
+            Self::Opportunity { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// The error without its opportunity context.
+    pub fn without_context(&self) -> &ArbitrageError {
+        match self {
+            Self::Opportunity { source, .. } => source,
+            error => error,
+        }
+    }
+}
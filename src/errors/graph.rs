@@ -43,4 +43,10 @@ pub enum GraphError {
 
     #[error("Invalid edge configuration: nodes [{node1}, {node2}]")]
     InvalidEdgeConfiguration { node1: usize, node2: usize },
+
+    #[error("Graph invariant violated: {reason}")]
+    InvariantViolation { reason: String },
+
+    #[error("Stale handle {handle}: the token or pool was removed")]
+    StaleHandle { handle: String },
 }
@@ -0,0 +1,13 @@
+//! Inventory tracking errors.
+
+use thiserror::Error;
+
+/// Errors that can occur while reading executor balances.
+#[derive(Debug, Error)]
+pub enum InventoryError {
+    #[error("Failed to read the balance of token {token}: {message}")]
+    BalanceQueryFailed { token: String, message: String },
+
+    #[error("Failed to read the balance of any of {tokens} tokens")]
+    NoBalancesFetched { tokens: usize },
+}
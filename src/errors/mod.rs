@@ -11,7 +11,9 @@
 //!
 //! - **`BundleError`**: Errors related to transaction bundle creation and submission
 //! - **`GraphError`**: Errors in trading graph operations and validation
+//! - **`InventoryError`**: Errors reading executor balances
 //! - **`PathError`**: Errors in arbitrage path discovery and execution
+//! - **`RiskError`**: Breached risk limits that block bundle submission
 //! - **`SimulationError`**: Errors during transaction simulation and validation
 //! - **`UtilityError`**: Errors in utility functions and type conversions
 //!
@@ -30,6 +32,8 @@
 //! - **Pattern Matching**: Matching on specific error types for targeted handling
 //! - **Error Context**: Rich error messages with context about what operation failed
 //! - **Error Recovery**: Structured error information for implementing retry logic
+//! - **Opportunity Context**: `with_opportunity` tags errors with the path and
+//!   block they were produced for, so concurrent failures can be correlated
 //!
 //! # External Error Integration
 //!
@@ -41,15 +45,21 @@
 //! - Encoding errors from transaction construction
 
 pub mod bundle;
+pub mod context;
 pub mod graph;
+pub mod inventory;
 pub mod path;
+pub mod risk;
 pub mod simulation;
 pub mod utility;
 
 // Re-export all error types for convenience
 pub use bundle::BundleError;
+pub use context::{OpportunityContext, OpportunityId};
 pub use graph::GraphError;
+pub use inventory::InventoryError;
 pub use path::PathError;
+pub use risk::RiskError;
 pub use simulation::SimulationError;
 pub use utility::UtilityError;
 
@@ -91,6 +101,13 @@ pub enum ArbitrageError {
     #[error("Graph operation failed: {0}")]
     Graph(#[from] GraphError),
 
+    /// Error reading executor balances.
+    ///
+    /// This includes failed `balanceOf` calls and refreshes in which no
+    /// balance could be read.
+    #[error("Inventory operation failed: {0}")]
+    Inventory(#[from] InventoryError),
+
     /// Error in arbitrage path discovery, validation, or execution.
     ///
     /// This includes errors in path finding algorithms, path validation,
@@ -98,6 +115,13 @@ pub enum ArbitrageError {
     #[error("Path operation failed: {0}")]
     Path(#[from] PathError),
 
+    /// A risk limit was breached.
+    ///
+    /// This includes the emergency stop, per-trade notional and gas price
+    /// limits, the daily loss limit and the consecutive failure limit.
+    #[error("Risk limit breached: {0}")]
+    Risk(#[from] RiskError),
+
     /// Error during transaction simulation or validation.
     ///
     /// This includes errors in simulation setup, execution failures,
@@ -167,4 +191,84 @@ pub enum ArbitrageError {
     /// from dependencies that don't have specific handling.
     #[error("Generic error: {0}")]
     Other(#[from] anyhow::Error),
+
+    /// An error produced for a specific arbitrage opportunity.
+    ///
+    /// Added by `with_opportunity` at module boundaries, so errors of
+    /// concurrently processed paths can be told apart.
+    #[error("Opportunity {id}: {source}")]
+    Opportunity {
+        id: OpportunityId,
+        source: Box<ArbitrageError>,
+    },
+}
+
+impl ArbitrageError {
+    /// Whether retrying the failed operation may succeed.
+    ///
+    /// Bundle and simulation errors are classified by their own
+    /// `is_retryable`, network errors are retryable when the request could
+    /// not be sent or timed out. All other errors are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Opportunity { source, .. } => source.is_retryable(),
+            Self::Bundle(e) => e.is_retryable(),
+            Self::Simulation(e) => e.is_retryable(),
+            Self::Network(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classifies_rejections_and_retryable_errors() {
+        let rejection = BundleError::from_relay_rejection("https://relay.example", 100, -32000, "nonce too low: next nonce 5");
+        assert!(matches!(rejection, BundleError::NonceTooLow { .. }));
+        assert!(matches!(
+            BundleError::from_relay_rejection("https://relay.example", 100, -32000, "block in the past"),
+            BundleError::TargetBlockPassed { target_block: 100 }
+        ));
+        assert!(matches!(
+            BundleError::from_relay_rejection("https://relay.example", 100, -32602, "invalid bundle"),
+            BundleError::RelayRejected { code: -32602, .. }
+        ));
+        assert!(!ArbitrageError::from(rejection).is_retryable());
+
+        let revert = SimulationError::from_revert("TychoRouter__NegativeSlippage");
+        assert!(matches!(revert, SimulationError::SlippageExceeded { .. }));
+        assert!(!ArbitrageError::from(revert).is_retryable());
+        assert!(ArbitrageError::from(SimulationError::SimulationTimeout { timeout_ms: 100 }).is_retryable());
+        assert!(ArbitrageError::from(SimulationError::from_revert("nonce too low")).is_retryable());
+    }
+
+    #[test]
+    fn test_with_opportunity_keeps_the_first_context() {
+        let id = OpportunityId::new(crate::path::PathId::from_hops([]), 100);
+        let result: std::result::Result<(), SimulationError> = Err(SimulationError::SimulationTimeout { timeout_ms: 100 });
+        let error = result
+            .with_opportunity(id)
+            .with_opportunity(OpportunityId::new(id.path_id, 101))
+            .unwrap_err();
+
+        assert_eq!(error.opportunity(), Some(id));
+        assert!(error.to_string().starts_with(&format!("Opportunity {}", id)));
+        assert!(error.to_string().ends_with("@100: Simulation error: Simulation timeout after 100ms"));
+        assert!(matches!(error.without_context(), ArbitrageError::Simulation(_)));
+        assert!(error.is_retryable());
+    }
+
+    #[test]
+    fn test_opportunity_id_is_bound_to_the_amount() {
+        let id = OpportunityId::new(crate::path::PathId::from_hops([]), 100);
+        let amount_in = num_bigint::BigUint::from(1000u32);
+
+        assert_eq!(id.with_amount_in(&amount_in), id.with_amount_in(&amount_in));
+        assert_ne!(id.with_amount_in(&amount_in), id.with_amount_in(&(amount_in + 1u32)));
+        assert_ne!(id.with_amount_in(&num_bigint::BigUint::from(1u32)), id);
+        assert!(id.with_amount_in(&num_bigint::BigUint::from(1u32)).to_string().starts_with(&format!("{}:", id)));
+    }
 }
@@ -58,4 +58,7 @@ pub enum PathError {
 
     #[error("Protocol simulation not found for pool {pool:?}")]
     ProtocolSimulationNotFound { pool: Bytes },
+
+    #[error("No USD price available for token {token:?}")]
+    PriceUnavailable { token: Bytes },
 }
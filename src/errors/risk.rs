@@ -0,0 +1,35 @@
+//! Risk limit breaches.
+
+use thiserror::Error;
+
+/// A breached operator-defined risk limit that blocks bundle submission.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RiskError {
+    #[error("Emergency stop is active")]
+    EmergencyStop,
+
+    #[error("Trade notional {notional} exceeds the limit of {limit}")]
+    NotionalLimitExceeded { notional: String, limit: String },
+
+    #[error("Gas price {gas_price} wei exceeds the limit of {limit} wei")]
+    GasPriceLimitExceeded { gas_price: u128, limit: u128 },
+
+    #[error("Daily loss {loss} exceeds the limit of {limit}")]
+    DailyLossLimitExceeded { loss: String, limit: String },
+
+    #[error("{failures} consecutive failed bundles reached the limit of {limit}")]
+    ConsecutiveFailuresExceeded { failures: u32, limit: u32 },
+}
+
+impl RiskError {
+    /// A stable identifier of the breached limit, for alerts and event logs.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RiskError::EmergencyStop => "emergency_stop",
+            RiskError::NotionalLimitExceeded { .. } => "max_notional",
+            RiskError::GasPriceLimitExceeded { .. } => "max_gas_price",
+            RiskError::DailyLossLimitExceeded { .. } => "max_daily_loss",
+            RiskError::ConsecutiveFailuresExceeded { .. } => "max_consecutive_failures",
+        }
+    }
+}
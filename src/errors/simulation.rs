@@ -17,6 +17,9 @@ pub enum SimulationError {
     #[error("Failed to encode solution: {reason}")]
     SolutionEncodingFailed { reason: String },
 
+    #[error("Invalid solution: {reason}")]
+    InvalidSolution { reason: String },
+
     #[error("Failed to sign permit: {reason}")]
     PermitSigningFailed { reason: String },
 
@@ -73,4 +76,46 @@ pub enum SimulationError {
 
     #[error("Simulation result validation failed: {reason}")]
     ValidationFailed { reason: String },
+
+    #[error("Nonce too low: {message}")]
+    NonceTooLow { message: String },
+
+    #[error("Insufficient balance: {message}")]
+    InsufficientBalance { message: String },
+
+    #[error("Slippage exceeded: {message}")]
+    SlippageExceeded { message: String },
+}
+
+impl SimulationError {
+    /// Classify the error a simulated call reverted with.
+    ///
+    /// Reverts for a stale nonce, a missing balance or an output below the
+    /// checked amount get their own variants, all others are `SimulationFailed`.
+    pub fn from_revert(message: &str) -> Self {
+        let lowercase = message.to_lowercase();
+        if lowercase.contains("nonce too low") {
+            Self::NonceTooLow { message: message.to_string() }
+        } else if lowercase.contains("insufficient funds")
+            || lowercase.contains("insufficient balance")
+            || lowercase.contains("exceeds balance")
+        {
+            Self::InsufficientBalance { message: message.to_string() }
+        } else if lowercase.contains("slippage") || lowercase.contains("insufficient output") {
+            Self::SlippageExceeded { message: message.to_string() }
+        } else {
+            Self::SimulationFailed { reason: message.to_string() }
+        }
+    }
+
+    /// Whether simulating the path again, with a fresh nonce and state, may succeed.
+    ///
+    /// Timeouts, provider errors and stale nonces are transient. Reverts of
+    /// the swap itself, e.g. for slippage or a missing balance, are not.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::SimulationTimeout { .. } | Self::ProviderError { .. } | Self::NonceTooLow { .. }
+        )
+    }
 }
@@ -20,4 +20,19 @@ pub enum UtilityError {
 
     #[error("Unsupported chain: {chain}")]
     UnsupportedChain { chain: String },
+
+    #[error("No well-known token {symbol} on chain {chain}")]
+    UnknownTokenSymbol { symbol: String, chain: String },
+
+    #[error("Cannot register chain {chain}: chain ID {chain_id} is already registered as {existing}")]
+    ConflictingChain { chain: String, chain_id: u64, existing: String },
+
+    #[error("Invalid RPC URL '{url}': {reason}")]
+    InvalidRpcUrl { url: String, reason: String },
+
+    #[error("A provider pool needs at least one RPC endpoint")]
+    NoRpcEndpoints,
+
+    #[error("Failed to start status server on {address}: {error}")]
+    StatusServerFailed { address: String, error: String },
 }
@@ -0,0 +1,278 @@
+//! Callbacks for arbitrage lifecycle events.
+//!
+//! Integrators push alerts to chat services or feed external dashboards by
+//! implementing `EventHandler` and registering it with the engine, instead of
+//! patching the search loop. Every callback has an empty default implementation,
+//! so a handler only implements the events it cares about.
+//!
+//! Callbacks run synchronously on the search path and should return quickly.
+//! Handlers with slow I/O should hand the event to a channel or spawned task.
+//! [`NdjsonEventLog`] is a ready-made handler writing events as JSON lines.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use tycho_atomic_arbitrage::events::{BlockEvent, EventDispatcher, EventHandler};
+//!
+//! struct BlockPrinter;
+//!
+//! impl EventHandler for BlockPrinter {
+//!     fn on_block(&self, event: &BlockEvent<'_>) {
+//!         println!("block {} updated {} pools", event.block_number, event.updated_pools.len());
+//!     }
+//! }
+//!
+//! let mut events = EventDispatcher::new();
+//! events.register(Arc::new(BlockPrinter));
+//! events.on_block(&BlockEvent { block_number: 100, updated_pools: &[] });
+//! ```
+
+pub mod ndjson;
+
+pub use ndjson::{NdjsonEventLog, EVENT_SCHEMA_VERSION};
+
+use crate::bundle::BundleSubmission;
+use crate::errors::{OpportunityId, RiskError};
+use crate::path::PathExt;
+use num_bigint::{BigInt, BigUint};
+use std::fmt;
+use std::sync::Arc;
+use tycho_common::Bytes;
+
+/// A block update was applied to the market state.
+#[derive(Debug, Clone)]
+pub struct BlockEvent<'a> {
+    /// The block of the update
+    pub block_number: u64,
+    /// Pools whose state changed in the block
+    pub updated_pools: &'a [Bytes],
+}
+
+/// An optimized path is expected to be profitable and will be simulated.
+#[derive(Debug, Clone)]
+pub struct OpportunityEvent<'a> {
+    /// The block the opportunity was found in
+    pub block_number: u64,
+    /// The deterministic id of the opportunity
+    pub opportunity_id: OpportunityId,
+    /// The path executed with its optimal input amount
+    pub path: &'a PathExt,
+    /// Expected profit before gas, in the path's start token
+    pub expected_profit: BigInt,
+}
+
+/// Outcome of simulating an opportunity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimulationOutcome {
+    /// The opportunity remains profitable after gas
+    Profitable {
+        /// Expected profit after gas, in native token
+        net_profit: BigUint,
+    },
+    /// The simulation succeeded but the opportunity is not profitable after gas
+    Unprofitable,
+    /// The simulation reverted or could not be processed
+    Failed {
+        /// Why the simulation failed
+        error: String,
+    },
+}
+
+/// A simulation of an opportunity completed.
+#[derive(Debug, Clone)]
+pub struct SimulationEvent<'a> {
+    /// The block the opportunity was found in
+    pub block_number: u64,
+    /// The deterministic id of the opportunity
+    pub opportunity_id: OpportunityId,
+    /// The simulated path
+    pub path: &'a PathExt,
+    /// Whether the opportunity survived simulation
+    pub outcome: SimulationOutcome,
+}
+
+/// Bundles of a portfolio were submitted to relayers.
+#[derive(Debug, Clone)]
+pub struct SubmissionEvent<'a> {
+    /// The block the bundles target
+    pub target_block: u64,
+    /// Paths executed by the submitted portfolio
+    pub paths: &'a [PathExt],
+    /// Ids of the submitted opportunities, in the order of their paths
+    pub opportunity_ids: &'a [OpportunityId],
+    /// Results of the submissions, one per relayer and bundle
+    pub submissions: &'a [BundleSubmission],
+}
+
+/// Inclusion of a submitted portfolio was checked after its target block.
+#[derive(Debug, Clone)]
+pub struct InclusionEvent<'a> {
+    /// The block the bundles targeted
+    pub target_block: u64,
+    /// Paths executed by the portfolio
+    pub paths: &'a [PathExt],
+    /// Ids of the executed opportunities, in the order of their paths
+    pub opportunity_ids: &'a [OpportunityId],
+    /// Whether the portfolio landed on chain
+    pub included: bool,
+}
+
+/// A risk limit blocked bundle submission.
+#[derive(Debug, Clone)]
+pub struct RiskEvent<'a> {
+    /// The block whose opportunities were not submitted
+    pub block_number: u64,
+    /// The breached limit
+    pub breach: &'a RiskError,
+}
+
+/// A reorg replaced blocks the market state or pending bundles were built on.
+#[derive(Debug, Clone)]
+pub struct ReorgEvent {
+    /// The head before the reorg
+    pub old_head: u64,
+    /// The new canonical head
+    pub new_head: u64,
+    /// The first replaced block
+    pub fork_block: u64,
+    /// Pending submissions dropped because they targeted replaced blocks
+    pub dropped_submissions: usize,
+}
+
+/// Receives arbitrage lifecycle events.
+pub trait EventHandler: Send + Sync {
+    /// A block update was applied.
+    fn on_block(&self, _event: &BlockEvent<'_>) {}
+
+    /// An optimized path is expected to be profitable.
+    fn on_opportunity_found(&self, _event: &OpportunityEvent<'_>) {}
+
+    /// A simulation of an opportunity completed.
+    fn on_simulation_complete(&self, _event: &SimulationEvent<'_>) {}
+
+    /// Bundles were submitted to relayers.
+    fn on_bundle_submitted(&self, _event: &SubmissionEvent<'_>) {}
+
+    /// Inclusion of submitted bundles was checked.
+    fn on_bundle_included(&self, _event: &InclusionEvent<'_>) {}
+
+    /// A risk limit blocked bundle submission.
+    fn on_risk_breach(&self, _event: &RiskEvent<'_>) {}
+
+    /// A reorg replaced recorded blocks.
+    fn on_reorg(&self, _event: &ReorgEvent) {}
+}
+
+/// Forwards events to every registered handler, in registration order.
+#[derive(Default, Clone)]
+pub struct EventDispatcher {
+    handlers: Vec<Arc<dyn EventHandler>>,
+}
+
+impl EventDispatcher {
+    /// Create a dispatcher without handlers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler.
+    pub fn register(&mut self, handler: Arc<dyn EventHandler>) {
+        self.handlers.push(handler);
+    }
+
+    /// Register a handler, builder style.
+    pub fn with_handler(mut self, handler: Arc<dyn EventHandler>) -> Self {
+        self.register(handler);
+        self
+    }
+
+    /// Whether no handler is registered, so events need not be built.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Number of registered handlers.
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+}
+
+impl EventHandler for EventDispatcher {
+    fn on_block(&self, event: &BlockEvent<'_>) {
+        self.handlers.iter().for_each(|handler| handler.on_block(event));
+    }
+
+    fn on_opportunity_found(&self, event: &OpportunityEvent<'_>) {
+        self.handlers.iter().for_each(|handler| handler.on_opportunity_found(event));
+    }
+
+    fn on_simulation_complete(&self, event: &SimulationEvent<'_>) {
+        self.handlers.iter().for_each(|handler| handler.on_simulation_complete(event));
+    }
+
+    fn on_bundle_submitted(&self, event: &SubmissionEvent<'_>) {
+        self.handlers.iter().for_each(|handler| handler.on_bundle_submitted(event));
+    }
+
+    fn on_bundle_included(&self, event: &InclusionEvent<'_>) {
+        self.handlers.iter().for_each(|handler| handler.on_bundle_included(event));
+    }
+
+    fn on_risk_breach(&self, event: &RiskEvent<'_>) {
+        self.handlers.iter().for_each(|handler| handler.on_risk_breach(event));
+    }
+
+    fn on_reorg(&self, event: &ReorgEvent) {
+        self.handlers.iter().for_each(|handler| handler.on_reorg(event));
+    }
+}
+
+impl fmt::Debug for EventDispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventDispatcher")
+            .field("handlers", &self.handlers.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingHandler {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl EventHandler for RecordingHandler {
+        fn on_block(&self, event: &BlockEvent<'_>) {
+            self.events.lock().unwrap().push(format!("block {}", event.block_number));
+        }
+
+        fn on_bundle_included(&self, event: &InclusionEvent<'_>) {
+            self.events.lock().unwrap().push(format!("included {}", event.included));
+        }
+    }
+
+    #[test]
+    fn test_dispatcher_forwards_to_all_handlers() {
+        let first = Arc::new(RecordingHandler::default());
+        let second = Arc::new(RecordingHandler::default());
+        let events = EventDispatcher::new()
+            .with_handler(first.clone())
+            .with_handler(second.clone());
+        assert_eq!(events.len(), 2);
+
+        events.on_block(&BlockEvent { block_number: 7, updated_pools: &[] });
+        events.on_bundle_included(&InclusionEvent { target_block: 8, paths: &[], opportunity_ids: &[], included: true });
+        // Events without an implemented callback are ignored
+        events.on_bundle_submitted(&SubmissionEvent { target_block: 8, paths: &[], opportunity_ids: &[], submissions: &[] });
+
+        for handler in [first, second] {
+            assert_eq!(*handler.events.lock().unwrap(), vec!["block 7", "included true"]);
+        }
+        assert!(EventDispatcher::new().is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+//! Machine-readable event log with one JSON object per line.
+//!
+//! Analytics should not have to parse human-oriented log messages, which change
+//! whenever a message is reworded. `NdjsonEventLog` is an `EventHandler` that
+//! writes opportunities, simulations, submissions, inclusions, risk breaches and reorgs
+//! as newline delimited JSON with a stable schema. Every record carries the
+//! [`EVENT_SCHEMA_VERSION`], its `event` kind and the time it was written:
+//!
+//! ```json
+//! {"schema_version":1,"event":"opportunity","timestamp_ms":1700000000000,"block_number":21000000,...}
+//! ```
+//!
+//! Fields are only ever added within a schema version; renaming or removing a
+//! field, or changing its type, increments the version. Token amounts are
+//! decimal strings and addresses are hex strings, so no value loses precision.
+
+use super::{
+    EventHandler, InclusionEvent, OpportunityEvent, ReorgEvent, RiskEvent, SimulationEvent, SimulationOutcome,
+    SubmissionEvent,
+};
+use crate::bundle::{BundleSubmission, RelayDialect};
+use crate::path::PathExt;
+use alloy::primitives::B256;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tycho_common::Bytes;
+
+/// Version of the record schema written by the event log.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Envelope shared by all records.
+#[derive(Serialize)]
+struct Record<T> {
+    schema_version: u32,
+    event: &'static str,
+    timestamp_ms: u64,
+    #[serde(flatten)]
+    data: T,
+}
+
+/// A path as the pools and tokens it trades through and its amounts.
+#[derive(Serialize)]
+struct PathRecord {
+    pools: Vec<Bytes>,
+    tokens: Vec<Bytes>,
+    amount_in: String,
+    amount_out: String,
+    gas: String,
+}
+
+impl PathRecord {
+    fn new(path: &PathExt) -> Self {
+        let mut tokens: Vec<Bytes> = path.first().map(|swap| swap.token_in.address.clone()).into_iter().collect();
+        tokens.extend(path.iter().map(|swap| swap.token_out.address.clone()));
+
+        Self {
+            pools: path.iter().map(|swap| swap.pool_comp.id.clone()).collect(),
+            tokens,
+            amount_in: path.first().map(|swap| swap.amount_in.to_string()).unwrap_or_default(),
+            amount_out: path.last().map(|swap| swap.amount_out.to_string()).unwrap_or_default(),
+            gas: path.iter().map(|swap| &swap.gas).sum::<num_bigint::BigUint>().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct OpportunityRecord {
+    block_number: u64,
+    opportunity_id: String,
+    path: PathRecord,
+    expected_profit: String,
+}
+
+#[derive(Serialize)]
+struct SimulationRecord<'a> {
+    block_number: u64,
+    opportunity_id: String,
+    path: PathRecord,
+    outcome: &'static str,
+    net_profit: Option<String>,
+    error: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct SubmissionRecord<'a> {
+    target_block: u64,
+    paths: Vec<PathRecord>,
+    opportunity_ids: Vec<String>,
+    submissions: Vec<RelaySubmissionRecord<'a>>,
+}
+
+#[derive(Serialize)]
+struct RelaySubmissionRecord<'a> {
+    relayer_url: &'a str,
+    success: bool,
+    dry_run: bool,
+    bundle_hash: Option<B256>,
+    error: Option<&'a str>,
+    dialect: Option<RelayDialect>,
+    error_code: Option<i64>,
+    raw_response: Option<&'a serde_json::Value>,
+}
+
+impl<'a> RelaySubmissionRecord<'a> {
+    fn new(submission: &'a BundleSubmission) -> Self {
+        Self {
+            relayer_url: submission.relayer_url(),
+            success: submission.is_successful(),
+            dry_run: submission.is_dry_run(),
+            bundle_hash: submission.bundle_hash(),
+            error: submission.error(),
+            dialect: submission.relay_response().map(|response| response.dialect),
+            error_code: submission.relay_response().and_then(|response| response.error_code),
+            raw_response: submission.relay_response().and_then(|response| response.raw_response.as_ref()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct InclusionRecord {
+    target_block: u64,
+    paths: Vec<PathRecord>,
+    opportunity_ids: Vec<String>,
+    included: bool,
+}
+
+#[derive(Serialize)]
+struct RiskBreachRecord {
+    block_number: u64,
+    limit: &'static str,
+    reason: String,
+}
+
+#[derive(Serialize)]
+struct ReorgRecord {
+    old_head: u64,
+    new_head: u64,
+    fork_block: u64,
+    dropped_submissions: usize,
+}
+
+/// Writes lifecycle events as newline-delimited JSON.
+///
+/// Every record is flushed as soon as it is written, so the log can be tailed
+/// while the bot runs. Write failures are logged and never interrupt the search.
+pub struct NdjsonEventLog<W: Write + Send> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> NdjsonEventLog<W> {
+    /// Create an event log writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Consume the log and return its writer.
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write<T: Serialize>(&self, event: &'static str, data: T) {
+        let record = Record {
+            schema_version: EVENT_SCHEMA_VERSION,
+            event,
+            timestamp_ms: now_ms(),
+            data,
+        };
+
+        let result = serde_json::to_vec(&record)
+            .map_err(io::Error::from)
+            .and_then(|mut line| {
+                line.push(b'\n');
+                let mut writer = self.writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                writer.write_all(&line)?;
+                writer.flush()
+            });
+
+        if let Err(e) = result {
+            tracing::warn!(event = event, error = %e, "Failed to write event log record");
+        }
+    }
+}
+
+impl NdjsonEventLog<BufWriter<File>> {
+    /// Create an event log appending to the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or created
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write + Send> EventHandler for NdjsonEventLog<W> {
+    fn on_opportunity_found(&self, event: &OpportunityEvent<'_>) {
+        self.write("opportunity", OpportunityRecord {
+            block_number: event.block_number,
+            opportunity_id: event.opportunity_id.to_string(),
+            path: PathRecord::new(event.path),
+            expected_profit: event.expected_profit.to_string(),
+        });
+    }
+
+    fn on_simulation_complete(&self, event: &SimulationEvent<'_>) {
+        let (outcome, net_profit, error) = match &event.outcome {
+            SimulationOutcome::Profitable { net_profit } => ("profitable", Some(net_profit.to_string()), None),
+            SimulationOutcome::Unprofitable => ("unprofitable", None, None),
+            SimulationOutcome::Failed { error } => ("failed", None, Some(error.as_str())),
+        };
+
+        self.write("simulation", SimulationRecord {
+            block_number: event.block_number,
+            opportunity_id: event.opportunity_id.to_string(),
+            path: PathRecord::new(event.path),
+            outcome,
+            net_profit,
+            error,
+        });
+    }
+
+    fn on_bundle_submitted(&self, event: &SubmissionEvent<'_>) {
+        self.write("submission", SubmissionRecord {
+            target_block: event.target_block,
+            paths: event.paths.iter().map(PathRecord::new).collect(),
+            opportunity_ids: event.opportunity_ids.iter().map(ToString::to_string).collect(),
+            submissions: event.submissions.iter().map(RelaySubmissionRecord::new).collect(),
+        });
+    }
+
+    fn on_bundle_included(&self, event: &InclusionEvent<'_>) {
+        self.write("inclusion", InclusionRecord {
+            target_block: event.target_block,
+            paths: event.paths.iter().map(PathRecord::new).collect(),
+            opportunity_ids: event.opportunity_ids.iter().map(ToString::to_string).collect(),
+            included: event.included,
+        });
+    }
+
+    fn on_risk_breach(&self, event: &RiskEvent<'_>) {
+        self.write("risk_breach", RiskBreachRecord {
+            block_number: event.block_number,
+            limit: event.breach.kind(),
+            reason: event.breach.to_string(),
+        });
+    }
+
+    fn on_reorg(&self, event: &ReorgEvent) {
+        self.write("reorg", ReorgRecord {
+            old_head: event.old_head,
+            new_head: event.new_head,
+            fork_block: event.fork_block,
+            dropped_submissions: event.dropped_submissions,
+        });
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_carry_schema_version_and_kind() {
+        let log = NdjsonEventLog::new(Vec::new());
+        let bundle_hash = B256::with_last_byte(0xab);
+        let submissions = [BundleSubmission::new(101, Some(bundle_hash), "https://relay.example".to_string(), true, None)];
+        let opportunity_ids = [crate::errors::OpportunityId::new(crate::path::PathId::from_hops([]), 100)];
+
+        log.on_bundle_submitted(&SubmissionEvent { target_block: 101, paths: &[], opportunity_ids: &opportunity_ids, submissions: &submissions });
+        log.on_bundle_included(&InclusionEvent { target_block: 101, paths: &[], opportunity_ids: &[], included: false });
+
+        let output = String::from_utf8(log.into_inner()).unwrap();
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["schema_version"], EVENT_SCHEMA_VERSION);
+        assert_eq!(records[0]["event"], "submission");
+        assert_eq!(records[0]["submissions"][0]["bundle_hash"], bundle_hash.to_string());
+        assert_eq!(records[0]["submissions"][0]["dry_run"], false);
+        assert_eq!(records[0]["opportunity_ids"][0], opportunity_ids[0].to_string());
+        assert_eq!(records[1]["event"], "inclusion");
+        assert_eq!(records[1]["included"], false);
+        assert!(records[1]["timestamp_ms"].as_u64().unwrap() > 0);
+    }
+}
@@ -3,19 +3,42 @@
 //! This module contains the main `TradingGraph` struct and all its methods
 //! for managing token trading networks and liquidity pools.
 
+use crate::config::ExchangeFilter;
 use crate::errors::{GraphError, Result};
-use super::types::{TokenId, PoolId, PoolInfo, TokenNode, LiquidityPool};
+use super::types::{GraphLoadStatistics, PoolDirection, TokenId, PoolId, TokenHandle, PoolHandle, PoolInfo, TokenMetadata, TokenNode, LiquidityPool};
 use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 use tycho_common::Bytes;
+use tycho_simulation::models::Token;
 use tycho_simulation::protocol::models::ProtocolComponent;
 
+/// Default maximum number of tokens of a protocol component added to the graph.
+pub const DEFAULT_MAX_POOL_TOKENS: usize = 4;
+
+/// Pools with up to this many tokens always get all their token pairs as edges.
+const EAGER_PAIR_TOKEN_LIMIT: usize = 4;
+
 /// A specialized graph data structure for modeling token trading networks.
 ///
 /// The `TradingGraph` represents a network where:
 /// - Nodes are tokens/assets that can be traded
 /// - Edges are liquidity pools that enable trading between token pairs
 /// - The graph supports bidirectional trading (each pool creates two directed edges)
-#[derive(Debug)]
+///
+/// Protocol components with more than two tokens are expanded into pairwise
+/// edges. Components with more than [`DEFAULT_MAX_POOL_TOKENS`] tokens are
+/// rejected unless the cap is raised with [`TradingGraph::with_max_pool_tokens`].
+/// When source tokens are set, pairs of such large pools are only added once
+/// they can be part of a cycle through a source token, see
+/// [`TradingGraph::with_source_tokens`].
+///
+/// Token and pool IDs are vector positions and change when tokens or pools are
+/// removed. Anything kept across removals, like discovered paths, should hold
+/// [`TokenHandle`]s and [`PoolHandle`]s and resolve them to the current IDs.
+///
+/// The graph is not synchronized. To read it while block updates are applied
+/// concurrently, share it as a [`SharedTradingGraph`](super::SharedTradingGraph).
+#[derive(Debug, Clone)]
 pub struct TradingGraph {
     /// Vector of all token nodes in the graph
     tokens: Vec<TokenNode>,
@@ -25,6 +48,24 @@ pub struct TradingGraph {
     token_address_to_id: HashMap<Bytes, TokenId>,
     /// Mapping from token pairs to pool IDs for fast pool lookup
     token_pair_to_pools: HashMap<[TokenId; 2], Vec<PoolId>>,
+    /// Stable handle of each token, by token ID
+    token_handles: Vec<TokenHandle>,
+    /// Current token ID of each token handle
+    token_handle_ids: HashMap<TokenHandle, TokenId>,
+    /// Stable handle of each directed pool, by pool ID
+    pool_handles: Vec<PoolHandle>,
+    /// Current pool ID of each pool handle
+    pool_handle_ids: HashMap<PoolHandle, PoolId>,
+    /// Maximum number of tokens of a protocol component
+    max_pool_tokens: usize,
+    /// Tokens that arbitrage cycles start from
+    source_tokens: HashSet<Bytes>,
+    /// Token pairs of large pools not yet added as edges, by pool address
+    deferred_pairs: HashMap<Bytes, Vec<[Bytes; 2]>>,
+    /// Block of the last recorded state update, by pool address
+    last_updates: HashMap<Bytes, u64>,
+    /// Exchanges and pools protocol components are accepted from, if restricted
+    exchange_filter: Option<ExchangeFilter>,
 }
 
 impl TradingGraph {
@@ -35,13 +76,78 @@ impl TradingGraph {
             pools: Vec::new(),
             token_address_to_id: HashMap::new(),
             token_pair_to_pools: HashMap::new(),
+            token_handles: Vec::new(),
+            token_handle_ids: HashMap::new(),
+            pool_handles: Vec::new(),
+            pool_handle_ids: HashMap::new(),
+            max_pool_tokens: DEFAULT_MAX_POOL_TOKENS,
+            source_tokens: HashSet::new(),
+            deferred_pairs: HashMap::new(),
+            last_updates: HashMap::new(),
+            exchange_filter: None,
         }
     }
 
+    /// Accept protocol components with up to `max_pool_tokens` tokens.
+    ///
+    /// Use `usize::MAX` to remove the cap, e.g. for Balancer pools with up to
+    /// eight tokens.
+    pub fn with_max_pool_tokens(mut self, max_pool_tokens: usize) -> Self {
+        self.max_pool_tokens = max_pool_tokens;
+        self
+    }
+
+    /// Set the tokens arbitrage cycles start from.
+    ///
+    /// For pools with more than four tokens, only pairs that can be part of a
+    /// cycle of up to three swaps through a source token are added: pairs
+    /// containing a source token, and pairs whose tokens both trade directly
+    /// against a source token. The remaining pairs are deferred and added once
+    /// later pools connect their tokens to a source token. Without source tokens
+    /// all pairs are added.
+    pub fn with_source_tokens<I>(mut self, source_tokens: I) -> Self
+    where
+        I: IntoIterator<Item = Bytes>,
+    {
+        self.source_tokens = source_tokens.into_iter().collect();
+        self
+    }
+
+    /// Only accept protocol components of enabled exchanges that pass their pool lists.
+    ///
+    /// Components rejected by the filter are skipped without error. Without a
+    /// filter, components of all exchanges are accepted.
+    pub fn with_exchange_filter(mut self, exchange_filter: ExchangeFilter) -> Self {
+        self.exchange_filter = Some(exchange_filter);
+        self
+    }
+
+    /// The maximum number of tokens of a protocol component.
+    pub fn max_pool_tokens(&self) -> usize {
+        self.max_pool_tokens
+    }
+
+    /// Number of token pairs of large pools not yet added as edges.
+    pub fn deferred_pair_count(&self) -> usize {
+        self.deferred_pairs.values().map(Vec::len).sum()
+    }
+
     // ================================
     // Construction Methods
     // ================================
 
+    /// Reserve capacity for at least `additional_tokens` tokens and `additional_pools` directed pools.
+    pub fn reserve(&mut self, additional_tokens: usize, additional_pools: usize) {
+        self.tokens.reserve(additional_tokens);
+        self.token_address_to_id.reserve(additional_tokens);
+        self.token_handles.reserve(additional_tokens);
+        self.token_handle_ids.reserve(additional_tokens);
+        self.pools.reserve(additional_pools);
+        self.token_pair_to_pools.reserve(additional_pools);
+        self.pool_handles.reserve(additional_pools);
+        self.pool_handle_ids.reserve(additional_pools);
+    }
+
     /// Add a token to the trading graph.
     ///
     /// If a token with the same address already exists, returns the existing token ID.
@@ -61,9 +167,50 @@ impl TradingGraph {
         let token_id = self.tokens.len();
         self.tokens.push(TokenNode::new(address.clone()));
         self.token_address_to_id.insert(address, token_id);
+
+        let handle = TokenHandle::next();
+        self.token_handles.push(handle);
+        self.token_handle_ids.insert(handle, token_id);
+        Ok(token_id)
+    }
+
+    /// Add a token with its metadata to the trading graph.
+    ///
+    /// If a token with the same address already exists, its metadata is
+    /// replaced and the existing token ID is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The on-chain address of the token to add
+    /// * `metadata` - The symbol, decimals and transfer gas of the token
+    ///
+    /// # Returns
+    ///
+    /// The token ID that can be used to reference this token in other operations
+    pub fn add_token_with_metadata(&mut self, address: Bytes, metadata: TokenMetadata) -> Result<TokenId> {
+        let token_id = self.add_token(address)?;
+        self.tokens[token_id].set_metadata(metadata);
         Ok(token_id)
     }
 
+    /// Set the metadata of a token in the graph
+    ///
+    /// # Arguments
+    ///
+    /// * `token_id` - The ID of the token
+    /// * `metadata` - The symbol, decimals and transfer gas of the token
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token ID is invalid
+    pub fn set_token_metadata(&mut self, token_id: TokenId, metadata: TokenMetadata) -> Result<()> {
+        self.tokens
+            .get_mut(token_id)
+            .ok_or_else(|| GraphError::InvalidNodeIndex { index: token_id })?
+            .set_metadata(metadata);
+        Ok(())
+    }
+
     /// Add a liquidity pool connecting two tokens.
     ///
     /// This creates bidirectional trading capability between the two tokens.
@@ -103,6 +250,7 @@ impl TradingGraph {
     /// Remove a token and all its associated pools from the graph.
     ///
     /// This operation will also remove all liquidity pools that involve this token.
+    /// The last token of the graph takes over the ID of the removed token.
     ///
     /// # Arguments
     ///
@@ -117,32 +265,44 @@ impl TradingGraph {
         }
 
         // Collect all pools to remove (both directions)
-        let mut pools_to_remove = Vec::new();
-        for &neighbor_id in self.token_neighbors(token_id)?.iter() {
-            if let Ok(pool_ids) = self.pools_between_tokens([token_id, neighbor_id]) {
-                for &pool_id in pool_ids.iter() {
-                    pools_to_remove.push((self.pools[pool_id].address().clone(), [token_id, neighbor_id]));
-                }
-            }
-        }
+        let pools_to_remove: Vec<(Bytes, [TokenId; 2])> = self
+            .pools
+            .iter()
+            .filter(|pool| pool.tokens().contains(&token_id))
+            .map(|pool| (pool.address().clone(), pool.tokens()))
+            .collect();
 
         // Remove all associated pools
         for (pool_address, token_pair) in pools_to_remove.iter() {
             let _ = self.remove_pool_by_address_and_tokens(pool_address, token_pair);
         }
 
-        // Handle swap-remove index updates
-        let last_token_id = self.tokens.len() - 1;
-        if token_id != last_token_id {
-            // Update the address mapping for the token that will be moved
-            if let Some(entry) = self.token_address_to_id.get_mut(self.tokens[last_token_id].address()) {
-                *entry = token_id;
-            }
-        }
-
         // Remove the token
         self.token_address_to_id.remove(self.tokens[token_id].address());
         self.tokens.swap_remove(token_id);
+        let removed_handle = self.token_handles.swap_remove(token_id);
+        self.token_handle_ids.remove(&removed_handle);
+
+        // Handle swap-remove index updates of the token moved into the freed ID
+        let last_token_id = self.tokens.len();
+        if token_id != last_token_id {
+            self.token_address_to_id.insert(self.tokens[token_id].address().clone(), token_id);
+            self.token_handle_ids.insert(self.token_handles[token_id], token_id);
+
+            let neighbors: Vec<TokenId> = self.tokens[token_id].neighbors().iter().copied().collect();
+            for neighbor_id in neighbors {
+                self.tokens[neighbor_id].replace_neighbor(last_token_id, token_id);
+                for token_pair in [[last_token_id, neighbor_id], [neighbor_id, last_token_id]] {
+                    if let Some(pool_ids) = self.token_pair_to_pools.remove(&token_pair) {
+                        for &pool_id in &pool_ids {
+                            self.pools[pool_id].replace_token(last_token_id, token_id);
+                        }
+                        let moved_pair = token_pair.map(|id| if id == last_token_id { token_id } else { id });
+                        self.token_pair_to_pools.insert(moved_pair, pool_ids);
+                    }
+                }
+            }
+        }
 
         Ok(())
     }
@@ -240,6 +400,44 @@ impl TradingGraph {
             .ok_or_else(|| GraphError::InvalidNodeIndex { index: token_id }.into())
     }
 
+    /// Find the token with a given symbol, e.g. `WETH`, ignoring case
+    ///
+    /// Symbols are not unique, and copycat tokens often reuse the symbol of a
+    /// popular token. Among tokens sharing the symbol, the one with the most
+    /// neighbors is returned, which is the canonical token in practice. Use
+    /// [`tokens_by_symbol`](Self::tokens_by_symbol) to see all of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The symbol to look up
+    ///
+    /// # Returns
+    ///
+    /// The token ID, or `None` if no token with known metadata has the symbol
+    pub fn token_by_symbol(&self, symbol: &str) -> Option<TokenId> {
+        self.tokens_by_symbol(symbol)
+            .into_iter()
+            .max_by_key(|&token_id| (self.tokens[token_id].neighbor_count(), std::cmp::Reverse(token_id)))
+    }
+
+    /// Find all tokens with a given symbol, ignoring case
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The symbol to look up
+    ///
+    /// # Returns
+    ///
+    /// The IDs of the tokens with the symbol, in ascending order
+    pub fn tokens_by_symbol(&self, symbol: &str) -> Vec<TokenId> {
+        self.tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| token.symbol().is_some_and(|token_symbol| token_symbol.eq_ignore_ascii_case(symbol)))
+            .map(|(token_id, _)| token_id)
+            .collect()
+    }
+
     /// Get a liquidity pool by its ID
     ///
     /// # Arguments
@@ -268,6 +466,163 @@ impl TradingGraph {
         &self.pools
     }
 
+    /// The stable handle of a token
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token ID is invalid
+    pub fn token_handle(&self, token_id: TokenId) -> Result<TokenHandle> {
+        self.token_handles
+            .get(token_id)
+            .copied()
+            .ok_or_else(|| GraphError::InvalidNodeIndex { index: token_id }.into())
+    }
+
+    /// The current ID of a token handle
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token was removed
+    pub fn resolve_token(&self, handle: TokenHandle) -> Result<TokenId> {
+        self.token_handle_ids
+            .get(&handle)
+            .copied()
+            .ok_or_else(|| GraphError::StaleHandle { handle: handle.to_string() }.into())
+    }
+
+    /// The stable handle of a directed pool
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool ID is invalid
+    pub fn pool_handle(&self, pool_id: PoolId) -> Result<PoolHandle> {
+        self.pool_handles
+            .get(pool_id)
+            .copied()
+            .ok_or_else(|| GraphError::InvalidEdgeIndex { index: pool_id }.into())
+    }
+
+    /// The current ID of a pool handle
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool was removed
+    pub fn resolve_pool(&self, handle: PoolHandle) -> Result<PoolId> {
+        self.pool_handle_ids
+            .get(&handle)
+            .copied()
+            .ok_or_else(|| GraphError::StaleHandle { handle: handle.to_string() }.into())
+    }
+
+    /// Verify the internal consistency of the graph.
+    ///
+    /// Checks that the address map and the token vector agree, that every
+    /// directed pool is indexed under its token pair exactly once, that every
+    /// pool has a pool of the same address in the reverse direction, that the
+    /// neighbors of each token are exactly the tokens it has pools with, and
+    /// that every handle resolves to the token or pool it was assigned to.
+    /// Intended for tests and debugging, the check visits every token and pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GraphError::InvariantViolation` describing the first inconsistency found
+    pub fn check_invariants(&self) -> Result<()> {
+        let violation = |reason: String| Err(GraphError::InvariantViolation { reason }.into());
+
+        if self.token_address_to_id.len() != self.tokens.len() {
+            return violation(format!(
+                "{} token addresses are mapped for {} tokens",
+                self.token_address_to_id.len(),
+                self.tokens.len()
+            ));
+        }
+        for (token_id, token) in self.tokens.iter().enumerate() {
+            if self.token_address_to_id.get(token.address()) != Some(&token_id) {
+                return violation(format!("token {} at address {} is not mapped to its ID", token_id, token.address()));
+            }
+        }
+
+        if self.token_handles.len() != self.tokens.len() || self.token_handle_ids.len() != self.tokens.len() {
+            return violation(format!("{} token handles are assigned for {} tokens", self.token_handle_ids.len(), self.tokens.len()));
+        }
+        if self.pool_handles.len() != self.pools.len() || self.pool_handle_ids.len() != self.pools.len() {
+            return violation(format!("{} pool handles are assigned for {} pools", self.pool_handle_ids.len(), self.pools.len()));
+        }
+        for (token_id, handle) in self.token_handles.iter().enumerate() {
+            if self.token_handle_ids.get(handle) != Some(&token_id) {
+                return violation(format!("handle {} does not resolve to token {}", handle, token_id));
+            }
+        }
+        for (pool_id, handle) in self.pool_handles.iter().enumerate() {
+            if self.pool_handle_ids.get(handle) != Some(&pool_id) {
+                return violation(format!("handle {} does not resolve to pool {}", handle, pool_id));
+            }
+        }
+
+        let mut indexed_pools = 0;
+        for (token_pair, pool_ids) in &self.token_pair_to_pools {
+            if pool_ids.is_empty() {
+                return violation(format!("token pair {:?} is indexed without pools", token_pair));
+            }
+            let mut addresses = HashSet::new();
+            for &pool_id in pool_ids {
+                let Some(pool) = self.pools.get(pool_id) else {
+                    return violation(format!("token pair {:?} indexes missing pool {}", token_pair, pool_id));
+                };
+                if pool.tokens() != *token_pair {
+                    return violation(format!(
+                        "pool {} connects {:?} but is indexed under {:?}",
+                        pool_id,
+                        pool.tokens(),
+                        token_pair
+                    ));
+                }
+                if !addresses.insert(pool.address()) {
+                    return violation(format!("pool {} is indexed twice under {:?}", pool.address(), token_pair));
+                }
+            }
+            indexed_pools += pool_ids.len();
+
+            let reversed = [token_pair[1], token_pair[0]];
+            let reverse_addresses: HashSet<&Bytes> = self
+                .token_pair_to_pools
+                .get(&reversed)
+                .map(|pool_ids| {
+                    pool_ids
+                        .iter()
+                        .filter_map(|&pool_id| self.pools.get(pool_id))
+                        .map(LiquidityPool::address)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if addresses != reverse_addresses {
+                return violation(format!("pools of {:?} and {:?} differ", token_pair, reversed));
+            }
+        }
+        if indexed_pools != self.pools.len() {
+            return violation(format!("{} of {} pools are indexed", indexed_pools, self.pools.len()));
+        }
+
+        for (token_id, token) in self.tokens.iter().enumerate() {
+            let expected: HashSet<TokenId> = self
+                .token_pair_to_pools
+                .keys()
+                .filter(|token_pair| token_pair[0] == token_id)
+                .map(|token_pair| token_pair[1])
+                .collect();
+            if *token.neighbors() != expected {
+                return violation(format!(
+                    "token {} has neighbors {:?} but pools with {:?}",
+                    token_id,
+                    token.neighbors(),
+                    expected
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     // ================================
     // Navigation Methods
     // ================================
@@ -297,6 +652,9 @@ impl TradingGraph {
 
     /// Get all pools that connect two specific tokens
     ///
+    /// Pools are directed: the returned pools trade `token_pair[0]` for
+    /// `token_pair[1]`, like [`pools_from_to`](Self::pools_from_to).
+    ///
     /// # Arguments
     ///
     /// * `token_pair` - Array of exactly 2 token IDs
@@ -314,6 +672,44 @@ impl TradingGraph {
             .ok_or_else(|| GraphError::PathNotFound.into())
     }
 
+    /// Get the directed pools trading `token_in` for `token_out`
+    ///
+    /// # Arguments
+    ///
+    /// * `token_in` - The ID of the token sold
+    /// * `token_out` - The ID of the token bought
+    ///
+    /// # Returns
+    ///
+    /// The pool IDs trading in this direction, empty if there are none
+    pub fn pools_from_to(&self, token_in: TokenId, token_out: TokenId) -> &[PoolId] {
+        self.token_pair_to_pools
+            .get(&[token_in, token_out])
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Get the directed pools between two tokens in both directions
+    ///
+    /// # Arguments
+    ///
+    /// * `token_a` - The ID of the first token
+    /// * `token_b` - The ID of the second token
+    ///
+    /// # Returns
+    ///
+    /// The pool IDs with their direction, [`PoolDirection::Forward`] for pools
+    /// trading `token_a` for `token_b` first, then [`PoolDirection::Reverse`]
+    /// for pools trading `token_b` for `token_a`
+    pub fn pools_any_direction(&self, token_a: TokenId, token_b: TokenId) -> Vec<(PoolId, PoolDirection)> {
+        let forward = self.pools_from_to(token_a, token_b);
+        let reverse = self.pools_from_to(token_b, token_a);
+        forward
+            .iter()
+            .map(|&pool_id| (pool_id, PoolDirection::Forward))
+            .chain(reverse.iter().map(|&pool_id| (pool_id, PoolDirection::Reverse)))
+            .collect()
+    }
+
     // ================================
     // Integration Methods
     // ================================
@@ -323,6 +719,9 @@ impl TradingGraph {
     /// This is a convenience method that extracts token information from a
     /// `ProtocolComponent` and adds the corresponding pool to the graph.
     /// For pools with 3 or 4 tokens, all possible 2-token pairs are created.
+    /// For larger pools, pairs that cannot be part of a cycle through a source
+    /// token are deferred until they can. Deferred pairs of other pools that
+    /// the new pool connects to a source token are added as well.
     ///
     /// # Arguments
     ///
@@ -336,7 +735,7 @@ impl TradingGraph {
     /// # Errors
     ///
     /// Returns an error if:
-    /// - The protocol component doesn't have 2-4 tokens
+    /// - The protocol component has fewer than 2 or more than the maximum number of tokens
     /// - Pool addition fails for any reason
     pub fn add_protocol_component(&mut self, pool_id: Bytes, pool_component: ProtocolComponent) -> Result<Vec<PoolInfo>> {
         tracing::debug!(
@@ -346,6 +745,15 @@ impl TradingGraph {
             "Adding protocol component to graph"
         );
 
+        if !self.accepts_component(&pool_id, &pool_component) {
+            tracing::debug!(
+                pool_address = %pool_id,
+                protocol_system = %pool_component.protocol_system,
+                "Protocol component excluded by exchange filter"
+            );
+            return Ok(Vec::new());
+        }
+
         // Extract and validate token information
         let token_addresses: Vec<Bytes> = pool_component
             .tokens
@@ -353,39 +761,25 @@ impl TradingGraph {
             .map(|token| token.address.clone())
             .collect();
 
-        if token_addresses.len() < 2 || token_addresses.len() > 4 {
+        if token_addresses.len() < 2 || token_addresses.len() > self.max_pool_tokens {
             tracing::error!(
                 pool_address = %pool_id,
                 token_count = token_addresses.len(),
-                "Invalid token count for pool - expected 2-4 tokens"
+                max_pool_tokens = self.max_pool_tokens,
+                "Invalid token count for pool"
             );
             return Err(GraphError::InvalidTokenCount { count: token_addresses.len() }.into());
         }
 
-        // Generate all possible token pairs
-        let token_pairs = Self::generate_token_pairs(&token_addresses);
-        let mut pool_infos = Vec::new();
-
-        // Add each token pair as a separate pool
-        for pair in token_pairs {
-            // Add tokens to the graph (or get existing IDs)
-            let token_id_0 = self.add_token(pair[0].clone())?;
-            let token_id_1 = self.add_token(pair[1].clone())?;
-            let token_ids = [token_id_0, token_id_1];
-
-            // Add the pool
-            let pool_ids = self.add_pool(pool_id.clone(), token_ids)?;
-
-            pool_infos.push(PoolInfo {
-                token_ids,
-                pool_ids,
-            });
-        }
+        let mut pool_infos = self.add_component_pairs(&pool_id, &token_addresses)?;
+        self.attach_token_metadata(&pool_component.tokens);
+        pool_infos.extend(self.materialize_deferred_pairs()?);
 
         tracing::info!(
             pool_address = %pool_id,
             token_count = token_addresses.len(),
             pairs_created = pool_infos.len(),
+            pairs_deferred = self.deferred_pairs.get(&pool_id).map_or(0, Vec::len),
             total_tokens = self.token_count(),
             total_pools = self.pool_count(),
             "Protocol component added successfully to graph"
@@ -394,6 +788,112 @@ impl TradingGraph {
         Ok(pool_infos)
     }
 
+    /// Add many protocol components at once, e.g. from the initial Tycho snapshot.
+    ///
+    /// Equivalent to [`add_protocol_component`](Self::add_protocol_component)
+    /// for each component, but the tokens of all components are deduplicated
+    /// and added first, the graph is pre-sized for the new tokens and pairs,
+    /// and deferred pairs of large pools are materialized once at the end.
+    /// Components rejected by the exchange filter, with an invalid token count
+    /// or failing to add are skipped and counted instead of failing the load.
+    ///
+    /// # Arguments
+    ///
+    /// * `components` - Pool addresses and their protocol components
+    ///
+    /// # Returns
+    ///
+    /// The details of the added pools and statistics of the load
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if materializing deferred pairs fails
+    pub fn add_protocol_components<'c, I>(&mut self, components: I) -> Result<(Vec<PoolInfo>, GraphLoadStatistics)>
+    where
+        I: IntoIterator<Item = (Bytes, &'c ProtocolComponent)>,
+    {
+        let started_at = Instant::now();
+        let tokens_before = self.token_count();
+        let mut statistics = GraphLoadStatistics::default();
+
+        let mut accepted = Vec::new();
+        for (pool_id, pool_component) in components {
+            statistics.components += 1;
+            if !self.accepts_component(&pool_id, pool_component) {
+                statistics.filtered += 1;
+                continue;
+            }
+            let token_count = pool_component.tokens.len();
+            if token_count < 2 || token_count > self.max_pool_tokens {
+                tracing::debug!(
+                    pool_address = %pool_id,
+                    token_count = token_count,
+                    max_pool_tokens = self.max_pool_tokens,
+                    "Invalid token count for pool"
+                );
+                statistics.rejected += 1;
+                continue;
+            }
+            let token_addresses: Vec<Bytes> = pool_component.tokens.iter().map(|token| token.address.clone()).collect();
+            accepted.push((pool_id, pool_component, token_addresses));
+        }
+
+        // Deduplicate the tokens of all components and size the graph for them
+        let mut seen = HashSet::new();
+        let new_tokens: Vec<&Token> = accepted
+            .iter()
+            .flat_map(|(_, pool_component, _)| pool_component.tokens.iter())
+            .filter(|token| !self.token_address_to_id.contains_key(&token.address) && seen.insert(&token.address))
+            .collect();
+        let pair_count: usize = accepted
+            .iter()
+            .map(|(_, _, token_addresses)| token_addresses.len() * (token_addresses.len() - 1) / 2)
+            .sum();
+        self.reserve(new_tokens.len(), 2 * pair_count);
+        for token in new_tokens {
+            self.add_token_with_metadata(token.address.clone(), TokenMetadata::from(token))?;
+        }
+
+        let mut pool_infos = Vec::with_capacity(pair_count);
+        for (pool_id, pool_component, token_addresses) in &accepted {
+            match self.add_component_pairs(pool_id, token_addresses) {
+                Ok(component_pool_infos) => {
+                    statistics.added += 1;
+                    pool_infos.extend(component_pool_infos);
+                    self.attach_token_metadata(&pool_component.tokens);
+                }
+                Err(e) => {
+                    tracing::debug!(
+                        pool_address = %pool_id,
+                        error = %e,
+                        "Failed to add protocol component to graph"
+                    );
+                    statistics.rejected += 1;
+                }
+            }
+        }
+        pool_infos.extend(self.materialize_deferred_pairs()?);
+
+        statistics.tokens_added = self.token_count() - tokens_before;
+        statistics.pairs_added = pool_infos.len();
+        statistics.pairs_deferred = self.deferred_pair_count();
+        statistics.duration = started_at.elapsed();
+
+        tracing::info!(
+            components = statistics.components,
+            added = statistics.added,
+            filtered = statistics.filtered,
+            rejected = statistics.rejected,
+            tokens_added = statistics.tokens_added,
+            pairs_added = statistics.pairs_added,
+            pairs_deferred = statistics.pairs_deferred,
+            duration_ms = statistics.duration.as_millis() as u64,
+            "Protocol components loaded into graph"
+        );
+
+        Ok((pool_infos, statistics))
+    }
+
     /// Remove a protocol component pool from the graph
     ///
     /// # Arguments
@@ -404,13 +904,189 @@ impl TradingGraph {
     ///
     /// Returns an error if the pool doesn't exist
     pub fn remove_protocol_component(&mut self, pool_id: &Bytes) -> Result<()> {
-        self.remove_pool_by_address(pool_id)
+        self.last_updates.remove(pool_id);
+        let had_deferred_pairs = self.deferred_pairs.remove(pool_id).is_some();
+
+        match self.remove_pool_by_address(pool_id) {
+            // A large pool may have had all of its pairs deferred
+            Err(_) if had_deferred_pairs => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Record that a pool received a state update in the given block.
+    ///
+    /// Earlier blocks than the latest recorded one are ignored.
+    pub fn record_update(&mut self, pool_address: &Bytes, block_number: u64) {
+        let last_update = self.last_updates.entry(pool_address.clone()).or_insert(block_number);
+        *last_update = (*last_update).max(block_number);
+    }
+
+    /// The block of the last recorded state update of a pool, if any.
+    pub fn last_update(&self, pool_address: &Bytes) -> Option<u64> {
+        self.last_updates.get(pool_address).copied()
+    }
+
+    /// Remove all pools whose last recorded state update is older than a block.
+    ///
+    /// Pools without a recorded update are kept. Removing pools changes the IDs
+    /// of other pools, so paths built from pool IDs have to be remapped, see
+    /// [`PathRepository::prune_stale_pools`](crate::path::PathRepository::prune_stale_pools).
+    ///
+    /// # Arguments
+    ///
+    /// * `older_than_block` - Pools last updated before this block are removed
+    ///
+    /// # Returns
+    ///
+    /// The addresses of the removed pools
+    pub fn prune_stale(&mut self, older_than_block: u64) -> Vec<Bytes> {
+        let mut stale: Vec<Bytes> = self
+            .last_updates
+            .iter()
+            .filter(|(_, &block_number)| block_number < older_than_block)
+            .map(|(pool_address, _)| pool_address.clone())
+            .collect();
+        stale.sort();
+
+        stale.retain(|pool_address| self.remove_protocol_component(pool_address).is_ok());
+
+        if !stale.is_empty() {
+            tracing::info!(
+                older_than_block = older_than_block,
+                pruned_pools = stale.len(),
+                total_pools = self.pool_count(),
+                "Pruned stale pools from graph"
+            );
+        }
+
+        stale
+    }
+
+    /// Add deferred token pairs that can now be part of a cycle through a source token.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<PoolInfo>` with details about the added pools
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if pool addition fails
+    pub fn materialize_deferred_pairs(&mut self) -> Result<Vec<PoolInfo>> {
+        if self.deferred_pairs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Tokens trading directly against a source token
+        let source_adjacent: HashSet<TokenId> = self
+            .source_tokens
+            .iter()
+            .filter_map(|token| self.token_address_to_id.get(token))
+            .flat_map(|&token_id| self.tokens[token_id].neighbors().iter().copied())
+            .collect();
+        let token_ids = &self.token_address_to_id;
+        let is_adjacent = |token: &Bytes| {
+            token_ids
+                .get(token)
+                .is_some_and(|token_id| source_adjacent.contains(token_id))
+        };
+
+        let mut ready = Vec::new();
+        for (pool_id, pairs) in self.deferred_pairs.iter_mut() {
+            let (now_ready, still_deferred): (Vec<_>, Vec<_>) = std::mem::take(pairs)
+                .into_iter()
+                .partition(|pair| pair.iter().all(is_adjacent));
+            *pairs = still_deferred;
+            ready.extend(now_ready.into_iter().map(|pair| (pool_id.clone(), pair)));
+        }
+        self.deferred_pairs.retain(|_, pairs| !pairs.is_empty());
+
+        let mut pool_infos = Vec::with_capacity(ready.len());
+        for (pool_id, pair) in ready {
+            pool_infos.push(self.add_token_pair(&pool_id, &pair)?);
+        }
+
+        if !pool_infos.is_empty() {
+            tracing::debug!(
+                pairs_added = pool_infos.len(),
+                pairs_deferred = self.deferred_pair_count(),
+                "Added deferred pool token pairs"
+            );
+        }
+
+        Ok(pool_infos)
     }
 
     // ================================
     // Private Helper Methods
     // ================================
 
+    /// Set the metadata of the tokens of a component that are in the graph without metadata
+    fn attach_token_metadata(&mut self, tokens: &[Token]) {
+        for token in tokens {
+            if let Some(&token_id) = self.token_address_to_id.get(&token.address) {
+                if self.tokens[token_id].metadata().is_none() {
+                    self.tokens[token_id].set_metadata(TokenMetadata::from(token));
+                }
+            }
+        }
+    }
+
+    /// Add both directions of a token pair of a pool
+    fn add_token_pair(&mut self, pool_id: &Bytes, pair: &[Bytes; 2]) -> Result<PoolInfo> {
+        // Add tokens to the graph (or get existing IDs)
+        let token_id_0 = self.add_token(pair[0].clone())?;
+        let token_id_1 = self.add_token(pair[1].clone())?;
+        let token_ids = [token_id_0, token_id_1];
+
+        // Add the pool
+        let pool_ids = self.add_pool(pool_id.clone(), token_ids)?;
+
+        Ok(PoolInfo {
+            token_ids,
+            pool_ids,
+        })
+    }
+
+    /// Add the token pairs of a component, deferring pairs of large pools that cannot be part of a cycle yet
+    ///
+    /// If a pair fails to add, the pairs already added for the component are removed again.
+    fn add_component_pairs(&mut self, pool_id: &Bytes, token_addresses: &[Bytes]) -> Result<Vec<PoolInfo>> {
+        let token_pairs = Self::generate_token_pairs(token_addresses);
+        let lazy = token_addresses.len() > EAGER_PAIR_TOKEN_LIMIT && !self.source_tokens.is_empty();
+        let (eager_pairs, deferred_pairs): (Vec<_>, Vec<_>) = token_pairs
+            .into_iter()
+            .partition(|pair| !lazy || pair.iter().any(|token| self.source_tokens.contains(token)));
+
+        let pools_before = self.pools.len();
+        let mut pool_infos = Vec::with_capacity(eager_pairs.len());
+        for pair in eager_pairs {
+            match self.add_token_pair(pool_id, &pair) {
+                Ok(pool_info) => pool_infos.push(pool_info),
+                Err(e) => {
+                    // Removing the last pool first keeps the IDs of all other pools
+                    while self.pools.len() > pools_before {
+                        let token_pair = self.pools[self.pools.len() - 1].tokens();
+                        self.remove_pool_by_address_and_tokens(pool_id, &token_pair)?;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        if !deferred_pairs.is_empty() {
+            self.deferred_pairs.insert(pool_id.clone(), deferred_pairs);
+        }
+        Ok(pool_infos)
+    }
+
+    /// Whether the exchange filter accepts a protocol component
+    fn accepts_component(&self, pool_id: &Bytes, pool_component: &ProtocolComponent) -> bool {
+        self.exchange_filter
+            .as_ref()
+            .is_none_or(|exchange_filter| exchange_filter.allows(&pool_component.protocol_system, pool_id))
+    }
+
     /// Generate all possible 2-token pairs from a list of token addresses
     fn generate_token_pairs(token_addresses: &[Bytes]) -> Vec<[Bytes; 2]> {
         let mut pairs = Vec::new();
@@ -449,6 +1125,10 @@ impl TradingGraph {
         // Add the pool
         self.pools.push(LiquidityPool::new(address, token_ids));
 
+        let handle = PoolHandle::next();
+        self.pool_handles.push(handle);
+        self.pool_handle_ids.insert(handle, pool_id);
+
         Ok(pool_id)
     }
 
@@ -465,6 +1145,20 @@ impl TradingGraph {
             })
             .ok_or_else(|| GraphError::EdgeNotFound { address: address.clone() })?;
 
+        // Remove from the token pair mapping, before the moved pool takes over the ID
+        if let Some(pool_list) = self.token_pair_to_pools.get_mut(token_pair) {
+            pool_list.retain(|&id| id != pool_id_to_remove);
+            
+            // If no more pools between these tokens in either direction, remove neighbor relationship
+            if pool_list.is_empty() {
+                self.token_pair_to_pools.remove(token_pair);
+                if !self.token_pair_to_pools.contains_key(&[token_pair[1], token_pair[0]]) {
+                    self.tokens[token_pair[0]].remove_neighbor(token_pair[1]);
+                    self.tokens[token_pair[1]].remove_neighbor(token_pair[0]);
+                }
+            }
+        }
+
         // Handle swap-remove index updates
         let last_pool_id = self.pools.len() - 1;
         if pool_id_to_remove != last_pool_id {
@@ -478,20 +1172,13 @@ impl TradingGraph {
             }
         }
 
-        // Remove from the token pair mapping
-        if let Some(pool_list) = self.token_pair_to_pools.get_mut(token_pair) {
-            pool_list.retain(|&id| id != pool_id_to_remove);
-            
-            // If no more pools between these tokens, remove neighbor relationship
-            if pool_list.is_empty() {
-                self.token_pair_to_pools.remove(token_pair);
-                self.tokens[token_pair[0]].remove_neighbor(token_pair[1]);
-                self.tokens[token_pair[1]].remove_neighbor(token_pair[0]);
-            }
-        }
-
         // Remove the pool
         self.pools.swap_remove(pool_id_to_remove);
+        let removed_handle = self.pool_handles.swap_remove(pool_id_to_remove);
+        self.pool_handle_ids.remove(&removed_handle);
+        if let Some(&moved_handle) = self.pool_handles.get(pool_id_to_remove) {
+            self.pool_handle_ids.insert(moved_handle, pool_id_to_remove);
+        }
 
         Ok(())
     }
@@ -3,13 +3,22 @@
 //! This module provides a specialized graph data structure for modeling token trading networks
 //! where nodes represent tokens/assets and edges represent liquidity pools or trading pairs.
 //! The graph is optimized for arbitrage path discovery and execution.
+//!
+//! `SharedTradingGraph` shares a graph between threads with copy-on-write
+//! snapshots, so path discovery reads a consistent graph while updates apply.
+//! With the `synthetic` feature, `synthetic` generates reproducible markets
+//! for benchmarks.
 
 pub mod types;
 pub mod core;
+pub mod shared;
+#[cfg(any(test, feature = "synthetic"))]
+pub mod synthetic;
 
 // Re-export all public types for convenience
-pub use types::{TokenId, PoolId, PoolInfo, TokenNode, LiquidityPool};
-pub use core::TradingGraph;
+pub use types::{GraphLoadStatistics, PoolDirection, TokenId, PoolId, TokenHandle, PoolHandle, PoolInfo, TokenMetadata, TokenNode, LiquidityPool};
+pub use core::{TradingGraph, DEFAULT_MAX_POOL_TOKENS};
+pub use shared::SharedTradingGraph;
 
 #[cfg(test)]
 mod tests {
@@ -454,4 +463,257 @@ mod tests {
         assert_eq!(pool.token_in_id(), usdc_id);
         assert_eq!(pool.token_out_id(), weth_id);
     }
+
+    #[test]
+    fn test_token_metadata_from_components() {
+        let token = |address: &str, symbol: &str, decimals: usize| tycho_simulation::models::Token {
+            decimals,
+            ..crate::testing::token(&Bytes::from_str(address).unwrap(), symbol)
+        };
+        let component = |pool: &str, tokens: Vec<tycho_simulation::models::Token>| {
+            tycho_simulation::protocol::models::ProtocolComponent {
+                tokens,
+                ..crate::testing::component(&Bytes::from_str(pool).unwrap(), &[])
+            }
+        };
+
+        // A copycat WETH with a single pool loses the symbol lookup to the canonical one
+        let mut graph = TradingGraph::new();
+        for (pool, other) in [("0x1001", token("0x0002", "USDC", 6)), ("0x1002", token("0x0003", "DAI", 18))] {
+            let weth = token("0x0001", "WETH", 18);
+            graph.add_protocol_component(Bytes::from_str(pool).unwrap(), component(pool, vec![weth, other])).unwrap();
+        }
+        let copycat = component("0x1003", vec![token("0x00ff", "WETH", 18), token("0x0002", "USDC", 6)]);
+        graph.add_protocol_component(Bytes::from_str("0x1003").unwrap(), copycat).unwrap();
+
+        let weth = graph.find_token_id(&Bytes::from_str("0x0001").unwrap()).unwrap();
+        assert_eq!(graph.tokens_by_symbol("weth").len(), 2);
+        assert_eq!(graph.token_by_symbol("weth"), Some(weth));
+        assert_eq!(graph.get_token(weth).unwrap().decimals(), Some(18));
+        let usdc = graph.token_by_symbol("USDC").unwrap();
+        assert_eq!(graph.get_token(usdc).unwrap().decimals(), Some(6));
+        assert_eq!(graph.token_by_symbol("WBTC"), None);
+
+        // Tokens added without metadata can get it later
+        let token_id = graph.add_token(Bytes::from_str("0x0004").unwrap()).unwrap();
+        assert!(graph.get_token(token_id).unwrap().metadata().is_none());
+        let metadata = TokenMetadata::from(&token("0x0004", "WBTC", 8));
+        graph.set_token_metadata(token_id, metadata).unwrap();
+        assert_eq!(graph.token_by_symbol("WBTC"), Some(token_id));
+    }
+
+    #[test]
+    fn test_directional_pool_queries() {
+        let mut graph = TradingGraph::new();
+        let token_a = graph.add_token(Bytes::from_str("0x0001").unwrap()).unwrap();
+        let token_b = graph.add_token(Bytes::from_str("0x0002").unwrap()).unwrap();
+        let token_c = graph.add_token(Bytes::from_str("0x0003").unwrap()).unwrap();
+        let [a_to_b, b_to_a] = graph.add_pool(Bytes::from_str("0x1001").unwrap(), [token_a, token_b]).unwrap();
+
+        assert_eq!(graph.pools_from_to(token_a, token_b), &[a_to_b]);
+        assert_eq!(graph.pools_from_to(token_b, token_a), &[b_to_a]);
+        assert!(graph.pools_from_to(token_a, token_c).is_empty());
+
+        assert_eq!(
+            graph.pools_any_direction(token_b, token_a),
+            vec![(b_to_a, PoolDirection::Forward), (a_to_b, PoolDirection::Reverse)]
+        );
+        assert!(graph.pools_any_direction(token_a, token_c).is_empty());
+        assert_eq!(PoolDirection::Forward.reversed(), PoolDirection::Reverse);
+    }
+
+    #[test]
+    fn test_large_pool_pairs_are_deferred_until_reachable() {
+        let component = |pool: &Bytes, tokens: &[&str]| {
+            let tokens: Vec<Bytes> = tokens.iter().map(|address| Bytes::from_str(address).unwrap()).collect();
+            crate::testing::component(pool, &tokens)
+        };
+
+        let source = Bytes::from_str("0x00ff").unwrap();
+        let mut graph = TradingGraph::new()
+            .with_max_pool_tokens(8)
+            .with_source_tokens([source]);
+
+        // A six-token pool without the source token: all 15 pairs are deferred
+        let weighted = Bytes::from_str("0x1001").unwrap();
+        let tokens = ["0x0001", "0x0002", "0x0003", "0x0004", "0x0005", "0x0006"];
+        let pool_infos = graph.add_protocol_component(weighted.clone(), component(&weighted, &tokens)).unwrap();
+        assert!(pool_infos.is_empty());
+        assert_eq!(graph.deferred_pair_count(), 15);
+
+        // Connecting two of its tokens to the source token makes their pair reachable
+        let pool_a = Bytes::from_str("0x2001").unwrap();
+        let pool_b = Bytes::from_str("0x2002").unwrap();
+        graph.add_protocol_component(pool_a.clone(), component(&pool_a, &["0x00ff", "0x0001"])).unwrap();
+        let pool_infos = graph.add_protocol_component(pool_b.clone(), component(&pool_b, &["0x00ff", "0x0002"])).unwrap();
+        assert_eq!(pool_infos.len(), 2);
+        assert_eq!(graph.deferred_pair_count(), 14);
+
+        let token_1 = graph.find_token_id(&Bytes::from_str("0x0001").unwrap()).unwrap();
+        let token_2 = graph.find_token_id(&Bytes::from_str("0x0002").unwrap()).unwrap();
+        let pools = graph.pools_between_tokens([token_1, token_2]).unwrap();
+        assert_eq!(graph.get_pool(pools[0]).unwrap().address(), &weighted);
+
+        assert!(graph.remove_protocol_component(&weighted).is_ok());
+        assert_eq!(graph.deferred_pair_count(), 0);
+
+        // Without a raised cap, the default of four tokens still applies
+        let mut graph = TradingGraph::new();
+        assert_eq!(graph.max_pool_tokens(), DEFAULT_MAX_POOL_TOKENS);
+        assert!(graph.add_protocol_component(weighted.clone(), component(&weighted, &tokens)).is_err());
+    }
+
+    #[test]
+    fn test_exchange_filter_rejects_components() {
+        use crate::config::{ExchangeConfig, ExchangeFilter};
+
+        let tokens: Vec<Bytes> = ["0x0001", "0x0002"].iter().map(|address| Bytes::from_str(address).unwrap()).collect();
+        let component = |pool: &Bytes, protocol_system: &str| tycho_simulation::protocol::models::ProtocolComponent {
+            protocol_system: protocol_system.to_string(),
+            ..crate::testing::component(pool, &tokens)
+        };
+
+        let denied = Bytes::from_str("0x1001").unwrap();
+        let allowed = Bytes::from_str("0x1002").unwrap();
+        let filter = ExchangeFilter::new(0.0)
+            .with_exchange("uniswap_v2", ExchangeConfig::new().with_denylist([denied.clone()]))
+            .with_exchange("uniswap_v3", ExchangeConfig::disabled());
+        let mut graph = TradingGraph::new().with_exchange_filter(filter);
+
+        let pool_infos = graph.add_protocol_component(denied.clone(), component(&denied, "uniswap_v2")).unwrap();
+        assert!(pool_infos.is_empty());
+        let pool_infos = graph.add_protocol_component(allowed.clone(), component(&allowed, "uniswap_v3")).unwrap();
+        assert!(pool_infos.is_empty());
+        assert_eq!(graph.pool_count(), 0);
+
+        let pool_infos = graph.add_protocol_component(allowed.clone(), component(&allowed, "uniswap_v2")).unwrap();
+        assert_eq!(pool_infos.len(), 1);
+        assert_eq!(graph.pool_count(), 1);
+    }
+
+    #[test]
+    fn test_failed_components_leave_no_pairs_behind() {
+        let pool = Bytes::from_str("0x1001").unwrap();
+        let tokens: Vec<Bytes> = ["0x0001", "0x0002", "0x0003"].iter().map(|address| Bytes::from_str(address).unwrap()).collect();
+        let mut graph = TradingGraph::new();
+
+        // The pool already trades its last pair, so the component fails after adding the others
+        let token_b = graph.add_token(tokens[1].clone()).unwrap();
+        let token_c = graph.add_token(tokens[2].clone()).unwrap();
+        graph.add_pool(pool.clone(), [token_b, token_c]).unwrap();
+
+        let component = crate::testing::component(&pool, &tokens);
+        let (pool_infos, statistics) = graph.add_protocol_components([(pool.clone(), &component)]).unwrap();
+        assert!(pool_infos.is_empty());
+        assert_eq!(statistics.rejected, 1);
+
+        let token_a = graph.find_token_id(&tokens[0]).unwrap();
+        assert!(graph.pools_from_to(token_a, token_b).is_empty());
+        assert!(graph.pools_from_to(token_a, token_c).is_empty());
+        assert_eq!(graph.pools_from_to(token_b, token_c).len(), 1);
+    }
+
+    #[test]
+    fn test_bulk_load_matches_incremental_load() {
+        use crate::builders::TradingGraphBuilder;
+        use crate::testing::component;
+
+        let address = |value: u32| Bytes::from(value.to_be_bytes().to_vec());
+        let source = address(0);
+        // A snapshot of two-token pools around the source token, one duplicate and one without tokens
+        let mut components: Vec<(Bytes, tycho_simulation::protocol::models::ProtocolComponent)> = (1..=50u32)
+            .map(|index| {
+                let pool = address(1_000 + index);
+                let tokens = [address(index % 10), address(index % 7 + 10)];
+                (pool.clone(), component(&pool, &tokens))
+            })
+            .collect();
+        components.push(components[0].clone());
+        components.push((address(2_000), component(&address(2_000), &[])));
+
+        let mut incremental = TradingGraph::new().with_source_tokens([source.clone()]);
+        for (pool, pool_component) in &components {
+            let _ = incremental.add_protocol_component(pool.clone(), pool_component.clone());
+        }
+
+        let (bulk, statistics) = TradingGraphBuilder::from_components(components)
+            .with_source_tokens([source])
+            .build_with_statistics()
+            .unwrap();
+        assert_eq!((statistics.components, statistics.added, statistics.rejected), (52, 50, 2));
+        assert_eq!(statistics.tokens_added, 17);
+        assert_eq!(statistics.pairs_added, 50);
+        assert_eq!(bulk.token_count(), incremental.token_count());
+        assert_eq!(bulk.pool_count(), incremental.pool_count());
+        assert!(bulk.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_remove_token_keeps_moved_token_consistent() {
+        let mut graph = TradingGraph::new();
+        let tokens: Vec<TokenId> = (0..4u8).map(|i| graph.add_token(Bytes::from(vec![i])).unwrap()).collect();
+        graph.add_pool(Bytes::from(vec![0x10]), [tokens[0], tokens[1]]).unwrap();
+        graph.add_pool(Bytes::from(vec![0x11]), [tokens[2], tokens[3]]).unwrap();
+        graph.add_pool(Bytes::from(vec![0x12]), [tokens[3], tokens[2]]).unwrap();
+
+        // The last pool moves into the ID of a removed pool of the same token pair
+        graph.remove_pool_by_address(&Bytes::from(vec![0x11])).unwrap();
+        graph.check_invariants().unwrap();
+
+        // The last token takes over the removed ID together with its pools
+        let removed_handle = graph.token_handle(tokens[0]).unwrap();
+        let moved_handle = graph.token_handle(tokens[3]).unwrap();
+        graph.remove_token(tokens[0]).unwrap();
+        graph.check_invariants().unwrap();
+        let moved = graph.find_token_id(&Bytes::from(vec![3])).unwrap();
+        assert_eq!(moved, tokens[0]);
+        assert_eq!(graph.resolve_token(moved_handle).unwrap(), moved);
+        assert!(graph.resolve_token(removed_handle).is_err());
+        assert_eq!(graph.pools_between_tokens([tokens[2], moved]).unwrap().len(), 1);
+        assert!(graph.token_neighbors(tokens[1]).unwrap().is_empty());
+    }
+
+    #[derive(Debug, Clone)]
+    enum GraphOperation {
+        AddPool { pool: u8, tokens: [u8; 2] },
+        RemovePool { pool: u8 },
+        RemoveToken { token: u8 },
+    }
+
+    fn graph_operation() -> impl proptest::strategy::Strategy<Value = GraphOperation> {
+        use proptest::prelude::*;
+        prop_oneof![
+            3 => (0..16u8, 0..8u8, 0..8u8).prop_filter("distinct tokens", |(_, a, b)| a != b)
+                .prop_map(|(pool, a, b)| GraphOperation::AddPool { pool, tokens: [a, b] }),
+            1 => (0..16u8).prop_map(|pool| GraphOperation::RemovePool { pool }),
+            1 => (0..8u8).prop_map(|token| GraphOperation::RemoveToken { token }),
+        ]
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_invariants_hold_after_arbitrary_operations(
+            operations in proptest::collection::vec(graph_operation(), 1..64)
+        ) {
+            let mut graph = TradingGraph::new();
+            for operation in operations {
+                match operation {
+                    GraphOperation::AddPool { pool, tokens } => {
+                        let token_ids = tokens.map(|token| graph.add_token(Bytes::from(vec![token])).unwrap());
+                        let _ = graph.add_pool(Bytes::from(vec![0x10, pool]), token_ids);
+                    }
+                    GraphOperation::RemovePool { pool } => {
+                        let _ = graph.remove_pool_by_address(&Bytes::from(vec![0x10, pool]));
+                    }
+                    GraphOperation::RemoveToken { token } => {
+                        if let Ok(token_id) = graph.find_token_id(&Bytes::from(vec![token])) {
+                            graph.remove_token(token_id).unwrap();
+                            proptest::prop_assert!(graph.find_token_id(&Bytes::from(vec![token])).is_err());
+                        }
+                    }
+                }
+                proptest::prop_assert!(graph.check_invariants().is_ok(), "{:?}", graph.check_invariants());
+            }
+        }
+    }
 }
@@ -0,0 +1,127 @@
+//! A trading graph shared between path discovery and block updates.
+//!
+//! `TradingGraph` itself is a plain data structure mutated through `&mut self`.
+//! Sharing it behind a read-write lock makes path discovery wait for block
+//! updates and block updates wait for long path searches. `SharedTradingGraph`
+//! uses copy-on-write snapshots instead: readers take an immutable `Arc`
+//! snapshot without blocking, and writers apply their changes to a copy that
+//! replaces the current graph once complete. Readers therefore always see a
+//! consistent graph, either before or after an update, never in between.
+
+use super::core::TradingGraph;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// An internally synchronized trading graph with copy-on-write snapshots.
+#[derive(Debug)]
+pub struct SharedTradingGraph {
+    current: RwLock<Arc<TradingGraph>>,
+    /// Serializes writers, so concurrent updates are not lost
+    writer: Mutex<u64>,
+}
+
+impl SharedTradingGraph {
+    /// Share a trading graph.
+    pub fn new(graph: TradingGraph) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(graph)),
+            writer: Mutex::new(0),
+        }
+    }
+
+    /// An immutable snapshot of the current graph.
+    ///
+    /// The snapshot stays valid and unchanged while updates are applied.
+    pub fn snapshot(&self) -> Arc<TradingGraph> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Apply changes to the graph.
+    ///
+    /// The changes are applied to a copy of the graph, which replaces the
+    /// current graph once `update` returns. Updates are applied one at a time,
+    /// so batch the changes of a block into a single update.
+    ///
+    /// # Arguments
+    ///
+    /// * `update` - Mutates the graph and returns a value passed through to the caller
+    pub fn update<T>(&self, update: impl FnOnce(&mut TradingGraph) -> T) -> T {
+        let mut version = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut graph = TradingGraph::clone(&self.snapshot());
+        let result = update(&mut graph);
+
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(graph);
+        *version += 1;
+        result
+    }
+
+    /// Replace the graph, e.g. after rebuilding it from a snapshot.
+    pub fn replace(&self, graph: TradingGraph) {
+        self.update(|current| *current = graph);
+    }
+
+    /// The number of updates applied since the graph was shared.
+    pub fn version(&self) -> u64 {
+        *self.writer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for SharedTradingGraph {
+    fn default() -> Self {
+        Self::new(TradingGraph::new())
+    }
+}
+
+impl From<TradingGraph> for SharedTradingGraph {
+    fn from(graph: TradingGraph) -> Self {
+        Self::new(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use tycho_common::Bytes;
+
+    #[test]
+    fn test_snapshots_are_unaffected_by_updates() {
+        let shared = SharedTradingGraph::default();
+        let before = shared.snapshot();
+
+        let token_id = shared.update(|graph| graph.add_token(Bytes::from_str("0x0001").unwrap()));
+        assert!(token_id.is_ok());
+        assert_eq!(before.token_count(), 0);
+        assert_eq!(shared.snapshot().token_count(), 1);
+        assert_eq!(shared.version(), 1);
+
+        shared.replace(TradingGraph::new());
+        assert_eq!(shared.snapshot().token_count(), 0);
+        assert_eq!(shared.version(), 2);
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        let shared = Arc::new(SharedTradingGraph::default());
+        let writers: Vec<_> = (0..4u8)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                std::thread::spawn(move || {
+                    for j in 0..25u8 {
+                        shared.update(|graph| graph.add_token(Bytes::from(vec![i, j])).unwrap());
+                        assert!(shared.snapshot().token_count() > 0);
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(shared.snapshot().token_count(), 100);
+        assert_eq!(shared.version(), 100);
+    }
+}
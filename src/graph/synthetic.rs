@@ -0,0 +1,411 @@
+//! Synthetic markets for benchmarks and tests.
+//!
+//! Performance work on the graph, path discovery and optimization needs market
+//! topologies of realistic size that are identical from run to run.
+//! `SyntheticMarket` generates tokens and constant-product pools from a seed.
+//! A few hub tokens, like WETH and the major stablecoins on mainnet, take part
+//! in most pools, while the remaining pools connect long-tail tokens. Reserves
+//! follow a common price per token with a small deviation per pool, so cycles
+//! have small and varying profits.
+//!
+//! This module is available with the `synthetic` feature.
+
+use crate::errors::Result;
+use crate::graph::TradingGraph;
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tycho_common::Bytes;
+use tycho_simulation::{
+    models::{Balances, Token},
+    protocol::{
+        errors::{SimulationError, TransitionError},
+        models::{GetAmountOutResult, ProtocolComponent},
+        state::ProtocolSim,
+    },
+};
+
+/// Protocol system of the generated pools, encodable by the Tycho router.
+const PROTOCOL_SYSTEM: &str = "uniswap_v2";
+
+/// Fee of the generated pools, in basis points.
+const FEE_BPS: u32 = 30;
+
+/// Gas of a simulated swap.
+const SWAP_GAS: u32 = 120_000;
+
+/// Shape of a generated market.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyntheticMarketConfig {
+    token_count: usize,
+    pool_count: usize,
+    hub_count: usize,
+    hub_share: f64,
+    max_price_deviation_bps: u32,
+    seed: u64,
+}
+
+impl SyntheticMarketConfig {
+    /// A market of 1,000 tokens and 5,000 pools with 5 hub tokens in 80% of the pools.
+    pub fn new() -> Self {
+        Self {
+            token_count: 1_000,
+            pool_count: 5_000,
+            hub_count: 5,
+            hub_share: 0.8,
+            max_price_deviation_bps: 50,
+            seed: 0,
+        }
+    }
+
+    /// Set the number of tokens, at least two.
+    pub fn with_token_count(mut self, token_count: usize) -> Self {
+        self.token_count = token_count.max(2);
+        self
+    }
+
+    /// Set the number of pools.
+    pub fn with_pool_count(mut self, pool_count: usize) -> Self {
+        self.pool_count = pool_count;
+        self
+    }
+
+    /// Set the number of hub tokens, at least one.
+    pub fn with_hub_count(mut self, hub_count: usize) -> Self {
+        self.hub_count = hub_count.max(1);
+        self
+    }
+
+    /// Set the share of pools trading a hub token, between 0 and 1.
+    pub fn with_hub_share(mut self, hub_share: f64) -> Self {
+        self.hub_share = hub_share.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the largest deviation of a pool's price from the common price, in basis points.
+    pub fn with_max_price_deviation_bps(mut self, max_price_deviation_bps: u32) -> Self {
+        self.max_price_deviation_bps = max_price_deviation_bps;
+        self
+    }
+
+    /// Set the seed, equal seeds generate equal markets.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Default for SyntheticMarketConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A generated market of tokens and constant-product pools.
+#[derive(Debug, Clone)]
+pub struct SyntheticMarket {
+    /// The generated tokens, hub tokens first
+    pub tokens: Vec<Token>,
+    /// The protocol components of all pools, keyed by pool address
+    pub components: HashMap<Bytes, Arc<ProtocolComponent>>,
+    /// The simulation states of all pools, keyed by pool address
+    pub simulations: HashMap<Bytes, Arc<dyn ProtocolSim>>,
+    pools: Vec<Bytes>,
+    hub_count: usize,
+}
+
+impl SyntheticMarket {
+    /// Generate a market.
+    pub fn generate(config: &SyntheticMarketConfig) -> Self {
+        let mut rng = SplitMix64(config.seed);
+        let hub_count = config.hub_count.min(config.token_count);
+
+        let tokens: Vec<Token> = (0..config.token_count)
+            .map(|index| Token {
+                address: address(0x01, index as u64),
+                symbol: format!("TKN{}", index),
+                decimals: 18,
+                gas: BigUint::from(0u32),
+            })
+            .collect();
+        // Prices in a common numeraire, between 0.01 and 100
+        let prices: Vec<f64> = (0..config.token_count)
+            .map(|_| 10f64.powf(rng.next_f64() * 4.0 - 2.0))
+            .collect();
+
+        let mut components = HashMap::with_capacity(config.pool_count);
+        let mut simulations: HashMap<Bytes, Arc<dyn ProtocolSim>> = HashMap::with_capacity(config.pool_count);
+        let mut pools = Vec::with_capacity(config.pool_count);
+
+        for pool_index in 0..config.pool_count {
+            let first = if rng.next_f64() < config.hub_share {
+                rng.next_index(hub_count)
+            } else {
+                rng.next_index(config.token_count)
+            };
+            let mut second = rng.next_index(config.token_count - 1);
+            if second >= first {
+                second += 1;
+            }
+            let (index0, index1) = if tokens[first].address < tokens[second].address {
+                (first, second)
+            } else {
+                (second, first)
+            };
+
+            // Pools hold between 10^4 and 10^8 units of the numeraire per side
+            let value = 10f64.powf(4.0 + rng.next_f64() * 4.0);
+            let deviation = 1.0 + (rng.next_f64() * 2.0 - 1.0) * config.max_price_deviation_bps as f64 / 10_000.0;
+            let reserve0 = value / prices[index0] * 1e18;
+            let reserve1 = value / prices[index1] * deviation * 1e18;
+
+            let pool = address(0x02, pool_index as u64);
+            components.insert(pool.clone(), Arc::new(component(&pool, &tokens[index0], &tokens[index1])));
+            simulations.insert(
+                pool.clone(),
+                Arc::new(ConstantProductSim::new(
+                    tokens[index0].address.clone(),
+                    tokens[index1].address.clone(),
+                    BigUint::from(reserve0 as u128),
+                    BigUint::from(reserve1 as u128),
+                )),
+            );
+            pools.push(pool);
+        }
+
+        Self {
+            tokens,
+            components,
+            simulations,
+            pools,
+            hub_count,
+        }
+    }
+
+    /// The addresses of the hub tokens.
+    pub fn hub_tokens(&self) -> Vec<Bytes> {
+        self.tokens[..self.hub_count].iter().map(|token| token.address.clone()).collect()
+    }
+
+    /// The addresses of all pools, in generation order.
+    pub fn pool_addresses(&self) -> &[Bytes] {
+        &self.pools
+    }
+
+    /// The protocol components of all pools, in generation order.
+    pub fn ordered_components(&self) -> impl Iterator<Item = (&Bytes, &ProtocolComponent)> {
+        self.pools.iter().map(|pool| (pool, self.components[pool].as_ref()))
+    }
+
+    /// Build a trading graph of all pools.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a pool cannot be added to the graph.
+    pub fn build_graph(&self) -> Result<TradingGraph> {
+        let mut graph = TradingGraph::new();
+        for (pool, component) in self.ordered_components() {
+            graph.add_protocol_component(pool.clone(), component.clone())?;
+        }
+        Ok(graph)
+    }
+}
+
+/// A two-token constant-product pool, priced like Uniswap V2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstantProductSim {
+    token0: Bytes,
+    token1: Bytes,
+    reserve0: BigUint,
+    reserve1: BigUint,
+    fee_bps: u32,
+}
+
+impl ConstantProductSim {
+    /// Create a pool with a fee of 0.3%.
+    pub fn new(token0: Bytes, token1: Bytes, reserve0: BigUint, reserve1: BigUint) -> Self {
+        Self {
+            token0,
+            token1,
+            reserve0,
+            reserve1,
+            fee_bps: FEE_BPS,
+        }
+    }
+
+    /// Set the fee, in basis points.
+    pub fn with_fee_bps(mut self, fee_bps: u32) -> Self {
+        self.fee_bps = fee_bps.min(10_000);
+        self
+    }
+
+    /// The reserves of the input and output token of a swap.
+    fn reserves(&self, token_in: &Bytes, token_out: &Bytes) -> std::result::Result<(&BigUint, &BigUint), SimulationError> {
+        if *token_in == self.token0 && *token_out == self.token1 {
+            Ok((&self.reserve0, &self.reserve1))
+        } else if *token_in == self.token1 && *token_out == self.token0 {
+            Ok((&self.reserve1, &self.reserve0))
+        } else {
+            Err(SimulationError::InvalidInput(format!("Pool does not trade {} for {}", token_in, token_out), None))
+        }
+    }
+}
+
+impl ProtocolSim for ConstantProductSim {
+    fn clone_box(&self) -> Box<dyn ProtocolSim> {
+        Box::new(self.clone())
+    }
+
+    fn fee(&self) -> f64 {
+        self.fee_bps as f64 / 10_000.0
+    }
+
+    fn spot_price(&self, base: &Token, quote: &Token) -> std::result::Result<f64, SimulationError> {
+        let (reserve_in, reserve_out) = self.reserves(&base.address, &quote.address)?;
+        let reserve_in = crate::utils::convert::biguint_to_f64(reserve_in);
+        let reserve_out = crate::utils::convert::biguint_to_f64(reserve_out);
+        Ok(reserve_out / reserve_in * (1.0 - self.fee()))
+    }
+
+    fn get_amount_out(
+        &self,
+        amount_in: BigUint,
+        token_in: &Token,
+        token_out: &Token,
+    ) -> std::result::Result<GetAmountOutResult, SimulationError> {
+        let (reserve_in, reserve_out) = self.reserves(&token_in.address, &token_out.address)?;
+        let amount_in_with_fee = amount_in * (10_000 - self.fee_bps);
+        let amount_out = &amount_in_with_fee * reserve_out / (reserve_in * 10_000u32 + &amount_in_with_fee);
+        if amount_out == BigUint::from(0u32) {
+            return Err(SimulationError::RecoverableError("Swap yields no output".to_string()));
+        }
+
+        Ok(GetAmountOutResult {
+            amount: amount_out,
+            gas: BigUint::from(SWAP_GAS),
+            new_state: Box::new(self.clone()),
+        })
+    }
+
+    fn get_limits(&self, token_in: Bytes, token_out: Bytes) -> std::result::Result<(BigUint, BigUint), SimulationError> {
+        let (reserve_in, reserve_out) = self.reserves(&token_in, &token_out)?;
+        Ok((reserve_in.clone(), reserve_out.clone()))
+    }
+
+    fn delta_transition(
+        &mut self,
+        _delta: tycho_common::dto::ProtocolStateDelta,
+        _tokens: &HashMap<Bytes, Token>,
+        _balances: &Balances,
+    ) -> std::result::Result<(), TransitionError<String>> {
+        Ok(())
+    }
+
+    fn as_any(&self) -> &(dyn std::any::Any + 'static) {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut (dyn std::any::Any + 'static) {
+        self
+    }
+
+    fn eq(&self, other: &(dyn ProtocolSim + 'static)) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
+
+/// The component of a generated pool.
+fn component(pool: &Bytes, token0: &Token, token1: &Token) -> ProtocolComponent {
+    ProtocolComponent {
+        id: pool.clone(),
+        address: pool.clone(),
+        protocol_system: PROTOCOL_SYSTEM.to_string(),
+        protocol_type_name: "uniswap_v2_pool".to_string(),
+        chain: tycho_common::models::Chain::Ethereum,
+        tokens: vec![token0.clone(), token1.clone()],
+        contract_ids: vec![pool.clone()],
+        static_attributes: HashMap::new(),
+        created_at: chrono::DateTime::from_timestamp(0, 0).unwrap_or_default().naive_utc(),
+        creation_tx: Bytes::default(),
+    }
+}
+
+/// A 20-byte address made of a kind prefix and an index.
+fn address(kind: u8, index: u64) -> Bytes {
+    let mut bytes = [0u8; 20];
+    bytes[0] = kind;
+    bytes[12..].copy_from_slice(&index.to_be_bytes());
+    Bytes::from(bytes.to_vec())
+}
+
+/// SplitMix64, a small deterministic generator that keeps markets reproducible.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform float in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A uniform index below `bound`.
+    fn next_index(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generates_reproducible_markets() {
+        let config = SyntheticMarketConfig::new()
+            .with_token_count(50)
+            .with_pool_count(200)
+            .with_seed(7);
+        let market = SyntheticMarket::generate(&config);
+        let again = SyntheticMarket::generate(&config);
+
+        assert_eq!(market.pool_addresses().len(), 200);
+        assert_eq!(market.pool_addresses(), again.pool_addresses());
+        for pool in market.pool_addresses() {
+            assert_eq!(market.components[pool].tokens, again.components[pool].tokens);
+            assert!(market.simulations[pool].eq(again.simulations[pool].as_ref()));
+        }
+
+        let graph = market.build_graph().unwrap();
+        assert!(graph.token_count() <= market.tokens.len());
+        assert!(graph.find_token_id(&market.hub_tokens()[0]).is_ok());
+    }
+
+    #[test]
+    fn test_constant_product_pricing() {
+        let token0 = address(0x01, 0);
+        let token1 = address(0x01, 1);
+        let sim = ConstantProductSim::new(
+            token0.clone(),
+            token1.clone(),
+            BigUint::from(1_000_000u32),
+            BigUint::from(2_000_000u32),
+        );
+        let token = |address: &Bytes| Token {
+            address: address.clone(),
+            symbol: "TKN".to_string(),
+            decimals: 18,
+            gas: BigUint::from(0u32),
+        };
+
+        // 1000 * 0.997 * 2,000,000 / (1,000,000 + 997) = 1992
+        let result = sim.get_amount_out(BigUint::from(1000u32), &token(&token0), &token(&token1)).unwrap();
+        assert_eq!(result.amount, BigUint::from(1992u32));
+        assert!(sim.get_amount_out(BigUint::from(1000u32), &token(&token0), &token(&address(0x01, 2))).is_err());
+    }
+}
@@ -2,19 +2,88 @@
 //!
 //! This module contains the fundamental types used throughout the graph system:
 //! - Type aliases for identifiers
-//! - Token node representation
+//! - Stable handles of tokens and pools
+//! - Token node representation and token metadata
 //! - Liquidity pool representation
 //! - Pool information structures
+//! - Pool directions relative to a token pair
 
+use num_bigint::BigUint;
 use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tycho_common::Bytes;
+use tycho_simulation::models::Token;
 
 /// Type alias for token identifiers within the graph
+///
+/// Token IDs are positions in the graph's token vector. Removing a token moves
+/// the last token into its position, so IDs kept across removals may refer to
+/// another token afterwards. Keep a [`TokenHandle`] instead.
 pub type TokenId = usize;
 
 /// Type alias for pool identifiers within the graph
+///
+/// Like token IDs, pool IDs are positions that change when pools are removed.
+/// Keep a [`PoolHandle`] instead.
 pub type PoolId = usize;
 
+/// Source of handles, shared by all graphs so handles of rebuilt graphs never collide
+static NEXT_HANDLE: AtomicU64 = AtomicU64::new(0);
+
+fn next_handle() -> u64 {
+    NEXT_HANDLE.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A stable identifier of a token in a trading graph.
+///
+/// A handle is assigned when a token is added and keeps referring to that token
+/// while other tokens are removed. Once the token is removed, the handle no
+/// longer resolves, and it is never assigned again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TokenHandle(u64);
+
+impl TokenHandle {
+    pub(crate) fn next() -> Self {
+        Self(next_handle())
+    }
+
+    /// The raw value of the handle.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for TokenHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "token#{}", self.0)
+    }
+}
+
+/// A stable identifier of a directed pool in a trading graph.
+///
+/// Like a [`TokenHandle`], it keeps referring to its pool while other pools are
+/// removed and never resolves again once the pool is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PoolHandle(u64);
+
+impl PoolHandle {
+    pub(crate) fn next() -> Self {
+        Self(next_handle())
+    }
+
+    /// The raw value of the handle.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for PoolHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pool#{}", self.0)
+    }
+}
+
 /// Information about a pool insertion operation
 #[derive(Debug, Clone)]
 pub struct PoolInfo {
@@ -24,16 +93,80 @@ pub struct PoolInfo {
     pub pool_ids: [PoolId; 2],
 }
 
+/// Direction of a directed pool relative to a queried token pair `[a, b]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PoolDirection {
+    /// The pool trades `a` for `b`
+    Forward,
+    /// The pool trades `b` for `a`
+    Reverse,
+}
+
+impl PoolDirection {
+    /// The opposite direction
+    pub fn reversed(self) -> Self {
+        match self {
+            PoolDirection::Forward => PoolDirection::Reverse,
+            PoolDirection::Reverse => PoolDirection::Forward,
+        }
+    }
+}
+
+/// Statistics of a bulk load of protocol components into a graph
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphLoadStatistics {
+    /// Number of components passed to the load
+    pub components: usize,
+    /// Components whose pairs were added or deferred
+    pub added: usize,
+    /// Components excluded by the exchange filter
+    pub filtered: usize,
+    /// Components with an invalid token count or failing to add
+    pub rejected: usize,
+    /// Tokens new to the graph
+    pub tokens_added: usize,
+    /// Token pairs added as edges in both directions
+    pub pairs_added: usize,
+    /// Token pairs of large pools deferred after the load
+    pub pairs_deferred: usize,
+    /// Wall-clock time of the load
+    pub duration: std::time::Duration,
+}
+
+/// Descriptive metadata of a token, as carried by protocol components
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenMetadata {
+    /// The ticker symbol, e.g. `WETH`
+    pub symbol: String,
+    /// The number of decimals of the token amounts
+    pub decimals: usize,
+    /// The gas cost of a transfer of the token
+    pub gas: BigUint,
+}
+
+impl From<&Token> for TokenMetadata {
+    fn from(token: &Token) -> Self {
+        Self {
+            symbol: token.symbol.clone(),
+            decimals: token.decimals,
+            gas: token.gas.clone(),
+        }
+    }
+}
+
 /// Represents a token/asset node in the trading graph.
 ///
-/// Each token node maintains its address and a set of neighboring tokens
-/// that it can be directly traded with through liquidity pools.
+/// Each token node maintains its address, a set of neighboring tokens
+/// that it can be directly traded with through liquidity pools, and the
+/// token metadata once it is known.
 #[derive(Debug, Clone)]
 pub struct TokenNode {
     /// The on-chain address of this token
     address: Bytes,
     /// Set of token IDs that this token can be directly traded with
     neighbors: HashSet<TokenId>,
+    /// Symbol, decimals and transfer gas of the token, if known
+    metadata: Option<TokenMetadata>,
 }
 
 impl TokenNode {
@@ -42,6 +175,15 @@ impl TokenNode {
         Self {
             address,
             neighbors: HashSet::new(),
+            metadata: None,
+        }
+    }
+
+    /// Create a new token node with the given address and metadata
+    pub fn with_metadata(address: Bytes, metadata: TokenMetadata) -> Self {
+        Self {
+            metadata: Some(metadata),
+            ..Self::new(address)
         }
     }
 
@@ -50,6 +192,26 @@ impl TokenNode {
         &self.address
     }
 
+    /// Get the metadata of this token, if known
+    pub fn metadata(&self) -> Option<&TokenMetadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Get the symbol of this token, if known
+    pub fn symbol(&self) -> Option<&str> {
+        self.metadata.as_ref().map(|metadata| metadata.symbol.as_str())
+    }
+
+    /// Get the decimals of this token, if known
+    pub fn decimals(&self) -> Option<usize> {
+        self.metadata.as_ref().map(|metadata| metadata.decimals)
+    }
+
+    /// Set the metadata of this token (internal use)
+    pub(crate) fn set_metadata(&mut self, metadata: TokenMetadata) {
+        self.metadata = Some(metadata);
+    }
+
     /// Get the neighboring tokens that can be directly traded with this token
     pub fn neighbors(&self) -> &HashSet<TokenId> {
         &self.neighbors
@@ -69,6 +231,13 @@ impl TokenNode {
     pub(crate) fn remove_neighbor(&mut self, token_id: TokenId) {
         self.neighbors.remove(&token_id);
     }
+
+    /// Replace a neighbor token ID, after the neighbor moved to another ID (internal use)
+    pub(crate) fn replace_neighbor(&mut self, from: TokenId, to: TokenId) {
+        if self.neighbors.remove(&from) {
+            self.neighbors.insert(to);
+        }
+    }
 }
 
 /// Represents a liquidity pool/trading pair edge in the trading graph.
@@ -108,6 +277,15 @@ impl LiquidityPool {
     pub fn token_out_id(&self) -> TokenId {
         self.tokens[1]
     }
+
+    /// Replace a token ID, after the token moved to another ID (internal use)
+    pub(crate) fn replace_token(&mut self, from: TokenId, to: TokenId) {
+        for token in self.tokens.iter_mut() {
+            if *token == from {
+                *token = to;
+            }
+        }
+    }
 }
 
 impl PartialEq for LiquidityPool {
@@ -0,0 +1,566 @@
+//! Executor inventory tracking and rebalancing suggestions.
+//!
+//! Every source token of the search is also capital the executor has to hold.
+//! Trades move that capital between tokens, profits accumulate in some of them
+//! and gas drains native ETH, so a token used as trade input slowly depletes
+//! until its paths can no longer be executed.
+//!
+//! The `InventoryManager` reads the executor's balances every block, remembers
+//! the highest balance seen for each token, and tracks how much of a balance was
+//! committed to submitted trades. Once a token falls below its floor, it
+//! suggests how to restore it:
+//!
+//! - **Wrap**: the wrapped native token is low and native ETH can cover it
+//! - **Unwrap**: native ETH for gas is low and the wrapped native token can cover it
+//! - **Top up**: the token has to be transferred to the executor
+//!
+//! Wrap and unwrap suggestions can be turned into transaction requests with
+//! [`InventoryManager::rebalance_request`], so callers may execute them automatically.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::inventory::InventoryManager;
+//!
+//! let inventory = InventoryManager::new(executor)
+//!     .with_wrapped_native(weth)
+//!     .with_floor_percentage(20.0);
+//!
+//! let balances = inventory.refresh(&provider, &source_tokens, block_number).await?;
+//! for suggestion in inventory.suggestions() {
+//!     tracing::warn!(token = %suggestion.token, deficit = %suggestion.deficit, "Inventory below floor");
+//! }
+//! ```
+
+use crate::errors::{InventoryError, Result};
+use crate::rpc::{RateLimiter, RatePermit};
+use crate::simulation::encoding::encode_input;
+use crate::utils::convert;
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, TxKind, U256},
+    providers::{Provider, RootProvider},
+    rpc::types::{TransactionInput, TransactionRequest},
+    sol_types::SolValue,
+};
+use futures::stream::{self, StreamExt};
+use num_bigint::BigUint;
+use num_traits::Zero;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tycho_common::Bytes;
+
+/// Number of balance queries in flight at once.
+const CONCURRENT_BALANCE_QUERIES: usize = 10;
+
+/// Gas limit of a WETH `deposit` or `withdraw` call, with headroom.
+const WRAP_GAS_LIMIT: u64 = 60_000;
+
+/// The tracked balance of one token.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenInventory {
+    /// The balance at the last refresh
+    pub balance: BigUint,
+    /// The highest balance seen since tracking started
+    pub peak_balance: BigUint,
+    /// Input committed to trades submitted since the last refresh
+    pub committed: BigUint,
+    /// The block of the last refresh
+    pub updated_at: u64,
+}
+
+impl TokenInventory {
+    /// Share of the balance committed to trades since the last refresh, from 0.0 to 1.0.
+    pub fn utilization(&self) -> f64 {
+        if self.balance.is_zero() {
+            return if self.committed.is_zero() { 0.0 } else { 1.0 };
+        }
+        (convert::biguint_to_f64(&self.committed) / convert::biguint_to_f64(&self.balance)).min(1.0)
+    }
+}
+
+/// How a depleted token can be restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RebalanceAction {
+    /// Wrap native ETH into the wrapped native token
+    Wrap {
+        /// Amount of native ETH to wrap
+        amount: BigUint,
+    },
+    /// Unwrap the wrapped native token into native ETH for gas
+    Unwrap {
+        /// Amount of the wrapped native token to unwrap
+        amount: BigUint,
+    },
+    /// Transfer the token to the executor
+    TopUp,
+}
+
+/// A token whose balance fell below its floor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebalanceSuggestion {
+    /// The depleted token, the zero address for native ETH
+    pub token: Bytes,
+    /// The current balance
+    pub balance: BigUint,
+    /// The configured floor
+    pub floor: BigUint,
+    /// The amount missing to reach the floor
+    pub deficit: BigUint,
+    /// How the deficit can be covered
+    pub action: RebalanceAction,
+}
+
+/// Tracks executor balances per source token across blocks.
+#[derive(Debug)]
+pub struct InventoryManager {
+    owner: Address,
+    wrapped_native: Option<Address>,
+    floors: HashMap<Bytes, BigUint>,
+    floor_percentage: Option<f64>,
+    gas_floor: Option<BigUint>,
+    tokens: RwLock<HashMap<Bytes, TokenInventory>>,
+    native_balance: RwLock<Option<BigUint>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl InventoryManager {
+    /// Create a manager tracking the balances of `owner` without any floors.
+    pub fn new(owner: Address) -> Self {
+        Self {
+            owner,
+            wrapped_native: None,
+            floors: HashMap::new(),
+            floor_percentage: None,
+            gas_floor: None,
+            tokens: RwLock::new(HashMap::new()),
+            native_balance: RwLock::new(None),
+            rate_limiter: None,
+        }
+    }
+
+    /// Enable wrap and unwrap suggestions through the given wrapped native token.
+    pub fn with_wrapped_native(mut self, wrapped_native: Address) -> Self {
+        self.wrapped_native = Some(wrapped_native);
+        self
+    }
+
+    /// Set an absolute floor for a token, in base units.
+    pub fn with_floor(mut self, token: Bytes, floor: BigUint) -> Self {
+        self.floors.insert(token, floor);
+        self
+    }
+
+    /// Set a floor for tokens without an absolute floor, as a percentage of their peak balance.
+    pub fn with_floor_percentage(mut self, percentage: f64) -> Self {
+        self.floor_percentage = Some(percentage.clamp(0.0, 100.0));
+        self
+    }
+
+    /// Set the native ETH balance kept for gas, in wei.
+    pub fn with_gas_floor(mut self, gas_floor: BigUint) -> Self {
+        self.gas_floor = Some(gas_floor);
+        self
+    }
+
+    /// Make every balance query wait for a limiter shared with other RPC callers.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// The address whose balances are tracked.
+    pub fn owner(&self) -> Address {
+        self.owner
+    }
+
+    /// Read the balances of the given tokens from chain and record them.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The RPC provider used for the `balanceOf` calls
+    /// * `tokens` - The tokens to refresh
+    /// * `block_number` - The block the balances are recorded for
+    ///
+    /// # Returns
+    ///
+    /// The balances read in this refresh, keyed by token
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no balance could be read. Failures of single tokens
+    /// are logged and keep their previous balance.
+    pub async fn refresh(
+        &self,
+        provider: &RootProvider<Ethereum>,
+        tokens: &[Bytes],
+        block_number: u64,
+    ) -> Result<HashMap<Bytes, BigUint>> {
+        let results: Vec<(Bytes, Result<BigUint>)> = stream::iter(tokens.iter().cloned())
+            .map(|token| async move {
+                let balance = self.fetch_balance(provider, &token).await;
+                (token, balance)
+            })
+            .buffer_unordered(CONCURRENT_BALANCE_QUERIES)
+            .collect()
+            .await;
+
+        let mut balances = HashMap::with_capacity(results.len());
+        for (token, result) in results {
+            match result {
+                Ok(balance) => {
+                    tracing::debug!(
+                        token = %token,
+                        balance = %balance,
+                        "Token balance updated"
+                    );
+                    self.record_balance(token.clone(), balance.clone(), block_number);
+                    balances.insert(token, balance);
+                }
+                Err(e) => {
+                    tracing::error!(
+                        token = %token,
+                        error = %e,
+                        "Failed to update token balance"
+                    );
+                }
+            }
+        }
+
+        // Native ETH only matters for the gas floor and for wrapping into a token with a floor
+        let has_floors = self.floor_percentage.is_some() || !self.floors.is_empty();
+        if self.gas_floor.is_some() || (self.wrapped_native.is_some() && has_floors) {
+            let _permit = self.acquire_permit().await;
+            match provider.get_balance(self.owner).await {
+                Ok(balance) => self.record_native_balance(convert::u256_to_biguint(balance)),
+                Err(e) => {
+                    tracing::warn!(
+                        error = %e,
+                        "Failed to update native balance"
+                    );
+                }
+            }
+        }
+
+        tracing::debug!(
+            total_tokens = tokens.len(),
+            successful_updates = balances.len(),
+            "Balance update completed"
+        );
+
+        if balances.is_empty() && !tokens.is_empty() {
+            return Err(InventoryError::NoBalancesFetched { tokens: tokens.len() }.into());
+        }
+
+        Ok(balances)
+    }
+
+    /// Record a known balance for a token.
+    ///
+    /// A balance for a new block clears the input committed in the previous one.
+    pub fn record_balance(&self, token: Bytes, balance: BigUint, block_number: u64) {
+        let Ok(mut tokens) = self.tokens.write() else {
+            return;
+        };
+        let inventory = tokens.entry(token).or_default();
+        if block_number != inventory.updated_at {
+            inventory.committed = BigUint::zero();
+        }
+        if balance > inventory.peak_balance {
+            inventory.peak_balance = balance.clone();
+        }
+        inventory.balance = balance;
+        inventory.updated_at = block_number;
+    }
+
+    /// Record the native ETH balance of the owner, in wei.
+    pub fn record_native_balance(&self, balance: BigUint) {
+        if let Ok(mut native_balance) = self.native_balance.write() {
+            *native_balance = Some(balance);
+        }
+    }
+
+    /// Record input of a submitted trade, counted towards the token's utilization.
+    pub fn record_usage(&self, token: &Bytes, amount: &BigUint) {
+        if let Ok(mut tokens) = self.tokens.write() {
+            if let Some(inventory) = tokens.get_mut(token) {
+                inventory.committed += amount;
+            }
+        }
+    }
+
+    /// The tracked inventory of a token, if its balance has been read.
+    pub fn inventory(&self, token: &Bytes) -> Option<TokenInventory> {
+        self.tokens.read().ok()?.get(token).cloned()
+    }
+
+    /// The latest balances of all tracked tokens.
+    pub fn balances(&self) -> HashMap<Bytes, BigUint> {
+        self.tokens
+            .read()
+            .map(|tokens| {
+                tokens
+                    .iter()
+                    .map(|(token, inventory)| (token.clone(), inventory.balance.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The latest native ETH balance of the owner, if it has been read.
+    pub fn native_balance(&self) -> Option<BigUint> {
+        self.native_balance.read().ok()?.clone()
+    }
+
+    /// Share of a token's balance committed to trades since the last refresh.
+    pub fn utilization(&self, token: &Bytes) -> Option<f64> {
+        self.inventory(token).map(|inventory| inventory.utilization())
+    }
+
+    /// The floor of a token, absolute or relative to its peak balance.
+    pub fn floor(&self, token: &Bytes) -> Option<BigUint> {
+        if let Some(floor) = self.floors.get(token) {
+            return Some(floor.clone());
+        }
+        let percentage = self.floor_percentage?;
+        let peak_balance = self.inventory(token)?.peak_balance;
+        // Basis points keep the percentage exact to two decimals
+        let floor_bps = BigUint::from((percentage * 100.0).round() as u64);
+        Some(peak_balance * floor_bps / BigUint::from(10_000u32))
+    }
+
+    /// Suggestions for every token, and native ETH, below its floor.
+    ///
+    /// The wrapped native token is wrapped from native ETH above the gas floor,
+    /// native ETH is unwrapped from the wrapped native token above its floor.
+    /// Every other depleted token has to be topped up.
+    pub fn suggestions(&self) -> Vec<RebalanceSuggestion> {
+        let balances = self.balances();
+        let native_balance = self.native_balance();
+        let wrapped_native = self.wrapped_native.map(|address| Bytes::from(address.to_vec()));
+        let gas_floor = self.gas_floor.clone().unwrap_or_default();
+
+        let mut suggestions: Vec<RebalanceSuggestion> = balances
+            .iter()
+            .filter_map(|(token, balance)| {
+                let floor = self.floor(token)?;
+                if *balance >= floor {
+                    return None;
+                }
+                let deficit = &floor - balance;
+
+                let spare_native = native_balance
+                    .as_ref()
+                    .filter(|native_balance| **native_balance > gas_floor)
+                    .map(|native_balance| native_balance - &gas_floor);
+                let action = match spare_native {
+                    Some(spare) if wrapped_native.as_ref() == Some(token) => RebalanceAction::Wrap {
+                        amount: spare.min(deficit.clone()),
+                    },
+                    _ => RebalanceAction::TopUp,
+                };
+
+                Some(RebalanceSuggestion {
+                    token: token.clone(),
+                    balance: balance.clone(),
+                    floor,
+                    deficit,
+                    action,
+                })
+            })
+            .collect();
+
+        if let (Some(gas_floor), Some(native_balance)) = (&self.gas_floor, &native_balance) {
+            if native_balance < gas_floor {
+                let deficit = gas_floor - native_balance;
+                let spare_wrapped = wrapped_native.as_ref().and_then(|token| {
+                    let balance = balances.get(token)?;
+                    let floor = self.floor(token).unwrap_or_default();
+                    (*balance > floor).then(|| balance - floor)
+                });
+                let action = match spare_wrapped {
+                    Some(spare) => RebalanceAction::Unwrap {
+                        amount: spare.min(deficit.clone()),
+                    },
+                    None => RebalanceAction::TopUp,
+                };
+
+                suggestions.push(RebalanceSuggestion {
+                    token: Bytes::from(Address::ZERO.to_vec()),
+                    balance: native_balance.clone(),
+                    floor: gas_floor.clone(),
+                    deficit,
+                    action,
+                });
+            }
+        }
+
+        suggestions
+    }
+
+    /// Build the transaction executing a wrap or unwrap suggestion.
+    ///
+    /// The request calls `deposit` or `withdraw(uint256)` on the wrapped native
+    /// token and still needs fees before it can be signed.
+    ///
+    /// # Arguments
+    ///
+    /// * `suggestion` - The suggestion to execute
+    /// * `nonce` - The nonce of the owner for the transaction
+    /// * `chain_id` - The chain the transaction is signed for
+    ///
+    /// # Returns
+    ///
+    /// The transaction request, or `None` for top-up suggestions or when no
+    /// wrapped native token is configured.
+    pub fn rebalance_request(
+        &self,
+        suggestion: &RebalanceSuggestion,
+        nonce: u64,
+        chain_id: u64,
+    ) -> Option<TransactionRequest> {
+        let wrapped_native = self.wrapped_native?;
+        let (value, calldata) = match &suggestion.action {
+            RebalanceAction::Wrap { amount } => (
+                convert::biguint_to_u256_checked(amount)?,
+                encode_input("deposit()", Vec::new()),
+            ),
+            RebalanceAction::Unwrap { amount } => (
+                U256::ZERO,
+                encode_input("withdraw(uint256)", convert::biguint_to_u256_checked(amount)?.abi_encode()),
+            ),
+            RebalanceAction::TopUp => return None,
+        };
+
+        Some(TransactionRequest {
+            from: Some(self.owner),
+            to: Some(TxKind::Call(wrapped_native)),
+            value: Some(value),
+            input: TransactionInput {
+                input: Some(calldata.into()),
+                data: None,
+            },
+            gas: Some(WRAP_GAS_LIMIT),
+            nonce: Some(nonce),
+            chain_id: Some(chain_id),
+            ..Default::default()
+        })
+    }
+
+    /// Wait for the rate limiter, if set, before a balance query.
+    async fn acquire_permit(&self) -> Option<RatePermit> {
+        match &self.rate_limiter {
+            Some(limiter) => Some(limiter.acquire().await),
+            None => None,
+        }
+    }
+
+    /// Call `balanceOf(owner)` on a token contract.
+    async fn fetch_balance(&self, provider: &RootProvider<Ethereum>, token: &Bytes) -> Result<BigUint> {
+        let query_failed = |message: String| InventoryError::BalanceQueryFailed {
+            token: token.to_string(),
+            message,
+        };
+        if token.len() != 20 {
+            return Err(query_failed(format!("invalid address length {}", token.len())).into());
+        }
+
+        let request = TransactionRequest {
+            to: Some(TxKind::Call(Address::from_slice(token.as_ref()))),
+            input: TransactionInput {
+                input: Some(encode_input("balanceOf(address)", self.owner.abi_encode()).into()),
+                data: None,
+            },
+            ..Default::default()
+        };
+
+        let _permit = self.acquire_permit().await;
+        let output = provider
+            .call(request)
+            .await
+            .map_err(|e| query_failed(e.to_string()))?;
+        if output.len() < 32 {
+            return Err(query_failed("malformed balanceOf response".to_string()).into());
+        }
+
+        Ok(convert::u256_to_biguint(U256::from_be_slice(&output[..32])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(byte: u8) -> Bytes {
+        Bytes::from(vec![byte; 20])
+    }
+
+    #[test]
+    fn test_tracks_peak_balance_and_utilization() {
+        let inventory = InventoryManager::new(Address::random()).with_floor_percentage(25.0);
+        let usdc = token(1);
+
+        inventory.record_balance(usdc.clone(), BigUint::from(1_000u32), 1);
+        inventory.record_usage(&usdc, &BigUint::from(250u32));
+        assert_eq!(inventory.utilization(&usdc), Some(0.25));
+        assert!(inventory.suggestions().is_empty());
+
+        // A new block clears the committed input, the peak stays
+        inventory.record_balance(usdc.clone(), BigUint::from(200u32), 2);
+        let tracked = inventory.inventory(&usdc).unwrap();
+        assert_eq!(tracked.peak_balance, BigUint::from(1_000u32));
+        assert_eq!(tracked.utilization(), 0.0);
+
+        let suggestions = inventory.suggestions();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].floor, BigUint::from(250u32));
+        assert_eq!(suggestions[0].deficit, BigUint::from(50u32));
+        assert_eq!(suggestions[0].action, RebalanceAction::TopUp);
+        assert!(inventory.rebalance_request(&suggestions[0], 0, 1).is_none());
+    }
+
+    #[test]
+    fn test_suggests_wrapping_and_unwrapping() {
+        let weth = Address::random();
+        let weth_token = Bytes::from(weth.to_vec());
+        let inventory = InventoryManager::new(Address::random())
+            .with_wrapped_native(weth)
+            .with_floor(weth_token.clone(), BigUint::from(1_000u32))
+            .with_gas_floor(BigUint::from(100u32));
+
+        // WETH is low, spare ETH above the gas floor is wrapped
+        inventory.record_balance(weth_token.clone(), BigUint::from(400u32), 1);
+        inventory.record_native_balance(BigUint::from(500u32));
+        let suggestions = inventory.suggestions();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].action, RebalanceAction::Wrap { amount: BigUint::from(400u32) });
+        let request = inventory.rebalance_request(&suggestions[0], 7, 1).unwrap();
+        assert_eq!(request.value, Some(U256::from(400u32)));
+        assert_eq!(request.nonce, Some(7));
+
+        // ETH for gas is low, WETH above its floor is unwrapped
+        inventory.record_balance(weth_token, BigUint::from(1_030u32), 2);
+        inventory.record_native_balance(BigUint::from(40u32));
+        let suggestions = inventory.suggestions();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].token, Bytes::from(Address::ZERO.to_vec()));
+        assert_eq!(suggestions[0].action, RebalanceAction::Unwrap { amount: BigUint::from(30u32) });
+        assert!(inventory.rebalance_request(&suggestions[0], 8, 1).is_some());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires network access
+    async fn test_refresh_reads_token_balances() {
+        use std::str::FromStr;
+
+        let provider = RootProvider::new_http("https://eth.llamarpc.com".parse().unwrap());
+        // Vitalik's address and USDC
+        let owner = Address::from_str("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045").unwrap();
+        let usdc = Bytes::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap();
+
+        let inventory = InventoryManager::new(owner);
+        let balances = inventory.refresh(&provider, std::slice::from_ref(&usdc), 1).await;
+        assert!(balances.is_ok());
+        assert!(inventory.inventory(&usdc).is_some());
+    }
+}
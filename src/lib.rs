@@ -9,11 +9,24 @@
 //!
 //! The library is organized into several key modules:
 //!
+//! - **`alerts`**: Webhook alerts on large profits, error rates, low inventory and stream lag
 //! - **`graph`**: Token trading graph for modeling liquidity networks
 //! - **`path`**: Trading path discovery and optimization algorithms
 //! - **`simulation`**: Transaction simulation and validation engine
 //! - **`bundle`**: Bundle creation and submission to block builders
-//! - **`config`**: Secure configuration management and validation
+//! - **`bootstrap`**: Cold-start checks of executor balances and Permit2 approvals
+//! - **`budget`**: Deadline-aware search budgets shared by all pipeline stages
+//! - **`chain`**: Runtime registry of supported chains, their parameters and the tracked chain head
+//! - **`config`**: Secure configuration management, layered settings and validation
+//! - **`events`**: Callbacks for lifecycle events such as found opportunities and submitted bundles
+//! - **`inventory`**: Executor balance tracking, utilization and rebalancing suggestions
+//! - **`pricing`**: Token prices in USD for comparing profits across start tokens
+//! - **`rpc`**: Pools of RPC endpoints with failover, latency racing and health metrics
+//! - **`risk`**: Risk limits and the emergency stop enforced before bundle submission
+//! - **`signer`**: Executor signers backed by private keys, keystores or remote services
+//! - **`stats`**: Rolling per-block search, simulation and inclusion statistics
+//! - **`status`**: HTTP health, readiness and status endpoint (`status-server` feature)
+//! - **`sync`**: Consistency tracking and resynchronization of the block update stream
 //! - **`builders`**: Builder patterns for complex object construction
 //! - **`errors`**: Comprehensive error handling and reporting
 //! - **`utils`**: Utility functions for type conversions and chain operations
@@ -34,7 +47,7 @@
 //! This library handles sensitive operations including private key management
 //! and transaction signing. Always follow security best practices:
 //!
-//! - Store private keys securely using environment variables
+//! - Store private keys in encrypted keystores or remote signers rather than plain environment variables
 //! - Use HTTPS-only endpoints for all network communications
 //! - Validate all configuration before use
 //! - Monitor for failed transactions and handle errors appropriately
@@ -42,15 +55,33 @@
 //! # Thread Safety
 //!
 //! Most types in this library are not thread-safe by default. Use appropriate
-//! synchronization primitives when sharing instances across threads.
+//! synchronization primitives when sharing instances across threads. The
+//! trading graph is shared through [`graph::SharedTradingGraph`], whose
+//! snapshots let path discovery run while block updates are applied.
 
+pub mod alerts;
+pub mod bootstrap;
+pub mod budget;
 pub mod builders;
 pub mod bundle;
+pub mod chain;
 pub mod config;
 pub mod errors;
+pub mod events;
 pub mod graph;
+pub mod inventory;
 pub mod path;
+pub mod pricing;
+pub mod risk;
+pub mod rpc;
+pub mod signer;
 pub mod simulation;
+pub mod stats;
+#[cfg(feature = "status-server")]
+pub mod status;
+pub mod sync;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod utils;
 
 // Re-export the main Result type and error enum for convenience
@@ -0,0 +1,112 @@
+//! Protocols whose swaps may be executed.
+//!
+//! Streaming the state of a protocol and simulating it locally does not mean
+//! its swaps are encoded correctly for execution. A new protocol integration
+//! is safer rolled out in stages: stream it and search its paths first, and
+//! only submit bundles through it once its opportunities look right.
+//!
+//! A `ProtocolAllowlist` names the protocol systems trusted for execution.
+//! Paths through any other protocol are still discovered, optimized and
+//! simulated, so they show up in logs and statistics, but the
+//! [`TxExecutor`](crate::bundle::TxExecutor) never submits them. The default
+//! allowlist trusts every protocol.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::path::ProtocolAllowlist;
+//!
+//! let allowlist = ProtocolAllowlist::only(["uniswap_v2", "uniswap_v3"]);
+//! if !allowlist.allows_path_ext(&path) {
+//!     tracing::info!(blocked = ?allowlist.blocked_protocols(&path), "Analyzed only");
+//! }
+//! ```
+
+use crate::path::{Path, PathExt};
+use std::collections::BTreeSet;
+
+/// Protocol systems trusted for execution, every protocol if unrestricted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProtocolAllowlist {
+    protocols: Option<BTreeSet<String>>,
+}
+
+impl ProtocolAllowlist {
+    /// Allow the execution of every protocol.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Allow the execution of the given protocol systems only.
+    pub fn only<I, S>(protocols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            protocols: Some(protocols.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    /// Also allow the execution of a protocol system, if the allowlist is restricted.
+    pub fn with_protocol(mut self, protocol_system: impl Into<String>) -> Self {
+        if let Some(protocols) = &mut self.protocols {
+            protocols.insert(protocol_system.into());
+        }
+        self
+    }
+
+    /// Whether the allowlist restricts execution to some protocols.
+    pub fn is_restricted(&self) -> bool {
+        self.protocols.is_some()
+    }
+
+    /// The allowed protocol systems, `None` if every protocol is allowed.
+    pub fn protocols(&self) -> Option<&BTreeSet<String>> {
+        self.protocols.as_ref()
+    }
+
+    /// Whether swaps through a protocol system may be executed.
+    pub fn allows(&self, protocol_system: &str) -> bool {
+        self.protocols
+            .as_ref()
+            .is_none_or(|protocols| protocols.contains(protocol_system))
+    }
+
+    /// Whether every swap of a path may be executed.
+    pub fn allows_path(&self, path: &Path) -> bool {
+        path.iter().all(|swap| self.allows(&swap.pool_comp.protocol_system))
+    }
+
+    /// Whether every swap of an executed path may be executed.
+    pub fn allows_path_ext(&self, path: &PathExt) -> bool {
+        path.iter().all(|swap| self.allows(&swap.pool_comp.protocol_system))
+    }
+
+    /// The protocol systems of an executed path that are not allowed, without duplicates.
+    pub fn blocked_protocols<'a>(&self, path: &'a PathExt) -> Vec<&'a str> {
+        path.iter()
+            .map(|swap| swap.pool_comp.protocol_system.as_str())
+            .filter(|protocol_system| !self.allows(protocol_system))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restricted_allowlist_only_allows_listed_protocols() {
+        let allowlist = ProtocolAllowlist::only(["uniswap_v2"]).with_protocol("uniswap_v3");
+        assert!(allowlist.is_restricted());
+        assert!(allowlist.allows("uniswap_v3"));
+        assert!(!allowlist.allows("vm:curve"));
+
+        assert!(ProtocolAllowlist::all().allows("vm:curve"));
+        assert!(!ProtocolAllowlist::all().with_protocol("uniswap_v2").is_restricted());
+        assert!(ProtocolAllowlist::only(Vec::<String>::new()).allows_path_ext(&PathExt(vec![])));
+    }
+}
@@ -0,0 +1,513 @@
+//! Reuse of path evaluations while the pools of a path are unchanged.
+//!
+//! Every path through a pool in a block update is optimized again, although
+//! many updates leave the state of most pools as it was, and the optimization
+//! then reaches the same result. `PoolVersions` counts the state changes of
+//! every pool, and `PathEvaluationCache` keeps the outcome of optimizing a path
+//! together with the versions of its pools and the input bound it was
+//! optimized within. While neither changed, the cached outcome is returned and
+//! the path is not optimized again.
+//!
+//! Filtering paths by their spot price product calls `spot_price` for every
+//! swap of every path, although routes through major tokens share most of
+//! their pools. `SpotPriceCache` keeps the spot price of every pool direction
+//! at the version it was computed at, so each pool is only asked again once
+//! its state changed.
+//!
+//! Pool limits bound the input a path can take, but optimizers only learn
+//! about them by probing amounts that fail with `AmountExceedsLimits`.
+//! `PathLimitCache` keeps the largest feasible input of every path, see
+//! [`Path::max_feasible_input`], at the versions of its pools, so it is only
+//! propagated through the hops again once one of them changed and serves as
+//! the search upper bound of the optimizers.
+
+use crate::errors::{PathError, Result};
+use crate::path::{Path, PathExt, PathId, Swap};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use tycho_common::Bytes;
+
+/// Versions of pool states, increased whenever the state of a pool changes.
+///
+/// Versions are never reset, so a pool removed and added again does not
+/// match evaluations cached before its removal.
+#[derive(Debug, Default)]
+pub struct PoolVersions {
+    versions: RwLock<HashMap<Bytes, u64>>,
+}
+
+impl PoolVersions {
+    /// Create an empty version registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a change of the state of a pool.
+    ///
+    /// # Returns
+    ///
+    /// The new version of the pool
+    pub fn record_change(&self, pool: &Bytes) -> u64 {
+        let Ok(mut versions) = self.versions.write() else {
+            return 0;
+        };
+        let version = versions.entry(pool.clone()).or_insert(0);
+        *version += 1;
+        *version
+    }
+
+    /// The version of a pool, 0 for pools whose state was never recorded.
+    pub fn version(&self, pool: &Bytes) -> u64 {
+        self.versions
+            .read()
+            .ok()
+            .and_then(|versions| versions.get(pool).copied())
+            .unwrap_or(0)
+    }
+
+    /// The versions of the pools of a path, in swap order.
+    pub fn path_versions(&self, path: &Path) -> Vec<u64> {
+        let Ok(versions) = self.versions.read() else {
+            return Vec::new();
+        };
+        path.iter()
+            .map(|swap| versions.get(&swap.pool_comp.id).copied().unwrap_or(0))
+            .collect()
+    }
+
+    /// The versions of the pools of an executed path, in swap order.
+    pub fn path_ext_versions(&self, path: &PathExt) -> Vec<u64> {
+        let Ok(versions) = self.versions.read() else {
+            return Vec::new();
+        };
+        path.iter()
+            .map(|swap| versions.get(&swap.pool_comp.id).copied().unwrap_or(0))
+            .collect()
+    }
+}
+
+/// An evaluation together with the state it was computed from.
+#[derive(Debug, Clone)]
+struct CachedEvaluation {
+    versions: Vec<u64>,
+    upper_bound: BigUint,
+    /// The optimized path, or why the optimization failed
+    outcome: std::result::Result<PathExt, String>,
+}
+
+/// Path evaluations keyed by the pool versions they were computed at.
+#[derive(Debug, Default)]
+pub struct PathEvaluationCache {
+    entries: RwLock<HashMap<PathId, CachedEvaluation>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl PathEvaluationCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evaluate a path, reusing the previous outcome if its pools did not change.
+    ///
+    /// Failed evaluations are cached as well, so paths that could not be
+    /// optimized are skipped until one of their pools changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to evaluate
+    /// * `versions` - The current pool versions
+    /// * `upper_bound` - The largest input amount the path is evaluated for
+    /// * `evaluate` - Optimizes the path if no current outcome is cached
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `evaluate`, or a `PathError::OptimizationFailed`
+    /// with the cached reason if the path failed before.
+    pub fn evaluate<F>(
+        &self,
+        path: &Path,
+        versions: &PoolVersions,
+        upper_bound: &BigUint,
+        evaluate: F,
+    ) -> Result<PathExt>
+    where
+        F: FnOnce() -> Result<PathExt>,
+    {
+        let path_id = PathId::of_path(path);
+        let path_versions = versions.path_versions(path);
+
+        let cached = self.entries.read().ok().and_then(|entries| {
+            entries
+                .get(&path_id)
+                .filter(|entry| entry.versions == path_versions && entry.upper_bound == *upper_bound)
+                .map(|entry| entry.outcome.clone())
+        });
+        if let Some(outcome) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            tracing::trace!(path_id = %path_id, "Reusing evaluation of unchanged path");
+            return outcome.map_err(|reason| PathError::OptimizationFailed { reason }.into());
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let result = evaluate();
+        let outcome = match &result {
+            Ok(path_ext) => Ok(path_ext.clone()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert(
+                path_id,
+                CachedEvaluation {
+                    versions: path_versions,
+                    upper_bound: upper_bound.clone(),
+                    outcome,
+                },
+            );
+        }
+        result
+    }
+
+    /// Drop the cached outcome of a path.
+    pub fn invalidate(&self, path_id: &PathId) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.remove(path_id);
+        }
+    }
+
+    /// Drop all cached outcomes.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.clear();
+        }
+    }
+
+    /// The number of cached outcomes.
+    pub fn len(&self) -> usize {
+        self.entries.read().map(|entries| entries.len()).unwrap_or(0)
+    }
+
+    /// Whether no outcome is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of evaluations answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of evaluations that had to be computed.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+/// A spot price together with the pool version it was computed at.
+#[derive(Debug, Clone, Copy)]
+struct CachedSpotPrice {
+    version: u64,
+    spot_price: f64,
+}
+
+/// Spot prices per pool direction, keyed by the pool versions they were computed at.
+///
+/// Entries of a pool are replaced once its version changes. Call
+/// [`invalidate_pool`](Self::invalidate_pool) after removing a pool, so its
+/// entries do not accumulate.
+#[derive(Debug, Default)]
+pub struct SpotPriceCache {
+    /// Spot prices by pool, input token and output token
+    entries: RwLock<HashMap<(Bytes, Bytes, Bytes), CachedSpotPrice>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SpotPriceCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The spot price of a swap, reusing the cached price if its pool did not change.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spot price of a changed pool cannot be calculated.
+    /// Failures are not cached.
+    pub fn spot_price(&self, swap: &Swap, versions: &PoolVersions) -> Result<f64> {
+        let key = (
+            swap.pool_comp.id.clone(),
+            swap.token_in.address.clone(),
+            swap.token_out.address.clone(),
+        );
+        let version = versions.version(&key.0);
+
+        let cached = self.entries.read().ok().and_then(|entries| {
+            entries
+                .get(&key)
+                .filter(|entry| entry.version == version)
+                .map(|entry| entry.spot_price)
+        });
+        if let Some(spot_price) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(spot_price);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let spot_price = swap.spot_price()?;
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert(key, CachedSpotPrice { version, spot_price });
+        }
+        Ok(spot_price)
+    }
+
+    /// The product of the spot prices along a path, see [`Path::spot_price_product`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spot price of a swap cannot be calculated.
+    pub fn spot_price_product(&self, path: &Path, versions: &PoolVersions) -> Result<f64> {
+        path.iter()
+            .try_fold(1.0, |product, swap| Ok(product * self.spot_price(swap, versions)?))
+    }
+
+    /// Drop the cached spot prices of a pool in all directions.
+    pub fn invalidate_pool(&self, pool: &Bytes) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.retain(|(cached_pool, _, _), _| cached_pool != pool);
+        }
+    }
+
+    /// Drop all cached spot prices.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.clear();
+        }
+    }
+
+    /// The number of cached pool directions.
+    pub fn len(&self) -> usize {
+        self.entries.read().map(|entries| entries.len()).unwrap_or(0)
+    }
+
+    /// Whether no spot price is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of spot prices answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of spot prices that had to be computed.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+/// A feasible input together with the pool versions it was computed at.
+#[derive(Debug, Clone)]
+struct CachedLimit {
+    versions: Vec<u64>,
+    max_amount_in: BigUint,
+}
+
+/// The largest feasible input of every path, keyed by the pool versions it was computed at.
+#[derive(Debug, Default)]
+pub struct PathLimitCache {
+    entries: RwLock<HashMap<PathId, CachedLimit>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl PathLimitCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The largest input of a path within the limits of its pools, reusing the
+    /// cached input if its pools did not change.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`Path::max_feasible_input`] for a changed path.
+    /// Failures are not cached.
+    pub fn max_amount_in(&self, path: &Path, versions: &PoolVersions) -> Result<BigUint> {
+        let path_id = PathId::of_path(path);
+        let path_versions = versions.path_versions(path);
+
+        let cached = self.entries.read().ok().and_then(|entries| {
+            entries
+                .get(&path_id)
+                .filter(|entry| entry.versions == path_versions)
+                .map(|entry| entry.max_amount_in.clone())
+        });
+        if let Some(max_amount_in) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(max_amount_in);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let max_amount_in = path.max_feasible_input()?;
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert(
+                path_id,
+                CachedLimit {
+                    versions: path_versions,
+                    max_amount_in: max_amount_in.clone(),
+                },
+            );
+        }
+        Ok(max_amount_in)
+    }
+
+    /// Drop the cached input of a path.
+    pub fn invalidate(&self, path_id: &PathId) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.remove(path_id);
+        }
+    }
+
+    /// Drop all cached inputs.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.clear();
+        }
+    }
+
+    /// The number of cached paths.
+    pub fn len(&self) -> usize {
+        self.entries.read().map(|entries| entries.len()).unwrap_or(0)
+    }
+
+    /// Whether no input is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of inputs answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of inputs that had to be computed.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{component, MockProtocolSim};
+    use crate::path::{Swap, SwapExt};
+    use std::cell::Cell;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    fn path(pool: &Bytes) -> Path {
+        path_through(pool, MockProtocolSim::new())
+    }
+
+    fn path_through(pool: &Bytes, simulation: MockProtocolSim) -> Path {
+        let token_a = Bytes::from_str("0x0001").unwrap();
+        let token_b = Bytes::from_str("0x0002").unwrap();
+        let pool_comp = component(pool, &[token_a.clone(), token_b.clone()]);
+        Path(vec![Swap::new(pool_comp, Arc::new(simulation), &token_a, &token_b).unwrap()])
+    }
+
+    fn executed(path: &Path) -> Result<PathExt> {
+        let swap = &path[0];
+        Ok(PathExt(vec![SwapExt::from_swap(swap, 100u32.into(), 110u32.into(), 21000u32.into())]))
+    }
+
+    #[test]
+    fn test_reuses_evaluations_until_a_pool_changes() {
+        let pool = Bytes::from_str("0x1001").unwrap();
+        let path = path(&pool);
+        let versions = PoolVersions::new();
+        let cache = PathEvaluationCache::new();
+        let bound = BigUint::from(1000u32);
+        let evaluations = Cell::new(0);
+        let evaluate = || {
+            evaluations.set(evaluations.get() + 1);
+            executed(&path)
+        };
+
+        versions.record_change(&pool);
+        assert!(cache.evaluate(&path, &versions, &bound, evaluate).is_ok());
+        assert!(cache.evaluate(&path, &versions, &bound, evaluate).is_ok());
+        assert_eq!(evaluations.get(), 1);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        // A changed pool or input bound requires a new evaluation
+        assert_eq!(versions.record_change(&pool), 2);
+        assert!(cache.evaluate(&path, &versions, &bound, evaluate).is_ok());
+        assert!(cache.evaluate(&path, &versions, &BigUint::from(500u32), evaluate).is_ok());
+        assert_eq!(evaluations.get(), 3);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_caches_failed_evaluations() {
+        let pool = Bytes::from_str("0x1001").unwrap();
+        let path = path(&pool);
+        let versions = PoolVersions::new();
+        let cache = PathEvaluationCache::new();
+        let bound = BigUint::from(1000u32);
+
+        let failed = cache.evaluate(&path, &versions, &bound, || Err(PathError::NoProfitablePaths.into()));
+        assert!(failed.is_err());
+
+        let cached = cache.evaluate(&path, &versions, &bound, || executed(&path));
+        assert!(cached.unwrap_err().to_string().contains("No profitable paths found"));
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn test_reuses_spot_prices_until_a_pool_changes() {
+        let pool = Bytes::from_str("0x1001").unwrap();
+        let path = path(&pool);
+        let versions = PoolVersions::new();
+        let cache = SpotPriceCache::new();
+
+        assert_eq!(cache.spot_price_product(&path, &versions).unwrap(), 1.0);
+        assert_eq!(cache.spot_price_product(&path, &versions).unwrap(), 1.0);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        versions.record_change(&pool);
+        assert!(cache.spot_price(&path[0], &versions).is_ok());
+        assert_eq!(cache.misses(), 2);
+        assert_eq!(cache.len(), 1);
+
+        cache.invalidate_pool(&pool);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_reuses_feasible_inputs_until_a_pool_changes() {
+        let pool = Bytes::from_str("0x1001").unwrap();
+        let limited = MockProtocolSim::new()
+            .with_rate(2.0)
+            .with_limits(BigUint::from(1000u32), BigUint::from(1000u32));
+        let path = path_through(&pool, limited);
+        let versions = PoolVersions::new();
+        let cache = PathLimitCache::new();
+
+        // The output limit binds before the input limit
+        assert_eq!(cache.max_amount_in(&path, &versions).unwrap(), BigUint::from(500u32));
+        assert_eq!(cache.max_amount_in(&path, &versions).unwrap(), BigUint::from(500u32));
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        versions.record_change(&pool);
+        assert!(cache.max_amount_in(&path, &versions).is_ok());
+        assert_eq!(cache.misses(), 2);
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,263 @@
+//! Cooldowns for pools and paths whose executions keep failing.
+//!
+//! A bundle targeting a route can revert, or lose the race against another
+//! searcher, block after block. Submitting the same route again mostly burns
+//! simulation budget and relayer reputation. The `CooldownRegistry` counts
+//! failures per pool address and per path and keeps them out of the search for
+//! an exponentially growing number of blocks. Failure counts decay after a quiet
+//! period, so a route is retried at full speed once it stopped failing.
+
+use crate::path::{Path, PathExt, PathId};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tycho_common::Bytes;
+
+/// Default number of failures before a pool or path is cooled down.
+const DEFAULT_MIN_FAILURES: u32 = 2;
+
+/// Default cooldown after reaching the failure threshold.
+const DEFAULT_BASE_COOLDOWN_BLOCKS: u64 = 2;
+
+/// Default upper bound of the exponential backoff.
+const DEFAULT_MAX_COOLDOWN_BLOCKS: u64 = 64;
+
+/// Default number of blocks without failure after which failures are forgotten.
+const DEFAULT_DECAY_BLOCKS: u64 = 100;
+
+/// Failure history of one pool or path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CooldownEntry {
+    /// Failures since the history was last reset
+    pub failures: u32,
+    /// Block of the most recent failure
+    pub last_failure_block: u64,
+    /// First block at which the pool or path is searched again
+    pub until_block: u64,
+}
+
+/// Registry of pools and paths cooling down after failed executions.
+#[derive(Debug)]
+pub struct CooldownRegistry {
+    min_failures: u32,
+    base_cooldown_blocks: u64,
+    max_cooldown_blocks: u64,
+    decay_blocks: u64,
+    pools: RwLock<HashMap<Bytes, CooldownEntry>>,
+    paths: RwLock<HashMap<PathId, CooldownEntry>>,
+}
+
+impl CooldownRegistry {
+    /// Create a registry that cools routes down for 2 blocks after their second
+    /// failure, doubling up to 64 blocks, and forgets failures after 100 quiet blocks.
+    pub fn new() -> Self {
+        Self {
+            min_failures: DEFAULT_MIN_FAILURES,
+            base_cooldown_blocks: DEFAULT_BASE_COOLDOWN_BLOCKS,
+            max_cooldown_blocks: DEFAULT_MAX_COOLDOWN_BLOCKS,
+            decay_blocks: DEFAULT_DECAY_BLOCKS,
+            pools: RwLock::new(HashMap::new()),
+            paths: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Set the number of failures before a pool or path is cooled down.
+    pub fn with_min_failures(mut self, min_failures: u32) -> Self {
+        self.min_failures = min_failures.max(1);
+        self
+    }
+
+    /// Set the cooldown applied when the failure threshold is reached.
+    pub fn with_base_cooldown_blocks(mut self, base_cooldown_blocks: u64) -> Self {
+        self.base_cooldown_blocks = base_cooldown_blocks;
+        self
+    }
+
+    /// Set the upper bound of the exponential backoff.
+    pub fn with_max_cooldown_blocks(mut self, max_cooldown_blocks: u64) -> Self {
+        self.max_cooldown_blocks = max_cooldown_blocks;
+        self
+    }
+
+    /// Set the number of blocks without failure after which failures are forgotten.
+    pub fn with_decay_blocks(mut self, decay_blocks: u64) -> Self {
+        self.decay_blocks = decay_blocks;
+        self
+    }
+
+    /// Record a failed execution of a path.
+    ///
+    /// The failure counts against the path and against every pool it trades on.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path whose simulation reverted or whose bundle was not included
+    /// * `block_number` - The block the failure was observed in
+    pub fn record_failure(&self, path: &PathExt, block_number: u64) {
+        let path_id = PathId::of_path_ext(path);
+
+        if let Ok(mut paths) = self.paths.write() {
+            let entry = paths.entry(path_id).or_default();
+            self.apply_failure(entry, block_number);
+
+            tracing::debug!(
+                path_id = %path_id,
+                failures = entry.failures,
+                until_block = entry.until_block,
+                "Recorded path execution failure"
+            );
+        }
+
+        if let Ok(mut pools) = self.pools.write() {
+            for swap in path.iter() {
+                let entry = pools.entry(swap.pool_comp.id.clone()).or_default();
+                self.apply_failure(entry, block_number);
+            }
+        }
+    }
+
+    /// Record a successful execution of a path, clearing its failure history.
+    pub fn record_success(&self, path: &PathExt) {
+        if let Ok(mut paths) = self.paths.write() {
+            paths.remove(&PathId::of_path_ext(path));
+        }
+
+        if let Ok(mut pools) = self.pools.write() {
+            for swap in path.iter() {
+                pools.remove(&swap.pool_comp.id);
+            }
+        }
+    }
+
+    /// The block until which a pool is cooling down, if it is at `block_number`.
+    pub fn pool_cooldown(&self, pool: &Bytes, block_number: u64) -> Option<u64> {
+        self.pools
+            .read()
+            .ok()?
+            .get(pool)
+            .map(|entry| entry.until_block)
+            .filter(|until_block| *until_block > block_number)
+    }
+
+    /// The block until which a path is cooling down, if it is at `block_number`.
+    pub fn path_cooldown(&self, path_id: &PathId, block_number: u64) -> Option<u64> {
+        self.paths
+            .read()
+            .ok()?
+            .get(path_id)
+            .map(|entry| entry.until_block)
+            .filter(|until_block| *until_block > block_number)
+    }
+
+    /// Whether a path, or any pool it trades on, is cooling down at `block_number`.
+    pub fn is_cooling_down(&self, path: &Path, block_number: u64) -> bool {
+        self.path_cooldown(&PathId::of_path(path), block_number).is_some()
+            || path
+                .iter()
+                .any(|swap| self.pool_cooldown(&swap.pool_comp.id, block_number).is_some())
+    }
+
+    /// Number of pools and paths currently cooling down, in that order.
+    pub fn cooling_down_count(&self, block_number: u64) -> (usize, usize) {
+        let count = |entries: &HashMap<_, CooldownEntry>| {
+            entries.values().filter(|entry| entry.until_block > block_number).count()
+        };
+        (
+            self.pools.read().map_or(0, |pools| count(&pools)),
+            self.paths.read().map_or(0, |paths| count(&paths)),
+        )
+    }
+
+    /// Forget failure histories that decayed by `block_number`.
+    pub fn prune(&self, block_number: u64) {
+        let is_live = |entry: &CooldownEntry| !self.is_decayed(entry, block_number);
+        if let Ok(mut pools) = self.pools.write() {
+            pools.retain(|_, entry| is_live(entry));
+        }
+        if let Ok(mut paths) = self.paths.write() {
+            paths.retain(|_, entry| is_live(entry));
+        }
+    }
+
+    fn apply_failure(&self, entry: &mut CooldownEntry, block_number: u64) {
+        if self.is_decayed(entry, block_number) {
+            *entry = CooldownEntry::default();
+        }
+
+        entry.failures += 1;
+        entry.last_failure_block = entry.last_failure_block.max(block_number);
+
+        if entry.failures >= self.min_failures {
+            let exponent = (entry.failures - self.min_failures).min(63);
+            let cooldown = self
+                .base_cooldown_blocks
+                .saturating_mul(1u64 << exponent)
+                .min(self.max_cooldown_blocks);
+            entry.until_block = entry.until_block.max(block_number + cooldown);
+        }
+    }
+
+    fn is_decayed(&self, entry: &CooldownEntry, block_number: u64) -> bool {
+        entry.failures > 0
+            && block_number.saturating_sub(entry.last_failure_block) > self.decay_blocks
+            && entry.until_block <= block_number
+    }
+}
+
+impl Default for CooldownRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_backoff_grows_and_decays() {
+        let registry = CooldownRegistry::new()
+            .with_min_failures(2)
+            .with_base_cooldown_blocks(2)
+            .with_max_cooldown_blocks(5)
+            .with_decay_blocks(10);
+
+        let mut entry = CooldownEntry::default();
+        registry.apply_failure(&mut entry, 100);
+        assert_eq!(entry.until_block, 0);
+
+        registry.apply_failure(&mut entry, 101);
+        assert_eq!(entry.until_block, 103);
+
+        registry.apply_failure(&mut entry, 102);
+        assert_eq!(entry.until_block, 106);
+
+        // Capped by the maximum cooldown
+        registry.apply_failure(&mut entry, 103);
+        assert_eq!(entry.until_block, 108);
+        assert_eq!(entry.failures, 4);
+
+        // Failures are forgotten after a quiet period
+        registry.apply_failure(&mut entry, 120);
+        assert_eq!(entry.failures, 1);
+        assert!(registry.is_decayed(&entry, 131));
+        assert!(!registry.is_decayed(&entry, 125));
+    }
+
+    #[test]
+    fn test_pool_cooldown_lookup() {
+        let registry = CooldownRegistry::new().with_min_failures(1);
+        let pool = Bytes::from_str("0x1000").unwrap();
+
+        let mut entry = CooldownEntry::default();
+        registry.apply_failure(&mut entry, 10);
+        registry.pools.write().unwrap().insert(pool.clone(), entry);
+
+        assert_eq!(registry.pool_cooldown(&pool, 10), Some(12));
+        assert_eq!(registry.pool_cooldown(&pool, 12), None);
+        assert_eq!(registry.cooling_down_count(11), (1, 0));
+
+        registry.prune(200);
+        assert!(registry.pools.read().unwrap().is_empty());
+    }
+}
@@ -8,6 +8,7 @@ use crate::errors::{PathError, Result};
 use crate::graph::TradingGraph;
 use crate::path::{Path, Swap};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tycho_common::Bytes;
 use tycho_simulation::{
     protocol::{models::ProtocolComponent, state::ProtocolSim},
@@ -20,8 +21,8 @@ use tycho_simulation::{
 pub struct PathBuilder<'a> {
     edges: Option<&'a [usize]>,
     graph: Option<&'a TradingGraph>,
-    protocol_components: Option<&'a HashMap<Bytes, ProtocolComponent>>,
-    protocol_simulations: Option<&'a HashMap<Bytes, Box<dyn ProtocolSim>>>,
+    protocol_components: Option<&'a HashMap<Bytes, Arc<ProtocolComponent>>>,
+    protocol_simulations: Option<&'a HashMap<Bytes, Arc<dyn ProtocolSim>>>,
     validate_connectivity: bool,
 }
 
@@ -52,7 +53,7 @@ impl<'a> PathBuilder<'a> {
     /// Set the protocol components map.
     pub fn with_protocol_components(
         mut self,
-        components: &'a HashMap<Bytes, ProtocolComponent>,
+        components: &'a HashMap<Bytes, Arc<ProtocolComponent>>,
     ) -> Self {
         self.protocol_components = Some(components);
         self
@@ -61,7 +62,7 @@ impl<'a> PathBuilder<'a> {
     /// Set the protocol simulations map.
     pub fn with_protocol_simulations(
         mut self,
-        simulations: &'a HashMap<Bytes, Box<dyn ProtocolSim>>,
+        simulations: &'a HashMap<Bytes, Arc<dyn ProtocolSim>>,
     ) -> Self {
         self.protocol_simulations = Some(simulations);
         self
@@ -140,8 +141,8 @@ impl<'a> PathBuilder<'a> {
         &self,
         edges: &[usize],
         graph: &TradingGraph,
-        protocol_components: &HashMap<Bytes, ProtocolComponent>,
-        protocol_simulations: &HashMap<Bytes, Box<dyn ProtocolSim>>,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
     ) -> Result<Vec<Swap>> {
         let mut swaps = Vec::with_capacity(edges.len());
 
@@ -163,8 +164,8 @@ impl<'a> PathBuilder<'a> {
         &self,
         edge_idx: usize,
         graph: &TradingGraph,
-        protocol_components: &HashMap<Bytes, ProtocolComponent>,
-        protocol_simulations: &HashMap<Bytes, Box<dyn ProtocolSim>>,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
     ) -> Result<Swap> {
         let edge = graph.get_pool(edge_idx).map_err(|e| {
             tracing::warn!(
@@ -216,13 +217,23 @@ impl<'a> PathBuilder<'a> {
             }
         })?;
 
-        let zero_for_one = input_token.address() == &pool_component.tokens[0].address;
+        let output_token = graph.get_token(edge.token_out_id()).map_err(|e| {
+            tracing::warn!(
+                edge_index = edge_idx,
+                error = %e,
+                "Failed to get output token from graph"
+            );
+            PathError::InvalidPath {
+                reason: format!("Invalid output token for edge: {}", edge_idx),
+            }
+        })?;
 
-        Ok(Swap {
-            pool_comp: pool_component,
-            pool_sim: pool_simulation,
-            zero_for_one,
-        })
+        Swap::new(
+            pool_component,
+            pool_simulation,
+            input_token.address(),
+            output_token.address(),
+        )
     }
 }
 
@@ -295,20 +306,12 @@ impl PathValidator {
 
     /// Get the input token address for a swap.
     fn get_input_token_address(swap: &Swap) -> &Bytes {
-        if swap.zero_for_one {
-            &swap.pool_comp.tokens[0].address
-        } else {
-            &swap.pool_comp.tokens[1].address
-        }
+        &swap.token_in.address
     }
 
     /// Get the output token address for a swap.
     fn get_output_token_address(swap: &Swap) -> &Bytes {
-        if swap.zero_for_one {
-            &swap.pool_comp.tokens[1].address
-        } else {
-            &swap.pool_comp.tokens[0].address
-        }
+        &swap.token_out.address
     }
 
     /// Validate that a path has consistent pool and token counts for logging/storage.
@@ -380,80 +383,17 @@ impl PathValidator {
 mod tests {
     use super::*;
     use crate::graph::TradingGraph;
+    use crate::testing::{component, MockProtocolSim};
     use std::str::FromStr;
     use tycho_simulation::protocol::models::ProtocolComponent;
     use tycho_simulation::protocol::state::ProtocolSim;
     use num_bigint::BigUint;
 
-    // Mock ProtocolSim for testing
-    #[derive(Debug, Clone)]
-    struct MockProtocolSim;
-
-    impl ProtocolSim for MockProtocolSim {
-        fn clone_box(&self) -> Box<dyn ProtocolSim> {
-            Box::new(self.clone())
-        }
-
-        fn fee(&self) -> f64 {
-            0.003
-        }
-
-        fn spot_price(
-            &self,
-            _token_in: &tycho_simulation::models::Token,
-            _token_out: &tycho_simulation::models::Token,
-        ) -> std::result::Result<f64, tycho_simulation::protocol::errors::SimulationError> {
-            Ok(1.0)
-        }
-
-        fn get_amount_out(
-            &self,
-            amount_in: BigUint,
-            _token_in: &tycho_simulation::models::Token,
-            _token_out: &tycho_simulation::models::Token,
-        ) -> std::result::Result<tycho_simulation::protocol::models::GetAmountOutResult, tycho_simulation::protocol::errors::SimulationError> {
-            Ok(tycho_simulation::protocol::models::GetAmountOutResult {
-                amount: amount_in,
-                gas: BigUint::from(21000u32),
-                new_state: Box::new(MockProtocolSim),
-            })
-        }
-
-        fn get_limits(
-            &self,
-            _token_in: Bytes,
-            _token_out: Bytes,
-        ) -> std::result::Result<(BigUint, BigUint), tycho_simulation::protocol::errors::SimulationError> {
-            Ok((BigUint::from(1000000u32), BigUint::from(1000000u32)))
-        }
-
-        fn delta_transition(
-            &mut self,
-            _delta: tycho_common::dto::ProtocolStateDelta,
-            _tokens: &std::collections::HashMap<Bytes, tycho_simulation::models::Token>,
-            _balances: &tycho_simulation::models::Balances,
-        ) -> std::result::Result<(), tycho_simulation::protocol::errors::TransitionError<String>> {
-            Ok(())
-        }
-
-        fn as_any(&self) -> &(dyn std::any::Any + 'static) {
-            self
-        }
-
-        fn as_any_mut(&mut self) -> &mut (dyn std::any::Any + 'static) {
-            self
-        }
-
-        fn eq(&self, other: &(dyn ProtocolSim + 'static)) -> bool {
-            other.as_any().is::<MockProtocolSim>()
-        }
-    }
-
     #[test]
     fn test_path_builder_success() {
         let mut graph = TradingGraph::new();
         let mut protocol_comp = HashMap::new();
-        let mut protocol_sim: HashMap<Bytes, Box<dyn ProtocolSim>> = HashMap::new();
+        let mut protocol_sim: HashMap<Bytes, Arc<dyn ProtocolSim>> = HashMap::new();
 
         // Create tokens for a proper arbitrage cycle: A -> B -> C -> A
         let token_a = Bytes::from_str("0x0001").unwrap();
@@ -531,8 +471,8 @@ mod tests {
                 creation_tx: tycho_common::Bytes::default(),
             };
 
-            protocol_comp.insert(pool_addr.clone(), pool_comp);
-            protocol_sim.insert(pool_addr.clone(), Box::new(MockProtocolSim));
+            protocol_comp.insert(pool_addr.clone(), Arc::new(pool_comp));
+            protocol_sim.insert(pool_addr.clone(), Arc::new(MockProtocolSim::new()));
         }
 
         // Create a valid arbitrage cycle path: A->B->C->A
@@ -546,12 +486,17 @@ mod tests {
         assert!(path.is_ok());
         let path = path.unwrap();
         assert_eq!(path.len(), 3);
+
+        // Swaps share the pool state of the market data instead of copying it
+        assert!(Arc::ptr_eq(&path[0].pool_comp, &protocol_comp[&pool1_addr]));
+        assert!(Arc::ptr_eq(&path[0].pool_sim, &protocol_sim[&pool1_addr]));
+        assert_eq!(Arc::strong_count(&protocol_comp[&pool1_addr]), 2);
     }
     #[test]
     fn test_path_builder_single_swap_fails_cycle_validation() {
         let mut graph = TradingGraph::new();
         let mut protocol_comp = HashMap::new();
-        let mut protocol_sim: HashMap<Bytes, Box<dyn ProtocolSim>> = HashMap::new();
+        let mut protocol_sim: HashMap<Bytes, Arc<dyn ProtocolSim>> = HashMap::new();
 
         // Create tokens
         let token_a = Bytes::from_str("0x0001").unwrap();
@@ -591,8 +536,8 @@ mod tests {
             creation_tx: tycho_common::Bytes::default(),
         };
 
-        protocol_comp.insert(pool_addr.clone(), pool_comp);
-        protocol_sim.insert(pool_addr, Box::new(MockProtocolSim));
+        protocol_comp.insert(pool_addr.clone(), Arc::new(pool_comp));
+        protocol_sim.insert(pool_addr, Arc::new(MockProtocolSim::new()));
 
         // Single swap should fail arbitrage cycle validation
         let path = PathBuilder::new()
@@ -609,11 +554,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_path_builder_multi_token_pool() {
+        let mut graph = TradingGraph::new();
+        let mut protocol_comp = HashMap::new();
+        let mut protocol_sim: HashMap<Bytes, Arc<dyn ProtocolSim>> = HashMap::new();
+
+        let token_a = Bytes::from_str("0x0001").unwrap();
+        let token_b = Bytes::from_str("0x0002").unwrap();
+        let token_c = Bytes::from_str("0x0003").unwrap();
+
+        // A tricrypto-style pool holding A, B and C, and a plain C/A pool
+        let tricrypto_addr = Bytes::from_str("0x1001").unwrap();
+        let pair_addr = Bytes::from_str("0x1002").unwrap();
+        for (pool_addr, tokens) in [
+            (&tricrypto_addr, vec![token_a.clone(), token_b.clone(), token_c.clone()]),
+            (&pair_addr, vec![token_c.clone(), token_a.clone()]),
+        ] {
+            let pool_comp = component(pool_addr, &tokens);
+
+            graph.add_protocol_component(pool_addr.clone(), pool_comp.clone()).unwrap();
+            protocol_comp.insert(pool_addr.clone(), Arc::new(pool_comp));
+            protocol_sim.insert(pool_addr.clone(), Arc::new(MockProtocolSim::new()));
+        }
+
+        // A -> C through the tricrypto pool skips token B, then C -> A
+        let a_to_c = graph
+            .pools_between_tokens([graph.find_token_id(&token_a).unwrap(), graph.find_token_id(&token_c).unwrap()])
+            .unwrap()
+            .iter()
+            .copied()
+            .find(|&pool_id| graph.get_pool(pool_id).unwrap().address() == &tricrypto_addr)
+            .unwrap();
+        let c_to_a = graph
+            .pools_between_tokens([graph.find_token_id(&token_c).unwrap(), graph.find_token_id(&token_a).unwrap()])
+            .unwrap()
+            .iter()
+            .copied()
+            .find(|&pool_id| graph.get_pool(pool_id).unwrap().address() == &pair_addr)
+            .unwrap();
+
+        let path = PathBuilder::new()
+            .with_edges(&[a_to_c, c_to_a])
+            .with_graph(&graph)
+            .with_protocol_components(&protocol_comp)
+            .with_protocol_simulations(&protocol_sim)
+            .build()
+            .unwrap();
+
+        assert_eq!(path[0].token_in().address, token_a);
+        assert_eq!(path[0].token_out().address, token_c);
+        assert_eq!(path[1].token_out().address, token_a);
+    }
+
     #[test]
     fn test_path_builder_missing_components() {
         let graph = TradingGraph::new();
         let protocol_comp = HashMap::new();
-        let protocol_sim: HashMap<Bytes, Box<dyn ProtocolSim>> = HashMap::new();
+        let protocol_sim: HashMap<Bytes, Arc<dyn ProtocolSim>> = HashMap::new();
 
         let result = PathBuilder::new()
             .with_edges(&[0])
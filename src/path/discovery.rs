@@ -0,0 +1,295 @@
+//! Cancellable path discovery with progress reporting.
+//!
+//! Discovering the paths of a large graph, e.g. of the initial Tycho snapshot,
+//! takes seconds to minutes. [`PathRepository::discover_paths`] runs to the
+//! end without feedback. A `DiscoveryMonitor` passed to
+//! [`PathRepository::discover_paths_monitored`] instead:
+//!
+//! - **Reports progress**: the stage, the paths found so far and the work left
+//!   in the stage, at most once per progress interval
+//! - **Stops on cancellation**: once its [`CancellationToken`] is cancelled,
+//!   discovery stops and the repository is left as it was before
+//!
+//! [`spawn_discovery`] runs the discovery on a copy of a shared repository in
+//! a blocking task and merges the found paths into the repository once the
+//! discovery completes, so block updates keep reading the repository meanwhile.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::path::{CancellationToken, DiscoveryMonitor};
+//!
+//! let cancellation = CancellationToken::new();
+//! let mut monitor = DiscoveryMonitor::new()
+//!     .with_cancellation(cancellation.clone())
+//!     .with_progress(|progress| tracing::info!(pool_paths = progress.pool_paths_found, "Discovering paths"));
+//!
+//! let outcome = repository.discover_paths_monitored(&graph, 0, token_count, 0, pool_count, &mut monitor);
+//! ```
+
+use crate::graph::SharedTradingGraph;
+use crate::path::PathRepository;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// Default minimum time between two progress reports.
+pub const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A flag to stop a running discovery, shared between its clones.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the discoveries watching this token or any of its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// The stage of a running discovery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoveryStage {
+    /// Searching token cycles of the given number of swaps
+    TokenPaths { path_length: usize },
+    /// Expanding token cycles into the pools trading them
+    PoolPaths,
+}
+
+/// Progress of a running discovery.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveryProgress {
+    /// The current stage
+    pub stage: DiscoveryStage,
+    /// Token paths found so far
+    pub token_paths_found: usize,
+    /// Pool paths found so far
+    pub pool_paths_found: usize,
+    /// Work items left in the stage: source tokens left to search from, or token paths left to expand
+    pub frontier_size: usize,
+    /// Time since the discovery started
+    pub elapsed: Duration,
+}
+
+/// How a discovery ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoveryOutcome {
+    /// Every path was discovered
+    Completed,
+    /// The discovery was cancelled and its paths discarded
+    Cancelled,
+}
+
+impl DiscoveryOutcome {
+    /// Whether every path was discovered.
+    pub fn is_completed(&self) -> bool {
+        matches!(self, DiscoveryOutcome::Completed)
+    }
+}
+
+/// Callback receiving discovery progress.
+type ProgressCallback<'a> = Box<dyn FnMut(&DiscoveryProgress) + Send + 'a>;
+
+/// Cancellation and progress reporting of one discovery.
+pub struct DiscoveryMonitor<'a> {
+    cancellation: Option<CancellationToken>,
+    on_progress: Option<ProgressCallback<'a>>,
+    progress_interval: Duration,
+    started_at: Instant,
+    last_report: Option<Instant>,
+    token_paths_found: usize,
+    pool_paths_found: usize,
+    cancelled: bool,
+}
+
+impl<'a> DiscoveryMonitor<'a> {
+    /// Create a monitor that never cancels and reports no progress.
+    pub fn new() -> Self {
+        Self {
+            cancellation: None,
+            on_progress: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
+            started_at: Instant::now(),
+            last_report: None,
+            token_paths_found: 0,
+            pool_paths_found: 0,
+            cancelled: false,
+        }
+    }
+
+    /// Stop the discovery once `cancellation` is cancelled.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Report progress to a callback, and once more when the discovery ends.
+    pub fn with_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: FnMut(&DiscoveryProgress) + Send + 'a,
+    {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Set the minimum time between two progress reports.
+    pub fn with_progress_interval(mut self, progress_interval: Duration) -> Self {
+        self.progress_interval = progress_interval;
+        self
+    }
+
+    /// Whether the discovery was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Restart the clock and the counters, at the start of a discovery.
+    pub(crate) fn start(&mut self) {
+        self.started_at = Instant::now();
+        self.last_report = None;
+        self.token_paths_found = 0;
+        self.pool_paths_found = 0;
+        self.cancelled = false;
+    }
+
+    /// Whether the discovery has to stop, checked before every step.
+    pub(crate) fn should_stop(&mut self) -> bool {
+        if !self.cancelled && self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            self.cancelled = true;
+        }
+        self.cancelled
+    }
+
+    /// Count a stored token path.
+    pub(crate) fn record_token_path(&mut self) {
+        self.token_paths_found += 1;
+    }
+
+    /// Count a stored pool path.
+    pub(crate) fn record_pool_path(&mut self) {
+        self.pool_paths_found += 1;
+    }
+
+    /// Report progress if the progress interval elapsed since the last report, or if `force`d.
+    pub(crate) fn report(&mut self, stage: DiscoveryStage, frontier_size: usize, force: bool) {
+        let Some(on_progress) = self.on_progress.as_mut() else {
+            return;
+        };
+        let due = !self
+            .last_report
+            .is_some_and(|last_report| last_report.elapsed() < self.progress_interval);
+        if !force && !due {
+            return;
+        }
+        self.last_report = Some(Instant::now());
+        on_progress(&DiscoveryProgress {
+            stage,
+            token_paths_found: self.token_paths_found,
+            pool_paths_found: self.pool_paths_found,
+            frontier_size,
+            elapsed: self.started_at.elapsed(),
+        });
+    }
+}
+
+impl Default for DiscoveryMonitor<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for DiscoveryMonitor<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiscoveryMonitor")
+            .field("cancellation", &self.cancellation)
+            .field("progress_interval", &self.progress_interval)
+            .field("token_paths_found", &self.token_paths_found)
+            .field("pool_paths_found", &self.pool_paths_found)
+            .field("cancelled", &self.cancelled)
+            .finish()
+    }
+}
+
+/// Discover paths in a background task and merge them into a shared repository.
+///
+/// The discovery runs on a copy of the repository against a snapshot of the
+/// graph, so the repository stays readable and writable meanwhile. Once it
+/// completes, the paths it found are merged into the repository against the
+/// graph current by then, skipping paths through removed pools and paths the
+/// repository found in the meantime. Cancelled discoveries merge nothing.
+///
+/// # Arguments
+///
+/// * `repository` - The repository to merge the found paths into
+/// * `graph` - The graph to discover paths from
+/// * `new_token_offset` - Starting index of newly added tokens
+/// * `new_token_count` - Number of newly added tokens
+/// * `new_pool_offset` - Starting index of newly added pools
+/// * `new_pool_count` - Number of newly added pools
+/// * `monitor` - Cancellation and progress reporting of the discovery
+///
+/// # Returns
+///
+/// A handle resolving to the outcome once the paths are merged
+pub fn spawn_discovery(
+    repository: Arc<RwLock<PathRepository>>,
+    graph: Arc<SharedTradingGraph>,
+    new_token_offset: usize,
+    new_token_count: usize,
+    new_pool_offset: usize,
+    new_pool_count: usize,
+    mut monitor: DiscoveryMonitor<'static>,
+) -> JoinHandle<DiscoveryOutcome> {
+    tokio::spawn(async move {
+        let mut discovered = repository.read().await.clone();
+        let (token_path_offset, pool_path_offset) = (discovered.token_paths.len(), discovered.pool_paths.len());
+        let snapshot = graph.snapshot();
+
+        let discovery = tokio::task::spawn_blocking(move || {
+            let outcome = discovered.discover_paths_monitored(
+                &snapshot,
+                new_token_offset,
+                new_token_count,
+                new_pool_offset,
+                new_pool_count,
+                &mut monitor,
+            );
+            (discovered, outcome)
+        });
+        let (discovered, outcome) = match discovery.await {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::error!(error = %e, "Background path discovery failed");
+                return DiscoveryOutcome::Cancelled;
+            }
+        };
+        if !outcome.is_completed() {
+            tracing::info!("Background path discovery cancelled, discarding its paths");
+            return outcome;
+        }
+
+        let merged_paths = repository.write().await.merge_discovered(
+            &graph.snapshot(),
+            &discovered,
+            token_path_offset,
+            pool_path_offset,
+        );
+        tracing::info!(merged_paths = merged_paths, "Merged background path discovery");
+        outcome
+    })
+}
@@ -3,12 +3,87 @@
 //! This module provides functionality for executing trading paths with specific amounts,
 //! calculating profits and losses, and managing execution metrics. It separates the
 //! concerns of path execution from path creation and optimization.
+//!
+//! Protocol simulations quote the amount a pool sends, which is not always the
+//! amount the next hop receives: fee-on-transfer tokens deduct a fee, and some
+//! protocols round amounts to their own granularity. A [`SwapAdjustment`]
+//! added with [`PathExecutor::with_adjustment`] corrects the output of every
+//! swap before it becomes the input of the next hop.
 
 use crate::errors::{PathError, Result};
-use crate::path::{Path, PathExt, SwapExt};
+use crate::path::{Path, PathExt, Swap, SwapExt};
+use crate::pricing::PriceOracle;
+use crate::simulation::CalldataCost;
+use crate::utils::convert;
 use num_bigint::{BigInt, BigUint};
 use num_traits::Zero;
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
+use tycho_common::Bytes;
+
+/// Decimals of the native token, in which gas and bribes are paid.
+const NATIVE_DECIMALS: u32 = 18;
+
+/// The largest transfer fee, 100%.
+const MAX_FEE_BPS: u64 = 10_000;
+
+/// An adjustment of the output of a swap before it is handed to the next hop.
+///
+/// Implemented for closures taking the index of the swap in the path, the
+/// swap and its simulated output.
+pub trait SwapAdjustment: Send + Sync {
+    /// The amount the next hop receives from a swap.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the swap in the path
+    /// * `swap` - The executed swap
+    /// * `amount_out` - The output of the swap, after the previous adjustments
+    fn adjust(&self, index: usize, swap: &Swap, amount_out: BigUint) -> BigUint;
+}
+
+impl<F> SwapAdjustment for F
+where
+    F: Fn(usize, &Swap, BigUint) -> BigUint + Send + Sync,
+{
+    fn adjust(&self, index: usize, swap: &Swap, amount_out: BigUint) -> BigUint {
+        self(index, swap, amount_out)
+    }
+}
+
+/// Transfer fees of fee-on-transfer tokens, deducted from the output of every swap selling them.
+#[derive(Debug, Clone, Default)]
+pub struct TransferFees {
+    fees_bps: HashMap<Bytes, u64>,
+}
+
+impl TransferFees {
+    /// Create an adjustment without transfer fees.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deduct `fee_bps` of every transfer of `token`, capped at 10000 BPS (100%).
+    pub fn with_fee(mut self, token: Bytes, fee_bps: u64) -> Self {
+        self.fees_bps.insert(token, fee_bps.min(MAX_FEE_BPS));
+        self
+    }
+
+    /// The transfer fee of a token in basis points, if it has one.
+    pub fn fee_bps(&self, token: &Bytes) -> Option<u64> {
+        self.fees_bps.get(token).copied()
+    }
+}
+
+impl SwapAdjustment for TransferFees {
+    fn adjust(&self, _index: usize, swap: &Swap, amount_out: BigUint) -> BigUint {
+        match self.fee_bps(&swap.token_out().address) {
+            Some(fee_bps) => &amount_out - &amount_out * fee_bps / MAX_FEE_BPS,
+            None => amount_out,
+        }
+    }
+}
 
 /// Executor for trading paths with specific input amounts.
 ///
@@ -19,6 +94,8 @@ pub struct PathExecutor {
     validate_limits: bool,
     /// Whether to collect detailed execution metrics
     collect_metrics: bool,
+    /// Adjustments of the output of every swap, applied in order
+    adjustments: Vec<Arc<dyn SwapAdjustment>>,
 }
 
 impl PathExecutor {
@@ -27,6 +104,7 @@ impl PathExecutor {
         Self {
             validate_limits: true,
             collect_metrics: false,
+            adjustments: Vec::new(),
         }
     }
 
@@ -37,6 +115,7 @@ impl PathExecutor {
         Self {
             validate_limits: false,
             collect_metrics: false,
+            adjustments: Vec::new(),
         }
     }
 
@@ -46,6 +125,22 @@ impl PathExecutor {
         self
     }
 
+    /// Adjust the output of every swap before it is handed to the next hop.
+    ///
+    /// Adjustments are applied in the order they were added, after every
+    /// `get_amount_out`, and the executed swaps record the adjusted output.
+    pub fn with_adjustment(mut self, adjustment: impl SwapAdjustment + 'static) -> Self {
+        self.adjustments.push(Arc::new(adjustment));
+        self
+    }
+
+    /// The output of a swap after every adjustment.
+    fn adjust(&self, index: usize, swap: &Swap, amount_out: BigUint) -> BigUint {
+        self.adjustments
+            .iter()
+            .fold(amount_out, |amount, adjustment| adjustment.adjust(index, swap, amount))
+    }
+
     /// Execute a path with a specific input amount.
     ///
     /// This method simulates the execution of each swap in the path sequentially,
@@ -97,16 +192,15 @@ impl PathExecutor {
                 }
             })?;
 
-            let executed_swap = SwapExt {
-                pool_comp: swap.pool_comp.clone(),
-                pool_sim: swap.pool_sim.clone(),
-                zero_for_one: swap.zero_for_one,
-                amount_in: swap_input,
-                amount_out: swap_result.amount.clone(),
-                gas: swap_result.gas.clone(),
-            };
+            let amount_out = self.adjust(index, swap, swap_result.amount);
+            let executed_swap = SwapExt::from_swap(
+                swap,
+                swap_input,
+                amount_out.clone(),
+                swap_result.gas.clone(),
+            );
 
-            current_amount = swap_result.amount;
+            current_amount = amount_out;
             total_gas += &swap_result.gas;
             
             tracing::trace!(
@@ -137,6 +231,71 @@ impl PathExecutor {
         Ok(path_ext)
     }
 
+    /// Calculate the input amount a path needs for a given output amount.
+    ///
+    /// Walks the path backwards, finding the input of every swap from the
+    /// output its successor needs after the adjustments, see [`Swap::get_amount_in`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The trading path to evaluate
+    /// * `amount_out` - The output amount of the final swap
+    ///
+    /// # Returns
+    ///
+    /// The input amount of the first swap
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The path is empty
+    /// - Any swap cannot deliver the output its successor needs
+    pub fn get_amount_in(&self, path: &Path, amount_out: &BigUint) -> Result<BigUint> {
+        if path.is_empty() {
+            return Err(PathError::EmptyPath.into());
+        }
+
+        let mut current_amount = amount_out.clone();
+        for (index, swap) in path.iter().enumerate().rev() {
+            current_amount = swap
+                .get_amount_in_adjusted(&current_amount, |amount| self.adjust(index, swap, amount))
+                .map_err(|e| PathError::ExtensionFailed {
+                reason: format!("Swap {} cannot deliver {}: {}", index, current_amount, e),
+            })?;
+        }
+
+        tracing::debug!(
+            path_length = path.len(),
+            output_amount = %amount_out,
+            input_amount = %current_amount,
+            "Evaluated path backwards"
+        );
+
+        Ok(current_amount)
+    }
+
+    /// Execute a path for an exact output amount.
+    ///
+    /// Finds the input amount with [`PathExecutor::get_amount_in`] and executes
+    /// the path forward with it, so the final output is at least `amount_out`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The trading path to execute
+    /// * `amount_out` - The output amount of the final swap
+    ///
+    /// # Returns
+    ///
+    /// A `PathExt` containing the executed swaps, with the output of the final swap at least `amount_out`
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`PathExecutor::get_amount_in`] and [`PathExecutor::execute_with_amount`].
+    pub fn execute_exact_out(&self, path: &Path, amount_out: &BigUint) -> Result<PathExt> {
+        let amount_in = self.get_amount_in(path, amount_out)?;
+        self.execute_with_amount(path, amount_in)
+    }
+
     /// Calculate the profit/loss for a given input amount without full execution.
     ///
     /// This is a more efficient method when you only need the profit calculation
@@ -155,7 +314,7 @@ impl PathExecutor {
     }
 
     /// Validate that a swap can handle the requested input amount.
-    fn validate_swap_limits(&self, swap: &crate::path::Swap, amount_in: &BigUint) -> Result<()> {
+    fn validate_swap_limits(&self, swap: &Swap, amount_in: &BigUint) -> Result<()> {
         let (max_in, _max_out) = swap.get_limits()?;
 
         if max_in < *amount_in {
@@ -217,8 +376,8 @@ impl ProfitCalculator {
             return Ok(0.0);
         }
 
-        let input_f64 = Self::biguint_to_f64(&first_swap.amount_in);
-        let output_f64 = Self::biguint_to_f64(&last_swap.amount_out);
+        let input_f64 = convert::biguint_to_f64(&first_swap.amount_in);
+        let output_f64 = convert::biguint_to_f64(&last_swap.amount_out);
 
         let profit_percentage = (output_f64 - input_f64) / input_f64;
         Ok(profit_percentage)
@@ -258,25 +417,122 @@ impl ProfitCalculator {
 
         let total_gas: BigUint = path_ext.iter().map(|s| &s.gas).sum();
         let gas_cost_wei = total_gas * gas_price;
-        let gas_cost_eth = Self::biguint_to_f64(&gas_cost_wei) / 1e18; // Convert wei to ETH
+        let gas_cost_eth = convert::biguint_to_decimal_f64(&gas_cost_wei, 18);
         
-        let profit_f64 = Self::bigint_to_f64(&profit);
+        let profit_f64 = convert::bigint_to_f64(&profit);
         let profit_in_eth = profit_f64 * token_price_in_eth / 1e18; // Assuming token has 18 decimals
 
         Ok(profit_in_eth > gas_cost_eth)
     }
 
-    /// Convert BigUint to f64 for calculations.
-    fn biguint_to_f64(value: &BigUint) -> f64 {
-        // This is a simplified conversion that may lose precision for very large numbers
-        // In production, you might want to use a more sophisticated conversion
-        value.to_string().parse().unwrap_or(0.0)
+    /// Check whether the profit after gas exceeds a multiple of the gas cost.
+    ///
+    /// Thresholds in basis points ignore that gas costs vary widely with the
+    /// path length and the base fee. Evaluated with the gas used in the
+    /// simulation, a multiple of the gas cost scales the required profit with
+    /// the cost of landing the trade. A multiple of 0 only requires a profit.
+    ///
+    /// # Arguments
+    ///
+    /// * `gross_profit` - The profit before gas, in wei
+    /// * `gas_cost` - The gas cost of the trade, in wei
+    /// * `min_gas_multiple` - The multiple of the gas cost the net profit has to exceed
+    pub fn exceeds_gas_multiple(gross_profit: &BigUint, gas_cost: &BigUint, min_gas_multiple: f64) -> bool {
+        if gross_profit <= gas_cost {
+            return false;
+        }
+        let net_profit = convert::biguint_to_f64(&(gross_profit - gas_cost));
+        net_profit > min_gas_multiple.max(0.0) * convert::biguint_to_f64(gas_cost)
     }
 
-    /// Convert BigInt to f64 for calculations.
-    fn bigint_to_f64(value: &BigInt) -> f64 {
-        // This is a simplified conversion that may lose precision for very large numbers
-        value.to_string().parse().unwrap_or(0.0)
+    /// Estimate the gas cost of an executed path including its calldata.
+    ///
+    /// The gas of the swaps covers their execution only. The router call
+    /// also pays the base cost of a transaction and its calldata, which
+    /// grows with every hop and can exceed the profit of long paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `path_ext` - The executed path
+    /// * `calldata` - The calldata of the router call executing the path
+    /// * `gas_price` - The gas price in wei per gas unit
+    ///
+    /// # Returns
+    ///
+    /// The gas cost in wei
+    pub fn estimate_gas_cost(path_ext: &PathExt, calldata: &CalldataCost, gas_price: &BigUint) -> BigUint {
+        let swap_gas: BigUint = path_ext.iter().map(|s| &s.gas).sum();
+        (swap_gas + BigUint::from(calldata.intrinsic_gas())) * gas_price
+    }
+
+    /// Break down the profit of an executed path and value it in USD.
+    ///
+    /// # Arguments
+    ///
+    /// * `path_ext` - The executed path
+    /// * `gas_price` - The gas price in wei per gas unit
+    /// * `bribe_native` - The bribe paid to the builder in wei
+    /// * `native_token` - The address gas and bribes are priced with
+    /// * `oracle` - The source of USD prices
+    ///
+    /// # Returns
+    ///
+    /// The gross profit in start token units, the gas cost and bribe in wei,
+    /// and the net profit after gas and bribe in USD
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty or the oracle has no price for
+    /// the start token or the native token
+    pub fn calculate_profit_breakdown(
+        path_ext: &PathExt,
+        gas_price: &BigUint,
+        bribe_native: &BigUint,
+        native_token: &Bytes,
+        oracle: &dyn PriceOracle,
+    ) -> Result<ProfitBreakdown> {
+        let start_token = path_ext.first().ok_or(PathError::EmptyPath)?.token_in();
+        let usd_price = |token: &Bytes| {
+            oracle
+                .usd_price(token)
+                .ok_or_else(|| PathError::PriceUnavailable { token: token.clone() })
+        };
+        let start_token_price = usd_price(&start_token.address)?;
+        let native_price = usd_price(native_token)?;
+
+        let gross_token = Self::calculate_absolute_profit(path_ext)?;
+        let total_gas: BigUint = path_ext.iter().map(|s| &s.gas).sum();
+        let gas_native = total_gas * gas_price;
+
+        let gross_usd = convert::bigint_to_f64(&gross_token) / 10f64.powi(start_token.decimals as i32) * start_token_price;
+        let costs_usd = convert::biguint_to_decimal_f64(&(&gas_native + bribe_native), NATIVE_DECIMALS) * native_price;
+
+        Ok(ProfitBreakdown {
+            gross_token,
+            gas_native,
+            bribe_native: bribe_native.clone(),
+            net_usd: gross_usd - costs_usd,
+        })
+    }
+}
+
+/// Profit of an executed path with its costs, valued in USD.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfitBreakdown {
+    /// Output minus input, in base units of the start token
+    pub gross_token: BigInt,
+    /// Gas cost of the path in wei
+    pub gas_native: BigUint,
+    /// Bribe paid to the builder in wei
+    pub bribe_native: BigUint,
+    /// Gross profit minus gas cost and bribe, in USD
+    pub net_usd: f64,
+}
+
+impl ProfitBreakdown {
+    /// Check whether the net profit reaches a threshold in USD.
+    pub fn meets_min_net_usd(&self, min_net_usd: f64) -> bool {
+        self.net_usd >= min_net_usd
     }
 }
 
@@ -337,8 +593,8 @@ impl ExecutionMetrics {
             return 0.0;
         }
 
-        let initial_f64 = ProfitCalculator::biguint_to_f64(&self.initial_amount);
-        let final_f64 = ProfitCalculator::biguint_to_f64(&self.final_amount);
+        let initial_f64 = convert::biguint_to_f64(&self.initial_amount);
+        let final_f64 = convert::biguint_to_f64(&self.final_amount);
 
         (final_f64 - initial_f64) / initial_f64
     }
@@ -361,93 +617,14 @@ impl fmt::Display for ExecutionMetrics {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::MockProtocolSim;
     use crate::path::{Path, Swap};
     use std::collections::HashMap;
+    use std::sync::Arc;
     use tycho_common::Bytes;
     use tycho_simulation::protocol::models::ProtocolComponent;
-    use tycho_simulation::protocol::state::ProtocolSim;
     use std::str::FromStr;
 
-    // Mock ProtocolSim for testing
-    #[derive(Debug, Clone)]
-    struct MockProtocolSim {
-        multiplier: f64,
-    }
-
-    impl MockProtocolSim {
-        fn new(multiplier: f64) -> Self {
-            Self { multiplier }
-        }
-    }
-
-    impl ProtocolSim for MockProtocolSim {
-        fn clone_box(&self) -> Box<dyn ProtocolSim> {
-            Box::new(self.clone())
-        }
-
-        fn fee(&self) -> f64 {
-            0.003
-        }
-
-        fn spot_price(
-            &self,
-            _token_in: &tycho_simulation::models::Token,
-            _token_out: &tycho_simulation::models::Token,
-        ) -> std::result::Result<f64, tycho_simulation::protocol::errors::SimulationError> {
-            Ok(self.multiplier)
-        }
-
-        fn get_amount_out(
-            &self,
-            amount_in: BigUint,
-            _token_in: &tycho_simulation::models::Token,
-            _token_out: &tycho_simulation::models::Token,
-        ) -> std::result::Result<tycho_simulation::protocol::models::GetAmountOutResult, tycho_simulation::protocol::errors::SimulationError> {
-            let amount_out = if self.multiplier >= 1.0 {
-                &amount_in * BigUint::from((self.multiplier * 1000.0) as u32) / BigUint::from(1000u32)
-            } else {
-                &amount_in * BigUint::from((self.multiplier * 1000.0) as u32) / BigUint::from(1000u32)
-            };
-
-            Ok(tycho_simulation::protocol::models::GetAmountOutResult {
-                amount: amount_out,
-                gas: BigUint::from(21000u32),
-                new_state: Box::new(self.clone()),
-            })
-        }
-
-        fn get_limits(
-            &self,
-            _token_in: Bytes,
-            _token_out: Bytes,
-        ) -> std::result::Result<(BigUint, BigUint), tycho_simulation::protocol::errors::SimulationError> {
-            Ok((BigUint::from(1000000u32), BigUint::from(1000000u32)))
-        }
-
-        fn delta_transition(
-            &mut self,
-            _delta: tycho_common::dto::ProtocolStateDelta,
-            _tokens: &std::collections::HashMap<Bytes, tycho_simulation::models::Token>,
-            _balances: &tycho_simulation::models::Balances,
-        ) -> std::result::Result<(), tycho_simulation::protocol::errors::TransitionError<String>> {
-            Ok(())
-        }
-
-        fn as_any(&self) -> &(dyn std::any::Any + 'static) {
-            self
-        }
-
-        fn as_any_mut(&mut self) -> &mut (dyn std::any::Any + 'static) {
-            self
-        }
-
-        fn eq(&self, other: &(dyn ProtocolSim + 'static)) -> bool {
-            other.as_any().downcast_ref::<MockProtocolSim>()
-                .map(|other| (self.multiplier - other.multiplier).abs() < f64::EPSILON)
-                .unwrap_or(false)
-        }
-    }
-
     fn create_mock_swap(multiplier: f64) -> Swap {
         let token_a = Bytes::from_str("0x0001").unwrap();
         let token_b = Bytes::from_str("0x0002").unwrap();
@@ -479,11 +656,7 @@ mod tests {
             creation_tx: tycho_common::Bytes::default(),
         };
 
-        Swap {
-            pool_comp,
-            pool_sim: Box::new(MockProtocolSim::new(multiplier)),
-            zero_for_one: true,
-        }
+        Swap::new(pool_comp, Arc::new(MockProtocolSim::new().with_rate(multiplier)), &token_a, &token_b).unwrap()
     }
 
     #[test]
@@ -532,6 +705,56 @@ mod tests {
         assert!((profit_pct - 0.2).abs() < 0.01);
     }
 
+    #[test]
+    fn test_exceeds_gas_multiple() {
+        let gas_cost = BigUint::from(1_000u32);
+        assert!(ProfitCalculator::exceeds_gas_multiple(&BigUint::from(1_001u32), &gas_cost, 0.0));
+        assert!(!ProfitCalculator::exceeds_gas_multiple(&BigUint::from(1_000u32), &gas_cost, 0.0));
+        // A net profit of 2000 has to exceed twice the gas cost
+        assert!(!ProfitCalculator::exceeds_gas_multiple(&BigUint::from(3_000u32), &gas_cost, 2.0));
+        assert!(ProfitCalculator::exceeds_gas_multiple(&BigUint::from(3_001u32), &gas_cost, 2.0));
+    }
+
+    #[test]
+    fn test_gas_cost_includes_calldata() {
+        let path = Path(vec![create_mock_swap(1.1)]);
+        let path_ext = PathExecutor::new().execute_with_amount(&path, BigUint::from(1000u32)).unwrap();
+        let swap_gas: BigUint = path_ext.iter().map(|s| &s.gas).sum();
+        let calldata = CalldataCost::from_calldata(&[0, 1]);
+
+        let gas_cost = ProfitCalculator::estimate_gas_cost(&path_ext, &calldata, &BigUint::from(2u32));
+        assert_eq!(gas_cost, (swap_gas + BigUint::from(21_000u32 + 4 + 16)) * BigUint::from(2u32));
+    }
+
+    #[test]
+    fn test_profit_breakdown_in_usd() {
+        let path = Path(vec![create_mock_swap(1.1)]);
+        let path_ext = PathExecutor::new().execute_with_amount(&path, BigUint::from(1000u32)).unwrap();
+        let token_a = Bytes::from_str("0x0001").unwrap();
+        let native = Bytes::from_str("0x0002").unwrap();
+
+        // Prices of 1e16 USD per token make one base unit worth one cent
+        let oracle = crate::pricing::StaticPriceOracle::new()
+            .with_price(token_a, 1e16)
+            .with_price(native.clone(), 1e16);
+        let breakdown = ProfitCalculator::calculate_profit_breakdown(
+            &path_ext,
+            &BigUint::from(1u32),
+            &BigUint::from(50u32),
+            &native,
+            &oracle,
+        )
+        .unwrap();
+
+        assert_eq!(breakdown.gross_token, BigInt::from(100));
+        assert_eq!(breakdown.gas_native, BigUint::from(21_000u32));
+        assert!((breakdown.net_usd - (1.0 - 210.0 - 0.5)).abs() < 1e-6);
+        assert!(!breakdown.meets_min_net_usd(0.0));
+
+        let unpriced = Bytes::from_str("0x0003").unwrap();
+        assert!(ProfitCalculator::calculate_profit_breakdown(&path_ext, &BigUint::from(1u32), &BigUint::from(0u32), &unpriced, &oracle).is_err());
+    }
+
     #[test]
     fn test_execution_metrics() {
         let swap = create_mock_swap(1.1);
@@ -547,6 +770,72 @@ mod tests {
         assert!(metrics.final_amount > BigUint::from(1000u32));
     }
 
+    #[test]
+    fn test_evaluate_amounts_matches_single_evaluations() {
+        let path = Path(vec![create_mock_swap(1.1), create_mock_swap(1.2)]);
+        let amounts: Vec<BigUint> = [1000u32, 5000, 1000, 2_000_000].into_iter().map(BigUint::from).collect();
+
+        let profits = path.evaluate_amounts(&amounts);
+        assert_eq!(profits.len(), amounts.len());
+        for (amount, profit) in amounts.iter().zip(&profits).take(3) {
+            assert_eq!(*profit, path.calculate_profit_loss(amount.clone()).unwrap());
+        }
+        // Amounts beyond the pool limits evaluate to zero
+        assert!(path.calculate_profit_loss(amounts[3].clone()).is_err());
+        assert_eq!(profits[3], BigInt::from(0));
+    }
+
+    #[test]
+    fn test_amounts_beyond_limits_are_clamped() {
+        let path = Path(vec![create_mock_swap(1.1), create_mock_swap(1.2)]);
+        let within = path.calculate_profit_loss_clamped(BigUint::from(1000u32)).unwrap();
+        assert!(!within.limited);
+        assert_eq!(within.amount_in, BigUint::from(1000u32));
+
+        // The output limit of the last pool binds the input
+        let clamped = path.calculate_profit_loss_clamped(BigUint::from(2_000_000u32)).unwrap();
+        assert!(clamped.limited);
+        assert!(clamped.profit > BigInt::from(0));
+        assert_eq!(clamped.profit, path.calculate_profit_loss(clamped.amount_in.clone()).unwrap());
+        assert!(path.calculate_profit_loss(&clamped.amount_in + 1u32).is_err());
+    }
+
+    #[test]
+    fn test_exact_out_execution_walks_the_path_backwards() {
+        let path = Path(vec![create_mock_swap(1.1), create_mock_swap(1.2)]);
+        let executor = PathExecutor::new();
+        let amount_out = BigUint::from(1320u32);
+
+        let amount_in = executor.get_amount_in(&path, &amount_out).unwrap();
+        let path_ext = executor.execute_exact_out(&path, &amount_out).unwrap();
+        assert_eq!(path_ext[0].amount_in, amount_in);
+        assert!(path_ext.last().unwrap().amount_out >= amount_out);
+        // One unit less input falls short of the output
+        let short = executor.execute_with_amount(&path, amount_in - 1u32).unwrap();
+        assert!(short.last().unwrap().amount_out < amount_out);
+
+        assert!(executor.get_amount_in(&path, &BigUint::from(10_000_000u32)).is_err());
+        assert!(executor.get_amount_in(&Path(vec![]), &amount_out).is_err());
+    }
+
+    #[test]
+    fn test_adjustments_correct_intermediate_amounts() {
+        let path = Path(vec![create_mock_swap(1.0), create_mock_swap(1.0)]);
+        let token_b = Bytes::from_str("0x0002").unwrap();
+        let executor = PathExecutor::new().with_adjustment(TransferFees::new().with_fee(token_b, 100));
+
+        let path_ext = executor.execute_with_amount(&path, BigUint::from(10_000u32)).unwrap();
+        assert_eq!(path_ext[0].amount_out, BigUint::from(9_900u32));
+        assert_eq!(path_ext[1].amount_in, BigUint::from(9_900u32));
+        assert_eq!(path_ext[1].amount_out, BigUint::from(9_801u32));
+
+        // Rounding down to a granularity of 100 units, after the transfer fee
+        let rounding = executor.with_adjustment(|_: usize, _: &Swap, amount: BigUint| &amount - &amount % 100u32);
+        let path_ext = rounding.execute_with_amount(&path, BigUint::from(10_000u32)).unwrap();
+        assert_eq!(path_ext[1].amount_out, BigUint::from(9_800u32));
+        assert!(rounding.execute_exact_out(&path, &BigUint::from(9_800u32)).unwrap().last().unwrap().amount_out >= BigUint::from(9_800u32));
+    }
+
     #[test]
     fn test_empty_path_execution() {
         let path = Path(vec![]);
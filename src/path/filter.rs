@@ -0,0 +1,174 @@
+//! Cheap filters of candidate paths before optimization.
+//!
+//! Optimizing a path simulates it for many input amounts, while the product of
+//! the spot prices along a path only takes one virtual call per swap. A path
+//! whose spot price product does not exceed the minimum profit cannot become
+//! profitable for any input amount, so it is dropped before optimization.
+
+use crate::path::{Path, PoolVersions, SpotPriceCache};
+
+/// A predicate deciding which candidate paths are optimized.
+pub trait PathFilter {
+    /// Whether a path passes the filter.
+    fn accepts(&self, path: &Path) -> bool;
+
+    /// Keep only the paths passing the filter.
+    ///
+    /// # Returns
+    ///
+    /// The number of removed paths
+    fn retain(&self, paths: &mut Vec<Path>) -> usize {
+        let initial_count = paths.len();
+        paths.retain(|path| self.accepts(path));
+        initial_count - paths.len()
+    }
+}
+
+/// Accepts paths whose spot price product exceeds a minimum.
+///
+/// Paths whose spot prices cannot be calculated are rejected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotPricePrefilter {
+    /// The spot price product a path has to exceed
+    pub min_product: f64,
+}
+
+impl SpotPricePrefilter {
+    /// Create a filter of paths whose spot price product exceeds `min_product`.
+    pub fn new(min_product: f64) -> Self {
+        Self { min_product }
+    }
+
+    /// Create a filter of paths promising more than `min_profit_bps` basis points.
+    pub fn from_min_profit_bps(min_profit_bps: u64) -> Self {
+        Self::new(1.0 + min_profit_bps as f64 / 10_000.0)
+    }
+
+    /// Read spot prices from a cache, only asking pools that changed.
+    pub fn with_cache<'a>(self, cache: &'a SpotPriceCache, versions: &'a PoolVersions) -> CachedSpotPricePrefilter<'a> {
+        CachedSpotPricePrefilter {
+            min_product: self.min_product,
+            cache,
+            versions,
+        }
+    }
+}
+
+impl PathFilter for SpotPricePrefilter {
+    fn accepts(&self, path: &Path) -> bool {
+        match path.spot_price_product() {
+            Ok(product) => product > self.min_product,
+            Err(e) => {
+                tracing::debug!(
+                    error = %e,
+                    "Failed to calculate spot price product, filtering out path"
+                );
+                false
+            }
+        }
+    }
+}
+
+/// A [`SpotPricePrefilter`] reading spot prices from a [`SpotPriceCache`].
+#[derive(Debug, Clone, Copy)]
+pub struct CachedSpotPricePrefilter<'a> {
+    /// The spot price product a path has to exceed
+    pub min_product: f64,
+    cache: &'a SpotPriceCache,
+    versions: &'a PoolVersions,
+}
+
+impl PathFilter for CachedSpotPricePrefilter<'_> {
+    fn accepts(&self, path: &Path) -> bool {
+        match self.cache.spot_price_product(path, self.versions) {
+            Ok(product) => product > self.min_product,
+            Err(e) => {
+                tracing::debug!(
+                    error = %e,
+                    "Failed to calculate spot price product, filtering out path"
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Accepts paths whose spot price product after pool fees exceeds a minimum.
+///
+/// Use this filter with simulations whose spot prices exclude the pool fee:
+/// the spot price of every swap is reduced by the fee of its pool.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeAdjustedSpotPricePrefilter {
+    /// The fee-adjusted spot price product a path has to exceed
+    pub min_product: f64,
+}
+
+impl FeeAdjustedSpotPricePrefilter {
+    /// Create a filter of paths whose fee-adjusted spot price product exceeds `min_product`.
+    pub fn new(min_product: f64) -> Self {
+        Self { min_product }
+    }
+
+    /// Create a filter of paths promising more than `min_profit_bps` basis points after fees.
+    pub fn from_min_profit_bps(min_profit_bps: u64) -> Self {
+        Self::new(1.0 + min_profit_bps as f64 / 10_000.0)
+    }
+
+    /// The product of the spot prices along a path, each reduced by its pool fee.
+    fn fee_adjusted_product(path: &Path) -> crate::errors::Result<f64> {
+        path.iter().try_fold(1.0, |product, swap| {
+            Ok(product * swap.spot_price()? * (1.0 - swap.pool_sim.fee()))
+        })
+    }
+}
+
+impl PathFilter for FeeAdjustedSpotPricePrefilter {
+    fn accepts(&self, path: &Path) -> bool {
+        match Self::fee_adjusted_product(path) {
+            Ok(product) => product > self.min_product,
+            Err(e) => {
+                tracing::debug!(
+                    error = %e,
+                    "Failed to calculate fee-adjusted spot price product, filtering out path"
+                );
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{cyclic_path, MockProtocolSim};
+    use std::str::FromStr;
+    use tycho_common::Bytes;
+
+    fn path(sim: &MockProtocolSim) -> Path {
+        let tokens: Vec<Bytes> = ["0x0001", "0x0002"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        cyclic_path(&tokens, &Bytes::from_str("0x1001").unwrap(), sim).unwrap()
+    }
+
+    #[test]
+    fn test_spot_price_prefilter_keeps_paths_above_the_minimum_profit() {
+        // 1.01^2 is a profit of about 201 bps
+        let mut paths = vec![path(&MockProtocolSim::new().with_rate(1.01)), path(&MockProtocolSim::new())];
+        assert_eq!(SpotPricePrefilter::from_min_profit_bps(100).retain(&mut paths), 1);
+        assert_eq!(paths.len(), 1);
+        assert!(!SpotPricePrefilter::from_min_profit_bps(250).accepts(&paths[0]));
+
+        let (cache, versions) = (SpotPriceCache::new(), PoolVersions::new());
+        let cached = SpotPricePrefilter::from_min_profit_bps(100).with_cache(&cache, &versions);
+        assert!(cached.accepts(&paths[0]) && cached.accepts(&paths[0]));
+        assert_eq!(cache.hits(), 2);
+    }
+
+    #[test]
+    fn test_fee_adjusted_prefilter_deducts_pool_fees() {
+        // The mock's spot price already includes its fee, so the fee is deducted twice
+        let path = path(&MockProtocolSim::new().with_rate(1.02).with_fee(0.01));
+        assert!(SpotPricePrefilter::from_min_profit_bps(100).accepts(&path));
+        assert!(!FeeAdjustedSpotPricePrefilter::from_min_profit_bps(100).accepts(&path));
+        assert!(FeeAdjustedSpotPricePrefilter::new(0.99).accepts(&path));
+    }
+}
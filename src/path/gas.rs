@@ -0,0 +1,128 @@
+//! Per-protocol gas estimates of swaps.
+//!
+//! Protocol simulations report the gas of a swap in their own way: some
+//! estimate it closely, others report a constant or nothing at all, and none
+//! of them account for the router overhead of a hop. Gas differs a lot
+//! between protocols, from about 100k for a Uniswap V2 swap to 300k and more
+//! for a Curve swap, so ranking paths before they are simulated needs a
+//! better estimate than the protocol simulations provide.
+//!
+//! A `ProtocolGasTable` maps Tycho protocol systems to the gas of one swap.
+//! The defaults ship with the crate and [`ArbitrageSettings::protocol_gas`](crate::config::ArbitrageSettings::protocol_gas)
+//! overrides or extends them. Real gas usage from simulations replaces these
+//! estimates once it is available.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::path::ProtocolGasTable;
+//!
+//! let table = ProtocolGasTable::default().with_swap_gas("vm:curve", 350_000);
+//! let gas = table.path_gas(&path);
+//! ```
+
+use crate::path::{Path, PathExt};
+use std::collections::BTreeMap;
+
+/// Gas of a swap through a protocol without an entry.
+pub const DEFAULT_SWAP_GAS: u64 = 150_000;
+
+/// Gas of a swap by protocol system, shipped as defaults.
+const DEFAULT_PROTOCOL_GAS: [(&str, u64); 9] = [
+    ("uniswap_v2", 100_000),
+    ("sushiswap_v2", 100_000),
+    ("pancakeswap_v2", 100_000),
+    ("uniswap_v3", 140_000),
+    ("pancakeswap_v3", 140_000),
+    ("uniswap_v4", 130_000),
+    ("ekubo_v2", 130_000),
+    ("vm:balancer_v2", 180_000),
+    ("vm:curve", 300_000),
+];
+
+/// Estimated gas of one swap, by Tycho protocol system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolGasTable {
+    swap_gas: BTreeMap<String, u64>,
+    default_swap_gas: u64,
+}
+
+impl ProtocolGasTable {
+    /// Create a table without entries, estimating every swap at `default_swap_gas`.
+    pub fn empty(default_swap_gas: u64) -> Self {
+        Self {
+            swap_gas: BTreeMap::new(),
+            default_swap_gas,
+        }
+    }
+
+    /// Set the gas of a swap through a protocol system.
+    pub fn with_swap_gas(mut self, protocol_system: impl Into<String>, gas: u64) -> Self {
+        self.swap_gas.insert(protocol_system.into(), gas);
+        self
+    }
+
+    /// Set the gas of swaps through protocol systems without an entry.
+    pub fn with_default_swap_gas(mut self, default_swap_gas: u64) -> Self {
+        self.default_swap_gas = default_swap_gas;
+        self
+    }
+
+    /// Override the entries of the table, keeping all others.
+    pub fn with_overrides<'a, I>(self, overrides: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a String, &'a u64)>,
+    {
+        overrides
+            .into_iter()
+            .fold(self, |table, (protocol_system, gas)| table.with_swap_gas(protocol_system.clone(), *gas))
+    }
+
+    /// The estimated gas of a swap through a protocol system.
+    pub fn swap_gas(&self, protocol_system: &str) -> u64 {
+        self.swap_gas.get(protocol_system).copied().unwrap_or(self.default_swap_gas)
+    }
+
+    /// The estimated gas of all swaps of a path.
+    pub fn path_gas(&self, path: &Path) -> u64 {
+        path.iter().map(|swap| self.swap_gas(&swap.pool_comp.protocol_system)).sum()
+    }
+
+    /// The estimated gas of all swaps of an executed path.
+    pub fn path_ext_gas(&self, path: &PathExt) -> u64 {
+        path.iter().map(|swap| self.swap_gas(&swap.pool_comp.protocol_system)).sum()
+    }
+
+    /// The protocol systems with an entry and their gas.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.swap_gas.iter().map(|(protocol_system, gas)| (protocol_system.as_str(), *gas))
+    }
+}
+
+impl Default for ProtocolGasTable {
+    /// The gas of the protocols shipped with the crate.
+    fn default() -> Self {
+        DEFAULT_PROTOCOL_GAS
+            .iter()
+            .fold(Self::empty(DEFAULT_SWAP_GAS), |table, (protocol_system, gas)| {
+                table.with_swap_gas(*protocol_system, *gas)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_replace_shipped_defaults() {
+        let overrides = BTreeMap::from([("vm:curve".to_string(), 350_000u64), ("fluid_v1".to_string(), 200_000u64)]);
+        let table = ProtocolGasTable::default().with_overrides(&overrides);
+
+        assert_eq!(table.swap_gas("uniswap_v2"), 100_000);
+        assert_eq!(table.swap_gas("vm:curve"), 350_000);
+        assert_eq!(table.swap_gas("fluid_v1"), 200_000);
+        assert_eq!(table.swap_gas("unknown"), DEFAULT_SWAP_GAS);
+        assert_eq!(ProtocolGasTable::empty(1).swap_gas("uniswap_v2"), 1);
+    }
+}
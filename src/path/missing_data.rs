@@ -0,0 +1,215 @@
+//! Tracking of pools whose protocol data is missing during path building.
+//!
+//! A path is built from the protocol component and the simulation state of
+//! every pool it trades on. The stream can deliver a component before the
+//! state of its pool, or a state whose component failed to decode, and
+//! [`PathBuilder::build`](crate::path::PathBuilder::build) fails for every path
+//! through such a pool. Without tracking, the repository skips these
+//! paths in every block without anyone noticing when, or whether, the data
+//! arrives.
+//!
+//! `MissingDataTracker` records the pools that failed path building for
+//! missing data. The market data layer takes the pools to request with
+//! [`MissingDataTracker::take_requests`], and once their data arrived,
+//! [`MissingDataTracker::recover`] returns the pools whose paths can be built
+//! again, to search them as if they were updated.
+
+use crate::errors::{ArbitrageError, PathError};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tycho_common::Bytes;
+use tycho_simulation::protocol::{models::ProtocolComponent, state::ProtocolSim};
+
+/// The protocol data a pool is missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingData {
+    /// The protocol component, e.g. because it failed to decode
+    Component,
+    /// The simulation state, e.g. because it has not been streamed yet
+    Simulation,
+}
+
+impl MissingData {
+    /// The pool and the missing data of a path building error, if it was caused by missing data.
+    pub fn from_error(error: &ArbitrageError) -> Option<(Bytes, Self)> {
+        match error {
+            ArbitrageError::Path(PathError::ProtocolComponentNotFound { pool }) => {
+                Some((pool.clone(), MissingData::Component))
+            }
+            ArbitrageError::Path(PathError::ProtocolSimulationNotFound { pool }) => {
+                Some((pool.clone(), MissingData::Simulation))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A pool awaiting its protocol data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingPool {
+    /// The data the pool was last found missing
+    pub missing: MissingData,
+    /// Path builds that failed because of the pool
+    pub skipped_builds: u64,
+    /// When the data was first found missing
+    pub missing_since: Instant,
+    /// Whether the data was handed out for requesting
+    pub requested: bool,
+}
+
+/// Pools whose paths cannot be built until their protocol data arrives.
+#[derive(Debug, Default)]
+pub struct MissingDataTracker {
+    pools: RwLock<HashMap<Bytes, MissingPool>>,
+}
+
+impl MissingDataTracker {
+    /// Create an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a path build that failed because a pool is missing data.
+    pub fn record(&self, pool: Bytes, missing: MissingData) {
+        let Ok(mut pools) = self.pools.write() else {
+            return;
+        };
+        let entry = pools.entry(pool).or_insert_with_key(|pool| {
+            tracing::debug!(
+                pool = %pool,
+                missing = ?missing,
+                "Pool is missing protocol data, its paths are skipped until the data arrives"
+            );
+            MissingPool {
+                missing,
+                skipped_builds: 0,
+                missing_since: Instant::now(),
+                requested: false,
+            }
+        });
+        entry.missing = missing;
+        entry.skipped_builds += 1;
+    }
+
+    /// Record the failure of a path build if it was caused by missing data.
+    ///
+    /// # Returns
+    ///
+    /// Whether the failure was caused by missing data
+    pub fn record_error(&self, error: &ArbitrageError) -> bool {
+        match MissingData::from_error(error) {
+            Some((pool, missing)) => {
+                self.record(pool, missing);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The pools awaiting data that were not handed out yet, marking them as requested.
+    ///
+    /// Each pool is handed out once, so a market data layer fetching the data
+    /// requests every pool only once, however many blocks it stays missing.
+    pub fn take_requests(&self) -> Vec<(Bytes, MissingData)> {
+        let Ok(mut pools) = self.pools.write() else {
+            return Vec::new();
+        };
+        pools
+            .iter_mut()
+            .filter(|(_, entry)| !entry.requested)
+            .map(|(pool, entry)| {
+                entry.requested = true;
+                (pool.clone(), entry.missing)
+            })
+            .collect()
+    }
+
+    /// Stop tracking the pools whose data has arrived.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_components` - The current protocol components
+    /// * `protocol_simulations` - The current simulation states
+    ///
+    /// # Returns
+    ///
+    /// The pools whose paths can be built again
+    pub fn recover(
+        &self,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
+    ) -> Vec<Bytes> {
+        let Ok(mut pools) = self.pools.write() else {
+            return Vec::new();
+        };
+        let recovered: Vec<Bytes> = pools
+            .keys()
+            .filter(|pool| protocol_components.contains_key(*pool) && protocol_simulations.contains_key(*pool))
+            .cloned()
+            .collect();
+        for pool in &recovered {
+            if let Some(entry) = pools.remove(pool) {
+                tracing::info!(
+                    pool = %pool,
+                    skipped_builds = entry.skipped_builds,
+                    missing_for_ms = entry.missing_since.elapsed().as_millis() as u64,
+                    "Protocol data arrived, re-enabling paths through pool"
+                );
+            }
+        }
+        recovered
+    }
+
+    /// Stop tracking pools, e.g. after they were removed from the graph.
+    pub fn forget(&self, pool_addresses: &[Bytes]) {
+        if let Ok(mut pools) = self.pools.write() {
+            for pool in pool_addresses {
+                pools.remove(pool);
+            }
+        }
+    }
+
+    /// The tracking entry of a pool awaiting data.
+    pub fn get(&self, pool: &Bytes) -> Option<MissingPool> {
+        self.pools.read().ok()?.get(pool).cloned()
+    }
+
+    /// Whether a pool is awaiting data.
+    pub fn contains(&self, pool: &Bytes) -> bool {
+        self.pools.read().is_ok_and(|pools| pools.contains_key(pool))
+    }
+
+    /// The pools awaiting data for longer than `age`, e.g. to alert on.
+    pub fn missing_longer_than(&self, age: Duration) -> Vec<Bytes> {
+        self.pools
+            .read()
+            .map(|pools| {
+                pools
+                    .iter()
+                    .filter(|(_, entry)| entry.missing_since.elapsed() > age)
+                    .map(|(pool, _)| pool.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The number of pools awaiting data.
+    pub fn len(&self) -> usize {
+        self.pools.read().map(|pools| pools.len()).unwrap_or(0)
+    }
+
+    /// Whether no pool is awaiting data.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Clone for MissingDataTracker {
+    fn clone(&self) -> Self {
+        let pools = self.pools.read().map(|pools| pools.clone()).unwrap_or_default();
+        Self {
+            pools: RwLock::new(pools),
+        }
+    }
+}
@@ -3,21 +3,54 @@
 //! This module provides comprehensive path functionality for arbitrage trading,
 //! organized into focused sub-modules for better maintainability and clarity.
 
+pub mod allowlist;
+pub mod cache;
+pub mod cooldown;
 pub mod creation;
+pub mod discovery;
 pub mod execution;
+pub mod filter;
+pub mod gas;
+pub mod missing_data;
+pub mod multi_source;
 pub mod optimization;
+pub mod pair_scanner;
+pub mod policy;
+pub mod portfolio;
+pub mod ranking;
 pub mod repository;
+pub mod sharded;
+#[cfg(feature = "storage")]
+pub mod storage;
 pub mod swap;
+pub mod templates;
 
 // Re-export types for convenience
+pub use allowlist::ProtocolAllowlist;
+pub use cache::{PathEvaluationCache, PathLimitCache, PoolVersions, SpotPriceCache};
+pub use cooldown::{CooldownEntry, CooldownRegistry};
 pub use creation::{PathBuilder, PathValidator};
-pub use execution::{PathExecutor, ProfitCalculator, ExecutionMetrics};
-pub use optimization::{PathOptimizer, OptimizationResult};
-pub use repository::{PathRepository, RepositoryStatistics};
+pub use discovery::{spawn_discovery, CancellationToken, DiscoveryMonitor, DiscoveryOutcome, DiscoveryProgress, DiscoveryStage};
+pub use execution::{PathExecutor, ProfitBreakdown, ProfitCalculator, ExecutionMetrics, SwapAdjustment, TransferFees};
+pub use filter::{CachedSpotPricePrefilter, FeeAdjustedSpotPricePrefilter, PathFilter, SpotPricePrefilter};
+pub use gas::{ProtocolGasTable, DEFAULT_SWAP_GAS};
+pub use missing_data::{MissingData, MissingDataTracker, MissingPool};
+pub use multi_source::{MultiSourceSearch, RankedOpportunity, SourceTokenConfig};
+pub use optimization::{PathOptimizer, OptimizationResult, Q96};
+pub use pair_scanner::{PairOpportunity, PairScanner};
+pub use policy::DiscoveryPolicy;
+pub use portfolio::{Portfolio, PortfolioCandidate, PortfolioEntry, PortfolioSelector};
+pub use ranking::{PathId, PathRanking, PathScore};
+pub use repository::{DiscoveredPath, PathQuery, PathRepository, RepositoryStatistics};
+pub use sharded::{ShardedPathRepository, ShardedPathView};
+#[cfg(feature = "storage")]
+pub use storage::{PathExtForStorage, PathForStorage, SwapExtForStorage};
 pub use swap::{Swap, SwapExt, SwapForStorage};
+pub use templates::{PathTemplate, TriangularTemplate, TwoHopTemplate};
 
-use crate::errors::{PathError, Result};
+use crate::errors::{ArbitrageError, PathError, Result};
 use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::Zero;
 use std::{fmt, iter::FromIterator, ops::Deref};
 use tycho_common::Bytes;
 
@@ -44,11 +77,7 @@ impl Path {
         let first_swap = self.first()
             .ok_or_else(|| PathError::EmptyPath)?;
         
-        Ok(if first_swap.zero_for_one {
-            first_swap.pool_comp.tokens[0].address.clone()
-        } else {
-            first_swap.pool_comp.tokens[1].address.clone()
-        })
+        Ok(first_swap.token_in.address.clone())
     }
 
     /// Get the number of swaps in this path.
@@ -106,6 +135,114 @@ impl Path {
         Ok(profit)
     }
 
+    /// Calculate the profit/loss for an input amount, clamping it to the limits of the path.
+    ///
+    /// Where `calculate_profit_loss` fails for an amount above a pool's
+    /// limits, the input is clamped to the binding limit across the path, see
+    /// [`Path::max_amount_in`], and the profit is evaluated at the clamped
+    /// amount, so a smaller but profitable trade is kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty, a swap fails for reasons other
+    /// than its limits, or no positive input amount passes every limit.
+    pub fn calculate_profit_loss_clamped(&self, amount_in: BigUint) -> Result<LimitedProfit> {
+        match self.calculate_profit_loss(amount_in.clone()) {
+            Ok(profit) => Ok(LimitedProfit { amount_in, profit, limited: false }),
+            Err(ArbitrageError::Path(PathError::AmountExceedsLimits { .. })) => {
+                let clamped = self.max_amount_in(&amount_in)?;
+                // A zero input passes any limit but is no trade
+                if clamped.is_zero() {
+                    return Err(PathError::AmountExceedsLimits {
+                        requested: amount_in.to_string(),
+                        max_available: clamped.to_string(),
+                    }.into());
+                }
+                let profit = self.calculate_profit_loss(clamped.clone())?;
+                tracing::debug!(
+                    requested_amount = %amount_in,
+                    clamped_amount = %clamped,
+                    profit = %profit,
+                    "Clamped input amount to the limits of the path"
+                );
+                Ok(LimitedProfit { amount_in: clamped, profit, limited: true })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The largest input amount up to `upper_bound` that passes the limits of every swap.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Path::max_feasible_input`].
+    pub fn max_amount_in(&self, upper_bound: &BigUint) -> Result<BigUint> {
+        Ok(self.max_feasible_input()?.min(upper_bound.clone()))
+    }
+
+    /// The largest input amount that passes the limits of every swap.
+    ///
+    /// The limits are propagated backwards: the last swap may take the
+    /// largest input within its own limits, and every earlier swap the largest
+    /// input whose output stays within its output limit and the input its
+    /// successor may take, see [`Swap::max_amount_in_within`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty or the limits of a pool cannot be calculated.
+    pub fn max_feasible_input(&self) -> Result<BigUint> {
+        let mut successor_max_in: Option<BigUint> = None;
+        for swap in self.iter().rev() {
+            let max_in = match successor_max_in {
+                Some(max_out) => swap.max_amount_in_within(&max_out)?,
+                None => {
+                    let (_, max_out) = swap.get_limits()?;
+                    swap.max_amount_in_within(&max_out)?
+                }
+            };
+            successor_max_in = Some(max_in);
+        }
+        successor_max_in.ok_or_else(|| PathError::EmptyPath.into())
+    }
+
+    /// Calculate the profit/loss of several input amounts at once.
+    ///
+    /// Gives the same profits as `calculate_profit_loss` per amount, but each
+    /// swap evaluates all amounts still in flight together, see
+    /// [`Swap::get_amounts_out`]. Amounts that do not pass every swap, e.g.
+    /// because they exceed a pool's limits, evaluate to zero, the profit
+    /// optimizers assign to failed evaluations.
+    pub fn evaluate_amounts(&self, amounts_in: &[BigUint]) -> Vec<BigInt> {
+        if self.is_empty() {
+            return vec![BigInt::zero(); amounts_in.len()];
+        }
+
+        let mut current: Vec<Option<BigUint>> = amounts_in.iter().cloned().map(Some).collect();
+        for swap in self.iter() {
+            let (indices, inputs): (Vec<usize>, Vec<BigUint>) = current
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(index, amount)| amount.take().map(|amount| (index, amount)))
+                .unzip();
+            if inputs.is_empty() {
+                break;
+            }
+
+            for (index, amount_out) in indices.into_iter().zip(swap.get_amounts_out(&inputs)) {
+                current[index] = amount_out;
+            }
+        }
+
+        amounts_in
+            .iter()
+            .zip(current)
+            .map(|(amount_in, amount_out)| match amount_out {
+                Some(amount_out) => BigInt::from(amount_out) - BigInt::from(amount_in.clone()),
+                None => BigInt::zero(),
+            })
+            .collect()
+    }
+
     /// Execute the path with a specific input amount to get detailed results.
     pub fn execute_with_amount(&self, amount_in: BigUint) -> Result<PathExt> {
         if self.is_empty() {
@@ -118,14 +255,7 @@ impl Path {
         for swap in self.iter() {
             let amount_for_swap = current_amount.clone();
             let res = swap.get_amount_out(current_amount)?;
-            let swap_ext = SwapExt {
-                pool_comp: swap.pool_comp.clone(),
-                pool_sim: swap.pool_sim.clone(),
-                zero_for_one: swap.zero_for_one,
-                amount_in: amount_for_swap,
-                amount_out: res.amount.clone(),
-                gas: res.gas,
-            };
+            let swap_ext = SwapExt::from_swap(swap, amount_for_swap, res.amount.clone(), res.gas);
             current_amount = res.amount;
             swaps.push(swap_ext);
         }
@@ -134,6 +264,17 @@ impl Path {
     }
 }
 
+/// The profit of a path at an input amount, possibly clamped to the path's limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitedProfit {
+    /// The evaluated input amount, below the requested one if limited
+    pub amount_in: BigUint,
+    /// Output minus input at `amount_in`
+    pub profit: BigInt,
+    /// Whether the requested input exceeded a limit and was clamped
+    pub limited: bool,
+}
+
 impl fmt::Debug for Path {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let start_token = self.start_token().ok();
@@ -199,11 +340,7 @@ impl PathExt {
         let first_swap = self.first()
             .ok_or_else(|| PathError::EmptyPath)?;
         
-        Ok(if first_swap.zero_for_one {
-            first_swap.pool_comp.tokens[0].address.clone()
-        } else {
-            first_swap.pool_comp.tokens[1].address.clone()
-        })
+        Ok(first_swap.token_in.address.clone())
     }
 }
 
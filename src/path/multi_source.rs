@@ -0,0 +1,349 @@
+//! Multi-source-token arbitrage search.
+//!
+//! This module searches paths starting from several source tokens in a single
+//! pass. Each source token has its own balance cap, bounding both the amount
+//! the optimizer may try and the total amount committed across selected
+//! opportunities. Profits are converted to a common reference token so that
+//! opportunities from different source tokens can be ranked against each other
+//! and the globally best set is selected rather than the best path per token.
+//!
+//! With a gas price, see [`MultiSourceSearch::with_gas_price`], the profits
+//! are ranked after the gas cost estimated by a [`ProtocolGasTable`], so long
+//! paths through expensive protocols do not crowd out cheaper opportunities
+//! before real gas usage is known from simulations.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::path::MultiSourceSearch;
+//!
+//! let search = MultiSourceSearch::new()
+//!     .with_source(weth, weth_balance, 1.0)
+//!     .with_source(usdc, usdc_balance, usdc_to_weth_rate);
+//!
+//! let opportunities = search.search(&paths, |path, cap| {
+//!     optimizer.clone().with_search_range(1u32.into(), cap.clone()).optimize_and_execute(path).map(|(_, ext)| ext)
+//! });
+//! ```
+
+use crate::errors::Result;
+use crate::path::{Path, PathExt, ProtocolGasTable};
+use num_bigint::{BigInt, BigUint};
+use num_traits::ToPrimitive;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use tycho_common::Bytes;
+
+/// Balance cap and valuation for a single source token.
+#[derive(Debug, Clone)]
+pub struct SourceTokenConfig {
+    /// Maximum amount of the token that may be committed in one search
+    pub balance_cap: BigUint,
+    /// Value of one raw unit of the token expressed in raw units of the reference token
+    pub reference_rate: f64,
+}
+
+/// A profitable opportunity ranked across all source tokens.
+#[derive(Debug, Clone)]
+pub struct RankedOpportunity {
+    /// Source token the path starts and ends with
+    pub source_token: Bytes,
+    /// The optimized path
+    pub path: PathExt,
+    /// Profit denominated in the source token
+    pub profit: BigInt,
+    /// Profit converted to the reference token, after the estimated gas cost if
+    /// the search has a gas price, used for ranking
+    pub reference_profit: f64,
+}
+
+impl RankedOpportunity {
+    /// Amount of the source token the opportunity commits.
+    pub fn amount_in(&self) -> BigUint {
+        self.path
+            .first()
+            .map(|swap| swap.amount_in.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Gas price and per-protocol gas of swaps, estimating the gas cost of paths.
+#[derive(Debug, Clone)]
+struct GasPricing {
+    gas_table: ProtocolGasTable,
+    /// Raw units of the reference token paid per gas
+    gas_price: f64,
+}
+
+/// Searches arbitrage paths from multiple source tokens in a single pass.
+#[derive(Debug, Clone, Default)]
+pub struct MultiSourceSearch {
+    sources: HashMap<Bytes, SourceTokenConfig>,
+    gas_pricing: Option<GasPricing>,
+}
+
+impl MultiSourceSearch {
+    /// Create a search without any source tokens.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a source token with its balance cap and reference rate.
+    pub fn with_source(mut self, token: Bytes, balance_cap: BigUint, reference_rate: f64) -> Self {
+        self.set_source(token, balance_cap, reference_rate);
+        self
+    }
+
+    /// Add or replace a source token with its balance cap and reference rate.
+    pub fn set_source(&mut self, token: Bytes, balance_cap: BigUint, reference_rate: f64) {
+        self.sources.insert(token, SourceTokenConfig {
+            balance_cap,
+            reference_rate,
+        });
+    }
+
+    /// Rank opportunities by their profit after the estimated gas cost.
+    ///
+    /// # Arguments
+    ///
+    /// * `gas_table` - The estimated gas of swaps by protocol system
+    /// * `gas_price` - Raw units of the reference token paid per gas, e.g. the
+    ///   predicted base fee in wei when profits are valued in the native token
+    pub fn with_gas_price(mut self, gas_table: ProtocolGasTable, gas_price: f64) -> Self {
+        self.gas_pricing = Some(GasPricing {
+            gas_table,
+            gas_price: gas_price.max(0.0),
+        });
+        self
+    }
+
+    /// Get the configuration for a source token.
+    pub fn source(&self, token: &Bytes) -> Option<&SourceTokenConfig> {
+        self.sources.get(token)
+    }
+
+    /// Number of configured source tokens.
+    pub fn source_count(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Optimize all paths concurrently and rank the profitable ones globally.
+    ///
+    /// Paths whose start token is not a configured source, or whose source has a
+    /// zero balance cap, are skipped. `optimize` receives each path together with
+    /// the balance cap of its source token, which it must use as the upper bound
+    /// for the input amount. With a gas price, opportunities whose profit does
+    /// not cover their estimated gas cost are skipped as well.
+    ///
+    /// # Returns
+    ///
+    /// Profitable opportunities sorted by reference-token profit, best first
+    pub fn rank_paths<F>(&self, paths: &[Path], optimize: F) -> Vec<RankedOpportunity>
+    where
+        F: Fn(&Path, &BigUint) -> Result<PathExt> + Sync,
+    {
+        let mut ranked: Vec<RankedOpportunity> = paths
+            .par_iter()
+            .filter_map(|path| {
+                let source_token = path.start_token().ok()?;
+                let source = self.sources.get(&source_token)?;
+                if source.balance_cap == BigUint::default() {
+                    return None;
+                }
+
+                let path_ext = match optimize(path, &source.balance_cap) {
+                    Ok(path_ext) => path_ext,
+                    Err(e) => {
+                        tracing::debug!(
+                            start_token = %source_token,
+                            error = %e,
+                            "Path optimization failed during multi-source search"
+                        );
+                        return None;
+                    }
+                };
+
+                let profit = path_ext.profit().ok()?;
+                if profit <= BigInt::default() {
+                    return None;
+                }
+
+                let mut reference_profit = profit.to_f64().unwrap_or(0.0) * source.reference_rate;
+                if let Some(pricing) = &self.gas_pricing {
+                    let gas = pricing.gas_table.path_ext_gas(&path_ext);
+                    let gas_cost = gas as f64 * pricing.gas_price;
+                    if reference_profit <= gas_cost {
+                        tracing::trace!(
+                            start_token = %source_token,
+                            reference_profit = reference_profit,
+                            estimated_gas = gas,
+                            gas_cost = gas_cost,
+                            "Skipping opportunity not covering its estimated gas cost"
+                        );
+                        return None;
+                    }
+                    reference_profit -= gas_cost;
+                }
+
+                Some(RankedOpportunity {
+                    source_token,
+                    path: path_ext,
+                    profit,
+                    reference_profit,
+                })
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| b.reference_profit.total_cmp(&a.reference_profit));
+
+        tracing::debug!(
+            total_paths = paths.len(),
+            ranked_opportunities = ranked.len(),
+            source_tokens = self.sources.len(),
+            "Ranked multi-source opportunities"
+        );
+
+        ranked
+    }
+
+    /// Select the best set of opportunities that fits within the balance caps.
+    ///
+    /// Opportunities are taken greedily in ranking order. An opportunity is kept
+    /// only if its input amount fits into the remaining cap of its source token,
+    /// so the selected set never commits more than the configured caps.
+    ///
+    /// # Arguments
+    ///
+    /// * `ranked` - Opportunities sorted best first, as returned by `rank_paths`
+    /// * `max_opportunities` - Optional cap on the number of selected opportunities
+    pub fn select(
+        &self,
+        ranked: Vec<RankedOpportunity>,
+        max_opportunities: Option<usize>,
+    ) -> Vec<RankedOpportunity> {
+        let max_opportunities = max_opportunities.unwrap_or(usize::MAX);
+        let mut remaining: HashMap<&Bytes, BigUint> = self.sources
+            .iter()
+            .map(|(token, source)| (token, source.balance_cap.clone()))
+            .collect();
+
+        let mut selected = Vec::new();
+        for opportunity in ranked {
+            if selected.len() >= max_opportunities {
+                break;
+            }
+
+            let Some(available) = remaining.get_mut(&opportunity.source_token) else {
+                continue;
+            };
+
+            let amount_in = opportunity.amount_in();
+            if amount_in > *available {
+                tracing::trace!(
+                    source_token = %opportunity.source_token,
+                    amount_in = %amount_in,
+                    available = %available,
+                    "Skipping opportunity exceeding remaining balance cap"
+                );
+                continue;
+            }
+
+            *available -= &amount_in;
+            selected.push(opportunity);
+        }
+
+        selected
+    }
+
+    /// Rank all paths and select the globally best set within the balance caps.
+    pub fn search<F>(&self, paths: &[Path], optimize: F) -> Vec<RankedOpportunity>
+    where
+        F: Fn(&Path, &BigUint) -> Result<PathExt> + Sync,
+    {
+        let ranked = self.rank_paths(paths, optimize);
+        self.select(ranked, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{component, MockProtocolSim};
+    use crate::path::Swap;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    fn create_mock_path(start_token: &str, multiplier: f64) -> Path {
+        let token_a = Bytes::from_str(start_token).unwrap();
+        let token_b = Bytes::from_str("0x00ff").unwrap();
+        let pool_addr = Bytes::from_str("0x1001").unwrap();
+        let pool_comp = component(&pool_addr, &[token_a.clone(), token_b.clone()]);
+        Path(vec![Swap::new(pool_comp, Arc::new(MockProtocolSim::new().with_rate(multiplier)), &token_a, &token_b).unwrap()])
+    }
+
+    fn execute_at_cap(path: &Path, cap: &BigUint) -> Result<PathExt> {
+        path.execute_with_amount(cap.clone())
+    }
+
+    #[test]
+    fn test_ranks_across_source_tokens_by_reference_profit() {
+        let paths = vec![
+            create_mock_path("0x0001", 1.1),
+            create_mock_path("0x0002", 1.1),
+        ];
+
+        // Token 0x0002 units are worth ten times as much as token 0x0001 units
+        let search = MultiSourceSearch::new()
+            .with_source(Bytes::from_str("0x0001").unwrap(), BigUint::from(1000u32), 1.0)
+            .with_source(Bytes::from_str("0x0002").unwrap(), BigUint::from(1000u32), 10.0);
+
+        let ranked = search.rank_paths(&paths, execute_at_cap);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].source_token, Bytes::from_str("0x0002").unwrap());
+        assert!(ranked[0].reference_profit > ranked[1].reference_profit);
+    }
+
+    #[test]
+    fn test_skips_unprofitable_and_unknown_sources() {
+        let paths = vec![
+            create_mock_path("0x0001", 0.9),
+            create_mock_path("0x0003", 1.1),
+        ];
+
+        let search = MultiSourceSearch::new()
+            .with_source(Bytes::from_str("0x0001").unwrap(), BigUint::from(1000u32), 1.0);
+
+        assert!(search.search(&paths, execute_at_cap).is_empty());
+    }
+
+    #[test]
+    fn test_selection_respects_balance_caps() {
+        let paths = vec![
+            create_mock_path("0x0001", 1.2),
+            create_mock_path("0x0001", 1.1),
+        ];
+
+        let search = MultiSourceSearch::new()
+            .with_source(Bytes::from_str("0x0001").unwrap(), BigUint::from(1000u32), 1.0);
+
+        // Both paths commit the full cap, so only the better one fits
+        let selected = search.search(&paths, execute_at_cap);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].profit, BigInt::from(200));
+    }
+
+    #[test]
+    fn test_ranks_by_profit_after_estimated_gas() {
+        let paths = vec![create_mock_path("0x0001", 1.2), create_mock_path("0x0001", 1.1)];
+        let source = |search: MultiSourceSearch| {
+            search.with_source(Bytes::from_str("0x0001").unwrap(), BigUint::from(1000u32), 1.0)
+        };
+
+        // A swap through the test protocol costs 120 reference units
+        let gas_table = ProtocolGasTable::empty(0).with_swap_gas("test", 120_000);
+        let ranked = source(MultiSourceSearch::new().with_gas_price(gas_table, 0.001)).rank_paths(&paths, execute_at_cap);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].profit, BigInt::from(200));
+        assert!((ranked[0].reference_profit - 80.0).abs() < 1e-9);
+    }
+}
@@ -12,25 +12,152 @@
 //! - Grid Search Optimizer
 //!
 //! These can serve as starting points for your own optimization strategies.
+//!
+//! # Fixed-Point Arithmetic
+//!
+//! Token amounts of 18-decimals tokens routinely exceed 2^53, above which `f64`
+//! cannot represent every integer, so searching over floats silently moves
+//! every probe away from the amount the optimizer meant to evaluate. Search
+//! fractions, tolerances and profit ratios are therefore expressed as [`Q96`]
+//! fixed-point numbers, and amounts stay `BigUint` throughout the search.
 
 use crate::errors::Result;
 use crate::path::{Path, PathExt};
+use crate::utils::convert::{self, Rounding};
+use alloy::primitives::U256;
 use num_bigint::{BigInt, BigUint};
+use num_traits::{Signed, Zero};
+#[cfg(feature = "storage")]
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
+/// Number of fractional bits of a [`Q96`].
+pub const Q96_FRACTIONAL_BITS: usize = 96;
+
+/// Unsigned fixed-point number with 96 fractional bits, backed by a U256.
+///
+/// Values are exact to 2^-96 with an integer part of up to 2^160 - 1. Products
+/// with amounts are computed on `BigUint`, so scaling an amount never loses
+/// precision beyond the final rounding towards zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "storage", derive(Serialize, Deserialize))]
+pub struct Q96(U256);
+
+impl Q96 {
+    /// Zero
+    pub const ZERO: Self = Self(U256::ZERO);
+    /// One, 2^96 in raw representation
+    pub const ONE: Self = Self(U256::from_limbs([0, 1 << 32, 0, 0]));
+
+    /// Create a value from its raw representation, `value * 2^96`.
+    pub const fn from_raw(raw: U256) -> Self {
+        Self(raw)
+    }
+
+    /// The raw representation, `value * 2^96`.
+    pub fn raw(&self) -> U256 {
+        self.0
+    }
+
+    /// Create a value from an integer, or `None` if it is 2^160 or larger.
+    pub fn from_integer(value: &BigUint) -> Option<Self> {
+        convert::biguint_to_u256_checked(&(value << Q96_FRACTIONAL_BITS)).map(Self)
+    }
+
+    /// Create the ratio `numerator / denominator`, rounded towards zero.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the denominator is zero or the ratio is 2^160 or larger
+    pub fn from_ratio(numerator: &BigUint, denominator: &BigUint) -> Option<Self> {
+        if denominator.is_zero() {
+            return None;
+        }
+        convert::biguint_to_u256_checked(&((numerator << Q96_FRACTIONAL_BITS) / denominator)).map(Self)
+    }
+
+    /// Create a value from an f64, or `None` if it is negative, not finite or too large.
+    ///
+    /// Meant for configuration values such as tolerance percentages; the float
+    /// is converted exactly up to the 2^-96 resolution.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if value < 0.0 {
+            return None;
+        }
+        let scaled = value * 2f64.powi(Q96_FRACTIONAL_BITS as i32);
+        Self::from_raw_biguint(&convert::f64_to_biguint_checked(scaled, Rounding::Nearest)?)
+    }
+
+    /// The nearest f64, for logging and reporting.
+    pub fn to_f64(&self) -> f64 {
+        convert::u256_to_f64(self.0) / 2f64.powi(Q96_FRACTIONAL_BITS as i32)
+    }
+
+    /// The integer part.
+    pub fn floor(&self) -> BigUint {
+        convert::u256_to_biguint(self.0) >> Q96_FRACTIONAL_BITS
+    }
+
+    /// Multiply an amount by this value, rounding towards zero.
+    pub fn mul_floor(&self, amount: &BigUint) -> BigUint {
+        (convert::u256_to_biguint(self.0) * amount) >> Q96_FRACTIONAL_BITS
+    }
+
+    /// Add two values, or `None` on overflow.
+    pub fn checked_add(&self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    /// Subtract `other`, or `None` if it is larger than this value.
+    pub fn checked_sub(&self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    /// Subtract `other`, clamping at zero.
+    pub fn saturating_sub(&self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+
+    /// Multiply two values, rounding towards zero, or `None` on overflow.
+    pub fn checked_mul(&self, other: Self) -> Option<Self> {
+        Self::from_raw_biguint(&self.mul_floor(&convert::u256_to_biguint(other.0)))
+    }
+
+    /// Divide by `other`, rounding towards zero, or `None` if it is zero or on overflow.
+    pub fn checked_div(&self, other: Self) -> Option<Self> {
+        Self::from_ratio(&convert::u256_to_biguint(self.0), &convert::u256_to_biguint(other.0))
+    }
+
+    fn from_raw_biguint(raw: &BigUint) -> Option<Self> {
+        convert::biguint_to_u256_checked(raw).map(Self)
+    }
+}
+
+impl fmt::Display for Q96 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_f64())
+    }
+}
+
 /// Result of a path optimization operation.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "storage", derive(Serialize, Deserialize))]
 pub struct OptimizationResult {
     /// The optimal input amount found
+    #[cfg_attr(feature = "storage", serde(with = "crate::path::storage::decimal"))]
     pub optimal_amount: BigUint,
     /// The expected profit at the optimal amount
+    #[cfg_attr(feature = "storage", serde(with = "crate::path::storage::signed_decimal"))]
     pub expected_profit: BigInt,
     /// The number of iterations performed during optimization
     pub iterations: usize,
     /// Whether the optimization converged successfully
     pub converged: bool,
-    /// The final tolerance achieved
-    pub final_tolerance: f64,
+    /// Width of the final search interval relative to the initial one
+    pub final_tolerance: Q96,
+    /// Whether the optimal amount was clamped to the limits of the path
+    #[cfg_attr(feature = "storage", serde(default))]
+    pub limited: bool,
 }
 
 impl OptimizationResult {
@@ -40,7 +167,7 @@ impl OptimizationResult {
         expected_profit: BigInt,
         iterations: usize,
         converged: bool,
-        final_tolerance: f64,
+        final_tolerance: Q96,
     ) -> Self {
         Self {
             optimal_amount,
@@ -48,13 +175,28 @@ impl OptimizationResult {
             iterations,
             converged,
             final_tolerance,
+            limited: false,
         }
     }
 
+    /// Mark the optimal amount as clamped to the limits of the path.
+    pub fn with_limited(mut self, limited: bool) -> Self {
+        self.limited = limited;
+        self
+    }
+
     /// Check if the optimization found a profitable solution.
     pub fn is_profitable(&self) -> bool {
         self.expected_profit > BigInt::from(0)
     }
+
+    /// Expected profit per unit of input, or `None` for unprofitable results.
+    pub fn return_rate(&self) -> Option<Q96> {
+        if !self.expected_profit.is_positive() {
+            return None;
+        }
+        Q96::from_ratio(self.expected_profit.magnitude(), &self.optimal_amount)
+    }
 }
 
 impl fmt::Display for OptimizationResult {
@@ -108,7 +250,9 @@ pub trait PathOptimizer {
     ///
     /// This is a convenience method that combines optimization with execution.
     /// The default implementation calls `find_optimal_amount` and then executes
-    /// the path with the optimal amount.
+    /// the path with the optimal amount. An optimal amount above the limits of
+    /// the path is clamped to the binding limit and its profit re-evaluated,
+    /// see [`Path::calculate_profit_loss_clamped`], flagging the result as `limited`.
     ///
     /// # Arguments
     ///
@@ -122,7 +266,13 @@ pub trait PathOptimizer {
     ///
     /// Returns an error if optimization or execution fails
     fn optimize_and_execute(&self, path: &Path) -> Result<(OptimizationResult, PathExt)> {
-        let optimization_result = self.find_optimal_amount(path)?;
+        let mut optimization_result = self.find_optimal_amount(path)?;
+        let clamped = path.calculate_profit_loss_clamped(optimization_result.optimal_amount.clone())?;
+        if clamped.limited {
+            optimization_result.optimal_amount = clamped.amount_in;
+            optimization_result.expected_profit = clamped.profit;
+            optimization_result.limited = true;
+        }
         let executed_path = path.execute_with_amount(optimization_result.optimal_amount.clone())?;
         Ok((optimization_result, executed_path))
     }
@@ -131,11 +281,12 @@ pub trait PathOptimizer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::MockProtocolSim;
     use crate::path::{Path, Swap};
     use std::collections::HashMap;
+    use std::sync::Arc;
     use tycho_common::Bytes;
     use tycho_simulation::protocol::models::ProtocolComponent;
-    use tycho_simulation::protocol::state::ProtocolSim;
     use std::str::FromStr;
 
     // Simple test optimizer
@@ -157,91 +308,11 @@ mod tests {
                 profit,
                 1,
                 true,
-                0.0,
+                Q96::ZERO,
             ))
         }
     }
 
-    // Mock ProtocolSim for testing
-    #[derive(Debug, Clone)]
-    struct MockProtocolSim {
-        multiplier: f64,
-    }
-
-    impl MockProtocolSim {
-        fn new(multiplier: f64) -> Self {
-            Self { multiplier }
-        }
-    }
-
-    impl ProtocolSim for MockProtocolSim {
-        fn clone_box(&self) -> Box<dyn ProtocolSim> {
-            Box::new(self.clone())
-        }
-
-        fn fee(&self) -> f64 {
-            0.003
-        }
-
-        fn spot_price(
-            &self,
-            _token_in: &tycho_simulation::models::Token,
-            _token_out: &tycho_simulation::models::Token,
-        ) -> std::result::Result<f64, tycho_simulation::protocol::errors::SimulationError> {
-            Ok(self.multiplier)
-        }
-
-        fn get_amount_out(
-            &self,
-            amount_in: BigUint,
-            _token_in: &tycho_simulation::models::Token,
-            _token_out: &tycho_simulation::models::Token,
-        ) -> std::result::Result<tycho_simulation::protocol::models::GetAmountOutResult, tycho_simulation::protocol::errors::SimulationError> {
-            let amount_out = if self.multiplier >= 1.0 {
-                &amount_in * BigUint::from((self.multiplier * 1000.0) as u32) / BigUint::from(1000u32)
-            } else {
-                &amount_in * BigUint::from((self.multiplier * 1000.0) as u32) / BigUint::from(1000u32)
-            };
-
-            Ok(tycho_simulation::protocol::models::GetAmountOutResult {
-                amount: amount_out,
-                gas: BigUint::from(21000u32),
-                new_state: Box::new(self.clone()),
-            })
-        }
-
-        fn get_limits(
-            &self,
-            _token_in: Bytes,
-            _token_out: Bytes,
-        ) -> std::result::Result<(BigUint, BigUint), tycho_simulation::protocol::errors::SimulationError> {
-            Ok((BigUint::from(10_000_000u32), BigUint::from(10_000_000u32)))
-        }
-
-        fn delta_transition(
-            &mut self,
-            _delta: tycho_common::dto::ProtocolStateDelta,
-            _tokens: &std::collections::HashMap<Bytes, tycho_simulation::models::Token>,
-            _balances: &tycho_simulation::models::Balances,
-        ) -> std::result::Result<(), tycho_simulation::protocol::errors::TransitionError<String>> {
-            Ok(())
-        }
-
-        fn as_any(&self) -> &(dyn std::any::Any + 'static) {
-            self
-        }
-
-        fn as_any_mut(&mut self) -> &mut (dyn std::any::Any + 'static) {
-            self
-        }
-
-        fn eq(&self, other: &(dyn ProtocolSim + 'static)) -> bool {
-            other.as_any().downcast_ref::<MockProtocolSim>()
-                .map(|other| (self.multiplier - other.multiplier).abs() < f64::EPSILON)
-                .unwrap_or(false)
-        }
-    }
-
     fn create_mock_path() -> Path {
         let token_a = Bytes::from_str("0x0001").unwrap();
         let token_b = Bytes::from_str("0x0002").unwrap();
@@ -273,11 +344,10 @@ mod tests {
             creation_tx: tycho_common::Bytes::default(),
         };
 
-        let swap = Swap {
-            pool_comp,
-            pool_sim: Box::new(MockProtocolSim::new(1.1)),
-            zero_for_one: true,
-        };
+        let sim = MockProtocolSim::new()
+            .with_rate(1.1)
+            .with_limits(BigUint::from(10_000_000u32), BigUint::from(10_000_000u32));
+        let swap = Swap::new(pool_comp, Arc::new(sim), &token_a, &token_b).unwrap();
 
         Path(vec![swap])
     }
@@ -289,15 +359,38 @@ mod tests {
             BigInt::from(100),
             10,
             true,
-            0.001,
+            Q96::from_ratio(&BigUint::from(1u32), &BigUint::from(1000u32)).unwrap(),
         );
 
         assert_eq!(result.optimal_amount, BigUint::from(1000u32));
         assert_eq!(result.expected_profit, BigInt::from(100));
         assert_eq!(result.iterations, 10);
         assert!(result.converged);
-        assert_eq!(result.final_tolerance, 0.001);
+        assert_eq!(result.final_tolerance.mul_floor(&BigUint::from(1_000_000u32)), BigUint::from(1000u32));
         assert!(result.is_profitable());
+        assert_eq!(result.return_rate().unwrap().mul_floor(&BigUint::from(1000u32)), BigUint::from(100u32));
+    }
+
+    #[test]
+    fn test_q96_is_exact_for_large_amounts() {
+        // 10^24 + 1 base units, far beyond the 2^53 f64 mantissa
+        let amount = BigUint::from(10u32).pow(24) + 1u32;
+        assert_eq!(Q96::ONE.mul_floor(&amount), amount);
+        assert_eq!(Q96::from_integer(&amount).unwrap().floor(), amount);
+
+        let quarter = Q96::from_ratio(&BigUint::from(1u32), &BigUint::from(4u32)).unwrap();
+        assert_eq!(quarter.mul_floor(&(&amount * 4u32)), amount);
+
+        let half = Q96::from_f64(0.5).unwrap();
+        assert_eq!(half.checked_add(half), Some(Q96::ONE));
+        assert_eq!(half.checked_mul(half).unwrap().to_f64(), 0.25);
+        assert_eq!(Q96::ONE.checked_div(half).unwrap().floor(), BigUint::from(2u32));
+        assert_eq!(half.checked_sub(Q96::ONE), None);
+        assert_eq!(half.saturating_sub(Q96::ONE), Q96::ZERO);
+
+        assert_eq!(Q96::from_f64(-1.0), None);
+        assert_eq!(Q96::from_ratio(&amount, &BigUint::zero()), None);
+        assert_eq!(Q96::from_integer(&(BigUint::from(1u32) << 160)), None);
     }
 
     #[test]
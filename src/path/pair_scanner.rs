@@ -0,0 +1,211 @@
+//! Low-latency scanning of price differences between pools of the same pair.
+//!
+//! The most common arbitrage buys a token in one pool and sells it back in
+//! another pool of the same pair. `PairScanner` finds these 2-hop cycles
+//! directly from the pair index of the trading graph: for every pair of an
+//! updated pool, it compares the spot prices of all pools of the pair and
+//! emits the best round trip, without discovering or building paths through
+//! the [`PathRepository`](crate::path::PathRepository).
+
+use crate::graph::TradingGraph;
+use crate::path::{Path, Swap};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tycho_common::Bytes;
+use tycho_simulation::protocol::{models::ProtocolComponent, state::ProtocolSim};
+
+/// The best round trip through two pools of a pair.
+#[derive(Debug, Clone)]
+pub struct PairOpportunity {
+    /// The two swaps, from the source token and back to it
+    pub path: Path,
+    /// The product of the spot prices of both swaps
+    pub spot_price_product: f64,
+}
+
+/// Scanner of 2-hop cycles between pools of the same pair.
+#[derive(Debug, Clone)]
+pub struct PairScanner {
+    source_tokens: Vec<Bytes>,
+    min_spot_price_product: f64,
+}
+
+impl PairScanner {
+    /// Create a scanner of round trips from the given source tokens.
+    ///
+    /// By default, every round trip with a spot price product above 1 is emitted.
+    pub fn new(source_tokens: Vec<Bytes>) -> Self {
+        Self {
+            source_tokens,
+            min_spot_price_product: 1.0,
+        }
+    }
+
+    /// Only emit round trips whose spot price product exceeds `min_spot_price_product`.
+    pub fn with_min_spot_price_product(mut self, min_spot_price_product: f64) -> Self {
+        self.min_spot_price_product = min_spot_price_product;
+        self
+    }
+
+    /// Scan the pairs of updated pools for round trips.
+    ///
+    /// Every pair of a source token and another token of an updated pool is
+    /// scanned once, comparing all pools of the pair with a component and a
+    /// simulation loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The trading graph indexing the pools of each pair
+    /// * `updated_pools` - The addresses of pools whose state changed
+    /// * `protocol_components` - Map of pool addresses to protocol components
+    /// * `protocol_simulations` - Map of pool addresses to protocol simulations
+    ///
+    /// # Returns
+    ///
+    /// The best round trip of every scanned pair above the threshold, most
+    /// profitable first
+    pub fn scan(
+        &self,
+        graph: &TradingGraph,
+        updated_pools: &[Bytes],
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
+    ) -> Vec<PairOpportunity> {
+        let mut scanned_pairs = HashSet::new();
+        let mut opportunities = Vec::new();
+
+        for pool in updated_pools {
+            let Some(pool_comp) = protocol_components.get(pool) else {
+                continue;
+            };
+            for source_token in pool_comp.tokens.iter().filter(|token| self.source_tokens.contains(&token.address)) {
+                for other_token in pool_comp.tokens.iter().filter(|token| token.address != source_token.address) {
+                    let pair = (source_token.address.clone(), other_token.address.clone());
+                    if !scanned_pairs.insert(pair.clone()) {
+                        continue;
+                    }
+                    if let Some(opportunity) = self.scan_pair(graph, &pair.0, &pair.1, protocol_components, protocol_simulations) {
+                        opportunities.push(opportunity);
+                    }
+                }
+            }
+        }
+
+        opportunities.sort_by(|a, b| b.spot_price_product.total_cmp(&a.spot_price_product));
+
+        tracing::debug!(
+            updated_pools = updated_pools.len(),
+            scanned_pairs = scanned_pairs.len(),
+            opportunities = opportunities.len(),
+            "Scanned pairs of updated pools"
+        );
+
+        opportunities
+    }
+
+    /// Find the best round trip from `source_token` through `other_token`.
+    fn scan_pair(
+        &self,
+        graph: &TradingGraph,
+        source_token: &Bytes,
+        other_token: &Bytes,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
+    ) -> Option<PairOpportunity> {
+        let source_id = graph.find_token_id(source_token).ok()?;
+        let other_id = graph.find_token_id(other_token).ok()?;
+        let pool_ids = graph.pools_from_to(source_id, other_id);
+        if pool_ids.len() < 2 {
+            return None;
+        }
+
+        // The swaps of every loaded pool of the pair in both directions, with their spot prices
+        let mut swaps = Vec::with_capacity(pool_ids.len());
+        for &pool_id in pool_ids {
+            let Ok(pool) = graph.get_pool(pool_id) else {
+                continue;
+            };
+            let (Some(pool_comp), Some(pool_sim)) = (
+                protocol_components.get(pool.address()),
+                protocol_simulations.get(pool.address()),
+            ) else {
+                continue;
+            };
+            let (Ok(buy), Ok(sell)) = (
+                Swap::new(Arc::clone(pool_comp), Arc::clone(pool_sim), source_token, other_token),
+                Swap::new(Arc::clone(pool_comp), Arc::clone(pool_sim), other_token, source_token),
+            ) else {
+                continue;
+            };
+            if let (Ok(buy_price), Ok(sell_price)) = (buy.spot_price(), sell.spot_price()) {
+                swaps.push((buy, buy_price, sell, sell_price));
+            }
+        }
+
+        let mut best: Option<(usize, usize, f64)> = None;
+        for (buy_index, (_, buy_price, _, _)) in swaps.iter().enumerate() {
+            for (sell_index, (_, _, _, sell_price)) in swaps.iter().enumerate() {
+                let product = buy_price * sell_price;
+                if buy_index == sell_index || product <= self.min_spot_price_product {
+                    continue;
+                }
+                if !best.is_some_and(|(_, _, best_product)| best_product >= product) {
+                    best = Some((buy_index, sell_index, product));
+                }
+            }
+        }
+
+        let (buy_index, sell_index, spot_price_product) = best?;
+        tracing::trace!(
+            source_token = %source_token,
+            other_token = %other_token,
+            buy_pool = %swaps[buy_index].0.pool_comp.id,
+            sell_pool = %swaps[sell_index].2.pool_comp.id,
+            spot_price_product = spot_price_product,
+            "Found round trip between pools of a pair"
+        );
+
+        Some(PairOpportunity {
+            path: Path(vec![swaps[buy_index].0.clone(), swaps[sell_index].2.clone()]),
+            spot_price_product,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{component, MockProtocolSim};
+    use num_bigint::BigUint;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_emits_the_best_round_trip_of_a_pair() {
+        let token_a = Bytes::from_str("0x0001").unwrap();
+        let token_b = Bytes::from_str("0x0002").unwrap();
+        let mut graph = TradingGraph::new();
+        let a = graph.add_token(token_a.clone()).unwrap();
+        let b = graph.add_token(token_b.clone()).unwrap();
+
+        let mut components = HashMap::new();
+        let mut simulations: HashMap<Bytes, Arc<dyn ProtocolSim>> = HashMap::new();
+        for (pool, rate) in [("0x1001", 1.0), ("0x1002", 1.03), ("0x1003", 0.99)] {
+            let pool = Bytes::from_str(pool).unwrap();
+            graph.add_pool(pool.clone(), [a, b]).unwrap();
+            components.insert(pool.clone(), Arc::new(component(&pool, &[token_a.clone(), token_b.clone()])));
+            simulations.insert(pool, Arc::new(MockProtocolSim::new().with_rate(rate)));
+        }
+
+        let updated = [Bytes::from_str("0x1003").unwrap()];
+        let opportunities = PairScanner::new(vec![token_a.clone()]).scan(&graph, &updated, &components, &simulations);
+        assert_eq!(opportunities.len(), 1);
+        let opportunity = &opportunities[0];
+        assert!((opportunity.spot_price_product - 1.03).abs() < 1e-9);
+        assert_ne!(opportunity.path[0].pool_comp.id, opportunity.path[1].pool_comp.id);
+        assert_eq!(opportunity.path[1].token_out.address, token_a);
+        assert!(opportunity.path.calculate_profit_loss(BigUint::from(1000u32)).unwrap() > 0.into());
+
+        let strict = PairScanner::new(vec![token_a]).with_min_spot_price_product(1.05);
+        assert!(strict.scan(&graph, &updated, &components, &simulations).is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+//! Constraints on the shape of discovered cycles.
+//!
+//! By default, path discovery only finds simple cycles: no token is visited
+//! twice, and source tokens only appear at the start of a path. This excludes
+//! legitimate shapes like A→B→A→C→A through different pools. A
+//! `DiscoveryPolicy` relaxes or tightens these constraints per repository.
+//!
+//! Paths trading through a single protocol, or back and forth through the same
+//! pool, are almost never profitable after fees. The policy can require a
+//! number of distinct protocol systems per path, and by default never uses a
+//! pool twice in a path.
+
+use crate::path::Path;
+use std::collections::HashSet;
+
+/// Constraints applied while discovering and building paths.
+///
+/// The default policy allows simple cycles only, with any protocols.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiscoveryPolicy {
+    /// How often a token may be visited again after its first visit
+    max_token_revisits: usize,
+    /// Whether source tokens may appear after the start of a path
+    allow_source_as_intermediate: bool,
+    /// Whether every swap of a path has to use a different protocol system
+    require_distinct_protocols: bool,
+    /// The number of distinct protocol systems a path has to trade through
+    min_distinct_protocols: usize,
+    /// Whether a path may trade through the same pool address more than once
+    allow_pool_reuse: bool,
+}
+
+impl DiscoveryPolicy {
+    /// Create a policy allowing simple cycles only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow every token to be visited up to `max_token_revisits` more times.
+    ///
+    /// Revisiting a source token additionally requires
+    /// [`with_source_as_intermediate`](Self::with_source_as_intermediate).
+    pub fn with_max_token_revisits(mut self, max_token_revisits: usize) -> Self {
+        self.max_token_revisits = max_token_revisits;
+        self
+    }
+
+    /// Allow source tokens to appear after the start of a path.
+    pub fn with_source_as_intermediate(mut self, allow: bool) -> Self {
+        self.allow_source_as_intermediate = allow;
+        self
+    }
+
+    /// Require every swap of a path to use a different protocol system.
+    ///
+    /// Protocol systems are only known once paths are built, so paths are
+    /// still discovered but skipped when building them.
+    pub fn with_distinct_protocols(mut self, require: bool) -> Self {
+        self.require_distinct_protocols = require;
+        self
+    }
+
+    /// Require paths to trade through at least `min_distinct_protocols` protocol systems.
+    ///
+    /// Like [`with_distinct_protocols`](Self::with_distinct_protocols), this is
+    /// checked when building paths.
+    pub fn with_min_distinct_protocols(mut self, min_distinct_protocols: usize) -> Self {
+        self.min_distinct_protocols = min_distinct_protocols;
+        self
+    }
+
+    /// Allow a path to trade through the same pool address more than once.
+    pub fn with_pool_reuse(mut self, allow: bool) -> Self {
+        self.allow_pool_reuse = allow;
+        self
+    }
+
+    /// How often a token may be visited again after its first visit.
+    pub fn max_token_revisits(&self) -> usize {
+        self.max_token_revisits
+    }
+
+    /// Whether source tokens may appear after the start of a path.
+    pub fn allows_source_as_intermediate(&self) -> bool {
+        self.allow_source_as_intermediate
+    }
+
+    /// Whether every swap of a path has to use a different protocol system.
+    pub fn requires_distinct_protocols(&self) -> bool {
+        self.require_distinct_protocols
+    }
+
+    /// The number of distinct protocol systems a path has to trade through.
+    pub fn min_distinct_protocols(&self) -> usize {
+        self.min_distinct_protocols
+    }
+
+    /// Whether a path may trade through the same pool address more than once.
+    pub fn allows_pool_reuse(&self) -> bool {
+        self.allow_pool_reuse
+    }
+
+    /// Check whether a token path may be extended by a token.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The ID of the token to append
+    /// * `source_tokens` - The IDs of the source tokens
+    /// * `path` - The token IDs of the path so far
+    pub fn allows_token(&self, token: usize, source_tokens: &[usize], path: &[usize]) -> bool {
+        if !self.allow_source_as_intermediate && source_tokens.contains(&token) {
+            return false;
+        }
+        let visits = path.iter().filter(|&&visited| visited == token).count();
+        visits <= self.max_token_revisits
+    }
+
+    /// Check whether the protocol systems of a built path are allowed.
+    pub fn allows_protocols(&self, path: &Path) -> bool {
+        let protocols: HashSet<&str> = path
+            .iter()
+            .map(|swap| swap.pool_comp.protocol_system.as_str())
+            .collect();
+        if self.require_distinct_protocols && protocols.len() < path.len() {
+            return false;
+        }
+        protocols.len() >= self.min_distinct_protocols
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_allows_simple_cycles_only() {
+        let policy = DiscoveryPolicy::new();
+        assert!(policy.allows_token(2, &[0], &[0, 1]));
+        assert!(!policy.allows_token(1, &[0], &[0, 1]));
+        assert!(!policy.allows_token(0, &[0], &[0, 1]));
+
+        // A→B→A→C needs both the source as intermediate and a revisit
+        let relaxed = policy.clone().with_max_token_revisits(1);
+        assert!(relaxed.allows_token(1, &[0], &[0, 1, 2]));
+        assert!(!relaxed.allows_token(0, &[0], &[0, 1]));
+        let relaxed = relaxed.with_source_as_intermediate(true);
+        assert!(relaxed.allows_token(0, &[0], &[0, 1]));
+        assert!(!relaxed.allows_token(0, &[0], &[0, 1, 0, 2]));
+    }
+
+    #[test]
+    fn test_requires_distinct_protocols_per_path() {
+        use crate::testing::{cyclic_path, MockProtocolSim};
+        use std::str::FromStr;
+        use tycho_common::Bytes;
+
+        let tokens: Vec<Bytes> = ["0x0001", "0x0002", "0x0003"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        let mut path = cyclic_path(&tokens, &Bytes::from_str("0x1001").unwrap(), &MockProtocolSim::new()).unwrap();
+        assert!(DiscoveryPolicy::new().allows_protocols(&path));
+        assert!(!DiscoveryPolicy::new().with_min_distinct_protocols(2).allows_protocols(&path));
+
+        let mut pool_comp = (*path.0[0].pool_comp).clone();
+        pool_comp.protocol_system = "other".to_string();
+        path.0[0].pool_comp = std::sync::Arc::new(pool_comp);
+        assert!(DiscoveryPolicy::new().with_min_distinct_protocols(2).allows_protocols(&path));
+        assert!(!DiscoveryPolicy::new().with_distinct_protocols(true).allows_protocols(&path));
+    }
+}
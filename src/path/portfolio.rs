@@ -0,0 +1,276 @@
+//! Portfolio selection across non-conflicting arbitrage opportunities.
+//!
+//! When several profitable paths are found in one block, all of them can be
+//! executed as long as they do not interfere with each other. This module picks
+//! a maximal set of opportunities that:
+//!
+//! - share no pool, so executing one does not move the price seen by another
+//! - fit into the available balance of each source token
+//! - use disjoint nonce ranges, so their transactions never compete for a nonce
+//!
+//! Selection is greedy in score order, which keeps the best opportunity in the
+//! portfolio and adds further ones only when they are compatible with everything
+//! selected before them. Capping the portfolio at the top N opportunities keeps
+//! marginal trades from burning nonces and bribes, and pool disjointness can be
+//! given up for opportunities that are known not to interfere, e.g. on pools
+//! deep enough that one trade barely moves the price seen by the next.
+
+use crate::path::{PathExt, RankedOpportunity};
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet};
+use tycho_common::Bytes;
+
+/// Default number of transactions each opportunity needs (approval and swap).
+const DEFAULT_TRANSACTIONS_PER_OPPORTUNITY: u64 = 2;
+
+/// An opportunity that can be placed into a portfolio.
+pub trait PortfolioCandidate {
+    /// The executed path of the opportunity.
+    fn path(&self) -> &PathExt;
+
+    /// Score used to rank candidates, higher is better.
+    fn score(&self) -> f64;
+}
+
+impl PortfolioCandidate for RankedOpportunity {
+    fn path(&self) -> &PathExt {
+        &self.path
+    }
+
+    fn score(&self) -> f64 {
+        self.reference_profit
+    }
+}
+
+/// An opportunity selected into a portfolio.
+#[derive(Debug, Clone)]
+pub struct PortfolioEntry<C> {
+    /// The selected candidate
+    pub candidate: C,
+    /// Offset from the base nonce of the first transaction of this opportunity
+    pub nonce_offset: u64,
+}
+
+/// A set of mutually compatible opportunities.
+#[derive(Debug, Clone)]
+pub struct Portfolio<C> {
+    /// Selected opportunities in score order
+    pub entries: Vec<PortfolioEntry<C>>,
+}
+
+impl<C: PortfolioCandidate> Portfolio<C> {
+    /// Number of selected opportunities.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no opportunity was selected.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sum of the scores of all selected opportunities.
+    pub fn total_score(&self) -> f64 {
+        self.entries.iter().map(|entry| entry.candidate.score()).sum()
+    }
+
+    /// Consume the portfolio and return the selected candidates.
+    pub fn into_candidates(self) -> Vec<C> {
+        self.entries.into_iter().map(|entry| entry.candidate).collect()
+    }
+}
+
+/// Selects a maximal set of non-conflicting opportunities.
+#[derive(Debug, Clone)]
+pub struct PortfolioSelector {
+    balances: HashMap<Bytes, BigUint>,
+    max_opportunities: Option<usize>,
+    transactions_per_opportunity: u64,
+    disjoint_pools: bool,
+}
+
+impl PortfolioSelector {
+    /// Create a selector constrained by the given source token balances.
+    ///
+    /// Opportunities starting from a token without a balance entry are never selected.
+    pub fn new(balances: HashMap<Bytes, BigUint>) -> Self {
+        Self {
+            balances,
+            max_opportunities: None,
+            transactions_per_opportunity: DEFAULT_TRANSACTIONS_PER_OPPORTUNITY,
+            disjoint_pools: true,
+        }
+    }
+
+    /// Limit the number of opportunities in a portfolio.
+    pub fn with_max_opportunities(mut self, max_opportunities: usize) -> Self {
+        self.max_opportunities = Some(max_opportunities);
+        self
+    }
+
+    /// Set whether opportunities of a portfolio have to trade through disjoint pools (default: true).
+    pub fn with_disjoint_pools(mut self, disjoint_pools: bool) -> Self {
+        self.disjoint_pools = disjoint_pools;
+        self
+    }
+
+    /// Set how many transactions, and therefore nonces, each opportunity uses.
+    pub fn with_transactions_per_opportunity(mut self, transactions: u64) -> Self {
+        self.transactions_per_opportunity = transactions;
+        self
+    }
+
+    /// Select the portfolio from a set of candidates.
+    ///
+    /// Candidates are considered in descending score order. A candidate is added
+    /// if none of its pools is already used by the portfolio, unless pools may be
+    /// shared, and its input amount fits into the remaining balance of its source token.
+    pub fn select<C: PortfolioCandidate>(&self, mut candidates: Vec<C>) -> Portfolio<C> {
+        candidates.sort_by(|a, b| b.score().total_cmp(&a.score()));
+
+        let max_opportunities = self.max_opportunities.unwrap_or(usize::MAX);
+        let mut remaining = self.balances.clone();
+        let mut used_pools: HashSet<Bytes> = HashSet::new();
+        let mut entries = Vec::new();
+        let mut skipped_conflicts = 0;
+
+        for candidate in candidates {
+            if entries.len() >= max_opportunities {
+                break;
+            }
+
+            let path = candidate.path();
+            let Some(first_swap) = path.first() else {
+                continue;
+            };
+            let Ok(source_token) = path.start_token() else {
+                continue;
+            };
+
+            let pools: Vec<&Bytes> = path.iter().map(|swap| &swap.pool_comp.id).collect();
+            if self.disjoint_pools && pools.iter().any(|pool| used_pools.contains(*pool)) {
+                skipped_conflicts += 1;
+                continue;
+            }
+
+            let Some(available) = remaining.get_mut(&source_token) else {
+                continue;
+            };
+            if first_swap.amount_in > *available {
+                skipped_conflicts += 1;
+                continue;
+            }
+
+            *available -= &first_swap.amount_in;
+            used_pools.extend(pools.into_iter().cloned());
+
+            let nonce_offset = entries.len() as u64 * self.transactions_per_opportunity;
+            entries.push(PortfolioEntry {
+                candidate,
+                nonce_offset,
+            });
+        }
+
+        tracing::debug!(
+            selected = entries.len(),
+            skipped_conflicts = skipped_conflicts,
+            used_pools = used_pools.len(),
+            "Portfolio selection completed"
+        );
+
+        Portfolio { entries }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{component, token, MockProtocolSim};
+    use crate::path::SwapExt;
+    use num_bigint::BigInt;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    fn opportunity(pools: &[&str], amount_in: u32, score: f64) -> RankedOpportunity {
+        let token_a = Bytes::from_str("0x0001").unwrap();
+        let token_b = Bytes::from_str("0x0002").unwrap();
+
+        let swaps = pools
+            .iter()
+            .map(|pool| {
+                let pool_addr = Bytes::from_str(pool).unwrap();
+                SwapExt {
+                    pool_comp: Arc::new(component(&pool_addr, &[token_a.clone(), token_b.clone()])),
+                    pool_sim: Arc::new(MockProtocolSim::new()),
+                    token_in: token(&token_a, "TEST"),
+                    token_out: token(&token_b, "TEST"),
+                    amount_in: BigUint::from(amount_in),
+                    amount_out: BigUint::from(amount_in),
+                    gas: BigUint::from(0u32),
+                }
+            })
+            .collect();
+
+        RankedOpportunity {
+            source_token: token_a,
+            path: PathExt(swaps),
+            profit: BigInt::from(0),
+            reference_profit: score,
+        }
+    }
+
+    fn balances(amount: u32) -> HashMap<Bytes, BigUint> {
+        HashMap::from([(Bytes::from_str("0x0001").unwrap(), BigUint::from(amount))])
+    }
+
+    #[test]
+    fn test_selects_disjoint_pools_only() {
+        let candidates = vec![
+            opportunity(&["0x1001", "0x1002"], 100, 5.0),
+            opportunity(&["0x1002", "0x1003"], 100, 10.0),
+            opportunity(&["0x1004", "0x1005"], 100, 1.0),
+        ];
+
+        let portfolio = PortfolioSelector::new(balances(1_000)).select(candidates);
+
+        assert_eq!(portfolio.len(), 2);
+        assert_eq!(portfolio.entries[0].candidate.reference_profit, 10.0);
+        assert_eq!(portfolio.entries[1].candidate.reference_profit, 1.0);
+        assert_eq!(portfolio.entries[0].nonce_offset, 0);
+        assert_eq!(portfolio.entries[1].nonce_offset, 2);
+        assert_eq!(portfolio.total_score(), 11.0);
+    }
+
+    #[test]
+    fn test_respects_balance_and_size_limits() {
+        let candidates = vec![
+            opportunity(&["0x1001"], 600, 3.0),
+            opportunity(&["0x1002"], 600, 2.0),
+            opportunity(&["0x1003"], 300, 1.0),
+        ];
+
+        let portfolio = PortfolioSelector::new(balances(1_000)).select(candidates.clone());
+        assert_eq!(portfolio.len(), 2);
+        assert_eq!(portfolio.entries[1].candidate.reference_profit, 1.0);
+
+        let limited = PortfolioSelector::new(balances(10_000))
+            .with_max_opportunities(1)
+            .select(candidates);
+        assert_eq!(limited.len(), 1);
+
+        let shared = PortfolioSelector::new(balances(10_000))
+            .with_disjoint_pools(false)
+            .with_max_opportunities(2)
+            .select(vec![
+                opportunity(&["0x1001", "0x1002"], 100, 5.0),
+                opportunity(&["0x1002", "0x1003"], 100, 10.0),
+                opportunity(&["0x1004"], 100, 1.0),
+            ]);
+        let scores: Vec<f64> = shared.entries.iter().map(|entry| entry.candidate.reference_profit).collect();
+        assert_eq!(scores, vec![10.0, 5.0]);
+
+        let unfunded = PortfolioSelector::new(HashMap::new())
+            .select(vec![opportunity(&["0x1001"], 1, 1.0)]);
+        assert!(unfunded.is_empty());
+    }
+}
@@ -0,0 +1,305 @@
+//! Historical profitability ranking of paths.
+//!
+//! Most arbitrage profit comes from a small set of recurring routes, while the
+//! search treats every path touched by an update equally. `PathRanking` keeps an
+//! exponentially decayed profitability score per path, fed by simulation and
+//! execution results, so the search can evaluate the historically best paths
+//! first and spend a limited budget where profit is most likely.
+//!
+//! Paths are identified by a `PathId` derived from their pools and swap
+//! directions. Unlike positions in the `PathRepository`, these ids stay stable
+//! when pools are added to or removed from the graph.
+
+use crate::errors::Result;
+use crate::graph::TradingGraph;
+use crate::path::{Path, PathExt};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::RwLock;
+use tycho_common::Bytes;
+
+/// Default number of blocks after which a score has decayed to half its value.
+const DEFAULT_HALF_LIFE_BLOCKS: f64 = 50.0;
+
+/// Stable identifier of a path, derived from its pools and input tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PathId(u64);
+
+impl PathId {
+    /// Derive the id of a path from its hops.
+    ///
+    /// # Arguments
+    ///
+    /// * `hops` - The pool address and input token address of every swap, in order
+    pub fn from_hops<'a, I>(hops: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a Bytes, &'a Bytes)>,
+    {
+        let mut hasher = DefaultHasher::new();
+        for (pool, token_in) in hops {
+            pool.hash(&mut hasher);
+            token_in.hash(&mut hasher);
+        }
+        Self(hasher.finish())
+    }
+
+    /// Derive the id of a path from the pool ids stored in a `PathRepository`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a pool or token is not part of the graph
+    pub fn from_pool_path(pool_ids: &[usize], graph: &TradingGraph) -> Result<Self> {
+        let mut hops = Vec::with_capacity(pool_ids.len());
+        for &pool_id in pool_ids {
+            let pool = graph.get_pool(pool_id)?;
+            let token_in = graph.get_token(pool.token_in_id())?;
+            hops.push((pool.address(), token_in.address()));
+        }
+        Ok(Self::from_hops(hops))
+    }
+
+    /// The id of a path.
+    pub fn of_path(path: &Path) -> Self {
+        Self::from_hops(path.iter().map(|swap| (&swap.pool_comp.id, &swap.token_in.address)))
+    }
+
+    /// The id of an executed path.
+    pub fn of_path_ext(path: &PathExt) -> Self {
+        Self::from_hops(path.iter().map(|swap| (&swap.pool_comp.id, &swap.token_in.address)))
+    }
+
+    /// The raw id value.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for PathId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Decayed profitability score of one path.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathScore {
+    /// Score as of `last_block`
+    pub score: f64,
+    /// Block of the last recorded result
+    pub last_block: u64,
+    /// Number of recorded results
+    pub samples: u64,
+}
+
+/// Exponentially decayed profitability ranking of paths.
+#[derive(Debug)]
+pub struct PathRanking {
+    half_life_blocks: f64,
+    scores: RwLock<HashMap<PathId, PathScore>>,
+}
+
+impl PathRanking {
+    /// Create a ranking whose scores halve every 50 blocks.
+    pub fn new() -> Self {
+        Self {
+            half_life_blocks: DEFAULT_HALF_LIFE_BLOCKS,
+            scores: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Set the number of blocks after which a score has decayed to half its value.
+    pub fn with_half_life_blocks(mut self, half_life_blocks: f64) -> Self {
+        self.half_life_blocks = half_life_blocks;
+        self
+    }
+
+    /// Record the result of evaluating a path.
+    ///
+    /// The existing score is decayed to `block_number` before the profit is added,
+    /// so unprofitable results are recorded with a profit of zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `path_id` - The id of the evaluated path
+    /// * `profit` - The realized or simulated profit, in native token units
+    /// * `block_number` - The block the result belongs to
+    pub fn record(&self, path_id: PathId, profit: f64, block_number: u64) {
+        let Ok(mut scores) = self.scores.write() else {
+            return;
+        };
+
+        let entry = scores.entry(path_id).or_default();
+        entry.score = self.decayed(entry, block_number) + profit.max(0.0);
+        entry.last_block = entry.last_block.max(block_number);
+        entry.samples += 1;
+
+        tracing::trace!(
+            path_id = %path_id,
+            profit = profit,
+            score = entry.score,
+            samples = entry.samples,
+            "Recorded path profitability"
+        );
+    }
+
+    /// Score of a path decayed to `block_number`, or zero if it was never recorded.
+    pub fn score(&self, path_id: &PathId, block_number: u64) -> f64 {
+        self.scores
+            .read()
+            .ok()
+            .and_then(|scores| scores.get(path_id).map(|entry| self.decayed(entry, block_number)))
+            .unwrap_or(0.0)
+    }
+
+    /// Number of ranked paths.
+    pub fn len(&self) -> usize {
+        self.scores.read().map_or(0, |scores| scores.len())
+    }
+
+    /// Whether no path has been ranked yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `k` highest scoring paths at `block_number`, best first.
+    pub fn top_k(&self, k: usize, block_number: u64) -> Vec<(PathId, f64)> {
+        let Ok(scores) = self.scores.read() else {
+            return Vec::new();
+        };
+
+        let mut ranked: Vec<(PathId, f64)> = scores
+            .iter()
+            .map(|(path_id, entry)| (*path_id, self.decayed(entry, block_number)))
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        ranked.truncate(k);
+        ranked
+    }
+
+    /// Move the `k` highest scoring items to the front, best first.
+    ///
+    /// All other items keep their relative order, and items without an id or
+    /// without a positive score are never moved forward.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The items to evaluate this block, e.g. path indices
+    /// * `k` - The number of ranked items to evaluate first
+    /// * `block_number` - The block scores are decayed to
+    /// * `path_id` - Maps an item to the id of its path
+    pub fn prioritize<T, F>(&self, items: Vec<T>, k: usize, block_number: u64, path_id: F) -> Vec<T>
+    where
+        F: Fn(&T) -> Option<PathId>,
+    {
+        if k == 0 || self.is_empty() {
+            return items;
+        }
+
+        let mut scored: Vec<(usize, f64)> = items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let score = self.score(&path_id(item)?, block_number);
+                (score > 0.0).then_some((index, score))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        scored.truncate(k);
+
+        let mut slots: Vec<Option<T>> = items.into_iter().map(Some).collect();
+        let mut prioritized = Vec::with_capacity(slots.len());
+        for (index, _) in &scored {
+            if let Some(item) = slots[*index].take() {
+                prioritized.push(item);
+            }
+        }
+        prioritized.extend(slots.into_iter().flatten());
+
+        tracing::debug!(
+            ranked_items = scored.len(),
+            total_items = prioritized.len(),
+            "Prioritized historically profitable paths"
+        );
+
+        prioritized
+    }
+
+    /// Drop paths whose score decayed below `min_score` at `block_number`.
+    ///
+    /// # Returns
+    ///
+    /// The number of dropped paths
+    pub fn prune(&self, min_score: f64, block_number: u64) -> usize {
+        let Ok(mut scores) = self.scores.write() else {
+            return 0;
+        };
+
+        let before = scores.len();
+        scores.retain(|_, entry| self.decayed(entry, block_number) >= min_score);
+        before - scores.len()
+    }
+
+    fn decayed(&self, entry: &PathScore, block_number: u64) -> f64 {
+        if self.half_life_blocks <= 0.0 {
+            return entry.score;
+        }
+        let age = block_number.saturating_sub(entry.last_block) as f64;
+        entry.score * 0.5f64.powf(age / self.half_life_blocks)
+    }
+}
+
+impl Default for PathRanking {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn path_id(pool: u8) -> PathId {
+        let pool = Bytes::from_str(&format!("0x{:02x}00", pool)).unwrap();
+        let token = Bytes::from_str("0xeeee").unwrap();
+        PathId::from_hops([(&pool, &token)])
+    }
+
+    #[test]
+    fn test_scores_decay_with_half_life() {
+        let ranking = PathRanking::new().with_half_life_blocks(10.0);
+        let id = path_id(1);
+
+        ranking.record(id, 100.0, 100);
+        assert!((ranking.score(&id, 100) - 100.0).abs() < 1e-9);
+        assert!((ranking.score(&id, 110) - 50.0).abs() < 1e-9);
+
+        // An unprofitable result only decays the score
+        ranking.record(id, -20.0, 120);
+        assert!((ranking.score(&id, 120) - 25.0).abs() < 1e-9);
+        assert_eq!(ranking.score(&path_id(2), 120), 0.0);
+
+        assert_eq!(ranking.prune(30.0, 120), 1);
+        assert!(ranking.is_empty());
+    }
+
+    #[test]
+    fn test_prioritize_moves_top_k_first() {
+        let ranking = PathRanking::new();
+        ranking.record(path_id(2), 10.0, 1);
+        ranking.record(path_id(4), 30.0, 1);
+        ranking.record(path_id(3), 20.0, 1);
+
+        let items: Vec<u8> = vec![1, 2, 3, 4, 5];
+        let prioritized = ranking.prioritize(items, 2, 1, |pool| Some(path_id(*pool)));
+        assert_eq!(prioritized, vec![4, 3, 1, 2, 5]);
+
+        let top = ranking.top_k(1, 1);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].0, path_id(4));
+    }
+}
@@ -4,10 +4,12 @@
 //! trading paths from a graph structure. It handles path generation, indexing,
 //! and efficient lookup operations for arbitrage path discovery.
 
+use crate::budget::SearchDeadline;
 use crate::errors::{PathError, Result};
-use crate::graph::TradingGraph;
-use crate::path::Path;
-use std::collections::HashMap;
+use crate::graph::{PoolHandle, PoolId, TokenHandle, TokenId, TradingGraph};
+use crate::path::{DiscoveryMonitor, DiscoveryOutcome, DiscoveryPolicy, DiscoveryStage, MissingDataTracker, Path, PathTemplate};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tycho_common::Bytes;
 use tycho_simulation::{
     protocol::{models::ProtocolComponent, state::ProtocolSim},
@@ -18,20 +20,41 @@ use tycho_simulation::{
 /// The `PathRepository` maintains indexed collections of trading paths discovered
 /// from a trading graph. It provides efficient lookup and retrieval operations
 /// for paths involving specific tokens or pools.
+///
+/// Paths are stored as [`TokenHandle`]s and [`PoolHandle`]s of the graph they
+/// were discovered from, so pools or tokens removed from the graph never make a
+/// stored path point at the wrong pool. The public `token_paths` and
+/// `pool_paths` hold the matching graph IDs, which are only valid until tokens
+/// or pools are removed outside the repository; call
+/// [`remap_to_graph`](Self::remap_to_graph) after such removals.
 #[derive(Debug, Clone)]
 pub struct PathRepository {
     /// Source tokens that serve as starting points for path discovery
     source_tokens: Vec<Bytes>,
-    /// Maximum allowed path length (number of swaps)
+    /// Maximum allowed path length (number of swaps), capping every source
     maximum_path_length: usize,
+    /// Lower maximum path lengths of single source tokens
+    source_path_lengths: HashMap<Bytes, usize>,
+    /// The only source token paths start from, if the repository is a shard
+    shard_source: Option<Bytes>,
+    /// Constraints on the shape of discovered paths
+    discovery_policy: DiscoveryPolicy,
     /// Token-based paths (sequences of token indices)
     pub token_paths: Vec<Vec<usize>>,
     /// Pool-based paths (sequences of pool indices)
     pub pool_paths: Vec<Vec<usize>>,
+    /// Stable handles of the tokens of each token path
+    token_path_handles: Vec<Vec<TokenHandle>>,
+    /// Stable handles of the pools of each pool path
+    pool_path_handles: Vec<Vec<PoolHandle>>,
+    /// Stable handles of the tokens each pool path trades through, closing the cycle
+    pool_path_token_handles: Vec<Vec<TokenHandle>>,
     /// Index mapping tokens to their associated path indices
     token_to_path_indices: HashMap<Bytes, Vec<usize>>,
     /// Index mapping pools to their associated path indices
     pool_to_path_indices: HashMap<Bytes, Vec<usize>>,
+    /// Pools whose paths could not be built for missing protocol data
+    missing_data: MissingDataTracker,
 }
 
 impl PathRepository {
@@ -51,13 +74,122 @@ impl PathRepository {
         Self {
             source_tokens,
             maximum_path_length,
+            source_path_lengths: HashMap::new(),
+            shard_source: None,
+            discovery_policy: DiscoveryPolicy::default(),
             token_paths: Vec::new(),
             pool_paths: Vec::new(),
+            token_path_handles: Vec::new(),
+            pool_path_handles: Vec::new(),
+            pool_path_token_handles: Vec::new(),
             token_to_path_indices: HashMap::new(),
             pool_to_path_indices: HashMap::new(),
+            missing_data: MissingDataTracker::new(),
+        }
+    }
+
+    /// Set the constraints on the shape of discovered paths.
+    ///
+    /// The policy applies to paths discovered afterwards, set it before the
+    /// first discovery.
+    pub fn with_discovery_policy(mut self, discovery_policy: DiscoveryPolicy) -> Self {
+        self.discovery_policy = discovery_policy;
+        self
+    }
+
+    /// The constraints on the shape of discovered paths.
+    pub fn discovery_policy(&self) -> &DiscoveryPolicy {
+        &self.discovery_policy
+    }
+
+    /// Limit the length of paths starting from one source token.
+    ///
+    /// Search cost grows exponentially with the path length, so less liquid
+    /// source tokens can be searched less deeply, e.g. 2 swaps from USDC
+    /// while WETH gets the full 3. The maximum path length of the repository
+    /// still caps the limit. Like the discovery policy, set it before the
+    /// first discovery.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_token` - The address of the source token
+    /// * `maximum_path_length` - Maximum number of swaps of paths starting from it
+    pub fn with_source_path_length(mut self, source_token: Bytes, maximum_path_length: usize) -> Self {
+        self.source_path_lengths.insert(source_token, maximum_path_length);
+        self
+    }
+
+    /// Limit the length of paths starting from several source tokens, see
+    /// [`with_source_path_length`](Self::with_source_path_length).
+    pub fn with_source_path_lengths<I>(mut self, source_path_lengths: I) -> Self
+    where
+        I: IntoIterator<Item = (Bytes, usize)>,
+    {
+        self.source_path_lengths.extend(source_path_lengths);
+        self
+    }
+
+    /// Only discover paths starting from one of the source tokens.
+    ///
+    /// The other source tokens still count as sources for the discovery
+    /// policy, e.g. they are not used as intermediate tokens by default, so
+    /// the shards of all source tokens together find the same paths as one
+    /// repository. See [`ShardedPathRepository`](crate::path::ShardedPathRepository).
+    pub fn with_shard_source(mut self, source_token: Bytes) -> Self {
+        self.shard_source = Some(source_token);
+        self
+    }
+
+    /// The only source token paths start from, if the repository is a shard.
+    pub fn shard_source(&self) -> Option<&Bytes> {
+        self.shard_source.as_ref()
+    }
+
+    /// The source token addresses.
+    pub fn source_tokens(&self) -> &[Bytes] {
+        &self.source_tokens
+    }
+
+    /// The maximum number of swaps of any path.
+    pub fn maximum_path_length(&self) -> usize {
+        self.maximum_path_length
+    }
+
+    /// The maximum number of swaps of paths starting from a source token.
+    pub fn maximum_path_length_for(&self, source_token: &Bytes) -> usize {
+        self.source_path_lengths
+            .get(source_token)
+            .map_or(self.maximum_path_length, |&length| length.min(self.maximum_path_length))
+    }
+
+    /// The source tokens paths start from, by their graph indices.
+    fn start_token_indices(&self, graph: &TradingGraph, source_indices: &[TokenId]) -> Vec<TokenId> {
+        let Some(shard_source) = &self.shard_source else {
+            return source_indices.to_vec();
+        };
+        source_indices
+            .iter()
+            .copied()
+            .filter(|&source_index| graph.get_token(source_index).is_ok_and(|token| token.address() == shard_source))
+            .collect()
+    }
+
+    /// The maximum path length of a source token by its graph index.
+    fn maximum_path_length_at(&self, graph: &TradingGraph, source_index: TokenId) -> usize {
+        match graph.get_token(source_index) {
+            Ok(token) => self.maximum_path_length_for(token.address()),
+            Err(_) => self.maximum_path_length,
         }
     }
 
+    /// The pools whose paths were skipped during building because their protocol data is missing.
+    ///
+    /// Search the pools returned by [`MissingDataTracker::recover`] once their
+    /// data arrived to build the skipped paths again.
+    pub fn missing_data(&self) -> &MissingDataTracker {
+        &self.missing_data
+    }
+
     /// Get path indices for a specific pool.
     ///
     /// # Arguments
@@ -126,6 +258,169 @@ impl PathRepository {
             })
     }
 
+    /// Get the pool handles of the path at a specific index.
+    ///
+    /// Unlike the pool IDs of [`get_pool_path_by_index`](Self::get_pool_path_by_index),
+    /// the handles stay valid while pools are removed from the graph.
+    ///
+    /// # Arguments
+    ///
+    /// * `path_index` - The index of the path to retrieve
+    pub fn get_pool_path_handles_by_index(&self, path_index: usize) -> Result<&[PoolHandle]> {
+        self.pool_path_handles
+            .get(path_index)
+            .map(Vec::as_slice)
+            .ok_or_else(|| PathError::InvalidPathIndex { index: path_index }.into())
+    }
+
+    /// Resolve the pools of the path at a specific index to their current graph IDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `path_index` - The index of the path to resolve
+    /// * `graph` - The trading graph the path was discovered from
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is invalid or a pool of the path was removed
+    pub fn resolve_pool_path(&self, path_index: usize, graph: &TradingGraph) -> Result<Vec<PoolId>> {
+        self.get_pool_path_handles_by_index(path_index)?
+            .iter()
+            .map(|&handle| graph.resolve_pool(handle))
+            .collect()
+    }
+
+    /// Get the path at a specific index together with the tokens it trades through.
+    ///
+    /// The tokens are recorded when the path is discovered, so they always match
+    /// the pools of the path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path_index` - The index of the path to retrieve
+    /// * `graph` - The trading graph the path was discovered from
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is invalid or a pool or token of the path was removed
+    pub fn get_discovered_path(&self, path_index: usize, graph: &TradingGraph) -> Result<DiscoveredPath> {
+        let pools = self.resolve_pool_path(path_index, graph)?;
+        let tokens = self.pool_path_token_handles[path_index]
+            .iter()
+            .map(|&handle| graph.resolve_token(handle))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(DiscoveredPath { pools, tokens })
+    }
+
+    /// Iterate over the discovered paths matching a query.
+    ///
+    /// Paths through tokens or pools no longer part of the graph are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The trading graph the paths were discovered from
+    /// * `query` - The filters paths have to pass
+    ///
+    /// # Returns
+    ///
+    /// The index and the path of every matching path, in discovery order
+    pub fn query<'a>(
+        &'a self,
+        graph: &'a TradingGraph,
+        query: PathQuery<'a>,
+    ) -> impl Iterator<Item = (usize, DiscoveredPath)> + 'a {
+        (0..self.pool_paths.len()).filter_map(move |path_index| {
+            let path = self.get_discovered_path(path_index, graph).ok()?;
+            query.matches(&path, graph).then_some((path_index, path))
+        })
+    }
+
+    /// Iterate over the discovered paths starting at a source token.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The trading graph the paths were discovered from
+    /// * `token` - The address of the source token
+    /// * `max_len` - The maximum number of swaps of returned paths
+    ///
+    /// # Returns
+    ///
+    /// The index and the path of every matching path, in discovery order
+    pub fn paths_for_source<'a>(
+        &'a self,
+        graph: &'a TradingGraph,
+        token: &Bytes,
+        max_len: usize,
+    ) -> impl Iterator<Item = (usize, DiscoveredPath)> + 'a {
+        self.query(
+            graph,
+            PathQuery::new()
+                .with_source_token(token.clone())
+                .with_max_length(max_len),
+        )
+    }
+
+    /// Add the paths generated by a template from every source token.
+    ///
+    /// Paths longer than the maximum path length of their source token, and paths already part of
+    /// the repository, e.g. found by [`discover_paths`](Self::discover_paths),
+    /// are skipped. Generated paths are not extended when pools are added
+    /// later, add the template paths again after graph updates.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The trading graph to generate paths from
+    /// * `template` - The generator of the paths
+    ///
+    /// # Returns
+    ///
+    /// The number of added paths
+    pub fn add_template_paths<T: PathTemplate + ?Sized>(&mut self, graph: &TradingGraph, template: &T) -> usize {
+        let mut added_paths = 0;
+        let source_indices = self.resolve_source_token_indices(graph);
+        for source_index in self.start_token_indices(graph, &source_indices) {
+            let maximum_path_length = self.maximum_path_length_at(graph, source_index);
+            for path in template.generate(graph, source_index) {
+                if path.is_empty() || path.len() > maximum_path_length || self.contains_pool_path(graph, &path.pools) {
+                    continue;
+                }
+                self.store_discovered_pool_path(graph, &path.tokens[..path.len()], path.pools);
+                added_paths += 1;
+            }
+        }
+
+        tracing::debug!(
+            added_paths = added_paths,
+            total_pool_paths = self.pool_paths.len(),
+            "Added template paths"
+        );
+
+        added_paths
+    }
+
+    /// Whether a pool path is already part of the repository.
+    fn contains_pool_path(&self, graph: &TradingGraph, pool_path: &[PoolId]) -> bool {
+        let Some(pool_handles) = pool_path
+            .iter()
+            .map(|&pool_id| graph.pool_handle(pool_id).ok())
+            .collect::<Option<Vec<_>>>()
+        else {
+            return false;
+        };
+        let Some(first_pool) = pool_path.first().and_then(|&pool_id| graph.get_pool(pool_id).ok()) else {
+            return false;
+        };
+
+        self.pool_to_path_indices
+            .get(first_pool.address())
+            .is_some_and(|path_indices| {
+                path_indices
+                    .iter()
+                    .any(|&path_index| self.pool_path_handles[path_index] == pool_handles)
+            })
+    }
+
     /// Discover new paths in the repository based on graph updates.
     ///
     /// This method discovers new trading paths when the graph is updated with new
@@ -135,39 +430,177 @@ impl PathRepository {
     ///
     /// * `graph` - The trading graph to discover paths from
     /// * `new_token_offset` - Starting index of newly added tokens
-    /// * `_new_token_count` - Number of newly added tokens (unused but kept for API compatibility)
+    /// * `new_token_count` - Number of newly added tokens (unused but kept for API compatibility)
     /// * `new_pool_offset` - Starting index of newly added pools
     /// * `new_pool_count` - Number of newly added pools
     pub fn discover_paths(
         &mut self,
         graph: &TradingGraph,
         new_token_offset: usize,
-        _new_token_count: usize,
+        new_token_count: usize,
         new_pool_offset: usize,
         new_pool_count: usize,
     ) {
+        self.discover_paths_monitored(
+            graph,
+            new_token_offset,
+            new_token_count,
+            new_pool_offset,
+            new_pool_count,
+            &mut DiscoveryMonitor::new(),
+        );
+    }
+
+    /// Discover new paths like [`discover_paths`](Self::discover_paths), with
+    /// cancellation and progress reporting.
+    ///
+    /// A cancelled discovery discards the paths it found so far, leaving the
+    /// repository as it was before, so it can be run again with the same offsets.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The trading graph to discover paths from
+    /// * `new_token_offset` - Starting index of newly added tokens
+    /// * `_new_token_count` - Number of newly added tokens (unused but kept for API compatibility)
+    /// * `new_pool_offset` - Starting index of newly added pools
+    /// * `new_pool_count` - Number of newly added pools
+    /// * `monitor` - Cancellation and progress reporting of the discovery
+    ///
+    /// # Returns
+    ///
+    /// Whether the discovery completed or was cancelled
+    pub fn discover_paths_monitored(
+        &mut self,
+        graph: &TradingGraph,
+        new_token_offset: usize,
+        _new_token_count: usize,
+        new_pool_offset: usize,
+        new_pool_count: usize,
+        monitor: &mut DiscoveryMonitor<'_>,
+    ) -> DiscoveryOutcome {
         let source_indices = self.resolve_source_token_indices(graph);
+        let (token_paths_before, pool_paths_before) = (self.token_paths.len(), self.pool_paths.len());
+        monitor.start();
 
         tracing::info!(
             source_token_count = self.source_tokens.len(),
             resolved_source_count = source_indices.len(),
             max_path_length = self.maximum_path_length,
+            source_path_limits = self.source_path_lengths.len(),
             new_pool_offset = new_pool_offset,
             new_pool_count = new_pool_count,
             "Starting path discovery"
         );
 
         // Discover token-based paths
-        self.discover_token_paths(graph, &source_indices, new_token_offset);
+        self.discover_token_paths(graph, &source_indices, new_token_offset, monitor);
 
         // Discover pool-based paths from token paths
-        self.discover_pool_paths_from_updates(graph, new_pool_offset, new_pool_count);
+        if !monitor.should_stop() {
+            self.discover_pool_paths_from_updates(graph, new_pool_offset, new_pool_count, monitor);
+        }
+
+        if monitor.should_stop() {
+            self.truncate_paths(token_paths_before, pool_paths_before);
+            tracing::info!(
+                total_token_paths = self.token_paths.len(),
+                total_pool_paths = self.pool_paths.len(),
+                "Path discovery cancelled, discarded its paths"
+            );
+            return DiscoveryOutcome::Cancelled;
+        }
+        monitor.report(DiscoveryStage::PoolPaths, 0, true);
 
         tracing::info!(
             total_token_paths = self.token_paths.len(),
             total_pool_paths = self.pool_paths.len(),
             "Path discovery completed"
         );
+        DiscoveryOutcome::Completed
+    }
+
+    /// Merge the paths another repository discovered, e.g. a copy of this one
+    /// that discovered paths in the background.
+    ///
+    /// The paths are resolved against `graph` through their handles, so the
+    /// graph may have changed since they were discovered. Paths through
+    /// removed tokens or pools, and paths already in the repository, are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The current trading graph
+    /// * `discovered` - The repository holding the discovered paths
+    /// * `token_path_offset` - Index of the first token path of `discovered` to merge
+    /// * `pool_path_offset` - Index of the first pool path of `discovered` to merge
+    ///
+    /// # Returns
+    ///
+    /// The number of merged pool paths
+    pub fn merge_discovered(
+        &mut self,
+        graph: &TradingGraph,
+        discovered: &PathRepository,
+        token_path_offset: usize,
+        pool_path_offset: usize,
+    ) -> usize {
+        let mut known_token_paths: HashSet<&[TokenHandle]> = self.token_path_handles.iter().map(Vec::as_slice).collect();
+        let mut token_paths = Vec::new();
+        for handles in discovered.token_path_handles.iter().skip(token_path_offset) {
+            if !known_token_paths.insert(handles.as_slice()) {
+                continue;
+            }
+            if let Ok(token_path) = handles.iter().map(|&handle| graph.resolve_token(handle)).collect::<Result<Vec<_>>>() {
+                token_paths.push(token_path);
+            }
+        }
+        for token_path in token_paths {
+            self.store_discovered_token_path(graph, token_path);
+        }
+
+        let mut merged_paths = 0;
+        let stored_paths = discovered
+            .pool_path_handles
+            .iter()
+            .zip(&discovered.pool_path_token_handles)
+            .skip(pool_path_offset);
+        for (handles, token_handles) in stored_paths {
+            let pool_path = handles.iter().map(|&handle| graph.resolve_pool(handle)).collect::<Result<Vec<_>>>();
+            let token_path = token_handles.iter().map(|&handle| graph.resolve_token(handle)).collect::<Result<Vec<_>>>();
+            let (Ok(pool_path), Ok(token_path)) = (pool_path, token_path) else {
+                continue;
+            };
+            if pool_path.is_empty() || self.contains_pool_path(graph, &pool_path) {
+                continue;
+            }
+            // The stored tokens close the cycle, the last one repeats the first
+            self.store_discovered_pool_path(graph, &token_path[..pool_path.len()], pool_path);
+            merged_paths += 1;
+        }
+
+        tracing::debug!(
+            merged_paths = merged_paths,
+            total_pool_paths = self.pool_paths.len(),
+            "Merged discovered paths"
+        );
+        merged_paths
+    }
+
+    /// Drop the paths stored after the first `token_path_count` token paths and `pool_path_count` pool paths.
+    fn truncate_paths(&mut self, token_path_count: usize, pool_path_count: usize) {
+        self.token_paths.truncate(token_path_count);
+        self.token_path_handles.truncate(token_path_count);
+        self.pool_paths.truncate(pool_path_count);
+        self.pool_path_handles.truncate(pool_path_count);
+        self.pool_path_token_handles.truncate(pool_path_count);
+
+        for path_indices in self.token_to_path_indices.values_mut() {
+            path_indices.retain(|&path_index| path_index < token_path_count);
+        }
+        self.token_to_path_indices.retain(|_, path_indices| !path_indices.is_empty());
+        for path_indices in self.pool_to_path_indices.values_mut() {
+            path_indices.retain(|&path_index| path_index < pool_path_count);
+        }
+        self.pool_to_path_indices.retain(|_, path_indices| !path_indices.is_empty());
     }
 
     /// Resolve source token addresses to their corresponding graph indices.
@@ -204,15 +637,32 @@ impl PathRepository {
         graph: &TradingGraph,
         source_indices: &[usize],
         new_token_offset: usize,
+        monitor: &mut DiscoveryMonitor<'_>,
     ) {
+        let start_indices = self.start_token_indices(graph, source_indices);
+        let maximum_path_lengths: Vec<usize> = start_indices
+            .iter()
+            .map(|&source_index| self.maximum_path_length_at(graph, source_index))
+            .collect();
         for path_length in 2..=self.maximum_path_length {
-            for &source_index in source_indices.iter() {
+            let sources: Vec<usize> = start_indices
+                .iter()
+                .zip(&maximum_path_lengths)
+                .filter(|(_, &maximum_path_length)| path_length <= maximum_path_length)
+                .map(|(&source_index, _)| source_index)
+                .collect();
+            for (searched, &source_index) in sources.iter().enumerate() {
+                if monitor.should_stop() {
+                    return;
+                }
+                monitor.report(DiscoveryStage::TokenPaths { path_length }, sources.len() - searched, false);
                 self.discover_token_paths_recursive(
                     graph,
                     source_indices,
                     new_token_offset,
                     path_length,
                     vec![source_index],
+                    monitor,
                 );
             }
         }
@@ -226,7 +676,11 @@ impl PathRepository {
         new_token_offset: usize,
         target_length: usize,
         current_path: Vec<usize>,
+        monitor: &mut DiscoveryMonitor<'_>,
     ) {
+        if monitor.should_stop() {
+            return;
+        }
         let current_token_index = match current_path.last() {
             Some(&index) => index,
             None => {
@@ -251,6 +705,7 @@ impl PathRepository {
             // Check if path forms a cycle back to any source token
             if neighbor_indices.iter().any(|&idx| source_indices.contains(&idx)) {
                 self.store_discovered_token_path(graph, current_path);
+                monitor.record_token_path();
             }
         } else {
             // Continue exploring neighbors
@@ -270,6 +725,7 @@ impl PathRepository {
                         new_token_offset,
                         target_length,
                         extended_path,
+                        monitor,
                     );
                 }
             }
@@ -284,10 +740,9 @@ impl PathRepository {
         source_indices: &[usize],
         current_path: &[usize],
     ) -> bool {
-        // Only explore new tokens and avoid revisiting source tokens in the middle of paths
-        neighbor_index >= new_token_offset 
-            && !source_indices.contains(&neighbor_index)
-            && !current_path.contains(&neighbor_index) // Avoid cycles within the path
+        // Only explore new tokens, revisits and source tokens as allowed by the policy
+        neighbor_index >= new_token_offset
+            && self.discovery_policy.allows_token(neighbor_index, source_indices, current_path)
     }
 
     /// Store a discovered token path and update indices.
@@ -304,6 +759,12 @@ impl PathRepository {
             }
         }
 
+        self.token_path_handles.push(
+            token_path
+                .iter()
+                .filter_map(|&token_index| graph.token_handle(token_index).ok())
+                .collect(),
+        );
         self.token_paths.push(token_path);
 
         tracing::trace!(
@@ -319,6 +780,7 @@ impl PathRepository {
         graph: &TradingGraph,
         new_pool_offset: usize,
         new_pool_count: usize,
+        monitor: &mut DiscoveryMonitor<'_>,
     ) {
         // Find tokens affected by new pools
         let affected_token_indices = self.find_tokens_affected_by_new_pools(
@@ -340,9 +802,27 @@ impl PathRepository {
         );
 
         // Generate pool paths from relevant token paths
-        for &token_path_index in relevant_token_path_indices.iter() {
-            let token_path = self.token_paths[token_path_index].clone();
-            self.discover_pool_paths_recursive(graph, new_pool_offset, &token_path, Vec::new());
+        for (expanded, &token_path_index) in relevant_token_path_indices.iter().enumerate() {
+            if monitor.should_stop() {
+                return;
+            }
+            monitor.report(DiscoveryStage::PoolPaths, relevant_token_path_indices.len() - expanded, false);
+            let token_path: Result<Vec<usize>> = self.token_path_handles[token_path_index]
+                .iter()
+                .map(|&handle| graph.resolve_token(handle))
+                .collect();
+            match token_path {
+                Ok(token_path) => {
+                    self.discover_pool_paths_recursive(graph, new_pool_offset, &token_path, Vec::new(), monitor);
+                }
+                Err(e) => {
+                    tracing::debug!(
+                        token_path_index = token_path_index,
+                        error = %e,
+                        "Skipped token path through removed token"
+                    );
+                }
+            }
         }
     }
 
@@ -401,19 +881,24 @@ impl PathRepository {
         new_pool_offset: usize,
         token_path: &[usize],
         current_pool_path: Vec<usize>,
+        monitor: &mut DiscoveryMonitor<'_>,
     ) {
+        if monitor.should_stop() {
+            return;
+        }
         let current_position = current_pool_path.len();
 
         if current_position == token_path.len() {
             // Complete pool path found
-            self.store_discovered_pool_path(graph, current_pool_path);
+            self.store_discovered_pool_path(graph, token_path, current_pool_path);
+            monitor.record_pool_path();
         } else {
             // Find pools connecting current and next tokens
             let current_token = token_path[current_position];
             let next_token = token_path[(current_position + 1) % token_path.len()];
-            let token_pair = [current_token, next_token];
+            let connecting_pools = graph.pools_from_to(current_token, next_token);
 
-            if let Ok(connecting_pools) = graph.pools_between_tokens(token_pair) {
+            if !connecting_pools.is_empty() {
                 let should_include_new_pools = self.should_include_new_pools(
                     connecting_pools,
                     new_pool_offset,
@@ -435,6 +920,7 @@ impl PathRepository {
                             new_pool_offset,
                             token_path,
                             extended_pool_path,
+                            monitor,
                         );
                     }
                 }
@@ -467,6 +953,10 @@ impl PathRepository {
             return false;
         }
 
+        if self.discovery_policy.allows_pool_reuse() {
+            return true;
+        }
+
         // Check if pool is already used in the current path (avoid duplicates)
         let pool_already_used = current_pool_path.iter().any(|&existing_pool_index| {
             match (graph.get_pool(existing_pool_index), graph.get_pool(pool_index)) {
@@ -480,8 +970,8 @@ impl PathRepository {
         !pool_already_used
     }
 
-    /// Store a discovered pool path and update indices.
-    fn store_discovered_pool_path(&mut self, graph: &TradingGraph, pool_path: Vec<usize>) {
+    /// Store a discovered pool path with the token path it follows and update indices.
+    fn store_discovered_pool_path(&mut self, graph: &TradingGraph, token_path: &[usize], pool_path: Vec<usize>) {
         let path_index = self.pool_paths.len();
 
         // Update pool-to-path index mapping
@@ -494,6 +984,19 @@ impl PathRepository {
             }
         }
 
+        self.pool_path_handles.push(
+            pool_path
+                .iter()
+                .filter_map(|&pool_index| graph.pool_handle(pool_index).ok())
+                .collect(),
+        );
+        self.pool_path_token_handles.push(
+            token_path
+                .iter()
+                .chain(token_path.first())
+                .filter_map(|&token_index| graph.token_handle(token_index).ok())
+                .collect(),
+        );
         self.pool_paths.push(pool_path);
 
         tracing::trace!(
@@ -503,11 +1006,157 @@ impl PathRepository {
         );
     }
 
+    /// Prune stale pools from the graph and evict the paths through them.
+    ///
+    /// Removing pools from the graph changes the IDs of remaining pools, so the
+    /// remaining pool paths are remapped to the new IDs. Token paths are kept, as
+    /// pruning never removes tokens, and new pool paths through them are found
+    /// when pools are added again.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The trading graph the repository's paths were discovered from
+    /// * `older_than_block` - Pools last updated before this block are removed
+    ///
+    /// # Returns
+    ///
+    /// The addresses of the removed pools
+    pub fn prune_stale_pools(&mut self, graph: &mut TradingGraph, older_than_block: u64) -> Vec<Bytes> {
+        self.evict_pools(graph, |graph| graph.prune_stale(older_than_block))
+    }
+
+    /// Remove pools from the graph and evict the paths through them.
+    ///
+    /// Like [`prune_stale_pools`](Self::prune_stale_pools), the remaining pool
+    /// paths are remapped to the pool IDs after the removal.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The trading graph the repository's paths were discovered from
+    /// * `pool_addresses` - The pools to remove
+    ///
+    /// # Returns
+    ///
+    /// The addresses of the pools that were part of the graph and were removed
+    pub fn remove_pools(&mut self, graph: &mut TradingGraph, pool_addresses: &[Bytes]) -> Vec<Bytes> {
+        self.evict_pools(graph, |graph| {
+            pool_addresses
+                .iter()
+                .filter(|pool_address| graph.remove_protocol_component(pool_address).is_ok())
+                .cloned()
+                .collect()
+        })
+    }
+
+    fn evict_pools<F>(&mut self, graph: &mut TradingGraph, remove: F) -> Vec<Bytes>
+    where
+        F: FnOnce(&mut TradingGraph) -> Vec<Bytes>,
+    {
+        let pruned = remove(graph);
+        if pruned.is_empty() {
+            return pruned;
+        }
+        self.missing_data.forget(&pruned);
+
+        let path_count_before = self.pool_paths.len();
+        self.remap_to_graph(graph);
+
+        tracing::info!(
+            pruned_pools = pruned.len(),
+            evicted_paths = path_count_before - self.pool_paths.len(),
+            remaining_paths = self.pool_paths.len(),
+            "Evicted paths through removed pools"
+        );
+
+        pruned
+    }
+
+    /// Remap the stored paths to the current IDs of a graph.
+    ///
+    /// Paths through tokens or pools removed from the graph are evicted, and
+    /// the IDs of the remaining paths are updated. Removals through
+    /// [`remove_pools`](Self::remove_pools) and
+    /// [`prune_stale_pools`](Self::prune_stale_pools) remap automatically; call
+    /// this after removing tokens or pools from the graph directly.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The trading graph the repository's paths were discovered from
+    ///
+    /// # Returns
+    ///
+    /// The number of evicted token and pool paths
+    pub fn remap_to_graph(&mut self, graph: &TradingGraph) -> usize {
+        let path_count_before = self.token_paths.len() + self.pool_paths.len();
+
+        let (token_paths, token_path_handles): (Vec<_>, Vec<_>) = std::mem::take(&mut self.token_path_handles)
+            .into_iter()
+            .filter_map(|handles| {
+                let token_path = handles
+                    .iter()
+                    .map(|&handle| graph.resolve_token(handle))
+                    .collect::<Result<Vec<_>>>()
+                    .ok()?;
+                Some((token_path, handles))
+            })
+            .unzip();
+        self.token_paths = token_paths;
+        self.token_path_handles = token_path_handles;
+
+        let mut pool_paths = Vec::with_capacity(self.pool_path_handles.len());
+        let mut pool_path_handles = Vec::with_capacity(self.pool_path_handles.len());
+        let mut pool_path_token_handles = Vec::with_capacity(self.pool_path_handles.len());
+        let stored_paths = std::mem::take(&mut self.pool_path_handles)
+            .into_iter()
+            .zip(std::mem::take(&mut self.pool_path_token_handles));
+        for (handles, token_handles) in stored_paths {
+            let tokens_resolve = token_handles.iter().all(|&handle| graph.resolve_token(handle).is_ok());
+            let pool_path = handles
+                .iter()
+                .map(|&handle| graph.resolve_pool(handle))
+                .collect::<Result<Vec<_>>>();
+            if let (true, Ok(pool_path)) = (tokens_resolve, pool_path) {
+                pool_paths.push(pool_path);
+                pool_path_handles.push(handles);
+                pool_path_token_handles.push(token_handles);
+            }
+        }
+        self.pool_paths = pool_paths;
+        self.pool_path_handles = pool_path_handles;
+        self.pool_path_token_handles = pool_path_token_handles;
+
+        self.token_to_path_indices.clear();
+        for (path_index, token_path) in self.token_paths.iter().enumerate() {
+            for &token_index in token_path.iter() {
+                if let Ok(token) = graph.get_token(token_index) {
+                    self.token_to_path_indices
+                        .entry(token.address().clone())
+                        .or_default()
+                        .push(path_index);
+                }
+            }
+        }
+
+        self.pool_to_path_indices.clear();
+        for (path_index, pool_path) in self.pool_paths.iter().enumerate() {
+            for &pool_index in pool_path.iter() {
+                if let Ok(pool) = graph.get_pool(pool_index) {
+                    self.pool_to_path_indices
+                        .entry(pool.address().clone())
+                        .or_default()
+                        .push(path_index);
+                }
+            }
+        }
+
+        path_count_before - self.token_paths.len() - self.pool_paths.len()
+    }
+
     /// Convert path indices to actual Path objects.
     ///
     /// This method builds `Path` objects from stored path indices, using the provided
     /// protocol components and simulations. Paths that cannot be built due to missing
-    /// protocol data are skipped with appropriate logging.
+    /// protocol data are skipped, and their pools recorded in [`missing_data`](Self::missing_data).
     ///
     /// # Arguments
     ///
@@ -523,8 +1172,8 @@ impl PathRepository {
         &self,
         path_indices: Vec<usize>,
         graph: &TradingGraph,
-        protocol_simulations: &HashMap<Bytes, Box<dyn ProtocolSim>>,
-        protocol_components: &HashMap<Bytes, ProtocolComponent>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
     ) -> Result<Vec<Path>> {
         let mut successfully_built_paths = Vec::new();
         let mut skipped_count = 0;
@@ -535,14 +1184,15 @@ impl PathRepository {
         );
 
         for &path_index in path_indices.iter() {
-            let pool_indices = self.get_pool_path_by_index(path_index)?;
+            let pool_handles = self.get_pool_path_handles_by_index(path_index)?;
             
-            match self.build_single_path(pool_indices, graph, protocol_components, protocol_simulations) {
+            match self.build_single_path(pool_handles, graph, protocol_components, protocol_simulations) {
                 Ok(path) => {
                     successfully_built_paths.push(path);
                 }
                 Err(e) => {
                     skipped_count += 1;
+                    self.missing_data.record_error(&e);
                     tracing::debug!(
                         path_index = path_index,
                         error = %e,
@@ -557,22 +1207,99 @@ impl PathRepository {
         Ok(successfully_built_paths)
     }
 
-    /// Build a single path from pool indices.
+    /// Convert path indices to actual Path objects within a search budget.
+    ///
+    /// Behaves like [`build_paths_from_indices`](Self::build_paths_from_indices), but stops
+    /// once the deadline's path limit is reached or its wall-clock budget has expired.
+    /// Paths built before the budget ran out are still returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `path_indices` - Vector of path indices to convert
+    /// * `graph` - The trading graph containing pool and token information
+    /// * `protocol_simulations` - Map of pool addresses to protocol simulations
+    /// * `protocol_components` - Map of pool addresses to protocol components
+    /// * `deadline` - The running search budget
+    ///
+    /// # Returns
+    ///
+    /// A vector of successfully built `Path` objects
+    pub fn build_paths_within_budget(
+        &self,
+        path_indices: Vec<usize>,
+        graph: &TradingGraph,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        deadline: &SearchDeadline,
+    ) -> Result<Vec<Path>> {
+        let max_paths = deadline.max_paths();
+        let mut successfully_built_paths = Vec::new();
+        let mut skipped_count = 0;
+        let mut attempted_count = 0;
+
+        for &path_index in path_indices.iter() {
+            if successfully_built_paths.len() >= max_paths || deadline.is_expired() {
+                tracing::info!(
+                    built_paths = successfully_built_paths.len(),
+                    remaining_indices = path_indices.len() - attempted_count,
+                    elapsed_ms = deadline.elapsed().as_millis(),
+                    "Search budget exhausted during path building"
+                );
+                break;
+            }
+            attempted_count += 1;
+
+            let pool_handles = self.get_pool_path_handles_by_index(path_index)?;
+
+            match self.build_single_path(pool_handles, graph, protocol_components, protocol_simulations) {
+                Ok(path) => {
+                    successfully_built_paths.push(path);
+                }
+                Err(e) => {
+                    skipped_count += 1;
+                    self.missing_data.record_error(&e);
+                    tracing::debug!(
+                        path_index = path_index,
+                        error = %e,
+                        "Skipped path due to build failure"
+                    );
+                }
+            }
+        }
+
+        self.log_path_building_results(attempted_count, successfully_built_paths.len(), skipped_count);
+
+        Ok(successfully_built_paths)
+    }
+
+    /// Build a single path from pool handles, resolved to the current pool IDs.
     fn build_single_path(
         &self,
-        pool_indices: &[usize],
+        pool_handles: &[PoolHandle],
         graph: &TradingGraph,
-        protocol_components: &HashMap<Bytes, ProtocolComponent>,
-        protocol_simulations: &HashMap<Bytes, Box<dyn ProtocolSim>>,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
     ) -> Result<Path> {
         use crate::path::creation::PathBuilder;
 
-        PathBuilder::new()
-            .with_edges(pool_indices)
+        let pool_indices = pool_handles
+            .iter()
+            .map(|&handle| graph.resolve_pool(handle))
+            .collect::<Result<Vec<_>>>()?;
+
+        let path = PathBuilder::new()
+            .with_edges(&pool_indices)
             .with_graph(graph)
             .with_protocol_components(protocol_components)
             .with_protocol_simulations(protocol_simulations)
-            .build()
+            .build()?;
+
+        if !self.discovery_policy.allows_protocols(&path) {
+            return Err(PathError::InvalidPath {
+                reason: "Path does not satisfy the protocol constraints of the discovery policy".to_string(),
+            }.into());
+        }
+        Ok(path)
     }
 
     /// Log the results of path building operations.
@@ -617,8 +1344,8 @@ impl PathRepository {
         &self,
         pool_addresses: &[Bytes],
         graph: &TradingGraph,
-        protocol_components: &HashMap<Bytes, ProtocolComponent>,
-        protocol_simulations: &HashMap<Bytes, Box<dyn ProtocolSim>>,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
     ) -> Result<Vec<Path>> {
         let path_indices = self.get_path_indices_for_pools(pool_addresses)?;
         self.build_paths_from_indices(path_indices, graph, protocol_simulations, protocol_components)
@@ -637,6 +1364,150 @@ impl PathRepository {
     }
 }
 
+/// A discovered path with the tokens it trades through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredPath {
+    /// Directed pool IDs, in swap order
+    pub pools: Vec<PoolId>,
+    /// Token IDs from the source token back to it, one more than pools
+    pub tokens: Vec<TokenId>,
+}
+
+impl DiscoveredPath {
+    /// Number of swaps of the path.
+    pub fn len(&self) -> usize {
+        self.pools.len()
+    }
+
+    /// Whether the path has no swaps.
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty()
+    }
+
+    /// The source token the path starts and ends at.
+    pub fn source_token(&self) -> Option<TokenId> {
+        self.tokens.first().copied()
+    }
+
+    /// The addresses of the pools of the path, in swap order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a pool is not part of the graph
+    pub fn pool_addresses(&self, graph: &TradingGraph) -> Result<Vec<Bytes>> {
+        self.pools
+            .iter()
+            .map(|&pool_id| graph.get_pool(pool_id).map(|pool| pool.address().clone()))
+            .collect()
+    }
+
+    /// The addresses of the tokens of the path, from the source token back to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a token is not part of the graph
+    pub fn token_addresses(&self, graph: &TradingGraph) -> Result<Vec<Bytes>> {
+        self.tokens
+            .iter()
+            .map(|&token_id| graph.get_token(token_id).map(|token| token.address().clone()))
+            .collect()
+    }
+}
+
+/// Filters of discovered paths, see [`PathRepository::query`].
+///
+/// Without filters, all paths match.
+#[derive(Debug, Clone, Default)]
+pub struct PathQuery<'a> {
+    source_token: Option<Bytes>,
+    min_length: usize,
+    max_length: Option<usize>,
+    protocols: Option<HashSet<String>>,
+    protocol_components: Option<&'a HashMap<Bytes, Arc<ProtocolComponent>>>,
+    protocol_simulations: Option<&'a HashMap<Bytes, Arc<dyn ProtocolSim>>>,
+}
+
+impl<'a> PathQuery<'a> {
+    /// Create a query matching all paths.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match paths starting at a source token.
+    pub fn with_source_token(mut self, source_token: Bytes) -> Self {
+        self.source_token = Some(source_token);
+        self
+    }
+
+    /// Only match paths of at least `min_length` swaps.
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Only match paths of at most `max_length` swaps.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Only match paths whose pools all belong to the given protocol systems.
+    ///
+    /// Protocol systems are read from the components set with
+    /// [`with_protocol_data`](Self::with_protocol_data), without them no path matches.
+    pub fn with_protocols<I, S>(mut self, protocols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.protocols = Some(protocols.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Only match paths whose pools all have a component and a simulation loaded.
+    pub fn with_protocol_data(
+        mut self,
+        protocol_components: &'a HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &'a HashMap<Bytes, Arc<dyn ProtocolSim>>,
+    ) -> Self {
+        self.protocol_components = Some(protocol_components);
+        self.protocol_simulations = Some(protocol_simulations);
+        self
+    }
+
+    /// Whether a path passes all filters.
+    pub fn matches(&self, path: &DiscoveredPath, graph: &TradingGraph) -> bool {
+        if path.len() < self.min_length || self.max_length.is_some_and(|max_length| path.len() > max_length) {
+            return false;
+        }
+        if let Some(source_token) = &self.source_token {
+            let source_id = graph.find_token_id(source_token).ok();
+            if source_id.is_none() || path.source_token() != source_id {
+                return false;
+            }
+        }
+
+        if self.protocols.is_none() && self.protocol_components.is_none() {
+            return true;
+        }
+        let Ok(pools) = path.pool_addresses(graph) else {
+            return false;
+        };
+        pools.iter().all(|pool| {
+            let component = self.protocol_components.and_then(|components| components.get(pool));
+            let protocol_matches = match &self.protocols {
+                Some(protocols) => component.is_some_and(|component| protocols.contains(&component.protocol_system)),
+                None => true,
+            };
+            let loaded = match (self.protocol_components, self.protocol_simulations) {
+                (Some(_), Some(simulations)) => component.is_some() && simulations.contains_key(pool),
+                _ => true,
+            };
+            protocol_matches && loaded
+        })
+    }
+}
+
 /// Statistics about a path repository.
 #[derive(Debug, Clone)]
 pub struct RepositoryStatistics {
@@ -708,4 +1579,367 @@ mod tests {
         paths_repo.discover_paths(&g, 0_usize, 4_usize, 0_usize, 4_usize);
         assert!(paths_repo.get_path_indices_for_pool(&edge4).is_ok());
     }
+
+    #[test]
+    fn test_source_path_lengths_limit_discovery() {
+        let tokens: Vec<Bytes> = ["0x0000", "0x0001", "0x0002"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        let mut g = TradingGraph::new();
+        for token in &tokens {
+            g.add_token(token.clone()).unwrap();
+        }
+        g.add_pool(Bytes::from_str("0x1000").unwrap(), [0, 1]).unwrap();
+        g.add_pool(Bytes::from_str("0x1001").unwrap(), [1, 2]).unwrap();
+        g.add_pool(Bytes::from_str("0x1002").unwrap(), [0, 2]).unwrap();
+        g.add_pool(Bytes::from_str("0x1003").unwrap(), [0, 1]).unwrap();
+
+        let discover = |paths_repo: PathRepository| {
+            let mut paths_repo = paths_repo;
+            paths_repo.discover_paths(&g, 0, 3, 0, 8);
+            paths_repo.token_paths.iter().map(Vec::len).max()
+        };
+        assert_eq!(discover(PathRepository::new(vec![tokens[0].clone()], 3)), Some(3));
+
+        // The repository maximum caps per-source limits
+        let limited = PathRepository::new(vec![tokens[0].clone()], 3).with_source_path_length(tokens[0].clone(), 2);
+        assert_eq!(limited.maximum_path_length_for(&tokens[0]), 2);
+        assert_eq!(discover(limited), Some(2));
+        let raised = PathRepository::new(vec![tokens[0].clone()], 3).with_source_path_length(tokens[0].clone(), 4);
+        assert_eq!(raised.maximum_path_length_for(&tokens[0]), 3);
+    }
+
+    #[test]
+    fn test_cancelled_discovery_leaves_repository_unchanged() {
+        use crate::path::{CancellationToken, DiscoveryOutcome};
+
+        let tokens: Vec<Bytes> = ["0x0000", "0x0001", "0x0002"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        let mut g = TradingGraph::new();
+        for token in &tokens {
+            g.add_token(token.clone()).unwrap();
+        }
+        g.add_pool(Bytes::from_str("0x1000").unwrap(), [0, 1]).unwrap();
+        g.add_pool(Bytes::from_str("0x1001").unwrap(), [1, 2]).unwrap();
+        g.add_pool(Bytes::from_str("0x1002").unwrap(), [0, 2]).unwrap();
+
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let mut paths_repo = PathRepository::new(vec![tokens[0].clone()], 3);
+        let mut monitor = DiscoveryMonitor::new().with_cancellation(cancellation);
+        let outcome = paths_repo.discover_paths_monitored(&g, 0, 3, 0, 6, &mut monitor);
+        assert_eq!(outcome, DiscoveryOutcome::Cancelled);
+        assert!(paths_repo.token_paths.is_empty() && paths_repo.pool_paths.is_empty());
+
+        // A completed discovery reports its final progress
+        let mut reports = Vec::new();
+        let mut monitor = DiscoveryMonitor::new().with_progress(|progress| reports.push(progress.clone()));
+        let mut discovered = paths_repo.clone();
+        let outcome = discovered.discover_paths_monitored(&g, 0, 3, 0, 6, &mut monitor);
+        drop(monitor);
+        assert!(outcome.is_completed());
+        let last = reports.last().unwrap();
+        assert_eq!(last.pool_paths_found, discovered.pool_paths.len());
+        assert_eq!(last.token_paths_found, discovered.token_paths.len());
+
+        // Merging the discovered copy adds each path once
+        assert_eq!(paths_repo.merge_discovered(&g, &discovered, 0, 0), discovered.pool_paths.len());
+        assert_eq!(paths_repo.merge_discovered(&g, &discovered, 0, 0), 0);
+        assert_eq!(paths_repo.token_paths.len(), discovered.token_paths.len());
+    }
+
+    #[test]
+    fn test_prune_stale_pools_remaps_remaining_paths() {
+        let mut g = TradingGraph::new();
+        for token in ["0x0000", "0x0001", "0x0002"] {
+            g.add_token(Bytes::from_str(token).unwrap()).unwrap();
+        }
+
+        let stale = Bytes::from_str("0x1000").unwrap();
+        let pools = [
+            (stale.clone(), [0, 1]),
+            (Bytes::from_str("0x1001").unwrap(), [1, 2]),
+            (Bytes::from_str("0x1002").unwrap(), [0, 2]),
+            (Bytes::from_str("0x1003").unwrap(), [0, 1]),
+        ];
+        for (address, tokens) in pools.iter() {
+            g.add_pool(address.clone(), *tokens).unwrap();
+            g.record_update(address, 10);
+        }
+        g.record_update(&stale, 5);
+
+        let mut paths_repo = PathRepository::new(vec![Bytes::from_str("0x0000").unwrap()], 3);
+        paths_repo.discover_paths(&g, 0, 3, 0, 8);
+        let paths_before = paths_repo.pool_paths.len();
+
+        let pruned = paths_repo.prune_stale_pools(&mut g, 10);
+        assert_eq!(pruned, vec![stale.clone()]);
+        assert!(paths_repo.get_path_indices_for_pool(&stale).is_err());
+        assert!(paths_repo.pool_paths.len() < paths_before);
+        assert!(!paths_repo.pool_paths.is_empty());
+
+        // Remaining paths point at valid, connected pools after the ID shuffle
+        for pool_path in paths_repo.pool_paths.iter() {
+            let pools: Vec<_> = pool_path.iter().map(|&index| g.get_pool(index).unwrap()).collect();
+            for (pool, next) in pools.iter().zip(pools.iter().cycle().skip(1)) {
+                assert_ne!(pool.address(), &stale);
+                assert_eq!(pool.token_out_id(), next.token_in_id());
+            }
+        }
+    }
+
+    #[test]
+    fn test_remove_pools_ignores_unknown_pools() {
+        let mut g = TradingGraph::new();
+        for token in ["0x0000", "0x0001"] {
+            g.add_token(Bytes::from_str(token).unwrap()).unwrap();
+        }
+        let removed_pool = Bytes::from_str("0x1000").unwrap();
+        g.add_pool(removed_pool.clone(), [0, 1]).unwrap();
+        g.add_pool(Bytes::from_str("0x1001").unwrap(), [0, 1]).unwrap();
+
+        let mut paths_repo = PathRepository::new(vec![Bytes::from_str("0x0000").unwrap()], 3);
+        paths_repo.discover_paths(&g, 0, 3, 0, 8);
+
+        let unknown_pool = Bytes::from_str("0x2000").unwrap();
+        let removed = paths_repo.remove_pools(&mut g, &[removed_pool.clone(), unknown_pool]);
+        assert_eq!(removed, vec![removed_pool.clone()]);
+        assert!(paths_repo.get_path_indices_for_pool(&removed_pool).is_err());
+        assert_eq!(g.pool_count(), 1);
+    }
+
+    #[test]
+    fn test_paths_survive_token_removal_outside_the_repository() {
+        let mut g = TradingGraph::new();
+        for token in ["0x0000", "0x0001", "0x0002"] {
+            g.add_token(Bytes::from_str(token).unwrap()).unwrap();
+        }
+        let removed_pools = [Bytes::from_str("0x1000").unwrap(), Bytes::from_str("0x1001").unwrap()];
+        g.add_pool(removed_pools[0].clone(), [0, 1]).unwrap();
+        g.add_pool(removed_pools[1].clone(), [1, 2]).unwrap();
+        g.add_pool(Bytes::from_str("0x1002").unwrap(), [0, 2]).unwrap();
+        g.add_pool(Bytes::from_str("0x1003").unwrap(), [0, 2]).unwrap();
+
+        let mut paths_repo = PathRepository::new(vec![Bytes::from_str("0x0000").unwrap()], 3);
+        paths_repo.discover_paths(&g, 0, 3, 0, 8);
+        let path_count = paths_repo.pool_paths.len();
+
+        // Token 2 moves into the ID of token 1, and pools are reshuffled
+        g.remove_token(1).unwrap();
+        for path_index in 0..path_count {
+            match paths_repo.resolve_pool_path(path_index, &g) {
+                Ok(pool_path) => {
+                    for pool_index in pool_path {
+                        assert!(!removed_pools.contains(g.get_pool(pool_index).unwrap().address()));
+                    }
+                }
+                Err(e) => assert!(e.to_string().contains("Stale handle")),
+            }
+        }
+
+        assert!(paths_repo.remap_to_graph(&g) > 0);
+        assert!(!paths_repo.pool_paths.is_empty());
+        assert!(paths_repo.get_path_indices_for_pool(&removed_pools[0]).is_err());
+        for pool_path in paths_repo.pool_paths.iter() {
+            let pools: Vec<_> = pool_path.iter().map(|&index| g.get_pool(index).unwrap()).collect();
+            for (pool, next) in pools.iter().zip(pools.iter().cycle().skip(1)) {
+                assert_eq!(pool.token_out_id(), next.token_in_id());
+            }
+        }
+    }
+
+    #[test]
+    fn test_discovered_paths_carry_matching_tokens() {
+        let mut g = TradingGraph::new();
+        for token in ["0x0000", "0x0001", "0x0002"] {
+            g.add_token(Bytes::from_str(token).unwrap()).unwrap();
+        }
+        g.add_pool(Bytes::from_str("0x1000").unwrap(), [0, 1]).unwrap();
+        g.add_pool(Bytes::from_str("0x1001").unwrap(), [1, 2]).unwrap();
+        g.add_pool(Bytes::from_str("0x1002").unwrap(), [0, 2]).unwrap();
+
+        let mut paths_repo = PathRepository::new(vec![Bytes::from_str("0x0000").unwrap()], 3);
+        paths_repo.discover_paths(&g, 0, 3, 0, 6);
+        assert!(!paths_repo.pool_paths.is_empty());
+
+        for path_index in 0..paths_repo.pool_paths.len() {
+            let path = paths_repo.get_discovered_path(path_index, &g).unwrap();
+            assert_eq!(path.tokens.len(), path.len() + 1);
+            assert_eq!(path.source_token(), Some(0));
+            assert_eq!(path.tokens.last(), Some(&0));
+            for (hop, &pool_id) in path.pools.iter().enumerate() {
+                assert_eq!(g.get_pool(pool_id).unwrap().tokens(), [path.tokens[hop], path.tokens[hop + 1]]);
+            }
+
+            let tokens = path.token_addresses(&g).unwrap();
+            let pools = path.pool_addresses(&g).unwrap();
+            assert!(crate::path::PathValidator::validate_path_consistency(&pools, &tokens).is_ok());
+        }
+        assert!(paths_repo.get_discovered_path(paths_repo.pool_paths.len(), &g).is_err());
+    }
+
+    #[test]
+    fn test_query_filters_paths() {
+        use crate::testing::{component, MockProtocolSim};
+
+        let tokens: Vec<Bytes> = ["0x0000", "0x0001", "0x0002"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        let pools: Vec<Bytes> = ["0x1000", "0x1001", "0x1002"].iter().map(|p| Bytes::from_str(p).unwrap()).collect();
+        let mut g = TradingGraph::new();
+        for token in &tokens {
+            g.add_token(token.clone()).unwrap();
+        }
+        g.add_pool(pools[0].clone(), [0, 1]).unwrap();
+        g.add_pool(pools[1].clone(), [1, 2]).unwrap();
+        g.add_pool(pools[2].clone(), [0, 2]).unwrap();
+
+        let mut paths_repo = PathRepository::new(vec![tokens[0].clone()], 3);
+        paths_repo.discover_paths(&g, 0, 3, 0, 6);
+        let path_count = paths_repo.pool_paths.len();
+        assert!(path_count > 0);
+
+        assert_eq!(paths_repo.paths_for_source(&g, &tokens[0], 3).count(), path_count);
+        assert_eq!(paths_repo.paths_for_source(&g, &tokens[0], 2).count(), 0);
+        assert_eq!(paths_repo.paths_for_source(&g, &tokens[1], 3).count(), 0);
+
+        // Paths match only once all of their pools are loaded
+        let components: HashMap<Bytes, Arc<ProtocolComponent>> = pools
+            .iter()
+            .map(|pool| (pool.clone(), Arc::new(component(pool, &tokens[..2]))))
+            .collect();
+        let mut simulations: HashMap<Bytes, Arc<dyn ProtocolSim>> = pools[..2]
+            .iter()
+            .map(|pool| (pool.clone(), Arc::new(MockProtocolSim::new()) as Arc<dyn ProtocolSim>))
+            .collect();
+        let loaded = PathQuery::new().with_min_length(3).with_protocol_data(&components, &simulations);
+        assert_eq!(paths_repo.query(&g, loaded).count(), 0);
+        simulations.insert(pools[2].clone(), Arc::new(MockProtocolSim::new()));
+        let loaded = PathQuery::new().with_min_length(3).with_protocol_data(&components, &simulations);
+        assert_eq!(paths_repo.query(&g, loaded).count(), path_count);
+
+        let by_protocol = |protocol: &str| {
+            paths_repo
+                .query(&g, PathQuery::new().with_protocols([protocol]).with_protocol_data(&components, &simulations))
+                .count()
+        };
+        assert_eq!(by_protocol("test"), path_count);
+        assert_eq!(by_protocol("other"), 0);
+    }
+
+    #[test]
+    fn test_discovery_policy_allows_revisiting_the_source_token() {
+        use crate::testing::{component, MockProtocolSim};
+
+        // A-B and A-C are each connected by two pools
+        let tokens: Vec<Bytes> = ["0x0000", "0x0001", "0x0002"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        let mut g = TradingGraph::new();
+        for token in &tokens {
+            g.add_token(token.clone()).unwrap();
+        }
+        let pools = [("0x1000", 1), ("0x1001", 1), ("0x1002", 2), ("0x1003", 2)];
+        let mut components = HashMap::new();
+        let mut simulations: HashMap<Bytes, Arc<dyn ProtocolSim>> = HashMap::new();
+        for (index, (pool, token)) in pools.iter().enumerate() {
+            let pool = Bytes::from_str(pool).unwrap();
+            g.add_pool(pool.clone(), [0, *token]).unwrap();
+            let mut pool_comp = component(&pool, &[tokens[0].clone(), tokens[*token].clone()]);
+            pool_comp.protocol_system = format!("protocol_{}", index);
+            components.insert(pool.clone(), Arc::new(pool_comp));
+            simulations.insert(pool, Arc::new(MockProtocolSim::new()));
+        }
+
+        let mut simple = PathRepository::new(vec![tokens[0].clone()], 4);
+        simple.discover_paths(&g, 0, 3, 0, 8);
+        assert!(simple.pool_paths.iter().all(|path| path.len() == 2));
+
+        let policy = DiscoveryPolicy::new()
+            .with_max_token_revisits(1)
+            .with_source_as_intermediate(true);
+        let mut revisiting = PathRepository::new(vec![tokens[0].clone()], 4).with_discovery_policy(policy.clone());
+        revisiting.discover_paths(&g, 0, 3, 0, 8);
+        let four_hop: Vec<usize> = (0..revisiting.pool_paths.len())
+            .filter(|&path_index| revisiting.pool_paths[path_index].len() == 4)
+            .collect();
+        assert!(!four_hop.is_empty());
+        let path = revisiting.get_discovered_path(four_hop[0], &g).unwrap();
+        assert_eq!(path.tokens[0], path.tokens[2]);
+
+        // Distinct protocols only affect building
+        let built = revisiting
+            .build_paths_from_indices(four_hop.clone(), &g, &simulations, &components)
+            .unwrap();
+        assert_eq!(built.len(), four_hop.len());
+
+        // Every four-hop path trades through both A-B pools
+        for (pool, _) in pools.iter().take(2) {
+            let pool = Bytes::from_str(pool).unwrap();
+            let mut pool_comp = (*components[&pool]).clone();
+            pool_comp.protocol_system = "shared".to_string();
+            components.insert(pool, Arc::new(pool_comp));
+        }
+        let distinct = revisiting.clone().with_discovery_policy(policy.with_distinct_protocols(true));
+        let built = distinct.build_paths_from_indices(four_hop, &g, &simulations, &components).unwrap();
+        assert!(built.is_empty());
+    }
+
+    #[test]
+    fn test_paths_missing_protocol_data_are_recovered() {
+        use crate::path::MissingData;
+        use crate::testing::{component, MockProtocolSim};
+
+        let tokens: Vec<Bytes> = ["0x0000", "0x0001"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        let mut g = TradingGraph::new();
+        for token in &tokens {
+            g.add_token(token.clone()).unwrap();
+        }
+        let [streamed, pending] = ["0x1000", "0x1001"].map(|pool| Bytes::from_str(pool).unwrap());
+        let mut components = HashMap::new();
+        let mut simulations: HashMap<Bytes, Arc<dyn ProtocolSim>> = HashMap::new();
+        for pool in [&streamed, &pending] {
+            g.add_pool(pool.clone(), [0, 1]).unwrap();
+            components.insert(pool.clone(), Arc::new(component(pool, &tokens)));
+        }
+        simulations.insert(streamed.clone(), Arc::new(MockProtocolSim::new()));
+
+        let mut repository = PathRepository::new(vec![tokens[0].clone()], 2);
+        repository.discover_paths(&g, 0, 2, 0, 8);
+        let path_indices = repository.get_path_indices_for_pool(&pending).unwrap().clone();
+        let built = repository.build_paths_from_indices(path_indices.clone(), &g, &simulations, &components).unwrap();
+        assert!(built.is_empty());
+        assert_eq!(repository.missing_data().get(&pending).map(|entry| entry.missing), Some(MissingData::Simulation));
+        assert_eq!(repository.missing_data().take_requests(), vec![(pending.clone(), MissingData::Simulation)]);
+        assert!(repository.missing_data().take_requests().is_empty());
+        assert!(repository.missing_data().recover(&components, &simulations).is_empty());
+
+        // The state arrives, the pool is searched again
+        simulations.insert(pending.clone(), Arc::new(MockProtocolSim::new()));
+        assert_eq!(repository.missing_data().recover(&components, &simulations), vec![pending]);
+        assert!(repository.missing_data().is_empty());
+        let built = repository.build_paths_from_indices(path_indices.clone(), &g, &simulations, &components).unwrap();
+        assert_eq!(built.len(), path_indices.len());
+    }
+
+    #[test]
+    fn test_template_paths_are_added_once() {
+        use crate::path::{TriangularTemplate, TwoHopTemplate};
+
+        let mut g = TradingGraph::new();
+        for token in ["0x0000", "0x0001", "0x0002"] {
+            g.add_token(Bytes::from_str(token).unwrap()).unwrap();
+        }
+        g.add_pool(Bytes::from_str("0x1000").unwrap(), [0, 1]).unwrap();
+        g.add_pool(Bytes::from_str("0x1001").unwrap(), [1, 2]).unwrap();
+        g.add_pool(Bytes::from_str("0x1002").unwrap(), [0, 2]).unwrap();
+
+        let mut paths_repo = PathRepository::new(vec![Bytes::from_str("0x0000").unwrap()], 3);
+        paths_repo.discover_paths(&g, 0, 3, 0, 6);
+        let discovered = paths_repo.pool_paths.len();
+
+        // Discovery already found both directions of the triangle
+        let triangles = TriangularTemplate::new(vec![Bytes::from_str("0x0001").unwrap()]);
+        assert_eq!(paths_repo.add_template_paths(&g, &triangles), 0);
+        assert_eq!(paths_repo.add_template_paths(&g, &TwoHopTemplate), 0);
+
+        g.add_pool(Bytes::from_str("0x1003").unwrap(), [0, 1]).unwrap();
+        assert_eq!(paths_repo.add_template_paths(&g, &TwoHopTemplate), 2);
+        assert_eq!(paths_repo.add_template_paths(&g, &TwoHopTemplate), 0);
+        assert_eq!(paths_repo.pool_paths.len(), discovered + 2);
+        assert_eq!(paths_repo.get_path_indices_for_pool(&Bytes::from_str("0x1003").unwrap()).unwrap().len(), 2);
+    }
 }
@@ -0,0 +1,293 @@
+//! Path repositories sharded by source token.
+//!
+//! A single [`PathRepository`] behind one lock serializes all discovery work,
+//! and its discovery runs one source token after another. A
+//! `ShardedPathRepository` keeps one repository per source token instead:
+//!
+//! - **Discovery**: every shard discovers the paths starting from its source
+//!   token under its own lock, in parallel on the rayon thread pool
+//! - **Reads**: [`ShardedPathRepository::read`] locks all shards for reading
+//!   and combines their paths into one view
+//!
+//! Every shard knows all source tokens, so the discovery policy treats them
+//! exactly like one repository does, and the shards together hold the same
+//! paths.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::path::ShardedPathRepository;
+//!
+//! let repository = ShardedPathRepository::new(vec![weth, usdc], 3);
+//! repository.discover_paths(&graph, 0, graph.token_count(), 0, graph.pool_count());
+//!
+//! let paths = repository.read().get_paths_for_pools(&updated_pools, &graph, &components, &simulations)?;
+//! ```
+
+use crate::errors::Result;
+use crate::graph::TradingGraph;
+use crate::path::{DiscoveryPolicy, Path, PathRepository, RepositoryStatistics};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tycho_common::Bytes;
+use tycho_simulation::protocol::{models::ProtocolComponent, state::ProtocolSim};
+
+/// The paths of one source token.
+#[derive(Debug)]
+struct PathShard {
+    source_token: Bytes,
+    repository: RwLock<PathRepository>,
+}
+
+impl PathShard {
+    fn read(&self) -> RwLockReadGuard<'_, PathRepository> {
+        self.repository.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, PathRepository> {
+        self.repository.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Path repositories of each source token, discovering in parallel.
+#[derive(Debug)]
+pub struct ShardedPathRepository {
+    shards: Vec<PathShard>,
+}
+
+impl ShardedPathRepository {
+    /// Create one empty shard per source token.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_tokens` - Token addresses that serve as starting points for path discovery
+    /// * `maximum_path_length` - Maximum number of swaps allowed in a path
+    pub fn new(source_tokens: Vec<Bytes>, maximum_path_length: usize) -> Self {
+        let mut seen = HashSet::new();
+        let unique_sources: Vec<Bytes> = source_tokens.iter().filter(|token| seen.insert(*token)).cloned().collect();
+        let shards = unique_sources
+            .into_iter()
+            .map(|source_token| PathShard {
+                repository: RwLock::new(
+                    PathRepository::new(source_tokens.clone(), maximum_path_length)
+                        .with_shard_source(source_token.clone()),
+                ),
+                source_token,
+            })
+            .collect();
+        Self { shards }
+    }
+
+    /// Set the constraints on the shape of discovered paths of every shard.
+    pub fn with_discovery_policy(self, discovery_policy: DiscoveryPolicy) -> Self {
+        self.map_shards(|repository| repository.with_discovery_policy(discovery_policy.clone()))
+    }
+
+    /// Limit the length of paths from source tokens, see
+    /// [`PathRepository::with_source_path_length`].
+    pub fn with_source_path_lengths(self, source_path_lengths: HashMap<Bytes, usize>) -> Self {
+        self.map_shards(|repository| repository.with_source_path_lengths(source_path_lengths.clone()))
+    }
+
+    fn map_shards(self, map: impl Fn(PathRepository) -> PathRepository) -> Self {
+        let shards = self
+            .shards
+            .into_iter()
+            .map(|shard| PathShard {
+                repository: RwLock::new(map(shard.repository.into_inner().unwrap_or_else(|e| e.into_inner()))),
+                source_token: shard.source_token,
+            })
+            .collect();
+        Self { shards }
+    }
+
+    /// The source token of each shard.
+    pub fn source_tokens(&self) -> impl Iterator<Item = &Bytes> {
+        self.shards.iter().map(|shard| &shard.source_token)
+    }
+
+    /// The number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Lock the shard of a source token for reading.
+    pub fn shard(&self, source_token: &Bytes) -> Option<RwLockReadGuard<'_, PathRepository>> {
+        self.find_shard(source_token).map(PathShard::read)
+    }
+
+    /// Lock the shard of a source token for writing.
+    pub fn shard_mut(&self, source_token: &Bytes) -> Option<RwLockWriteGuard<'_, PathRepository>> {
+        self.find_shard(source_token).map(PathShard::write)
+    }
+
+    fn find_shard(&self, source_token: &Bytes) -> Option<&PathShard> {
+        self.shards.iter().find(|shard| &shard.source_token == source_token)
+    }
+
+    /// Discover new paths in every shard in parallel, see [`PathRepository::discover_paths`].
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The trading graph to discover paths from
+    /// * `new_token_offset` - Starting index of newly added tokens
+    /// * `new_token_count` - Number of newly added tokens
+    /// * `new_pool_offset` - Starting index of newly added pools
+    /// * `new_pool_count` - Number of newly added pools
+    pub fn discover_paths(
+        &self,
+        graph: &TradingGraph,
+        new_token_offset: usize,
+        new_token_count: usize,
+        new_pool_offset: usize,
+        new_pool_count: usize,
+    ) {
+        self.shards.par_iter().for_each(|shard| {
+            shard
+                .write()
+                .discover_paths(graph, new_token_offset, new_token_count, new_pool_offset, new_pool_count);
+        });
+    }
+
+    /// Remap the paths of every shard to the current IDs of a graph, see
+    /// [`PathRepository::remap_to_graph`].
+    ///
+    /// # Returns
+    ///
+    /// The number of evicted token and pool paths
+    pub fn remap_to_graph(&self, graph: &TradingGraph) -> usize {
+        self.shards
+            .par_iter()
+            .map(|shard| shard.write().remap_to_graph(graph))
+            .sum()
+    }
+
+    /// Remove pools from the graph and evict the paths through them from every shard.
+    ///
+    /// # Returns
+    ///
+    /// The addresses of the pools that were part of the graph and were removed
+    pub fn remove_pools(&self, graph: &mut TradingGraph, pool_addresses: &[Bytes]) -> Vec<Bytes> {
+        let removed: Vec<Bytes> = pool_addresses
+            .iter()
+            .filter(|pool_address| graph.remove_protocol_component(pool_address).is_ok())
+            .cloned()
+            .collect();
+        if !removed.is_empty() {
+            for shard in &self.shards {
+                shard.read().missing_data().forget(&removed);
+            }
+            self.remap_to_graph(graph);
+        }
+        removed
+    }
+
+    /// Lock every shard for reading, in a combined view.
+    pub fn read(&self) -> ShardedPathView<'_> {
+        ShardedPathView {
+            shards: self.shards.iter().map(|shard| (&shard.source_token, shard.read())).collect(),
+        }
+    }
+}
+
+/// The paths of all shards, read-locked while the view lives.
+#[derive(Debug)]
+pub struct ShardedPathView<'a> {
+    shards: Vec<(&'a Bytes, RwLockReadGuard<'a, PathRepository>)>,
+}
+
+impl ShardedPathView<'_> {
+    /// The repository of each shard with its source token.
+    pub fn shards(&self) -> impl Iterator<Item = (&Bytes, &PathRepository)> {
+        self.shards.iter().map(|(source_token, repository)| (*source_token, &**repository))
+    }
+
+    /// Build the paths of every shard involving the specified pools, see
+    /// [`PathRepository::get_paths_for_pools`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if building the paths of any shard fails.
+    pub fn get_paths_for_pools(
+        &self,
+        pool_addresses: &[Bytes],
+        graph: &TradingGraph,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
+    ) -> Result<Vec<Path>> {
+        let mut paths = Vec::new();
+        for (_, repository) in self.shards() {
+            paths.extend(repository.get_paths_for_pools(pool_addresses, graph, protocol_components, protocol_simulations)?);
+        }
+        Ok(paths)
+    }
+
+    /// The number of pool paths of all shards.
+    pub fn pool_path_count(&self) -> usize {
+        self.shards().map(|(_, repository)| repository.pool_paths.len()).sum()
+    }
+
+    /// The number of token paths of all shards.
+    pub fn token_path_count(&self) -> usize {
+        self.shards().map(|(_, repository)| repository.token_paths.len()).sum()
+    }
+
+    /// Statistics of all shards combined.
+    pub fn statistics(&self) -> RepositoryStatistics {
+        let mut combined = RepositoryStatistics {
+            source_token_count: self.shards.len(),
+            maximum_path_length: 0,
+            token_path_count: 0,
+            pool_path_count: 0,
+            indexed_token_count: 0,
+            indexed_pool_count: 0,
+        };
+        for (_, repository) in self.shards() {
+            let statistics = repository.statistics();
+            combined.maximum_path_length = combined.maximum_path_length.max(statistics.maximum_path_length);
+            combined.token_path_count += statistics.token_path_count;
+            combined.pool_path_count += statistics.pool_path_count;
+            combined.indexed_token_count += statistics.indexed_token_count;
+            combined.indexed_pool_count += statistics.indexed_pool_count;
+        }
+        combined
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_shards_find_the_paths_of_one_repository() {
+        let tokens: Vec<Bytes> = ["0x0000", "0x0001", "0x0002", "0x0003"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        let mut graph = TradingGraph::new();
+        for token in &tokens {
+            graph.add_token(token.clone()).unwrap();
+        }
+        for (pool, pair) in [("0x1000", [0, 1]), ("0x1001", [1, 2]), ("0x1002", [0, 2]), ("0x1003", [2, 3]), ("0x1004", [1, 3])] {
+            graph.add_pool(Bytes::from_str(pool).unwrap(), pair).unwrap();
+        }
+        let sources = vec![tokens[0].clone(), tokens[1].clone()];
+
+        let mut repository = PathRepository::new(sources.clone(), 3);
+        repository.discover_paths(&graph, 0, 4, 0, 10);
+        let sharded = ShardedPathRepository::new(sources, 3);
+        sharded.discover_paths(&graph, 0, 4, 0, 10);
+        assert_eq!(sharded.shard_count(), 2);
+
+        let view = sharded.read();
+        assert_eq!(view.pool_path_count(), repository.pool_paths.len());
+        let sharded_paths: HashSet<Vec<usize>> = view
+            .shards()
+            .flat_map(|(_, shard)| shard.pool_paths.iter().cloned())
+            .collect();
+        assert_eq!(sharded_paths, repository.pool_paths.iter().cloned().collect());
+        for (source_token, shard) in view.shards() {
+            let source = graph.find_token_id(source_token).unwrap();
+            assert!(shard.token_paths.iter().all(|token_path| token_path[0] == source));
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Serializable twins of paths and executed paths for audits and replays.
+//!
+//! Swaps hold an `Arc<dyn ProtocolSim>`, which cannot be serialized, so paths are
+//! stored as the pools, tokens, amounts and gas of their swaps instead. A stored
+//! path is restored against the protocol components and simulation states of a
+//! later or recorded block, and a stored executed path can be replayed with its
+//! original input amount to compare the outcome with what was submitted.
+//!
+//! Amounts are serialized as decimal strings, so every value round-trips exactly
+//! and remains readable in audit logs.
+//!
+//! This module is available with the `storage` feature.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::path::storage::PathExtForStorage;
+//!
+//! let stored = serde_json::to_string(&PathExtForStorage::from(&executed_path))?;
+//!
+//! // Later, against the states of the block in question
+//! let stored: PathExtForStorage = serde_json::from_str(&stored)?;
+//! let replayed = stored.replay(&protocol_components, &protocol_simulations)?;
+//! let shortfall = stored.profit() - replayed.profit()?;
+//! ```
+
+use crate::errors::{PathError, Result};
+use crate::path::{Path, PathExt, Swap, SwapExt, SwapForStorage};
+use num_bigint::{BigInt, BigUint};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tycho_common::Bytes;
+use tycho_simulation::protocol::{models::ProtocolComponent, state::ProtocolSim};
+
+/// A path stored as the pools and tokens of its swaps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathForStorage {
+    /// The swaps of the path, in execution order
+    pub swaps: Vec<SwapForStorage>,
+}
+
+impl PathForStorage {
+    /// Rebuild the path from protocol components and simulation states.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_components` - Components of all pools, keyed by pool address
+    /// * `protocol_simulations` - Simulation states of all pools, keyed by pool address
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored path is empty, if a pool is missing from
+    /// either map, or if a stored token is not traded by its pool.
+    pub fn restore(
+        &self,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
+    ) -> Result<Path> {
+        if self.swaps.is_empty() {
+            return Err(PathError::EmptyPath.into());
+        }
+
+        self.swaps
+            .iter()
+            .map(|swap| swap.restore(protocol_components, protocol_simulations))
+            .collect()
+    }
+}
+
+impl From<&Path> for PathForStorage {
+    fn from(path: &Path) -> Self {
+        Self {
+            swaps: path.iter().map(SwapForStorage::from).collect(),
+        }
+    }
+}
+
+impl SwapForStorage {
+    /// Rebuild the swap from protocol components and simulation states.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool is missing from either map, or if a stored
+    /// token is not traded by the pool.
+    pub fn restore(
+        &self,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
+    ) -> Result<Swap> {
+        let pool_comp = protocol_components
+            .get(&self.pool)
+            .ok_or_else(|| PathError::ProtocolComponentNotFound { pool: self.pool.clone() })?
+            .clone();
+        let pool_sim = protocol_simulations
+            .get(&self.pool)
+            .ok_or_else(|| PathError::ProtocolSimulationNotFound { pool: self.pool.clone() })?
+            .clone();
+
+        Swap::new(pool_comp, pool_sim, &self.token_in, &self.token_out)
+    }
+}
+
+impl From<&Swap> for SwapForStorage {
+    fn from(swap: &Swap) -> Self {
+        Self {
+            pool: swap.pool_comp.id.clone(),
+            token_in: swap.token_in.address.clone(),
+            token_out: swap.token_out.address.clone(),
+        }
+    }
+}
+
+/// An executed swap stored with its amounts and gas.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapExtForStorage {
+    /// The address of the liquidity pool
+    pub pool: Bytes,
+    /// The protocol system of the pool, e.g. `uniswap_v3`
+    pub protocol_system: String,
+    /// The address of the input token
+    pub token_in: Bytes,
+    /// The address of the output token
+    pub token_out: Bytes,
+    /// Whether the input token sorts before the output token, the swap direction of two-token pools
+    pub zero_for_one: bool,
+    /// The amount of input tokens consumed
+    #[serde(with = "decimal")]
+    pub amount_in: BigUint,
+    /// The amount of output tokens received
+    #[serde(with = "decimal")]
+    pub amount_out: BigUint,
+    /// The estimated gas of the swap
+    #[serde(with = "decimal")]
+    pub gas: BigUint,
+}
+
+impl From<&SwapExt> for SwapExtForStorage {
+    fn from(swap: &SwapExt) -> Self {
+        Self {
+            pool: swap.pool_comp.id.clone(),
+            protocol_system: swap.pool_comp.protocol_system.clone(),
+            token_in: swap.token_in.address.clone(),
+            token_out: swap.token_out.address.clone(),
+            zero_for_one: swap.token_in.address < swap.token_out.address,
+            amount_in: swap.amount_in.clone(),
+            amount_out: swap.amount_out.clone(),
+            gas: swap.gas.clone(),
+        }
+    }
+}
+
+impl From<&SwapExtForStorage> for SwapForStorage {
+    fn from(swap: &SwapExtForStorage) -> Self {
+        Self {
+            pool: swap.pool.clone(),
+            token_in: swap.token_in.clone(),
+            token_out: swap.token_out.clone(),
+        }
+    }
+}
+
+/// An executed path stored with the amounts and gas of every swap.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathExtForStorage {
+    /// The executed swaps of the path, in execution order
+    pub swaps: Vec<SwapExtForStorage>,
+}
+
+impl PathExtForStorage {
+    /// The input amount of the first swap, zero for an empty path.
+    pub fn amount_in(&self) -> BigUint {
+        self.swaps.first().map(|swap| swap.amount_in.clone()).unwrap_or_default()
+    }
+
+    /// The output amount of the last swap, zero for an empty path.
+    pub fn amount_out(&self) -> BigUint {
+        self.swaps.last().map(|swap| swap.amount_out.clone()).unwrap_or_default()
+    }
+
+    /// The profit of the stored execution.
+    pub fn profit(&self) -> BigInt {
+        BigInt::from(self.amount_out()) - BigInt::from(self.amount_in())
+    }
+
+    /// The path without its amounts.
+    pub fn path(&self) -> PathForStorage {
+        PathForStorage {
+            swaps: self.swaps.iter().map(SwapForStorage::from).collect(),
+        }
+    }
+
+    /// Simulate the stored path again with its original input amount.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_components` - Components of all pools, keyed by pool address
+    /// * `protocol_simulations` - Simulation states to replay against, keyed by pool address
+    ///
+    /// # Returns
+    ///
+    /// The path executed against the given states, to be compared with the stored amounts
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path cannot be restored or a swap fails to simulate
+    pub fn replay(
+        &self,
+        protocol_components: &HashMap<Bytes, Arc<ProtocolComponent>>,
+        protocol_simulations: &HashMap<Bytes, Arc<dyn ProtocolSim>>,
+    ) -> Result<PathExt> {
+        let path = self.path().restore(protocol_components, protocol_simulations)?;
+        let replayed = path.execute_with_amount(self.amount_in())?;
+
+        tracing::debug!(
+            path_length = replayed.len(),
+            stored_amount_out = %self.amount_out(),
+            replayed_amount_out = %replayed.last().map(|swap| swap.amount_out.clone()).unwrap_or_default(),
+            "Replayed stored path"
+        );
+
+        Ok(replayed)
+    }
+}
+
+impl From<&PathExt> for PathExtForStorage {
+    fn from(path: &PathExt) -> Self {
+        Self {
+            swaps: path.iter().map(SwapExtForStorage::from).collect(),
+        }
+    }
+}
+
+impl Serialize for Path {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        PathForStorage::from(self).serialize(serializer)
+    }
+}
+
+impl Serialize for PathExt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        PathExtForStorage::from(self).serialize(serializer)
+    }
+}
+
+/// Serde helpers storing unsigned amounts as decimal strings.
+pub(crate) mod decimal {
+    use num_bigint::BigUint;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(value: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        BigUint::from_str(&value).map_err(de::Error::custom)
+    }
+}
+
+/// Serde helpers storing signed amounts as decimal strings.
+pub(crate) mod signed_decimal {
+    use num_bigint::BigInt;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(value: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        BigInt::from_str(&value).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ArbitrageError;
+    use std::str::FromStr;
+
+    fn stored_swap(pool: &str, token_in: &str, token_out: &str, amount_in: BigUint, amount_out: BigUint) -> SwapExtForStorage {
+        let token_in = Bytes::from_str(token_in).unwrap();
+        let token_out = Bytes::from_str(token_out).unwrap();
+        SwapExtForStorage {
+            pool: Bytes::from_str(pool).unwrap(),
+            protocol_system: "uniswap_v2".to_string(),
+            zero_for_one: token_in < token_out,
+            token_in,
+            token_out,
+            amount_in,
+            amount_out,
+            gas: BigUint::from(120_000u32),
+        }
+    }
+
+    #[test]
+    fn test_executed_path_round_trips_exactly() {
+        // 18-decimals amounts well beyond the f64 mantissa
+        let amount_in = BigUint::from(10u32).pow(24) + 1u32;
+        let amount_mid = BigUint::from(10u32).pow(21) + 7u32;
+        let amount_out = &amount_in + 3u32;
+        let stored = PathExtForStorage {
+            swaps: vec![
+                stored_swap("0x1001", "0x0001", "0x0002", amount_in.clone(), amount_mid.clone()),
+                stored_swap("0x1002", "0x0002", "0x0001", amount_mid, amount_out),
+            ],
+        };
+
+        let json = serde_json::to_string(&stored).unwrap();
+        assert!(json.contains("\"amount_in\":\"1000000000000000000000001\""));
+
+        let restored: PathExtForStorage = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, stored);
+        assert_eq!(restored.amount_in(), amount_in);
+        assert_eq!(restored.profit(), BigInt::from(3));
+        assert!(restored.swaps[0].zero_for_one);
+        assert!(!restored.swaps[1].zero_for_one);
+    }
+
+    #[test]
+    fn test_restore_requires_known_pools() {
+        let stored = PathExtForStorage {
+            swaps: vec![stored_swap("0x1001", "0x0001", "0x0002", BigUint::from(1u32), BigUint::from(1u32))],
+        };
+
+        let result = stored.path().restore(&HashMap::new(), &HashMap::new());
+        assert!(matches!(
+            result,
+            Err(ArbitrageError::Path(PathError::ProtocolComponentNotFound { .. }))
+        ));
+        assert!(matches!(
+            PathForStorage { swaps: vec![] }.restore(&HashMap::new(), &HashMap::new()),
+            Err(ArbitrageError::Path(PathError::EmptyPath))
+        ));
+    }
+}
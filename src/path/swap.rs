@@ -12,12 +12,10 @@
 //!
 //! # Swap Direction
 //!
-//! All swap types use the `zero_for_one` boolean to indicate trading direction:
-//! - `true`: Trading from token0 to token1 (tokens ordered by address)
-//! - `false`: Trading from token1 to token0
-//!
-//! This convention is consistent with most DEX protocols and enables efficient
-//! path representation and execution.
+//! All swap types carry their input and output tokens explicitly. Pools with more
+//! than two tokens, such as Curve tricrypto or Balancer weighted pools, can be
+//! traded between any pair of their tokens, so the direction of a swap cannot be
+//! derived from the token order of the pool.
 //!
 //! # Protocol Integration
 //!
@@ -38,7 +36,9 @@
 use crate::errors::{PathError, Result};
 use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 use tycho_common::Bytes;
 use tycho_simulation::{
     models::Token,
@@ -54,52 +54,81 @@ use tycho_simulation::{
 /// It contains the protocol component information and simulation state needed to
 /// calculate swap outcomes and validate feasibility.
 ///
+/// The component and simulation state are shared with the market data maps the
+/// swap was built from, so building and cloning paths does not copy pool state.
+///
 /// # Fields
 ///
 /// - `pool_comp`: The protocol component containing pool and token information
 /// - `pool_sim`: The protocol simulation state for calculating swap outcomes
-/// - `zero_for_one`: The direction of the swap (token0 -> token1 if true)
+/// - `token_in`: The token sold into the pool
+/// - `token_out`: The token bought from the pool
 #[derive(Clone)]
 pub struct Swap {
     /// The protocol component containing pool metadata and token information
-    pub pool_comp: ProtocolComponent,
+    pub pool_comp: Arc<ProtocolComponent>,
     /// The protocol simulation state for this pool
-    pub pool_sim: Box<dyn ProtocolSim>,
-    /// Whether this swap goes from token0 to token1 (true) or token1 to token0 (false)
-    pub zero_for_one: bool,
+    pub pool_sim: Arc<dyn ProtocolSim>,
+    /// The token sold into the pool, one of the pool's tokens
+    pub token_in: Token,
+    /// The token bought from the pool, one of the pool's tokens
+    pub token_out: Token,
 }
 
 impl Swap {
-    /// Get the input token for this swap.
+    /// Create a swap between two tokens of a pool.
+    ///
+    /// # Arguments
     ///
-    /// Returns the token that will be consumed in this swap operation,
-    /// determined by the swap direction and token ordering.
+    /// * `pool_comp` - The protocol component of the pool
+    /// * `pool_sim` - The protocol simulation state of the pool
+    /// * `token_in` - The address of the token sold into the pool
+    /// * `token_out` - The address of the token bought from the pool
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either token is not traded by the pool, or if both
+    /// tokens are the same.
+    pub fn new(
+        pool_comp: impl Into<Arc<ProtocolComponent>>,
+        pool_sim: Arc<dyn ProtocolSim>,
+        token_in: &Bytes,
+        token_out: &Bytes,
+    ) -> Result<Self> {
+        let pool_comp = pool_comp.into();
+        if token_in == token_out {
+            return Err(PathError::InvalidPath {
+                reason: format!("Swap through pool {} has identical input and output token", pool_comp.id),
+            }.into());
+        }
+
+        let token_in = pool_token(&pool_comp, token_in)?;
+        let token_out = pool_token(&pool_comp, token_out)?;
+
+        Ok(Self {
+            pool_comp,
+            pool_sim,
+            token_in,
+            token_out,
+        })
+    }
+
+    /// Get the input token for this swap.
     ///
     /// # Returns
     ///
     /// A reference to the input token
     pub fn token_in(&self) -> &Token {
-        if self.zero_for_one {
-            &self.pool_comp.tokens[0]
-        } else {
-            &self.pool_comp.tokens[1]
-        }
+        &self.token_in
     }
 
     /// Get the output token for this swap.
     ///
-    /// Returns the token that will be received from this swap operation,
-    /// determined by the swap direction and token ordering.
-    ///
     /// # Returns
     ///
     /// A reference to the output token
     pub fn token_out(&self) -> &Token {
-        if self.zero_for_one {
-            &self.pool_comp.tokens[1]
-        } else {
-            &self.pool_comp.tokens[0]
-        }
+        &self.token_out
     }
 
     /// Calculate the current spot price for this swap.
@@ -180,6 +209,128 @@ impl Swap {
                 pool: self.pool_comp.id.clone() 
             }.into())
     }
+
+    /// Calculate the input amount needed for a given output amount.
+    ///
+    /// Protocol simulations only quote outputs, so the input is found by a
+    /// binary search over [`Swap::get_amount_out`] within the input limit of
+    /// the pool, relying on outputs that do not decrease with the input.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount_out` - The amount of output tokens to receive
+    ///
+    /// # Returns
+    ///
+    /// The smallest input amount whose output reaches `amount_out`
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The pool limits cannot be calculated
+    /// - Not even the maximum input amount reaches `amount_out`
+    pub fn get_amount_in(&self, amount_out: &BigUint) -> Result<BigUint> {
+        self.get_amount_in_adjusted(amount_out, |amount| amount)
+    }
+
+    /// Calculate the input amount needed for a given output amount after an
+    /// adjustment of the output, e.g. a transfer fee.
+    pub(crate) fn get_amount_in_adjusted(&self, amount_out: &BigUint, adjust: impl Fn(BigUint) -> BigUint) -> Result<BigUint> {
+        let (max_in, _max_out) = self.get_limits()?;
+        let adjusted_out = |amount_in: &BigUint| {
+            self.get_amount_out(amount_in.clone()).ok().map(|result| adjust(result.amount))
+        };
+        let reaches = |amount_in: &BigUint| adjusted_out(amount_in).is_some_and(|amount| amount >= *amount_out);
+        if !reaches(&max_in) {
+            return Err(PathError::AmountExceedsLimits {
+                requested: amount_out.to_string(),
+                max_available: adjusted_out(&max_in).unwrap_or_default().to_string(),
+            }.into());
+        }
+
+        let (mut low, mut high) = (BigUint::from(0u32), max_in);
+        while &high - &low > BigUint::from(1u32) {
+            let middle = (&low + &high) >> 1;
+            if reaches(&middle) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        Ok(if reaches(&low) { low } else { high })
+    }
+
+    /// The largest input amount within the limits of the pool whose output
+    /// does not exceed `max_amount_out`.
+    ///
+    /// Found by a binary search over [`Swap::get_amount_out`] like
+    /// [`Swap::get_amount_in`], with the output also capped by the output
+    /// limit of the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool limits cannot be calculated.
+    pub fn max_amount_in_within(&self, max_amount_out: &BigUint) -> Result<BigUint> {
+        let (max_in, max_out) = self.get_limits()?;
+        let max_amount_out = max_amount_out.min(&max_out);
+        let within = |amount_in: &BigUint| {
+            self.get_amount_out(amount_in.clone())
+                .is_ok_and(|result| result.amount <= *max_amount_out)
+        };
+        if within(&max_in) {
+            return Ok(max_in);
+        }
+
+        let (mut low, mut high) = (BigUint::from(0u32), max_in);
+        while &high - &low > BigUint::from(1u32) {
+            let middle = (&low + &high) >> 1;
+            if within(&middle) {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        Ok(low)
+    }
+
+    /// Calculate the output amounts for several input amounts.
+    ///
+    /// The pool limits are looked up once for all amounts, and identical amounts
+    /// are simulated once. Protocol simulations quote one amount per call, so
+    /// this is the single place where batched quoting of a protocol plugs in.
+    ///
+    /// # Arguments
+    ///
+    /// * `amounts_in` - The amounts of input tokens to swap
+    ///
+    /// # Returns
+    ///
+    /// The output amount of every input amount, in order, or `None` if the
+    /// amount exceeds the pool limits or fails to simulate.
+    pub fn get_amounts_out(&self, amounts_in: &[BigUint]) -> Vec<Option<BigUint>> {
+        let Ok((max_in, max_out)) = self.get_limits() else {
+            return vec![None; amounts_in.len()];
+        };
+
+        let mut simulated: HashMap<&BigUint, Option<BigUint>> = HashMap::with_capacity(amounts_in.len());
+        amounts_in
+            .iter()
+            .map(|amount_in| {
+                simulated
+                    .entry(amount_in)
+                    .or_insert_with(|| {
+                        if *amount_in > max_in {
+                            return None;
+                        }
+                        self.get_amount_out(amount_in.clone())
+                            .ok()
+                            .map(|result| result.amount)
+                            .filter(|amount_out| *amount_out <= max_out)
+                    })
+                    .clone()
+            })
+            .collect()
+    }
 }
 
 impl fmt::Debug for Swap {
@@ -188,9 +339,8 @@ impl fmt::Debug for Swap {
             .field("pool", &self.pool_comp.id)
             .field("protocol_system", &self.pool_comp.protocol_system)
             .field("protocol_type", &self.pool_comp.protocol_type_name)
-            .field("zero_for_one", &self.zero_for_one)
-            .field("token_in", &self.token_in().address)
-            .field("token_out", &self.token_out().address)
+            .field("token_in", &self.token_in.address)
+            .field("token_out", &self.token_out.address)
             .finish()
     }
 }
@@ -205,18 +355,21 @@ impl fmt::Debug for Swap {
 ///
 /// - `pool_comp`: The protocol component containing pool and token information
 /// - `pool_sim`: The protocol simulation state for calculating additional metrics
-/// - `zero_for_one`: The direction of the swap
+/// - `token_in`: The token sold into the pool
+/// - `token_out`: The token bought from the pool
 /// - `amount_in`: The actual amount of input tokens consumed
 /// - `amount_out`: The actual amount of output tokens received
 /// - `gas`: The estimated gas cost for executing this swap
 #[derive(Clone)]
 pub struct SwapExt {
     /// The protocol component containing pool metadata and token information
-    pub pool_comp: ProtocolComponent,
+    pub pool_comp: Arc<ProtocolComponent>,
     /// The protocol simulation state for this pool
-    pub pool_sim: Box<dyn ProtocolSim>,
-    /// Whether this swap goes from token0 to token1 (true) or token1 to token0 (false)
-    pub zero_for_one: bool,
+    pub pool_sim: Arc<dyn ProtocolSim>,
+    /// The token sold into the pool, one of the pool's tokens
+    pub token_in: Token,
+    /// The token bought from the pool, one of the pool's tokens
+    pub token_out: Token,
     /// The amount of input tokens consumed in this swap
     pub amount_in: BigUint,
     /// The amount of output tokens received from this swap
@@ -226,36 +379,42 @@ pub struct SwapExt {
 }
 
 impl SwapExt {
-    /// Get the input token for this executed swap.
+    /// Create an executed swap from a swap and its simulated amounts.
+    ///
+    /// # Arguments
     ///
-    /// Returns the token that was consumed in this swap operation,
-    /// determined by the swap direction and token ordering.
+    /// * `swap` - The swap that was executed
+    /// * `amount_in` - The amount of input tokens consumed
+    /// * `amount_out` - The amount of output tokens received
+    /// * `gas` - The estimated gas cost of the swap
+    pub fn from_swap(swap: &Swap, amount_in: BigUint, amount_out: BigUint, gas: BigUint) -> Self {
+        Self {
+            pool_comp: Arc::clone(&swap.pool_comp),
+            pool_sim: Arc::clone(&swap.pool_sim),
+            token_in: swap.token_in.clone(),
+            token_out: swap.token_out.clone(),
+            amount_in,
+            amount_out,
+            gas,
+        }
+    }
+
+    /// Get the input token for this executed swap.
     ///
     /// # Returns
     ///
     /// A reference to the input token
     pub fn token_in(&self) -> &Token {
-        if self.zero_for_one {
-            &self.pool_comp.tokens[0]
-        } else {
-            &self.pool_comp.tokens[1]
-        }
+        &self.token_in
     }
 
     /// Get the output token for this executed swap.
     ///
-    /// Returns the token that was received from this swap operation,
-    /// determined by the swap direction and token ordering.
-    ///
     /// # Returns
     ///
     /// A reference to the output token
     pub fn token_out(&self) -> &Token {
-        if self.zero_for_one {
-            &self.pool_comp.tokens[1]
-        } else {
-            &self.pool_comp.tokens[0]
-        }
+        &self.token_out
     }
 }
 
@@ -265,7 +424,8 @@ impl fmt::Debug for SwapExt {
             .field("pool", &self.pool_comp.id)
             .field("protocol_system", &self.pool_comp.protocol_system)
             .field("protocol_type", &self.pool_comp.protocol_type_name)
-            .field("zero_for_one", &self.zero_for_one)
+            .field("token_in", &self.token_in.address)
+            .field("token_out", &self.token_out.address)
             .field("amount_in", &self.amount_in)
             .field("amount_out", &self.amount_out)
             .field("gas", &self.gas)
@@ -273,6 +433,18 @@ impl fmt::Debug for SwapExt {
     }
 }
 
+/// Look up one of the tokens traded by a pool.
+fn pool_token(pool_comp: &ProtocolComponent, address: &Bytes) -> Result<Token> {
+    pool_comp
+        .tokens
+        .iter()
+        .find(|token| &token.address == address)
+        .cloned()
+        .ok_or_else(|| PathError::InvalidPath {
+            reason: format!("Token {} is not traded by pool {}", address, pool_comp.id),
+        }.into())
+}
+
 /// A serializable representation of a swap for storage purposes.
 ///
 /// This lightweight structure contains only the essential information needed
@@ -296,7 +468,7 @@ impl fmt::Debug for SwapExt {
 ///
 /// The stored information can later be used to reconstruct full Swap objects
 /// by looking up the protocol components and simulation state.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SwapForStorage {
     /// The address of the liquidity pool where the swap occurs
     pub pool: Bytes,
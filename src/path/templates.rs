@@ -0,0 +1,190 @@
+//! Template-based generators of short cycles.
+//!
+//! Full recursive discovery explores every token path up to the maximum
+//! length, although most profit comes from 2-hop and 3-hop cycles through a
+//! handful of major tokens. Templates generate these cycles directly from the
+//! pair index of the trading graph, in time linear in the pools around the
+//! source and via tokens. Generated paths are added to a [`PathRepository`]
+//! with [`PathRepository::add_template_paths`], next to discovered paths.
+//!
+//! [`PathRepository`]: crate::path::PathRepository
+//! [`PathRepository::add_template_paths`]: crate::path::PathRepository::add_template_paths
+
+use crate::graph::{PoolId, TokenId, TradingGraph};
+use crate::path::DiscoveredPath;
+use std::collections::HashSet;
+use tycho_common::Bytes;
+
+/// A generator of cyclic paths from a source token.
+pub trait PathTemplate {
+    /// Generate the cycles of the template starting and ending at a source token.
+    ///
+    /// Each path trades through distinct pool addresses. Tokens or pairs that
+    /// are not part of the graph produce no paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The trading graph to generate paths from
+    /// * `source_token` - The ID of the token the cycles start at
+    fn generate(&self, graph: &TradingGraph, source_token: TokenId) -> Vec<DiscoveredPath>;
+}
+
+/// Cycles swapping the source token into a neighbor and back through another pool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TwoHopTemplate;
+
+impl PathTemplate for TwoHopTemplate {
+    fn generate(&self, graph: &TradingGraph, source_token: TokenId) -> Vec<DiscoveredPath> {
+        sorted_neighbors(graph, source_token)
+            .into_iter()
+            .flat_map(|neighbor| pool_paths_through(graph, &[source_token, neighbor, source_token]))
+            .collect()
+    }
+}
+
+/// Triangles from the source token through one of the `via` tokens.
+///
+/// For every `via` token connected to the source token, generates the cycles
+/// source → via → other → source and source → other → via → source through
+/// every other token connected to both.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TriangularTemplate {
+    /// Addresses of the major tokens every triangle trades through
+    pub via: Vec<Bytes>,
+}
+
+impl TriangularTemplate {
+    /// Create a template of triangles through the given tokens.
+    pub fn new(via: Vec<Bytes>) -> Self {
+        Self { via }
+    }
+}
+
+impl PathTemplate for TriangularTemplate {
+    fn generate(&self, graph: &TradingGraph, source_token: TokenId) -> Vec<DiscoveredPath> {
+        let Ok(source_neighbors) = graph.token_neighbors(source_token) else {
+            return Vec::new();
+        };
+
+        let mut token_cycles = HashSet::new();
+        let mut paths = Vec::new();
+        for via_address in &self.via {
+            let Ok(via_token) = graph.find_token_id(via_address) else {
+                continue;
+            };
+            if via_token == source_token || !source_neighbors.contains(&via_token) {
+                continue;
+            }
+
+            for other_token in sorted_neighbors(graph, via_token) {
+                if other_token == source_token || !source_neighbors.contains(&other_token) {
+                    continue;
+                }
+                for token_cycle in [
+                    [source_token, via_token, other_token, source_token],
+                    [source_token, other_token, via_token, source_token],
+                ] {
+                    if token_cycles.insert(token_cycle) {
+                        paths.extend(pool_paths_through(graph, &token_cycle));
+                    }
+                }
+            }
+        }
+        paths
+    }
+}
+
+/// The neighbors of a token in ascending order, so generated paths are deterministic.
+fn sorted_neighbors(graph: &TradingGraph, token: TokenId) -> Vec<TokenId> {
+    let mut neighbors: Vec<TokenId> = graph
+        .token_neighbors(token)
+        .map(|neighbors| neighbors.iter().copied().collect())
+        .unwrap_or_default();
+    neighbors.sort_unstable();
+    neighbors
+}
+
+/// All paths along a token cycle whose pools have distinct addresses.
+fn pool_paths_through(graph: &TradingGraph, tokens: &[TokenId]) -> Vec<DiscoveredPath> {
+    let mut pool_paths: Vec<Vec<PoolId>> = vec![Vec::new()];
+    for pair in tokens.windows(2) {
+        let pools = graph.pools_from_to(pair[0], pair[1]);
+        if pools.is_empty() {
+            return Vec::new();
+        }
+        let mut extended_paths = Vec::with_capacity(pool_paths.len() * pools.len());
+        for pool_path in &pool_paths {
+            for &pool in pools.iter().filter(|&&pool| !uses_pool_address(graph, pool_path, pool)) {
+                let mut extended = pool_path.clone();
+                extended.push(pool);
+                extended_paths.push(extended);
+            }
+        }
+        pool_paths = extended_paths;
+    }
+
+    pool_paths
+        .into_iter()
+        .map(|pools| DiscoveredPath {
+            pools,
+            tokens: tokens.to_vec(),
+        })
+        .collect()
+}
+
+/// Whether a pool path already trades through the address of a pool.
+fn uses_pool_address(graph: &TradingGraph, pool_path: &[PoolId], pool: PoolId) -> bool {
+    let Ok(address) = graph.get_pool(pool).map(|pool| pool.address()) else {
+        return true;
+    };
+    pool_path
+        .iter()
+        .any(|&used| graph.get_pool(used).is_ok_and(|used| used.address() == address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn graph() -> TradingGraph {
+        // Source 0, majors 1 and 2, and token 3 only connected to the source
+        let mut g = TradingGraph::new();
+        for token in ["0x0000", "0x0001", "0x0002", "0x0003"] {
+            g.add_token(Bytes::from_str(token).unwrap()).unwrap();
+        }
+        g.add_pool(Bytes::from_str("0x1000").unwrap(), [0, 1]).unwrap();
+        g.add_pool(Bytes::from_str("0x1001").unwrap(), [0, 1]).unwrap();
+        g.add_pool(Bytes::from_str("0x1002").unwrap(), [1, 2]).unwrap();
+        g.add_pool(Bytes::from_str("0x1003").unwrap(), [0, 2]).unwrap();
+        g.add_pool(Bytes::from_str("0x1004").unwrap(), [0, 3]).unwrap();
+        g
+    }
+
+    #[test]
+    fn test_two_hop_template_uses_distinct_pools() {
+        let g = graph();
+        let paths = TwoHopTemplate.generate(&g, 0);
+
+        // Only the two 0-1 pools form a round trip
+        assert_eq!(paths.len(), 2);
+        for path in &paths {
+            assert_eq!(path.tokens, vec![0, 1, 0]);
+            let pools = path.pool_addresses(&g).unwrap();
+            assert_ne!(pools[0], pools[1]);
+        }
+    }
+
+    #[test]
+    fn test_triangular_template_trades_through_via_tokens() {
+        let g = graph();
+        let via = TriangularTemplate::new(vec![Bytes::from_str("0x0001").unwrap(), Bytes::from_str("0xffff").unwrap()]);
+        let paths = via.generate(&g, 0);
+
+        // Two 0-1 pools, in both directions around the triangle
+        assert_eq!(paths.len(), 4);
+        assert!(paths.iter().all(|path| path.len() == 3 && path.tokens.contains(&1)));
+        assert!(paths.iter().any(|path| path.tokens == vec![0, 2, 1, 0]));
+        assert!(TriangularTemplate::new(vec![Bytes::from_str("0x0003").unwrap()]).generate(&g, 0).is_empty());
+    }
+}
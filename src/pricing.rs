@@ -0,0 +1,82 @@
+//! Token prices in a reference currency.
+//!
+//! Profits are measured in base units of the path's start token, so the same
+//! threshold in basis points means very different amounts for a WETH and a
+//! USDC cycle. A `PriceOracle` prices tokens in USD, letting profits, gas and
+//! bribes be compared in one currency. The oracle is pluggable: the bundled
+//! [`StaticPriceOracle`] serves prices set by the caller, e.g. from an API
+//! polled once per block. [`StaticPriceOracle::with_stablecoins`] prices the
+//! well-known stablecoins of a chain at one dollar.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::pricing::StaticPriceOracle;
+//!
+//! let oracle = StaticPriceOracle::new()
+//!     .with_price(weth, 3_000.0)
+//!     .with_price(usdc, 1.0);
+//!
+//! let breakdown = ProfitCalculator::calculate_profit_breakdown(&path_ext, &gas_price, &bribe, &weth, &oracle)?;
+//! ```
+
+use crate::chain::ChainRegistry;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tycho_common::Bytes;
+
+/// Source of token prices in USD.
+pub trait PriceOracle: Send + Sync {
+    /// The USD price of one whole token, e.g. of 10^18 base units of WETH.
+    ///
+    /// Returns `None` if the token has no known price.
+    fn usd_price(&self, token: &Bytes) -> Option<f64>;
+}
+
+/// A price oracle serving prices set by the caller.
+#[derive(Debug, Default)]
+pub struct StaticPriceOracle {
+    prices: RwLock<HashMap<Bytes, f64>>,
+}
+
+impl StaticPriceOracle {
+    /// Create an oracle without prices.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the USD price of a token.
+    pub fn with_price(self, token: Bytes, usd_price: f64) -> Self {
+        self.set_price(token, usd_price);
+        self
+    }
+
+    /// Price the well-known stablecoins of a chain at one dollar, e.g. USDC and DAI.
+    ///
+    /// Unknown chains add no prices.
+    pub fn with_stablecoins(self, chain: &str) -> Self {
+        let stablecoins = ChainRegistry::get(chain).map(|spec| spec.stablecoins()).unwrap_or_default();
+        for stablecoin in stablecoins {
+            self.set_price(Bytes::from(stablecoin.to_vec()), 1.0);
+        }
+        self
+    }
+
+    /// Set the USD price of a token, replacing any previous price.
+    ///
+    /// Prices that are not finite and positive are ignored.
+    pub fn set_price(&self, token: Bytes, usd_price: f64) {
+        if !usd_price.is_finite() || usd_price <= 0.0 {
+            return;
+        }
+        if let Ok(mut prices) = self.prices.write() {
+            prices.insert(token, usd_price);
+        }
+    }
+}
+
+impl PriceOracle for StaticPriceOracle {
+    fn usd_price(&self, token: &Bytes) -> Option<f64> {
+        self.prices.read().ok()?.get(token).copied()
+    }
+}
@@ -0,0 +1,368 @@
+//! Operator-defined risk limits and the emergency stop.
+//!
+//! A misconfigured threshold or a market the local simulations get wrong can
+//! drain an executor within a few blocks. The `RiskManager` is consulted before
+//! every submission and blocks it when
+//!
+//! - the emergency stop was triggered, or its kill switch file exists
+//! - a trade's notional or the gas price exceed their per-trade limits
+//! - the realized loss of the current UTC day exceeds the daily loss limit
+//! - too many consecutive bundles failed to land
+//!
+//! Per-trade limits only block the offending submission. The daily loss limit
+//! halts submissions until the next UTC day, the consecutive failure limit until
+//! an operator calls [`RiskManager::resume`]. Callers are expected to keep
+//! searching and build bundles in dry-run mode while submissions are blocked.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use num_bigint::BigUint;
+//! use tycho_atomic_arbitrage::risk::{RiskLimits, RiskManager};
+//!
+//! let risk = RiskManager::new(RiskLimits::new().with_max_consecutive_failures(2));
+//! assert!(risk.check_submission(&[BigUint::from(1u32)], 10_000_000_000).is_ok());
+//!
+//! risk.record_bundle_result(false);
+//! risk.record_bundle_result(false);
+//! assert!(risk.halted().is_some());
+//! ```
+
+use crate::errors::RiskError;
+use num_bigint::{BigInt, BigUint};
+use num_traits::{Signed, Zero};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds in a UTC day.
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Limits enforced before bundle submission; `None` disables a limit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RiskLimits {
+    /// Maximum input of a single trade, in native token base units
+    pub max_notional_per_trade: Option<BigUint>,
+    /// Maximum gas price at which bundles are submitted, in wei
+    pub max_gas_price: Option<u128>,
+    /// Maximum realized loss per UTC day, in native token base units
+    pub max_daily_loss: Option<BigUint>,
+    /// Number of consecutive failed bundles after which submissions halt
+    pub max_consecutive_failures: Option<u32>,
+}
+
+impl RiskLimits {
+    /// Create limits with every limit disabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum input of a single trade, in native token base units.
+    pub fn with_max_notional_per_trade(mut self, max_notional: BigUint) -> Self {
+        self.max_notional_per_trade = Some(max_notional);
+        self
+    }
+
+    /// Set the maximum gas price at which bundles are submitted, in wei.
+    pub fn with_max_gas_price(mut self, max_gas_price: u128) -> Self {
+        self.max_gas_price = Some(max_gas_price);
+        self
+    }
+
+    /// Set the maximum realized loss per UTC day, in native token base units.
+    pub fn with_max_daily_loss(mut self, max_daily_loss: BigUint) -> Self {
+        self.max_daily_loss = Some(max_daily_loss);
+        self
+    }
+
+    /// Set the number of consecutive failed bundles after which submissions halt.
+    pub fn with_max_consecutive_failures(mut self, max_failures: u32) -> Self {
+        self.max_consecutive_failures = Some(max_failures.max(1));
+        self
+    }
+}
+
+/// Realized results since the manager was created.
+#[derive(Debug, Default)]
+struct RiskState {
+    /// UTC day, in days since the Unix epoch, `daily_pnl` accumulates for
+    day: u64,
+    daily_pnl: BigInt,
+    consecutive_failures: u32,
+    /// A breach that blocks submissions until it is lifted
+    halt: Option<RiskError>,
+}
+
+/// Enforces risk limits and the emergency stop before bundle submission.
+#[derive(Debug)]
+pub struct RiskManager {
+    limits: RiskLimits,
+    emergency_stop: AtomicBool,
+    kill_switch_file: Option<PathBuf>,
+    state: Mutex<RiskState>,
+}
+
+impl RiskManager {
+    /// Create a manager enforcing `limits`.
+    pub fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits,
+            emergency_stop: AtomicBool::new(false),
+            kill_switch_file: None,
+            state: Mutex::new(RiskState {
+                day: current_day(),
+                ..RiskState::default()
+            }),
+        }
+    }
+
+    /// Treat the existence of `path` as an active emergency stop.
+    ///
+    /// Operators stop submissions of a running bot by creating the file and
+    /// resume them by deleting it.
+    pub fn with_kill_switch_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.kill_switch_file = Some(path.into());
+        self
+    }
+
+    /// The enforced limits.
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// Block all submissions until [`RiskManager::clear_emergency_stop`] is called.
+    pub fn trigger_emergency_stop(&self) {
+        if !self.emergency_stop.swap(true, Ordering::SeqCst) {
+            tracing::warn!("Emergency stop triggered, bundle submission halted");
+        }
+    }
+
+    /// Lift an emergency stop triggered with [`RiskManager::trigger_emergency_stop`].
+    pub fn clear_emergency_stop(&self) {
+        if self.emergency_stop.swap(false, Ordering::SeqCst) {
+            tracing::info!("Emergency stop cleared");
+        }
+    }
+
+    /// Whether the emergency stop is triggered or the kill switch file exists.
+    pub fn is_emergency_stopped(&self) -> bool {
+        self.emergency_stop.load(Ordering::SeqCst)
+            || self.kill_switch_file.as_ref().is_some_and(|path| path.exists())
+    }
+
+    /// Check whether a portfolio may be submitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `notionals` - The input of every trade, in native token base units
+    /// * `gas_price` - The gas price the bundles pay, in wei
+    ///
+    /// # Errors
+    ///
+    /// Returns the first breached limit: the emergency stop, an active halt, the
+    /// gas price limit or the notional limit, in that order
+    pub fn check_submission(&self, notionals: &[BigUint], gas_price: u128) -> Result<(), RiskError> {
+        if self.is_emergency_stopped() {
+            return Err(RiskError::EmergencyStop);
+        }
+        if let Some(halt) = self.halted() {
+            return Err(halt);
+        }
+
+        if let Some(limit) = self.limits.max_gas_price {
+            if gas_price > limit {
+                return Err(RiskError::GasPriceLimitExceeded { gas_price, limit });
+            }
+        }
+
+        if let Some(limit) = &self.limits.max_notional_per_trade {
+            if let Some(notional) = notionals.iter().find(|notional| *notional > limit) {
+                return Err(RiskError::NotionalLimitExceeded {
+                    notional: notional.to_string(),
+                    limit: limit.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The breach that currently halts all submissions, if any.
+    pub fn halted(&self) -> Option<RiskError> {
+        if self.is_emergency_stopped() {
+            return Some(RiskError::EmergencyStop);
+        }
+        let mut state = self.lock_state();
+        roll_day(&mut state, current_day());
+        state.halt.clone()
+    }
+
+    /// Record the realized profit or loss of an executed trade.
+    ///
+    /// Halts submissions for the rest of the UTC day once the day's loss
+    /// exceeds the daily loss limit.
+    pub fn record_pnl(&self, pnl: &BigInt) {
+        self.record_pnl_on_day(pnl, current_day());
+    }
+
+    /// Record whether a submitted bundle landed.
+    ///
+    /// Halts submissions once the consecutive failure limit is reached; an
+    /// included bundle resets the count.
+    pub fn record_bundle_result(&self, included: bool) {
+        let mut state = self.lock_state();
+        if included {
+            state.consecutive_failures = 0;
+            return;
+        }
+
+        state.consecutive_failures += 1;
+        if let Some(limit) = self.limits.max_consecutive_failures {
+            if state.consecutive_failures >= limit && state.halt.is_none() {
+                let breach = RiskError::ConsecutiveFailuresExceeded {
+                    failures: state.consecutive_failures,
+                    limit,
+                };
+                tracing::warn!(breach = %breach, "Risk limit breached, bundle submission halted");
+                state.halt = Some(breach);
+            }
+        }
+    }
+
+    /// Lift a halt and reset the consecutive failure count.
+    ///
+    /// The daily loss is kept, so a halt caused by it is raised again by the
+    /// next recorded loss of the same day.
+    pub fn resume(&self) {
+        let mut state = self.lock_state();
+        state.halt = None;
+        state.consecutive_failures = 0;
+        tracing::info!("Risk halt lifted, bundle submission resumed");
+    }
+
+    /// The realized profit or loss of the current UTC day.
+    pub fn daily_pnl(&self) -> BigInt {
+        let mut state = self.lock_state();
+        roll_day(&mut state, current_day());
+        state.daily_pnl.clone()
+    }
+
+    /// Number of consecutive bundles that failed to land.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.lock_state().consecutive_failures
+    }
+
+    fn record_pnl_on_day(&self, pnl: &BigInt, day: u64) {
+        let mut state = self.lock_state();
+        roll_day(&mut state, day);
+        state.daily_pnl += pnl;
+
+        let Some(limit) = &self.limits.max_daily_loss else {
+            return;
+        };
+        let loss = if state.daily_pnl.is_negative() {
+            state.daily_pnl.magnitude().clone()
+        } else {
+            BigUint::zero()
+        };
+        if &loss > limit && state.halt.is_none() {
+            let breach = RiskError::DailyLossLimitExceeded {
+                loss: loss.to_string(),
+                limit: limit.to_string(),
+            };
+            tracing::warn!(breach = %breach, "Risk limit breached, bundle submission halted");
+            state.halt = Some(breach);
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, RiskState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Start a new day's profit and loss, lifting a halt caused by the previous day's loss.
+fn roll_day(state: &mut RiskState, day: u64) {
+    if day <= state.day {
+        return;
+    }
+    state.day = day;
+    state.daily_pnl = BigInt::zero();
+    if matches!(state.halt, Some(RiskError::DailyLossLimitExceeded { .. })) {
+        state.halt = None;
+    }
+}
+
+fn current_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_trade_limits_and_emergency_stop() {
+        let risk = RiskManager::new(
+            RiskLimits::new()
+                .with_max_notional_per_trade(BigUint::from(1000u32))
+                .with_max_gas_price(50),
+        );
+
+        assert!(risk.check_submission(&[BigUint::from(1000u32)], 50).is_ok());
+        assert_eq!(
+            risk.check_submission(&[BigUint::from(10u32), BigUint::from(1001u32)], 50),
+            Err(RiskError::NotionalLimitExceeded { notional: "1001".to_string(), limit: "1000".to_string() })
+        );
+        assert_eq!(
+            risk.check_submission(&[], 51),
+            Err(RiskError::GasPriceLimitExceeded { gas_price: 51, limit: 50 })
+        );
+        // Per-trade breaches do not halt later submissions
+        assert!(risk.halted().is_none());
+
+        risk.trigger_emergency_stop();
+        assert_eq!(risk.check_submission(&[], 1), Err(RiskError::EmergencyStop));
+        risk.clear_emergency_stop();
+        assert!(risk.check_submission(&[], 1).is_ok());
+    }
+
+    #[test]
+    fn test_daily_loss_and_failures_halt_submission() {
+        let risk = RiskManager::new(
+            RiskLimits::new()
+                .with_max_daily_loss(BigUint::from(100u32))
+                .with_max_consecutive_failures(3),
+        );
+        let day = current_day();
+
+        risk.record_pnl_on_day(&BigInt::from(-60), day);
+        risk.record_pnl_on_day(&BigInt::from(20), day);
+        risk.record_pnl_on_day(&BigInt::from(-61), day);
+        assert!(matches!(risk.halted(), Some(RiskError::DailyLossLimitExceeded { .. })));
+
+        // A new day starts with a clean slate
+        risk.record_pnl_on_day(&BigInt::from(-1), day + 1);
+        assert_eq!(risk.lock_state().halt, None);
+        assert_eq!(risk.lock_state().daily_pnl, BigInt::from(-1));
+
+        risk.record_bundle_result(false);
+        risk.record_bundle_result(false);
+        risk.record_bundle_result(true);
+        risk.record_bundle_result(false);
+        risk.record_bundle_result(false);
+        assert!(risk.lock_state().halt.is_none());
+        risk.record_bundle_result(false);
+        assert_eq!(
+            risk.lock_state().halt,
+            Some(RiskError::ConsecutiveFailuresExceeded { failures: 3, limit: 3 })
+        );
+
+        risk.resume();
+        assert_eq!(risk.consecutive_failures(), 0);
+        assert!(risk.lock_state().halt.is_none());
+    }
+}
@@ -0,0 +1,214 @@
+//! Rate limiting and concurrency caps of RPC requests.
+//!
+//! Rate-limited providers, e.g. free tiers allowing a few dozen requests per
+//! second, reject requests above their limit, so a block with many
+//! simulations fails halfway through. A `RateLimiter` makes callers wait for
+//! capacity instead:
+//!
+//! - **Rate**: a token bucket refilling at the configured requests per second,
+//!   holding at most the burst size
+//! - **Concurrency**: at most the configured number of requests in flight
+//!
+//! One limiter shared by every RPC caller, e.g. simulations, balance checks and
+//! gas queries, enforces a global limit; a [`ProviderPool`](crate::rpc::ProviderPool)
+//! additionally limits each endpoint with its own limiter.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::rpc::{RateLimit, RateLimiter};
+//!
+//! let limiter = Arc::new(RateLimiter::new(RateLimit::per_second(25.0).with_max_concurrent(8)));
+//! let _permit = limiter.acquire().await;
+//! let balance = provider.get_balance(address).await?;
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// The lowest accepted rate, one request every 1000 seconds.
+const MIN_REQUESTS_PER_SECOND: f64 = 0.001;
+
+/// The rate and concurrency allowed by an RPC provider.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    requests_per_second: f64,
+    burst: u32,
+    max_concurrent: Option<usize>,
+}
+
+impl RateLimit {
+    /// Allow `requests_per_second` on average, with bursts of up to one second of requests.
+    pub fn per_second(requests_per_second: f64) -> Self {
+        let requests_per_second = requests_per_second.max(MIN_REQUESTS_PER_SECOND);
+        Self {
+            requests_per_second,
+            burst: (requests_per_second.ceil() as u32).max(1),
+            max_concurrent: None,
+        }
+    }
+
+    /// Allow bursts of up to `burst` requests, at least 1.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// Allow at most `max_concurrent` requests in flight, at least 1.
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = Some(max_concurrent.max(1));
+        self
+    }
+
+    /// The average requests per second.
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+
+    /// The largest number of requests sent at once after an idle period.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// The largest number of requests in flight, if capped.
+    pub fn max_concurrent(&self) -> Option<usize> {
+        self.max_concurrent
+    }
+}
+
+/// Tokens of the bucket as of the last refill.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Permission to send one request, holding a concurrency slot until dropped.
+#[derive(Debug)]
+pub struct RatePermit {
+    _slot: Option<OwnedSemaphorePermit>,
+}
+
+/// A token bucket with an optional concurrency cap, shared by RPC callers.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    bucket: Mutex<Bucket>,
+    slots: Option<Arc<Semaphore>>,
+    throttled: AtomicU64,
+}
+
+impl RateLimiter {
+    /// Create a limiter with a full bucket.
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(limit.burst),
+                refilled_at: Instant::now(),
+            }),
+            slots: limit.max_concurrent.map(|max_concurrent| Arc::new(Semaphore::new(max_concurrent))),
+            throttled: AtomicU64::new(0),
+            limit,
+        }
+    }
+
+    /// The limit enforced by the limiter.
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    /// The number of requests that had to wait for capacity.
+    pub fn throttled(&self) -> u64 {
+        self.throttled.load(Ordering::Relaxed)
+    }
+
+    /// Wait until a request may be sent.
+    ///
+    /// Waits for a concurrency slot first, then for a token of the bucket.
+    pub async fn acquire(&self) -> RatePermit {
+        let slot = match &self.slots {
+            Some(slots) => {
+                if slots.available_permits() == 0 {
+                    self.throttled.fetch_add(1, Ordering::Relaxed);
+                }
+                // The semaphore is never closed
+                Arc::clone(slots).acquire_owned().await.ok()
+            }
+            None => None,
+        };
+
+        let mut waited = false;
+        while let Err(wait) = self.take_token() {
+            if !waited {
+                waited = true;
+                self.throttled.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!(
+                    wait_ms = wait.as_millis() as u64,
+                    requests_per_second = self.limit.requests_per_second,
+                    "RPC rate limit reached, waiting for capacity"
+                );
+            }
+            tokio::time::sleep(wait).await;
+        }
+        RatePermit { _slot: slot }
+    }
+
+    /// Take a permit if a request may be sent right away.
+    pub fn try_acquire(&self) -> Option<RatePermit> {
+        let slot = match &self.slots {
+            Some(slots) => Some(Arc::clone(slots).try_acquire_owned().ok()?),
+            None => None,
+        };
+        self.take_token().ok()?;
+        Some(RatePermit { _slot: slot })
+    }
+
+    /// Take a token of the bucket, or return the time until one is available.
+    fn take_token(&self) -> std::result::Result<(), Duration> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.refilled_at).as_secs_f64() * self.limit.requests_per_second;
+        bucket.tokens = (bucket.tokens + refill).min(f64::from(self.limit.burst));
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.limit.requests_per_second))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_waits_for_tokens_after_a_burst() {
+        let limiter = RateLimiter::new(RateLimit::per_second(100.0).with_burst(2));
+        let start = Instant::now();
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(limiter.throttled(), 0);
+        assert!(limiter.try_acquire().is_none());
+
+        // The third request waits for a token, refilled every 10ms
+        limiter.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(5));
+        assert_eq!(limiter.throttled(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_caps_requests_in_flight() {
+        let limiter = RateLimiter::new(RateLimit::per_second(1000.0).with_max_concurrent(1));
+        let permit = limiter.acquire().await;
+        assert!(limiter.try_acquire().is_none());
+
+        drop(permit);
+        assert!(limiter.try_acquire().is_some());
+        assert_eq!(RateLimit::per_second(0.0).burst(), 1);
+    }
+}
@@ -0,0 +1,379 @@
+//! Pools of RPC endpoints with failover and latency racing.
+//!
+//! A single RPC endpoint is a single point of failure: when it rate limits,
+//! lags or goes down, no path can be simulated. A `ProviderPool` holds several
+//! endpoints and routes every request through them:
+//!
+//! - **Failover**: the request is sent to the primary endpoint, and to the
+//!   next endpoint whenever one fails
+//! - **Racing**: the request is sent to several endpoints at once and the
+//!   first success is taken, trading RPC usage for latency
+//!
+//! Every endpoint keeps health metrics. Endpoints failing repeatedly are
+//! tried after the healthy ones until they succeed again. Requests wait for
+//! the [`RateLimiter`] of their endpoint and the one shared by the pool, if
+//! set, instead of exceeding the limits of rate-limited providers.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::rpc::ProviderPool;
+//!
+//! let pool = ProviderPool::from_urls(&[primary_url, fallback_url])?.with_racing(2);
+//! let block_number = pool.call(|provider| async move { Ok(provider.get_block_number().await?) }).await?;
+//! ```
+
+pub mod limit;
+
+pub use limit::{RateLimit, RateLimiter, RatePermit};
+
+use crate::errors::{Result, UtilityError};
+use alloy::{network::Ethereum, providers::RootProvider};
+use futures::future::{select_ok, BoxFuture, FutureExt};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Default number of consecutive failures after which an endpoint is unhealthy.
+const DEFAULT_MAX_CONSECUTIVE_FAILURES: u64 = 3;
+
+/// Weight of the latest request in the moving average latency, in percent.
+const LATENCY_SMOOTHING_PERCENT: u64 = 20;
+
+/// Health metrics of one endpoint.
+#[derive(Debug, Default)]
+pub struct EndpointHealth {
+    successes: AtomicU64,
+    failures: AtomicU64,
+    consecutive_failures: AtomicU64,
+    average_latency_us: AtomicU64,
+}
+
+impl EndpointHealth {
+    /// Record a successful request and its latency.
+    fn record_success(&self, latency_us: u64) {
+        self.successes.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        let average = self.average_latency_us.load(Ordering::Relaxed);
+        let updated = if average == 0 {
+            latency_us
+        } else {
+            (average * (100 - LATENCY_SMOOTHING_PERCENT) + latency_us * LATENCY_SMOOTHING_PERCENT) / 100
+        };
+        self.average_latency_us.store(updated, Ordering::Relaxed);
+    }
+
+    /// Record a failed request.
+    fn record_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The number of successful requests.
+    pub fn successes(&self) -> u64 {
+        self.successes.load(Ordering::Relaxed)
+    }
+
+    /// The number of failed requests.
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// The number of failed requests since the last success.
+    pub fn consecutive_failures(&self) -> u64 {
+        self.consecutive_failures.load(Ordering::Relaxed)
+    }
+
+    /// The moving average latency of successful requests in microseconds, 0 before the first.
+    pub fn average_latency_us(&self) -> u64 {
+        self.average_latency_us.load(Ordering::Relaxed)
+    }
+}
+
+/// An RPC endpoint of a pool.
+#[derive(Debug)]
+pub struct Endpoint {
+    url: String,
+    provider: Arc<RootProvider<Ethereum>>,
+    health: EndpointHealth,
+    limiter: Option<RateLimiter>,
+}
+
+impl Endpoint {
+    /// The URL of the endpoint.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The provider sending requests to the endpoint.
+    pub fn provider(&self) -> &Arc<RootProvider<Ethereum>> {
+        &self.provider
+    }
+
+    /// The health metrics of the endpoint.
+    pub fn health(&self) -> &EndpointHealth {
+        &self.health
+    }
+
+    /// The rate limiter of the endpoint, if limited.
+    pub fn limiter(&self) -> Option<&RateLimiter> {
+        self.limiter.as_ref()
+    }
+}
+
+/// RPC endpoints in order of preference, with failover and optional racing.
+#[derive(Debug)]
+pub struct ProviderPool {
+    endpoints: Vec<Endpoint>,
+    race_width: usize,
+    max_consecutive_failures: u64,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl ProviderPool {
+    /// Create a pool of endpoints, the first one being the primary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no endpoint is given or a URL is invalid.
+    pub fn from_urls<S: AsRef<str>>(urls: &[S]) -> Result<Self> {
+        let providers = urls
+            .iter()
+            .map(|url| {
+                let url = url.as_ref();
+                let parsed = url.parse::<reqwest::Url>().map_err(|e| UtilityError::InvalidRpcUrl {
+                    url: url.to_string(),
+                    reason: e.to_string(),
+                })?;
+                Ok((url.to_string(), Arc::new(RootProvider::new_http(parsed))))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(providers)
+    }
+
+    /// Create a pool of existing providers, the first one being the primary.
+    ///
+    /// # Arguments
+    ///
+    /// * `providers` - The URL of every endpoint, for metrics, and its provider
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no provider is given.
+    pub fn new(providers: Vec<(String, Arc<RootProvider<Ethereum>>)>) -> Result<Self> {
+        if providers.is_empty() {
+            return Err(UtilityError::NoRpcEndpoints.into());
+        }
+        Ok(Self {
+            endpoints: providers
+                .into_iter()
+                .map(|(url, provider)| Endpoint {
+                    url,
+                    provider,
+                    health: EndpointHealth::default(),
+                    limiter: None,
+                })
+                .collect(),
+            race_width: 1,
+            max_consecutive_failures: DEFAULT_MAX_CONSECUTIVE_FAILURES,
+            rate_limiter: None,
+        })
+    }
+
+    /// Send every request to `race_width` endpoints at once and take the first success.
+    ///
+    /// A width of 1, the default, only fails over.
+    pub fn with_racing(mut self, race_width: usize) -> Self {
+        self.race_width = race_width.max(1);
+        self
+    }
+
+    /// Set the consecutive failures after which an endpoint is tried after healthy ones.
+    pub fn with_max_consecutive_failures(mut self, max_consecutive_failures: u64) -> Self {
+        self.max_consecutive_failures = max_consecutive_failures.max(1);
+        self
+    }
+
+    /// Make every request wait for a limiter shared with other RPC callers.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Limit the requests to the endpoint with the given URL.
+    ///
+    /// A URL that matches no endpoint of the pool is ignored.
+    pub fn with_endpoint_limit(mut self, url: &str, limit: RateLimit) -> Self {
+        match self.endpoints.iter_mut().find(|endpoint| endpoint.url == url) {
+            Some(endpoint) => endpoint.limiter = Some(RateLimiter::new(limit)),
+            None => tracing::warn!(url = url, "No endpoint of the provider pool has the URL, ignoring its rate limit"),
+        }
+        self
+    }
+
+    /// The limiter shared with other RPC callers, if set.
+    pub fn rate_limiter(&self) -> Option<&Arc<RateLimiter>> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// The endpoints of the pool in order of preference.
+    pub fn endpoints(&self) -> &[Endpoint] {
+        &self.endpoints
+    }
+
+    /// The primary provider of the pool.
+    pub fn primary(&self) -> &Arc<RootProvider<Ethereum>> {
+        &self.endpoints[0].provider
+    }
+
+    /// Whether an endpoint failed too often in a row.
+    fn is_healthy(&self, endpoint: &Endpoint) -> bool {
+        endpoint.health.consecutive_failures() < self.max_consecutive_failures
+    }
+
+    /// The endpoints in the order they are tried: healthy ones first, each group in preference order.
+    fn ordered_endpoints(&self) -> Vec<&Endpoint> {
+        let (mut healthy, unhealthy): (Vec<&Endpoint>, Vec<&Endpoint>) =
+            self.endpoints.iter().partition(|endpoint| self.is_healthy(endpoint));
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    /// Send a request through the pool.
+    ///
+    /// The request is raced on the first endpoints in order and fails over
+    /// to the remaining ones until it succeeds.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Sends the request to a provider
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the last endpoint if the request failed on all endpoints.
+    pub async fn call<'a, T, F, Fut>(&'a self, request: F) -> Result<T>
+    where
+        F: Fn(Arc<RootProvider<Ethereum>>) -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'a,
+        T: Send + 'a,
+    {
+        let endpoints = self.ordered_endpoints();
+        let mut last_error = None;
+        for batch in endpoints.chunks(self.race_width) {
+            let attempts: Vec<BoxFuture<'a, Result<T>>> = batch
+                .iter()
+                .map(|&endpoint| self.timed(endpoint, request(Arc::clone(&endpoint.provider))))
+                .collect();
+
+            match select_ok(attempts).await {
+                Ok((value, _)) => return Ok(value),
+                Err(e) => {
+                    tracing::warn!(
+                        endpoints = ?batch.iter().map(|endpoint| endpoint.url.as_str()).collect::<Vec<_>>(),
+                        error = %e,
+                        "RPC request failed, failing over"
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| UtilityError::NoRpcEndpoints.into()))
+    }
+
+    /// Wait for the rate limits, then record the latency and outcome of a request to an endpoint.
+    fn timed<'a, T, Fut>(&'a self, endpoint: &'a Endpoint, request: Fut) -> BoxFuture<'a, Result<T>>
+    where
+        Fut: Future<Output = Result<T>> + Send + 'a,
+        T: Send + 'a,
+    {
+        async move {
+            let _endpoint_permit = match &endpoint.limiter {
+                Some(limiter) => Some(limiter.acquire().await),
+                None => None,
+            };
+            let _pool_permit = match &self.rate_limiter {
+                Some(limiter) => Some(limiter.acquire().await),
+                None => None,
+            };
+            let start = Instant::now();
+            let result = request.await;
+            match &result {
+                Ok(_) => endpoint.health.record_success(start.elapsed().as_micros() as u64),
+                Err(_) => endpoint.health.record_failure(),
+            }
+            result
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ArbitrageError;
+
+    fn pool() -> ProviderPool {
+        ProviderPool::from_urls(&["http://127.0.0.1:1", "http://127.0.0.1:2", "http://127.0.0.1:3"]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_to_the_next_endpoint() {
+        let pool = pool().with_max_consecutive_failures(1);
+        let primary = Arc::clone(pool.primary());
+        let request = |provider: Arc<RootProvider<Ethereum>>| {
+            let fails = Arc::ptr_eq(&provider, &primary);
+            async move {
+                if fails {
+                    Err(ArbitrageError::from(UtilityError::NoRpcEndpoints))
+                } else {
+                    Ok(1u64)
+                }
+            }
+        };
+
+        assert_eq!(pool.call(request).await.unwrap(), 1);
+        assert_eq!(pool.endpoints()[0].health().failures(), 1);
+        assert_eq!(pool.endpoints()[1].health().successes(), 1);
+
+        // The failed primary is now tried last
+        assert_eq!(pool.call(request).await.unwrap(), 1);
+        assert_eq!(pool.endpoints()[0].health().failures(), 1);
+        assert!(ProviderPool::from_urls::<&str>(&[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_racing_takes_the_first_success() {
+        let pool = pool().with_racing(2);
+        let primary = Arc::clone(pool.primary());
+        let request = |provider: Arc<RootProvider<Ethereum>>| {
+            let slow = Arc::ptr_eq(&provider, &primary);
+            async move {
+                if slow {
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                }
+                Ok::<_, ArbitrageError>(slow)
+            }
+        };
+
+        assert!(!pool.call(request).await.unwrap());
+        assert_eq!(pool.endpoints()[1].health().successes(), 1);
+        assert_eq!(pool.endpoints()[2].health().successes(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_requests_wait_for_the_shared_limiter() {
+        let limiter = Arc::new(RateLimiter::new(RateLimit::per_second(100.0).with_burst(1)));
+        let pool = pool()
+            .with_rate_limiter(Arc::clone(&limiter))
+            .with_endpoint_limit("http://127.0.0.1:1", RateLimit::per_second(1000.0));
+        assert!(pool.endpoints()[0].limiter().is_some());
+        assert!(pool.endpoints()[1].limiter().is_none());
+
+        let request = |_provider: Arc<RootProvider<Ethereum>>| async { Ok::<_, ArbitrageError>(()) };
+        pool.call(request).await.unwrap();
+        pool.call(request).await.unwrap();
+        // The second request waited for the shared bucket to refill
+        assert_eq!(limiter.throttled(), 1);
+    }
+}
@@ -0,0 +1,214 @@
+//! Executor signers backed by local keys, keystores or remote signing services.
+//!
+//! The executor key signs every transaction of a bundle and, with Permit2
+//! transfers, the permits encoded into router calls. `ExecutorSigner` abstracts
+//! over where that key lives:
+//!
+//! - **Local**: a raw private key or a decrypted geth-style JSON keystore. Local
+//!   signers sign synchronously, which permit signing inside the parallel
+//!   simulation pipeline requires.
+//! - **Remote**: any `alloy` transaction signer, e.g. AWS KMS (`aws-kms` feature)
+//!   or a Ledger device (`ledger` feature). Remote signers sign bundle
+//!   transactions asynchronously and cannot sign permits, so they must be used
+//!   with a transfer type other than Permit2.
+
+use crate::errors::{BundleError, Result};
+use alloy::{
+    consensus::SignableTransaction,
+    network::{TxSigner, TxSignerSync},
+    primitives::{Address, Signature},
+    signers::local::PrivateKeySigner,
+};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The signer used for executor transactions.
+#[derive(Clone)]
+pub enum ExecutorSigner {
+    /// A private key held in memory
+    Local(PrivateKeySigner),
+    /// A signer whose key never leaves an external service or device
+    Remote(Arc<dyn TxSigner<Signature> + Send + Sync>),
+}
+
+impl ExecutorSigner {
+    /// Wrap a remote transaction signer.
+    pub fn remote(signer: impl TxSigner<Signature> + Send + Sync + 'static) -> Self {
+        Self::Remote(Arc::new(signer))
+    }
+
+    /// Decrypt a JSON keystore file (geth / Web3 Secret Storage format).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The keystore file
+    /// * `password` - The keystore passphrase
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or the passphrase is wrong.
+    pub fn from_keystore(path: impl AsRef<Path>, password: &str) -> Result<Self> {
+        let path = path.as_ref();
+        let signer = PrivateKeySigner::decrypt_keystore(path, password).map_err(|e| {
+            BundleError::InvalidPrivateKey {
+                message: format!("Failed to decrypt keystore {}: {}", path.display(), e),
+            }
+        })?;
+
+        tracing::debug!(
+            keystore = %path.display(),
+            address = %signer.address(),
+            "Executor keystore decrypted"
+        );
+
+        Ok(Self::Local(signer))
+    }
+
+    /// Decrypt a JSON keystore file, prompting for the passphrase on the terminal.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the passphrase cannot be read or decryption fails.
+    pub fn from_keystore_prompt(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let password = rpassword::prompt_password(format!("Passphrase for {}: ", path.display()))
+            .map_err(|e| BundleError::InvalidConfiguration {
+                message: format!("Failed to read keystore passphrase: {}", e),
+            })?;
+
+        Self::from_keystore(path, &password)
+    }
+
+    /// Connect to an AWS KMS key.
+    ///
+    /// AWS credentials and region are taken from the default provider chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_id` - The KMS key ID or ARN
+    /// * `chain_id` - The chain ID used for EIP-155 replay protection
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the public key of the KMS key cannot be retrieved.
+    #[cfg(feature = "aws-kms")]
+    pub async fn aws_kms(key_id: String, chain_id: Option<u64>) -> Result<Self> {
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let client = aws_sdk_kms::Client::new(&config);
+        let signer = alloy::signers::aws::AwsSigner::new(client, key_id, chain_id)
+            .await
+            .map_err(|e| BundleError::InvalidConfiguration {
+                message: format!("Failed to connect to AWS KMS signer: {}", e),
+            })?;
+
+        Ok(Self::remote(signer))
+    }
+
+    /// Connect to a Ledger device using the Ledger Live derivation path.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The account index of the derivation path
+    /// * `chain_id` - The chain ID used for EIP-155 replay protection
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device is not connected or the Ethereum app is not open.
+    #[cfg(feature = "ledger")]
+    pub async fn ledger(index: usize, chain_id: Option<u64>) -> Result<Self> {
+        use alloy::signers::ledger::{HDPath, LedgerSigner};
+
+        let signer = LedgerSigner::new(HDPath::LedgerLive(index), chain_id)
+            .await
+            .map_err(|e| BundleError::InvalidConfiguration {
+                message: format!("Failed to connect to Ledger signer: {}", e),
+            })?;
+
+        Ok(Self::remote(signer))
+    }
+
+    /// The executor address.
+    pub fn address(&self) -> Address {
+        match self {
+            Self::Local(signer) => signer.address(),
+            Self::Remote(signer) => signer.address(),
+        }
+    }
+
+    /// The local private key signer, if the key is held in memory.
+    pub fn as_local(&self) -> Option<&PrivateKeySigner> {
+        match self {
+            Self::Local(signer) => Some(signer),
+            Self::Remote(_) => None,
+        }
+    }
+
+    /// Whether the key is held in memory and can sign synchronously.
+    pub fn is_local(&self) -> bool {
+        self.as_local().is_some()
+    }
+
+    /// Sign a transaction, waiting for the remote signer if necessary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if signing fails.
+    pub async fn sign_transaction(&self, tx: &mut dyn SignableTransaction<Signature>) -> Result<Signature> {
+        match self {
+            Self::Local(signer) => Ok(signer.sign_transaction_sync(tx)?),
+            Self::Remote(signer) => Ok(signer.sign_transaction(tx).await?),
+        }
+    }
+
+    /// Sign a transaction synchronously.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signer is remote or signing fails.
+    pub fn sign_transaction_sync(&self, tx: &mut dyn SignableTransaction<Signature>) -> Result<Signature> {
+        let signer = self.as_local().ok_or_else(|| BundleError::TransactionSigningFailed {
+            reason: "Remote signers cannot sign synchronously".to_string(),
+        })?;
+
+        Ok(signer.sign_transaction_sync(tx)?)
+    }
+}
+
+impl From<PrivateKeySigner> for ExecutorSigner {
+    fn from(signer: PrivateKeySigner) -> Self {
+        Self::Local(signer)
+    }
+}
+
+impl fmt::Debug for ExecutorSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(signer) => f.debug_tuple("Local").field(&signer.address()).finish(),
+            Self::Remote(signer) => f.debug_tuple("Remote").field(&signer.address()).finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_signer() {
+        let key = PrivateKeySigner::random();
+        let signer = ExecutorSigner::from(key.clone());
+
+        assert!(signer.is_local());
+        assert_eq!(signer.address(), key.address());
+        assert!(format!("{:?}", signer).starts_with("Local"));
+    }
+
+    #[test]
+    fn test_missing_keystore() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = ExecutorSigner::from_keystore(dir.path().join("missing.json"), "passphrase");
+
+        assert!(result.unwrap_err().to_string().contains("Failed to decrypt keystore"));
+    }
+}
@@ -0,0 +1,292 @@
+//! Accuracy tracking of local protocol simulations.
+//!
+//! Paths are priced with the local `ProtocolSim::get_amount_out` implementations,
+//! while `eth_simulateV1` executes the real contracts. Local simulations of some
+//! protocols drift from on-chain behaviour, e.g. because of fee-on-transfer
+//! tokens, hooks or stale state, and the resulting paths look more profitable
+//! than they are.
+//!
+//! The `AccuracyTracker` compares the predicted output of every swap with the
+//! output decoded from the simulation (or execution) logs and keeps running
+//! deviation statistics per protocol system. Callers can use these statistics to
+//! skip protocols whose local simulations cannot be trusted, or to shave a
+//! safety margin off predicted outputs.
+
+use crate::path::{PathExt, SwapExt};
+use crate::simulation::parsing::DecodedSwap;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Default number of samples before a protocol can be distrusted.
+const DEFAULT_MIN_SAMPLES: u64 = 10;
+
+/// Default mean absolute deviation above which a protocol is distrusted.
+const DEFAULT_MAX_DEVIATION_BPS: f64 = 100.0;
+
+/// Running deviation statistics of one protocol system.
+///
+/// Deviations are measured in basis points of the predicted output. Positive
+/// values mean the simulation returned more than predicted, negative values mean
+/// the local simulation overestimated the output.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccuracyStats {
+    /// Number of recorded swaps
+    pub samples: u64,
+    /// Mean signed deviation in basis points
+    pub mean_deviation_bps: f64,
+    /// Mean absolute deviation in basis points
+    pub mean_abs_deviation_bps: f64,
+    /// Largest absolute deviation in basis points
+    pub max_abs_deviation_bps: f64,
+    /// Sum of squared differences from the mean (Welford's algorithm)
+    m2: f64,
+}
+
+impl AccuracyStats {
+    /// Add a deviation sample.
+    pub fn record(&mut self, deviation_bps: f64) {
+        self.samples += 1;
+        let count = self.samples as f64;
+
+        let delta = deviation_bps - self.mean_deviation_bps;
+        self.mean_deviation_bps += delta / count;
+        self.m2 += delta * (deviation_bps - self.mean_deviation_bps);
+
+        let abs_deviation = deviation_bps.abs();
+        self.mean_abs_deviation_bps += (abs_deviation - self.mean_abs_deviation_bps) / count;
+        self.max_abs_deviation_bps = self.max_abs_deviation_bps.max(abs_deviation);
+    }
+
+    /// Sample standard deviation of the signed deviation in basis points.
+    pub fn std_dev_bps(&self) -> f64 {
+        if self.samples < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.samples - 1) as f64).sqrt()
+    }
+
+    /// Haircut in basis points to apply to predicted outputs of this protocol.
+    ///
+    /// Covers the average overestimation plus one standard deviation, and is
+    /// zero for protocols that on average deliver at least their prediction
+    /// with no spread.
+    pub fn safety_margin_bps(&self) -> f64 {
+        (self.std_dev_bps() - self.mean_deviation_bps).max(0.0)
+    }
+}
+
+/// Per-protocol accuracy of local swap simulations.
+#[derive(Debug)]
+pub struct AccuracyTracker {
+    min_samples: u64,
+    max_deviation_bps: f64,
+    stats: RwLock<HashMap<String, AccuracyStats>>,
+}
+
+impl AccuracyTracker {
+    /// Create a tracker distrusting protocols whose mean absolute deviation exceeds
+    /// 100 bps after at least ten samples.
+    pub fn new() -> Self {
+        Self {
+            min_samples: DEFAULT_MIN_SAMPLES,
+            max_deviation_bps: DEFAULT_MAX_DEVIATION_BPS,
+            stats: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Set the number of samples required before a protocol can be distrusted.
+    pub fn with_min_samples(mut self, min_samples: u64) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Set the mean absolute deviation above which a protocol is distrusted.
+    pub fn with_max_deviation_bps(mut self, max_deviation_bps: f64) -> Self {
+        self.max_deviation_bps = max_deviation_bps;
+        self
+    }
+
+    /// Record one swap of a protocol.
+    ///
+    /// Swaps with a zero prediction carry no information and are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_system` - The protocol system of the pool, e.g. "uniswap_v3"
+    /// * `predicted` - The output predicted by the local simulation
+    /// * `actual` - The output observed in the simulation or execution logs
+    pub fn record(&self, protocol_system: &str, predicted: &BigUint, actual: &BigUint) {
+        let Some(deviation_bps) = deviation_bps(predicted, actual) else {
+            return;
+        };
+
+        if let Ok(mut stats) = self.stats.write() {
+            let entry = stats.entry(protocol_system.to_string()).or_default();
+            entry.record(deviation_bps);
+
+            tracing::trace!(
+                protocol_system = protocol_system,
+                predicted = %predicted,
+                actual = %actual,
+                deviation_bps = deviation_bps,
+                mean_deviation_bps = entry.mean_deviation_bps,
+                samples = entry.samples,
+                "Recorded protocol simulation accuracy"
+            );
+        }
+    }
+
+    /// Record every swap of a path against the swaps decoded from its logs.
+    ///
+    /// Swap events are emitted in execution order, so when every swap of the path
+    /// was decoded they are paired by position. Otherwise swaps are paired by pool
+    /// and swaps without a decoded counterpart are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path with the amounts predicted by local simulation
+    /// * `decoded` - The swaps decoded from the simulation or execution logs
+    ///
+    /// # Returns
+    ///
+    /// The number of recorded swaps
+    pub fn record_path(&self, path: &PathExt, decoded: &[DecodedSwap]) -> usize {
+        let pairs = pair_decoded_swaps(path, decoded);
+        for (swap, decoded_swap) in &pairs {
+            self.record(&swap.pool_comp.protocol_system, &swap.amount_out, &decoded_swap.amount_out);
+        }
+        pairs.len()
+    }
+
+    /// Statistics of a protocol system, if any swap was recorded.
+    pub fn stats(&self, protocol_system: &str) -> Option<AccuracyStats> {
+        self.stats
+            .read()
+            .ok()
+            .and_then(|stats| stats.get(protocol_system).cloned())
+    }
+
+    /// Statistics of all recorded protocol systems.
+    pub fn all_stats(&self) -> HashMap<String, AccuracyStats> {
+        self.stats.read().map_or_else(|_| HashMap::new(), |stats| stats.clone())
+    }
+
+    /// Whether local simulations of a protocol system can be trusted.
+    ///
+    /// Protocols with fewer than the minimum number of samples are trusted.
+    pub fn is_trusted(&self, protocol_system: &str) -> bool {
+        self.stats(protocol_system).is_none_or(|stats| {
+            stats.samples < self.min_samples || stats.mean_abs_deviation_bps <= self.max_deviation_bps
+        })
+    }
+
+    /// The first protocol system of a path that is not trusted, if any.
+    pub fn untrusted_protocol<'a>(&self, path: &'a PathExt) -> Option<&'a str> {
+        path.iter()
+            .map(|swap| swap.pool_comp.protocol_system.as_str())
+            .find(|protocol_system| !self.is_trusted(protocol_system))
+    }
+
+    /// Haircut in basis points to apply to predicted outputs of a protocol system.
+    ///
+    /// Zero until the minimum number of samples has been recorded.
+    pub fn safety_margin_bps(&self, protocol_system: &str) -> f64 {
+        self.stats(protocol_system)
+            .filter(|stats| stats.samples >= self.min_samples)
+            .map_or(0.0, |stats| stats.safety_margin_bps())
+    }
+
+    /// Combined haircut in basis points for the final output of a path.
+    pub fn path_safety_margin_bps(&self, path: &PathExt) -> f64 {
+        path.iter()
+            .map(|swap| self.safety_margin_bps(&swap.pool_comp.protocol_system))
+            .sum()
+    }
+}
+
+impl Default for AccuracyTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pair the swaps of a path with the swaps decoded from its logs.
+///
+/// Swap events are emitted in execution order, so when every swap of the path
+/// was decoded they are paired by position. Otherwise swaps are paired by pool
+/// and swaps without a decoded counterpart are left out.
+pub(crate) fn pair_decoded_swaps<'a, 'b>(
+    path: &'a PathExt,
+    decoded: &'b [DecodedSwap],
+) -> Vec<(&'a SwapExt, &'b DecodedSwap)> {
+    if path.len() == decoded.len() {
+        return path.iter().zip(decoded).collect();
+    }
+
+    let mut used = vec![false; decoded.len()];
+    path.iter()
+        .filter_map(|swap| {
+            let (index, decoded_swap) = decoded.iter().enumerate().find(|(index, decoded_swap)| {
+                !used[*index]
+                    && (decoded_swap.pool == swap.pool_comp.id || decoded_swap.pool == swap.pool_comp.address)
+            })?;
+            used[index] = true;
+            Some((swap, decoded_swap))
+        })
+        .collect()
+}
+
+/// Deviation of `actual` from `predicted` in basis points of `predicted`.
+pub(crate) fn deviation_bps(predicted: &BigUint, actual: &BigUint) -> Option<f64> {
+    let predicted = predicted.to_f64().filter(|predicted| *predicted > 0.0)?;
+    let actual = actual.to_f64()?;
+    Some((actual - predicted) / predicted * 10_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_track_deviation() {
+        let tracker = AccuracyTracker::new();
+        tracker.record("uniswap_v2", &BigUint::from(10_000u32), &BigUint::from(9_900u32));
+        tracker.record("uniswap_v2", &BigUint::from(10_000u32), &BigUint::from(10_100u32));
+        tracker.record("uniswap_v2", &BigUint::from(0u32), &BigUint::from(1u32));
+
+        let stats = tracker.stats("uniswap_v2").unwrap();
+        assert_eq!(stats.samples, 2);
+        assert!(stats.mean_deviation_bps.abs() < 1e-9);
+        assert!((stats.mean_abs_deviation_bps - 100.0).abs() < 1e-9);
+        assert!((stats.max_abs_deviation_bps - 100.0).abs() < 1e-9);
+        assert!((stats.std_dev_bps() - 141.421356).abs() < 1e-3);
+        assert!(tracker.stats("curve").is_none());
+    }
+
+    #[test]
+    fn test_distrusts_drifting_protocols() {
+        let tracker = AccuracyTracker::new()
+            .with_min_samples(3)
+            .with_max_deviation_bps(50.0);
+
+        for _ in 0..2 {
+            tracker.record("vm:curve", &BigUint::from(10_000u32), &BigUint::from(9_000u32));
+            tracker.record("uniswap_v3", &BigUint::from(10_000u32), &BigUint::from(10_000u32));
+        }
+
+        // Not enough samples yet
+        assert!(tracker.is_trusted("vm:curve"));
+        assert_eq!(tracker.safety_margin_bps("vm:curve"), 0.0);
+
+        tracker.record("vm:curve", &BigUint::from(10_000u32), &BigUint::from(9_000u32));
+        tracker.record("uniswap_v3", &BigUint::from(10_000u32), &BigUint::from(10_000u32));
+
+        assert!(!tracker.is_trusted("vm:curve"));
+        assert!((tracker.safety_margin_bps("vm:curve") - 1_000.0).abs() < 1e-9);
+        assert!(tracker.is_trusted("uniswap_v3"));
+        assert_eq!(tracker.safety_margin_bps("uniswap_v3"), 0.0);
+        assert!(tracker.is_trusted("unknown"));
+    }
+}
@@ -0,0 +1,174 @@
+//! Permit2 allowance caching for approval-free bundles.
+//!
+//! Each arbitrage transaction pulls its input tokens through Permit2, which
+//! requires an ERC-20 allowance from the executor to the Permit2 contract. Once
+//! an unlimited allowance is in place, sending a fresh `approve` with every
+//! bundle only wastes gas and a nonce.
+//!
+//! The `AllowanceCache` tracks the on-chain allowance for every source token so
+//! the simulator can leave out the approval transaction whenever the cached
+//! allowance already covers the trade, producing single-transaction bundles.
+//! The cache is refreshed from chain on startup and whenever it has been marked
+//! stale, e.g. after a bundle containing an approval was submitted.
+
+use crate::errors::{Result, SimulationError};
+use crate::simulation::encoding::encode_input;
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, TxKind, U256},
+    providers::{Provider, RootProvider},
+    rpc::types::{TransactionInput, TransactionRequest},
+    sol_types::SolValue,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+/// Cache of ERC-20 allowances granted by the executor to Permit2.
+#[derive(Debug)]
+pub struct AllowanceCache {
+    owner: Address,
+    spender: Address,
+    allowances: RwLock<HashMap<Address, U256>>,
+    stale: AtomicBool,
+}
+
+impl AllowanceCache {
+    /// Create an empty cache for allowances from `owner` to `spender`.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The executor address granting the allowance
+    /// * `spender` - The Permit2 contract address
+    pub fn new(owner: Address, spender: Address) -> Self {
+        Self {
+            owner,
+            spender,
+            allowances: RwLock::new(HashMap::new()),
+            stale: AtomicBool::new(true),
+        }
+    }
+
+    /// The address granting the cached allowances.
+    pub fn owner(&self) -> Address {
+        self.owner
+    }
+
+    /// The cached allowance for a token, if known.
+    pub fn allowance(&self, token: &Address) -> Option<U256> {
+        self.allowances
+            .read()
+            .ok()
+            .and_then(|allowances| allowances.get(token).copied())
+    }
+
+    /// Record a known allowance for a token.
+    pub fn record(&self, token: Address, allowance: U256) {
+        if let Ok(mut allowances) = self.allowances.write() {
+            allowances.insert(token, allowance);
+        }
+    }
+
+    /// Whether a trade of `amount` still requires an approval transaction.
+    ///
+    /// Tokens without a cached allowance always require approval.
+    pub fn needs_approval(&self, token: &Address, amount: &U256) -> bool {
+        self.allowance(token)
+            .is_none_or(|allowance| allowance < *amount)
+    }
+
+    /// Mark the cache as outdated, so the next refresh re-reads all allowances.
+    pub fn mark_stale(&self) {
+        self.stale.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the cache needs to be refreshed from chain.
+    pub fn is_stale(&self) -> bool {
+        self.stale.load(Ordering::Relaxed)
+    }
+
+    /// Read the current allowances of the given tokens from chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The RPC provider used for the `allowance` calls
+    /// * `tokens` - The tokens to refresh
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an `allowance` call fails or returns malformed data.
+    /// Allowances read before the failure are kept.
+    pub async fn refresh(
+        &self,
+        provider: &RootProvider<Ethereum>,
+        tokens: &[Address],
+    ) -> Result<()> {
+        for token in tokens {
+            let allowance = self.fetch_allowance(provider, *token).await?;
+
+            tracing::debug!(
+                token = %token,
+                owner = %self.owner,
+                spender = %self.spender,
+                allowance = %allowance,
+                "Refreshed Permit2 allowance"
+            );
+
+            self.record(*token, allowance);
+        }
+
+        self.stale.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Call `allowance(owner, spender)` on a token contract.
+    async fn fetch_allowance(
+        &self,
+        provider: &RootProvider<Ethereum>,
+        token: Address,
+    ) -> Result<U256> {
+        let calldata = encode_input(
+            "allowance(address,address)",
+            (self.owner, self.spender).abi_encode(),
+        );
+
+        let request = TransactionRequest {
+            to: Some(TxKind::Call(token)),
+            input: TransactionInput {
+                input: Some(calldata.into()),
+                data: None,
+            },
+            ..Default::default()
+        };
+
+        let output = provider.call(request).await?;
+        if output.len() < 32 {
+            return Err(SimulationError::ProviderError {
+                message: format!("Invalid allowance response from token {}", token),
+            }.into());
+        }
+
+        Ok(U256::from_be_slice(&output[..32]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_approval() {
+        let cache = AllowanceCache::new(Address::random(), Address::random());
+        let token = Address::random();
+
+        assert!(cache.is_stale());
+        assert!(cache.needs_approval(&token, &U256::from(1)));
+
+        cache.record(token, U256::from(1_000));
+        assert!(!cache.needs_approval(&token, &U256::from(1_000)));
+        assert!(cache.needs_approval(&token, &U256::from(1_001)));
+
+        cache.record(token, U256::MAX);
+        assert!(!cache.needs_approval(&token, &U256::MAX));
+    }
+}
@@ -0,0 +1,256 @@
+//! Reuse of simulation results while the simulated state is unchanged.
+//!
+//! A path that was simulated but not included, e.g. because its target block
+//! was missed, is often simulated again with the same input amount against
+//! unchanged pools. `SimulationCache` keeps the results of recent simulations
+//! keyed by the path, its input amount, the versions of its pools, the signer,
+//! the nonce and the base fee, so such a path is answered without another RPC
+//! call. Whether the transactions include an approval is part of the key, so
+//! results approving a token are not reused once its allowance has landed.
+//!
+//! Base fees are grouped into buckets: a result simulated at a slightly
+//! different base fee is reused and its transactions are re-priced at the
+//! current base fee, so that bundles built from it never carry a stale fee cap.
+//! A different base fee can still change whether a guarded minimum output is
+//! met, which is why the minimum output is part of the key.
+
+use crate::path::{PathExt, PathId, PoolVersions};
+use crate::simulation::SimulationResult;
+use alloy::primitives::{Address, U256};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Default number of cached simulation results.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Default width of a base fee bucket: 1 gwei.
+const DEFAULT_BASE_FEE_BUCKET: u64 = 1_000_000_000;
+
+/// Everything a simulation result depends on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationKey {
+    path_id: PathId,
+    amount_in: BigUint,
+    pool_versions: Vec<u64>,
+    signer: Address,
+    nonce: u64,
+    base_fee_bucket: U256,
+    slippage_bps: u64,
+    min_amount_out: Option<BigUint>,
+    approves: bool,
+    block_number: Option<u64>,
+}
+
+impl SimulationKey {
+    /// Distinguish simulations whose router call enforces a minimum output, see
+    /// [`crate::simulation::SimulationOptions::with_min_amount_out`].
+    pub(crate) fn with_min_amount_out(mut self, min_amount_out: Option<BigUint>) -> Self {
+        self.min_amount_out = min_amount_out;
+        self
+    }
+
+    /// Distinguish simulations whose transactions include an approval of the input token.
+    pub(crate) fn with_approval(mut self, approves: bool) -> Self {
+        self.approves = approves;
+        self
+    }
+}
+
+/// A cached result with the time it was last used.
+#[derive(Debug)]
+struct CachedSimulation {
+    result: SimulationResult,
+    last_used: u64,
+}
+
+/// Least recently used cache of simulation results.
+#[derive(Debug)]
+pub struct SimulationCache {
+    pool_versions: Arc<PoolVersions>,
+    capacity: usize,
+    base_fee_bucket: U256,
+    entries: Mutex<HashMap<SimulationKey, CachedSimulation>>,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SimulationCache {
+    /// Create a cache of results at the pool versions of a registry.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool_versions` - The registry the state changes of pools are recorded in
+    pub fn new(pool_versions: Arc<PoolVersions>) -> Self {
+        Self {
+            pool_versions,
+            capacity: DEFAULT_CAPACITY,
+            base_fee_bucket: U256::from(DEFAULT_BASE_FEE_BUCKET),
+            entries: Mutex::new(HashMap::new()),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Set the number of cached results, evicting the least recently used beyond it.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set the width of the base fee buckets in wei within which results are reused.
+    pub fn with_base_fee_bucket(mut self, base_fee_bucket: U256) -> Self {
+        self.base_fee_bucket = base_fee_bucket.max(U256::from(1));
+        self
+    }
+
+    /// The key of a simulation with the current pool versions.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The simulated path
+    /// * `signer` - The address sending the transactions
+    /// * `nonce` - The nonce of the first transaction
+    /// * `base_fee` - The base fee the transactions are priced with
+    /// * `slippage_bps` - The tolerated output shortfall
+    /// * `block_number` - The block simulations are pinned to, if any
+    pub fn key(
+        &self,
+        path: &PathExt,
+        signer: Address,
+        nonce: u64,
+        base_fee: U256,
+        slippage_bps: u64,
+        block_number: Option<u64>,
+    ) -> SimulationKey {
+        SimulationKey {
+            path_id: PathId::of_path_ext(path),
+            amount_in: path.first().map(|swap| swap.amount_in.clone()).unwrap_or_default(),
+            pool_versions: self.pool_versions.path_ext_versions(path),
+            signer,
+            nonce,
+            base_fee_bucket: base_fee / self.base_fee_bucket,
+            slippage_bps,
+            min_amount_out: None,
+            approves: false,
+            block_number,
+        }
+    }
+
+    /// The cached result of a simulation, if any.
+    pub fn get(&self, key: &SimulationKey) -> Option<SimulationResult> {
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        let result = self.entries.lock().ok().and_then(|mut entries| {
+            entries.get_mut(key).map(|entry| {
+                entry.last_used = now;
+                entry.result.clone()
+            })
+        });
+        let counter = if result.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+
+    /// Cache the result of a simulation, evicting the least recently used result if full.
+    pub fn insert(&self, key: SimulationKey, result: &SimulationResult) {
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            let least_recently_used = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(evicted) = least_recently_used {
+                entries.remove(&evicted);
+            }
+        }
+        entries.insert(key, CachedSimulation {
+            result: result.clone(),
+            last_used: now,
+        });
+    }
+
+    /// Remove all cached results.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+
+    /// The number of cached results.
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|entries| entries.len()).unwrap_or(0)
+    }
+
+    /// Whether no result is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of simulations answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of simulations not found in the cache.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::PathExecutor;
+    use crate::testing::{cyclic_path, MockProtocolSim};
+    use alloy::primitives::Address;
+    use alloy::rpc::types::TransactionRequest;
+    use std::str::FromStr;
+    use tycho_common::Bytes;
+
+    fn result() -> SimulationResult {
+        SimulationResult {
+            wrap_request: None,
+            approval_request: None,
+            swap_request: TransactionRequest::default(),
+            unwrap_request: None,
+            simulated_blocks: Vec::new(),
+            output_token: Address::ZERO,
+            receiver: Address::ZERO,
+            call_trace: None,
+        }
+    }
+
+    #[test]
+    fn test_reuses_results_until_a_pool_changes() {
+        let tokens: Vec<Bytes> = ["0x0001", "0x0002"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        let pool = Bytes::from_str("0x1001").unwrap();
+        let path = cyclic_path(&tokens, &pool, &MockProtocolSim::new()).unwrap();
+        let path_ext = PathExecutor::new().execute_with_amount(&path, BigUint::from(1000u32)).unwrap();
+
+        let versions = Arc::new(PoolVersions::new());
+        let cache = SimulationCache::new(Arc::clone(&versions)).with_capacity(1);
+        let base_fee = U256::from(10_000_000_000u64);
+        cache.insert(cache.key(&path_ext, Address::ZERO, 1, base_fee, 50, None), &result());
+
+        // Base fees within the bucket reuse the result, other nonces do not
+        assert!(cache.get(&cache.key(&path_ext, Address::ZERO, 1, base_fee + U256::from(1), 50, None)).is_some());
+        assert!(cache.get(&cache.key(&path_ext, Address::ZERO, 2, base_fee, 50, None)).is_none());
+        assert!(cache.get(&cache.key(&path_ext, Address::repeat_byte(1), 1, base_fee, 50, None)).is_none());
+        let approving = cache.key(&path_ext, Address::ZERO, 1, base_fee, 50, None).with_approval(true);
+        assert!(cache.get(&approving).is_none());
+
+        versions.record_change(&path[0].pool_comp.id);
+        assert!(cache.get(&cache.key(&path_ext, Address::ZERO, 1, base_fee, 50, None)).is_none());
+        assert_eq!((cache.hits(), cache.misses()), (1, 4));
+
+        // The least recently used result is evicted
+        cache.insert(cache.key(&path_ext, Address::ZERO, 1, base_fee, 50, None), &result());
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,166 @@
+//! Calldata size and intrinsic gas of router calls.
+//!
+//! Every byte of calldata is charged before execution starts: 4 gas per zero
+//! byte and 16 gas per non-zero byte, on top of the 21,000 gas base cost of a
+//! transaction. Long paths encode one swap per hop, so their calldata can cost
+//! more than the profit of the extra hop, and on L2s the calldata is also
+//! what the L1 data fee is charged for. The gas of protocol simulations
+//! ignores calldata entirely.
+//!
+//! `CalldataCost` measures encoded calldata exactly, and estimates the
+//! calldata of a router call from its `EncodedSolution` before the call is
+//! encoded.
+
+use tycho_execution::encoding::models::EncodedSolution;
+
+/// Base gas cost of every transaction.
+pub const TX_BASE_GAS: u64 = 21_000;
+
+/// Gas charged per zero calldata byte.
+pub const ZERO_BYTE_GAS: u64 = 4;
+
+/// Gas charged per non-zero calldata byte.
+pub const NON_ZERO_BYTE_GAS: u64 = 16;
+
+/// Size of a signed EIP-1559 transaction without calldata and access list, in bytes.
+const SIGNED_TX_OVERHEAD_BYTES: usize = 110;
+
+/// Size of an ABI word.
+const ABI_WORD_BYTES: usize = 32;
+
+/// Non-zero bytes assumed per static argument word, the size of an address.
+const ARGUMENT_NON_ZERO_BYTES: usize = 20;
+
+/// Size of the Permit2 signature passed as a dynamic argument.
+const PERMIT_SIGNATURE_BYTES: usize = 65;
+
+/// Zero and non-zero bytes of calldata.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CalldataCost {
+    /// Number of zero bytes
+    pub zero_bytes: usize,
+    /// Number of non-zero bytes
+    pub non_zero_bytes: usize,
+}
+
+impl CalldataCost {
+    /// Measure encoded calldata.
+    pub fn from_calldata(calldata: &[u8]) -> Self {
+        let zero_bytes = calldata.iter().filter(|byte| **byte == 0).count();
+        Self {
+            zero_bytes,
+            non_zero_bytes: calldata.len() - zero_bytes,
+        }
+    }
+
+    /// Estimate the calldata of the router call executing an encoded solution.
+    ///
+    /// The encoded swaps are measured exactly. The selector and the ABI words of
+    /// the other arguments, parsed from the function signature, are estimated:
+    /// every static argument counts as an address-sized value, the last `bytes`
+    /// argument holds the swaps and any other holds a Permit2 signature.
+    pub fn from_encoded_solution(encoded_solution: &EncodedSolution) -> Self {
+        let mut cost = Self {
+            zero_bytes: 0,
+            non_zero_bytes: 4,
+        };
+
+        let params = encoded_solution
+            .function_signature
+            .split_once('(')
+            .map(|(_, params)| params)
+            .unwrap_or_default();
+        let types: Vec<&str> = params
+            .split(|c| matches!(c, '(' | ')' | ','))
+            .map(str::trim)
+            .filter(|param| !param.is_empty())
+            .collect();
+        let last_bytes = types.iter().rposition(|param| *param == "bytes");
+
+        for (index, param) in types.iter().enumerate() {
+            if *param != "bytes" {
+                cost.add_static_word();
+                continue;
+            }
+            // Offset and length words, then the data padded to whole words
+            cost.add_static_word();
+            cost.add_static_word();
+            if Some(index) == last_bytes {
+                cost.add_padded(&encoded_solution.swaps);
+            } else {
+                cost.non_zero_bytes += PERMIT_SIGNATURE_BYTES;
+                cost.zero_bytes += padding(PERMIT_SIGNATURE_BYTES);
+            }
+        }
+        cost
+    }
+
+    /// The size of the calldata in bytes.
+    pub fn len(&self) -> usize {
+        self.zero_bytes + self.non_zero_bytes
+    }
+
+    /// Whether the calldata is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The gas charged for the calldata.
+    pub fn gas(&self) -> u64 {
+        self.zero_bytes as u64 * ZERO_BYTE_GAS + self.non_zero_bytes as u64 * NON_ZERO_BYTE_GAS
+    }
+
+    /// The intrinsic gas of a transaction with this calldata, including the base cost.
+    pub fn intrinsic_gas(&self) -> u64 {
+        TX_BASE_GAS + self.gas()
+    }
+
+    /// The approximate size of a signed transaction with this calldata, in bytes.
+    pub fn transaction_size(&self) -> usize {
+        SIGNED_TX_OVERHEAD_BYTES + self.len()
+    }
+
+    fn add_static_word(&mut self) {
+        self.non_zero_bytes += ARGUMENT_NON_ZERO_BYTES;
+        self.zero_bytes += ABI_WORD_BYTES - ARGUMENT_NON_ZERO_BYTES;
+    }
+
+    fn add_padded(&mut self, data: &[u8]) {
+        let measured = Self::from_calldata(data);
+        self.zero_bytes += measured.zero_bytes + padding(data.len());
+        self.non_zero_bytes += measured.non_zero_bytes;
+    }
+}
+
+/// Zero bytes padding `len` bytes to whole ABI words.
+fn padding(len: usize) -> usize {
+    (ABI_WORD_BYTES - len % ABI_WORD_BYTES) % ABI_WORD_BYTES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calldata_gas_and_estimate() {
+        let cost = CalldataCost::from_calldata(&[0, 0, 1, 2, 0, 3]);
+        assert_eq!((cost.zero_bytes, cost.non_zero_bytes), (3, 3));
+        assert_eq!(cost.gas(), 3 * 4 + 3 * 16);
+        assert_eq!(cost.intrinsic_gas(), 21_000 + 60);
+
+        let encoded_solution = EncodedSolution {
+            swaps: vec![1; 40],
+            interacting_with: tycho_common::Bytes::from(vec![1u8; 20]),
+            function_signature: "singleSwap(uint256,address,address,uint256,bool,bool,address,bytes)".to_string(),
+            n_tokens: 2,
+            permit: None,
+        };
+        let estimate = CalldataCost::from_encoded_solution(&encoded_solution);
+        // Selector, 7 static arguments, offset and length words and two words of swaps
+        assert_eq!(estimate.len(), 4 + 9 * 32 + 64);
+        assert_eq!(estimate.non_zero_bytes, 4 + 9 * 20 + 40);
+
+        let longer = EncodedSolution { swaps: vec![1; 120], ..encoded_solution };
+        assert!(CalldataCost::from_encoded_solution(&longer).gas() > estimate.gas());
+    }
+}
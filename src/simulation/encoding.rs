@@ -11,6 +11,8 @@
 //! - **Permit2 Integration**: Creating EIP-712 signatures for gasless token approvals
 //! - **Transaction Construction**: Building complete transaction payloads with proper encoding
 //! - **Router Integration**: Interfacing with the Tycho router system for execution
+//! - **Native Token Wrapping**: `deposit()`/`withdraw()` legs for paths starting from the native token
+//! - **Exact-Output Solutions**: Encoded as exact-input routes spending the maximum input
 //!
 //! # Encoding Process
 //!
@@ -37,6 +39,7 @@
 //! - Encoding failures from malformed data structures
 
 use crate::errors::{SimulationError, Result};
+use crate::simulation::solution::SolutionBuilder;
 use crate::utils::biguint_to_u256;
 use alloy::{
     primitives::{Address, Bytes as AlloyBytes, Keccak256, U256},
@@ -100,30 +103,123 @@ pub fn encode_input(selector: &str, mut encoded_args: Vec<u8>) -> Vec<u8> {
 ///
 /// # Arguments
 ///
-/// * `permit2_address` - The address of the Permit2 contract
+/// * `spender` - The address allowed to spend, usually the Permit2 contract or the router
 /// * `amount` - The amount of tokens to approve for spending
 ///
 /// # Returns
 ///
 /// The encoded calldata for the approval transaction
-pub fn create_approval_calldata(permit2_address: Address, amount: U256) -> AlloyBytes {
+pub fn create_approval_calldata(spender: Address, amount: U256) -> AlloyBytes {
     let approve_calldata = encode_input(
         "approve(address,uint256)",
-        (permit2_address, amount).abi_encode(),
+        (spender, amount).abi_encode(),
     );
     AlloyBytes::from(approve_calldata)
 }
 
+/// Create the calldata for wrapping the native token.
+///
+/// Calls `deposit()` on the wrapped native contract. The amount to wrap is sent
+/// as the transaction value, so the call carries no arguments.
+///
+/// # Returns
+///
+/// The encoded calldata for the wrap transaction
+pub fn create_wrap_calldata() -> AlloyBytes {
+    AlloyBytes::from(encode_input("deposit()", Vec::new()))
+}
+
+/// Create the calldata for unwrapping the wrapped native token.
+///
+/// Calls `withdraw(uint256)` on the wrapped native contract, returning the given
+/// amount as native token to the caller.
+///
+/// # Arguments
+///
+/// * `amount` - The amount of wrapped native token to unwrap
+///
+/// # Returns
+///
+/// The encoded calldata for the unwrap transaction
+pub fn create_unwrap_calldata(amount: U256) -> AlloyBytes {
+    AlloyBytes::from(encode_input("withdraw(uint256)", amount.abi_encode()))
+}
+
+/// Replace the native token with its wrapped counterpart in a swap sequence.
+///
+/// The router and pools operate on the wrapped token, so paths that start from
+/// the native token are encoded against the wrapped token after a wrap leg.
+///
+/// # Arguments
+///
+/// * `swaps` - The sequence of swaps to rewrite
+/// * `native_token` - The address representing the native token
+/// * `wrapped_native` - The wrapped native token address
+///
+/// # Returns
+///
+/// The swaps with every native token reference replaced by the wrapped token
+pub fn wrap_native_swaps(
+    swaps: Vec<tycho_execution::encoding::models::Swap>,
+    native_token: &Bytes,
+    wrapped_native: &Bytes,
+) -> Vec<tycho_execution::encoding::models::Swap> {
+    swaps
+        .into_iter()
+        .map(|mut swap| {
+            if swap.token_in == *native_token {
+                swap.token_in = wrapped_native.clone();
+            }
+            if swap.token_out == *native_token {
+                swap.token_out = wrapped_native.clone();
+            }
+            swap
+        })
+        .collect()
+}
+
+/// Convert an exact-output solution into the exact-input route the router executes.
+///
+/// The router only executes exact-input routes. An exact-output solution is
+/// executed by selling its maximum input and checking that the output reaches
+/// the exact amount, so the output is at least the exact amount. Exact-input
+/// solutions are returned unchanged.
+///
+/// # Arguments
+///
+/// * `solution` - The solution to convert
+///
+/// # Returns
+///
+/// The exact-input solution selling the checked amount of the checked token
+/// for at least the given amount of the given token
+pub fn exact_in_equivalent(solution: &Solution) -> Solution {
+    if !solution.exact_out {
+        return solution.clone();
+    }
+    Solution {
+        exact_out: false,
+        given_token: solution.checked_token.clone(),
+        given_amount: solution.checked_amount.clone(),
+        checked_token: solution.given_token.clone(),
+        checked_amount: solution.given_amount.clone(),
+        ..solution.clone()
+    }
+}
+
 /// Encode a trading solution using the Tycho router encoder.
 ///
 /// Takes a high-level trading solution and encodes it into a format that can be
 /// executed by the Tycho router system. This includes encoding swap details,
-/// token information, and execution parameters.
+/// token information, and execution parameters. Exact-output solutions are
+/// encoded as their [`exact_in_equivalent`].
 ///
 /// # Arguments
 ///
 /// * `solution` - The trading solution to encode
 /// * `chain` - The blockchain network name (e.g., "ethereum", "base", "unichain")
+/// * `transfer_type` - How the router obtains the input tokens from the user
+/// * `router_address` - The router to encode for, or `None` for the default router of the chain
 ///
 /// # Returns
 ///
@@ -135,16 +231,24 @@ pub fn create_approval_calldata(permit2_address: Address, amount: U256) -> Alloy
 /// - The chain configuration is invalid or unsupported
 /// - The solution encoding fails
 /// - The encoder builder cannot be constructed
-pub fn encode_solution(solution: &Solution, chain: &str) -> Result<EncodedSolution> {
-    let encoder = TychoRouterEncoderBuilder::new()
+pub fn encode_solution(
+    solution: &Solution,
+    chain: &str,
+    transfer_type: UserTransferType,
+    router_address: Option<&Bytes>,
+) -> Result<EncodedSolution> {
+    let mut encoder_builder = TychoRouterEncoderBuilder::new()
         .chain(TychoChain::from_str(chain).map_err(|e| SimulationError::InvalidChain { 
             chain: format!("{}: {}", chain, e) 
         })?)
-        .user_transfer_type(UserTransferType::TransferFromPermit2)
-        .build()?;
+        .user_transfer_type(transfer_type);
+    if let Some(router_address) = router_address {
+        encoder_builder = encoder_builder.router_address(router_address.clone());
+    }
+    let encoder = encoder_builder.build()?;
     
     encoder
-        .encode_solutions(vec![solution.clone()])?
+        .encode_solutions(vec![exact_in_equivalent(solution)])?
         .into_iter()
         .next()
         .ok_or_else(|| SimulationError::SolutionEncodingFailed { 
@@ -161,7 +265,7 @@ pub fn encode_solution(solution: &Solution, chain: &str) -> Result<EncodedSoluti
 /// # Arguments
 ///
 /// * `encoded_solution` - The encoded trading solution
-/// * `amount_in` - The input amount for the trade
+/// * `amount_in` - The input amount for the trade, the maximum input for exact-output solutions
 /// * `solution` - The original solution for token address extraction
 /// * `permit_signature` - The Permit2 signature for token approval
 ///
@@ -187,6 +291,7 @@ pub fn encode_router_call(
         .ok_or(SimulationError::InvalidSimulationPayload)?;
     
     let exec_permit = ExecPermitSingle::try_from(permit)?;
+    let solution = exact_in_equivalent(solution);
     let min_amt_out = biguint_to_u256(&solution.checked_amount)?;
 
     let method_calldata = (
@@ -208,6 +313,53 @@ pub fn encode_router_call(
     Ok(AlloyBytes::from(call_data))
 }
 
+/// Create router call calldata without a permit.
+///
+/// Used with transfer types other than Permit2. With `transfer_from` set, the
+/// router pulls the input tokens using a standard ERC-20 allowance; otherwise it
+/// spends tokens already held by the router.
+///
+/// # Arguments
+///
+/// * `encoded_solution` - The encoded trading solution
+/// * `amount_in` - The input amount for the trade, the maximum input for exact-output solutions
+/// * `solution` - The original solution for token address extraction
+/// * `transfer_from` - Whether the router transfers the input from the sender
+///
+/// # Returns
+///
+/// The complete calldata for the router execution transaction
+///
+/// # Errors
+///
+/// This function will return an error if the checked amount does not fit into a U256
+pub fn encode_direct_router_call(
+    encoded_solution: &EncodedSolution,
+    amount_in: &U256,
+    solution: &Solution,
+    transfer_from: bool,
+) -> Result<AlloyBytes> {
+    let solution = exact_in_equivalent(solution);
+    let min_amt_out = biguint_to_u256(&solution.checked_amount)?;
+
+    let method_calldata = (
+        *amount_in,
+        Address::from_slice(solution.given_token.as_ref()),
+        Address::from_slice(solution.checked_token.as_ref()),
+        min_amt_out,
+        false,
+        false,
+        Address::from_slice(solution.receiver.as_ref()),
+        transfer_from,
+        encoded_solution.swaps.clone(),
+    )
+        .abi_encode();
+
+    let call_data = encode_input(&encoded_solution.function_signature, method_calldata);
+
+    Ok(AlloyBytes::from(call_data))
+}
+
 /// Sign a Permit2 permit for token approval.
 ///
 /// Creates an EIP-712 signature for a Permit2 token approval, enabling gasless
@@ -257,14 +409,18 @@ pub fn sign_permit(
 ///
 /// Creates a complete Solution struct from swap details and user parameters.
 /// The solution represents the entire arbitrage strategy including token flows,
-/// amounts, and execution parameters.
+/// amounts, and execution parameters. The input token of the first swap is
+/// checked; use [`SolutionBuilder`] to check the output of the final hop or to
+/// encode for another router.
 ///
 /// # Arguments
 ///
 /// * `swaps` - The sequence of swaps to execute
 /// * `amount_in` - The initial input amount for the arbitrage
 /// * `sender_address` - The address executing the arbitrage
+/// * `receiver_address` - The address receiving the output, e.g. a cold wallet
 /// * `expected_amount_out` - The expected final output amount from the path
+/// * `slippage_bps` - The tolerated shortfall of the output, in basis points
 ///
 /// # Returns
 ///
@@ -275,56 +431,109 @@ pub fn sign_permit(
 /// This function will return an error if:
 /// - The swap list is empty
 /// - The swap data is malformed
-/// - The slippage configuration is invalid
+/// - The slippage exceeds 10000 BPS (100%)
 pub fn build_solution(
     swaps: &[tycho_execution::encoding::models::Swap],
     amount_in: BigUint,
     sender_address: &Bytes,
+    receiver_address: &Bytes,
     expected_amount_out: BigUint,
+    slippage_bps: u64,
 ) -> Result<Solution> {
-    if swaps.is_empty() {
-        return Err(SimulationError::SimulationFailed { 
-            reason: "No swaps provided for solution".to_string() 
-        }.into());
-    }
-
-    // Read slippage tolerance from environment variables
-    let slippage_bps = std::env::var("TYCHO_SLIPPAGE_BPS")
-        .unwrap_or_else(|_| "50".to_string())
-        .parse::<u64>()
-        .map_err(|e| SimulationError::SimulationFailed {
-            reason: format!("Invalid TYCHO_SLIPPAGE_BPS value: {}", e)
+    let first_token = swaps
+        .first()
+        .map(|swap| swap.token_in.clone())
+        .ok_or_else(|| SimulationError::InvalidSolution {
+            reason: "No swaps provided for solution".to_string(),
         })?;
 
-    // Calculate slippage-adjusted checked amount
-    // slippage_amount = expected_amount_out * slippage_bps / 10000
-    let slippage_amount = &expected_amount_out * slippage_bps / 10000u64;
-    let checked_amount = if expected_amount_out > slippage_amount {
-        &expected_amount_out - &slippage_amount
-    } else {
-        // If slippage would result in negative amount, use a minimal amount
-        BigUint::from(1_u32)
-    };
+    SolutionBuilder::new(swaps.to_vec(), amount_in, expected_amount_out, sender_address.clone())
+        .with_receiver(receiver_address.clone())
+        .with_checked_token(first_token)
+        .with_slippage_bps(slippage_bps)
+        .build()
+}
 
-    tracing::debug!(
-        expected_amount_out = %expected_amount_out,
-        slippage_bps = slippage_bps,
-        slippage_amount = %slippage_amount,
-        checked_amount = %checked_amount,
-        "Calculated slippage-adjusted checked amount"
-    );
+/// Build a solution whose checked amount also enforces a post-gas profit threshold.
+///
+/// Like [`build_solution`], but the router reverts unless the output reaches
+/// the larger of `min_amount_out` and the slippage-adjusted expected output,
+/// e.g. the threshold of a [`ProfitGuard`](crate::simulation::ProfitGuard).
+///
+/// # Arguments
+///
+/// * `swaps` - The sequence of swaps to execute
+/// * `amount_in` - The initial input amount for the arbitrage
+/// * `sender_address` - The address executing the arbitrage
+/// * `receiver_address` - The address receiving the output, e.g. a cold wallet
+/// * `expected_amount_out` - The expected final output amount from the path
+/// * `slippage_bps` - The tolerated shortfall of the output, in basis points
+/// * `min_amount_out` - The output below which the solution reverts regardless of slippage
+///
+/// # Errors
+///
+/// Returns the errors of [`build_solution`], and an error if `min_amount_out`
+/// exceeds the expected output.
+pub fn build_guarded_solution(
+    swaps: &[tycho_execution::encoding::models::Swap],
+    amount_in: BigUint,
+    sender_address: &Bytes,
+    receiver_address: &Bytes,
+    expected_amount_out: BigUint,
+    slippage_bps: u64,
+    min_amount_out: BigUint,
+) -> Result<Solution> {
+    let first_token = swaps
+        .first()
+        .map(|swap| swap.token_in.clone())
+        .ok_or_else(|| SimulationError::InvalidSolution {
+            reason: "No swaps provided for solution".to_string(),
+        })?;
 
-    Ok(Solution {
-        exact_out: false,
-        swaps: swaps.to_vec(),
-        sender: sender_address.clone(),
-        receiver: sender_address.clone(),
-        given_token: swaps[0].token_in.clone(),
-        given_amount: amount_in,
-        checked_token: swaps[0].token_in.clone(),
-        checked_amount,
-        ..Default::default()
-    })
+    SolutionBuilder::new(swaps.to_vec(), amount_in, expected_amount_out, sender_address.clone())
+        .with_receiver(receiver_address.clone())
+        .with_checked_token(first_token)
+        .with_slippage_bps(slippage_bps)
+        .with_min_checked_amount(min_amount_out)
+        .build()
+}
+
+/// Build an exact-output solution, e.g. to repay a flash loan of a fixed amount.
+///
+/// The router reverts unless the route delivers `amount_out` while spending at
+/// most the expected input plus the slippage tolerance.
+///
+/// # Arguments
+///
+/// * `swaps` - The sequence of swaps to execute
+/// * `amount_out` - The exact output amount of the final swap
+/// * `sender_address` - The address executing the arbitrage
+/// * `receiver_address` - The address receiving the output
+/// * `expected_amount_in` - The expected input amount, e.g. from [`PathExecutor::get_amount_in`](crate::path::PathExecutor::get_amount_in)
+/// * `slippage_bps` - The tolerated excess of the input, in basis points
+///
+/// # Returns
+///
+/// A complete exact-output Solution ready for encoding and execution
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The swap list is empty
+/// - The swap data is malformed
+/// - The slippage exceeds 10000 BPS (100%)
+pub fn build_exact_out_solution(
+    swaps: &[tycho_execution::encoding::models::Swap],
+    amount_out: BigUint,
+    sender_address: &Bytes,
+    receiver_address: &Bytes,
+    expected_amount_in: BigUint,
+    slippage_bps: u64,
+) -> Result<Solution> {
+    SolutionBuilder::exact_out(swaps.to_vec(), amount_out, expected_amount_in, sender_address.clone())
+        .with_receiver(receiver_address.clone())
+        .with_slippage_bps(slippage_bps)
+        .build()
 }
 
 /// Convert BigUint to U256 with simulation-specific error handling.
@@ -394,11 +603,23 @@ mod tests {
         assert_eq!(&calldata[..4], expected_selector);
     }
 
+    #[test]
+    fn test_create_wrap_and_unwrap_calldata() {
+        let wrap = create_wrap_calldata();
+        assert_eq!(wrap.len(), 4);
+        assert_eq!(&wrap[..], &encode_input("deposit()", Vec::new())[..]);
+
+        let unwrap = create_unwrap_calldata(U256::from(1000));
+        assert_eq!(unwrap.len(), 36);
+
+        let mut hasher = Keccak256::new();
+        hasher.update("withdraw(uint256)".as_bytes());
+        let expected_selector = &hasher.finalize()[..4];
+        assert_eq!(&unwrap[..4], expected_selector);
+    }
+
     #[test]
     fn test_build_solution_slippage_calculation() {
-        // Set up test environment variable
-        std::env::set_var("TYCHO_SLIPPAGE_BPS", "100"); // 1% slippage
-        
         // Create mock swap data
         let token_in = Bytes::from_str("0x1234567890123456789012345678901234567890").unwrap();
         let token_out = Bytes::from_str("0x0987654321098765432109876543210987654321").unwrap();
@@ -440,7 +661,7 @@ mod tests {
         let expected_amount_out = BigUint::from(2000u32); // 2x return
         let sender_address = Bytes::from_str("0x1111111111111111111111111111111111111111").unwrap();
 
-        let result = build_solution(&swaps, amount_in.clone(), &sender_address, expected_amount_out.clone());
+        let result = build_solution(&swaps, amount_in.clone(), &sender_address, &sender_address, expected_amount_out.clone(), 100);
         
         assert!(result.is_ok());
         let solution = result.unwrap();
@@ -454,16 +675,14 @@ mod tests {
         assert_eq!(solution.given_amount, amount_in);
         assert_eq!(solution.given_token, token_in);
         assert_eq!(solution.checked_token, token_in);
-        
-        // Clean up
-        std::env::remove_var("TYCHO_SLIPPAGE_BPS");
+
+        // Slippage beyond 100% cannot be expressed as a checked amount
+        let result = build_solution(&swaps, amount_in, &sender_address, &sender_address, expected_amount_out, 10_001);
+        assert!(result.is_err());
     }
 
     #[test]
-    fn test_build_solution_default_slippage() {
-        // Remove any existing environment variable to test default
-        std::env::remove_var("TYCHO_SLIPPAGE_BPS");
-        
+    fn test_build_solution_separate_receiver() {
         // Create mock swap data
         let token_in = Bytes::from_str("0x1234567890123456789012345678901234567890").unwrap();
         let token_out = Bytes::from_str("0x0987654321098765432109876543210987654321").unwrap();
@@ -504,19 +723,18 @@ mod tests {
         let amount_in = BigUint::from(1000u32);
         let expected_amount_out = BigUint::from(10000u32);
         let sender_address = Bytes::from_str("0x1111111111111111111111111111111111111111").unwrap();
+        let receiver_address = Bytes::from_str("0x2222222222222222222222222222222222222222").unwrap();
 
-        let result = build_solution(&swaps, amount_in.clone(), &sender_address, expected_amount_out.clone());
+        let result = build_solution(&swaps, amount_in.clone(), &sender_address, &receiver_address, expected_amount_out.clone(), 50);
         
         assert!(result.is_ok());
         let solution = result.unwrap();
+
+        // Output lands at the receiver while the executor stays the sender
+        assert_eq!(solution.sender, sender_address);
+        assert_eq!(solution.receiver, receiver_address);
         
-        // The actual calculation shows 9900, which means there might be some other slippage value set
-        // Let's check what the actual value is and verify it's reasonable
-        // With some slippage BPS on 10000 expected output, we should get a value less than 10000
-        assert!(solution.checked_amount < expected_amount_out);
-        assert!(solution.checked_amount > BigUint::from(9000u32)); // Should be reasonable
-        
-        // Let's also verify the calculation is working by checking it's not the hardcoded 1
-        assert!(solution.checked_amount > BigUint::from(1u32));
+        // With the default 50 BPS on 10000 expected output
+        assert_eq!(solution.checked_amount, BigUint::from(9950u32));
     }
 }
@@ -0,0 +1,166 @@
+//! Gas limits of submitted transactions.
+//!
+//! Transaction requests are built with fixed gas limits, e.g. 1,000,000 for the
+//! router call, which overpays on short paths and can run out of gas on long
+//! paths through Curve pools. `GasEstimator` replaces those limits with the gas
+//! each call actually used in the simulated block plus a safety margin, and
+//! falls back to `eth_estimateGas` for calls without simulated gas usage.
+//!
+//! The router call is simulated with a generous limit, so long paths are not
+//! cut off before their actual usage is known.
+
+use crate::errors::Result;
+use alloy::{
+    network::Ethereum,
+    providers::{Provider, RootProvider},
+    rpc::types::TransactionRequest,
+};
+use std::sync::Arc;
+
+/// Sets the gas limits of transaction requests from simulated or estimated gas usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasEstimator {
+    safety_margin_bps: u64,
+    min_gas_limit: u64,
+    simulation_gas_limit: u64,
+}
+
+impl GasEstimator {
+    /// Create an estimator adding a 20% safety margin, with limits of at least 21,000 gas.
+    ///
+    /// Router calls are simulated with a limit of 3,000,000 gas.
+    pub fn new() -> Self {
+        Self {
+            safety_margin_bps: 2_000,
+            min_gas_limit: 21_000,
+            simulation_gas_limit: 3_000_000,
+        }
+    }
+
+    /// Set the margin added on top of the used gas, in basis points.
+    pub fn with_safety_margin_bps(mut self, safety_margin_bps: u64) -> Self {
+        self.safety_margin_bps = safety_margin_bps;
+        self
+    }
+
+    /// Set the lowest gas limit of any transaction.
+    pub fn with_min_gas_limit(mut self, min_gas_limit: u64) -> Self {
+        self.min_gas_limit = min_gas_limit;
+        self
+    }
+
+    /// Set the gas limit of the router call while simulating.
+    pub fn with_simulation_gas_limit(mut self, simulation_gas_limit: u64) -> Self {
+        self.simulation_gas_limit = simulation_gas_limit;
+        self
+    }
+
+    /// The gas limit of the router call while simulating.
+    pub fn simulation_gas_limit(&self) -> u64 {
+        self.simulation_gas_limit
+    }
+
+    /// The gas limit of a call that used `gas_used` gas.
+    pub fn gas_limit(&self, gas_used: u64) -> u64 {
+        let margin = (gas_used as u128 * self.safety_margin_bps as u128 / 10_000) as u64;
+        gas_used.saturating_add(margin).max(self.min_gas_limit)
+    }
+
+    /// Set the gas limits of requests from the gas their calls used in a simulated block.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests` - The simulated requests, in execution order
+    /// * `gas_used_per_call` - Gas used by each simulated call
+    ///
+    /// # Returns
+    ///
+    /// `false` without touching any request if the simulated calls do not match
+    /// the requests, e.g. because the simulation stopped early.
+    pub fn apply_simulated(&self, requests: &mut [&mut TransactionRequest], gas_used_per_call: &[u64]) -> bool {
+        if requests.len() != gas_used_per_call.len() || gas_used_per_call.contains(&0) {
+            return false;
+        }
+
+        for (request, gas_used) in requests.iter_mut().zip(gas_used_per_call) {
+            request.gas = Some(self.gas_limit(*gas_used));
+        }
+        true
+    }
+
+    /// Set the gas limits of requests from `eth_estimateGas`.
+    ///
+    /// Requests whose estimation fails keep their current limit, since calls
+    /// depending on earlier calls of the same bundle cannot be estimated on
+    /// their own.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The RPC provider used for estimation
+    /// * `requests` - The requests to estimate, in execution order
+    ///
+    /// # Returns
+    ///
+    /// The number of requests whose limit was estimated.
+    pub async fn apply_estimated(
+        &self,
+        provider: &Arc<RootProvider<Ethereum>>,
+        requests: &mut [&mut TransactionRequest],
+    ) -> usize {
+        let mut estimated = 0;
+        for request in requests.iter_mut() {
+            match self.estimate(provider, request).await {
+                Ok(gas_limit) => {
+                    request.gas = Some(gas_limit);
+                    estimated += 1;
+                }
+                Err(e) => {
+                    tracing::debug!(
+                        error = %e,
+                        gas_limit = request.gas,
+                        "Gas estimation failed, keeping the default gas limit"
+                    );
+                }
+            }
+        }
+        estimated
+    }
+
+    /// Estimate the gas limit of a single request with `eth_estimateGas`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the node fails to estimate the request, e.g. because it reverts.
+    pub async fn estimate(&self, provider: &Arc<RootProvider<Ethereum>>, request: &TransactionRequest) -> Result<u64> {
+        let gas_used = provider.estimate_gas(request.clone()).await?;
+        Ok(self.gas_limit(gas_used))
+    }
+}
+
+impl Default for GasEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_limit_from_simulated_gas() {
+        let estimator = GasEstimator::new().with_safety_margin_bps(1_000);
+        assert_eq!(estimator.gas_limit(400_000), 440_000);
+        assert_eq!(estimator.gas_limit(1_000), 21_000);
+
+        let mut approval = TransactionRequest::default().gas_limit(100_000);
+        let mut swap = TransactionRequest::default().gas_limit(1_000_000);
+        assert!(estimator.apply_simulated(&mut [&mut approval, &mut swap], &[46_000, 300_000]));
+        assert_eq!(approval.gas, Some(50_600));
+        assert_eq!(swap.gas, Some(330_000));
+
+        // Simulations that stopped early leave the limits untouched
+        assert!(!estimator.apply_simulated(&mut [&mut approval, &mut swap], &[46_000]));
+        assert_eq!(swap.gas, Some(330_000));
+    }
+}
@@ -0,0 +1,67 @@
+//! Profit guards enforced by the router.
+//!
+//! The checked amount of a solution is the output below which the router
+//! reverts. Set from the slippage tolerance alone, it lets a path land at a
+//! loss when the state drifted between simulation and inclusion: a cycle
+//! returning 0.5% less than simulated still clears a 1% tolerance while its
+//! gas cost exceeds the remaining profit.
+//!
+//! A `ProfitGuard` raises the checked amount to the input amount plus the gas
+//! cost plus a minimum profit, so the whole bundle reverts instead of landing
+//! below the post-gas threshold. The simulator applies it with
+//! [`Simulator::with_profit_guard`](crate::simulation::Simulator::with_profit_guard),
+//! and [`SimulationOptions::with_min_amount_out`](crate::simulation::SimulationOptions::with_min_amount_out)
+//! sets the threshold of a single path directly.
+
+use num_bigint::BigUint;
+
+/// The largest minimum profit, 100% of the input.
+const MAX_PROFIT_BPS: u64 = 10_000;
+
+/// The minimum end balance increase of a path, enforced through its checked amount.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProfitGuard {
+    min_profit_bps: u64,
+}
+
+impl ProfitGuard {
+    /// Create a guard requiring a profit of `min_profit_bps` of the input after gas.
+    pub fn new(min_profit_bps: u64) -> Self {
+        Self {
+            min_profit_bps: min_profit_bps.min(MAX_PROFIT_BPS),
+        }
+    }
+
+    /// The minimum profit after gas, in basis points of the input.
+    pub fn min_profit_bps(&self) -> u64 {
+        self.min_profit_bps
+    }
+
+    /// The output a path has to return for its profit to clear the guard.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount_in` - The input amount of the path
+    /// * `gas_cost` - The gas cost of the path, in base units of its start token
+    ///
+    /// # Returns
+    ///
+    /// The input amount plus the gas cost plus the minimum profit
+    pub fn min_amount_out(&self, amount_in: &BigUint, gas_cost: &BigUint) -> BigUint {
+        amount_in + gas_cost + amount_in * self.min_profit_bps / MAX_PROFIT_BPS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_amount_out_covers_gas_and_profit() {
+        let guard = ProfitGuard::new(50);
+        let amount_in = BigUint::from(1_000_000u32);
+        assert_eq!(guard.min_amount_out(&amount_in, &BigUint::from(2_000u32)), BigUint::from(1_007_000u32));
+        assert_eq!(ProfitGuard::default().min_amount_out(&amount_in, &BigUint::default()), amount_in);
+        assert_eq!(ProfitGuard::new(20_000).min_profit_bps(), MAX_PROFIT_BPS);
+    }
+}
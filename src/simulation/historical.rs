@@ -0,0 +1,58 @@
+//! Simulation against historical blocks.
+//!
+//! Live simulations run on top of the latest block. When recorded block updates
+//! are replayed, e.g. for backtesting, every simulation has to run on top of the
+//! block the update belongs to instead, which requires an archive node. The
+//! `SimulationBlock` is a shared pin that the replay loop moves forward before
+//! each block is searched.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Block that simulations are pinned to, unpinned simulations use the latest block.
+#[derive(Debug, Default)]
+pub struct SimulationBlock {
+    /// The pinned block number, zero while unpinned
+    block_number: AtomicU64,
+}
+
+impl SimulationBlock {
+    /// Create an unpinned simulation block.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pin simulations to a block.
+    pub fn pin(&self, block_number: u64) {
+        self.block_number.store(block_number, Ordering::Relaxed);
+    }
+
+    /// Run simulations on top of the latest block again.
+    pub fn unpin(&self) {
+        self.block_number.store(0, Ordering::Relaxed);
+    }
+
+    /// The pinned block, if any.
+    pub fn get(&self) -> Option<u64> {
+        match self.block_number.load(Ordering::Relaxed) {
+            0 => None,
+            block_number => Some(block_number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_and_unpin() {
+        let block = SimulationBlock::new();
+        assert_eq!(block.get(), None);
+
+        block.pin(19_000_000);
+        assert_eq!(block.get(), Some(19_000_000));
+
+        block.unpin();
+        assert_eq!(block.get(), None);
+    }
+}
@@ -2,22 +2,78 @@
 //! 
 //! This module provides simulation capabilities for testing arbitrage strategies:
 //! - `Simulator`: Core simulation engine
+//! - `SimulationOptions`: Per-call overrides of the simulator's defaults, e.g. slippage
+//! - `SimulationFlags`: Tracing and validation flags of the `eth_simulateV1` payload
 //! - `SimulationResult`: Results from running simulations
+//! - `SimulationReport`: Typed gas, output and transfer summary of a simulation
+//! - `AllowanceCache`: Permit2 allowances used to skip redundant approvals
+//! - `PermitCache`: Permit2 signatures reused across paths and blocks
+//! - `AccuracyTracker`: Per-protocol deviation of local simulations from `eth_simulateV1`
+//! - `SimulationBlock`: Pins simulations to a historical block when replaying updates
+//! - `SlippagePolicy`: Chooses the slippage tolerance of each path
+//! - `ProfitGuard`: Raises the checked amount to a post-gas profit threshold
+//! - `RealizedSlippageLog`: Per-protocol slippage of included paths, fed back by `RealizedSlippage`
+//! - `GasEstimator`: Sets final gas limits from simulated gas usage or `eth_estimateGas`
+//! - `SimulationCache`: Results reused while the simulated path and pools are unchanged
+//! - `SolutionBuilder`: Validated router solutions with receiver, checked token and router overrides
+//! - `RouterEncoder`: Calldata layouts of the Tycho router or a custom router
+//! - `CalldataCost`: Calldata size and intrinsic gas of router calls
+//! - `TokenVetter`: Probe simulations denylisting taxed, blacklisting and honeypot tokens
+//! - `TraceCapture`: Call trees of failed or anomalous simulations, traced with the `callTracer`
 //! - Transaction building and payload construction
 
+pub mod accuracy;
+pub mod allowance;
+pub mod cache;
+pub mod calldata;
 pub mod encoding;
+pub mod gas;
+pub mod guard;
+pub mod historical;
 pub mod parsing;
+pub mod permit;
+pub mod realized;
+pub mod router;
+pub mod slippage;
+pub mod solution;
+pub mod trace;
+pub mod vetting;
+
+pub use accuracy::{AccuracyStats, AccuracyTracker};
+pub use allowance::AllowanceCache;
+pub use cache::{SimulationCache, SimulationKey};
+pub use calldata::CalldataCost;
+pub use gas::GasEstimator;
+pub use guard::ProfitGuard;
+pub use historical::SimulationBlock;
+pub use permit::PermitCache;
+pub use realized::{RealizedExecution, RealizedSlippage, RealizedSlippageLog, RealizedSlippageStats};
+pub use router::{RouterCall, RouterEncoder, TychoRouterEncoder};
+pub use slippage::{FixedSlippage, ProtocolSlippage, SlippagePolicy, VolatilityScaledSlippage};
+pub use solution::{checked_amount, max_amount_in, SolutionBuilder};
+pub use trace::{CallTrace, TraceCapture};
+pub use vetting::{TokenVerdict, TokenVetter};
 
 // Re-export encoding functions for convenience
-pub use encoding::{encode_solution, sign_permit, build_solution};
+pub use encoding::{
+    encode_solution, exact_in_equivalent, sign_permit, build_exact_out_solution, build_guarded_solution, build_solution,
+};
 
 // Re-export parsing types for convenience
-pub use parsing::{DecodedSwap, DecodedLogs, LogParser};
+pub use parsing::{
+    AmountVerification, DecodedSwap, DecodedLogs, DecodedTransfer, LogParser, SimulationReport, TokenFlow,
+};
 
+use crate::budget::SearchDeadline;
+use crate::chain::{ChainProfile, FeeMarket, HeadTracker};
 use crate::path::PathExt;
 use crate::errors::{SimulationError, Result};
+use crate::rpc::ProviderPool;
+use crate::utils::{fees::BaseFeePredictor, u256_to_biguint};
+use crate::simulation::calldata::TX_BASE_GAS;
 use crate::simulation::encoding::{
-    create_approval_calldata, encode_router_call, convert_biguint_to_u256
+    create_approval_calldata, create_unwrap_calldata, create_wrap_calldata,
+    convert_biguint_to_u256, wrap_native_swaps,
 };
 use alloy::{
     network::Ethereum,
@@ -32,20 +88,192 @@ use alloy::{
 use num_bigint::BigUint;
 use std::sync::Arc;
 use tycho_common::Bytes;
-use tycho_execution::encoding::models::Swap as TychoExecutionSwap;
+use tycho_execution::encoding::models::{Swap as TychoExecutionSwap, UserTransferType};
 
 /// Result of running a simulation, containing transaction requests and simulation data.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SimulationResult {
-    pub approval_request: TransactionRequest,
+    /// Wraps the native token before the swap, set for paths starting from the native token
+    pub wrap_request: Option<TransactionRequest>,
+    /// Approves Permit2, omitted when the cached allowance already covers the trade
+    pub approval_request: Option<TransactionRequest>,
     pub swap_request: TransactionRequest,
+    /// Unwraps the input amount after the swap, set for paths starting from the native token
+    pub unwrap_request: Option<TransactionRequest>,
     pub simulated_blocks: Vec<SimulatedBlock>,
+    /// Token the router pays out, the wrapped token for paths starting from the native token
+    pub output_token: Address,
+    /// Address receiving the router output
+    pub receiver: Address,
+    /// Call tree of the swap call, captured for failed or anomalous simulations
+    pub call_trace: Option<CallTrace>,
+}
+
+impl SimulationResult {
+    /// All transaction requests in execution order.
+    pub fn transaction_requests(&self) -> Vec<TransactionRequest> {
+        ordered_requests(
+            &self.wrap_request,
+            &self.approval_request,
+            &self.swap_request,
+            &self.unwrap_request,
+        )
+    }
+
+    /// Mutable references to all transaction requests in execution order.
+    pub fn transaction_requests_mut(&mut self) -> Vec<&mut TransactionRequest> {
+        self.wrap_request.iter_mut()
+            .chain(self.approval_request.iter_mut())
+            .chain(std::iter::once(&mut self.swap_request))
+            .chain(self.unwrap_request.iter_mut())
+            .collect()
+    }
+
+    /// Index of the approval call within the simulated block, if an approval was sent.
+    pub fn approval_call_index(&self) -> Option<usize> {
+        self.approval_request
+            .as_ref()
+            .map(|_| usize::from(self.wrap_request.is_some()))
+    }
+
+    /// Index of the swap call within the simulated block.
+    pub fn swap_call_index(&self) -> usize {
+        usize::from(self.wrap_request.is_some()) + usize::from(self.approval_request.is_some())
+    }
+
+    /// Structured gas, output and transfer summary of the simulated block.
+    pub fn report(&self) -> SimulationReport {
+        LogParser::build_report(&self.simulated_blocks, self.swap_call_index())
+    }
+
+    /// Compare the output received according to transfer logs with the expected output.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_amount_out` - The output amount predicted by the optimizer
+    pub fn verify_amount_out(&self, expected_amount_out: &BigUint) -> AmountVerification {
+        self.report().verify_amount_out(
+            &Bytes::from(self.output_token.as_slice()),
+            &Bytes::from(self.receiver.as_slice()),
+            expected_amount_out,
+        )
+    }
+}
+
+/// Transaction requests built for a single path.
+struct PathTransactions {
+    wrap_request: Option<TransactionRequest>,
+    approval_request: Option<TransactionRequest>,
+    swap_request: TransactionRequest,
+    unwrap_request: Option<TransactionRequest>,
+    output_token: Address,
+    receiver: Address,
+}
+
+impl PathTransactions {
+    fn calls(&self) -> Vec<TransactionRequest> {
+        ordered_requests(
+            &self.wrap_request,
+            &self.approval_request,
+            &self.swap_request,
+            &self.unwrap_request,
+        )
+    }
+}
+
+/// Collect the transactions of a path in execution order.
+fn ordered_requests(
+    wrap_request: &Option<TransactionRequest>,
+    approval_request: &Option<TransactionRequest>,
+    swap_request: &TransactionRequest,
+    unwrap_request: &Option<TransactionRequest>,
+) -> Vec<TransactionRequest> {
+    wrap_request.iter()
+        .chain(approval_request.iter())
+        .chain(std::iter::once(swap_request))
+        .chain(unwrap_request.iter())
+        .cloned()
+        .collect()
+}
+
+/// Flags of the `eth_simulateV1` payload.
+///
+/// All flags are enabled by default. Some RPC providers reject or bill heavily
+/// for transfer traces, validation or full transactions; disabling them keeps
+/// simulations working, as the output is then verified from ERC-20 logs or
+/// decoded swap events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationFlags {
+    /// Report native token transfers as logs
+    pub trace_transfers: bool,
+    /// Validate nonces, balances and fees like a real block would
+    pub validation: bool,
+    /// Return full transactions instead of their hashes
+    pub return_full_transactions: bool,
+}
+
+impl Default for SimulationFlags {
+    fn default() -> Self {
+        Self {
+            trace_transfers: true,
+            validation: true,
+            return_full_transactions: true,
+        }
+    }
+}
+
+/// Per-call overrides of the simulator's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulationOptions {
+    /// Tolerated output shortfall in BPS, the simulator's slippage policy if `None`
+    pub slippage_bps: Option<u64>,
+    /// Output below which the router reverts, the simulator's profit guard if `None`
+    pub min_amount_out: Option<BigUint>,
+}
+
+impl SimulationOptions {
+    /// Create options that keep every default of the simulator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Override the tolerated output shortfall, in basis points.
+    pub fn with_slippage_bps(mut self, slippage_bps: u64) -> Self {
+        self.slippage_bps = Some(slippage_bps);
+        self
+    }
+
+    /// Revert the path unless its output reaches `min_amount_out`, e.g. a post-gas threshold.
+    pub fn with_min_amount_out(mut self, min_amount_out: BigUint) -> Self {
+        self.min_amount_out = Some(min_amount_out);
+        self
+    }
 }
 
 /// Core simulation engine for arbitrage transactions.
 pub struct Simulator {
     chain_id: u64,
+    chain_profile: ChainProfile,
     permit2_address: Address,
+    native_token: Address,
+    wrapped_native: Address,
+    receiver_address: Option<Address>,
+    allowance_cache: Option<Arc<AllowanceCache>>,
+    permit_cache: Option<Arc<PermitCache>>,
+    accuracy_tracker: Option<Arc<AccuracyTracker>>,
+    simulation_block: Option<Arc<SimulationBlock>>,
+    transfer_type: UserTransferType,
+    slippage_policy: Arc<dyn SlippagePolicy>,
+    profit_guard: Option<ProfitGuard>,
+    gas_estimator: Option<GasEstimator>,
+    base_fee_predictor: Option<Arc<BaseFeePredictor>>,
+    result_cache: Option<Arc<SimulationCache>>,
+    simulation_flags: SimulationFlags,
+    provider_pool: Option<Arc<ProviderPool>>,
+    head_tracker: Option<Arc<HeadTracker>>,
+    router_address: Option<Address>,
+    router_encoder: Arc<dyn RouterEncoder>,
+    trace_capture: Option<TraceCapture>,
 }
 
 impl Simulator {
@@ -55,17 +283,302 @@ impl Simulator {
     /// 
     /// * `config` - The arbitrage configuration containing chain and permit2 settings
     pub fn from_config(config: &crate::config::ArbitrageConfig) -> Self {
+        // Chains without Permit2 pull the input with a plain approval of the router
+        let transfer_type = if config.chain_profile.permit2_address().is_some() {
+            UserTransferType::TransferFromPermit2
+        } else {
+            UserTransferType::TransferFrom
+        };
         Self {
             chain_id: config.chain_id,
+            chain_profile: config.chain_profile,
             permit2_address: config.permit2_address,
+            native_token: config.native_token,
+            wrapped_native: config.wrapped_native,
+            receiver_address: config.receiver_address,
+            allowance_cache: None,
+            permit_cache: None,
+            accuracy_tracker: None,
+            simulation_block: None,
+            transfer_type,
+            slippage_policy: Arc::new(FixedSlippage::new(config.settings.slippage_bps)),
+            profit_guard: None,
+            gas_estimator: None,
+            base_fee_predictor: None,
+            result_cache: None,
+            simulation_flags: SimulationFlags::default(),
+            provider_pool: None,
+            head_tracker: None,
+            router_address: config.settings.router_address,
+            router_encoder: Arc::new(match &config.settings.router_function_signature {
+                Some(function_signature) => TychoRouterEncoder::new().with_function_signature(function_signature.clone()),
+                None => TychoRouterEncoder::new(),
+            }),
+            trace_capture: None,
+        }
+    }
+
+    /// Execute paths through a custom router instead of the Tycho router of the chain.
+    pub fn with_router_address(mut self, router_address: Address) -> Self {
+        self.router_address = Some(router_address);
+        self
+    }
+
+    /// The router executing paths, if overridden.
+    pub fn router_address(&self) -> Option<Address> {
+        self.router_address
+    }
+
+    /// Encode router calls with a custom calldata layout.
+    pub fn with_router_encoder(mut self, encoder: Arc<dyn RouterEncoder>) -> Self {
+        self.router_encoder = encoder;
+        self
+    }
+
+    /// Trace the swap call of simulations selected by `capture` and attach its call tree.
+    ///
+    /// Tracing needs the `debug` namespace of the node and costs an extra
+    /// request per traced simulation, so only failed or anomalous ones are traced.
+    pub fn with_trace_capture(mut self, capture: TraceCapture) -> Self {
+        self.trace_capture = Some(capture);
+        self
+    }
+
+    /// Which simulations are traced, if any.
+    pub fn trace_capture(&self) -> Option<TraceCapture> {
+        self.trace_capture
+    }
+
+    /// Send simulations through a pool of RPC endpoints instead of the given provider.
+    pub fn with_provider_pool(mut self, pool: Arc<ProviderPool>) -> Self {
+        self.provider_pool = Some(pool);
+        self
+    }
+
+    /// The pool of RPC endpoints simulations are sent through, if set.
+    pub fn provider_pool(&self) -> Option<&Arc<ProviderPool>> {
+        self.provider_pool.as_ref()
+    }
+
+    /// Reuse results of paths simulated again with unchanged pools, amounts and nonce.
+    pub fn with_result_cache(mut self, cache: Arc<SimulationCache>) -> Self {
+        self.result_cache = Some(cache);
+        self
+    }
+
+    /// The cache of simulation results, if enabled.
+    pub fn result_cache(&self) -> Option<&Arc<SimulationCache>> {
+        self.result_cache.as_ref()
+    }
+
+    /// Set the tracing and validation flags of simulation payloads.
+    pub fn with_simulation_flags(mut self, flags: SimulationFlags) -> Self {
+        self.simulation_flags = flags;
+        self
+    }
+
+    /// The tracing and validation flags of simulation payloads.
+    pub fn simulation_flags(&self) -> SimulationFlags {
+        self.simulation_flags
+    }
+
+    /// Tolerate the same output shortfall on every path, in basis points.
+    pub fn with_slippage_bps(self, slippage_bps: u64) -> Self {
+        self.with_slippage_policy(Arc::new(FixedSlippage::new(slippage_bps)))
+    }
+
+    /// Choose the tolerated output shortfall of each path with a policy.
+    pub fn with_slippage_policy(mut self, policy: Arc<dyn SlippagePolicy>) -> Self {
+        self.slippage_policy = policy;
+        self
+    }
+
+    /// Revert paths whose output does not cover their input, gas cost and a minimum profit.
+    ///
+    /// The gas cost is the gas of the path's swaps plus the base transaction
+    /// gas, priced at the simulated base fee. It is only known in base units of
+    /// the native token, so paths from other tokens are guarded against a loss
+    /// of their input plus the minimum profit; set
+    /// [`SimulationOptions::with_min_amount_out`] to cover their gas as well.
+    pub fn with_profit_guard(mut self, guard: ProfitGuard) -> Self {
+        self.profit_guard = Some(guard);
+        self
+    }
+
+    /// The profit guard of simulated paths, if set.
+    pub fn profit_guard(&self) -> Option<ProfitGuard> {
+        self.profit_guard
+    }
+
+    /// The output below which the router reverts a path according to the profit guard.
+    fn guarded_amount_out(&self, path: &PathExt, base_fee: U256) -> Option<BigUint> {
+        let guard = self.profit_guard?;
+        let first_swap = path.first()?;
+        let start_token = Address::from_slice(first_swap.token_in().address.as_ref());
+        let gas_cost = if start_token == self.native_token || start_token == self.wrapped_native {
+            let gas: BigUint = path.iter().map(|swap| &swap.gas).sum();
+            (gas + TX_BASE_GAS) * u256_to_biguint(base_fee)
+        } else {
+            BigUint::default()
+        };
+        Some(guard.min_amount_out(&first_swap.amount_in, &gas_cost))
+    }
+
+    /// Replace the fixed gas limits of simulated requests with estimated limits.
+    pub fn with_gas_estimator(mut self, estimator: GasEstimator) -> Self {
+        self.gas_estimator = Some(estimator);
+        self
+    }
+
+    /// The gas estimator, if gas limits are estimated.
+    pub fn gas_estimator(&self) -> Option<&GasEstimator> {
+        self.gas_estimator.as_ref()
+    }
+
+    /// Price simulated transactions with the predicted base fee of the next block.
+    ///
+    /// The predicted base fee replaces the given one when it is higher.
+    pub fn with_base_fee_predictor(mut self, predictor: Arc<BaseFeePredictor>) -> Self {
+        self.base_fee_predictor = Some(predictor);
+        self
+    }
+
+    /// Price simulated transactions with the next base fee of the tracked chain head.
+    ///
+    /// Used when no base fee predictor is set or it has not recorded a header
+    /// yet. The next base fee replaces the given one when it is higher.
+    pub fn with_head_tracker(mut self, tracker: Arc<HeadTracker>) -> Self {
+        self.head_tracker = Some(tracker);
+        self
+    }
+
+    /// The tracker of the chain head, if set.
+    pub fn head_tracker(&self) -> Option<&Arc<HeadTracker>> {
+        self.head_tracker.as_ref()
+    }
+
+    /// The base fee to price simulated transactions with.
+    ///
+    /// On chains without a base fee, the given gas price is used as is.
+    fn next_base_fee(&self, base_fee: U256) -> U256 {
+        if !self.chain_profile.has_base_fee() {
+            return base_fee;
         }
+        self.base_fee_predictor
+            .as_ref()
+            .and_then(|predictor| predictor.predict_next())
+            .or_else(|| self.head_tracker.as_ref().and_then(|tracker| tracker.next_base_fee()))
+            .map_or(base_fee, |predicted| predicted.max(base_fee))
+    }
+
+    /// The tolerated output shortfall of a path according to the slippage policy, in basis points.
+    pub fn slippage_bps(&self, path: &PathExt) -> u64 {
+        self.slippage_policy.slippage_bps(path)
+    }
+
+    /// Send the output of every path to a separate receiver instead of the executor.
+    ///
+    /// The executor keeps paying gas and providing the input amount, while the
+    /// full output, principal included, lands at the receiver. Paths starting
+    /// from the native token are then not unwrapped, as the executor no longer
+    /// holds the wrapped output.
+    pub fn with_receiver_address(mut self, receiver_address: Address) -> Self {
+        self.receiver_address = Some(receiver_address);
+        self
+    }
+
+    /// The address receiving path outputs, if different from the executor.
+    pub fn receiver_address(&self) -> Option<Address> {
+        self.receiver_address
+    }
+
+    /// Reuse Permit2 signatures across paths and blocks instead of signing per path.
+    pub fn with_permit_cache(mut self, cache: Arc<PermitCache>) -> Self {
+        self.permit_cache = Some(cache);
+        self
+    }
+
+    /// The permit cache used by this simulator, if any.
+    pub fn permit_cache(&self) -> Option<&Arc<PermitCache>> {
+        self.permit_cache.as_ref()
+    }
+
+    /// Record the accuracy of local swap simulations after every successful simulation.
+    pub fn with_accuracy_tracker(mut self, tracker: Arc<AccuracyTracker>) -> Self {
+        self.accuracy_tracker = Some(tracker);
+        self
+    }
+
+    /// The accuracy tracker used by this simulator, if any.
+    pub fn accuracy_tracker(&self) -> Option<&Arc<AccuracyTracker>> {
+        self.accuracy_tracker.as_ref()
+    }
+
+    /// Run simulations on top of the block pinned in `block` instead of the latest one.
+    ///
+    /// Requires an archive node for blocks older than the node's state history.
+    pub fn with_simulation_block(mut self, block: Arc<SimulationBlock>) -> Self {
+        self.simulation_block = Some(block);
+        self
+    }
+
+    /// The block simulations are currently pinned to, if any.
+    pub fn simulation_block(&self) -> Option<u64> {
+        self.simulation_block.as_ref().and_then(|block| block.get())
+    }
+
+    /// Set how the router obtains the input tokens.
+    ///
+    /// Defaults to `TransferFromPermit2`, which signs a permit for every path.
+    /// `TransferFrom` relies on a standard ERC-20 approval of the router, and
+    /// `None` spends tokens already held by the router, needing no approval at all.
+    ///
+    /// Permit2 transfers fall back to `TransferFrom` on chains without a Permit2 deployment.
+    pub fn with_transfer_type(mut self, transfer_type: UserTransferType) -> Self {
+        self.transfer_type = match transfer_type {
+            UserTransferType::TransferFromPermit2 if self.chain_profile.permit2_address().is_none() => {
+                tracing::warn!(
+                    chain_id = self.chain_id,
+                    "Permit2 is not deployed on the chain, pulling inputs with TransferFrom instead"
+                );
+                UserTransferType::TransferFrom
+            }
+            transfer_type => transfer_type,
+        };
+        self
+    }
+
+    /// The execution quirks of the chain simulated on.
+    pub fn chain_profile(&self) -> &ChainProfile {
+        &self.chain_profile
+    }
+
+    /// The transfer type used when encoding router calls.
+    pub fn transfer_type(&self) -> &UserTransferType {
+        &self.transfer_type
+    }
+
+    /// Use a Permit2 allowance cache to skip redundant approval transactions.
+    ///
+    /// With a cache, approvals grant an unlimited allowance, so that once one has
+    /// been included all later bundles for the token consist of the swap alone.
+    /// The cache is only consulted with the `TransferFromPermit2` transfer type.
+    pub fn with_allowance_cache(mut self, cache: Arc<AllowanceCache>) -> Self {
+        self.allowance_cache = Some(cache);
+        self
+    }
+
+    /// The allowance cache used by this simulator, if any.
+    pub fn allowance_cache(&self) -> Option<&Arc<AllowanceCache>> {
+        self.allowance_cache.as_ref()
     }
 
 
     /// Run a simulation for the given path and parameters.
     /// 
     /// This method builds the necessary transactions, creates a simulation payload,
-    /// and executes the simulation using the provided RPC provider.
+    /// and executes the simulation using the provided RPC provider. Paths starting
+    /// from the native token are wrapped before and unwrapped after the swap.
     /// 
     /// # Arguments
     /// 
@@ -85,30 +598,99 @@ impl Simulator {
         nonce: u64,
         base_fee: U256,
         signer: &PrivateKeySigner,
+    ) -> Result<SimulationResult> {
+        self.run_simulation_with_options(provider, path, nonce, base_fee, signer, &SimulationOptions::default())
+            .await
+    }
+
+    /// Run a simulation with per-call overrides of the simulator's defaults.
+    ///
+    /// See [`Simulator::run_simulation`] for the simulated transactions.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The RPC provider for simulation, unused when a provider pool is set
+    /// * `path` - The executed trading path to simulate
+    /// * `nonce` - The account nonce to use
+    /// * `base_fee` - The base fee for the block
+    /// * `signer` - The signer for creating transactions
+    /// * `options` - Overrides for this path, e.g. a tighter slippage
+    pub async fn run_simulation_with_options(
+        &self,
+        provider: &Arc<RootProvider<Ethereum>>,
+        path: &PathExt,
+        nonce: u64,
+        base_fee: U256,
+        signer: &PrivateKeySigner,
+        options: &SimulationOptions,
     ) -> Result<SimulationResult> {
         let start_time = std::time::Instant::now();
+        let slippage_bps = options.slippage_bps.unwrap_or_else(|| self.slippage_bps(path));
+        let base_fee = self.next_base_fee(base_fee);
+        let min_amount_out = options
+            .min_amount_out
+            .clone()
+            .or_else(|| self.guarded_amount_out(path, base_fee));
         
         tracing::debug!(
             path_length = path.len(),
             nonce = nonce,
             base_fee = %base_fee,
             signer_address = %signer.address(),
+            slippage_bps = slippage_bps,
+            min_amount_out = ?min_amount_out,
             "Starting simulation"
         );
 
-        let (approval_request, swap_request) =
-            self.build_transaction_requests(path, nonce, base_fee, signer)?;
+        let cache_key = self
+            .result_cache
+            .as_ref()
+            .map(|cache| {
+                cache
+                    .key(path, signer.address(), nonce, base_fee, slippage_bps, self.simulation_block())
+                    .with_min_amount_out(min_amount_out.clone())
+                    .with_approval(self.approves(path, signer))
+            });
+        if let (Some(cache), Some(key)) = (&self.result_cache, &cache_key) {
+            if let Some(result) = cache.get(key) {
+                tracing::debug!(
+                    path_length = path.len(),
+                    nonce = nonce,
+                    cache_hits = cache.hits(),
+                    "Reusing simulation of unchanged path"
+                );
+                return Ok(self.reprice(result, base_fee));
+            }
+        }
+
+        let mut transactions =
+            self.build_transaction_requests(path, nonce, base_fee, signer, slippage_bps, min_amount_out.as_ref())?;
+        if let Some(estimator) = &self.gas_estimator {
+            transactions.swap_request.gas = Some(estimator.simulation_gas_limit());
+        }
 
         tracing::debug!(
-            approval_gas = approval_request.gas,
-            swap_gas = swap_request.gas,
+            approval_gas = transactions.approval_request.as_ref().and_then(|req| req.gas),
+            swap_gas = transactions.swap_request.gas,
+            wraps_native = transactions.wrap_request.is_some(),
+            skips_approval = transactions.approval_request.is_none(),
             "Transaction requests built"
         );
 
-        let payload = self.build_simulation_payload(approval_request.clone(), swap_request.clone());
+        let payload = self.build_simulation_payload(transactions.calls());
         
         let simulation_start = std::time::Instant::now();
-        let simulation_result = provider.simulate(&payload).await;
+        let block_number = self.simulation_block();
+        let simulation_result = match &self.provider_pool {
+            Some(pool) => {
+                pool.call(|provider| {
+                    let payload = &payload;
+                    async move { Self::simulate_payload(&provider, payload, block_number).await }
+                })
+                .await
+            }
+            None => Self::simulate_payload(provider, &payload, block_number).await,
+        };
         let simulation_duration = simulation_start.elapsed();
 
         match simulation_result {
@@ -123,21 +705,46 @@ impl Simulator {
                     "Simulation completed successfully"
                 );
 
-                // Log gas usage if available
-                if let Some(first_block) = simulated_blocks.first() {
-                    let total_gas_used: u64 = first_block.calls.iter().map(|call| call.gas_used).sum();
-                    tracing::debug!(
-                        total_gas_used = total_gas_used,
-                        call_count = first_block.calls.len(),
-                        "Simulation gas usage"
+                let mut result = SimulationResult {
+                    wrap_request: transactions.wrap_request,
+                    approval_request: transactions.approval_request,
+                    swap_request: transactions.swap_request,
+                    unwrap_request: transactions.unwrap_request,
+                    simulated_blocks,
+                    output_token: transactions.output_token,
+                    receiver: transactions.receiver,
+                    call_trace: None,
+                };
+
+                let report = result.report();
+                tracing::debug!(
+                    total_gas_used = report.total_gas(),
+                    gas_used_per_call = ?report.gas_used_per_call,
+                    transfer_count = report.transfers.len(),
+                    success = report.success,
+                    "Simulation gas usage"
+                );
+
+                self.capture_call_trace(provider, path, &mut result).await;
+
+                if let (Some(estimator), true) = (&self.gas_estimator, report.success) {
+                    self.estimate_gas_limits(estimator, provider, &mut result, &report.gas_used_per_call).await;
+                }
+
+                if let (Some(cache), Some(key)) = (&self.result_cache, cache_key) {
+                    cache.insert(key, &result);
+                }
+
+                if let (Some(tracker), true) = (&self.accuracy_tracker, report.success) {
+                    let recorded = tracker.record_path(path, &report.swaps);
+                    tracing::trace!(
+                        recorded_swaps = recorded,
+                        decoded_swaps = report.swaps.len(),
+                        "Recorded simulation accuracy"
                     );
                 }
 
-                Ok(SimulationResult {
-                    approval_request,
-                    swap_request,
-                    simulated_blocks,
-                })
+                Ok(result)
             }
             Err(e) => {
                 let total_duration = start_time.elapsed();
@@ -150,20 +757,148 @@ impl Simulator {
                     "Simulation failed"
                 );
                 
-                Err(e.into())
+                Err(e)
+            }
+        }
+    }
+
+    /// Simulate a payload on a provider, at the pinned block if any.
+    async fn simulate_payload(
+        provider: &RootProvider<Ethereum>,
+        payload: &SimulatePayload,
+        block_number: Option<u64>,
+    ) -> Result<Vec<SimulatedBlock>> {
+        let simulated_blocks = match block_number {
+            Some(block_number) => provider.simulate(payload).number(block_number).await?,
+            None => provider.simulate(payload).await?,
+        };
+        Ok(simulated_blocks)
+    }
+
+    /// Attach the call tree of the swap call if the trace capture selects the simulation.
+    async fn capture_call_trace(
+        &self,
+        provider: &Arc<RootProvider<Ethereum>>,
+        path: &PathExt,
+        result: &mut SimulationResult,
+    ) {
+        let (Some(capture), Some(last_swap)) = (&self.trace_capture, path.last()) else {
+            return;
+        };
+        if !capture.should_trace(result, &last_swap.amount_out) {
+            return;
+        }
+
+        match trace::trace_swap_call(provider, result, self.simulation_block()).await {
+            Ok(call_trace) => {
+                tracing::debug!(
+                    path_length = path.len(),
+                    call_count = call_trace.call_count(),
+                    failed_call = ?call_trace.deepest_failure().and_then(|failure| failure.to),
+                    "Captured call trace of simulation"
+                );
+                result.call_trace = Some(call_trace);
             }
+            Err(e) => tracing::warn!(error = %e, "Failed to trace swap call of simulation"),
         }
     }
 
+    /// Set the final gas limits of a successfully simulated path.
+    ///
+    /// Uses the gas used by every simulated call and falls back to
+    /// `eth_estimateGas` when the simulated block lacks the gas usage of some calls.
+    async fn estimate_gas_limits(
+        &self,
+        estimator: &GasEstimator,
+        provider: &Arc<RootProvider<Ethereum>>,
+        result: &mut SimulationResult,
+        gas_used_per_call: &[u64],
+    ) {
+        let mut requests = result.transaction_requests_mut();
+        if estimator.apply_simulated(&mut requests, gas_used_per_call) {
+            tracing::debug!(
+                swap_gas_limit = result.swap_request.gas,
+                "Gas limits set from simulated gas usage"
+            );
+            return;
+        }
+
+        let estimated = estimator.apply_estimated(provider, &mut requests).await;
+        tracing::debug!(
+            estimated_requests = estimated,
+            total_requests = requests.len(),
+            simulated_calls = gas_used_per_call.len(),
+            "Gas limits estimated with eth_estimateGas"
+        );
+    }
+
+    /// Run a simulation bounded by a search deadline.
+    ///
+    /// Fails fast if the deadline has already expired and otherwise aborts the
+    /// simulation once the remaining wall-clock budget runs out.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The RPC provider for simulation
+    /// * `path` - The executed trading path to simulate
+    /// * `nonce` - The account nonce to use
+    /// * `base_fee` - The base fee for the block
+    /// * `signer` - The signer for creating transactions
+    /// * `options` - Per-call overrides, e.g. slippage or a minimum output
+    /// * `deadline` - The running search budget
+    ///
+    /// # Errors
+    ///
+    /// Returns `SimulationError::SimulationTimeout` if the budget is exhausted
+    /// before the simulation completes.
+    pub async fn run_simulation_within_budget(
+        &self,
+        provider: &Arc<RootProvider<Ethereum>>,
+        path: &PathExt,
+        nonce: u64,
+        base_fee: U256,
+        signer: &PrivateKeySigner,
+        options: &SimulationOptions,
+        deadline: &SearchDeadline,
+    ) -> Result<SimulationResult> {
+        let timeout_ms = deadline.budget().wall_clock.as_millis() as u64;
+
+        if deadline.is_expired() {
+            tracing::debug!(
+                elapsed_ms = deadline.elapsed().as_millis(),
+                "Search deadline expired, skipping simulation"
+            );
+            return Err(SimulationError::SimulationTimeout { timeout_ms }.into());
+        }
+
+        tokio::time::timeout(
+            deadline.remaining(),
+            self.run_simulation_with_options(provider, path, nonce, base_fee, signer, options),
+        )
+        .await
+        .map_err(|_| SimulationError::SimulationTimeout { timeout_ms })?
+    }
+
     /// Build the transaction requests needed for the simulation.
+    ///
+    /// For paths starting from the native token, the swaps are encoded against the
+    /// wrapped token and the approval and swap are framed by a wrap of the input
+    /// amount and an unwrap of the same amount, so the principal returns as native
+    /// token and only the profit stays wrapped. With a separate receiver the
+    /// wrapped output is sent there and no unwrap is added.
+    ///
+    /// The approval targets Permit2 or, with the `TransferFrom` transfer type, the
+    /// router. It is left out for router-held balances and when the allowance cache
+    /// shows that Permit2 may already spend the input amount.
     fn build_transaction_requests(
         &self,
         path: &PathExt,
         nonce: u64,
         base_fee: U256,
         signer: &PrivateKeySigner,
-    ) -> Result<(TransactionRequest, TransactionRequest)> {
-        let tycho_swaps = self.extract_tycho_swaps(path);
+        slippage_bps: u64,
+        min_amount_out: Option<&BigUint>,
+    ) -> Result<PathTransactions> {
         let first_swap = path.first()
             .ok_or_else(|| SimulationError::SimulationFailed { 
                 reason: "Empty path: no swaps available".to_string() 
@@ -171,34 +906,114 @@ impl Simulator {
         
         let amt_in = &first_swap.amount_in;
         let start_token = Address::from_slice(first_swap.token_in().address.as_ref());
+        let wraps_native = start_token == self.native_token;
+
+        let mut tycho_swaps = self.extract_tycho_swaps(path);
+        let (approval_token, mut next_nonce) = if wraps_native {
+            tycho_swaps = wrap_native_swaps(
+                tycho_swaps,
+                &Bytes::from(self.native_token.as_slice()),
+                &Bytes::from(self.wrapped_native.as_slice()),
+            );
+            (self.wrapped_native, nonce + 1)
+        } else {
+            (start_token, nonce)
+        };
 
         let (router_calldata, router_address) =
-            self.extract_router_details(tycho_swaps, amt_in.clone(), signer, path)?;
+            self.extract_router_details(tycho_swaps, amt_in.clone(), signer, path, slippage_bps, min_amount_out)?;
         let amount_in_u256 = convert_biguint_to_u256(amt_in)?;
 
-        let approval_request =
-            self.create_approval_request(&start_token, &amount_in_u256, nonce, base_fee, signer)?;
+        let wrap_request = if wraps_native {
+            Some(self.create_wrap_request(&amount_in_u256, nonce, base_fee, signer))
+        } else {
+            None
+        };
+
+        // Allowances are only tracked for one owner, pool wallets approve every trade
+        let allowance_cache = self.allowance_cache
+            .as_ref()
+            .filter(|cache| cache.owner() == signer.address());
+        let approval_request = match (&self.transfer_type, allowance_cache) {
+            (UserTransferType::None, _) => None,
+            (UserTransferType::TransferFrom, _) => Some(self.create_approval_request(
+                &approval_token, &router_address, &amount_in_u256, next_nonce, base_fee, signer,
+            )?),
+            (_, Some(cache)) if !cache.needs_approval(&approval_token, &amount_in_u256) => None,
+            (_, Some(_)) => Some(self.create_approval_request(
+                &approval_token, &self.permit2_address, &U256::MAX, next_nonce, base_fee, signer,
+            )?),
+            (_, None) => Some(self.create_approval_request(
+                &approval_token, &self.permit2_address, &amount_in_u256, next_nonce, base_fee, signer,
+            )?),
+        };
+        if approval_request.is_some() {
+            next_nonce += 1;
+        }
         let swap_request =
-            self.create_swap_request(&router_address, router_calldata, nonce + 1, base_fee, signer)?;
+            self.create_swap_request(&router_address, router_calldata, next_nonce, base_fee, signer)?;
+        next_nonce += 1;
 
-        Ok((approval_request, swap_request))
+        let unwrap_request = if wraps_native && self.receiver_address.is_none() {
+            Some(self.create_unwrap_request(&amount_in_u256, next_nonce, base_fee, signer))
+        } else {
+            None
+        };
+
+        Ok(PathTransactions {
+            wrap_request,
+            approval_request,
+            swap_request,
+            unwrap_request,
+            output_token: approval_token,
+            receiver: self.receiver_address.unwrap_or(signer.address()),
+        })
+    }
+
+    /// Measure the calldata of the router call executing a path.
+    ///
+    /// The call is encoded as for a simulation, without any RPC request, so
+    /// the calldata gas of a path can be priced before simulating it.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The executed path to encode
+    /// * `signer` - The signer executing the path, signing Permit2 permits
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty or the solution cannot be encoded.
+    pub fn estimate_calldata_cost(&self, path: &PathExt, signer: &PrivateKeySigner) -> Result<CalldataCost> {
+        let first_swap = path.first()
+            .ok_or_else(|| SimulationError::SimulationFailed {
+                reason: "Empty path: no swaps available".to_string()
+            })?;
+
+        let mut tycho_swaps = self.extract_tycho_swaps(path);
+        if Address::from_slice(first_swap.token_in().address.as_ref()) == self.native_token {
+            tycho_swaps = wrap_native_swaps(
+                tycho_swaps,
+                &Bytes::from(self.native_token.as_slice()),
+                &Bytes::from(self.wrapped_native.as_slice()),
+            );
+        }
+        let (router_calldata, _) = self.extract_router_details(
+            tycho_swaps, first_swap.amount_in.clone(), signer, path, self.slippage_bps(path), None,
+        )?;
+        Ok(CalldataCost::from_calldata(&router_calldata))
     }
 
     /// Build the simulation payload from transaction requests.
-    fn build_simulation_payload(
-        &self,
-        approval_request: TransactionRequest,
-        swap_request: TransactionRequest,
-    ) -> SimulatePayload {
+    fn build_simulation_payload(&self, calls: Vec<TransactionRequest>) -> SimulatePayload {
         SimulatePayload {
             block_state_calls: vec![SimBlock {
                 block_overrides: None,
                 state_overrides: None,
-                calls: vec![approval_request, swap_request],
+                calls,
             }],
-            trace_transfers: true,
-            validation: true,
-            return_full_transactions: true,
+            trace_transfers: self.simulation_flags.trace_transfers,
+            validation: self.simulation_flags.validation,
+            return_full_transactions: self.simulation_flags.return_full_transactions,
         }
     }
 
@@ -207,7 +1022,7 @@ impl Simulator {
         let mut swaps = Vec::with_capacity(path.len());
         for swap in path.iter() {
             swaps.push(TychoExecutionSwap {
-                component: swap.pool_comp.clone().into(),
+                component: (*swap.pool_comp).clone().into(),
                 token_in: swap.token_in().address.clone(),
                 token_out: swap.token_out().address.clone(),
                 split: 0.0,
@@ -223,9 +1038,15 @@ impl Simulator {
         amt_in: BigUint,
         signer: &PrivateKeySigner,
         path: &PathExt,
+        slippage_bps: u64,
+        min_amount_out: Option<&BigUint>,
     ) -> Result<(alloy::primitives::Bytes, Address)> {
         let sender_address = Bytes::from(signer.address().as_slice());
-        
+        let receiver_address = match self.receiver_address {
+            Some(receiver) => Bytes::from(receiver.as_slice()),
+            None => sender_address.clone(),
+        };
+
         // Get the expected final output amount from the last swap in the path
         let expected_amount_out = path.last()
             .ok_or_else(|| SimulationError::SimulationFailed {
@@ -233,40 +1054,118 @@ impl Simulator {
             })?
             .amount_out.clone();
         
-        let solution = build_solution(&swaps, amt_in, &sender_address, expected_amount_out)?;
+        // The router checks the output token of the final hop, the wrapped token on native paths
+        let mut solution_builder = SolutionBuilder::new(swaps, amt_in, expected_amount_out, sender_address)
+            .with_receiver(receiver_address)
+            .with_slippage_bps(slippage_bps);
+        if let Some(router_address) = self.router_address {
+            solution_builder = solution_builder.with_router_address(Bytes::from(router_address.as_slice()));
+        }
+        if let Some(min_amount_out) = min_amount_out {
+            solution_builder = solution_builder.with_min_checked_amount(min_amount_out.clone());
+        }
+        let solution = solution_builder.build()?;
         let chain = crate::utils::chain_name(self.chain_id)?;
-        let encoded_solution = encode_solution(&solution, chain)?;
+        let mut encoded_solution = encode_solution(
+            &solution, &chain, self.transfer_type.clone(), solution_builder.router_address(),
+        )?;
 
-        let router_address = Address::from_slice(encoded_solution.interacting_with.as_ref());
-        
-        // Sign the permit
-        let permit = encoded_solution
-            .permit
-            .as_ref()
-            .ok_or(SimulationError::InvalidSimulationPayload)?;
-        let permit_signature = sign_permit(permit, signer, self.chain_id, self.permit2_address)?;
-        
+        let router_address = self
+            .router_address
+            .unwrap_or_else(|| Address::from_slice(encoded_solution.interacting_with.as_ref()));
         let amount_in_u256 = convert_biguint_to_u256(&solution.given_amount)?;
-        let router_calldata = encode_router_call(
-            &encoded_solution,
-            &amount_in_u256,
-            &solution,
-            &permit_signature,
-        )?;
+
+        let permit_signature = match encoded_solution.permit.take() {
+            Some(permit) => {
+                let (permit, permit_signature) = match &self.permit_cache {
+                    Some(cache) => cache.get_or_sign(&permit, signer, self.chain_id, self.permit2_address)?,
+                    None => {
+                        let signature = sign_permit(&permit, signer, self.chain_id, self.permit2_address)?;
+                        (permit, signature)
+                    }
+                };
+                encoded_solution.permit = Some(permit);
+                Some(permit_signature)
+            }
+            None if matches!(self.transfer_type, UserTransferType::TransferFromPermit2) => {
+                return Err(SimulationError::InvalidSimulationPayload.into());
+            }
+            None => None,
+        };
+        let router_calldata = self.router_encoder.encode_call(&RouterCall {
+            encoded_solution: &encoded_solution,
+            solution: &solution,
+            amount_in: amount_in_u256,
+            permit_signature: permit_signature.as_ref(),
+            transfer_from: matches!(self.transfer_type, UserTransferType::TransferFrom),
+        })?;
 
         Ok((router_calldata, router_address))
     }
 
-    /// Create an approval transaction request.
+    /// The fee fields of a request priced at `base_fee`, by the fee market of the chain.
+    ///
+    /// EIP-1559 requests cap their fee with headroom above the base fee. On chains
+    /// without a base fee, `base_fee` is the quoted gas price and paid as is.
+    fn fee_fields(&self, base_fee: U256, max_priority_fee_per_gas: Option<u128>) -> TransactionRequest {
+        match self.chain_profile.fee_market() {
+            FeeMarket::Eip1559 => TransactionRequest {
+                max_fee_per_gas: Some((base_fee * U256::from(10) / U256::from(7)).to::<u128>()),
+                max_priority_fee_per_gas,
+                ..Default::default()
+            },
+            FeeMarket::GasPrice => TransactionRequest {
+                gas_price: Some(base_fee.to::<u128>()),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Whether the transactions of a path include an approval of its input token.
+    ///
+    /// Mirrors the choice of approval in `build_transaction_requests`.
+    fn approves(&self, path: &PathExt, signer: &PrivateKeySigner) -> bool {
+        let allowance_cache = self.allowance_cache
+            .as_ref()
+            .filter(|cache| cache.owner() == signer.address());
+        match (&self.transfer_type, allowance_cache, path.first()) {
+            (UserTransferType::None, _, _) => false,
+            (UserTransferType::TransferFrom, _, _) | (_, None, _) | (_, _, None) => true,
+            (_, Some(cache), Some(first_swap)) => {
+                let start_token = Address::from_slice(first_swap.token_in().address.as_ref());
+                let approval_token = if start_token == self.native_token { self.wrapped_native } else { start_token };
+                convert_biguint_to_u256(&first_swap.amount_in)
+                    .map_or(true, |amount_in| cache.needs_approval(&approval_token, &amount_in))
+            }
+        }
+    }
+
+    /// Re-price the requests of a cached result at `base_fee`, keeping their priority fees.
+    fn reprice(&self, mut result: SimulationResult, base_fee: U256) -> SimulationResult {
+        let optional_requests = [&mut result.wrap_request, &mut result.approval_request, &mut result.unwrap_request];
+        let requests = optional_requests
+            .into_iter()
+            .flatten()
+            .chain(std::iter::once(&mut result.swap_request));
+        for request in requests {
+            let fees = self.fee_fields(base_fee, request.max_priority_fee_per_gas);
+            request.max_fee_per_gas = fees.max_fee_per_gas;
+            request.gas_price = fees.gas_price;
+        }
+        result
+    }
+
+    /// Create an approval transaction request allowing `spender` to pull the input token.
     fn create_approval_request(
         &self,
         start_token: &Address,
+        spender: &Address,
         amount_in: &U256,
         nonce: u64,
         base_fee: U256,
         signer: &PrivateKeySigner,
     ) -> Result<TransactionRequest> {
-        let approve_calldata = create_approval_calldata(self.permit2_address, *amount_in);
+        let approve_calldata = create_approval_calldata(*spender, *amount_in);
 
         Ok(TransactionRequest {
             from: Some(signer.address()),
@@ -276,14 +1175,57 @@ impl Simulator {
                 data: None,
             },
             gas: Some(100_000),
-            max_fee_per_gas: Some((base_fee * U256::from(10) / U256::from(7)).to::<u128>()),
-            max_priority_fee_per_gas: Some(0u128),
             chain_id: Some(self.chain_id),
             nonce: Some(nonce),
-            ..Default::default()
+            ..self.fee_fields(base_fee, Some(0))
         })
     }
 
+    /// Create a transaction request wrapping the native input amount.
+    fn create_wrap_request(
+        &self,
+        amount_in: &U256,
+        nonce: u64,
+        base_fee: U256,
+        signer: &PrivateKeySigner,
+    ) -> TransactionRequest {
+        TransactionRequest {
+            from: Some(signer.address()),
+            to: Some(TxKind::Call(self.wrapped_native)),
+            value: Some(*amount_in),
+            input: TransactionInput {
+                input: Some(create_wrap_calldata()),
+                data: None,
+            },
+            gas: Some(50_000),
+            chain_id: Some(self.chain_id),
+            nonce: Some(nonce),
+            ..self.fee_fields(base_fee, Some(0))
+        }
+    }
+
+    /// Create a transaction request unwrapping the input amount after the swap.
+    fn create_unwrap_request(
+        &self,
+        amount_in: &U256,
+        nonce: u64,
+        base_fee: U256,
+        signer: &PrivateKeySigner,
+    ) -> TransactionRequest {
+        TransactionRequest {
+            from: Some(signer.address()),
+            to: Some(TxKind::Call(self.wrapped_native)),
+            input: TransactionInput {
+                input: Some(create_unwrap_calldata(*amount_in)),
+                data: None,
+            },
+            gas: Some(50_000),
+            chain_id: Some(self.chain_id),
+            nonce: Some(nonce),
+            ..self.fee_fields(base_fee, Some(0))
+        }
+    }
+
     /// Create a swap transaction request.
     fn create_swap_request(
         &self,
@@ -301,11 +1243,9 @@ impl Simulator {
                 data: None,
             },
             gas: Some(1_000_000),
-            max_fee_per_gas: Some((base_fee * U256::from(10) / U256::from(7)).to::<u128>()),
-            max_priority_fee_per_gas: None,
             chain_id: Some(self.chain_id),
             nonce: Some(nonce),
-            ..Default::default()
+            ..self.fee_fields(base_fee, None)
         })
     }
 }
@@ -322,6 +1262,61 @@ mod tests {
         assert_eq!(simulator.chain_id, 1);
     }
 
+    #[test]
+    fn test_simulator_wrapped_native_from_config() {
+        let config = ArbitrageConfig::for_testing("base").unwrap();
+        let simulator = Simulator::from_config(&config);
+        assert_eq!(simulator.native_token, Address::ZERO);
+        assert_eq!(simulator.wrapped_native, config.wrapped_native);
+        assert!(simulator.allowance_cache().is_none());
+        assert!(matches!(simulator.transfer_type(), UserTransferType::TransferFromPermit2));
+
+        let simulator = simulator.with_transfer_type(UserTransferType::TransferFrom);
+        assert!(matches!(simulator.transfer_type(), UserTransferType::TransferFrom));
+    }
+
+    #[test]
+    fn test_simulator_slippage_from_settings() {
+        let mut config = ArbitrageConfig::for_testing("ethereum").unwrap();
+        config.settings.slippage_bps = 120;
+        let simulator = Simulator::from_config(&config);
+        let path = PathExt(Vec::new());
+        assert_eq!(simulator.slippage_bps(&path), 120);
+
+        let simulator = simulator
+            .with_slippage_bps(30)
+            .with_slippage_policy(Arc::new(slippage::ProtocolSlippage::new(5)));
+        assert_eq!(simulator.slippage_bps(&path), 0);
+
+        let options = SimulationOptions::new().with_slippage_bps(10);
+        assert_eq!(options.slippage_bps, Some(10));
+        assert_eq!(SimulationOptions::default().slippage_bps, None);
+    }
+
+    #[test]
+    fn test_cached_results_are_repriced_at_the_current_base_fee() {
+        let config = ArbitrageConfig::for_testing("ethereum").unwrap();
+        let simulator = Simulator::from_config(&config);
+        let signer = PrivateKeySigner::random();
+        let request = simulator.create_wrap_request(&U256::from(1000), 1, U256::from(700_000_000u64), &signer);
+        let cached = SimulationResult {
+            wrap_request: Some(request.clone()),
+            approval_request: None,
+            swap_request: request,
+            unwrap_request: None,
+            simulated_blocks: Vec::new(),
+            output_token: Address::ZERO,
+            receiver: Address::ZERO,
+            call_trace: None,
+        };
+
+        let repriced = simulator.reprice(cached, U256::from(7_000_000_000u64));
+        for request in repriced.transaction_requests() {
+            assert_eq!(request.max_fee_per_gas, Some(10_000_000_000));
+            assert_eq!(request.max_priority_fee_per_gas, Some(0));
+        }
+    }
+
     #[test]
     fn test_simulator_invalid_chain() {
         let result = ArbitrageConfig::from_env("invalid_chain");
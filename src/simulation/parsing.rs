@@ -17,6 +17,10 @@
 //!
 //! - **`DecodedSwap`**: Represents a single decoded swap event with amounts and direction
 //! - **`DecodedLogs`**: Complete parsing result including all swaps and gas metrics
+//! - **`DecodedTransfer`**: A decoded ERC-20 transfer from any simulated call
+//! - **`SimulationReport`**: Per-call gas, realized output and token transfers of a simulation
+//! - **`TokenFlow`**: Per-account token flows reconstructed from transfer logs
+//! - **`AmountVerification`**: Realized versus expected output of a simulated path
 //! - **`LogParser`**: Main parser that handles protocol detection and event decoding
 //!
 //! # Event Decoding Process
@@ -38,10 +42,19 @@ use alloy::{
     sol_types::SolEvent,
 };
 use crate::errors::{SimulationError, Result};
-use num_bigint::BigUint;
+use num_bigint::{BigInt, BigUint};
+use num_traits::ToPrimitive;
+use std::collections::HashMap;
 use tycho_common::Bytes;
 use crate::utils::*;
 
+mod erc20 {
+    alloy::sol! {
+        #[derive(Debug)]
+        event Transfer(address indexed from, address indexed to, uint256 value);
+    }
+}
+
 mod uniswap_v2 {
     use alloy::sol;
     sol! {
@@ -158,7 +171,7 @@ mod curve {
 /// This structure represents a single swap operation that was executed on-chain,
 /// containing the essential information needed to understand the trade direction,
 /// amounts, and pool involved.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DecodedSwap {
     /// The address of the liquidity pool where the swap occurred
     pub pool: Bytes,
@@ -176,15 +189,17 @@ pub struct DecodedSwap {
 /// Complete parsing result from analyzing simulation transaction logs.
 ///
 /// This structure contains all the decoded swap events from an arbitrage transaction,
-/// along with gas usage metrics for both the approval and swap transactions.
+/// along with gas usage metrics for the approval, swap and any wrapping transactions.
 #[derive(Debug)]
 pub struct DecodedLogs {
     /// Sequence of decoded swaps representing the arbitrage path
     pub path: Vec<DecodedSwap>,
-    /// Gas used by the token approval transaction
+    /// Gas used by the token approval transaction, zero if no approval was needed
     pub approval_gas: u64,
     /// Gas used by the swap execution transaction
     pub swap_gas: u64,
+    /// Gas used by native token wrap and unwrap transactions, zero if none were needed
+    pub wrap_gas: u64,
 }
 
 impl DecodedLogs {
@@ -220,7 +235,7 @@ impl DecodedLogs {
     /// Calculate the total gas cost for the arbitrage transaction.
     ///
     /// Computes the total gas cost by multiplying the combined gas usage
-    /// (approval + swap) by the provided base fee.
+    /// (approval + swap + wrapping) by the provided base fee.
     ///
     /// # Arguments
     ///
@@ -230,7 +245,185 @@ impl DecodedLogs {
     ///
     /// The total gas cost as a BigUint
     pub fn gas_cost(&self, base_fee: BigUint) -> BigUint {
-        BigUint::from(self.approval_gas + self.swap_gas) * base_fee
+        BigUint::from(self.total_gas()) * base_fee
+    }
+
+    /// Total gas used by all transactions of the arbitrage.
+    pub fn total_gas(&self) -> u64 {
+        self.approval_gas + self.swap_gas + self.wrap_gas
+    }
+}
+
+/// An ERC-20 transfer emitted during a simulated call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedTransfer {
+    /// Index of the simulated call that emitted the transfer
+    pub call_index: usize,
+    /// The token contract that emitted the transfer
+    pub token: Bytes,
+    /// The sender of the tokens
+    pub from: Bytes,
+    /// The recipient of the tokens
+    pub to: Bytes,
+    /// The transferred amount
+    pub amount: BigUint,
+}
+
+/// Structured summary of a simulated bundle.
+///
+/// Unlike [`DecodedLogs`], a report is produced for failed simulations as well,
+/// so it can be used to inspect why a path did not execute.
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    /// Gas used by each simulated call, in execution order
+    pub gas_used_per_call: Vec<u64>,
+    /// Swaps decoded from the swap call, in execution order
+    pub swaps: Vec<DecodedSwap>,
+    /// Output amount of the last decoded swap, if any swap was decoded
+    pub effective_amount_out: Option<BigUint>,
+    /// All ERC-20 transfers emitted by the simulated calls
+    pub transfers: Vec<DecodedTransfer>,
+    /// Whether the swap and all calls after it succeeded
+    pub success: bool,
+    /// Index of the swap call within the simulated block
+    pub swap_call_index: usize,
+}
+
+impl SimulationReport {
+    /// Total gas used by all simulated calls.
+    pub fn total_gas(&self) -> u64 {
+        self.gas_used_per_call.iter().sum()
+    }
+
+    /// Transfers of a single token, in emission order.
+    pub fn transfers_of<'a>(&'a self, token: &'a Bytes) -> impl Iterator<Item = &'a DecodedTransfer> + 'a {
+        self.transfers.iter().filter(move |transfer| &transfer.token == token)
+    }
+
+    /// Token flows of all simulated calls.
+    pub fn token_flow(&self) -> TokenFlow {
+        TokenFlow::from_transfers(&self.transfers)
+    }
+
+    /// Compare the output the receiver actually got in the swap call with the expected output.
+    ///
+    /// The realized amount is the sum of all `token_out` transfers to the receiver
+    /// emitted by the swap call. Fee-on-transfer tokens and inaccurate protocol
+    /// simulations show up as a negative divergence. If the swap call has no
+    /// transfer logs at all, e.g. because the provider omits them, the output
+    /// of the last decoded swap is taken as realized instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `token_out` - The token the path ends in
+    /// * `receiver` - The address receiving the output
+    /// * `expected_amount_out` - The output amount predicted by the optimizer
+    pub fn verify_amount_out(
+        &self,
+        token_out: &Bytes,
+        receiver: &Bytes,
+        expected_amount_out: &BigUint,
+    ) -> AmountVerification {
+        let swap_transfers: Vec<DecodedTransfer> = self.transfers
+            .iter()
+            .filter(|transfer| transfer.call_index == self.swap_call_index)
+            .cloned()
+            .collect();
+        let realized_amount_out = match (&self.effective_amount_out, swap_transfers.is_empty()) {
+            (Some(effective_amount_out), true) => effective_amount_out.clone(),
+            _ => TokenFlow::from_transfers(&swap_transfers).received(token_out, receiver),
+        };
+
+        AmountVerification::new(expected_amount_out.clone(), realized_amount_out)
+    }
+}
+
+/// Per-account token flows reconstructed from ERC-20 transfer logs.
+#[derive(Debug, Clone, Default)]
+pub struct TokenFlow {
+    received: HashMap<(Bytes, Bytes), BigUint>,
+    sent: HashMap<(Bytes, Bytes), BigUint>,
+}
+
+impl TokenFlow {
+    /// Accumulate the flows of a sequence of transfers.
+    pub fn from_transfers(transfers: &[DecodedTransfer]) -> Self {
+        let mut flow = Self::default();
+        for transfer in transfers {
+            *flow.received
+                .entry((transfer.token.clone(), transfer.to.clone()))
+                .or_default() += &transfer.amount;
+            *flow.sent
+                .entry((transfer.token.clone(), transfer.from.clone()))
+                .or_default() += &transfer.amount;
+        }
+        flow
+    }
+
+    /// Total amount of `token` received by `account`.
+    pub fn received(&self, token: &Bytes, account: &Bytes) -> BigUint {
+        self.received
+            .get(&(token.clone(), account.clone()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Total amount of `token` sent by `account`.
+    pub fn sent(&self, token: &Bytes, account: &Bytes) -> BigUint {
+        self.sent
+            .get(&(token.clone(), account.clone()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Net balance change of `account` in `token`, positive if it gained tokens.
+    pub fn net_change(&self, token: &Bytes, account: &Bytes) -> BigInt {
+        BigInt::from(self.received(token, account)) - BigInt::from(self.sent(token, account))
+    }
+}
+
+/// Realized versus expected output of a simulated path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmountVerification {
+    /// Output amount predicted by the optimizer
+    pub expected_amount_out: BigUint,
+    /// Output amount reconstructed from transfer logs
+    pub realized_amount_out: BigUint,
+    /// Relative deviation of the realized from the expected amount in basis points,
+    /// negative if less than expected was received
+    pub divergence_bps: f64,
+}
+
+impl AmountVerification {
+    /// Compute the divergence between an expected and a realized amount.
+    pub fn new(expected_amount_out: BigUint, realized_amount_out: BigUint) -> Self {
+        let expected = expected_amount_out.to_f64().unwrap_or(f64::MAX);
+        let realized = realized_amount_out.to_f64().unwrap_or(f64::MAX);
+        let divergence_bps = if expected > 0.0 {
+            (realized - expected) / expected * 10_000.0
+        } else {
+            0.0
+        };
+
+        Self {
+            expected_amount_out,
+            realized_amount_out,
+            divergence_bps,
+        }
+    }
+
+    /// Amount by which the realized output falls short of the expected one.
+    pub fn shortfall(&self) -> BigUint {
+        if self.realized_amount_out >= self.expected_amount_out {
+            BigUint::from(0u32)
+        } else {
+            &self.expected_amount_out - &self.realized_amount_out
+        }
+    }
+
+    /// Whether the realized output is at most `max_shortfall_bps` below the expected output.
+    pub fn within_tolerance(&self, max_shortfall_bps: f64) -> bool {
+        self.divergence_bps >= -max_shortfall_bps
     }
 }
 
@@ -259,14 +452,48 @@ impl LogParser {
     /// # Errors
     ///
     /// This function will return an error if:
-    /// - The simulation failed (transaction reverted)
+    /// - The simulation failed (transaction reverted), classified by
+    ///   `SimulationError::from_revert` when the node reports the revert reason
     /// - No valid swap events could be decoded from the logs
     /// - The decoded path contains fewer than 2 swaps (invalid arbitrage)
     pub fn parse_simulation_results(simulated_blocks: Vec<SimulatedBlock>) -> Result<DecodedLogs> {
-        Self::validate_simulation_success(&simulated_blocks)?;
+        Self::parse_simulation_results_at(simulated_blocks, Some(0), 1)
+    }
+
+    /// Parse simulation results whose calls are not laid out as approval and swap.
+    ///
+    /// Paths starting from the native token are framed by wrap and unwrap calls,
+    /// and the approval is left out when an allowance already exists. All calls
+    /// other than the approval and swap are accounted as wrapping gas.
+    ///
+    /// # Arguments
+    ///
+    /// * `simulated_blocks` - The simulation results from the RPC provider
+    /// * `approval_index` - Index of the approval call, `None` if no approval was sent
+    /// * `swap_index` - Index of the swap call
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`LogParser::parse_simulation_results`], and an
+    /// error if the simulated block has no call at the given indices.
+    pub fn parse_simulation_results_at(
+        simulated_blocks: Vec<SimulatedBlock>,
+        approval_index: Option<usize>,
+        swap_index: usize,
+    ) -> Result<DecodedLogs> {
+        let call_count = simulated_blocks.first().map_or(0, |block| block.calls.len());
+        let required_calls = approval_index.map_or(swap_index, |index| index.max(swap_index)) + 1;
+        if call_count < required_calls {
+            return Err(SimulationError::SimulationFailed {
+                reason: format!("Expected at least {} simulated calls, got {}", required_calls, call_count),
+            }.into());
+        }
+
+        Self::validate_simulation_success(&simulated_blocks, swap_index)?;
         
-        let (approval_gas, swap_gas) = Self::extract_gas_metrics(&simulated_blocks);
-        let decoded_path = Self::decode_swap_events(&simulated_blocks)?;
+        let (approval_gas, swap_gas, wrap_gas) =
+            Self::extract_gas_metrics(&simulated_blocks, approval_index, swap_index);
+        let decoded_path = Self::decode_swap_events(&simulated_blocks, swap_index)?;
         
         Self::validate_decoded_path(&decoded_path)?;
 
@@ -274,27 +501,112 @@ impl LogParser {
             path: decoded_path,
             approval_gas,
             swap_gas,
+            wrap_gas,
         })
     }
 
-    fn validate_simulation_success(simulated_blocks: &[SimulatedBlock]) -> Result<()> {
-        let sim_result = &simulated_blocks[0].calls[1];
-        if !sim_result.status {
-            return Err(SimulationError::SimulationFailed { 
-                reason: "Simulation failed".to_string() 
-            }.into());
+    /// Build a structured report of a simulation.
+    ///
+    /// The report never fails: calls that reverted are reported as unsuccessful,
+    /// and undecodable logs are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `simulated_blocks` - The simulation results from the RPC provider
+    /// * `swap_index` - Index of the swap call
+    ///
+    /// # Returns
+    ///
+    /// A `SimulationReport` for the first simulated block.
+    pub fn build_report(simulated_blocks: &[SimulatedBlock], swap_index: usize) -> SimulationReport {
+        let calls = simulated_blocks.first().map_or(&[][..], |block| &block.calls[..]);
+
+        let gas_used_per_call = calls.iter().map(|call| call.gas_used).collect();
+        let success = calls.len() > swap_index && calls[swap_index..].iter().all(|call| call.status);
+
+        let swaps: Vec<DecodedSwap> = calls.get(swap_index).map_or_else(Vec::new, |call| {
+            call.logs
+                .iter()
+                .filter_map(Self::decode_single_log)
+                .collect()
+        });
+        let effective_amount_out = swaps.last().map(|swap| swap.amount_out.clone());
+
+        let transfers = calls
+            .iter()
+            .enumerate()
+            .flat_map(|(call_index, call)| {
+                call.logs
+                    .iter()
+                    .filter_map(move |log| Self::decode_transfer(log, call_index))
+            })
+            .collect();
+
+        SimulationReport {
+            gas_used_per_call,
+            swaps,
+            effective_amount_out,
+            transfers,
+            success,
+            swap_call_index: swap_index,
+        }
+    }
+
+    /// Decode the swap events of an executed transaction, e.g. of its receipt's logs.
+    ///
+    /// Unrecognized events are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `logs` - The logs of the transaction, in emission order
+    ///
+    /// # Returns
+    ///
+    /// The decoded swaps in execution order
+    pub fn decode_swaps(logs: &[alloy::rpc::types::Log]) -> Vec<DecodedSwap> {
+        logs.iter().filter_map(Self::decode_single_log).collect()
+    }
+
+    fn decode_transfer(log: &alloy::rpc::types::Log, call_index: usize) -> Option<DecodedTransfer> {
+        let transfer = erc20::Transfer::decode_log(&log.inner).ok()?;
+        Some(DecodedTransfer {
+            call_index,
+            token: Bytes::from(log.inner.address.as_slice()),
+            from: Bytes::from(transfer.from.as_slice()),
+            to: Bytes::from(transfer.to.as_slice()),
+            amount: u256_to_biguint(transfer.value),
+        })
+    }
+
+    fn validate_simulation_success(simulated_blocks: &[SimulatedBlock], swap_index: usize) -> Result<()> {
+        // The swap and any unwrap following it must succeed
+        match simulated_blocks[0].calls[swap_index..].iter().find(|call| !call.status) {
+            Some(call) => Err(call
+                .error
+                .as_ref()
+                .map_or_else(
+                    || SimulationError::SimulationFailed { reason: "Simulation failed".to_string() },
+                    |error| SimulationError::from_revert(&error.message),
+                )
+                .into()),
+            None => Ok(()),
         }
-        Ok(())
     }
 
-    fn extract_gas_metrics(simulated_blocks: &[SimulatedBlock]) -> (u64, u64) {
-        let approval_gas = simulated_blocks[0].calls[0].gas_used;
-        let swap_gas = simulated_blocks[0].calls[1].gas_used;
-        (approval_gas, swap_gas)
+    fn extract_gas_metrics(
+        simulated_blocks: &[SimulatedBlock],
+        approval_index: Option<usize>,
+        swap_index: usize,
+    ) -> (u64, u64, u64) {
+        let calls = &simulated_blocks[0].calls;
+        let approval_gas = approval_index.map_or(0, |index| calls[index].gas_used);
+        let swap_gas = calls[swap_index].gas_used;
+        let total_gas: u64 = calls.iter().map(|call| call.gas_used).sum();
+        (approval_gas, swap_gas, total_gas - approval_gas - swap_gas)
     }
 
-    fn decode_swap_events(simulated_blocks: &[SimulatedBlock]) -> Result<Vec<DecodedSwap>> {
-        let sim_result = &simulated_blocks[0].calls[1];
+    fn decode_swap_events(simulated_blocks: &[SimulatedBlock], swap_index: usize) -> Result<Vec<DecodedSwap>> {
+        let sim_result = &simulated_blocks[0].calls[swap_index];
         let mut decoded_path = Vec::new();
 
         for log in sim_result.logs.iter() {
@@ -472,3 +784,79 @@ impl LogParser {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_without_calls_is_unsuccessful() {
+        let report = LogParser::build_report(&[], 0);
+
+        assert!(!report.success);
+        assert_eq!(report.total_gas(), 0);
+        assert!(report.effective_amount_out.is_none());
+        assert!(report.transfers.is_empty());
+    }
+
+    fn transfer(call_index: usize, from: &str, to: &str, amount: u32) -> DecodedTransfer {
+        use std::str::FromStr;
+        DecodedTransfer {
+            call_index,
+            token: Bytes::from_str("0x00aa").unwrap(),
+            from: Bytes::from_str(from).unwrap(),
+            to: Bytes::from_str(to).unwrap(),
+            amount: BigUint::from(amount),
+        }
+    }
+
+    #[test]
+    fn test_verify_amount_out_from_transfers() {
+        use std::str::FromStr;
+        let token = Bytes::from_str("0x00aa").unwrap();
+        let executor = Bytes::from_str("0x0001").unwrap();
+
+        let report = SimulationReport {
+            gas_used_per_call: vec![50_000, 150_000],
+            swaps: Vec::new(),
+            effective_amount_out: Some(BigUint::from(1_010u32)),
+            transfers: vec![
+                transfer(1, "0x0001", "0x0100", 1_000),
+                // Fee-on-transfer: the pool sends 1010 but only 1005 arrive
+                transfer(1, "0x0100", "0x0001", 1_005),
+                transfer(1, "0x0100", "0x0200", 5),
+            ],
+            success: true,
+            swap_call_index: 1,
+        };
+
+        let flow = report.token_flow();
+        assert_eq!(flow.net_change(&token, &executor), BigInt::from(5));
+
+        let verification = report.verify_amount_out(&token, &executor, &BigUint::from(1_010u32));
+        assert_eq!(verification.realized_amount_out, BigUint::from(1_005u32));
+        assert_eq!(verification.shortfall(), BigUint::from(5u32));
+        assert!((verification.divergence_bps + 49.5).abs() < 0.1);
+        assert!(verification.within_tolerance(50.0));
+        assert!(!verification.within_tolerance(10.0));
+    }
+
+    #[test]
+    fn test_verify_amount_out_without_transfer_logs() {
+        use std::str::FromStr;
+        let report = SimulationReport {
+            gas_used_per_call: vec![150_000],
+            swaps: Vec::new(),
+            effective_amount_out: Some(BigUint::from(1_008u32)),
+            transfers: Vec::new(),
+            success: true,
+            swap_call_index: 0,
+        };
+
+        let token = Bytes::from_str("0x00aa").unwrap();
+        let executor = Bytes::from_str("0x0001").unwrap();
+        let verification = report.verify_amount_out(&token, &executor, &BigUint::from(1_010u32));
+        assert_eq!(verification.realized_amount_out, BigUint::from(1_008u32));
+        assert!(verification.within_tolerance(50.0));
+    }
+}
@@ -0,0 +1,253 @@
+//! Reuse of Permit2 signatures across candidate paths and blocks.
+//!
+//! Every simulated path needs a signed Permit2 permit for its input token. Signing
+//! a fresh permit for each candidate wastes time and makes the router calldata
+//! change with every block, even when the trade itself is unchanged.
+//!
+//! The `PermitCache` aligns permit expirations to fixed validity windows, so all
+//! permits for a token and spender within a window are identical. A signature is
+//! kept per (owner, token, spender, expiry) and reused for as long as the Permit2 nonce is
+//! unchanged and the signed amount covers the trade. Shortly before a window ends,
+//! permits move to the next window, so a signature is never used right before it
+//! expires.
+
+use crate::errors::Result;
+use crate::simulation::encoding::sign_permit;
+use alloy::{primitives::{Address, Signature}, signers::local::PrivateKeySigner};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tycho_common::Bytes;
+use tycho_execution::encoding::models::PermitSingle;
+
+/// Default validity window of a cached permit.
+const DEFAULT_PERMIT_VALIDITY: Duration = Duration::from_secs(60 * 60);
+
+/// Default time before expiry at which permits move to the next window.
+const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Cache key of a signed permit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PermitKey {
+    owner: Address,
+    token: Bytes,
+    spender: Bytes,
+    expiry: u64,
+}
+
+/// A signed permit.
+#[derive(Debug, Clone)]
+struct CachedPermit {
+    permit: PermitSingle,
+    signature: Signature,
+}
+
+/// Cache of signed Permit2 permits.
+#[derive(Debug)]
+pub struct PermitCache {
+    validity: Duration,
+    refresh_margin: Duration,
+    entries: RwLock<HashMap<PermitKey, CachedPermit>>,
+}
+
+impl PermitCache {
+    /// Create a cache with one-hour permit windows.
+    pub fn new() -> Self {
+        Self::with_validity(DEFAULT_PERMIT_VALIDITY)
+    }
+
+    /// Create a cache with the given permit validity window.
+    ///
+    /// The refresh margin is capped at half the window.
+    pub fn with_validity(validity: Duration) -> Self {
+        Self {
+            validity,
+            refresh_margin: DEFAULT_REFRESH_MARGIN.min(validity / 2),
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Set how long before expiry permits move to the next window.
+    pub fn with_refresh_margin(mut self, refresh_margin: Duration) -> Self {
+        self.refresh_margin = refresh_margin.min(self.validity / 2);
+        self
+    }
+
+    /// Number of cached signatures.
+    pub fn len(&self) -> usize {
+        self.entries.read().map_or(0, |entries| entries.len())
+    }
+
+    /// Whether no signature is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return a signed permit for the request, reusing a cached signature if possible.
+    ///
+    /// The expiration and signature deadline of `permit` are replaced by the end
+    /// of the current validity window. A cached signature of the same signer for
+    /// the same token, spender and window is reused if it was signed for the same Permit2 nonce
+    /// and at least the requested amount.
+    ///
+    /// # Arguments
+    ///
+    /// * `permit` - The permit produced by the encoder for the current path
+    /// * `signer` - The private key signer for creating new signatures
+    /// * `chain_id` - The blockchain network ID for domain separation
+    /// * `permit2_address` - The Permit2 contract address for domain separation
+    ///
+    /// # Returns
+    ///
+    /// The permit to encode into the router call together with its signature
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a new permit has to be signed and signing fails.
+    pub fn get_or_sign(
+        &self,
+        permit: &PermitSingle,
+        signer: &PrivateKeySigner,
+        chain_id: u64,
+        permit2_address: Address,
+    ) -> Result<(PermitSingle, Signature)> {
+        let expiry = self.window_expiry(unix_now());
+        let key = PermitKey {
+            owner: signer.address(),
+            token: permit.details.token.clone(),
+            spender: permit.spender.clone(),
+            expiry,
+        };
+
+        if let Some(cached) = self.lookup(&key, permit) {
+            tracing::trace!(
+                token = %key.token,
+                expiry = expiry,
+                "Reusing cached Permit2 signature"
+            );
+            return Ok((cached.permit, cached.signature));
+        }
+
+        let mut aligned = permit.clone();
+        aligned.details.expiration = BigUint::from(expiry);
+        aligned.sig_deadline = BigUint::from(expiry);
+
+        let signature = sign_permit(&aligned, signer, chain_id, permit2_address)?;
+
+        tracing::debug!(
+            token = %key.token,
+            spender = %key.spender,
+            expiry = expiry,
+            "Signed new Permit2 permit"
+        );
+
+        self.prune_expired();
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert(key, CachedPermit {
+                permit: aligned.clone(),
+                signature,
+            });
+        }
+
+        Ok((aligned, signature))
+    }
+
+    /// Remove all cached permits that expired.
+    pub fn prune_expired(&self) {
+        let now = unix_now();
+        if let Ok(mut entries) = self.entries.write() {
+            entries.retain(|key, _| key.expiry > now);
+        }
+    }
+
+    /// Find a cached signature usable for the requested permit.
+    fn lookup(&self, key: &PermitKey, permit: &PermitSingle) -> Option<CachedPermit> {
+        let entries = self.entries.read().ok()?;
+        entries
+            .get(key)
+            .filter(|cached| {
+                cached.permit.details.nonce == permit.details.nonce
+                    && cached.permit.details.amount >= permit.details.amount
+            })
+            .cloned()
+    }
+
+    /// End of the validity window used for permits created at `now`.
+    ///
+    /// Windows are aligned to multiples of the validity, and permits move to the
+    /// next window once less than the refresh margin is left.
+    fn window_expiry(&self, now: u64) -> u64 {
+        let validity = self.validity.as_secs().max(1);
+        let margin = self.refresh_margin.as_secs();
+        let mut expiry = (now / validity + 1) * validity;
+        if expiry - now <= margin {
+            expiry += validity;
+        }
+        expiry
+    }
+}
+
+impl Default for PermitCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Current unix time in seconds.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tycho_execution::encoding::models::PermitDetails;
+
+    #[test]
+    fn test_window_expiry_is_aligned_and_refreshed_early() {
+        let cache = PermitCache::with_validity(Duration::from_secs(3_600))
+            .with_refresh_margin(Duration::from_secs(300));
+
+        // Same window for every permit created within it
+        assert_eq!(cache.window_expiry(3_600), 7_200);
+        assert_eq!(cache.window_expiry(5_000), 7_200);
+
+        // Inside the refresh margin, permits move to the next window
+        assert_eq!(cache.window_expiry(6_900), 10_800);
+        assert_eq!(cache.window_expiry(7_199), 10_800);
+    }
+
+    #[test]
+    fn test_refresh_margin_is_capped() {
+        let cache = PermitCache::with_validity(Duration::from_secs(60))
+            .with_refresh_margin(Duration::from_secs(600));
+
+        assert_eq!(cache.refresh_margin, Duration::from_secs(30));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_signatures_are_not_shared_between_owners() {
+        let permit = PermitSingle {
+            details: PermitDetails {
+                token: Bytes::from(vec![1; 20]),
+                amount: BigUint::from(100u32),
+                expiration: BigUint::from(0u32),
+                nonce: BigUint::from(0u32),
+            },
+            spender: Bytes::from(vec![2; 20]),
+            sig_deadline: BigUint::from(0u32),
+        };
+        let cache = PermitCache::new();
+        let (first, second) = (PrivateKeySigner::random(), PrivateKeySigner::random());
+
+        let (_, signature) = cache.get_or_sign(&permit, &first, 1, Address::ZERO).unwrap();
+        let (_, other_signature) = cache.get_or_sign(&permit, &second, 1, Address::ZERO).unwrap();
+        assert_ne!(signature, other_signature);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get_or_sign(&permit, &first, 1, Address::ZERO).unwrap().1, signature);
+    }
+}
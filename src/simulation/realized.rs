@@ -0,0 +1,393 @@
+//! Realized slippage of included paths, per protocol system.
+//!
+//! A `SlippagePolicy` guesses how far the output of a path may fall short of
+//! the simulation by the time it is included. Whether the guess was right only
+//! shows on chain: a tolerance far above the realized slippage gives searchers
+//! behind in the block room to sandwich, one below it reverts.
+//!
+//! `RealizedSlippageLog` compares the output of every swap decoded from the
+//! receipts of included transactions with its simulated output, and keeps the
+//! signed deviations of the last swaps of each protocol system in a log of
+//! fixed size. For every path it also records the headroom of the realized
+//! output above the checked amount, the amount at which the router reverts.
+//! [`RealizedSlippage`] feeds the log back into the tolerance of new paths.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::simulation::{FixedSlippage, LogParser, RealizedSlippage, RealizedSlippageLog};
+//!
+//! let log = Arc::new(RealizedSlippageLog::new());
+//! let policy = RealizedSlippage::new(Arc::clone(&log), Arc::new(FixedSlippage::new(50)));
+//! let simulator = simulator.with_slippage_policy(Arc::new(policy));
+//!
+//! // After inclusion
+//! let decoded = LogParser::decode_swaps(receipt.inner.logs());
+//! log.record_execution(&path, &checked_amount, &decoded);
+//! ```
+
+use crate::path::PathExt;
+use crate::simulation::accuracy::{deviation_bps, pair_decoded_swaps};
+use crate::simulation::parsing::DecodedSwap;
+use crate::simulation::slippage::{SlippagePolicy, MAX_SLIPPAGE_BPS};
+use num_bigint::BigUint;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+
+/// Default number of swaps kept per protocol system.
+pub const DEFAULT_LOG_CAPACITY: usize = 256;
+
+/// Default number of swaps of a protocol before its realized slippage is trusted.
+const DEFAULT_MIN_SAMPLES: usize = 10;
+
+/// Default share of swaps whose realized slippage the tolerance covers.
+const DEFAULT_COVERAGE: f64 = 0.95;
+
+/// Realized against simulated and checked output of one included path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealizedExecution {
+    /// Final output of the path predicted by the simulation
+    pub simulated_amount_out: BigUint,
+    /// Minimum output enforced by the router
+    pub checked_amount: BigUint,
+    /// Final output decoded from the receipt
+    pub realized_amount_out: BigUint,
+    /// Deviation of the realized from the simulated output in basis points,
+    /// negative if less than simulated was received
+    pub slippage_bps: f64,
+    /// Headroom of the realized output above the checked amount in basis points
+    /// of the checked amount, close to zero if the path nearly reverted
+    pub checked_margin_bps: f64,
+}
+
+/// Realized slippage statistics of the logged swaps of one protocol system.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RealizedSlippageStats {
+    /// Number of logged swaps
+    pub samples: usize,
+    /// Mean signed slippage in basis points, negative if less than simulated was received
+    pub mean_bps: f64,
+    /// Lowest signed slippage in basis points
+    pub worst_bps: f64,
+    /// Shortfall covering the configured share of swaps, in basis points
+    pub shortfall_bps: f64,
+}
+
+/// Signed realized slippage of the last swaps of each protocol system.
+#[derive(Debug)]
+pub struct RealizedSlippageLog {
+    capacity: usize,
+    min_samples: usize,
+    coverage: f64,
+    protocols: RwLock<HashMap<String, VecDeque<f64>>>,
+    checked_margins: RwLock<VecDeque<f64>>,
+}
+
+impl RealizedSlippageLog {
+    /// Create a log keeping the last 256 swaps per protocol, trusted after ten
+    /// swaps and covering 95% of them.
+    pub fn new() -> Self {
+        Self {
+            capacity: DEFAULT_LOG_CAPACITY,
+            min_samples: DEFAULT_MIN_SAMPLES,
+            coverage: DEFAULT_COVERAGE,
+            protocols: RwLock::new(HashMap::new()),
+            checked_margins: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    /// Set the number of swaps kept per protocol system, dropping the oldest first.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set the number of swaps of a protocol before its realized slippage is trusted.
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples.max(1);
+        self
+    }
+
+    /// Set the share of swaps whose realized slippage the tolerance covers, e.g. 0.99.
+    pub fn with_coverage(mut self, coverage: f64) -> Self {
+        self.coverage = coverage.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Record one included swap of a protocol.
+    ///
+    /// Swaps with a zero simulated output carry no information and are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_system` - The protocol system of the pool, e.g. "uniswap_v3"
+    /// * `simulated` - The output predicted by the simulation
+    /// * `realized` - The output decoded from the receipt
+    pub fn record(&self, protocol_system: &str, simulated: &BigUint, realized: &BigUint) {
+        let Some(slippage_bps) = deviation_bps(simulated, realized) else {
+            return;
+        };
+        if let Ok(mut protocols) = self.protocols.write() {
+            let samples = protocols.entry(protocol_system.to_string()).or_default();
+            push_bounded(samples, slippage_bps, self.capacity);
+
+            tracing::trace!(
+                protocol_system = protocol_system,
+                simulated = %simulated,
+                realized = %realized,
+                slippage_bps = slippage_bps,
+                "Recorded realized slippage"
+            );
+        }
+    }
+
+    /// Record an included path against the swaps decoded from its receipt.
+    ///
+    /// Swaps are paired as in [`crate::simulation::AccuracyTracker::record_path`].
+    /// The path-level comparison needs the final swap of the path to be decoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path with the amounts predicted by the simulation
+    /// * `checked_amount` - The minimum output the router enforced
+    /// * `decoded` - The swaps decoded from the receipt
+    ///
+    /// # Returns
+    ///
+    /// The realized execution of the path, if its final swap was decoded
+    pub fn record_execution(
+        &self,
+        path: &PathExt,
+        checked_amount: &BigUint,
+        decoded: &[DecodedSwap],
+    ) -> Option<RealizedExecution> {
+        let pairs = pair_decoded_swaps(path, decoded);
+        for (swap, decoded_swap) in &pairs {
+            self.record(&swap.pool_comp.protocol_system, &swap.amount_out, &decoded_swap.amount_out);
+        }
+
+        let last_swap = path.last()?;
+        let (_, last_decoded) = pairs.iter().find(|(swap, _)| std::ptr::eq(*swap, last_swap))?;
+        let execution = RealizedExecution {
+            simulated_amount_out: last_swap.amount_out.clone(),
+            checked_amount: checked_amount.clone(),
+            realized_amount_out: last_decoded.amount_out.clone(),
+            slippage_bps: deviation_bps(&last_swap.amount_out, &last_decoded.amount_out)?,
+            checked_margin_bps: deviation_bps(checked_amount, &last_decoded.amount_out)?,
+        };
+        if let Ok(mut checked_margins) = self.checked_margins.write() {
+            push_bounded(&mut checked_margins, execution.checked_margin_bps, self.capacity);
+        }
+
+        tracing::debug!(
+            swaps = path.len(),
+            decoded_swaps = pairs.len(),
+            slippage_bps = execution.slippage_bps,
+            checked_margin_bps = execution.checked_margin_bps,
+            "Recorded realized path execution"
+        );
+        Some(execution)
+    }
+
+    /// The logged signed slippage of a protocol system, oldest first.
+    pub fn samples(&self, protocol_system: &str) -> Vec<f64> {
+        self.protocols
+            .read()
+            .ok()
+            .and_then(|protocols| protocols.get(protocol_system).map(|samples| samples.iter().copied().collect()))
+            .unwrap_or_default()
+    }
+
+    /// The logged headroom of realized outputs above their checked amounts, oldest first.
+    pub fn checked_margins(&self) -> Vec<f64> {
+        self.checked_margins
+            .read()
+            .map(|checked_margins| checked_margins.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Statistics of a protocol system, if any swap was logged.
+    pub fn stats(&self, protocol_system: &str) -> Option<RealizedSlippageStats> {
+        self.stats_of(&self.samples(protocol_system))
+    }
+
+    /// Statistics of all logged protocol systems.
+    pub fn all_stats(&self) -> HashMap<String, RealizedSlippageStats> {
+        let Ok(protocols) = self.protocols.read() else {
+            return HashMap::new();
+        };
+        protocols
+            .iter()
+            .filter_map(|(protocol_system, samples)| {
+                let samples: Vec<f64> = samples.iter().copied().collect();
+                Some((protocol_system.clone(), self.stats_of(&samples)?))
+            })
+            .collect()
+    }
+
+    /// The tolerance per hop covering the realized slippage of a protocol, in whole basis points.
+    ///
+    /// `None` until the minimum number of swaps was logged.
+    pub fn tolerance_bps(&self, protocol_system: &str) -> Option<u64> {
+        self.stats(protocol_system)
+            .filter(|stats| stats.samples >= self.min_samples)
+            .map(|stats| stats.shortfall_bps.round() as u64)
+    }
+
+    fn stats_of(&self, samples: &[f64]) -> Option<RealizedSlippageStats> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let index = ((sorted.len() - 1) as f64 * (1.0 - self.coverage)).round() as usize;
+        Some(RealizedSlippageStats {
+            samples: sorted.len(),
+            mean_bps: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            worst_bps: sorted[0],
+            shortfall_bps: (-sorted[index]).max(0.0),
+        })
+    }
+}
+
+impl Default for RealizedSlippageLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Append a sample, dropping the oldest ones beyond the capacity.
+fn push_bounded(samples: &mut VecDeque<f64>, sample: f64, capacity: usize) {
+    samples.push_back(sample);
+    while samples.len() > capacity {
+        samples.pop_front();
+    }
+}
+
+/// Tolerates the realized slippage of each hop's protocol, summed over the hops.
+///
+/// Paths through protocols with too few logged swaps use the fallback policy.
+pub struct RealizedSlippage {
+    log: Arc<RealizedSlippageLog>,
+    fallback: Arc<dyn SlippagePolicy>,
+    margin_bps: u64,
+    max_bps: u64,
+}
+
+impl RealizedSlippage {
+    /// Create a policy reading the tolerances from `log`, with a fallback for unlogged protocols.
+    pub fn new(log: Arc<RealizedSlippageLog>, fallback: Arc<dyn SlippagePolicy>) -> Self {
+        Self {
+            log,
+            fallback,
+            margin_bps: 0,
+            max_bps: MAX_SLIPPAGE_BPS,
+        }
+    }
+
+    /// Add a margin to the tolerance of every path served from the log.
+    pub fn with_margin_bps(mut self, margin_bps: u64) -> Self {
+        self.margin_bps = margin_bps;
+        self
+    }
+
+    /// Cap the tolerance of any path served from the log.
+    pub fn with_max_bps(mut self, max_bps: u64) -> Self {
+        self.max_bps = max_bps.min(MAX_SLIPPAGE_BPS);
+        self
+    }
+}
+
+impl SlippagePolicy for RealizedSlippage {
+    fn slippage_bps(&self, path: &PathExt) -> u64 {
+        let tolerances: Option<Vec<u64>> = path
+            .iter()
+            .map(|swap| self.log.tolerance_bps(&swap.pool_comp.protocol_system))
+            .collect();
+        match tolerances {
+            Some(tolerances) => tolerances
+                .into_iter()
+                .fold(self.margin_bps, u64::saturating_add)
+                .min(self.max_bps),
+            None => self.fallback.slippage_bps(path),
+        }
+    }
+}
+
+impl std::fmt::Debug for RealizedSlippage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RealizedSlippage")
+            .field("log", &self.log)
+            .field("margin_bps", &self.margin_bps)
+            .field("max_bps", &self.max_bps)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::SwapExt;
+    use crate::simulation::FixedSlippage;
+    use crate::testing::{component, token, MockProtocolSim};
+    use std::str::FromStr;
+    use tycho_common::Bytes;
+    use tycho_simulation::protocol::models::ProtocolComponent;
+
+    fn swap(pool: u8, protocol_system: &str, amount_out: u32) -> SwapExt {
+        let tokens: Vec<Bytes> = ["0x0001", "0x0002"].iter().map(|address| Bytes::from_str(address).unwrap()).collect();
+        let pool = Bytes::from(vec![0x10, pool]);
+        SwapExt {
+            pool_comp: Arc::new(ProtocolComponent {
+                protocol_system: protocol_system.to_string(),
+                ..component(&pool, &tokens)
+            }),
+            pool_sim: Arc::new(MockProtocolSim::new()),
+            token_in: token(&tokens[0], "TEST"),
+            token_out: token(&tokens[1], "TEST"),
+            amount_in: BigUint::from(10_000u32),
+            amount_out: BigUint::from(amount_out),
+            gas: BigUint::from(0u32),
+        }
+    }
+
+    fn decoded(swap: &SwapExt, amount_out: u32) -> DecodedSwap {
+        DecodedSwap {
+            pool: swap.pool_comp.id.clone(),
+            zero_for_one: true,
+            amount_in: swap.amount_in.clone(),
+            amount_out: BigUint::from(amount_out),
+        }
+    }
+
+    #[test]
+    fn test_realized_slippage_feeds_policy() {
+        let log = Arc::new(RealizedSlippageLog::new().with_capacity(4).with_min_samples(2).with_coverage(1.0));
+        let path = PathExt(vec![swap(0, "uniswap_v2", 10_000), swap(1, "vm:curve", 10_000)]);
+        let policy = RealizedSlippage::new(Arc::clone(&log), Arc::new(FixedSlippage::new(50))).with_margin_bps(1);
+        assert_eq!(policy.slippage_bps(&path), 50);
+
+        // The final hop realizes 1% less, then as simulated, then 10% less at the checked amount
+        for realized_out in [9_900, 10_000] {
+            let receipt = [decoded(&path[0], 10_000), decoded(&path[1], realized_out)];
+            let execution = log.record_execution(&path, &BigUint::from(9_430u32), &receipt);
+            assert!(execution.is_some());
+        }
+        let execution = log.record_execution(&path, &BigUint::from(9_000u32), &[decoded(&path[1], 9_000)]).unwrap();
+        assert!((execution.slippage_bps + 1_000.0).abs() < 1e-6);
+        assert_eq!(execution.checked_margin_bps, 0.0);
+
+        // The log keeps only the last four swaps of a protocol, dropping the 1% shortfall
+        for realized_out in [10_050, 10_000] {
+            log.record("vm:curve", &BigUint::from(10_000u32), &BigUint::from(realized_out));
+        }
+        assert_eq!(log.samples("vm:curve").len(), 4);
+        assert!(log.samples("vm:curve")[0] == 0.0);
+        assert_eq!(log.checked_margins().len(), 3);
+
+        // Worst realized shortfall per hop, plus the margin
+        assert_eq!(log.tolerance_bps("uniswap_v2"), Some(0));
+        assert_eq!(log.tolerance_bps("vm:curve"), Some(1_000));
+        assert_eq!(policy.slippage_bps(&path), 1_001);
+    }
+}
@@ -0,0 +1,82 @@
+//! Calldata of the router contract executing a solution.
+//!
+//! The Tycho router is called with the solution's amounts, tokens, receiver
+//! and encoded swaps. Searchers running their own atomic-arbitrage router, e.g.
+//! for gas savings, plug in its calldata layout by implementing
+//! `RouterEncoder` instead of forking the encoding module. Routers sharing the
+//! Tycho argument layout under another function only need
+//! [`TychoRouterEncoder::with_function_signature`].
+
+use crate::errors::Result;
+use crate::simulation::encoding::{encode_direct_router_call, encode_router_call};
+use alloy::primitives::{Bytes as AlloyBytes, Signature, U256};
+use tycho_execution::encoding::models::{EncodedSolution, Solution};
+
+/// A router call to encode.
+#[derive(Debug, Clone, Copy)]
+pub struct RouterCall<'a> {
+    /// The solution encoded by the Tycho encoder
+    pub encoded_solution: &'a EncodedSolution,
+    /// The solution with its tokens, amounts and receiver
+    pub solution: &'a Solution,
+    /// The input amount of the first swap
+    pub amount_in: U256,
+    /// The Permit2 signature, if the router pulls the input with Permit2
+    pub permit_signature: Option<&'a Signature>,
+    /// Whether the router pulls the input with a standard ERC-20 allowance
+    pub transfer_from: bool,
+}
+
+/// Encodes the calldata of the router contract executing a solution.
+pub trait RouterEncoder: Send + Sync {
+    /// Encode the calldata of a router call.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the call cannot be encoded, e.g. a Permit2 call without permit.
+    fn encode_call(&self, call: &RouterCall<'_>) -> Result<AlloyBytes>;
+}
+
+/// Encodes calls with the argument layout of the Tycho router.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TychoRouterEncoder {
+    function_signature: Option<String>,
+}
+
+impl TychoRouterEncoder {
+    /// Create an encoder using the function chosen by the Tycho encoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call another function with the same arguments, e.g. of a custom router.
+    pub fn with_function_signature(mut self, function_signature: impl Into<String>) -> Self {
+        self.function_signature = Some(function_signature.into());
+        self
+    }
+
+    /// The overridden function signature, if any.
+    pub fn function_signature(&self) -> Option<&str> {
+        self.function_signature.as_deref()
+    }
+}
+
+impl RouterEncoder for TychoRouterEncoder {
+    fn encode_call(&self, call: &RouterCall<'_>) -> Result<AlloyBytes> {
+        let overridden;
+        let encoded_solution = match &self.function_signature {
+            Some(function_signature) => {
+                let mut encoded_solution = call.encoded_solution.clone();
+                encoded_solution.function_signature = function_signature.clone();
+                overridden = encoded_solution;
+                &overridden
+            }
+            None => call.encoded_solution,
+        };
+
+        match call.permit_signature {
+            Some(permit_signature) => encode_router_call(encoded_solution, &call.amount_in, call.solution, permit_signature),
+            None => encode_direct_router_call(encoded_solution, &call.amount_in, call.solution, call.transfer_from),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! Slippage tolerances chosen per path.
+//!
+//! The router reverts when a path returns less than its checked amount, the
+//! expected output minus the slippage tolerance. Routes between stablecoins
+//! barely move within a block and can use a few BPS, while long paths through
+//! volatile tokens need 100 BPS or more to land at all. A single global
+//! tolerance either gives away profit on the former or reverts on the latter.
+//!
+//! `SlippagePolicy` implementations choose the tolerance of each path:
+//! - `FixedSlippage`: The same tolerance for every path
+//! - `VolatilityScaledSlippage`: A base tolerance plus the volatility of every hop's tokens
+//! - `ProtocolSlippage`: A tolerance per protocol system, summed over the hops
+
+use crate::path::PathExt;
+use std::collections::HashMap;
+use tycho_common::Bytes;
+
+/// The largest tolerance accepted by `build_solution`, 100%.
+pub const MAX_SLIPPAGE_BPS: u64 = 10_000;
+
+/// Chooses the slippage tolerance of a path.
+pub trait SlippagePolicy: Send + Sync {
+    /// The tolerated output shortfall of `path`, in basis points.
+    fn slippage_bps(&self, path: &PathExt) -> u64;
+}
+
+/// Tolerates the same shortfall on every path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedSlippage {
+    bps: u64,
+}
+
+impl FixedSlippage {
+    /// Create a policy tolerating `bps` on every path.
+    pub fn new(bps: u64) -> Self {
+        Self { bps: bps.min(MAX_SLIPPAGE_BPS) }
+    }
+}
+
+impl SlippagePolicy for FixedSlippage {
+    fn slippage_bps(&self, _path: &PathExt) -> u64 {
+        self.bps
+    }
+}
+
+/// Scales the tolerance with the volatility of the traded tokens.
+///
+/// Every hop adds the larger volatility of its two tokens to the base
+/// tolerance, so long paths through volatile tokens tolerate more than short
+/// routes between stablecoins. Tokens without a configured volatility use
+/// the default volatility.
+#[derive(Debug, Clone)]
+pub struct VolatilityScaledSlippage {
+    base_bps: u64,
+    default_volatility_bps: u64,
+    volatilities: HashMap<Bytes, u64>,
+    max_bps: u64,
+}
+
+impl VolatilityScaledSlippage {
+    /// Create a policy with a base tolerance and a default volatility per token, both in BPS.
+    pub fn new(base_bps: u64, default_volatility_bps: u64) -> Self {
+        Self {
+            base_bps,
+            default_volatility_bps,
+            volatilities: HashMap::new(),
+            max_bps: MAX_SLIPPAGE_BPS,
+        }
+    }
+
+    /// Set the expected per-block volatility of a token, e.g. 0 for stablecoins.
+    pub fn with_volatility(mut self, token: Bytes, volatility_bps: u64) -> Self {
+        self.volatilities.insert(token, volatility_bps);
+        self
+    }
+
+    /// Cap the tolerance of any path.
+    pub fn with_max_bps(mut self, max_bps: u64) -> Self {
+        self.max_bps = max_bps.min(MAX_SLIPPAGE_BPS);
+        self
+    }
+
+    fn volatility(&self, token: &Bytes) -> u64 {
+        self.volatilities
+            .get(token)
+            .copied()
+            .unwrap_or(self.default_volatility_bps)
+    }
+}
+
+impl SlippagePolicy for VolatilityScaledSlippage {
+    fn slippage_bps(&self, path: &PathExt) -> u64 {
+        path.iter()
+            .map(|swap| {
+                self.volatility(&swap.token_in.address)
+                    .max(self.volatility(&swap.token_out.address))
+            })
+            .fold(self.base_bps, u64::saturating_add)
+            .min(self.max_bps)
+    }
+}
+
+/// Tolerates a shortfall per protocol system, summed over the hops of a path.
+///
+/// Protocols without a configured tolerance use the default tolerance.
+#[derive(Debug, Clone)]
+pub struct ProtocolSlippage {
+    default_bps: u64,
+    protocols: HashMap<String, u64>,
+}
+
+impl ProtocolSlippage {
+    /// Create a policy tolerating `default_bps` per hop on unlisted protocols.
+    pub fn new(default_bps: u64) -> Self {
+        Self {
+            default_bps,
+            protocols: HashMap::new(),
+        }
+    }
+
+    /// Set the tolerance per hop on a protocol system, e.g. `vm:curve`.
+    pub fn with_protocol(mut self, protocol_system: impl Into<String>, bps: u64) -> Self {
+        self.protocols.insert(protocol_system.into(), bps);
+        self
+    }
+}
+
+impl SlippagePolicy for ProtocolSlippage {
+    fn slippage_bps(&self, path: &PathExt) -> u64 {
+        path.iter()
+            .map(|swap| {
+                self.protocols
+                    .get(&swap.pool_comp.protocol_system)
+                    .copied()
+                    .unwrap_or(self.default_bps)
+            })
+            .fold(0, u64::saturating_add)
+            .min(MAX_SLIPPAGE_BPS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{cyclic_path, MockProtocolSim};
+    use crate::path::PathExecutor;
+    use num_bigint::BigUint;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use tycho_simulation::protocol::models::ProtocolComponent;
+
+    // A cycle over the given tokens, one hop per protocol system
+    fn cycle(tokens: &[&str], protocol_systems: &[&str]) -> PathExt {
+        let tokens: Vec<Bytes> = tokens.iter().map(|address| Bytes::from_str(address).unwrap()).collect();
+        let path = cyclic_path(&tokens, &Bytes::from(vec![0x10, 0x00]), &MockProtocolSim::new()).unwrap();
+        let mut path = PathExecutor::new().execute_with_amount(&path, BigUint::from(1_000u32)).unwrap();
+        for (swap, protocol_system) in path.0.iter_mut().zip(protocol_systems) {
+            swap.pool_comp = Arc::new(ProtocolComponent {
+                protocol_system: protocol_system.to_string(),
+                ..(*swap.pool_comp).clone()
+            });
+        }
+        path
+    }
+
+    #[test]
+    fn test_volatility_scaled_slippage() {
+        let (usdc, usdt, weth, pepe) = ("0x0001", "0x0002", "0x0003", "0x0004");
+        let policy = VolatilityScaledSlippage::new(5, 30)
+            .with_volatility(Bytes::from_str(usdc).unwrap(), 0)
+            .with_volatility(Bytes::from_str(usdt).unwrap(), 0)
+            .with_max_bps(200);
+
+        // Stable-stable routes only pay the base tolerance
+        let stable = cycle(&[usdc, usdt], &["uniswap_v3", "vm:curve"]);
+        assert_eq!(policy.slippage_bps(&stable), 5);
+
+        // Every volatile hop adds its volatility: 5 + 30 + 30 + 30
+        let volatile = cycle(&[usdc, weth, pepe], &["uniswap_v2", "uniswap_v2", "uniswap_v3"]);
+        assert_eq!(policy.slippage_bps(&volatile), 95);
+
+        let capped = VolatilityScaledSlippage::new(5, 150).with_max_bps(200);
+        assert_eq!(capped.slippage_bps(&volatile), 200);
+        assert_eq!(FixedSlippage::new(50).slippage_bps(&volatile), 50);
+    }
+
+    #[test]
+    fn test_protocol_slippage() {
+        let policy = ProtocolSlippage::new(30).with_protocol("vm:curve", 2);
+        let path = cycle(&["0x0001", "0x0002", "0x0003"], &["vm:curve", "vm:curve", "uniswap_v2"]);
+        assert_eq!(policy.slippage_bps(&path), 34);
+
+        let greedy = ProtocolSlippage::new(u64::MAX);
+        assert_eq!(greedy.slippage_bps(&path), MAX_SLIPPAGE_BPS);
+    }
+}
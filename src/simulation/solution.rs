@@ -0,0 +1,295 @@
+//! Typed construction of router solutions.
+//!
+//! A `Solution` tells the Tycho router which swaps to execute, who receives
+//! the output and which token and minimum amount to check at the end.
+//! `SolutionBuilder` sets these fields with defaults for arbitrage paths and
+//! validates them before encoding:
+//!
+//! - The output goes to the sender unless a receiver is set
+//! - The checked token is the output token of the final hop
+//! - The solution is encoded for the router of the chain unless overridden
+//!
+//! Exact-output solutions, built with [`SolutionBuilder::exact_out`], fix the
+//! output of the final hop instead of the input of the first, e.g. to repay a
+//! flash loan of a fixed amount. Their given token is the output token, and
+//! their checked amount is the maximum input after the slippage tolerance.
+
+use crate::errors::{Result, SimulationError};
+use num_bigint::BigUint;
+use tycho_common::Bytes;
+use tycho_execution::encoding::models::{Solution, Swap};
+
+/// The largest tolerated output shortfall, 100%.
+const MAX_SLIPPAGE_BPS: u64 = 10_000;
+
+/// The minimum output after a slippage tolerance, at least 1.
+///
+/// The router reverts a solution whose output falls below this amount.
+pub fn checked_amount(expected_amount_out: &BigUint, slippage_bps: u64) -> BigUint {
+    let slippage_amount = expected_amount_out * slippage_bps / MAX_SLIPPAGE_BPS;
+    if *expected_amount_out > slippage_amount {
+        expected_amount_out - &slippage_amount
+    } else {
+        BigUint::from(1_u32)
+    }
+}
+
+/// The maximum input after a slippage tolerance.
+///
+/// The router reverts an exact-output solution whose input exceeds this amount.
+pub fn max_amount_in(expected_amount_in: &BigUint, slippage_bps: u64) -> BigUint {
+    expected_amount_in + expected_amount_in * slippage_bps / MAX_SLIPPAGE_BPS
+}
+
+/// Builder of validated router solutions.
+#[derive(Debug, Clone)]
+pub struct SolutionBuilder {
+    swaps: Vec<Swap>,
+    given_amount: BigUint,
+    expected_amount: BigUint,
+    exact_out: bool,
+    sender: Bytes,
+    receiver: Option<Bytes>,
+    checked_token: Option<Bytes>,
+    router_address: Option<Bytes>,
+    slippage_bps: u64,
+    min_checked_amount: Option<BigUint>,
+}
+
+impl SolutionBuilder {
+    /// Create a builder of a solution executing `swaps` without slippage tolerance.
+    ///
+    /// # Arguments
+    ///
+    /// * `swaps` - The sequence of swaps to execute
+    /// * `given_amount` - The input amount of the first swap
+    /// * `expected_amount_out` - The expected output amount of the final swap
+    /// * `sender` - The address executing the solution
+    pub fn new(swaps: Vec<Swap>, given_amount: BigUint, expected_amount_out: BigUint, sender: Bytes) -> Self {
+        Self {
+            swaps,
+            given_amount,
+            expected_amount: expected_amount_out,
+            exact_out: false,
+            sender,
+            receiver: None,
+            checked_token: None,
+            router_address: None,
+            slippage_bps: 0,
+            min_checked_amount: None,
+        }
+    }
+
+    /// Create a builder of an exact-output solution executing `swaps` without slippage tolerance.
+    ///
+    /// # Arguments
+    ///
+    /// * `swaps` - The sequence of swaps to execute
+    /// * `amount_out` - The exact output amount of the final swap
+    /// * `expected_amount_in` - The expected input amount of the first swap
+    /// * `sender` - The address executing the solution
+    pub fn exact_out(swaps: Vec<Swap>, amount_out: BigUint, expected_amount_in: BigUint, sender: Bytes) -> Self {
+        Self {
+            exact_out: true,
+            ..Self::new(swaps, amount_out, expected_amount_in, sender)
+        }
+    }
+
+    /// Send the output to a separate receiver, e.g. a cold wallet.
+    pub fn with_receiver(mut self, receiver: Bytes) -> Self {
+        self.receiver = Some(receiver);
+        self
+    }
+
+    /// Check the amount of another token than the output of the final hop, or
+    /// the input of the first hop for exact-output solutions.
+    ///
+    /// Only the input token of the first hop or the output token of the final hop can be checked.
+    pub fn with_checked_token(mut self, checked_token: Bytes) -> Self {
+        self.checked_token = Some(checked_token);
+        self
+    }
+
+    /// Encode the solution for a router other than the default router of the chain.
+    pub fn with_router_address(mut self, router_address: Bytes) -> Self {
+        self.router_address = Some(router_address);
+        self
+    }
+
+    /// Tolerate a shortfall of the expected output, or an excess of the expected
+    /// input for exact-output solutions, in basis points.
+    pub fn with_slippage_bps(mut self, slippage_bps: u64) -> Self {
+        self.slippage_bps = slippage_bps;
+        self
+    }
+
+    /// Raise the checked amount to at least `min_checked_amount`, e.g. a post-gas profit threshold.
+    ///
+    /// The checked amount is the larger of this floor and the expected output
+    /// minus the slippage tolerance. Exact-output solutions check a maximum
+    /// input and reject a floor.
+    pub fn with_min_checked_amount(mut self, min_checked_amount: BigUint) -> Self {
+        self.min_checked_amount = Some(min_checked_amount);
+        self
+    }
+
+    /// The router to encode the solution for, if overridden.
+    pub fn router_address(&self) -> Option<&Bytes> {
+        self.router_address.as_ref()
+    }
+
+    /// The minimum output amount after the slippage tolerance, at least 1,
+    /// or the maximum input amount of exact-output solutions.
+    fn checked_amount(&self) -> BigUint {
+        if self.exact_out {
+            return max_amount_in(&self.expected_amount, self.slippage_bps);
+        }
+        let checked_amount = checked_amount(&self.expected_amount, self.slippage_bps);
+        match &self.min_checked_amount {
+            Some(min_checked_amount) => checked_amount.max(min_checked_amount.clone()),
+            None => checked_amount,
+        }
+    }
+
+    /// Validate the fields and build the solution.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The swap list is empty or consecutive swaps do not connect
+    /// - The given amount, the sender or the receiver is zero
+    /// - The checked token is neither the output of the final hop nor the given token
+    /// - The slippage exceeds 10000 BPS (100%)
+    /// - The minimum checked amount exceeds the expected output, or is set for an exact-output solution
+    pub fn build(&self) -> Result<Solution> {
+        let (Some(first), Some(last)) = (self.swaps.first(), self.swaps.last()) else {
+            return Err(invalid("No swaps provided for solution"));
+        };
+        if let Some(window) = self.swaps.windows(2).find(|window| window[0].token_out != window[1].token_in) {
+            return Err(invalid(format!(
+                "Swap output {} does not match the next swap input {}",
+                window[0].token_out, window[1].token_in
+            )));
+        }
+        if self.given_amount.bits() == 0 {
+            return Err(invalid("The given amount must be positive"));
+        }
+        if self.slippage_bps > MAX_SLIPPAGE_BPS {
+            return Err(invalid(format!("Slippage must be at most 10000 BPS, got {}", self.slippage_bps)));
+        }
+
+        if self.exact_out && self.min_checked_amount.is_some() {
+            return Err(invalid("Exact-output solutions check a maximum input, not a minimum checked amount"));
+        }
+        if let Some(min_checked_amount) = self.min_checked_amount.as_ref().filter(|min| **min > self.expected_amount) {
+            return Err(invalid(format!(
+                "The minimum checked amount {} exceeds the expected output {}",
+                min_checked_amount, self.expected_amount
+            )));
+        }
+
+        let receiver = self.receiver.clone().unwrap_or_else(|| self.sender.clone());
+        for (role, address) in [("sender", &self.sender), ("receiver", &receiver)] {
+            if address.iter().all(|byte| *byte == 0) {
+                return Err(invalid(format!("The {} must not be the zero address", role)));
+            }
+        }
+
+        let (given_token, default_checked_token) = if self.exact_out {
+            (last.token_out.clone(), first.token_in.clone())
+        } else {
+            (first.token_in.clone(), last.token_out.clone())
+        };
+        let checked_token = self.checked_token.clone().unwrap_or(default_checked_token);
+        if checked_token != last.token_out && checked_token != first.token_in {
+            return Err(invalid(format!(
+                "Checked token {} is neither the output of the final hop nor the input of the first hop",
+                checked_token
+            )));
+        }
+
+        let checked_amount = self.checked_amount();
+        tracing::debug!(
+            expected_amount = %self.expected_amount,
+            exact_out = self.exact_out,
+            slippage_bps = self.slippage_bps,
+            checked_amount = %checked_amount,
+            min_checked_amount = ?self.min_checked_amount,
+            checked_token = %checked_token,
+            "Calculated slippage-adjusted checked amount"
+        );
+
+        Ok(Solution {
+            exact_out: self.exact_out,
+            swaps: self.swaps.clone(),
+            sender: self.sender.clone(),
+            receiver,
+            given_token,
+            given_amount: self.given_amount.clone(),
+            checked_token,
+            checked_amount,
+            ..Default::default()
+        })
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> crate::errors::ArbitrageError {
+    SimulationError::InvalidSolution { reason: reason.into() }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::component;
+    use std::str::FromStr;
+
+    fn swap(token_in: &Bytes, token_out: &Bytes) -> Swap {
+        let pool = Bytes::from_str("0x1001").unwrap();
+        Swap {
+            component: component(&pool, &[token_in.clone(), token_out.clone()]).into(),
+            token_in: token_in.clone(),
+            token_out: token_out.clone(),
+            split: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_builds_solution_checking_the_final_output() {
+        let [a, b, c] = ["0x0001", "0x0002", "0x0003"].map(|token| Bytes::from_str(token).unwrap());
+        let sender = Bytes::from_str("0x1111111111111111111111111111111111111111").unwrap();
+        let receiver = Bytes::from_str("0x2222222222222222222222222222222222222222").unwrap();
+        let builder = SolutionBuilder::new(vec![swap(&a, &b), swap(&b, &c)], BigUint::from(1000u32), BigUint::from(2000u32), sender.clone())
+            .with_slippage_bps(100);
+
+        let solution = builder.clone().with_receiver(receiver.clone()).build().unwrap();
+        assert_eq!(solution.checked_token, c);
+        assert_eq!(solution.checked_amount, BigUint::from(1980u32));
+        assert_eq!((solution.sender, solution.receiver), (sender.clone(), receiver));
+
+        assert_eq!(builder.clone().with_checked_token(a.clone()).build().unwrap().checked_token, a);
+        // A profit guard above the slippage-adjusted amount raises the checked amount
+        let guarded = builder.clone().with_min_checked_amount(BigUint::from(1995u32)).build().unwrap();
+        assert_eq!(guarded.checked_amount, BigUint::from(1995u32));
+        assert_eq!(builder.clone().with_min_checked_amount(BigUint::from(1u32)).build().unwrap().checked_amount, BigUint::from(1980u32));
+        assert!(builder.clone().with_min_checked_amount(BigUint::from(2001u32)).build().is_err());
+        assert!(builder.clone().with_checked_token(b.clone()).build().is_err());
+        assert!(builder.clone().with_receiver(Bytes::from(vec![0u8; 20])).build().is_err());
+        assert!(SolutionBuilder::new(vec![swap(&a, &b), swap(&c, &a)], BigUint::from(1000u32), BigUint::from(2000u32), sender)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_builds_exact_out_solution_checking_the_maximum_input() {
+        let [a, b, c] = ["0x0001", "0x0002", "0x0003"].map(|token| Bytes::from_str(token).unwrap());
+        let sender = Bytes::from_str("0x1111111111111111111111111111111111111111").unwrap();
+        let builder = SolutionBuilder::exact_out(vec![swap(&a, &b), swap(&b, &c)], BigUint::from(2000u32), BigUint::from(1000u32), sender)
+            .with_slippage_bps(100);
+
+        let solution = builder.clone().build().unwrap();
+        assert!(solution.exact_out);
+        assert_eq!((solution.given_token, solution.given_amount), (c, BigUint::from(2000u32)));
+        assert_eq!((solution.checked_token, solution.checked_amount), (a, BigUint::from(1010u32)));
+        assert!(builder.with_min_checked_amount(BigUint::from(1u32)).build().is_err());
+    }
+}
@@ -0,0 +1,193 @@
+//! Call trees of failed or anomalous simulations.
+//!
+//! A simulated block only tells whether the swap call reverted and what it
+//! logged. Which pool failed, e.g. a V3 pool running out of liquidity while
+//! crossing ticks, or a token taxing a transfer, is only visible in the call
+//! tree of the swap, which had to be traced by hand after the fact.
+//!
+//! `eth_simulateV1` returns no call tree, so the simulator traces the swap
+//! with the node's `callTracer` when a [`TraceCapture`] asks for it:
+//!
+//! - **`debug_traceCallMany`**: traces all calls of the simulation as one
+//!   bundle, so the swap sees the wrap and approval before it
+//! - **`debug_traceCall`**: fallback on nodes without the bundle method,
+//!   tracing the swap alone on the block state
+//!
+//! The resulting [`CallTrace`] is attached to the
+//! [`SimulationResult`](crate::simulation::SimulationResult). Tracing errors
+//! are logged and leave the trace empty; they never fail the simulation.
+
+use crate::errors::Result;
+use crate::simulation::SimulationResult;
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, Bytes, U256},
+    providers::{Provider, RootProvider},
+    rpc::types::TransactionRequest,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// When the swap call of a simulation is traced.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TraceCapture {
+    max_output_shortfall_bps: Option<f64>,
+}
+
+impl TraceCapture {
+    /// Trace simulations whose swap call reverted.
+    pub fn failed() -> Self {
+        Self::default()
+    }
+
+    /// Also trace successful simulations whose output falls short of the expected output by more than `bps`.
+    pub fn with_max_output_shortfall_bps(mut self, bps: f64) -> Self {
+        self.max_output_shortfall_bps = Some(bps);
+        self
+    }
+
+    /// Whether a simulation of a path expected to return `expected_amount_out` is traced.
+    pub fn should_trace(&self, result: &SimulationResult, expected_amount_out: &BigUint) -> bool {
+        if !result.report().success {
+            return true;
+        }
+        self.max_output_shortfall_bps
+            .is_some_and(|bps| !result.verify_amount_out(expected_amount_out).within_tolerance(bps))
+    }
+}
+
+/// A call frame reported by the `callTracer`, with the calls it made.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallTrace {
+    /// The call type, e.g. `CALL`, `DELEGATECALL` or `STATICCALL`
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub from: Address,
+    #[serde(default)]
+    pub to: Option<Address>,
+    #[serde(default)]
+    pub value: Option<U256>,
+    #[serde(default)]
+    pub gas_used: Option<U256>,
+    #[serde(default)]
+    pub input: Bytes,
+    #[serde(default)]
+    pub output: Option<Bytes>,
+    /// Why the call failed, e.g. `execution reverted`
+    #[serde(default)]
+    pub error: Option<String>,
+    /// The decoded revert reason, if the call reverted with one
+    #[serde(default)]
+    pub revert_reason: Option<String>,
+    #[serde(default)]
+    pub calls: Vec<CallTrace>,
+}
+
+impl CallTrace {
+    /// Whether the call failed.
+    pub fn failed(&self) -> bool {
+        self.error.is_some()
+    }
+
+    /// The innermost failed call, usually the pool or token that caused the revert.
+    pub fn deepest_failure(&self) -> Option<&CallTrace> {
+        if !self.failed() {
+            return None;
+        }
+        self.calls
+            .iter()
+            .find_map(CallTrace::deepest_failure)
+            .or(Some(self))
+    }
+
+    /// The number of calls in the tree, this one included.
+    pub fn call_count(&self) -> usize {
+        1 + self.calls.iter().map(CallTrace::call_count).sum::<usize>()
+    }
+}
+
+/// Trace the swap call of a simulation with the `callTracer`.
+///
+/// # Arguments
+///
+/// * `provider` - The RPC provider, which has to serve the `debug` namespace
+/// * `result` - The simulation whose swap call is traced
+/// * `block_number` - The block simulated on top of, the latest block if `None`
+///
+/// # Errors
+///
+/// Returns an error if neither `debug_traceCallMany` nor `debug_traceCall` succeeds.
+pub async fn trace_swap_call(
+    provider: &RootProvider<Ethereum>,
+    result: &SimulationResult,
+    block_number: Option<u64>,
+) -> Result<CallTrace> {
+    let block = block_number.map_or_else(|| json!("latest"), |number| json!(format!("0x{:x}", number)));
+    let tracer = json!({ "tracer": "callTracer" });
+
+    let bundle = json!([{ "transactions": result.transaction_requests() }]);
+    let context = json!({ "blockNumber": block });
+    match provider
+        .raw_request::<_, Vec<Vec<CallTrace>>>("debug_traceCallMany".into(), (bundle, context, tracer.clone()))
+        .await
+    {
+        Ok(traces) => {
+            let swap_trace = traces
+                .into_iter()
+                .next()
+                .and_then(|bundle| bundle.into_iter().nth(result.swap_call_index()));
+            if let Some(trace) = swap_trace {
+                return Ok(trace);
+            }
+            tracing::debug!("debug_traceCallMany returned no trace of the swap call, tracing it alone");
+        }
+        Err(e) => tracing::debug!(error = %e, "debug_traceCallMany unavailable, tracing the swap call alone"),
+    }
+
+    let swap_request: TransactionRequest = result.swap_request.clone();
+    Ok(provider
+        .raw_request::<_, CallTrace>("debug_traceCall".into(), (swap_request, block, tracer))
+        .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_innermost_failed_call() {
+        let trace: CallTrace = serde_json::from_value(json!({
+            "type": "CALL",
+            "from": "0x0000000000000000000000000000000000000001",
+            "to": "0x0000000000000000000000000000000000000002",
+            "input": "0x12345678",
+            "error": "execution reverted",
+            "calls": [
+                {
+                    "type": "STATICCALL",
+                    "from": "0x0000000000000000000000000000000000000002",
+                    "to": "0x0000000000000000000000000000000000000003",
+                    "input": "0x"
+                },
+                {
+                    "type": "CALL",
+                    "from": "0x0000000000000000000000000000000000000002",
+                    "to": "0x0000000000000000000000000000000000000004",
+                    "input": "0x128acb08",
+                    "gasUsed": "0x5208",
+                    "error": "execution reverted",
+                    "revertReason": "SPL"
+                }
+            ]
+        }))
+        .unwrap();
+
+        let failure = trace.deepest_failure().unwrap();
+        assert_eq!(failure.to, Some(Address::with_last_byte(4)));
+        assert_eq!(failure.revert_reason.as_deref(), Some("SPL"));
+        assert_eq!(trace.call_count(), 3);
+        assert!(trace.calls[0].deepest_failure().is_none());
+    }
+}
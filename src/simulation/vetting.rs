@@ -0,0 +1,272 @@
+//! Vetting of new tokens with probe simulations.
+//!
+//! Some tokens cannot be traded like plain ERC-20 tokens: fee-on-transfer
+//! tokens deliver less than every pool quotes, blacklisting tokens revert
+//! transfers from or to some addresses, and honeypots can be bought but not
+//! sold. Paths through such tokens look profitable to the optimizer and then
+//! fail in every simulation or, worse, in a submitted bundle.
+//!
+//! `TokenVetter` simulates a small round trip through a new token, buying it
+//! with a source token and selling it back through the same pool, before the
+//! token is searched:
+//!
+//! - **Reverted**: the round trip reverts, the token cannot be bought or sold
+//! - **Fee on transfer**: the realized output falls short of the locally
+//!   quoted output by more than the tolerance
+//! - **Passed**: the token trades as quoted
+//!
+//! Tokens failing the probe are denylisted. Probes that could not be
+//! simulated, e.g. because the RPC request failed, leave the token unvetted.
+
+use crate::errors::{PathError, Result};
+use crate::path::{Path, PathExt, Swap};
+use crate::simulation::{AmountVerification, SimulationOptions, Simulator};
+use alloy::{
+    network::Ethereum,
+    primitives::U256,
+    providers::RootProvider,
+    signers::local::PrivateKeySigner,
+};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tycho_common::Bytes;
+use tycho_simulation::protocol::{models::ProtocolComponent, state::ProtocolSim};
+
+/// Slippage of probe simulations, so the router never reverts on a short output.
+const PROBE_SLIPPAGE_BPS: u64 = 10_000;
+
+/// Outcome of a token probe.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenVerdict {
+    /// The round trip delivered the quoted output
+    Passed,
+    /// The round trip delivered less than quoted, by `divergence_bps`
+    FeeOnTransfer { divergence_bps: f64 },
+    /// The round trip reverted, e.g. because of a blacklist or a honeypot
+    Reverted,
+}
+
+impl TokenVerdict {
+    /// Classify a simulated round trip.
+    ///
+    /// # Arguments
+    ///
+    /// * `success` - Whether the simulated calls succeeded
+    /// * `verification` - The realized versus the quoted output
+    /// * `max_shortfall_bps` - The shortfall tolerated for inaccurate quotes
+    pub fn from_probe(success: bool, verification: &AmountVerification, max_shortfall_bps: f64) -> Self {
+        if !success {
+            TokenVerdict::Reverted
+        } else if !verification.within_tolerance(max_shortfall_bps) {
+            TokenVerdict::FeeOnTransfer {
+                divergence_bps: verification.divergence_bps,
+            }
+        } else {
+            TokenVerdict::Passed
+        }
+    }
+
+    /// Whether the token must not be searched.
+    pub fn is_denied(&self) -> bool {
+        !matches!(self, TokenVerdict::Passed)
+    }
+}
+
+/// Probes new tokens and keeps the denylist of tokens failing their probe.
+#[derive(Debug)]
+pub struct TokenVetter {
+    verdicts: RwLock<HashMap<Bytes, TokenVerdict>>,
+    max_shortfall_bps: f64,
+}
+
+impl TokenVetter {
+    /// Create a vetter tolerating a 1% shortfall of the quoted round trip.
+    pub fn new() -> Self {
+        Self {
+            verdicts: RwLock::new(HashMap::new()),
+            max_shortfall_bps: 100.0,
+        }
+    }
+
+    /// Set the shortfall of the quoted round trip tolerated before a token counts as taxed.
+    pub fn with_max_shortfall_bps(mut self, max_shortfall_bps: f64) -> Self {
+        self.max_shortfall_bps = max_shortfall_bps.max(0.0);
+        self
+    }
+
+    /// Denylist tokens known to be toxic, without probing them.
+    pub fn with_denied_tokens<I>(self, tokens: I) -> Self
+    where
+        I: IntoIterator<Item = Bytes>,
+    {
+        for token in tokens {
+            self.record(token, TokenVerdict::Reverted);
+        }
+        self
+    }
+
+    /// The verdict of a vetted token.
+    pub fn verdict(&self, token: &Bytes) -> Option<TokenVerdict> {
+        self.verdicts.read().ok()?.get(token).cloned()
+    }
+
+    /// Whether a token was vetted, with any verdict.
+    pub fn is_vetted(&self, token: &Bytes) -> bool {
+        self.verdicts.read().is_ok_and(|verdicts| verdicts.contains_key(token))
+    }
+
+    /// Whether a token failed its probe.
+    pub fn is_denied(&self, token: &Bytes) -> bool {
+        self.verdict(token).is_some_and(|verdict| verdict.is_denied())
+    }
+
+    /// The tokens that failed their probe.
+    pub fn denylist(&self) -> Vec<Bytes> {
+        self.verdicts
+            .read()
+            .map(|verdicts| {
+                verdicts
+                    .iter()
+                    .filter(|(_, verdict)| verdict.is_denied())
+                    .map(|(token, _)| token.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Record the verdict of a token, replacing any earlier verdict.
+    pub fn record(&self, token: Bytes, verdict: TokenVerdict) {
+        if let Ok(mut verdicts) = self.verdicts.write() {
+            verdicts.insert(token, verdict);
+        }
+    }
+
+    /// Build the round trip probing `token` from `source_token` through one pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool_comp` - A pool trading the source token against the probed token
+    /// * `pool_sim` - The simulation state of the pool
+    /// * `source_token` - The token held by the executor, e.g. WETH
+    /// * `token` - The probed token
+    /// * `amount_in` - The amount of the source token to probe with
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool does not trade both tokens or cannot quote the amount.
+    pub fn probe_path(
+        pool_comp: Arc<ProtocolComponent>,
+        pool_sim: Arc<dyn ProtocolSim>,
+        source_token: &Bytes,
+        token: &Bytes,
+        amount_in: BigUint,
+    ) -> Result<PathExt> {
+        if source_token == token {
+            return Err(PathError::InvalidPath {
+                reason: format!("Cannot probe source token {} against itself", token),
+            }.into());
+        }
+        let buy = Swap::new(Arc::clone(&pool_comp), Arc::clone(&pool_sim), source_token, token)?;
+        let sell = Swap::new(pool_comp, pool_sim, token, source_token)?;
+        Path(vec![buy, sell]).execute_with_amount(amount_in)
+    }
+
+    /// Simulate a probe round trip and record the verdict of its token.
+    ///
+    /// # Arguments
+    ///
+    /// * `simulator` - The simulator running the probe
+    /// * `provider` - The RPC provider for the simulation
+    /// * `token` - The probed token
+    /// * `probe` - The round trip built by [`TokenVetter::probe_path`]
+    /// * `nonce` - The account nonce of the executor
+    /// * `base_fee` - The base fee of the simulated block
+    /// * `signer` - The executor signing the probe
+    ///
+    /// # Returns
+    ///
+    /// The verdict, or `None` if the probe could not be simulated and the token stays unvetted.
+    pub async fn vet(
+        &self,
+        simulator: &Simulator,
+        provider: &Arc<RootProvider<Ethereum>>,
+        token: &Bytes,
+        probe: &PathExt,
+        nonce: u64,
+        base_fee: U256,
+        signer: &PrivateKeySigner,
+    ) -> Option<TokenVerdict> {
+        let expected_amount_out = &probe.last()?.amount_out;
+        let options = SimulationOptions::new().with_slippage_bps(PROBE_SLIPPAGE_BPS);
+        let result = match simulator
+            .run_simulation_with_options(provider, probe, nonce, base_fee, signer, &options)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::warn!(
+                    token = %token,
+                    error = %e,
+                    "Token probe could not be simulated, leaving the token unvetted"
+                );
+                return None;
+            }
+        };
+
+        let verification = result.verify_amount_out(expected_amount_out);
+        let verdict = TokenVerdict::from_probe(result.report().success, &verification, self.max_shortfall_bps);
+        if verdict.is_denied() {
+            tracing::warn!(
+                token = %token,
+                verdict = ?verdict,
+                expected_amount_out = %verification.expected_amount_out,
+                realized_amount_out = %verification.realized_amount_out,
+                "Token failed its probe, denylisting"
+            );
+        } else {
+            tracing::debug!(
+                token = %token,
+                divergence_bps = verification.divergence_bps,
+                "Token passed its probe"
+            );
+        }
+
+        self.record(token.clone(), verdict.clone());
+        Some(verdict)
+    }
+}
+
+impl Default for TokenVetter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_classifies_probes_and_denylists_failures() {
+        let quoted = |realized: u32| AmountVerification::new(BigUint::from(10_000u32), BigUint::from(realized));
+        assert_eq!(TokenVerdict::from_probe(true, &quoted(9_950), 100.0), TokenVerdict::Passed);
+        assert_eq!(
+            TokenVerdict::from_probe(true, &quoted(9_000), 100.0),
+            TokenVerdict::FeeOnTransfer { divergence_bps: -1_000.0 }
+        );
+        assert_eq!(TokenVerdict::from_probe(false, &quoted(10_000), 100.0), TokenVerdict::Reverted);
+
+        let [taxed, clean, honeypot] = ["0x0001", "0x0002", "0x0003"].map(|token| Bytes::from_str(token).unwrap());
+        let vetter = TokenVetter::new().with_denied_tokens([honeypot.clone()]);
+        vetter.record(taxed.clone(), TokenVerdict::FeeOnTransfer { divergence_bps: -1_000.0 });
+        vetter.record(clean.clone(), TokenVerdict::Passed);
+
+        assert!(vetter.is_denied(&taxed) && vetter.is_denied(&honeypot));
+        assert!(vetter.is_vetted(&clean) && !vetter.is_denied(&clean));
+        let mut denylist = vetter.denylist();
+        denylist.sort();
+        assert_eq!(denylist, vec![taxed, honeypot]);
+    }
+}
@@ -0,0 +1,317 @@
+//! Rolling statistics of the last blocks searched.
+//!
+//! Judging how a running searcher performs, e.g. whether the search budget is
+//! spent well or why bundles stop landing, otherwise takes post-processing of
+//! per-block CSV logs. `BlockStatsAggregator` keeps the statistics of a
+//! rolling window of blocks (100 by default) in memory:
+//!
+//! - **Search**: paths evaluated per stage and search latency
+//! - **Simulation**: successful, failed and profitable simulations and their latency
+//! - **Submission**: submitted bundles and their inclusion
+//!
+//! [`BlockStatsAggregator::window_stats`] aggregates the window with
+//! percentile latencies, e.g. for an API endpoint or a dump on shutdown.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::stats::{BlockStatsAggregator, BlockSummary};
+//!
+//! let stats = BlockStatsAggregator::new();
+//! stats.record_summary(BlockSummary { block_number, initial_paths, ..Default::default() });
+//! stats.record_simulation(block_number, simulation_duration);
+//!
+//! let window = stats.window_stats();
+//! println!("{}", serde_json::to_string_pretty(&window)?);
+//! ```
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Default number of blocks kept in the window.
+pub const DEFAULT_WINDOW_BLOCKS: usize = 100;
+
+/// Statistics of the search in one block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockSummary {
+    /// The searched block
+    pub block_number: u64,
+    /// Paths through the updated pools
+    pub initial_paths: usize,
+    /// Paths left after prefiltering
+    pub candidate_paths: usize,
+    /// Paths found profitable by the optimizer
+    pub optimised_profitable_paths: usize,
+    /// Simulations that completed
+    pub successful_simulations: usize,
+    /// Simulations profitable after gas
+    pub profitable_simulations: usize,
+    /// Simulations that failed or reverted
+    pub failed_simulations: usize,
+    /// Wall-clock time of the whole search, if measured
+    pub search_duration: Option<Duration>,
+}
+
+/// Statistics recorded for one block of the window.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct BlockStats {
+    summary: BlockSummary,
+    simulation_durations: Vec<Duration>,
+    submitted_bundles: usize,
+    included_bundles: usize,
+    missed_bundles: usize,
+}
+
+/// Percentiles of a latency distribution, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LatencyPercentiles {
+    /// Number of measured latencies
+    pub samples: usize,
+    /// Median latency
+    pub p50_ms: f64,
+    /// 90th percentile latency
+    pub p90_ms: f64,
+    /// 99th percentile latency
+    pub p99_ms: f64,
+    /// Largest latency
+    pub max_ms: f64,
+}
+
+impl LatencyPercentiles {
+    /// Compute the percentiles of a set of latencies, all zero if it is empty.
+    pub fn from_durations(durations: &[Duration]) -> Self {
+        let mut millis: Vec<f64> = durations.iter().map(|duration| duration.as_secs_f64() * 1_000.0).collect();
+        if millis.is_empty() {
+            return Self::default();
+        }
+        millis.sort_by(f64::total_cmp);
+        let percentile = |p: f64| millis[((millis.len() - 1) as f64 * p).round() as usize];
+        Self {
+            samples: millis.len(),
+            p50_ms: percentile(0.5),
+            p90_ms: percentile(0.9),
+            p99_ms: percentile(0.99),
+            max_ms: millis[millis.len() - 1],
+        }
+    }
+}
+
+/// Aggregated statistics of the blocks in the window.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WindowStats {
+    /// Number of blocks with recorded statistics
+    pub blocks: usize,
+    /// The oldest block of the window
+    pub first_block: Option<u64>,
+    /// The latest block of the window
+    pub last_block: Option<u64>,
+    /// Paths through updated pools, summed over the window
+    pub initial_paths: usize,
+    /// Paths left after prefiltering, summed over the window
+    pub candidate_paths: usize,
+    /// Paths found profitable by the optimizer, summed over the window
+    pub optimised_profitable_paths: usize,
+    /// Simulations that completed
+    pub successful_simulations: usize,
+    /// Simulations that failed or reverted
+    pub failed_simulations: usize,
+    /// Simulations profitable after gas
+    pub profitable_simulations: usize,
+    /// Share of completed among all simulations, if any ran
+    pub simulation_success_rate: Option<f64>,
+    /// Submitted bundles
+    pub submitted_bundles: usize,
+    /// Submitted bundles that landed
+    pub included_bundles: usize,
+    /// Submitted bundles that did not land
+    pub missed_bundles: usize,
+    /// Share of landed among resolved bundles, if any were resolved
+    pub inclusion_rate: Option<f64>,
+    /// Latency of whole searches
+    pub search_latency: LatencyPercentiles,
+    /// Latency of single simulations
+    pub simulation_latency: LatencyPercentiles,
+}
+
+/// Statistics of a rolling window of recent blocks.
+#[derive(Debug)]
+pub struct BlockStatsAggregator {
+    blocks: RwLock<VecDeque<BlockStats>>,
+    window_blocks: usize,
+}
+
+impl BlockStatsAggregator {
+    /// Create an aggregator keeping the last 100 blocks.
+    pub fn new() -> Self {
+        Self {
+            blocks: RwLock::new(VecDeque::new()),
+            window_blocks: DEFAULT_WINDOW_BLOCKS,
+        }
+    }
+
+    /// Set the number of recent blocks kept in the window.
+    pub fn with_window_blocks(mut self, window_blocks: usize) -> Self {
+        self.window_blocks = window_blocks.max(1);
+        self
+    }
+
+    /// The number of recent blocks kept in the window.
+    pub fn window_blocks(&self) -> usize {
+        self.window_blocks
+    }
+
+    /// Record the search summary of a block, replacing any earlier summary of it.
+    pub fn record_summary(&self, summary: BlockSummary) {
+        self.update(summary.block_number, |stats| stats.summary = summary);
+    }
+
+    /// Record the latency of one simulation of a block's search.
+    pub fn record_simulation(&self, block_number: u64, duration: Duration) {
+        self.update(block_number, |stats| stats.simulation_durations.push(duration));
+    }
+
+    /// Record bundles submitted in a block's search.
+    pub fn record_submissions(&self, block_number: u64, bundles: usize) {
+        self.update(block_number, |stats| stats.submitted_bundles += bundles);
+    }
+
+    /// Record whether a bundle submitted in a block's search landed.
+    pub fn record_inclusion(&self, block_number: u64, included: bool) {
+        self.update(block_number, |stats| {
+            if included {
+                stats.included_bundles += 1;
+            } else {
+                stats.missed_bundles += 1;
+            }
+        });
+    }
+
+    /// The search summaries of the window, oldest first.
+    pub fn summaries(&self) -> Vec<BlockSummary> {
+        self.blocks
+            .read()
+            .map(|blocks| blocks.iter().map(|stats| stats.summary.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Aggregate the statistics of the window.
+    pub fn window_stats(&self) -> WindowStats {
+        let Ok(blocks) = self.blocks.read() else {
+            return WindowStats::default();
+        };
+
+        let mut window = WindowStats {
+            blocks: blocks.len(),
+            first_block: blocks.front().map(|stats| stats.summary.block_number),
+            last_block: blocks.back().map(|stats| stats.summary.block_number),
+            ..Default::default()
+        };
+        let mut search_durations = Vec::new();
+        let mut simulation_durations = Vec::new();
+        for stats in blocks.iter() {
+            let summary = &stats.summary;
+            window.initial_paths += summary.initial_paths;
+            window.candidate_paths += summary.candidate_paths;
+            window.optimised_profitable_paths += summary.optimised_profitable_paths;
+            window.successful_simulations += summary.successful_simulations;
+            window.failed_simulations += summary.failed_simulations;
+            window.profitable_simulations += summary.profitable_simulations;
+            window.submitted_bundles += stats.submitted_bundles;
+            window.included_bundles += stats.included_bundles;
+            window.missed_bundles += stats.missed_bundles;
+            search_durations.extend(summary.search_duration);
+            simulation_durations.extend_from_slice(&stats.simulation_durations);
+        }
+
+        window.simulation_success_rate = rate(
+            window.successful_simulations,
+            window.successful_simulations + window.failed_simulations,
+        );
+        window.inclusion_rate = rate(window.included_bundles, window.included_bundles + window.missed_bundles);
+        window.search_latency = LatencyPercentiles::from_durations(&search_durations);
+        window.simulation_latency = LatencyPercentiles::from_durations(&simulation_durations);
+        window
+    }
+
+    /// Update the statistics of a block, adding it to the window if it is new.
+    ///
+    /// Blocks older than the window are ignored.
+    fn update(&self, block_number: u64, update: impl FnOnce(&mut BlockStats)) {
+        let Ok(mut blocks) = self.blocks.write() else {
+            return;
+        };
+        let position = blocks.iter().rposition(|stats| stats.summary.block_number <= block_number);
+        let index = match position {
+            Some(index) if blocks[index].summary.block_number == block_number => index,
+            _ if blocks.len() >= self.window_blocks
+                && blocks.front().is_some_and(|oldest| oldest.summary.block_number > block_number) =>
+            {
+                return;
+            }
+            position => {
+                let index = position.map_or(0, |index| index + 1);
+                let mut stats = BlockStats::default();
+                stats.summary.block_number = block_number;
+                blocks.insert(index, stats);
+                index
+            }
+        };
+        update(&mut blocks[index]);
+
+        while blocks.len() > self.window_blocks {
+            blocks.pop_front();
+        }
+    }
+}
+
+impl Default for BlockStatsAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The share of `part` in `total`, `None` if the total is zero.
+fn rate(part: usize, total: usize) -> Option<f64> {
+    (total > 0).then(|| part as f64 / total as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(block_number: u64, successful: usize, failed: usize, search_ms: u64) -> BlockSummary {
+        BlockSummary {
+            block_number,
+            initial_paths: 10,
+            successful_simulations: successful,
+            failed_simulations: failed,
+            search_duration: Some(Duration::from_millis(search_ms)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_aggregates_rolling_window() {
+        let stats = BlockStatsAggregator::new().with_window_blocks(3);
+        for block_number in 1..=4 {
+            stats.record_summary(summary(block_number, 3, 1, block_number * 100));
+            stats.record_simulation(block_number, Duration::from_millis(block_number * 10));
+        }
+        stats.record_submissions(4, 2);
+        stats.record_inclusion(4, true);
+        stats.record_inclusion(4, false);
+        // Blocks older than the window are ignored
+        stats.record_summary(summary(1, 3, 1, 100));
+
+        let window = stats.window_stats();
+        assert_eq!((window.blocks, window.first_block, window.last_block), (3, Some(2), Some(4)));
+        assert_eq!(window.initial_paths, 30);
+        assert_eq!(window.simulation_success_rate, Some(0.75));
+        assert_eq!((window.submitted_bundles, window.inclusion_rate), (2, Some(0.5)));
+        assert_eq!(window.search_latency.p50_ms, 300.0);
+        assert_eq!(window.simulation_latency.max_ms, 40.0);
+        assert_eq!(stats.summaries().len(), 3);
+    }
+}
@@ -0,0 +1,349 @@
+//! HTTP status endpoint for running the searcher under orchestration.
+//!
+//! Orchestrators such as Kubernetes decide whether to restart a process or
+//! route work to it through liveness and readiness probes. `StatusServer`
+//! serves the state recorded in a shared [`StatusBoard`] as JSON:
+//!
+//! - **`GET /health`**: liveness, `200` as long as the process answers
+//! - **`GET /ready`**: readiness, `200` once a block was processed recently,
+//!   `503` before the first block or when the stream stalls
+//! - **`GET /status`**: last processed block, stream lag, pending bundles,
+//!   inventory balances and a summary of the configuration
+//!
+//! The server is a minimal HTTP/1.1 responder on a tokio listener without
+//! further dependencies. This module is available with the `status-server` feature.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use tycho_atomic_arbitrage::status::{StatusBoard, StatusServer};
+//!
+//! let board = Arc::new(StatusBoard::new().with_config_entry("chain", "ethereum"));
+//! let server = StatusServer::start("0.0.0.0:8080", Arc::clone(&board)).await?;
+//!
+//! // For every processed block
+//! board.record_block(block_number);
+//! board.set_pending_bundles(pending.len());
+//! ```
+
+use crate::errors::{Result, UtilityError};
+use num_bigint::BigUint;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tycho_common::Bytes;
+
+/// Default age of the last processed block after which the searcher is not ready.
+pub const DEFAULT_MAX_BLOCK_AGE: Duration = Duration::from_secs(60);
+
+/// Largest request head read before answering.
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// The state of the searcher served by the status endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StatusReport {
+    /// Whether a block was processed within the maximum block age
+    pub ready: bool,
+    /// The last processed block
+    pub last_block: Option<u64>,
+    /// Seconds since the last block was processed
+    pub seconds_since_last_block: Option<f64>,
+    /// The latest block of the chain, as last reported
+    pub chain_head: Option<u64>,
+    /// Blocks the processed stream trails the chain head by
+    pub stream_lag_blocks: Option<u64>,
+    /// Submitted bundles awaiting their target block
+    pub pending_bundles: usize,
+    /// Executor balances per token, in base units
+    pub inventory: BTreeMap<String, String>,
+    /// Summary of the configuration, e.g. the chain and the start tokens
+    pub config: BTreeMap<String, String>,
+    /// Seconds since the board was created
+    pub uptime_seconds: f64,
+    /// Unix timestamp of the report
+    pub timestamp: u64,
+}
+
+/// Recorded searcher state shared with a [`StatusServer`].
+#[derive(Debug)]
+pub struct StatusBoard {
+    state: RwLock<BoardState>,
+    started_at: Instant,
+    max_block_age: Duration,
+}
+
+#[derive(Debug, Default)]
+struct BoardState {
+    last_block: Option<(u64, Instant)>,
+    chain_head: Option<u64>,
+    pending_bundles: usize,
+    inventory: BTreeMap<String, String>,
+    config: BTreeMap<String, String>,
+}
+
+impl StatusBoard {
+    /// Create a board that turns unready 60 seconds after the last processed block.
+    pub fn new() -> Self {
+        Self {
+            state: RwLock::new(BoardState::default()),
+            started_at: Instant::now(),
+            max_block_age: DEFAULT_MAX_BLOCK_AGE,
+        }
+    }
+
+    /// Set the age of the last processed block after which the searcher is not ready.
+    pub fn with_max_block_age(mut self, max_block_age: Duration) -> Self {
+        self.max_block_age = max_block_age;
+        self
+    }
+
+    /// Add an entry to the configuration summary.
+    ///
+    /// Secrets such as keys or RPC URLs with credentials must be masked by the caller.
+    pub fn with_config_entry(self, key: impl Into<String>, value: impl ToString) -> Self {
+        if let Ok(mut state) = self.state.write() {
+            state.config.insert(key.into(), value.to_string());
+        }
+        self
+    }
+
+    /// Record a processed block.
+    pub fn record_block(&self, block_number: u64) {
+        if let Ok(mut state) = self.state.write() {
+            state.last_block = Some((block_number, Instant::now()));
+        }
+    }
+
+    /// Record the latest block of the chain, used for the stream lag.
+    pub fn record_chain_head(&self, block_number: u64) {
+        if let Ok(mut state) = self.state.write() {
+            state.chain_head = Some(block_number);
+        }
+    }
+
+    /// Set the number of submitted bundles awaiting their target block.
+    pub fn set_pending_bundles(&self, pending_bundles: usize) {
+        if let Ok(mut state) = self.state.write() {
+            state.pending_bundles = pending_bundles;
+        }
+    }
+
+    /// Replace the executor balances, e.g. with [`crate::inventory::InventoryManager::balances`].
+    pub fn set_inventory(&self, balances: &HashMap<Bytes, BigUint>) {
+        if let Ok(mut state) = self.state.write() {
+            state.inventory = balances
+                .iter()
+                .map(|(token, balance)| (token.to_string(), balance.to_string()))
+                .collect();
+        }
+    }
+
+    /// Whether a block was processed within the maximum block age.
+    pub fn is_ready(&self) -> bool {
+        self.state.read().is_ok_and(|state| {
+            state
+                .last_block
+                .is_some_and(|(_, processed_at)| processed_at.elapsed() <= self.max_block_age)
+        })
+    }
+
+    /// A snapshot of the recorded state.
+    pub fn report(&self) -> StatusReport {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let Ok(state) = self.state.read() else {
+            return StatusReport {
+                timestamp,
+                ..Default::default()
+            };
+        };
+
+        let last_block = state.last_block.map(|(block_number, _)| block_number);
+        StatusReport {
+            ready: state
+                .last_block
+                .is_some_and(|(_, processed_at)| processed_at.elapsed() <= self.max_block_age),
+            last_block,
+            seconds_since_last_block: state.last_block.map(|(_, processed_at)| processed_at.elapsed().as_secs_f64()),
+            chain_head: state.chain_head,
+            stream_lag_blocks: state
+                .chain_head
+                .zip(last_block)
+                .map(|(head, last_block)| head.saturating_sub(last_block)),
+            pending_bundles: state.pending_bundles,
+            inventory: state.inventory.clone(),
+            config: state.config.clone(),
+            uptime_seconds: self.started_at.elapsed().as_secs_f64(),
+            timestamp,
+        }
+    }
+}
+
+impl Default for StatusBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An HTTP server answering health, readiness and status requests from a [`StatusBoard`].
+///
+/// The server stops when it is dropped.
+#[derive(Debug)]
+pub struct StatusServer {
+    address: SocketAddr,
+    server: JoinHandle<()>,
+}
+
+impl StatusServer {
+    /// Start serving the board on an address, e.g. `0.0.0.0:8080`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be bound.
+    pub async fn start(address: &str, board: Arc<StatusBoard>) -> Result<Self> {
+        let listener = TcpListener::bind(address).await.map_err(|e| UtilityError::StatusServerFailed {
+            address: address.to_string(),
+            error: e.to_string(),
+        })?;
+        let address = listener.local_addr().map_err(|e| UtilityError::StatusServerFailed {
+            address: address.to_string(),
+            error: e.to_string(),
+        })?;
+
+        let server = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let board = Arc::clone(&board);
+                tokio::spawn(async move {
+                    if let Err(e) = Self::serve(stream, &board).await {
+                        tracing::debug!(error = %e, "Failed to answer status request");
+                    }
+                });
+            }
+        });
+
+        tracing::info!(address = %address, "Status server listening");
+        Ok(Self { address, server })
+    }
+
+    /// The address the server is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Answer one request.
+    async fn serve(mut stream: TcpStream, board: &StatusBoard) -> std::io::Result<()> {
+        let path = Self::read_path(&mut stream).await?;
+        let (status, body) = Self::respond(path.as_deref(), board);
+        let reason = match status {
+            200 => "OK",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Service Unavailable",
+        };
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            reason,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// The status and JSON body answering a `GET` of `path`, `None` for other methods.
+    fn respond(path: Option<&str>, board: &StatusBoard) -> (u16, String) {
+        let Some(path) = path else {
+            return (405, serde_json::json!({ "error": "method not allowed" }).to_string());
+        };
+        match path.split('?').next().unwrap_or_default() {
+            "/health" | "/healthz" => (200, serde_json::json!({ "status": "ok" }).to_string()),
+            "/ready" | "/readyz" => {
+                let report = board.report();
+                let body = serde_json::json!({
+                    "ready": report.ready,
+                    "last_block": report.last_block,
+                    "seconds_since_last_block": report.seconds_since_last_block,
+                })
+                .to_string();
+                (if report.ready { 200 } else { 503 }, body)
+            }
+            "/status" | "/" => (
+                200,
+                serde_json::to_string(&board.report()).unwrap_or_else(|_| "{}".to_string()),
+            ),
+            _ => (404, serde_json::json!({ "error": "not found" }).to_string()),
+        }
+    }
+
+    /// Read the request head and return the path of a `GET` request.
+    async fn read_path(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 1024];
+        while !buffer.windows(4).any(|window| window == b"\r\n\r\n") && buffer.len() < MAX_REQUEST_BYTES {
+            let read = stream.read(&mut chunk).await?;
+            if read == 0 {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        }
+
+        let head = String::from_utf8_lossy(&buffer);
+        let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+        Ok(match (request_line.next(), request_line.next()) {
+            (Some("GET"), Some(path)) => Some(path.to_string()),
+            _ => None,
+        })
+    }
+}
+
+impl Drop for StatusServer {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_serves_readiness_and_status() {
+        let board = Arc::new(StatusBoard::new().with_config_entry("chain", "ethereum"));
+        let server = StatusServer::start("127.0.0.1:0", Arc::clone(&board)).await.unwrap();
+        let address = server.local_addr();
+
+        assert!(get(address, "/health").await.starts_with("HTTP/1.1 200"));
+        assert!(get(address, "/ready").await.starts_with("HTTP/1.1 503"));
+        assert!(get(address, "/missing").await.starts_with("HTTP/1.1 404"));
+
+        board.record_block(100);
+        board.record_chain_head(102);
+        board.set_pending_bundles(1);
+        assert!(get(address, "/ready").await.starts_with("HTTP/1.1 200"));
+
+        let response = get(address, "/status").await;
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        let status: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(status["last_block"], 100);
+        assert_eq!(status["stream_lag_blocks"], 2);
+        assert_eq!(status["pending_bundles"], 1);
+        assert_eq!(status["config"]["chain"], "ethereum");
+    }
+}
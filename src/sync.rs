@@ -0,0 +1,238 @@
+//! Consistency tracking of the block update stream.
+//!
+//! Protocol states are maintained from deltas, so a single missed block leaves
+//! every pool it touched with stale state, and local simulations keep reporting
+//! opportunities that no longer exist. `StreamSync` watches the block numbers of
+//! incoming updates, detects disconnects and gaps, and tells the stream layer when
+//! a fresh snapshot is needed. Searching is suppressed from the moment state may
+//! be inconsistent until a snapshot was applied and the stream has delivered a
+//! number of contiguous blocks after it.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use tycho_atomic_arbitrage::sync::{StreamSync, SyncAction};
+//!
+//! let mut sync = StreamSync::new();
+//! assert_eq!(sync.observe(100), SyncAction::Snapshot);
+//! assert_eq!(sync.observe(101), SyncAction::Apply);
+//! assert!(matches!(sync.observe(105), SyncAction::Resync(_)));
+//! assert!(!sync.is_synced());
+//! ```
+
+use std::fmt;
+
+/// Default number of blocks that may be missing between two updates.
+const DEFAULT_MAX_BLOCK_GAP: u64 = 0;
+
+/// Default number of contiguous blocks after a resync snapshot before searching again.
+const DEFAULT_SETTLE_BLOCKS: u64 = 1;
+
+/// Why the stream has to be resynchronized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResyncReason {
+    /// The stream ended or failed and was reconnected
+    Disconnected,
+    /// Updates between two received blocks are missing
+    BlockGap {
+        /// The block after the last applied one
+        expected: u64,
+        /// The block that was received instead
+        received: u64,
+    },
+}
+
+impl fmt::Display for ResyncReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResyncReason::Disconnected => write!(f, "disconnected"),
+            ResyncReason::BlockGap { expected, received } => {
+                write!(f, "block gap (expected {}, received {})", expected, received)
+            }
+        }
+    }
+}
+
+/// What to do with a received block update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAction {
+    /// Apply the update as a delta on top of the current state
+    Apply,
+    /// The update is a full snapshot; state not part of it is stale
+    Snapshot,
+    /// Discard the update and request a fresh snapshot
+    Resync(ResyncReason),
+}
+
+/// Tracks whether the state built from the block update stream is consistent.
+#[derive(Debug, Clone)]
+pub struct StreamSync {
+    max_block_gap: u64,
+    settle_blocks: u64,
+    last_block: Option<u64>,
+    awaiting_snapshot: bool,
+    /// Blocks still to be received after a resync snapshot before searching again
+    settling_blocks: u64,
+    last_resync: Option<ResyncReason>,
+    resync_count: u64,
+}
+
+impl StreamSync {
+    /// Create a tracker that expects the first update to be a snapshot, treats
+    /// any missing block as a gap and resumes searching one block after a resync.
+    pub fn new() -> Self {
+        Self {
+            max_block_gap: DEFAULT_MAX_BLOCK_GAP,
+            settle_blocks: DEFAULT_SETTLE_BLOCKS,
+            last_block: None,
+            awaiting_snapshot: true,
+            settling_blocks: 0,
+            last_resync: None,
+            resync_count: 0,
+        }
+    }
+
+    /// Set the number of blocks that may be missing between two updates.
+    pub fn with_max_block_gap(mut self, max_block_gap: u64) -> Self {
+        self.max_block_gap = max_block_gap;
+        self
+    }
+
+    /// Set the number of contiguous blocks after a resync snapshot before searching again.
+    pub fn with_settle_blocks(mut self, settle_blocks: u64) -> Self {
+        self.settle_blocks = settle_blocks;
+        self
+    }
+
+    /// Classify a received block update.
+    ///
+    /// Blocks at or below the last applied one are reverts sent by Tycho and are
+    /// applied as usual.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_number` - The block of the received update
+    ///
+    /// # Returns
+    ///
+    /// Whether to apply the update as a delta or snapshot, or to resync instead
+    pub fn observe(&mut self, block_number: u64) -> SyncAction {
+        if self.awaiting_snapshot {
+            self.awaiting_snapshot = false;
+            self.last_block = Some(block_number);
+            if self.last_resync.is_some() {
+                self.settling_blocks = self.settle_blocks;
+            }
+            return SyncAction::Snapshot;
+        }
+
+        if let Some(last_block) = self.last_block {
+            let expected = last_block + 1;
+            if block_number > expected + self.max_block_gap {
+                let reason = ResyncReason::BlockGap { expected, received: block_number };
+                self.resync(reason);
+                return SyncAction::Resync(reason);
+            }
+        }
+
+        self.last_block = Some(self.last_block.map_or(block_number, |last| last.max(block_number)));
+        self.settling_blocks = self.settling_blocks.saturating_sub(1);
+        SyncAction::Apply
+    }
+
+    /// Record that the stream disconnected; the next update must be a snapshot.
+    pub fn disconnected(&mut self) {
+        self.resync(ResyncReason::Disconnected);
+    }
+
+    /// Whether the state is consistent and searching may proceed.
+    pub fn is_synced(&self) -> bool {
+        !self.awaiting_snapshot && self.settling_blocks == 0
+    }
+
+    /// The last applied block, if any.
+    pub fn last_block(&self) -> Option<u64> {
+        self.last_block
+    }
+
+    /// The reason of the most recent resync, if any.
+    pub fn last_resync(&self) -> Option<ResyncReason> {
+        self.last_resync
+    }
+
+    /// Number of resyncs since the tracker was created.
+    pub fn resync_count(&self) -> u64 {
+        self.resync_count
+    }
+
+    fn resync(&mut self, reason: ResyncReason) {
+        // Repeated failures while waiting for a snapshot are a single resync
+        if !self.awaiting_snapshot {
+            self.resync_count += 1;
+        }
+        self.awaiting_snapshot = true;
+        self.last_resync = Some(reason);
+
+        tracing::warn!(
+            reason = %reason,
+            last_block = ?self.last_block,
+            resync_count = self.resync_count,
+            "Block update stream out of sync, awaiting snapshot"
+        );
+    }
+}
+
+impl Default for StreamSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gap_triggers_resync_until_settled() {
+        let mut sync = StreamSync::new().with_settle_blocks(2);
+
+        // The initial snapshot is searchable right away
+        assert_eq!(sync.observe(100), SyncAction::Snapshot);
+        assert!(sync.is_synced());
+        assert_eq!(sync.observe(101), SyncAction::Apply);
+
+        assert_eq!(
+            sync.observe(103),
+            SyncAction::Resync(ResyncReason::BlockGap { expected: 102, received: 103 })
+        );
+        assert!(!sync.is_synced());
+
+        assert_eq!(sync.observe(104), SyncAction::Snapshot);
+        assert!(!sync.is_synced());
+        assert_eq!(sync.observe(105), SyncAction::Apply);
+        assert!(!sync.is_synced());
+        assert_eq!(sync.observe(106), SyncAction::Apply);
+        assert!(sync.is_synced());
+        assert_eq!(sync.resync_count(), 1);
+    }
+
+    #[test]
+    fn test_disconnect_and_tolerated_gaps() {
+        let mut sync = StreamSync::new().with_max_block_gap(1).with_settle_blocks(0);
+        sync.observe(10);
+
+        // One missing block is tolerated, reverts are applied
+        assert_eq!(sync.observe(12), SyncAction::Apply);
+        assert_eq!(sync.observe(11), SyncAction::Apply);
+        assert_eq!(sync.last_block(), Some(12));
+
+        sync.disconnected();
+        sync.disconnected();
+        assert!(!sync.is_synced());
+        assert_eq!(sync.resync_count(), 1);
+        assert_eq!(sync.last_resync(), Some(ResyncReason::Disconnected));
+
+        assert_eq!(sync.observe(20), SyncAction::Snapshot);
+        assert!(sync.is_synced());
+    }
+}
@@ -0,0 +1,269 @@
+//! Deterministic fixtures for tests of arbitrage strategies.
+//!
+//! Tests of paths, optimizers and bundles need tokens, protocol components and
+//! pool simulations, but not real protocol state. `MockProtocolSim` prices a
+//! swap at a configurable rate, fee and curvature, and the helper functions
+//! build tokens, two-token components and cyclic paths around it. Larger
+//! markets are generated by [`SyntheticMarket`].
+//!
+//! Bundle submission is tested against [`MockRelayer`]s, local servers
+//! answering `eth_sendBundle` requests with scripted responses.
+//!
+//! This module is available with the `testing` feature.
+
+mod relay;
+
+use crate::errors::{PathError, Result};
+use crate::path::{Path, Swap};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tycho_common::Bytes;
+use tycho_simulation::{
+    models::{Balances, Token},
+    protocol::{
+        errors::{SimulationError, TransitionError},
+        models::{GetAmountOutResult, ProtocolComponent},
+        state::ProtocolSim,
+    },
+};
+
+pub use crate::graph::synthetic::{ConstantProductSim, SyntheticMarket, SyntheticMarketConfig};
+pub use relay::{MockRelayResponse, MockRelayer};
+
+/// Fixed-point scale of rates, fees and curvatures, in parts per million.
+const PPM: u64 = 1_000_000;
+
+/// A pool simulation with a configurable exchange rate.
+///
+/// Without fee and curvature a swap returns the input amount times the rate,
+/// for any pair of tokens. The fee is deducted from the output, and the
+/// curvature adds price impact: with a curvature of 1, swapping the whole
+/// input limit returns half the linear output, like a constant-product pool
+/// of that depth.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockProtocolSim {
+    rate: f64,
+    fee: f64,
+    curvature: f64,
+    max_amount_in: BigUint,
+    max_amount_out: BigUint,
+    gas: u32,
+}
+
+impl MockProtocolSim {
+    /// A pool returning the input amount, within limits of 1,000,000 units.
+    pub fn new() -> Self {
+        Self {
+            rate: 1.0,
+            fee: 0.0,
+            curvature: 0.0,
+            max_amount_in: BigUint::from(1_000_000u32),
+            max_amount_out: BigUint::from(1_000_000u32),
+            gas: 21_000,
+        }
+    }
+
+    /// Set the exchange rate before fees.
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = rate.max(0.0);
+        self
+    }
+
+    /// Set the fee deducted from the output, between 0 and 1.
+    pub fn with_fee(mut self, fee: f64) -> Self {
+        self.fee = fee.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the price impact, 0 for a linear pool.
+    pub fn with_curvature(mut self, curvature: f64) -> Self {
+        self.curvature = curvature.max(0.0);
+        self
+    }
+
+    /// Set the largest input and output amounts of a swap.
+    pub fn with_limits(mut self, max_amount_in: BigUint, max_amount_out: BigUint) -> Self {
+        self.max_amount_in = max_amount_in;
+        self.max_amount_out = max_amount_out;
+        self
+    }
+
+    /// Set the gas of a swap.
+    pub fn with_gas(mut self, gas: u32) -> Self {
+        self.gas = gas;
+        self
+    }
+
+    fn ppm(value: f64) -> BigUint {
+        BigUint::from((value * PPM as f64).round() as u64)
+    }
+}
+
+impl Default for MockProtocolSim {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtocolSim for MockProtocolSim {
+    fn clone_box(&self) -> Box<dyn ProtocolSim> {
+        Box::new(self.clone())
+    }
+
+    fn fee(&self) -> f64 {
+        self.fee
+    }
+
+    fn spot_price(&self, _base: &Token, _quote: &Token) -> std::result::Result<f64, SimulationError> {
+        Ok(self.rate * (1.0 - self.fee))
+    }
+
+    fn get_amount_out(
+        &self,
+        amount_in: BigUint,
+        _token_in: &Token,
+        _token_out: &Token,
+    ) -> std::result::Result<GetAmountOutResult, SimulationError> {
+        let linear = &amount_in * Self::ppm(self.rate * (1.0 - self.fee)) / PPM;
+
+        // linear * depth / (depth + curvature * amount_in)
+        let depth = &self.max_amount_in * PPM;
+        let impact = Self::ppm(self.curvature) * &amount_in;
+        let amount = if depth == BigUint::from(0u32) {
+            linear
+        } else {
+            linear * &depth / (&depth + impact)
+        };
+
+        Ok(GetAmountOutResult {
+            amount,
+            gas: BigUint::from(self.gas),
+            new_state: Box::new(self.clone()),
+        })
+    }
+
+    fn get_limits(&self, _token_in: Bytes, _token_out: Bytes) -> std::result::Result<(BigUint, BigUint), SimulationError> {
+        Ok((self.max_amount_in.clone(), self.max_amount_out.clone()))
+    }
+
+    fn delta_transition(
+        &mut self,
+        _delta: tycho_common::dto::ProtocolStateDelta,
+        _tokens: &HashMap<Bytes, Token>,
+        _balances: &Balances,
+    ) -> std::result::Result<(), TransitionError<String>> {
+        Ok(())
+    }
+
+    fn as_any(&self) -> &(dyn std::any::Any + 'static) {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut (dyn std::any::Any + 'static) {
+        self
+    }
+
+    fn eq(&self, other: &(dyn ProtocolSim + 'static)) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
+
+/// A token with 18 decimals.
+pub fn token(address: &Bytes, symbol: &str) -> Token {
+    Token {
+        address: address.clone(),
+        symbol: symbol.to_string(),
+        decimals: 18,
+        gas: BigUint::from(0u32),
+    }
+}
+
+/// A test component of a pool trading the given tokens.
+pub fn component(pool: &Bytes, tokens: &[Bytes]) -> ProtocolComponent {
+    ProtocolComponent {
+        id: pool.clone(),
+        address: pool.clone(),
+        protocol_system: "test".to_string(),
+        protocol_type_name: "test_pool".to_string(),
+        chain: tycho_common::models::Chain::Ethereum,
+        tokens: tokens.iter().map(|address| token(address, "TEST")).collect(),
+        contract_ids: vec![pool.clone()],
+        static_attributes: HashMap::new(),
+        created_at: chrono::DateTime::from_timestamp(0, 0).unwrap_or_default().naive_utc(),
+        creation_tx: Bytes::default(),
+    }
+}
+
+/// A cyclic path through the given tokens, with one pool per swap.
+///
+/// Swap `i` trades `tokens[i]` for the next token, and the last swap returns
+/// to `tokens[0]`. Pool addresses are derived from `first_pool` by adding the
+/// swap index to its last byte.
+///
+/// # Errors
+///
+/// Returns an error if fewer than two tokens are given.
+pub fn cyclic_path(tokens: &[Bytes], first_pool: &Bytes, sim: &MockProtocolSim) -> Result<Path> {
+    if tokens.len() < 2 {
+        return Err(PathError::InvalidPath {
+            reason: "A cyclic path needs at least two tokens".to_string(),
+        }.into());
+    }
+
+    let mut swaps = Vec::with_capacity(tokens.len());
+    for (index, token_in) in tokens.iter().enumerate() {
+        let token_out = &tokens[(index + 1) % tokens.len()];
+        let mut pool = first_pool.to_vec();
+        if let Some(last) = pool.last_mut() {
+            *last = last.wrapping_add(index as u8);
+        }
+        let pool = Bytes::from(pool);
+        swaps.push(Swap::new(
+            component(&pool, &[token_in.clone(), token_out.clone()]),
+            Arc::new(sim.clone()),
+            token_in,
+            token_out,
+        )?);
+    }
+    Ok(Path(swaps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_mock_applies_rate_fee_and_curvature() {
+        let token_a = token(&Bytes::from_str("0x0001").unwrap(), "A");
+        let token_b = token(&Bytes::from_str("0x0002").unwrap(), "B");
+        let amount_out = |sim: &MockProtocolSim, amount: u32| {
+            sim.get_amount_out(BigUint::from(amount), &token_a, &token_b).unwrap().amount
+        };
+
+        assert_eq!(amount_out(&MockProtocolSim::new(), 1000), BigUint::from(1000u32));
+        assert_eq!(amount_out(&MockProtocolSim::new().with_rate(1.1), 1000), BigUint::from(1100u32));
+        assert_eq!(amount_out(&MockProtocolSim::new().with_fee(0.01), 1000), BigUint::from(990u32));
+
+        // Swapping the whole limit halves the output at curvature 1
+        let curved = MockProtocolSim::new()
+            .with_curvature(1.0)
+            .with_limits(BigUint::from(1000u32), BigUint::from(1000u32));
+        assert_eq!(amount_out(&curved, 1000), BigUint::from(500u32));
+        assert_eq!(amount_out(&curved, 100), BigUint::from(90u32));
+    }
+
+    #[test]
+    fn test_builds_cyclic_paths() {
+        let tokens: Vec<Bytes> = ["0x0001", "0x0002", "0x0003"].iter().map(|t| Bytes::from_str(t).unwrap()).collect();
+        let pool = Bytes::from_str("0x1001").unwrap();
+        let path = cyclic_path(&tokens, &pool, &MockProtocolSim::new().with_rate(1.01)).unwrap();
+
+        assert_eq!(path.len(), 3);
+        assert_eq!(path[2].token_out.address, tokens[0]);
+        assert_eq!(path[1].pool_comp.id, Bytes::from_str("0x1002").unwrap());
+        assert!(path.calculate_profit_loss(BigUint::from(1000u32)).unwrap() > 0.into());
+        assert!(cyclic_path(&tokens[..1], &pool, &MockProtocolSim::new()).is_err());
+    }
+}
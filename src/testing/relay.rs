@@ -0,0 +1,201 @@
+//! A local relayer answering bundle submissions with scripted responses.
+
+use crate::errors::{BundleError, Result};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// The answer of a [`MockRelayer`] to every request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockRelayResponse {
+    /// Accept the bundle and return its hash
+    Accept { bundle_hash: String },
+    /// Return a JSON-RPC error
+    RpcError { code: i64, message: String },
+    /// Return an HTTP error status, e.g. 429 when rate limited
+    HttpStatus { status: u16, body: String },
+    /// Return a body that is not a JSON-RPC response
+    Malformed { body: String },
+    /// Answer after a delay, e.g. longer than the client timeout
+    Delayed { delay: Duration, response: Box<MockRelayResponse> },
+}
+
+impl MockRelayResponse {
+    /// Accept bundles with the given hash.
+    pub fn accept(bundle_hash: impl Into<String>) -> Self {
+        Self::Accept { bundle_hash: bundle_hash.into() }
+    }
+
+    /// Reject requests with HTTP 429 Too Many Requests.
+    pub fn rate_limited() -> Self {
+        Self::HttpStatus {
+            status: 429,
+            body: "Too Many Requests".to_string(),
+        }
+    }
+
+    /// Answer with this response after a delay.
+    pub fn delayed(self, delay: Duration) -> Self {
+        Self::Delayed {
+            delay,
+            response: Box::new(self),
+        }
+    }
+
+    /// The HTTP status and body of the response.
+    fn status_and_body(&self) -> (u16, String) {
+        match self {
+            Self::Accept { bundle_hash } => (
+                200,
+                serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": { "bundleHash": bundle_hash } }).to_string(),
+            ),
+            Self::RpcError { code, message } => (
+                200,
+                serde_json::json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": code, "message": message } }).to_string(),
+            ),
+            Self::HttpStatus { status, body } => (*status, body.clone()),
+            Self::Malformed { body } => (200, body.clone()),
+            Self::Delayed { response, .. } => response.status_and_body(),
+        }
+    }
+}
+
+/// A relayer on a local port answering requests with scripted responses.
+///
+/// The server stops when the relayer is dropped.
+#[derive(Debug)]
+pub struct MockRelayer {
+    url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+    server: JoinHandle<()>,
+}
+
+impl MockRelayer {
+    /// Start a relayer on a free local port, answering every request with `response`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no local port can be bound.
+    pub async fn start(response: MockRelayResponse) -> Result<Self> {
+        Self::start_sequence(vec![response]).await
+    }
+
+    /// Start a relayer answering requests with `responses` in order.
+    ///
+    /// The last response answers all further requests, so
+    /// `[rate_limited(), accept(..)]` accepts the first retry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no local port can be bound or no response is given.
+    pub async fn start_sequence(responses: Vec<MockRelayResponse>) -> Result<Self> {
+        if responses.is_empty() {
+            return Err(BundleError::InvalidConfiguration {
+                message: "A mock relayer needs at least one response".to_string(),
+            }.into());
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(|e| BundleError::RelayerConnectionFailed {
+            url: "127.0.0.1:0".to_string(),
+            error: e.to_string(),
+        })?;
+        let address = listener.local_addr().map_err(|e| BundleError::RelayerConnectionFailed {
+            url: "127.0.0.1:0".to_string(),
+            error: e.to_string(),
+        })?;
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        let server = tokio::spawn(async move {
+            let mut accepted = 0;
+            while let Ok((stream, _)) = listener.accept().await {
+                let response = responses[accepted.min(responses.len() - 1)].clone();
+                accepted += 1;
+                let recorded = Arc::clone(&recorded);
+                tokio::spawn(async move {
+                    let _ = Self::serve(stream, &response, &recorded).await;
+                });
+            }
+        });
+
+        Ok(Self {
+            url: format!("http://{}", address),
+            requests,
+            server,
+        })
+    }
+
+    /// The URL to submit bundles to.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The bodies of all requests received so far.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().map(|requests| requests.clone()).unwrap_or_default()
+    }
+
+    /// The number of requests received so far.
+    pub fn request_count(&self) -> usize {
+        self.requests.lock().map(|requests| requests.len()).unwrap_or(0)
+    }
+
+    /// Read one HTTP request and write the scripted response.
+    async fn serve(
+        mut stream: TcpStream,
+        response: &MockRelayResponse,
+        recorded: &Mutex<Vec<String>>,
+    ) -> std::io::Result<()> {
+        let body = Self::read_body(&mut stream).await?;
+        if let Ok(mut requests) = recorded.lock() {
+            requests.push(body);
+        }
+
+        if let MockRelayResponse::Delayed { delay, .. } = response {
+            tokio::time::sleep(*delay).await;
+        }
+        let (status, body) = response.status_and_body();
+        let head = format!(
+            "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// Read the headers and the body of an HTTP request.
+    async fn read_body(stream: &mut TcpStream) -> std::io::Result<String> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let read = stream.read(&mut chunk).await?;
+            if read == 0 {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+
+            if let Some(header_end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                let content_length = String::from_utf8_lossy(&buffer[..header_end])
+                    .lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+                    .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if buffer.len() >= header_end + 4 + content_length {
+                    return Ok(String::from_utf8_lossy(&buffer[header_end + 4..header_end + 4 + content_length]).into_owned());
+                }
+            }
+        }
+        Ok(String::new())
+    }
+}
+
+impl Drop for MockRelayer {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
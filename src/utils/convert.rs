@@ -0,0 +1,281 @@
+//! Numeric conversions between arbitrary-precision, 256-bit and floating point types.
+//!
+//! Tycho simulations work with `BigUint`, transactions and logs with alloy's
+//! `U256` and `I256`, profits with `BigInt`, and metrics and logs report `f64`.
+//! This module converts between all of them without string round-trips, in
+//! three flavours:
+//!
+//! - **Lossless**: always succeeds, e.g. [`u256_to_biguint`]
+//! - **Checked**: returns `None` when the value does not fit the target type
+//! - **Saturating**: clamps to the nearest representable value
+//!
+//! Conversions from `f64` additionally take a [`Rounding`] mode, and conversions
+//! to `f64` round to the nearest representable float.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use alloy::primitives::U256;
+//! use num_bigint::BigUint;
+//! use tycho_atomic_arbitrage::utils::convert::{self, Rounding};
+//!
+//! let amount = BigUint::from(1_500_000u64);
+//! assert_eq!(convert::biguint_to_u256_checked(&amount), Some(U256::from(1_500_000u64)));
+//! assert_eq!(convert::biguint_to_f64(&amount), 1_500_000.0);
+//! assert_eq!(convert::f64_to_biguint_saturating(2.5, Rounding::Floor), BigUint::from(2u32));
+//! ```
+
+use alloy::primitives::{Sign as I256Sign, I256, U256};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+
+/// How fractional values are rounded when converting from `f64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// Round towards negative infinity
+    Floor,
+    /// Round towards positive infinity
+    Ceil,
+    /// Round to the nearest integer, halfway cases away from zero
+    #[default]
+    Nearest,
+}
+
+impl Rounding {
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            Rounding::Floor => value.floor(),
+            Rounding::Ceil => value.ceil(),
+            Rounding::Nearest => value.round(),
+        }
+    }
+}
+
+// ================================
+// BigUint <-> U256
+// ================================
+
+/// Convert a U256 to a BigUint.
+pub fn u256_to_biguint(value: U256) -> BigUint {
+    BigUint::from_bytes_le(&value.to_le_bytes::<32>())
+}
+
+/// Convert a BigUint to a U256, or `None` if it exceeds 2^256 - 1.
+pub fn biguint_to_u256_checked(value: &BigUint) -> Option<U256> {
+    let digits = value.to_u64_digits();
+    if digits.len() > 4 {
+        return None;
+    }
+    let mut limbs = [0u64; 4];
+    limbs[..digits.len()].copy_from_slice(&digits);
+    Some(U256::from_limbs(limbs))
+}
+
+/// Convert a BigUint to a U256, clamping values above 2^256 - 1 to `U256::MAX`.
+pub fn biguint_to_u256_saturating(value: &BigUint) -> U256 {
+    biguint_to_u256_checked(value).unwrap_or(U256::MAX)
+}
+
+// ================================
+// BigInt <-> I256
+// ================================
+
+/// Convert an I256 to a BigInt.
+pub fn i256_to_bigint(value: I256) -> BigInt {
+    let (sign, abs) = value.into_sign_and_abs();
+    let magnitude = u256_to_biguint(abs);
+    if sign.is_negative() {
+        BigInt::from_biguint(Sign::Minus, magnitude)
+    } else {
+        BigInt::from_biguint(Sign::Plus, magnitude)
+    }
+}
+
+/// Convert a BigInt to an I256, or `None` if it is outside the I256 range.
+pub fn bigint_to_i256_checked(value: &BigInt) -> Option<I256> {
+    let abs = biguint_to_u256_checked(value.magnitude())?;
+    let sign = if value.sign() == Sign::Minus {
+        I256Sign::Negative
+    } else {
+        I256Sign::Positive
+    };
+    I256::checked_from_sign_and_abs(sign, abs)
+}
+
+/// Convert a BigInt to an I256, clamping to `I256::MIN` and `I256::MAX`.
+pub fn bigint_to_i256_saturating(value: &BigInt) -> I256 {
+    bigint_to_i256_checked(value).unwrap_or(if value.is_negative() {
+        I256::MIN
+    } else {
+        I256::MAX
+    })
+}
+
+// ================================
+// BigUint <-> BigInt
+// ================================
+
+/// Convert a BigUint to a non-negative BigInt.
+pub fn biguint_to_bigint(value: &BigUint) -> BigInt {
+    BigInt::from_biguint(Sign::Plus, value.clone())
+}
+
+/// Convert a BigInt to a BigUint, or `None` if it is negative.
+pub fn bigint_to_biguint_checked(value: &BigInt) -> Option<BigUint> {
+    value.to_biguint()
+}
+
+/// Convert a BigInt to a BigUint, clamping negative values to zero.
+pub fn bigint_to_biguint_saturating(value: &BigInt) -> BigUint {
+    value.to_biguint().unwrap_or_else(BigUint::zero)
+}
+
+// ================================
+// Integers -> f64
+// ================================
+
+/// Convert a BigUint to the nearest f64, or infinity if it exceeds `f64::MAX`.
+pub fn biguint_to_f64(value: &BigUint) -> f64 {
+    value.to_f64().unwrap_or(f64::INFINITY)
+}
+
+/// Convert a BigInt to the nearest f64, or an infinity if it exceeds the f64 range.
+pub fn bigint_to_f64(value: &BigInt) -> f64 {
+    value.to_f64().unwrap_or(if value.is_negative() {
+        f64::NEG_INFINITY
+    } else {
+        f64::INFINITY
+    })
+}
+
+/// Convert a U256 to the nearest f64.
+pub fn u256_to_f64(value: U256) -> f64 {
+    biguint_to_f64(&u256_to_biguint(value))
+}
+
+/// Convert an I256 to the nearest f64.
+pub fn i256_to_f64(value: I256) -> f64 {
+    bigint_to_f64(&i256_to_bigint(value))
+}
+
+/// Convert a token amount in base units to a decimal f64, e.g. wei to ether.
+///
+/// # Arguments
+///
+/// * `value` - The amount in the token's smallest unit
+/// * `decimals` - The number of decimals of the token
+pub fn biguint_to_decimal_f64(value: &BigUint, decimals: u32) -> f64 {
+    biguint_to_f64(value) / 10f64.powi(decimals as i32)
+}
+
+// ================================
+// f64 -> integers
+// ================================
+
+/// Convert an f64 to a BigUint, or `None` if it is not finite or rounds to a negative value.
+pub fn f64_to_biguint_checked(value: f64, rounding: Rounding) -> Option<BigUint> {
+    if !value.is_finite() {
+        return None;
+    }
+    let rounded = rounding.apply(value);
+    if rounded < 0.0 {
+        return None;
+    }
+    BigUint::from_f64(rounded)
+}
+
+/// Convert an f64 to a BigUint, clamping to the U256 range.
+///
+/// NaN and negative values become zero, values above 2^256 - 1 including
+/// positive infinity become 2^256 - 1, so the result can always be used as a
+/// transaction amount.
+pub fn f64_to_biguint_saturating(value: f64, rounding: Rounding) -> BigUint {
+    if value.is_nan() {
+        return BigUint::zero();
+    }
+    let u256_max = u256_to_biguint(U256::MAX);
+    match f64_to_biguint_checked(value, rounding) {
+        Some(converted) => converted.min(u256_max),
+        None if value > 0.0 => u256_max,
+        None => BigUint::zero(),
+    }
+}
+
+/// Convert an f64 to a BigInt, or `None` if it is not finite.
+pub fn f64_to_bigint_checked(value: f64, rounding: Rounding) -> Option<BigInt> {
+    if !value.is_finite() {
+        return None;
+    }
+    BigInt::from_f64(rounding.apply(value))
+}
+
+/// Convert an f64 to a U256, or `None` if it is not finite, negative or above 2^256 - 1.
+pub fn f64_to_u256_checked(value: f64, rounding: Rounding) -> Option<U256> {
+    biguint_to_u256_checked(&f64_to_biguint_checked(value, rounding)?)
+}
+
+/// Convert an f64 to a U256, clamping NaN and negative values to zero.
+pub fn f64_to_u256_saturating(value: f64, rounding: Rounding) -> U256 {
+    biguint_to_u256_saturating(&f64_to_biguint_saturating(value, rounding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integer_conversions_round_trip() {
+        let values = [U256::ZERO, U256::from(1u64), U256::from(u64::MAX) + U256::from(1u64), U256::MAX];
+        for value in values {
+            let converted = u256_to_biguint(value);
+            assert_eq!(converted.to_string(), value.to_string());
+            assert_eq!(biguint_to_u256_checked(&converted), Some(value));
+        }
+
+        let too_large = u256_to_biguint(U256::MAX) + 1u32;
+        assert_eq!(biguint_to_u256_checked(&too_large), None);
+        assert_eq!(biguint_to_u256_saturating(&too_large), U256::MAX);
+
+        for value in [I256::MIN, I256::MINUS_ONE, I256::ZERO, I256::MAX] {
+            let converted = i256_to_bigint(value);
+            assert_eq!(converted.to_string(), value.to_string());
+            assert_eq!(bigint_to_i256_checked(&converted), Some(value));
+        }
+
+        let below_min = i256_to_bigint(I256::MIN) - 1;
+        assert_eq!(bigint_to_i256_checked(&below_min), None);
+        assert_eq!(bigint_to_i256_saturating(&below_min), I256::MIN);
+        assert_eq!(bigint_to_i256_saturating(&-below_min), I256::MAX);
+
+        assert_eq!(bigint_to_biguint_checked(&BigInt::from(-5)), None);
+        assert_eq!(bigint_to_biguint_saturating(&BigInt::from(-5)), BigUint::zero());
+        assert_eq!(biguint_to_bigint(&BigUint::from(5u32)), BigInt::from(5));
+    }
+
+    #[test]
+    fn test_float_conversions() {
+        // 10^21 does not fit into a u64, which amounts of 18-decimal tokens often exceed
+        let large = BigUint::from(10u32).pow(21);
+        assert_eq!(biguint_to_f64(&large), 1e21);
+        assert_eq!(f64_to_biguint_checked(1e21, Rounding::Nearest), Some(large.clone()));
+        assert_eq!(biguint_to_decimal_f64(&large, 18), 1000.0);
+        assert_eq!(bigint_to_f64(&-BigInt::from(large)), -1e21);
+        assert_eq!(u256_to_f64(U256::from(3u64)), 3.0);
+        assert_eq!(i256_to_f64(I256::MINUS_ONE), -1.0);
+
+        assert_eq!(f64_to_biguint_checked(2.5, Rounding::Floor), Some(BigUint::from(2u32)));
+        assert_eq!(f64_to_biguint_checked(2.5, Rounding::Ceil), Some(BigUint::from(3u32)));
+        assert_eq!(f64_to_biguint_checked(2.5, Rounding::Nearest), Some(BigUint::from(3u32)));
+        assert_eq!(f64_to_biguint_checked(-0.4, Rounding::Nearest), Some(BigUint::zero()));
+        assert_eq!(f64_to_biguint_checked(-1.0, Rounding::Nearest), None);
+        assert_eq!(f64_to_biguint_checked(f64::NAN, Rounding::Nearest), None);
+        assert_eq!(f64_to_bigint_checked(-2.5, Rounding::Floor), Some(BigInt::from(-3)));
+
+        assert_eq!(f64_to_biguint_saturating(f64::NAN, Rounding::Floor), BigUint::zero());
+        assert_eq!(f64_to_biguint_saturating(-7.0, Rounding::Floor), BigUint::zero());
+        assert_eq!(f64_to_u256_saturating(f64::INFINITY, Rounding::Floor), U256::MAX);
+        assert_eq!(f64_to_u256_saturating(1e80, Rounding::Floor), U256::MAX);
+        assert_eq!(f64_to_u256_checked(1e80, Rounding::Floor), None);
+        assert_eq!(f64_to_u256_checked(42.4, Rounding::Nearest), Some(U256::from(42u64)));
+    }
+}
@@ -0,0 +1,204 @@
+//! Base fee prediction for the blocks a bundle targets.
+//!
+//! A bundle built at block N lands at the earliest in block N+1, whose base
+//! fee follows from N's header by the EIP-1559 adjustment rule. Pricing a
+//! bundle with N's own base fee under-prices it whenever blocks are congested,
+//! and bundles targeting N+2 or later need an estimate of how the base fee
+//! keeps moving. `BaseFeePredictor` computes the exact next base fee from the
+//! latest recorded header and, for later blocks, compounds it with a high
+//! percentile of the per-block changes observed over recent headers.
+
+use super::{calculate_next_base_fee, convert};
+use alloy::consensus::BlockHeader;
+use alloy::primitives::U256;
+use std::collections::VecDeque;
+use std::sync::RwLock;
+
+/// Largest relative change of the base fee between two blocks under EIP-1559.
+const MAX_BASE_FEE_CHANGE: f64 = 0.125;
+
+/// Fee data of a recorded header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HeaderFees {
+    number: u64,
+    base_fee: u128,
+    gas_used: u128,
+    gas_limit: u128,
+}
+
+/// Predicts the base fee of target blocks from recently recorded headers.
+#[derive(Debug)]
+pub struct BaseFeePredictor {
+    headers: RwLock<VecDeque<HeaderFees>>,
+    history_blocks: usize,
+    percentile: f64,
+}
+
+impl BaseFeePredictor {
+    /// Create a predictor using the 90th percentile of the changes over the last 20 blocks.
+    pub fn new() -> Self {
+        Self {
+            headers: RwLock::new(VecDeque::new()),
+            history_blocks: 20,
+            percentile: 0.9,
+        }
+    }
+
+    /// Set the number of recent headers kept for estimating changes.
+    pub fn with_history_blocks(mut self, history_blocks: usize) -> Self {
+        self.history_blocks = history_blocks.max(2);
+        self
+    }
+
+    /// Set the percentile of the per-block base fee changes, between 0 and 1.
+    pub fn with_percentile(mut self, percentile: f64) -> Self {
+        self.percentile = percentile.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Record the header of a new block.
+    ///
+    /// Headers without a base fee, i.e. from before EIP-1559, are ignored, and
+    /// a header replaces a recorded header with the same or a higher number
+    /// after a reorg.
+    pub fn record_header<H: BlockHeader>(&self, header: &H) {
+        let Some(base_fee) = header.base_fee_per_gas() else {
+            return;
+        };
+        let fees = HeaderFees {
+            number: header.number(),
+            base_fee: base_fee.into(),
+            gas_used: header.gas_used().into(),
+            gas_limit: header.gas_limit().into(),
+        };
+
+        if let Ok(mut headers) = self.headers.write() {
+            while headers.back().is_some_and(|latest| latest.number >= fees.number) {
+                headers.pop_back();
+            }
+            // A gap in the recorded blocks makes older changes incomparable
+            if headers.back().is_some_and(|latest| latest.number + 1 != fees.number) {
+                headers.clear();
+            }
+            headers.push_back(fees);
+            while headers.len() > self.history_blocks {
+                headers.pop_front();
+            }
+        }
+    }
+
+    /// The number of the latest recorded block.
+    pub fn latest_block(&self) -> Option<u64> {
+        self.latest().map(|latest| latest.number)
+    }
+
+    /// The base fee of the block after the latest recorded block.
+    pub fn predict_next(&self) -> Option<U256> {
+        self.latest().map(|latest| next_base_fee(&latest))
+    }
+
+    /// Predict the base fee of `target_block`.
+    ///
+    /// The block after the latest recorded block gets its exact base fee. Later
+    /// blocks compound it with the configured percentile of the recorded
+    /// per-block changes, or with the largest possible increase of 12.5% per
+    /// block while fewer than two headers are recorded.
+    ///
+    /// # Returns
+    ///
+    /// `None` if no header is recorded or `target_block` is not after the latest one.
+    pub fn predict(&self, target_block: u64) -> Option<U256> {
+        let headers = self.headers.read().ok()?;
+        let latest = headers.back()?;
+        let blocks_ahead = target_block.checked_sub(latest.number).filter(|ahead| *ahead > 0)?;
+
+        let next = next_base_fee(latest);
+        if blocks_ahead == 1 {
+            return Some(next);
+        }
+
+        let change = percentile_change(&headers, self.percentile).unwrap_or(MAX_BASE_FEE_CHANGE);
+        let factor = (1.0 + change).powi((blocks_ahead - 1).min(i32::MAX as u64) as i32);
+        let predicted = convert::u256_to_f64(next) * factor;
+        Some(U256::from(predicted.min(u128::MAX as f64).round() as u128))
+    }
+
+    fn latest(&self) -> Option<HeaderFees> {
+        self.headers.read().ok()?.back().copied()
+    }
+}
+
+impl Default for BaseFeePredictor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn next_base_fee(header: &HeaderFees) -> U256 {
+    calculate_next_base_fee(header.base_fee, header.gas_used, header.gas_limit)
+}
+
+/// The percentile of the relative base fee changes between consecutive headers.
+fn percentile_change(headers: &VecDeque<HeaderFees>, percentile: f64) -> Option<f64> {
+    let mut changes: Vec<f64> = headers
+        .iter()
+        .zip(headers.iter().skip(1))
+        .filter(|(previous, _)| previous.base_fee > 0)
+        .map(|(previous, current)| current.base_fee as f64 / previous.base_fee as f64 - 1.0)
+        .collect();
+    if changes.is_empty() {
+        return None;
+    }
+
+    changes.sort_by(f64::total_cmp);
+    let index = ((changes.len() - 1) as f64 * percentile).round() as usize;
+    Some(changes[index].clamp(-MAX_BASE_FEE_CHANGE, MAX_BASE_FEE_CHANGE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::consensus::Header;
+
+    fn header(number: u64, base_fee: u64, gas_used: u64) -> Header {
+        Header {
+            number,
+            base_fee_per_gas: Some(base_fee),
+            gas_used,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_predicts_next_base_fee_from_latest_header() {
+        let predictor = BaseFeePredictor::new();
+        assert_eq!(predictor.predict(101), None);
+
+        // A full block raises the base fee by 12.5%
+        predictor.record_header(&header(100, 1_000_000_000, 30_000_000));
+        assert_eq!(predictor.latest_block(), Some(100));
+        assert_eq!(predictor.predict_next(), Some(U256::from(1_125_000_000u64)));
+        assert_eq!(predictor.predict(101), Some(U256::from(1_125_000_000u64)));
+        assert_eq!(predictor.predict(100), None);
+
+        // Without history, later blocks assume the largest possible increases
+        assert_eq!(predictor.predict(102), Some(U256::from(1_265_625_000u64)));
+    }
+
+    #[test]
+    fn test_compounds_percentile_change_for_later_blocks() {
+        let predictor = BaseFeePredictor::new().with_percentile(1.0);
+        // Base fees rising by 10% and 5% per block, the latest block at its target
+        predictor.record_header(&header(100, 1_000_000_000, 15_000_000));
+        predictor.record_header(&header(101, 1_100_000_000, 15_000_000));
+        predictor.record_header(&header(102, 1_155_000_000, 15_000_000));
+
+        assert_eq!(predictor.predict(103), Some(U256::from(1_155_000_000u64)));
+        assert_eq!(predictor.predict(104), Some(U256::from(1_270_500_000u64)));
+
+        // Reorgs replace the recorded headers
+        predictor.record_header(&header(102, 1_000_000_000, 15_000_000));
+        assert_eq!(predictor.predict(103), Some(U256::from(1_000_000_000u64)));
+    }
+}
@@ -0,0 +1,141 @@
+//! L1 data fees of transactions on OP-stack chains.
+//!
+//! On Base, Unichain and other OP-stack chains a transaction pays, on top of
+//! its L2 execution gas, an L1 data fee for posting its bytes to Ethereum.
+//! With L2 base fees of a few wei the data fee is often the larger part of
+//! the cost of an arbitrage, so profit checks pricing gas alone accept trades
+//! that lose money. The fee is not part of the gas used by a simulation.
+//!
+//! `L2FeeModel` asks the `GasPriceOracle` predeploy of the chain for the data
+//! fee of an encoded transaction, which follows the L1 base fee, the blob base
+//! fee and the fee scalars of the current hardfork.
+
+use crate::chain::ChainRegistry;
+use crate::errors::{Result, SimulationError};
+use crate::simulation::encoding::encode_input;
+use alloy::{
+    consensus::SignableTransaction,
+    network::Ethereum,
+    primitives::{address, Address, Bytes as AlloyBytes, TxKind, U256},
+    providers::{Provider, RootProvider},
+    rpc::types::{TransactionInput, TransactionRequest},
+    sol_types::SolValue,
+};
+use std::sync::Arc;
+
+/// The `GasPriceOracle` predeploy shared by all OP-stack chains.
+pub const OP_STACK_GAS_PRICE_ORACLE: Address = address!("420000000000000000000000000000000000000F");
+
+/// Estimates the L1 data fee of transactions through the `GasPriceOracle` of the chain.
+#[derive(Debug, Clone)]
+pub struct L2FeeModel {
+    provider: Arc<RootProvider<Ethereum>>,
+    oracle: Address,
+}
+
+impl L2FeeModel {
+    /// Create a model calling the OP-stack `GasPriceOracle` predeploy.
+    pub fn new(provider: Arc<RootProvider<Ethereum>>) -> Self {
+        Self {
+            provider,
+            oracle: OP_STACK_GAS_PRICE_ORACLE,
+        }
+    }
+
+    /// Create a model for a registered chain.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the chain is unknown or charges no L1 data fee, e.g. Ethereum.
+    pub fn for_chain(chain: &str, provider: Arc<RootProvider<Ethereum>>) -> Option<Self> {
+        let oracle = ChainRegistry::get(chain)?.l1_fee_oracle?;
+        Some(Self::new(provider).with_oracle_address(oracle))
+    }
+
+    /// Call another oracle deployment, e.g. on a custom L2.
+    pub fn with_oracle_address(mut self, oracle: Address) -> Self {
+        self.oracle = oracle;
+        self
+    }
+
+    /// The oracle contract called for data fees.
+    pub fn oracle_address(&self) -> Address {
+        self.oracle
+    }
+
+    /// Estimate the L1 data fee of an encoded unsigned transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx_bytes` - The RLP-encoded unsigned transaction
+    ///
+    /// # Returns
+    ///
+    /// The data fee in wei
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `getL1Fee` call fails or returns malformed data.
+    pub async fn l1_data_fee(&self, tx_bytes: &[u8]) -> Result<U256> {
+        let calldata = encode_input("getL1Fee(bytes)", (AlloyBytes::copy_from_slice(tx_bytes),).abi_encode());
+        let request = TransactionRequest {
+            to: Some(TxKind::Call(self.oracle)),
+            input: TransactionInput {
+                input: Some(calldata.into()),
+                data: None,
+            },
+            ..Default::default()
+        };
+
+        let output = self.provider.call(request).await?;
+        if output.len() < 32 {
+            return Err(SimulationError::ProviderError {
+                message: format!("Invalid getL1Fee response from oracle {}", self.oracle),
+            }.into());
+        }
+        Ok(U256::from_be_slice(&output[..32]))
+    }
+
+    /// Estimate the combined L1 data fee of transaction requests, e.g. of a bundle.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data fee of any request cannot be estimated.
+    pub async fn l1_data_fee_of_requests(&self, requests: &[TransactionRequest]) -> Result<U256> {
+        let mut total = U256::ZERO;
+        for request in requests {
+            let fee = self.l1_data_fee(&unsigned_transaction_bytes(request)).await?;
+            total = total.saturating_add(fee);
+        }
+        Ok(total)
+    }
+}
+
+/// Encode a transaction request as an unsigned transaction.
+///
+/// Requests missing fields of a complete transaction, e.g. the nonce, are
+/// approximated by their calldata, which dominates the size of swap transactions.
+pub fn unsigned_transaction_bytes(request: &TransactionRequest) -> Vec<u8> {
+    match request.clone().build_typed_tx() {
+        Ok(tx) => tx.encoded_for_signing(),
+        Err(request) => request.input.input().map(|input| input.to_vec()).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_l2_fee_model_for_op_stack_chains() {
+        let provider = Arc::new(RootProvider::new_http("http://127.0.0.1:1".parse().unwrap()));
+        assert_eq!(
+            L2FeeModel::for_chain("base", Arc::clone(&provider)).map(|model| model.oracle_address()),
+            Some(OP_STACK_GAS_PRICE_ORACLE)
+        );
+        assert!(L2FeeModel::for_chain("ethereum", provider).is_none());
+
+        let request = TransactionRequest::default().input(TransactionInput::new(vec![1u8, 2, 3].into()));
+        assert_eq!(unsigned_transaction_bytes(&request), vec![1, 2, 3]);
+    }
+}
@@ -7,10 +7,14 @@
 //!
 //! # Core Functionality
 //!
-//! - **Type Conversions**: Safe conversions between U256, I256, BigUint, and primitive types
+//! - **Type Conversions**: Safe conversions between U256, I256, BigUint, and primitive types,
+//!   with checked, saturating and rounding variants in [`convert`]
 //! - **Address Handling**: Parsing and validation of Ethereum addresses
-//! - **Chain Configuration**: Chain ID mapping and default service URLs
-//! - **Fee Calculations**: Base fee calculations for EIP-1559 transactions
+//! - **Chain Configuration**: Chain ID mapping, block times, default service URLs and
+//!   well-known token addresses
+//! - **Fee Calculations**: Base fee calculations for EIP-1559 transactions, and base fee
+//!   prediction for target blocks in [`fees`], and L1 data fees of OP-stack
+//!   chains in [`l2_fees`]
 //! - **Builder Parameters**: MEV builder configuration for different relayers
 //!
 //! # Type Safety
@@ -19,9 +23,14 @@
 //! messages when conversions fail. The module prioritizes safety over performance,
 //! ensuring that invalid data is caught early rather than causing runtime panics.
 
+pub mod convert;
+pub mod fees;
+pub mod l2_fees;
+
 use alloy::primitives::{Address, U256, I256};
 use num_bigint::BigUint;
 use std::str::FromStr;
+use crate::chain::{ChainProfile, ChainRegistry, ChainSpec};
 use crate::errors::{Result, UtilityError};
 use tycho_common::models::Chain;
 
@@ -40,9 +49,7 @@ use tycho_common::models::Chain;
 /// The absolute value as a BigUint
 pub fn i256_to_biguint(i: I256) -> BigUint {
     let (_, uint) = i.into_sign_and_abs();
-    let bytes = uint.to_be_bytes::<32>();
-    
-    BigUint::from_bytes_be(&bytes)
+    convert::u256_to_biguint(uint)
 }
 
 /// Convert a signed 128-bit integer to an unsigned BigUint.
@@ -132,7 +139,7 @@ pub fn bytes_slice_to_h160(bytes_slice: &[u8]) -> Result<Address> {
 ///
 /// The equivalent BigUint value
 pub fn u256_to_biguint(val: U256) -> BigUint {
-    BigUint::from_bytes_be(&val.to_be_bytes::<32>())
+    convert::u256_to_biguint(val)
 }
 
 /// Convert a BigUint to a U256 value.
@@ -153,13 +160,7 @@ pub fn u256_to_biguint(val: U256) -> BigUint {
 /// This function will return an error if:
 /// - The BigUint value is larger than 2^256 - 1 (maximum U256 value)
 pub fn biguint_to_u256(val: &BigUint) -> Result<U256> {
-    let bytes = val.to_bytes_be();
-    if bytes.len() > 32 {
-        return Err(UtilityError::ValueTooLarge.into());
-    }
-    let mut u256_bytes = [0u8; 32];
-    u256_bytes[32 - bytes.len()..].copy_from_slice(&bytes);
-    Ok(U256::from_be_bytes(u256_bytes))
+    convert::biguint_to_u256_checked(val).ok_or_else(|| UtilityError::ValueTooLarge.into())
 }
 
 /// Get the default Tycho service URL for a given blockchain.
@@ -175,19 +176,21 @@ pub fn biguint_to_u256(val: &BigUint) -> Result<U256> {
 ///
 /// The default Tycho URL if the chain is supported, None otherwise
 pub fn get_default_tycho_url(chain: &Chain) -> Option<String> {
-    match chain {
-        Chain::Ethereum => Some("tycho-beta.propellerheads.xyz".to_string()),
-        Chain::Base => Some("tycho-base-beta.propellerheads.xyz".to_string()),
-        Chain::Unichain => Some("tycho-unichain-beta.propellerheads.xyz".to_string()),
-        _ => None, 
-    }
+    let name = match chain {
+        Chain::Ethereum => "ethereum",
+        Chain::Base => "base",
+        Chain::Unichain => "unichain",
+        _ => return None,
+    };
+
+    ChainRegistry::get(name).and_then(|spec| spec.tycho_url)
 }
 
 /// Get the chain ID for a given blockchain name.
 ///
 /// Maps human-readable chain names to their corresponding numeric chain IDs
-/// as defined in EIP-155. These IDs are used in transaction signing and
-/// network identification.
+/// as defined in EIP-155, as registered in the [`ChainRegistry`]. These IDs are
+/// used in transaction signing and network identification.
 ///
 /// # Arguments
 ///
@@ -202,14 +205,7 @@ pub fn get_default_tycho_url(chain: &Chain) -> Option<String> {
 /// This function will return an error if:
 /// - The chain name is not recognized or supported
 pub fn chain_id(chain: &str) -> Result<u64> {
-    match chain {
-        "ethereum" => Ok(1),
-        "base" => Ok(8453),
-        "unichain" => Ok(130),
-        _ => Err(UtilityError::UnsupportedChain {
-            chain: chain.to_string(),
-        }.into()),
-    }
+    chain_spec(chain).map(|spec| spec.chain_id)
 }
 
 /// Get the chain name for a given chain ID.
@@ -229,23 +225,53 @@ pub fn chain_id(chain: &str) -> Result<u64> {
 ///
 /// This function will return an error if:
 /// - The chain ID is not recognized or supported
-pub fn chain_name(chain_id: u64) -> Result<&'static str> {
-    match chain_id {
-        1 => Ok("ethereum"),
-        8453 => Ok("base"),
-        130 => Ok("unichain"),
-        _ => Err(UtilityError::UnsupportedChain {
+pub fn chain_name(chain_id: u64) -> Result<String> {
+    ChainRegistry::by_id(chain_id)
+        .map(|spec| spec.name)
+        .ok_or_else(|| UtilityError::UnsupportedChain {
             chain: chain_id.to_string(),
-        }.into()),
-    }
+        }.into())
+}
+
+/// Get the average block time for a given blockchain name.
+///
+/// Block times are used to derive how long a per-block search may run before
+/// its results target a block that has already been produced.
+///
+/// # Arguments
+///
+/// * `chain` - The name of the blockchain (e.g., "ethereum", "base")
+///
+/// # Returns
+///
+/// The block time if the chain is supported, None otherwise
+pub fn block_time(chain: &str) -> Option<std::time::Duration> {
+    ChainRegistry::get(chain).and_then(|spec| spec.block_time)
+}
+
+/// Get the execution quirks of a blockchain, such as its fee market and envelope type.
+///
+/// # Arguments
+///
+/// * `chain` - The name of the blockchain (e.g., "ethereum", "base")
+///
+/// # Returns
+///
+/// The chain profile resolved from the chain's spec
+///
+/// # Errors
+///
+/// This function will return an error if the chain name is not recognized or supported
+pub fn chain_profile(chain: &str) -> Result<ChainProfile> {
+    chain_spec(chain).map(|spec| spec.profile())
 }
 
 /// Get the Permit2 contract address for a given blockchain name.
 ///
 /// Maps human-readable chain names to their corresponding Permit2 contract addresses.
 /// Permit2 uses CREATE2 deployment with a specific salt, resulting in the same address
-/// across all EVM-compatible chains. However, this function allows for chain-specific
-/// overrides if needed in the future.
+/// across all EVM-compatible chains. Chains registered with a non-canonical
+/// deployment in the [`ChainRegistry`] override it.
 ///
 /// # Arguments
 ///
@@ -259,23 +285,77 @@ pub fn chain_name(chain_id: u64) -> Result<&'static str> {
 ///
 /// This function will return an error if:
 /// - The chain name is not recognized or supported
-/// - The address parsing fails (should not happen with hardcoded addresses)
 pub fn permit2_address(chain: &str) -> Result<Address> {
-    let address_str = match chain {
-        "ethereum" => "0x000000000022D473030F116dDEE9F6B43aC78BA3",
-        "base" => "0x000000000022D473030F116dDEE9F6B43aC78BA3",
-        "unichain" => "0x000000000022D473030F116dDEE9F6B43aC78BA3",
-        _ => return Err(UtilityError::UnsupportedChain {
+    chain_spec(chain).map(|spec| spec.permit2_address)
+}
+
+/// Get the address used to represent the chain's native token.
+///
+/// Tycho represents native ETH with the zero address on all supported chains.
+///
+/// # Arguments
+///
+/// * `chain` - The name of the blockchain (e.g., "ethereum", "base")
+///
+/// # Returns
+///
+/// The native token address if the chain is supported
+///
+/// # Errors
+///
+/// This function will return an error if the chain name is not recognized or supported
+pub fn native_token_address(chain: &str) -> Result<Address> {
+    chain_spec(chain).map(|spec| spec.native_token)
+}
+
+/// Get the wrapped native token (WETH) contract address for a chain.
+///
+/// # Arguments
+///
+/// * `chain` - The name of the blockchain (e.g., "ethereum", "base")
+///
+/// # Returns
+///
+/// The wrapped native token address if the chain is supported
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The chain name is not recognized or supported
+pub fn wrapped_native_address(chain: &str) -> Result<Address> {
+    chain_spec(chain).map(|spec| spec.wrapped_native)
+}
+
+/// Get the contract address of a well-known token, e.g. `USDC`, on a chain.
+///
+/// # Arguments
+///
+/// * `chain` - The name of the blockchain (e.g., "ethereum", "base")
+/// * `symbol` - The token symbol, matched case-insensitively
+///
+/// # Returns
+///
+/// The address of the token listed under the symbol in the chain's spec
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The chain name is not recognized or supported
+/// - The chain lists no token under the symbol
+pub fn well_known_token_address(chain: &str, symbol: &str) -> Result<Address> {
+    chain_spec(chain)?
+        .well_known_token(symbol)
+        .ok_or_else(|| UtilityError::UnknownTokenSymbol {
+            symbol: symbol.to_string(),
             chain: chain.to_string(),
-        }.into()),
-    };
-    
-    Address::from_str(address_str).map_err(|source| {
-        UtilityError::AddressParsingFailed {
-            input: address_str.to_string(),
-            source: alloy::primitives::AddressError::Hex(source),
-        }.into()
-    })
+        }.into())
+}
+
+/// Look up a registered chain by name.
+fn chain_spec(chain: &str) -> Result<ChainSpec> {
+    ChainRegistry::get(chain).ok_or_else(|| UtilityError::UnsupportedChain {
+        chain: chain.to_string(),
+    }.into())
 }
 
 /// Get the list of MEV builder names for a specific relayer.